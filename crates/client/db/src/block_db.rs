@@ -13,18 +13,27 @@ use crate::{Column, DatabaseExt, DeoxysBackend, WriteBatchWithTransaction};
 
 type Result<T, E = DeoxysStorageError> = std::result::Result<T, E>;
 
+/// The tip of the chain, as last reported by the gateway.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HighestKnownBlock {
+    pub block_number: u64,
+    pub block_hash: Felt,
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
 pub struct ChainInfo {
     pub chain_id: starknet_types_core::felt::Felt,
     pub chain_name: String,
 }
 
-const ROW_CHAIN_INFO: &[u8] = b"chain_info";
-const ROW_PENDING_INFO: &[u8] = b"pending_info";
-const ROW_PENDING_STATE_UPDATE: &[u8] = b"pending_state_update";
-const ROW_PENDING_INNER: &[u8] = b"pending";
-const ROW_SYNC_TIP: &[u8] = b"sync_tip";
+pub(crate) const ROW_CHAIN_INFO: &[u8] = b"chain_info";
+pub(crate) const ROW_PENDING_INFO: &[u8] = b"pending_info";
+pub(crate) const ROW_PENDING_STATE_UPDATE: &[u8] = b"pending_state_update";
+pub(crate) const ROW_PENDING_INNER: &[u8] = b"pending";
+pub(crate) const ROW_SYNC_TIP: &[u8] = b"sync_tip";
 const ROW_L1_LAST_CONFIRMED_BLOCK: &[u8] = b"l1_last";
+const ROW_L1_LAST_CONFIRMED_ORIGIN: &[u8] = b"l1_last_origin";
+const ROW_HIGHEST_KNOWN_BLOCK: &[u8] = b"highest_known_block";
 
 pub struct TxIndex(pub u64);
 
@@ -80,23 +89,31 @@ impl DeoxysBackend {
         let col = self.db.get_column(Column::BlockNToStateDiff);
         let res = self.db.get_cf(&col, codec::Encode::encode(&block_n)?)?;
         let Some(res) = res else { return Ok(None) };
-        let block = bincode::deserialize(&res)?;
+        let block = codec::decode_value(&res)?;
         Ok(Some(block))
     }
 
     fn get_block_info_from_block_n(&self, block_n: u64) -> Result<Option<DeoxysBlockInfo>> {
+        if let Some(block) = self.read_cache.get_block_info(block_n) {
+            return Ok(Some(block));
+        }
         let col = self.db.get_column(Column::BlockNToBlockInfo);
         let res = self.db.get_cf(&col, codec::Encode::encode(&block_n)?)?;
         let Some(res) = res else { return Ok(None) };
-        let block = bincode::deserialize(&res)?;
+        let block: DeoxysBlockInfo = codec::decode_value(&res)?;
+        self.read_cache.put_block_info(block_n, block.clone());
         Ok(Some(block))
     }
 
     fn get_block_inner_from_block_n(&self, block_n: u64) -> Result<Option<DeoxysBlockInner>> {
+        if let Some(block) = self.read_cache.get_block_inner(block_n) {
+            return Ok(Some(block));
+        }
         let col = self.db.get_column(Column::BlockNToBlockInner);
         let res = self.db.get_cf(&col, codec::Encode::encode(&block_n)?)?;
         let Some(res) = res else { return Ok(None) };
-        let block = bincode::deserialize(&res)?;
+        let block: DeoxysBlockInner = codec::decode_value(&res)?;
+        self.read_cache.put_block_inner(block_n, block.clone());
         Ok(Some(block))
     }
 
@@ -110,14 +127,14 @@ impl DeoxysBackend {
     fn get_pending_block_info(&self) -> Result<Option<DeoxysPendingBlockInfo>> {
         let col = self.db.get_column(Column::BlockStorageMeta);
         let Some(res) = self.db.get_cf(&col, ROW_PENDING_INFO)? else { return Ok(None) };
-        let res = bincode::deserialize(&res)?;
+        let res = codec::decode_value(&res)?;
         Ok(Some(res))
     }
 
     fn get_pending_block_inner(&self) -> Result<Option<DeoxysBlockInner>> {
         let col = self.db.get_column(Column::BlockStorageMeta);
         let Some(res) = self.db.get_cf(&col, ROW_PENDING_INNER)? else { return Ok(None) };
-        let res = bincode::deserialize(&res)?;
+        let res = codec::decode_value(&res)?;
         Ok(Some(res))
     }
 
@@ -128,10 +145,26 @@ impl DeoxysBackend {
         Ok(Some(res))
     }
 
+    /// The number and hash of the L1 block the last confirmed block has been read from, to check on startup that it
+    /// has not been reorged out while the node was stopped.
+    pub fn get_l1_last_confirmed_origin(&self) -> Result<Option<(u64, [u8; 32])>> {
+        let col = self.db.get_column(Column::BlockStorageMeta);
+        let Some(res) = self.db.get_cf(&col, ROW_L1_LAST_CONFIRMED_ORIGIN)? else { return Ok(None) };
+        let res = bincode::deserialize(&res)?;
+        Ok(Some(res))
+    }
+
+    pub fn get_highest_known_block(&self) -> Result<Option<HighestKnownBlock>> {
+        let col = self.db.get_column(Column::BlockStorageMeta);
+        let Some(res) = self.db.get_cf(&col, ROW_HIGHEST_KNOWN_BLOCK)? else { return Ok(None) };
+        let res = bincode::deserialize(&res)?;
+        Ok(Some(res))
+    }
+
     pub fn get_pending_block_state_update(&self) -> Result<Option<StateDiff>> {
         let col = self.db.get_column(Column::BlockStorageMeta);
         let Some(res) = self.db.get_cf(&col, ROW_PENDING_STATE_UPDATE)? else { return Ok(None) };
-        let res = bincode::deserialize(&res)?;
+        let res = codec::decode_value(&res)?;
         Ok(Some(res))
     }
 
@@ -140,9 +173,9 @@ impl DeoxysBackend {
     pub(crate) fn block_db_store_pending(&self, block: &DeoxysPendingBlock, state_update: &StateDiff) -> Result<()> {
         let mut tx = WriteBatchWithTransaction::default();
         let col = self.db.get_column(Column::BlockStorageMeta);
-        tx.put_cf(&col, ROW_PENDING_INFO, bincode::serialize(&block.info)?);
-        tx.put_cf(&col, ROW_PENDING_INNER, bincode::serialize(&block.inner)?);
-        tx.put_cf(&col, ROW_PENDING_STATE_UPDATE, bincode::serialize(&state_update)?);
+        tx.put_cf(&col, ROW_PENDING_INFO, codec::encode_value(&block.info)?);
+        tx.put_cf(&col, ROW_PENDING_INNER, codec::encode_value(&block.inner)?);
+        tx.put_cf(&col, ROW_PENDING_STATE_UPDATE, codec::encode_value(state_update)?);
         let mut writeopts = WriteOptions::new();
         writeopts.disable_wal(true);
         self.db.write_opt(tx, &writeopts)?;
@@ -169,7 +202,32 @@ impl DeoxysBackend {
         Ok(())
     }
 
+    pub fn write_l1_last_confirmed_origin(&self, l1_block_n: u64, l1_block_hash: [u8; 32]) -> Result<()> {
+        let col = self.db.get_column(Column::BlockStorageMeta);
+        let mut writeopts = WriteOptions::default();
+        writeopts.disable_wal(true);
+        self.db.put_cf_opt(
+            &col,
+            ROW_L1_LAST_CONFIRMED_ORIGIN,
+            bincode::serialize(&(l1_block_n, l1_block_hash))?,
+            &writeopts,
+        )?;
+        Ok(())
+    }
+
+    pub fn write_highest_known_block(&self, highest_known_block: &HighestKnownBlock) -> Result<()> {
+        let col = self.db.get_column(Column::BlockStorageMeta);
+        let mut writeopts = WriteOptions::default();
+        writeopts.disable_wal(true);
+        self.db.put_cf_opt(&col, ROW_HIGHEST_KNOWN_BLOCK, bincode::serialize(highest_known_block)?, &writeopts)?;
+        Ok(())
+    }
+
     pub fn clear_last_confirmed_block(&self) -> Result<()> {
+        let col = self.db.get_column(Column::BlockStorageMeta);
+        let mut writeopts = WriteOptions::default();
+        writeopts.disable_wal(true);
+        self.db.delete_cf_opt(&col, ROW_L1_LAST_CONFIRMED_ORIGIN, &writeopts)?;
         self.write_last_confirmed_block(0)
     }
 
@@ -192,11 +250,15 @@ impl DeoxysBackend {
         }
 
         tx.put_cf(&block_hash_to_block_n, block_hash_encoded, &block_n_encoded);
-        tx.put_cf(&block_n_to_block, &block_n_encoded, bincode::serialize(&block.info)?);
-        tx.put_cf(&block_n_to_block_inner, &block_n_encoded, bincode::serialize(&block.inner)?);
-        tx.put_cf(&block_n_to_state_diff, &block_n_encoded, bincode::serialize(state_diff)?);
+        tx.put_cf(&block_n_to_block, &block_n_encoded, codec::encode_value(&block.info)?);
+        tx.put_cf(&block_n_to_block_inner, &block_n_encoded, codec::encode_value(&block.inner)?);
+        tx.put_cf(&block_n_to_state_diff, &block_n_encoded, codec::encode_value(state_diff)?);
         tx.put_cf(&meta, ROW_SYNC_TIP, block_n_encoded);
 
+        let event_addresses =
+            block.inner.receipts.iter().flat_map(|receipt| receipt.events().iter().map(|event| &event.from_address));
+        self.event_index_store_block(&mut tx, block.info.header.block_number, event_addresses)?;
+
         // clear pending
         tx.delete_cf(&meta, ROW_PENDING_INFO);
         tx.delete_cf(&meta, ROW_PENDING_INNER);
@@ -205,6 +267,7 @@ impl DeoxysBackend {
         let mut writeopts = WriteOptions::new();
         writeopts.disable_wal(true);
         self.db.write_opt(tx, &writeopts)?;
+        self.read_cache.invalidate_block(block.info.header.block_number);
         Ok(())
     }
 
@@ -216,6 +279,7 @@ impl DeoxysBackend {
             BlockId::Number(block_n) => Ok(Some(DbBlockId::BlockN(*block_n))),
             BlockId::Tag(BlockTag::Latest) => Ok(self.get_latest_block_n()?.map(DbBlockId::BlockN)),
             BlockId::Tag(BlockTag::Pending) => Ok(Some(DbBlockId::Pending)),
+            BlockId::Tag(BlockTag::L1Accepted) => Ok(self.get_l1_last_confirmed_block()?.map(DbBlockId::BlockN)),
         }
     }
 
@@ -29,7 +29,7 @@ impl DeoxysBackend {
             DbBlockId::Pending => {
                 let col = self.db.get_column(pending_col);
                 if let Some(res) = self.db.get_pinned_cf(&col, &key_encoded)? {
-                    return Ok(Some(bincode::deserialize(&res)?)); // found in pending
+                    return Ok(Some((bincode::deserialize(&res)?, None))); // found in pending
                 }
 
                 None
@@ -54,10 +54,27 @@ impl DeoxysBackend {
 
         log::debug!("class info {id:?} {class_hash:#x}");
 
-        let Some((info, block_n)) =
-            self.class_db_get_encoded_kv::<ClassInfo>(&id, class_hash, Column::PendingClassInfo, Column::ClassInfo)?
-        else {
-            return Ok(None);
+        let cached = match id {
+            DbBlockId::BlockN(block_n) => self.read_cache.get_class_info(class_hash).map(|info| (info, Some(block_n))),
+            DbBlockId::Pending => None,
+        };
+        let (info, block_n) = match cached {
+            Some(cached) => cached,
+            None => {
+                let Some((info, block_n)) = self.class_db_get_encoded_kv::<ClassInfo>(
+                    &id,
+                    class_hash,
+                    Column::PendingClassInfo,
+                    Column::ClassInfo,
+                )?
+                else {
+                    return Ok(None);
+                };
+                if block_n.is_some() {
+                    self.read_cache.put_class_info(*class_hash, info.clone());
+                }
+                (info, block_n)
+            }
         };
 
         log::debug!("class info got {block_n:?}");
@@ -171,7 +188,9 @@ impl DeoxysBackend {
         class_infos: &[(Felt, ClassInfo)],
         class_compiled: &[(Felt, CompiledClass)],
     ) -> Result<(), DeoxysStorageError> {
-        self.store_classes(Some(block_number), class_infos, class_compiled, Column::ClassInfo, Column::ClassCompiled)
+        self.store_classes(Some(block_number), class_infos, class_compiled, Column::ClassInfo, Column::ClassCompiled)?;
+        self.read_cache.invalidate_classes(class_infos.iter().map(|(class_hash, _)| class_hash));
+        Ok(())
     }
 
     /// NB: This functions needs to run on the rayon thread pool
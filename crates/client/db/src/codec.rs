@@ -1,5 +1,15 @@
+//! Encodings of the keys and values of the database.
+//!
+//! The values are stored with a versioned layout, see [`encode_value`]: a layout can change without breaking the
+//! existing databases, the values of the older layouts are decoded by [`DbValue::decode_older`].
+
 use std::io::{self, Cursor, Read, Write};
 
+use bincode::Options;
+use dp_block::{DeoxysBlockInfo, DeoxysBlockInner, DeoxysPendingBlockInfo};
+use dp_state_update::StateDiff;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use starknet_api::block::BlockHash;
 use starknet_api::core::{ClassHash, ContractAddress, Nonce, PatriciaKey};
 use starknet_api::hash::StarkFelt;
@@ -12,6 +22,8 @@ pub enum Error {
     DecodeError,
     #[error("Encode error")]
     EncodeError,
+    #[error("Unknown value layout version {0}, the database was written by a newer version of the node")]
+    UnknownVersion(u8),
 }
 
 pub trait Encode {
@@ -125,6 +137,52 @@ impl Decode for u32 {
     }
 }
 
+/// The layout version of the values, stored as their first byte.
+pub(crate) const VALUE_VERSION: u8 = 1;
+/// The plain bincode layout of the databases written before the values were versioned. These values have no version
+/// byte: they are upgraded when the database is opened, see [`crate::migration`].
+pub(crate) const LEGACY_VALUE_VERSION: u8 = 0;
+
+/// A value stored with a versioned layout.
+pub(crate) trait DbValue: Serialize + DeserializeOwned {
+    /// Decodes a value stored with the older layout `version`, upgrading it to the current one. The types whose
+    /// layout changed override it to decode their previous layouts.
+    fn decode_older(version: u8, payload: &[u8]) -> Result<Self, Error> {
+        match version {
+            LEGACY_VALUE_VERSION => bincode::deserialize(payload).map_err(|_| Error::DecodeError),
+            // The layout of this type did not change since
+            _ => compact().deserialize(payload).map_err(|_| Error::DecodeError),
+        }
+    }
+}
+
+impl DbValue for DeoxysBlockInfo {}
+impl DbValue for DeoxysPendingBlockInfo {}
+impl DbValue for DeoxysBlockInner {}
+impl DbValue for StateDiff {}
+
+/// The current layout: bincode with variable-length integers and lengths, rejecting trailing bytes.
+fn compact() -> impl Options {
+    bincode::DefaultOptions::new()
+}
+
+/// Encodes a value with the current layout, after its version byte.
+pub(crate) fn encode_value<T: DbValue>(value: &T) -> Result<Vec<u8>, Error> {
+    let mut buffer = vec![VALUE_VERSION];
+    compact().serialize_into(&mut buffer, value).map_err(|_| Error::EncodeError)?;
+    Ok(buffer)
+}
+
+/// Decodes a value stored with any layout up to the current one.
+pub(crate) fn decode_value<T: DbValue>(bytes: &[u8]) -> Result<T, Error> {
+    let (&version, payload) = bytes.split_first().ok_or(Error::DecodeError)?;
+    match version {
+        VALUE_VERSION => compact().deserialize(payload).map_err(|_| Error::DecodeError),
+        version if version < VALUE_VERSION => T::decode_older(version, payload),
+        version => Err(Error::UnknownVersion(version)),
+    }
+}
+
 /// Write a variable-length quantity to the writer from an u64.
 /// The value is written as a sequence of 7-bit bytes, with the most significant bits first.
 /// The most significant bit of each byte is set if there are more bytes to read.
@@ -217,6 +275,26 @@ mod tests {
         assert_eq!(value, decoded);
     }
 
+    #[test]
+    fn test_encode_decode_value() {
+        let state_diff = StateDiff {
+            storage_diffs: vec![],
+            deprecated_declared_classes: vec![Felt::MAX],
+            declared_classes: vec![],
+            deployed_contracts: vec![],
+            replaced_classes: vec![],
+            nonces: vec![],
+        };
+        let bytes = encode_value(&state_diff).unwrap();
+        assert_eq!(bytes[0], VALUE_VERSION);
+        assert!(bytes.len() < bincode::serialize(&state_diff).unwrap().len());
+        assert_eq!(decode_value::<StateDiff>(&bytes).unwrap(), state_diff);
+
+        let legacy = bincode::serialize(&state_diff).unwrap();
+        assert_eq!(StateDiff::decode_older(LEGACY_VALUE_VERSION, &legacy).unwrap(), state_diff);
+        assert!(matches!(decode_value::<StateDiff>(&[VALUE_VERSION + 1]), Err(Error::UnknownVersion(_))));
+    }
+
     #[test]
     fn test_encode_felt() {
         let felt = Felt::MAX;
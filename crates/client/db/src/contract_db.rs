@@ -12,6 +12,7 @@ use rocksdb::{BoundColumnFamily, IteratorMode, ReadOptions, WriteOptions};
 use starknet_core::types::Felt;
 
 use crate::{
+    block_db::ROW_SYNC_TIP,
     codec,
     db_block_id::{DbBlockId, DbBlockIdResolvable},
     Column, DatabaseExt, DeoxysBackend, DeoxysStorageError, WriteBatchWithTransaction, DB, DB_UPDATES_BATCH_SIZE,
@@ -58,12 +59,20 @@ impl DeoxysBackend {
         };
 
         // We try to find history values.
+        self.history_kv_from_block(nonpending_col, make_bin_prefix(k).as_ref(), block_n, ReadOptions::default())
+    }
 
+    /// Get the last value of a history column at or before `block_n`, reading with `options`.
+    fn history_kv_from_block<V: serde::de::DeserializeOwned>(
+        &self,
+        nonpending_col: Column,
+        bin_prefix: &[u8],
+        block_n: u64,
+        mut options: ReadOptions,
+    ) -> Result<Option<V>, DeoxysStorageError> {
         let block_n = u32::try_from(block_n).map_err(|_| DeoxysStorageError::InvalidBlockNumber)?;
-        let bin_prefix = make_bin_prefix(k);
-        let start_at = [bin_prefix.as_ref(), &block_n.to_be_bytes() as &[u8]].concat();
+        let start_at = [bin_prefix, &block_n.to_be_bytes() as &[u8]].concat();
 
-        let mut options = ReadOptions::default();
         options.set_prefix_same_as_start(true);
         // We don't need ot set an iteration range as we have set up a prefix extractor for the column.
         // We are doing prefix iteration
@@ -76,8 +85,9 @@ impl DeoxysBackend {
             Some(res) => {
                 #[allow(unused_variables)]
                 let (k, v) = res?;
+                // This should fail if we forgot to set up a prefix iterator for the column.
                 #[cfg(debug_assertions)]
-                assert!(k.starts_with(bin_prefix.as_ref())); // This should fail if we forgot to set up a prefix iterator for the column.
+                assert!(k.starts_with(bin_prefix));
 
                 Ok(Some(bincode::deserialize(&v)?))
             }
@@ -124,6 +134,79 @@ impl DeoxysBackend {
         )
     }
 
+    /// Get the storage values of several contracts at once. Everything is read from a single snapshot of the
+    /// database, so the values are consistent with each other even while blocks are being imported.
+    ///
+    /// Returns, in the order of `contracts`, [`None`] for the contracts that are not deployed at this block and
+    /// the values at the requested keys for the others. Unset storage keys read as zero.
+    pub fn get_contracts_storage_at(
+        &self,
+        id: &impl DbBlockIdResolvable,
+        contracts: &[(Felt, Vec<Felt>)],
+    ) -> Result<Option<Vec<Option<Vec<Felt>>>>, DeoxysStorageError> {
+        let Some(id) = id.resolve_db_block_id(self)? else { return Ok(None) };
+
+        let snapshot = self.db.snapshot();
+        let options = || {
+            let mut options = ReadOptions::default();
+            options.set_snapshot(&snapshot);
+            options
+        };
+
+        // The pending block is built on top of the latest block, which has to be read from the snapshot too
+        let (pending, block_n) = match id {
+            DbBlockId::Pending => {
+                let col = self.db.get_column(Column::BlockStorageMeta);
+                let Some(res) = self.db.get_pinned_cf_opt(&col, ROW_SYNC_TIP, &options())? else { return Ok(None) };
+                (true, codec::Decode::decode(&res)?)
+            }
+            DbBlockId::BlockN(block_n) => (false, block_n),
+        };
+
+        let get = |pending_col: Column,
+                   nonpending_col: Column,
+                   key: Vec<u8>,
+                   bin_prefix: &[u8]|
+         -> Result<Option<Felt>, DeoxysStorageError> {
+            if pending {
+                let col = self.db.get_column(pending_col);
+                if let Some(res) = self.db.get_pinned_cf_opt(&col, &key, &options())? {
+                    return Ok(Some(bincode::deserialize(&res)?));
+                }
+            }
+            self.history_kv_from_block(nonpending_col, bin_prefix, block_n, options())
+        };
+
+        contracts
+            .iter()
+            .map(|(contract_addr, keys)| {
+                let class_hash = get(
+                    Column::PendingContractToClassHashes,
+                    Column::ContractToClassHashes,
+                    bincode::serialize(contract_addr)?,
+                    &contract_addr.to_bytes_be(),
+                )?;
+                if class_hash.is_none() {
+                    return Ok(None);
+                }
+
+                keys.iter()
+                    .map(|key| {
+                        let value = get(
+                            Column::PendingContractStorage,
+                            Column::ContractStorage,
+                            bincode::serialize(&(*contract_addr, *key))?,
+                            &make_storage_key_prefix(*contract_addr, *key),
+                        )?;
+                        Ok(value.unwrap_or(Felt::ZERO))
+                    })
+                    .collect::<Result<_, DeoxysStorageError>>()
+                    .map(Some)
+            })
+            .collect::<Result<_, _>>()
+            .map(Some)
+    }
+
     /// NB: This functions needs to run on the rayon thread pool
     pub(crate) fn contract_db_store_block(
         &self,
@@ -197,44 +280,33 @@ impl DeoxysBackend {
         let mut writeopts = WriteOptions::new();
         writeopts.disable_wal(true);
 
-        fn write_chunk(
+        // The pending columns are keyed the same way they are read in `resolve_history_kv`, without a block number.
+        fn write_chunk<K: serde::Serialize>(
             db: &DB,
             writeopts: &WriteOptions,
             col: &Arc<BoundColumnFamily>,
-            chunk: impl IntoIterator<Item = (impl AsRef<[u8]>, Felt)>,
+            chunk: &[(K, Felt)],
         ) -> Result<(), DeoxysStorageError> {
             let mut batch = WriteBatchWithTransaction::default();
             for (key, value) in chunk {
                 // TODO: find a way to avoid this allocation
-                batch.put_cf(col, key.as_ref(), codec::Encode::encode(&value)?);
+                batch.put_cf(col, bincode::serialize(key)?, codec::Encode::encode(value)?);
             }
             db.write_opt(batch, writeopts)?;
             Ok(())
         }
 
         contract_class_updates.par_chunks(DB_UPDATES_BATCH_SIZE).try_for_each_init(
-            || self.db.get_column(Column::ContractToClassHashes),
-            |col, chunk| write_chunk(&self.db, &writeopts, col, chunk.iter().map(|(k, v)| (k.to_bytes_be(), *v))),
+            || self.db.get_column(Column::PendingContractToClassHashes),
+            |col, chunk| write_chunk(&self.db, &writeopts, col, chunk),
         )?;
         contract_nonces_updates.par_chunks(DB_UPDATES_BATCH_SIZE).try_for_each_init(
-            || self.db.get_column(Column::ContractToNonces),
-            |col, chunk| write_chunk(&self.db, &writeopts, col, chunk.iter().map(|(k, v)| (k.to_bytes_be(), *v))),
+            || self.db.get_column(Column::PendingContractToNonces),
+            |col, chunk| write_chunk(&self.db, &writeopts, col, chunk),
         )?;
         contract_kv_updates.par_chunks(DB_UPDATES_BATCH_SIZE).try_for_each_init(
-            || self.db.get_column(Column::ContractStorage),
-            |col, chunk| {
-                write_chunk(
-                    &self.db,
-                    &writeopts,
-                    col,
-                    chunk.iter().map(|((k1, k2), v)| {
-                        let mut key = [0u8; 64];
-                        key[..32].copy_from_slice(k1.to_bytes_be().as_ref());
-                        key[32..].copy_from_slice(k2.to_bytes_be().as_ref());
-                        (key, *v)
-                    }),
-                )
-            },
+            || self.db.get_column(Column::PendingContractStorage),
+            |col, chunk| write_chunk(&self.db, &writeopts, col, chunk),
         )?;
 
         Ok(())
@@ -0,0 +1,79 @@
+//! Index of the blocks containing events emitted by a contract.
+//!
+//! For each block, every contract address which emitted at least one event in that block gets an
+//! empty `address || block_n` row, using a fixed prefix extractor on the address. Looking up the events
+//! of a contract is then a prefix iteration instead of a scan of every block in the range.
+//!
+//! The index only covers the blocks stored after it was introduced: [`DeoxysBackend::get_event_index_start`]
+//! returns the first indexed block, and blocks below it have to be scanned.
+use std::collections::HashSet;
+
+use rocksdb::{IteratorMode, ReadOptions};
+use starknet_core::types::Felt;
+
+use crate::{Column, DatabaseExt, DeoxysBackend, DeoxysStorageError, WriteBatchWithTransaction};
+
+// NB: Columns cf needs prefix extractor of these length during creation
+pub(crate) const EVENT_ADDRESS_PREFIX_EXTRACTOR: usize = 32;
+
+const ROW_EVENT_INDEX_START: &[u8] = b"event_index_start";
+
+fn make_event_key(from_address: &Felt, block_n: u64) -> [u8; 40] {
+    let mut key = [0u8; 40];
+    key[..32].copy_from_slice(from_address.to_bytes_be().as_ref());
+    key[32..].copy_from_slice(&block_n.to_be_bytes());
+    key
+}
+
+impl DeoxysBackend {
+    /// The first block covered by the event index, if any block has been indexed yet.
+    pub fn get_event_index_start(&self) -> Result<Option<u64>, DeoxysStorageError> {
+        let col = self.db.get_column(Column::BlockStorageMeta);
+        let Some(res) = self.db.get_cf(&col, ROW_EVENT_INDEX_START)? else { return Ok(None) };
+        Ok(Some(bincode::deserialize(&res)?))
+    }
+
+    /// Adds the event index rows of a block to a write batch.
+    pub(crate) fn event_index_store_block<'a>(
+        &self,
+        tx: &mut WriteBatchWithTransaction,
+        block_n: u64,
+        from_addresses: impl IntoIterator<Item = &'a Felt>,
+    ) -> Result<(), DeoxysStorageError> {
+        if self.get_event_index_start()?.is_none() {
+            let col = self.db.get_column(Column::BlockStorageMeta);
+            tx.put_cf(&col, ROW_EVENT_INDEX_START, bincode::serialize(&block_n)?);
+        }
+
+        let col = self.db.get_column(Column::EventAddressToBlockN);
+        for from_address in from_addresses.into_iter().collect::<HashSet<_>>() {
+            tx.put_cf(&col, make_event_key(from_address, block_n), []);
+        }
+        Ok(())
+    }
+
+    /// Returns the indexed blocks in `from_block..=to_block` containing at least one event emitted by
+    /// `from_address`, in ascending order.
+    pub fn get_event_blocks(
+        &self,
+        from_address: &Felt,
+        from_block: u64,
+        to_block: u64,
+    ) -> impl Iterator<Item = Result<u64, DeoxysStorageError>> + '_ {
+        let prefix = from_address.to_bytes_be();
+        let start_at = make_event_key(from_address, from_block);
+
+        let mut options = ReadOptions::default();
+        options.set_prefix_same_as_start(true);
+        let mode = IteratorMode::From(&start_at, rocksdb::Direction::Forward);
+        let iter = self.db.iterator_cf_opt(&self.db.get_column(Column::EventAddressToBlockN), options, mode);
+
+        iter.map(move |res| {
+            let (k, _v) = res?;
+            debug_assert!(k.starts_with(&prefix));
+            let block_n: [u8; 8] = k[32..].try_into().map_err(|_| DeoxysStorageError::InvalidBlockNumber)?;
+            Ok(u64::from_be_bytes(block_n))
+        })
+        .take_while(move |res| res.as_ref().map_or(true, |block_n| *block_n <= to_block))
+    }
+}
@@ -0,0 +1,132 @@
+//! Index of the L1 -> L2 messages sent by the L1 transactions.
+//!
+//! The L1 sync service stores the hashes of the `l1_handler` transactions produced by the `LogMessageToL2` events of
+//! each L1 transaction, so that their status can be looked up from the hash of the L1 transaction. Only the messages
+//! sent since the first indexed L1 block are known, older messages are indexed by moving this block back with
+//! [`DeoxysBackend::backfill_l1_messages_from`].
+//!
+//! The L1 transactions are also indexed by L1 block, so that the messages of the L1 blocks removed by a reorg can be
+//! reverted with [`DeoxysBackend::revert_l1_messages_from`].
+use rocksdb::{IteratorMode, WriteOptions};
+use starknet_core::types::Felt;
+
+use crate::{Column, DatabaseExt, DeoxysBackend, DeoxysStorageError, WriteBatchWithTransaction};
+
+const ROW_L1_MESSAGES_SYNC_TIP: &[u8] = b"l1_messages_sync_tip";
+const ROW_L1_MESSAGES_FIRST_BLOCK: &[u8] = b"l1_messages_first_block";
+
+impl DeoxysBackend {
+    /// The hashes of the `l1_handler` transactions produced by an L1 transaction, in the order of the messages.
+    pub fn get_l1_messages_to_l2(&self, l1_tx_hash: &[u8; 32]) -> Result<Option<Vec<Felt>>, DeoxysStorageError> {
+        let col = self.db.get_column(Column::L1TxHashToL1HandlerTxHashes);
+        let Some(res) = self.db.get_pinned_cf(&col, l1_tx_hash)? else { return Ok(None) };
+        Ok(Some(bincode::deserialize(&res)?))
+    }
+
+    /// Adds the hash of the `l1_handler` transaction produced by a message of an L1 transaction, included in the L1
+    /// block `l1_block_n`.
+    pub fn add_l1_message_to_l2(
+        &self,
+        l1_block_n: u64,
+        l1_tx_hash: &[u8; 32],
+        l1_handler_tx_hash: Felt,
+    ) -> Result<(), DeoxysStorageError> {
+        let mut tx_hashes = self.get_l1_messages_to_l2(l1_tx_hash)?.unwrap_or_default();
+        // The same log can be received again after an L1 reorg
+        if tx_hashes.contains(&l1_handler_tx_hash) {
+            return Ok(());
+        }
+        tx_hashes.push(l1_handler_tx_hash);
+
+        let block_col = self.db.get_column(Column::L1BlockNToL1TxHashes);
+        let mut l1_tx_hashes: Vec<[u8; 32]> = match self.db.get_pinned_cf(&block_col, l1_block_n.to_be_bytes())? {
+            Some(res) => bincode::deserialize(&res)?,
+            None => vec![],
+        };
+
+        let mut tx = WriteBatchWithTransaction::default();
+        if !l1_tx_hashes.contains(l1_tx_hash) {
+            l1_tx_hashes.push(*l1_tx_hash);
+            tx.put_cf(&block_col, l1_block_n.to_be_bytes(), bincode::serialize(&l1_tx_hashes)?);
+        }
+        let col = self.db.get_column(Column::L1TxHashToL1HandlerTxHashes);
+        tx.put_cf(&col, l1_tx_hash, bincode::serialize(&tx_hashes)?);
+
+        let mut writeopts = WriteOptions::default();
+        writeopts.disable_wal(true);
+        self.db.write_opt(tx, &writeopts)?;
+        Ok(())
+    }
+
+    /// Removes the messages of the L1 blocks from `l1_block_n` onwards, which are no longer part of the canonical L1
+    /// chain, and moves the sync tip back so that the messages of these blocks are indexed again.
+    pub fn revert_l1_messages_from(&self, l1_block_n: u64) -> Result<(), DeoxysStorageError> {
+        let block_col = self.db.get_column(Column::L1BlockNToL1TxHashes);
+        let col = self.db.get_column(Column::L1TxHashToL1HandlerTxHashes);
+        let start_at = l1_block_n.to_be_bytes();
+
+        let mut tx = WriteBatchWithTransaction::default();
+        for res in self.db.iterator_cf(&block_col, IteratorMode::From(&start_at, rocksdb::Direction::Forward)) {
+            let (key, value) = res?;
+            let l1_tx_hashes: Vec<[u8; 32]> = bincode::deserialize(&value)?;
+            for l1_tx_hash in &l1_tx_hashes {
+                tx.delete_cf(&col, l1_tx_hash);
+            }
+            tx.delete_cf(&block_col, key);
+        }
+
+        if self.get_l1_messages_sync_tip()?.is_some_and(|sync_tip| sync_tip >= l1_block_n) {
+            let meta_col = self.db.get_column(Column::BlockStorageMeta);
+            match l1_block_n.checked_sub(1) {
+                Some(sync_tip) => tx.put_cf(&meta_col, ROW_L1_MESSAGES_SYNC_TIP, bincode::serialize(&sync_tip)?),
+                None => tx.delete_cf(&meta_col, ROW_L1_MESSAGES_SYNC_TIP),
+            }
+        }
+
+        let mut writeopts = WriteOptions::default();
+        writeopts.disable_wal(true);
+        self.db.write_opt(tx, &writeopts)?;
+        Ok(())
+    }
+
+    /// Moves the sync tip back to `l1_block_n` when the messages are indexed from a later L1 block, or have not been
+    /// indexed yet. The messages already indexed are kept, indexing them again does not change them. Returns whether
+    /// the sync tip was moved.
+    pub fn backfill_l1_messages_from(&self, l1_block_n: u64) -> Result<bool, DeoxysStorageError> {
+        let col = self.db.get_column(Column::BlockStorageMeta);
+        let first_block: Option<u64> = match self.db.get_cf(&col, ROW_L1_MESSAGES_FIRST_BLOCK)? {
+            Some(res) => Some(bincode::deserialize(&res)?),
+            None => None,
+        };
+        if first_block.is_some_and(|first_block| first_block <= l1_block_n) {
+            return Ok(false);
+        }
+
+        let mut tx = WriteBatchWithTransaction::default();
+        tx.put_cf(&col, ROW_L1_MESSAGES_FIRST_BLOCK, bincode::serialize(&l1_block_n)?);
+        match l1_block_n.checked_sub(1) {
+            Some(sync_tip) => tx.put_cf(&col, ROW_L1_MESSAGES_SYNC_TIP, bincode::serialize(&sync_tip)?),
+            None => tx.delete_cf(&col, ROW_L1_MESSAGES_SYNC_TIP),
+        }
+
+        let mut writeopts = WriteOptions::default();
+        writeopts.disable_wal(true);
+        self.db.write_opt(tx, &writeopts)?;
+        Ok(true)
+    }
+
+    /// The last L1 block whose messages have been indexed.
+    pub fn get_l1_messages_sync_tip(&self) -> Result<Option<u64>, DeoxysStorageError> {
+        let col = self.db.get_column(Column::BlockStorageMeta);
+        let Some(res) = self.db.get_cf(&col, ROW_L1_MESSAGES_SYNC_TIP)? else { return Ok(None) };
+        Ok(Some(bincode::deserialize(&res)?))
+    }
+
+    pub fn write_l1_messages_sync_tip(&self, l1_block_n: u64) -> Result<(), DeoxysStorageError> {
+        let col = self.db.get_column(Column::BlockStorageMeta);
+        let mut writeopts = WriteOptions::default();
+        writeopts.disable_wal(true);
+        self.db.put_cf_opt(&col, ROW_L1_MESSAGES_SYNC_TIP, bincode::serialize(&l1_block_n)?, &writeopts)?;
+        Ok(())
+    }
+}
@@ -11,6 +11,8 @@ use bonsai_db::{BonsaiDb, DatabaseKeyMapping};
 use bonsai_trie::id::BasicId;
 use bonsai_trie::{BonsaiStorage, BonsaiStorageConfig};
 use db_metrics::DbMetrics;
+use dp_block::{DeoxysBlock, DeoxysPendingBlock};
+use read_cache::ReadCache;
 use rocksdb::backup::{BackupEngine, BackupEngineOptions};
 
 pub mod block_db;
@@ -25,11 +27,16 @@ pub mod class_db;
 pub mod contract_db;
 pub mod db_block_id;
 pub mod db_metrics;
+pub mod events_db;
+pub mod l1_messages_db;
+mod migration;
+pub mod proof_db;
+mod read_cache;
 pub mod storage_updates;
 
 pub use error::{DeoxysStorageError, TrieType};
 use starknet_types_core::hash::{Pedersen, Poseidon, StarkHash};
-use tokio::sync::{mpsc, oneshot};
+use tokio::sync::{broadcast, mpsc, oneshot};
 
 pub type DB = DBWithThreadMode<MultiThreaded>;
 
@@ -38,6 +45,9 @@ pub type WriteBatchWithTransaction = rocksdb::WriteBatchWithTransaction<false>;
 
 const DB_UPDATES_BATCH_SIZE: usize = 1024;
 
+/// Number of stored blocks kept for the subscribers which are lagging behind.
+const NEW_BLOCKS_CHANNEL_CAPACITY: usize = 64;
+
 pub(crate) async fn open_rocksdb(
     path: &Path,
     create: bool,
@@ -132,6 +142,7 @@ fn spawn_backup_db_task(
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum Column {
+    /// Database metadata, such as the layout version of the values
     Meta,
 
     // Blocks storage
@@ -176,6 +187,16 @@ pub enum Column {
     /// Block number to state diff
     BlockStateDiff,
 
+    // Index of the blocks containing events
+    // (contract_address, block_number) => ()
+    EventAddressToBlockN,
+
+    // Index of the L1 -> L2 messages
+    // l1_tx_hash => l1_handler tx hashes
+    L1TxHashToL1HandlerTxHashes,
+    // l1_block_n => l1_tx_hashes, to revert the index after an L1 reorg
+    L1BlockNToL1TxHashes,
+
     // Each bonsai storage has 3 columns
     BonsaiContractsTrie,
     BonsaiContractsFlat,
@@ -222,6 +243,7 @@ impl Column {
             ContractClassHashes,
             ContractStorage,
             BlockStateDiff,
+            EventAddressToBlockN,
             BonsaiContractsTrie,
             BonsaiContractsFlat,
             BonsaiContractsLog,
@@ -234,6 +256,8 @@ impl Column {
             PendingContractToClassHashes,
             PendingContractToNonces,
             PendingContractStorage,
+            L1TxHashToL1HandlerTxHashes,
+            L1BlockNToL1TxHashes,
         ]
     };
     pub const NUM_COLUMNS: usize = Self::ALL.len();
@@ -258,6 +282,7 @@ impl Column {
             BonsaiClassesFlat => "bonsai_classes_flat",
             BonsaiClassesLog => "bonsai_classes_log",
             BlockStateDiff => "block_state_diff",
+            EventAddressToBlockN => "event_address_to_block_n",
             ClassInfo => "class_info",
             ClassCompiled => "class_compiled",
             PendingClassInfo => "pending_class_info",
@@ -269,6 +294,8 @@ impl Column {
             PendingContractToClassHashes => "pending_contract_to_class_hashes",
             PendingContractToNonces => "pending_contract_to_nonces",
             PendingContractStorage => "pending_contract_storage",
+            L1TxHashToL1HandlerTxHashes => "l1_tx_hash_to_l1_handler_tx_hashes",
+            L1BlockNToL1TxHashes => "l1_block_n_to_l1_tx_hashes",
         }
     }
 
@@ -292,6 +319,11 @@ impl Column {
                     contract_db::CONTRACT_NONCES_PREFIX_EXTRACTOR,
                 ));
             }
+            Column::EventAddressToBlockN => {
+                opts.set_prefix_extractor(SliceTransform::create_fixed_prefix(
+                    events_db::EVENT_ADDRESS_PREFIX_EXTRACTOR,
+                ));
+            }
             _ => {}
         }
         opts
@@ -318,6 +350,9 @@ pub struct DeoxysBackend {
     backup_handle: Option<mpsc::Sender<BackupRequest>>,
     db: Arc<DB>,
     last_flush_time: Mutex<Option<Instant>>,
+    new_blocks: broadcast::Sender<Arc<DeoxysBlock>>,
+    pending_blocks: broadcast::Sender<Arc<DeoxysPendingBlock>>,
+    read_cache: ReadCache,
 }
 
 pub struct DatabaseService {
@@ -368,7 +403,15 @@ impl DeoxysBackend {
 
         let (db, backup_handle) = open_rocksdb(&db_path, true, backup_dir, restore_from_latest_backup).await?;
 
-        let backend = Arc::new(Self { backup_handle, db, last_flush_time: Default::default() });
+        let backend = Arc::new(Self {
+            backup_handle,
+            db,
+            last_flush_time: Default::default(),
+            new_blocks: broadcast::channel(NEW_BLOCKS_CHANNEL_CAPACITY).0,
+            pending_blocks: broadcast::channel(NEW_BLOCKS_CHANNEL_CAPACITY).0,
+            read_cache: ReadCache::default(),
+        });
+        backend.upgrade_values()?;
         backend.assert_chain_info(chain_info)?;
         Ok(backend)
     }
@@ -395,6 +438,33 @@ impl DeoxysBackend {
         Ok(should_flush)
     }
 
+    /// Subscribe to the blocks stored in the database, once they are written. Pending blocks are not notified.
+    pub fn subscribe_new_blocks(&self) -> broadcast::Receiver<Arc<DeoxysBlock>> {
+        self.new_blocks.subscribe()
+    }
+
+    pub(crate) fn has_new_block_subscribers(&self) -> bool {
+        self.new_blocks.receiver_count() > 0
+    }
+
+    pub(crate) fn notify_new_block(&self, block: Arc<DeoxysBlock>) {
+        // an error only means that there is no subscriber anymore
+        let _ = self.new_blocks.send(block);
+    }
+
+    /// Subscribe to the pending blocks, every time the pending block is updated.
+    pub fn subscribe_pending_blocks(&self) -> broadcast::Receiver<Arc<DeoxysPendingBlock>> {
+        self.pending_blocks.subscribe()
+    }
+
+    pub(crate) fn has_pending_block_subscribers(&self) -> bool {
+        self.pending_blocks.receiver_count() > 0
+    }
+
+    pub(crate) fn notify_pending_block(&self, block: Arc<DeoxysPendingBlock>) {
+        let _ = self.pending_blocks.send(block);
+    }
+
     pub async fn backup(&self) -> Result<()> {
         let (callback_sender, callback_recv) = oneshot::channel();
         let _res = self
@@ -0,0 +1,114 @@
+//! Upgrade of the values stored with an older layout, when the database is opened.
+//!
+//! The values of the block columns are rewritten with the current layout, see [`codec::encode_value`]. The upgrade
+//! is written in batches along with a cursor: when the node stops midway, it resumes after the last written batch.
+
+use dp_block::{DeoxysBlockInfo, DeoxysBlockInner};
+use dp_state_update::StateDiff;
+use rocksdb::{Direction, IteratorMode};
+
+use crate::block_db::{ROW_CHAIN_INFO, ROW_PENDING_INFO, ROW_PENDING_INNER, ROW_PENDING_STATE_UPDATE, ROW_SYNC_TIP};
+use crate::codec::{self, DbValue};
+use crate::{Column, DatabaseExt, DeoxysBackend, DeoxysStorageError, WriteBatchWithTransaction, DB_UPDATES_BATCH_SIZE};
+
+/// The layout version of the values of the database.
+const ROW_VALUE_VERSION: &[u8] = b"value_version";
+/// The index of the column being upgraded, followed by the last upgraded key.
+const ROW_UPGRADE_CURSOR: &[u8] = b"upgrade_cursor";
+
+const UPGRADED_COLUMNS: [Column; 3] =
+    [Column::BlockNToBlockInfo, Column::BlockNToBlockInner, Column::BlockNToStateDiff];
+
+type Result<T, E = DeoxysStorageError> = std::result::Result<T, E>;
+
+impl DeoxysBackend {
+    /// Upgrades the values stored with an older layout. A database without layout version is either empty, or was
+    /// written before the values were versioned.
+    pub(crate) fn upgrade_values(&self) -> Result<()> {
+        let meta = self.db.get_column(Column::Meta);
+        let version = match self.db.get_cf(&meta, ROW_VALUE_VERSION)? {
+            Some(version) => *version.first().ok_or(codec::Error::DecodeError)?,
+            None if self.is_empty()? => codec::VALUE_VERSION,
+            None => codec::LEGACY_VALUE_VERSION,
+        };
+        if version > codec::VALUE_VERSION {
+            return Err(codec::Error::UnknownVersion(version).into());
+        }
+        if version == codec::VALUE_VERSION {
+            self.db.put_cf(&meta, ROW_VALUE_VERSION, [codec::VALUE_VERSION])?;
+            return Ok(());
+        }
+
+        log::info!("⏳ Upgrading the database values from layout version {version} to {}...", codec::VALUE_VERSION);
+        let cursor = self.db.get_cf(&meta, ROW_UPGRADE_CURSOR)?;
+        let (start_column, start_key) = match cursor.as_deref() {
+            Some([column, key @ ..]) => (usize::from(*column), Some(key).filter(|key| !key.is_empty())),
+            _ => (0, None),
+        };
+
+        for (index, column) in UPGRADED_COLUMNS.into_iter().enumerate().skip(start_column) {
+            let start_key = if index == start_column { start_key } else { None };
+            match column {
+                Column::BlockNToBlockInfo => self.upgrade_column::<DeoxysBlockInfo>(index, version, start_key)?,
+                Column::BlockNToBlockInner => self.upgrade_column::<DeoxysBlockInner>(index, version, start_key)?,
+                Column::BlockNToStateDiff => self.upgrade_column::<StateDiff>(index, version, start_key)?,
+                _ => unreachable!("Not an upgraded column"),
+            }
+        }
+
+        // The pending block is fetched again by the sync
+        let block_meta = self.db.get_column(Column::BlockStorageMeta);
+        let mut batch = WriteBatchWithTransaction::default();
+        batch.delete_cf(&block_meta, ROW_PENDING_INFO);
+        batch.delete_cf(&block_meta, ROW_PENDING_INNER);
+        batch.delete_cf(&block_meta, ROW_PENDING_STATE_UPDATE);
+        batch.delete_cf(&meta, ROW_UPGRADE_CURSOR);
+        batch.put_cf(&meta, ROW_VALUE_VERSION, [codec::VALUE_VERSION]);
+        self.db.write(batch)?;
+        log::info!("✅ Upgraded the database values");
+        Ok(())
+    }
+
+    fn is_empty(&self) -> Result<bool> {
+        let block_meta = self.db.get_column(Column::BlockStorageMeta);
+        Ok(self.db.get_cf(&block_meta, ROW_CHAIN_INFO)?.is_none()
+            && self.db.get_cf(&block_meta, ROW_SYNC_TIP)?.is_none())
+    }
+
+    /// Rewrites the values of the column `UPGRADED_COLUMNS[index]` after `start_key`, stored with the layout
+    /// `version`, with the current layout.
+    fn upgrade_column<T: DbValue>(&self, index: usize, version: u8, start_key: Option<&[u8]>) -> Result<()> {
+        let col = self.db.get_column(UPGRADED_COLUMNS[index]);
+        let meta = self.db.get_column(Column::Meta);
+        let mode = match start_key {
+            Some(key) => IteratorMode::From(key, Direction::Forward),
+            None => IteratorMode::Start,
+        };
+
+        let mut batch = WriteBatchWithTransaction::default();
+        let mut batch_len = 0;
+        for entry in self.db.iterator_cf(&col, mode) {
+            let (key, value) = entry?;
+            if Some(key.as_ref()) == start_key {
+                continue;
+            }
+            let value: T = match version {
+                // The legacy values have no version byte
+                codec::LEGACY_VALUE_VERSION => T::decode_older(version, &value)?,
+                _ => codec::decode_value(&value)?,
+            };
+            batch.put_cf(&col, &key, codec::encode_value(&value)?);
+            batch_len += 1;
+
+            if batch_len == DB_UPDATES_BATCH_SIZE {
+                batch.put_cf(&meta, ROW_UPGRADE_CURSOR, [&[index as u8], key.as_ref()].concat());
+                self.db.write(std::mem::take(&mut batch))?;
+                batch_len = 0;
+            }
+        }
+        // The next column starts from its first key
+        batch.put_cf(&meta, ROW_UPGRADE_CURSOR, [index as u8 + 1]);
+        self.db.write(batch)?;
+        Ok(())
+    }
+}
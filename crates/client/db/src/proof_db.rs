@@ -0,0 +1,100 @@
+//! Merkle proofs of the global state tries.
+//!
+//! The tries do not keep any trie log, which means proofs can only be generated against the latest state.
+//! Like when the tries are updated, the key of a leaf is made of the 251 low bits of the felt.
+use bitvec::order::Msb0;
+use bitvec::slice::BitSlice;
+use bitvec::view::AsBits;
+use starknet_core::types::Felt;
+use starknet_types_core::hash::StarkHash;
+
+use crate::{bonsai_identifier, DeoxysBackend, DeoxysStorageError};
+
+/// A node on the path from a trie root to a leaf.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProofNode {
+    Binary {
+        left: Felt,
+        right: Felt,
+    },
+    /// `path` holds the `length` bits of the edge path, most significant bit first.
+    Edge {
+        child: Felt,
+        path: Felt,
+        length: usize,
+    },
+}
+
+impl From<bonsai_trie::ProofNode> for ProofNode {
+    fn from(node: bonsai_trie::ProofNode) -> Self {
+        match node {
+            bonsai_trie::ProofNode::Binary { left, right } => Self::Binary { left, right },
+            bonsai_trie::ProofNode::Edge { child, path } => {
+                Self::Edge { child, path: bits_to_felt(&path.0), length: path.0.len() }
+            }
+        }
+    }
+}
+
+impl ProofNode {
+    /// Hash of the node, with the hash function of its trie: Pedersen for the contract and contract storage
+    /// tries, Poseidon for the class trie.
+    pub fn hash<H: StarkHash>(&self) -> Felt {
+        match self {
+            Self::Binary { left, right } => H::hash(left, right),
+            Self::Edge { child, path, length } => H::hash(child, path) + Felt::from(*length),
+        }
+    }
+}
+
+fn bits_to_felt(bits: &BitSlice<u8, Msb0>) -> Felt {
+    bits.iter().fold(Felt::ZERO, |acc, bit| acc * Felt::TWO + if *bit { Felt::ONE } else { Felt::ZERO })
+}
+
+impl DeoxysBackend {
+    /// Proof of the leaf of a contract in the contract trie, at the latest block.
+    pub fn get_contract_proof(&self, contract_address: &Felt) -> Result<Vec<ProofNode>, DeoxysStorageError> {
+        let key = contract_address.to_bytes_be();
+        let proof = self.contract_trie().get_proof(bonsai_identifier::CONTRACT, &key.as_bits()[5..])?;
+        Ok(proof.into_iter().map(Into::into).collect())
+    }
+
+    /// Proofs of the storage `keys` of a contract in its storage trie, at the latest block.
+    pub fn get_contract_storage_proofs(
+        &self,
+        contract_address: &Felt,
+        keys: &[Felt],
+    ) -> Result<Vec<Vec<ProofNode>>, DeoxysStorageError> {
+        let trie = self.contract_storage_trie();
+        let identifier = contract_address.to_bytes_be();
+        keys.iter()
+            .map(|key| {
+                let key = key.to_bytes_be();
+                let proof = trie.get_proof(&identifier, &key.as_bits()[5..])?;
+                Ok(proof.into_iter().map(Into::into).collect())
+            })
+            .collect()
+    }
+
+    /// Proof of the leaf of a class in the class trie, at the latest block.
+    pub fn get_class_proof(&self, class_hash: &Felt) -> Result<Vec<ProofNode>, DeoxysStorageError> {
+        let key = class_hash.to_bytes_be();
+        let proof = self.class_trie().get_proof(bonsai_identifier::CLASS, &key.as_bits()[5..])?;
+        Ok(proof.into_iter().map(Into::into).collect())
+    }
+
+    /// Root of the storage trie of a contract, at the latest block.
+    pub fn get_contract_storage_root(&self, contract_address: &Felt) -> Result<Felt, DeoxysStorageError> {
+        Ok(self.contract_storage_trie().root_hash(&contract_address.to_bytes_be())?)
+    }
+
+    /// Root of the class trie, at the latest block.
+    pub fn get_class_trie_root(&self) -> Result<Felt, DeoxysStorageError> {
+        Ok(self.class_trie().root_hash(bonsai_identifier::CLASS)?)
+    }
+
+    /// Root of the contract trie, at the latest block.
+    pub fn get_contract_trie_root(&self) -> Result<Felt, DeoxysStorageError> {
+        Ok(self.contract_trie().root_hash(bonsai_identifier::CONTRACT)?)
+    }
+}
@@ -0,0 +1,74 @@
+//! In-process cache for decoded values which are read often by the RPCs.
+//!
+//! Only confirmed blocks and classes are cached, pending data is always read from the db. Entries for a block number
+//! or class hash are invalidated when a new value is written for them.
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+
+use dp_block::{DeoxysBlockInfo, DeoxysBlockInner};
+use dp_class::ClassInfo;
+use lru::LruCache;
+use starknet_core::types::Felt;
+
+/// Number of recent blocks kept decoded in memory.
+const BLOCKS_CACHE_SIZE: usize = 128;
+/// Number of class infos kept decoded in memory.
+const CLASSES_CACHE_SIZE: usize = 256;
+
+pub(crate) struct ReadCache {
+    block_info: Mutex<LruCache<u64, DeoxysBlockInfo>>,
+    block_inner: Mutex<LruCache<u64, DeoxysBlockInner>>,
+    class_info: Mutex<LruCache<Felt, ClassInfo>>,
+}
+
+impl Default for ReadCache {
+    fn default() -> Self {
+        let blocks = NonZeroUsize::new(BLOCKS_CACHE_SIZE).expect("cache size is not zero");
+        let classes = NonZeroUsize::new(CLASSES_CACHE_SIZE).expect("cache size is not zero");
+        Self {
+            block_info: Mutex::new(LruCache::new(blocks)),
+            block_inner: Mutex::new(LruCache::new(blocks)),
+            class_info: Mutex::new(LruCache::new(classes)),
+        }
+    }
+}
+
+impl ReadCache {
+    pub fn get_block_info(&self, block_n: u64) -> Option<DeoxysBlockInfo> {
+        self.block_info.lock().expect("poisoned mutex").get(&block_n).cloned()
+    }
+
+    pub fn put_block_info(&self, block_n: u64, info: DeoxysBlockInfo) {
+        self.block_info.lock().expect("poisoned mutex").put(block_n, info);
+    }
+
+    pub fn get_block_inner(&self, block_n: u64) -> Option<DeoxysBlockInner> {
+        self.block_inner.lock().expect("poisoned mutex").get(&block_n).cloned()
+    }
+
+    pub fn put_block_inner(&self, block_n: u64, inner: DeoxysBlockInner) {
+        self.block_inner.lock().expect("poisoned mutex").put(block_n, inner);
+    }
+
+    pub fn get_class_info(&self, class_hash: &Felt) -> Option<ClassInfo> {
+        self.class_info.lock().expect("poisoned mutex").get(class_hash).cloned()
+    }
+
+    pub fn put_class_info(&self, class_hash: Felt, info: ClassInfo) {
+        self.class_info.lock().expect("poisoned mutex").put(class_hash, info);
+    }
+
+    /// Called when a block is imported, in case an older version of it was cached.
+    pub fn invalidate_block(&self, block_n: u64) {
+        self.block_info.lock().expect("poisoned mutex").pop(&block_n);
+        self.block_inner.lock().expect("poisoned mutex").pop(&block_n);
+    }
+
+    /// Called when classes are imported, in case an older version of them was cached.
+    pub fn invalidate_classes<'a>(&self, class_hashes: impl IntoIterator<Item = &'a Felt>) {
+        let mut class_info = self.class_info.lock().expect("poisoned mutex");
+        for class_hash in class_hashes {
+            class_info.pop(class_hash);
+        }
+    }
+}
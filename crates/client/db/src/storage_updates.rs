@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use dp_block::{DeoxysBlock, DeoxysMaybePendingBlock, DeoxysMaybePendingBlockInfo, DeoxysPendingBlock};
 use dp_class::ConvertedClass;
@@ -17,6 +18,11 @@ pub struct DbClassUpdate {
     pub compiled_class_hash: Felt,
 }
 
+enum BlockNotification {
+    Block(Arc<DeoxysBlock>),
+    Pending(Arc<DeoxysPendingBlock>),
+}
+
 impl DeoxysBackend {
     /// NB: This functions needs to run on the rayon thread pool
     pub fn store_block(
@@ -28,6 +34,19 @@ impl DeoxysBackend {
         let block_n = block.info.block_n();
         let state_diff_cpy = state_diff.clone();
 
+        let notification = match &block.info {
+            DeoxysMaybePendingBlockInfo::NotPending(info) if self.has_new_block_subscribers() => {
+                Some(BlockNotification::Block(Arc::new(DeoxysBlock { info: info.clone(), inner: block.inner.clone() })))
+            }
+            DeoxysMaybePendingBlockInfo::Pending(info) if self.has_pending_block_subscribers() => {
+                Some(BlockNotification::Pending(Arc::new(DeoxysPendingBlock {
+                    info: info.clone(),
+                    inner: block.inner.clone(),
+                })))
+            }
+            _ => None,
+        };
+
         let task_block_db = || match block.info {
             DeoxysMaybePendingBlockInfo::Pending(info) => {
                 self.block_db_store_pending(&DeoxysPendingBlock { info, inner: block.inner }, &state_diff_cpy)
@@ -89,7 +108,14 @@ impl DeoxysBackend {
 
         let ((r1, r2), r3) = rayon::join(|| rayon::join(task_block_db, task_contract_db), task_class_db);
 
-        r1.and(r2).and(r3)
+        r1.and(r2).and(r3)?;
+
+        match notification {
+            Some(BlockNotification::Block(block)) => self.notify_new_block(block),
+            Some(BlockNotification::Pending(block)) => self.notify_pending_block(block),
+            None => {}
+        }
+        Ok(())
     }
 
     pub fn clear_pending_block(&self) -> Result<(), DeoxysStorageError> {
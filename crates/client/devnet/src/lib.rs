@@ -0,0 +1,425 @@
+//! Local block production, for the devnet mode of the node.
+//!
+//! In devnet mode the node stops following the chain: the blocks are produced locally, on top of the blocks already
+//! in the database. The transactions submitted through the write rpc methods are executed right away and added to
+//! the pending block, which is closed either at once, on a timer or on demand. The `dev_` rpc methods drive it: they
+//! mint fee tokens, mine blocks, set the timestamp of the next blocks and impersonate accounts, whose transactions are
+//! then executed without calling their validation entry point.
+
+mod mint;
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use dc_db::storage_updates::DbClassUpdate;
+use dc_db::{DeoxysBackend, DeoxysStorageError};
+use dc_exec::ExecutionContext;
+use dc_sync::commitments::compute_state_root;
+use dc_sync::convert::{convert_and_verify_class, ConvertClassError};
+use dp_block::commitments::BlockCommitments;
+use dp_block::header::PendingHeader;
+use dp_block::{
+    BlockId, BlockTag, DeoxysBlockInfo, DeoxysBlockInner, DeoxysMaybePendingBlock, DeoxysMaybePendingBlockInfo,
+    DeoxysPendingBlockInfo, Header, StarknetVersion,
+};
+use dp_receipt::{PriceUnit, TransactionReceipt};
+use dp_state_update::StateDiff;
+use dp_transactions::{broadcasted_to_blockifier, Transaction, TransactionWithHash};
+use dp_utils::wait_or_graceful_shutdown;
+use starknet_core::types::{BroadcastedDeclareTransaction, BroadcastedTransaction, ContractClass};
+use starknet_types_core::felt::Felt;
+
+pub use mint::fee_token_address;
+
+/// Protocol version of the blocks produced by the devnet, the latest one whose versioned constants are known.
+pub const DEVNET_PROTOCOL_VERSION: StarknetVersion = StarknetVersion::STARKNET_VERSION_0_13_2;
+
+#[derive(thiserror::Error, Debug)]
+pub enum DevnetError {
+    #[error("The database has no block to produce the devnet blocks on top of, sync at least one block first")]
+    NoParentBlock,
+    #[error("Invalid transaction: {0}")]
+    InvalidTransaction(String),
+    #[error("The balance of {0:#x} would overflow")]
+    BalanceOverflow(Felt),
+    #[error("The block timestamp {0} is too large")]
+    TimestampOverflow(u64),
+    #[error("{0:#}")]
+    Execution(#[from] dc_exec::Error),
+    #[error("Converting class: {0:#}")]
+    Class(#[from] ConvertClassError),
+    #[error("Storage error: {0:#}")]
+    Storage(#[from] DeoxysStorageError),
+    #[error("Devnet task failed: {0:#}")]
+    Task(#[from] tokio::task::JoinError),
+}
+
+/// A transaction added to the pending block of the devnet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddedTransaction {
+    pub transaction_hash: Felt,
+    /// The class declared by a declare transaction.
+    pub class_hash: Option<Felt>,
+    /// The account deployed by a deploy account transaction.
+    pub contract_address: Option<Felt>,
+}
+
+/// Handle on the devnet block production, cheap to clone.
+#[derive(Clone)]
+pub struct Devnet {
+    inner: Arc<DevnetInner>,
+}
+
+struct DevnetInner {
+    backend: Arc<DeoxysBackend>,
+    chain_id: Felt,
+    /// Defaults to the sequencer of the latest block.
+    sequencer_address: Option<Felt>,
+    state: Mutex<DevnetState>,
+}
+
+struct DevnetState {
+    pending: Option<PendingBlock>,
+    /// Whether a block is mined for each transaction.
+    auto_mine: bool,
+    /// Seconds added to the wall clock for the timestamp of the next blocks.
+    timestamp_offset: i64,
+    impersonated: HashSet<Felt>,
+}
+
+/// The pending block being built, with the state diff and the classes of all its transactions.
+struct PendingBlock {
+    header: PendingHeader,
+    transactions: Vec<Transaction>,
+    receipts: Vec<TransactionReceipt>,
+    tx_hashes: Vec<Felt>,
+    state_diff: StateDiff,
+    /// Class hash, class and compiled class hash of the declared classes.
+    classes: Vec<(Felt, ContractClass, Felt)>,
+}
+
+impl Devnet {
+    /// Produces blocks on top of the latest block of the database. The pending block synced from the chain, if any,
+    /// is cleared. Without `auto_mine`, the blocks are only mined by [`Devnet::mine`].
+    pub fn new(
+        backend: Arc<DeoxysBackend>,
+        sequencer_address: Option<Felt>,
+        auto_mine: bool,
+    ) -> Result<Self, DevnetError> {
+        let Some(latest_block_n) = backend.get_latest_block_n()? else { return Err(DevnetError::NoParentBlock) };
+        backend.clear_pending_block()?;
+        let chain_id = backend.chain_info()?.chain_id;
+        log::info!("🧪 Devnet producing blocks on top of block #{latest_block_n}");
+
+        Ok(Self {
+            inner: Arc::new(DevnetInner {
+                backend,
+                chain_id,
+                sequencer_address,
+                state: Mutex::new(DevnetState {
+                    pending: None,
+                    auto_mine,
+                    timestamp_offset: 0,
+                    impersonated: HashSet::new(),
+                }),
+            }),
+        })
+    }
+
+    /// Executes a transaction on top of the pending block and adds it to the pending block, which is then mined in
+    /// auto-mine mode. Transactions which fail validation or cannot pay their fee are rejected, reverted transactions
+    /// are added.
+    pub async fn add_transaction(&self, transaction: BroadcastedTransaction) -> Result<AddedTransaction, DevnetError> {
+        let this = self.clone();
+        tokio::task::spawn_blocking(move || this.inner.add_transaction(transaction)).await?
+    }
+
+    /// Credits `amount` of the fee token of `unit` to `address`, in the pending block.
+    pub async fn mint(&self, address: Felt, amount: Felt, unit: PriceUnit) -> Result<(), DevnetError> {
+        let this = self.clone();
+        tokio::task::spawn_blocking(move || this.inner.mint(address, amount, unit)).await?
+    }
+
+    /// Closes the pending block, then mines `n_blocks - 1` empty blocks. Returns the hash of the last block.
+    pub async fn mine(&self, n_blocks: u64) -> Result<Felt, DevnetError> {
+        let this = self.clone();
+        tokio::task::spawn_blocking(move || this.inner.mine(n_blocks)).await?
+    }
+
+    /// Mines a block every `block_time`, until the node shuts down.
+    pub async fn mine_every(&self, block_time: Duration) -> Result<(), DevnetError> {
+        let mut interval = tokio::time::interval(block_time);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        // the first tick completes immediately
+        interval.tick().await;
+        while wait_or_graceful_shutdown(interval.tick()).await.is_some() {
+            self.mine(1).await?;
+        }
+        Ok(())
+    }
+
+    pub fn set_auto_mine(&self, auto_mine: bool) {
+        self.inner.state().auto_mine = auto_mine;
+    }
+
+    /// Sets the timestamp of the next block, the following blocks advance from it with the wall clock. The block
+    /// being built keeps its timestamp, since its transactions were executed with it.
+    pub fn set_block_timestamp(&self, timestamp: u64) -> Result<(), DevnetError> {
+        let signed = i64::try_from(timestamp).map_err(|_| DevnetError::TimestampOverflow(timestamp))?;
+        self.inner.state().timestamp_offset = signed - now() as i64;
+        Ok(())
+    }
+
+    /// The transactions of `address` are executed without calling its validation entry point, so they need no
+    /// valid signature. Returns false if the account was already impersonated.
+    pub fn impersonate(&self, address: Felt) -> bool {
+        self.inner.state().impersonated.insert(address)
+    }
+
+    /// Returns false if the account was not impersonated.
+    pub fn stop_impersonating(&self, address: Felt) -> bool {
+        self.inner.state().impersonated.remove(&address)
+    }
+}
+
+impl DevnetInner {
+    fn state(&self) -> std::sync::MutexGuard<'_, DevnetState> {
+        self.state.lock().expect("poisoned mutex")
+    }
+
+    fn add_transaction(&self, transaction: BroadcastedTransaction) -> Result<AddedTransaction, DevnetError> {
+        if dp_transactions::is_query(&transaction) {
+            return Err(DevnetError::InvalidTransaction("query transactions cannot be added to a block".into()));
+        }
+        let class = declared_class(&transaction)?;
+        let class_hash = class.as_ref().map(|(class_hash, _, _)| *class_hash);
+        let TransactionWithHash { transaction: tx, hash } =
+            TransactionWithHash::from_broadcasted(transaction.clone(), self.chain_id, class_hash);
+        let blockifier_tx = broadcasted_to_blockifier(transaction, self.chain_id)
+            .map_err(|err| DevnetError::InvalidTransaction(format!("{err:#}")))?;
+        let contract_address = match &tx {
+            Transaction::DeployAccount(tx) => Some(tx.calculate_contract_address()),
+            _ => None,
+        };
+
+        let mut state = self.state();
+        let state = &mut *state;
+        let validate = !sender_address(&tx).is_some_and(|sender| state.impersonated.contains(&sender));
+        let pending = self.pending_block(state)?;
+
+        let block_info = DeoxysMaybePendingBlockInfo::Pending(DeoxysPendingBlockInfo::new(
+            pending.header.clone(),
+            pending.tx_hashes.clone(),
+        ));
+        let mut results = ExecutionContext::new(&self.backend, &block_info)?.execute_transactions(
+            [],
+            [blockifier_tx],
+            true,
+            validate,
+        )?;
+        let result = results.pop().expect("one transaction executed");
+
+        pending.state_diff.merge(result.state_diff.into());
+        pending.transactions.push(tx);
+        pending.receipts.push(result.receipt);
+        pending.tx_hashes.push(hash);
+        let new_classes = match class {
+            Some(class) => {
+                pending.classes.push(class.clone());
+                vec![class]
+            }
+            None => vec![],
+        };
+        self.store_pending(pending, new_classes)?;
+        log::info!("🧪 Added transaction {hash:#x} to the pending block");
+
+        if state.auto_mine {
+            self.close_block(state)?;
+        }
+        Ok(AddedTransaction { transaction_hash: hash, class_hash, contract_address })
+    }
+
+    fn mint(&self, address: Felt, amount: Felt, unit: PriceUnit) -> Result<(), DevnetError> {
+        let mut state = self.state();
+        let pending = self.pending_block(&mut state)?;
+        pending.state_diff.merge(mint::mint_state_diff(&self.backend, address, amount, unit)?);
+        self.store_pending(pending, vec![])?;
+        log::info!("🧪 Minted {amount:#x} {unit:?} to {address:#x}");
+
+        if state.auto_mine {
+            self.close_block(&mut state)?;
+        }
+        Ok(())
+    }
+
+    fn mine(&self, n_blocks: u64) -> Result<Felt, DevnetError> {
+        let mut state = self.state();
+        let mut block_hash = self.close_block(&mut state)?;
+        for _ in 1..n_blocks {
+            block_hash = self.close_block(&mut state)?;
+        }
+        Ok(block_hash)
+    }
+
+    /// The block being built, started if there is none.
+    fn pending_block<'a>(&self, state: &'a mut DevnetState) -> Result<&'a mut PendingBlock, DevnetError> {
+        if state.pending.is_none() {
+            state.pending = Some(self.new_pending_block(state.timestamp_offset)?);
+        }
+        Ok(state.pending.as_mut().expect("pending block started"))
+    }
+
+    /// A new pending block on top of the latest block, which it takes its gas prices from.
+    fn new_pending_block(&self, timestamp_offset: i64) -> Result<PendingBlock, DevnetError> {
+        let Some(DeoxysMaybePendingBlockInfo::NotPending(parent)) =
+            self.backend.get_block_info(&BlockId::Tag(BlockTag::Latest))?
+        else {
+            return Err(DevnetError::NoParentBlock);
+        };
+        let block_timestamp = now().saturating_add_signed(timestamp_offset).max(parent.header.block_timestamp);
+
+        Ok(PendingBlock {
+            header: PendingHeader {
+                parent_block_hash: parent.block_hash,
+                sequencer_address: self.sequencer_address.unwrap_or(parent.header.sequencer_address),
+                block_timestamp,
+                protocol_version: DEVNET_PROTOCOL_VERSION,
+                l1_gas_price: parent.header.l1_gas_price,
+                l1_da_mode: parent.header.l1_da_mode,
+            },
+            transactions: vec![],
+            receipts: vec![],
+            tx_hashes: vec![],
+            state_diff: StateDiff::default(),
+            classes: vec![],
+        })
+    }
+
+    /// Stores the pending block, along with the classes declared since it was last stored.
+    fn store_pending(
+        &self,
+        pending: &PendingBlock,
+        new_classes: Vec<(Felt, ContractClass, Felt)>,
+    ) -> Result<(), DevnetError> {
+        let block = DeoxysMaybePendingBlock {
+            info: DeoxysMaybePendingBlockInfo::Pending(DeoxysPendingBlockInfo::new(
+                pending.header.clone(),
+                pending.tx_hashes.clone(),
+            )),
+            inner: DeoxysBlockInner::new(pending.transactions.clone(), pending.receipts.clone()),
+        };
+        let classes = convert_and_verify_class(new_classes.into_iter().map(class_update).collect(), None)?;
+        self.backend.store_block(block, pending.state_diff.clone(), classes)?;
+        Ok(())
+    }
+
+    /// Turns the pending block into the next block of the chain, an empty one if there is no pending block, and
+    /// returns its hash.
+    fn close_block(&self, state: &mut DevnetState) -> Result<Felt, DevnetError> {
+        let pending = match state.pending.take() {
+            Some(pending) => pending,
+            None => self.new_pending_block(state.timestamp_offset)?,
+        };
+        let block_number = self.backend.get_latest_block_n()?.map_or(0, |block_n| block_n + 1);
+        let protocol_version = pending.header.protocol_version;
+
+        let inner = DeoxysBlockInner::new(pending.transactions, pending.receipts);
+        let events_with_tx_hash: Vec<_> = inner
+            .receipts
+            .iter()
+            .flat_map(|receipt| receipt.events().iter().map(move |event| (receipt.transaction_hash(), event.clone())))
+            .collect();
+        let BlockCommitments {
+            transaction_commitment,
+            txs_hashes,
+            event_commitment,
+            receipt_commitment,
+            state_diff_commitment,
+        } = BlockCommitments::compute(
+            &inner,
+            &events_with_tx_hash,
+            &pending.state_diff,
+            self.chain_id,
+            protocol_version,
+            block_number,
+        );
+        let global_state_root = compute_state_root(&self.backend, &pending.state_diff, block_number);
+
+        let header = Header::new(
+            pending.header.parent_block_hash,
+            block_number,
+            global_state_root,
+            pending.header.sequencer_address,
+            pending.header.block_timestamp,
+            inner.transactions.len() as u64,
+            transaction_commitment,
+            events_with_tx_hash.len() as u64,
+            event_commitment,
+            pending.state_diff.len() as u64,
+            state_diff_commitment,
+            receipt_commitment,
+            protocol_version,
+            pending.header.l1_gas_price,
+            pending.header.l1_da_mode,
+        );
+        let block_hash = header.compute_hash(self.chain_id);
+        let transaction_count = inner.transactions.len();
+
+        let classes =
+            convert_and_verify_class(pending.classes.into_iter().map(class_update).collect(), Some(block_number))?;
+        self.backend.store_block(
+            DeoxysMaybePendingBlock {
+                info: DeoxysMaybePendingBlockInfo::NotPending(DeoxysBlockInfo::new(header, txs_hashes, block_hash)),
+                inner,
+            },
+            pending.state_diff,
+            classes,
+        )?;
+        self.backend.clear_pending_block()?;
+        self.backend.maybe_flush(true)?;
+
+        log::info!("⛏️  Mined block #{block_number} ({block_hash:#x}) with {transaction_count} transactions");
+        Ok(block_hash)
+    }
+}
+
+/// Class hash, class and compiled class hash of the class declared by a declare transaction.
+fn declared_class(transaction: &BroadcastedTransaction) -> Result<Option<(Felt, ContractClass, Felt)>, DevnetError> {
+    let BroadcastedTransaction::Declare(tx) = transaction else { return Ok(None) };
+    Ok(Some(match tx {
+        BroadcastedDeclareTransaction::V1(tx) => {
+            let class_hash =
+                tx.contract_class.class_hash().map_err(|err| DevnetError::InvalidTransaction(format!("{err:#}")))?;
+            (class_hash, ContractClass::Legacy(tx.contract_class.as_ref().clone()), Felt::ZERO)
+        }
+        BroadcastedDeclareTransaction::V2(tx) => (
+            tx.contract_class.class_hash(),
+            ContractClass::Sierra(tx.contract_class.as_ref().clone()),
+            tx.compiled_class_hash,
+        ),
+        BroadcastedDeclareTransaction::V3(tx) => (
+            tx.contract_class.class_hash(),
+            ContractClass::Sierra(tx.contract_class.as_ref().clone()),
+            tx.compiled_class_hash,
+        ),
+    }))
+}
+
+fn class_update((class_hash, contract_class, compiled_class_hash): (Felt, ContractClass, Felt)) -> DbClassUpdate {
+    DbClassUpdate { class_hash, contract_class, compiled_class_hash }
+}
+
+/// The account whose validation entry point is called for this transaction.
+fn sender_address(transaction: &Transaction) -> Option<Felt> {
+    match transaction {
+        Transaction::Invoke(tx) => Some(*tx.sender_address()),
+        Transaction::Declare(tx) => Some(*tx.sender_address()),
+        Transaction::DeployAccount(tx) => Some(tx.calculate_contract_address()),
+        Transaction::L1Handler(_) | Transaction::Deploy(_) => None,
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |now| now.as_secs())
+}
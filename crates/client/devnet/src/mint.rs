@@ -0,0 +1,81 @@
+//! Fee token balances, which are u256 values stored as their low and high 128 bits in two consecutive storage slots.
+
+use dc_db::DeoxysBackend;
+use dp_block::{BlockId, BlockTag};
+use dp_receipt::PriceUnit;
+use dp_state_update::{ContractStorageDiffItem, StateDiff, StorageEntry};
+use starknet_core::utils::get_storage_var_address;
+use starknet_types_core::felt::Felt;
+
+use crate::DevnetError;
+
+/// The fee token the fees of this unit are paid in.
+pub fn fee_token_address(unit: PriceUnit) -> Felt {
+    match unit {
+        PriceUnit::Wei => dc_exec::ETH_TOKEN_ADDR,
+        PriceUnit::Fri => dc_exec::STRK_TOKEN_ADDR,
+    }
+}
+
+/// State diff crediting `amount` to the balance of `address` in the fee token of `unit`, on top of the pending state.
+/// The total supply of the token is increased by the same amount.
+pub(crate) fn mint_state_diff(
+    backend: &DeoxysBackend,
+    address: Felt,
+    amount: Felt,
+    unit: PriceUnit,
+) -> Result<StateDiff, DevnetError> {
+    let token = fee_token_address(unit);
+    let mut storage_entries = Vec::with_capacity(4);
+    for key in [storage_var_address("ERC20_balances", &[address]), storage_var_address("ERC20_total_supply", &[])] {
+        let high_key = key + Felt::ONE;
+        let low = get_storage(backend, token, key)?;
+        let high = get_storage(backend, token, high_key)?;
+        let (low, high) = add_u256((low, high), amount).ok_or(DevnetError::BalanceOverflow(address))?;
+        storage_entries.push(StorageEntry { key, value: low });
+        storage_entries.push(StorageEntry { key: high_key, value: high });
+    }
+
+    Ok(StateDiff {
+        storage_diffs: vec![ContractStorageDiffItem { address: token, storage_entries }],
+        ..Default::default()
+    })
+}
+
+fn storage_var_address(name: &str, args: &[Felt]) -> Felt {
+    // the names are ascii constants
+    get_storage_var_address(name, args).expect("Invalid storage variable name")
+}
+
+fn get_storage(backend: &DeoxysBackend, contract: Felt, key: Felt) -> Result<Felt, DevnetError> {
+    Ok(backend.get_contract_storage_at(&BlockId::Tag(BlockTag::Pending), &contract, &key)?.unwrap_or(Felt::ZERO))
+}
+
+/// Adds `amount` to a u256 value given by its low and high 128 bits. `None` when a part of the value is not a 128 bits
+/// value or when the sum overflows.
+fn add_u256((low, high): (Felt, Felt), amount: Felt) -> Option<(Felt, Felt)> {
+    let low = dp_convert::felt_to_u128(&low).ok()?;
+    let high = dp_convert::felt_to_u128(&high).ok()?;
+    let amount = amount.to_bytes_be();
+    let amount_high = u128::from_be_bytes(amount[..16].try_into().expect("16 bytes"));
+    let amount_low = u128::from_be_bytes(amount[16..].try_into().expect("16 bytes"));
+
+    let (low, carry) = low.overflowing_add(amount_low);
+    let high = high.checked_add(amount_high)?.checked_add(carry.into())?;
+    Some((low.into(), high.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_u256() {
+        assert_eq!(add_u256((Felt::ONE, Felt::TWO), Felt::THREE), Some((Felt::from(4u8), Felt::TWO)));
+        assert_eq!(add_u256((u128::MAX.into(), Felt::ZERO), Felt::ONE), Some((Felt::ZERO, Felt::ONE)));
+        assert_eq!(add_u256((Felt::ZERO, Felt::ONE), Felt::from(u128::MAX) + Felt::ONE), Some((Felt::ZERO, Felt::TWO)));
+        assert_eq!(add_u256((u128::MAX.into(), u128::MAX.into()), Felt::ONE), None);
+        // a low part which is not a 128 bits value
+        assert_eq!(add_u256((Felt::from(u128::MAX) + Felt::ONE, Felt::ZERO), Felt::ONE), None);
+    }
+}
@@ -1,18 +1,28 @@
+use std::num::NonZeroU128;
+use std::sync::Arc;
+
 use blockifier::{
     context::{BlockContext, ChainInfo, FeeTokenAddresses},
     state::cached_state::{CachedState, GlobalContractCache},
-    versioned_constants::VersionedConstants,
 };
 use dc_db::{db_block_id::DbBlockId, DeoxysBackend};
-use dp_block::{
-    header::{L1DataAvailabilityMode, BLOCKIFIER_VERSIONED_CONSTANTS_0_13_0, BLOCKIFIER_VERSIONED_CONSTANTS_0_13_1},
-    DeoxysMaybePendingBlockInfo, StarknetVersion,
-};
+use dp_block::{header::L1DataAvailabilityMode, DeoxysMaybePendingBlockInfo, StarknetVersion};
 use dp_convert::ToStarkFelt;
 use starknet_api::block::{BlockNumber, BlockTimestamp};
 use starknet_types_core::felt::Felt;
 
-use crate::{blockifier_state_adapter::BlockifierStateAdapter, Error};
+use crate::{
+    blockifier_state_adapter::BlockifierStateAdapter,
+    cancel::CancellationToken,
+    fee::FeeMargin,
+    limits::ExecutionLimits,
+    metrics::ExecutionMetrics,
+    state_cache::{BlockStateCache, SharedState, StateCache},
+    state_overrides::{OverrideState, StateOverrides, StateOverridesDiff},
+    syscalls::SyscallHook,
+    versioned_constants::{has_versioned_constants, versioned_constants},
+    Error,
+};
 
 pub const ETH_TOKEN_ADDR: Felt =
     Felt::from_hex_unchecked("0x49d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7");
@@ -20,83 +30,236 @@ pub const ETH_TOKEN_ADDR: Felt =
 pub const STRK_TOKEN_ADDR: Felt =
     Felt::from_hex_unchecked("0x04718f5a0fc34cc1af16a1cdee98ffb20c31f5cd61d6ab07201858f4287c938d");
 
+/// Changes to the header of the block the transactions are executed in, to execute them as if they were included
+/// in a later block. The gas prices are in wei and fri.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockHeaderOverrides {
+    pub block_timestamp: Option<u64>,
+    pub sequencer_address: Option<Felt>,
+    pub eth_l1_gas_price: Option<u128>,
+    pub strk_l1_gas_price: Option<u128>,
+    pub eth_l1_data_gas_price: Option<u128>,
+    pub strk_l1_data_gas_price: Option<u128>,
+}
+
 pub struct ExecutionContext<'a> {
-    pub(crate) block_context: BlockContext,
+    pub(crate) block_context: Arc<BlockContext>,
     pub(crate) db_id: DbBlockId,
+    /// `None` for the pending block.
+    pub(crate) block_hash: Option<Felt>,
     pub(crate) backend: &'a DeoxysBackend,
+    pub(crate) state_overrides: Option<Arc<StateOverridesDiff>>,
+    /// Whether the header of the block is overridden.
+    pub(crate) header_overridden: bool,
+    pub(crate) syscall_hook: Option<Arc<dyn SyscallHook>>,
+    pub(crate) limits: ExecutionLimits,
+    pub(crate) fee_margin: FeeMargin,
+    pub(crate) state_cache: Option<Arc<BlockStateCache>>,
+    pub(crate) metrics: Option<ExecutionMetrics>,
+    pub(crate) cancellation: Option<CancellationToken>,
+    /// Transactions are only executed when the versioned constants of this version are exact, see
+    /// [`has_versioned_constants`], as their fees would differ from the ones which were charged.
+    pub(crate) protocol_version: StarknetVersion,
 }
 
 impl<'a> ExecutionContext<'a> {
-    pub fn init_cached_state(&self) -> CachedState<BlockifierStateAdapter<'a>> {
-        let on_top_of = match self.db_id {
+    pub fn init_cached_state(&self) -> CachedState<OverrideState<SharedState<BlockifierStateAdapter<'a>>>> {
+        CachedState::new(self.init_state_adapter(), GlobalContractCache::new(16))
+    }
+
+    /// State of the block the transactions are executed on top of, with the state overrides applied.
+    pub(crate) fn init_state_adapter(&self) -> OverrideState<SharedState<BlockifierStateAdapter<'a>>> {
+        let overrides = self.state_overrides.clone().unwrap_or_default();
+        let state_adapter = SharedState::new(
+            BlockifierStateAdapter::new(self.backend, self.on_top_of()),
+            self.state_cache.clone(),
+            self.metrics.clone(),
+            self.cancellation.clone(),
+        );
+        OverrideState::new(state_adapter, overrides)
+    }
+
+    /// The block whose state the transactions are executed on top of, `None` for the genesis block.
+    pub(crate) fn on_top_of(&self) -> Option<DbBlockId> {
+        match self.db_id {
             DbBlockId::Pending => Some(DbBlockId::Pending),
-            DbBlockId::BlockN(block_n) => {
-                // We exec on top of the previous block. None means we are executing genesis.
-                block_n.checked_sub(1).map(DbBlockId::BlockN)
-            }
-        };
+            // We exec on top of the previous block.
+            DbBlockId::BlockN(block_n) => block_n.checked_sub(1).map(DbBlockId::BlockN),
+        }
+    }
 
-        CachedState::new(BlockifierStateAdapter::new(self.backend, on_top_of), GlobalContractCache::new(16))
+    /// Executes on top of the state of the block with these changes.
+    pub fn with_state_overrides(self, state_overrides: &StateOverrides) -> Result<Self, Error> {
+        Ok(self.with_state_diff_overrides(StateOverridesDiff::new(state_overrides)?))
     }
 
-    pub fn new(backend: &'a DeoxysBackend, block_info: &DeoxysMaybePendingBlockInfo) -> Result<Self, Error> {
-        let (db_id, protocol_version, block_number, block_timestamp, sequencer_address, l1_gas_price, l1_da_mode) =
-            match block_info {
-                DeoxysMaybePendingBlockInfo::Pending(block) => (
-                    DbBlockId::Pending,
-                    block.header.protocol_version,
-                    backend.get_latest_block_n()?.map(|el| el + 1).unwrap_or(0), // when the block is pending, we use the latest block n + 1
-                    block.header.block_timestamp,
-                    block.header.sequencer_address,
-                    block.header.l1_gas_price.clone(),
-                    block.header.l1_da_mode,
-                ),
-                DeoxysMaybePendingBlockInfo::NotPending(block) => (
-                    DbBlockId::BlockN(block.header.block_number),
-                    block.header.protocol_version,
-                    block.header.block_number,
-                    block.header.block_timestamp,
-                    block.header.sequencer_address,
-                    block.header.l1_gas_price.clone(),
-                    block.header.l1_da_mode,
-                ),
-            };
-
-        // safe unwrap because address is always valid and static
-        let fee_token_addresses: FeeTokenAddresses = FeeTokenAddresses {
-            strk_fee_token_address: STRK_TOKEN_ADDR.to_stark_felt().try_into().unwrap(),
-            eth_fee_token_address: ETH_TOKEN_ADDR.to_stark_felt().try_into().unwrap(),
-        };
-        let chain_id = starknet_api::core::ChainId(
-            String::from_utf8(backend.chain_info()?.chain_id.to_bytes_be().to_vec())
-                .expect("Failed to convert chain id to string"),
+    /// Executes on top of the state of the block with these changes, layered on top of the previous overrides.
+    pub fn with_state_diff_overrides(self, overrides: StateOverridesDiff) -> Self {
+        let mut state_overrides = self.state_overrides.as_deref().cloned().unwrap_or_default();
+        state_overrides.extend(overrides);
+        Self { state_overrides: Some(Arc::new(state_overrides)), ..self }
+    }
+
+    /// Executes in a block with this header instead of the header of the block. The state the transactions are
+    /// executed on top of is unchanged.
+    pub fn with_header_overrides(self, overrides: &BlockHeaderOverrides) -> Result<Self, Error> {
+        let mut block_info = self.block_context.block_info().clone();
+        if let Some(block_timestamp) = overrides.block_timestamp {
+            block_info.block_timestamp = BlockTimestamp(block_timestamp);
+        }
+        if let Some(sequencer_address) = overrides.sequencer_address {
+            block_info.sequencer_address =
+                sequencer_address.to_stark_felt().try_into().map_err(Error::HeaderOverride)?;
+        }
+        let gas_prices = &mut block_info.gas_prices;
+        for (price, value) in [
+            (&mut gas_prices.eth_l1_gas_price, overrides.eth_l1_gas_price),
+            (&mut gas_prices.strk_l1_gas_price, overrides.strk_l1_gas_price),
+            (&mut gas_prices.eth_l1_data_gas_price, overrides.eth_l1_data_gas_price),
+            (&mut gas_prices.strk_l1_data_gas_price, overrides.strk_l1_data_gas_price),
+        ] {
+            // Zero prices are replaced by one, as in the headers of the blocks
+            if let Some(value) = value {
+                *price = NonZeroU128::new(value).unwrap_or(NonZeroU128::MIN);
+            }
+        }
+
+        let block_context = BlockContext::new_unchecked(
+            &block_info,
+            self.block_context.chain_info(),
+            self.block_context.versioned_constants(),
         );
+        Ok(Self { block_context: Arc::new(block_context), header_overridden: true, ..self })
+    }
 
-        let versioned_constants = if protocol_version < StarknetVersion::STARKNET_VERSION_0_13_0 {
-            return Err(Error::UnsupportedProtocolVersion);
-        } else if protocol_version < StarknetVersion::STARKNET_VERSION_0_13_1 {
-            &BLOCKIFIER_VERSIONED_CONSTANTS_0_13_0
-        } else if protocol_version < StarknetVersion::STARKNET_VERSION_0_13_1_1 {
-            &BLOCKIFIER_VERSIONED_CONSTANTS_0_13_1
-        } else {
-            VersionedConstants::latest_constants()
-        };
+    /// Shares the values read from the state of the block with the other executions on top of it using this cache.
+    /// The pending block is never cached.
+    pub fn with_state_cache(self, state_cache: &StateCache) -> Self {
+        Self { state_cache: self.block_hash.and_then(|block_hash| state_cache.block(block_hash)), ..self }
+    }
 
-        let chain_info = ChainInfo { chain_id, fee_token_addresses };
+    /// Records the metrics of the executions.
+    pub fn with_metrics(self, metrics: ExecutionMetrics) -> Self {
+        Self { metrics: Some(metrics), ..self }
+    }
 
-        let block_info = blockifier::block::BlockInfo {
-            block_number: BlockNumber(block_number),
-            block_timestamp: BlockTimestamp(block_timestamp),
-            sequencer_address: sequencer_address.to_stark_felt().try_into().unwrap(),
-            gas_prices: (&l1_gas_price).into(),
-            // TODO: Verify if this is correct
-            use_kzg_da: l1_da_mode == L1DataAvailabilityMode::Blob,
+    /// Executes with these limits instead of the ones of the versioned constants of the block.
+    pub fn with_limits(self, limits: ExecutionLimits) -> Self {
+        Self { block_context: Arc::new(limits.apply_to_context(&self.block_context)), limits, ..self }
+    }
+
+    /// Stops the executions once `cancellation` is cancelled, see the `cancel` module.
+    pub fn with_cancellation(self, cancellation: CancellationToken) -> Self {
+        Self { cancellation: Some(cancellation), ..self }
+    }
+
+    /// Fails with [`Error::UnsupportedProtocolVersion`] when the fees of the transactions of the block cannot be
+    /// computed exactly. Calls do not need it.
+    pub(crate) fn check_exact_fees(&self) -> Result<(), Error> {
+        if !has_versioned_constants(self.protocol_version) {
+            return Err(Error::UnsupportedProtocolVersion(self.protocol_version));
+        }
+        Ok(())
+    }
+
+    /// Fails with [`Error::Cancelled`] once the executions are cancelled.
+    pub(crate) fn check_cancelled(&self) -> Result<(), Error> {
+        match &self.cancellation {
+            Some(cancellation) if cancellation.is_cancelled() => Err(Error::Cancelled),
+            _ => Ok(()),
+        }
+    }
+
+    /// Reports the syscalls of the traced transactions to `hook`.
+    pub fn with_syscall_hook(self, hook: Arc<dyn SyscallHook>) -> Self {
+        Self { syscall_hook: Some(hook), ..self }
+    }
+
+    pub fn new(backend: &'a DeoxysBackend, block_info: &DeoxysMaybePendingBlockInfo) -> Result<Self, Error> {
+        let (db_id, block_number) = match block_info {
+            // when the block is pending, we use the latest block n + 1
+            DeoxysMaybePendingBlockInfo::Pending(_) => {
+                (DbBlockId::Pending, backend.get_latest_block_n()?.map(|el| el + 1).unwrap_or(0))
+            }
+            DeoxysMaybePendingBlockInfo::NotPending(block) => {
+                (DbBlockId::BlockN(block.header.block_number), block.header.block_number)
+            }
         };
 
-        Ok(ExecutionContext {
-            block_context: BlockContext::new_unchecked(&block_info, &chain_info, versioned_constants),
+        let block_context = make_block_context(backend.chain_info()?.chain_id, block_info, block_number)?;
+        Ok(Self::with_block_context(backend, block_info, Arc::new(block_context), db_id))
+    }
+
+    /// A context executing in `block_context`, which was built for this block.
+    pub(crate) fn with_block_context(
+        backend: &'a DeoxysBackend,
+        block_info: &DeoxysMaybePendingBlockInfo,
+        block_context: Arc<BlockContext>,
+        db_id: DbBlockId,
+    ) -> Self {
+        ExecutionContext {
+            block_context,
             db_id,
+            block_hash: block_info.as_nonpending().map(|block| block.block_hash),
             backend,
-        })
+            state_overrides: None,
+            header_overridden: false,
+            syscall_hook: None,
+            limits: ExecutionLimits::default(),
+            fee_margin: FeeMargin::default(),
+            state_cache: None,
+            metrics: None,
+            cancellation: None,
+            protocol_version: *block_info.protocol_version(),
+        }
     }
 }
+
+/// Context of the execution of the transactions of a block. `block_number` is the number of the block, which the
+/// pending block does not know.
+pub(crate) fn make_block_context(
+    chain_id: Felt,
+    block_info: &DeoxysMaybePendingBlockInfo,
+    block_number: u64,
+) -> Result<BlockContext, Error> {
+    let (protocol_version, block_timestamp, sequencer_address, l1_gas_price, l1_da_mode) = match block_info {
+        DeoxysMaybePendingBlockInfo::Pending(block) => (
+            block.header.protocol_version,
+            block.header.block_timestamp,
+            block.header.sequencer_address,
+            &block.header.l1_gas_price,
+            block.header.l1_da_mode,
+        ),
+        DeoxysMaybePendingBlockInfo::NotPending(block) => (
+            block.header.protocol_version,
+            block.header.block_timestamp,
+            block.header.sequencer_address,
+            &block.header.l1_gas_price,
+            block.header.l1_da_mode,
+        ),
+    };
+
+    // safe unwrap because address is always valid and static
+    let fee_token_addresses: FeeTokenAddresses = FeeTokenAddresses {
+        strk_fee_token_address: STRK_TOKEN_ADDR.to_stark_felt().try_into().unwrap(),
+        eth_fee_token_address: ETH_TOKEN_ADDR.to_stark_felt().try_into().unwrap(),
+    };
+    let chain_id = starknet_api::core::ChainId(
+        String::from_utf8(chain_id.to_bytes_be().to_vec()).expect("Failed to convert chain id to string"),
+    );
+
+    let versioned_constants = versioned_constants(protocol_version)?;
+
+    let chain_info = ChainInfo { chain_id, fee_token_addresses };
+
+    let block_info = blockifier::block::BlockInfo {
+        block_number: BlockNumber(block_number),
+        block_timestamp: BlockTimestamp(block_timestamp),
+        sequencer_address: sequencer_address.to_stark_felt().try_into().unwrap(),
+        gas_prices: l1_gas_price.into(),
+        // TODO: Verify if this is correct
+        use_kzg_da: l1_da_mode == L1DataAvailabilityMode::Blob,
+    };
+
+    Ok(BlockContext::new_unchecked(&block_info, &chain_info, versioned_constants))
+}
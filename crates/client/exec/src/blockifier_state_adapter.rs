@@ -63,6 +63,9 @@ impl<'a> StateReader for BlockifierStateAdapter<'a> {
                 .to_stark_felt());
         }
 
+        if let Some(value) = self.storage_update.get(&contract_address).and_then(|storage| storage.get(&key)) {
+            return Ok(*value);
+        }
         let Some(on_top_of_block_id) = self.on_top_of_block_id else { return Ok(StarkFelt::ZERO) };
 
         Ok(self
@@ -0,0 +1,81 @@
+//! In-process cache of the execution results.
+//!
+//! Tracing a transaction re-executes every transaction before it in its block, and callers tend to estimate or
+//! simulate the same transactions repeatedly. An execution result only depends on its block and on the transactions
+//! executed before it on top of that block, and on the execution flags and limits, which make up its key. Blocks are
+//! keyed by their hash, so that a reorged block is never served from the cache. The pending block and the executions
+//! with state or header overrides are never cached, as their state or header changes. Neither are the executions with
+//! a syscall hook, which needs the transactions to be executed.
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+
+use lru::LruCache;
+use starknet_api::transaction::TransactionHash;
+use starknet_types_core::felt::Felt;
+
+use crate::{ExecutionLimits, ExecutionResult};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct ExecutionCacheKey {
+    block_hash: Felt,
+    tx_hash: TransactionHash,
+    /// Hash of the transactions executed before this one, on top of the block.
+    preceding: u64,
+    charge_fee: bool,
+    validate: bool,
+    limits: ExecutionLimits,
+}
+
+pub struct ExecutionCache {
+    /// `None` when the cache is disabled.
+    results: Option<Mutex<LruCache<ExecutionCacheKey, Arc<ExecutionResult>>>>,
+    /// Randomly seeded, so that callers cannot craft transactions whose preceding hashes collide.
+    hasher: RandomState,
+}
+
+impl ExecutionCache {
+    /// A cache holding up to `capacity` execution results. A capacity of zero disables the cache.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            results: NonZeroUsize::new(capacity).map(|capacity| Mutex::new(LruCache::new(capacity))),
+            hasher: RandomState::new(),
+        }
+    }
+
+    /// Keys of the transactions executed one after the other on top of the block.
+    pub(crate) fn keys(
+        &self,
+        block_hash: Felt,
+        tx_hashes: impl IntoIterator<Item = TransactionHash>,
+        charge_fee: bool,
+        validate: bool,
+        limits: ExecutionLimits,
+    ) -> Vec<ExecutionCacheKey> {
+        let mut preceding = 0;
+        tx_hashes
+            .into_iter()
+            .map(|tx_hash| {
+                let key = ExecutionCacheKey { block_hash, tx_hash, preceding, charge_fee, validate, limits };
+                preceding = self.hasher.hash_one((preceding, tx_hash));
+                key
+            })
+            .collect()
+    }
+
+    /// The results of all the keys, `None` if any of them is missing.
+    pub(crate) fn get_all(&self, keys: &[ExecutionCacheKey]) -> Option<Vec<Arc<ExecutionResult>>> {
+        let mut results = self.results.as_ref()?.lock().expect("poisoned mutex");
+        keys.iter().map(|key| results.get(key).cloned()).collect()
+    }
+
+    pub(crate) fn put_all(&self, entries: impl IntoIterator<Item = (ExecutionCacheKey, Arc<ExecutionResult>)>) {
+        if let Some(results) = &self.results {
+            let mut results = results.lock().expect("poisoned mutex");
+            for (key, result) in entries {
+                results.put(key, result);
+            }
+        }
+    }
+}
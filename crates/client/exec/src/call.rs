@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 use blockifier::context::TransactionContext;
@@ -12,6 +13,15 @@ use starknet_types_core::felt::Felt;
 
 use crate::{CallContractError, Error, ExecutionContext};
 
+/// Resources used by a contract call, including its inner calls.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CallResources {
+    pub steps: usize,
+    pub memory_holes: usize,
+    /// Number of applications of each builtin, by builtin name.
+    pub builtins: BTreeMap<String, usize>,
+}
+
 impl<'a> ExecutionContext<'a> {
     pub fn call_contract(
         &self,
@@ -19,6 +29,16 @@ impl<'a> ExecutionContext<'a> {
         entry_point_selector: &Felt,
         calldata: &[Felt],
     ) -> Result<Vec<Felt>, Error> {
+        self.call_contract_with_resources(contract_address, entry_point_selector, calldata).map(|(result, _)| result)
+    }
+
+    /// Same as [`ExecutionContext::call_contract`], also returns the resources used by the call.
+    pub fn call_contract_with_resources(
+        &self,
+        contract_address: &Felt,
+        entry_point_selector: &Felt,
+        calldata: &[Felt],
+    ) -> Result<(Vec<Felt>, CallResources), Error> {
         log::debug!("calling contract {contract_address:#x}");
 
         let make_err = |err| CallContractError { block_n: self.db_id, contract: *contract_address, err };
@@ -41,7 +61,7 @@ impl<'a> ExecutionContext<'a> {
         let mut resources = cairo_vm::vm::runners::cairo_runner::ExecutionResources::default();
         let mut entry_point_execution_context = EntryPointExecutionContext::new_invoke(
             Arc::new(TransactionContext {
-                block_context: self.block_context.clone(),
+                block_context: (*self.block_context).clone(),
                 tx_info: TransactionInfo::Deprecated(DeprecatedTransactionInfo::default()),
             }),
             false,
@@ -50,11 +70,15 @@ impl<'a> ExecutionContext<'a> {
 
         let mut cached_state = self.init_cached_state();
 
-        let res = entrypoint
-            .execute(&mut cached_state, &mut resources, &mut entry_point_execution_context)
-            .map_err(TransactionExecutionError::ContractConstructorExecutionFailed)
-            .map_err(make_err)?;
+        let res = entrypoint.execute(&mut cached_state, &mut resources, &mut entry_point_execution_context);
+        self.check_cancelled()?;
+        let res = res.map_err(TransactionExecutionError::ContractConstructorExecutionFailed).map_err(make_err)?;
 
-        Ok(res.execution.retdata.0.iter().map(ToFelt::to_felt).collect())
+        let resources = CallResources {
+            steps: resources.n_steps,
+            memory_holes: resources.n_memory_holes,
+            builtins: resources.builtin_instance_counter.into_iter().collect(),
+        };
+        Ok((res.execution.retdata.0.iter().map(ToFelt::to_felt).collect(), resources))
     }
 }
@@ -0,0 +1,52 @@
+//! Cancellation of the executions.
+//!
+//! Blockifier cannot be interrupted: an execution whose caller gave up, such as an rpc call which timed out, keeps
+//! running on the pool until it is done. An [`ExecutionContext`](crate::ExecutionContext) with a
+//! [`CancellationToken`] checks it before every transaction and on every read of the state of the block, so that a
+//! cancelled execution stops at the next transaction or state read. The number of steps of a transaction between two
+//! state reads is bounded by the execution limits.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use blockifier::state::errors::StateError;
+
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels the executions using this token, or any of its clones.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// A guard cancelling the executions when dropped, to cancel them when the future waiting for them is dropped.
+    pub fn cancel_on_drop(&self) -> CancelOnDrop {
+        CancelOnDrop(self.clone())
+    }
+
+    /// Fails the state reads of a cancelled execution.
+    pub(crate) fn check_read(&self) -> Result<(), StateError> {
+        if self.is_cancelled() {
+            return Err(StateError::StateReadError("Execution cancelled".into()));
+        }
+        Ok(())
+    }
+}
+
+/// Cancels its token when dropped, see [`CancellationToken::cancel_on_drop`].
+pub struct CancelOnDrop(CancellationToken);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
@@ -0,0 +1,89 @@
+//! Execution contexts kept alive between the executions on top of the same block.
+//!
+//! Building an [`ExecutionContext`] reads the chain info, and the latest block number for the pending block, from the
+//! database, and copies the versioned constants of the block twice to apply the execution limits. Bursts of calls on
+//! top of `latest` or `pending` build the same context over and over: the pool keeps it for a short time instead.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use blockifier::context::BlockContext;
+use dc_db::db_block_id::DbBlockId;
+use dc_db::DeoxysBackend;
+use dp_block::DeoxysMaybePendingBlockInfo;
+use starknet_types_core::felt::Felt;
+
+use crate::{Error, ExecutionContext, ExecutionLimits};
+
+/// The pending block is identified by its parent and its timestamp, which changes with every new pending block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum PoolKey {
+    Block(Felt),
+    Pending { parent_block_hash: Felt, block_timestamp: u64 },
+}
+
+struct PooledContext {
+    block_context: Arc<BlockContext>,
+    db_id: DbBlockId,
+    created_at: Instant,
+}
+
+pub struct ExecutionContextPool {
+    contexts: Mutex<HashMap<PoolKey, PooledContext>>,
+    /// How long a context is kept, the pool is disabled when zero.
+    ttl: Duration,
+    limits: ExecutionLimits,
+}
+
+impl ExecutionContextPool {
+    /// A pool keeping the contexts, with these limits applied, for `ttl`. A zero `ttl` disables the pool.
+    pub fn new(ttl: Duration, limits: ExecutionLimits) -> Self {
+        Self { contexts: Mutex::default(), ttl, limits }
+    }
+}
+
+impl<'a> ExecutionContext<'a> {
+    /// Same as `ExecutionContext::new(backend, block_info)?.with_limits(limits)` with the limits of the pool, but the
+    /// context is reused from the pool when it was built for the same block less than its ttl ago.
+    pub fn pooled(
+        pool: &ExecutionContextPool,
+        backend: &'a DeoxysBackend,
+        block_info: &DeoxysMaybePendingBlockInfo,
+    ) -> Result<Self, Error> {
+        if pool.ttl.is_zero() {
+            return Ok(Self::new(backend, block_info)?.with_limits(pool.limits));
+        }
+
+        let key = match block_info {
+            DeoxysMaybePendingBlockInfo::Pending(block) => PoolKey::Pending {
+                parent_block_hash: block.header.parent_block_hash,
+                block_timestamp: block.header.block_timestamp,
+            },
+            DeoxysMaybePendingBlockInfo::NotPending(block) => PoolKey::Block(block.block_hash),
+        };
+        let pooled = pool.contexts.lock().expect("poisoned mutex").get(&key).and_then(|pooled| {
+            (pooled.created_at.elapsed() < pool.ttl).then(|| (Arc::clone(&pooled.block_context), pooled.db_id))
+        });
+        if let Some((block_context, db_id)) = pooled {
+            // The limits are already applied to the pooled block context
+            return Ok(Self {
+                limits: pool.limits,
+                ..Self::with_block_context(backend, block_info, block_context, db_id)
+            });
+        }
+
+        let context = Self::new(backend, block_info)?.with_limits(pool.limits);
+        let mut contexts = pool.contexts.lock().expect("poisoned mutex");
+        contexts.retain(|_, pooled| pooled.created_at.elapsed() < pool.ttl);
+        contexts.insert(
+            key,
+            PooledContext {
+                block_context: Arc::clone(&context.block_context),
+                db_id: context.db_id,
+                created_at: Instant::now(),
+            },
+        );
+        Ok(context)
+    }
+}
@@ -1,17 +1,81 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Instant;
+
+use blockifier::context::BlockContext;
+use blockifier::execution::contract_class::ContractClass;
 use blockifier::fee::gas_usage::estimate_minimal_gas_vector;
-use blockifier::state::cached_state::CachedState;
-use blockifier::state::state_api::State;
+use blockifier::state::cached_state::{CachedState, CommitmentStateDiff, GlobalContractCache};
+use blockifier::state::state_api::{State, StateReader};
 use blockifier::transaction::account_transaction::AccountTransaction;
-use blockifier::transaction::errors::TransactionExecutionError;
-use blockifier::transaction::objects::{FeeType, HasRelatedFeeType};
+use blockifier::transaction::errors::{TransactionExecutionError, TransactionFeeError, TransactionPreValidationError};
+use blockifier::transaction::objects::{FeeType, HasRelatedFeeType, TransactionExecutionInfo};
 use blockifier::transaction::transaction_execution::Transaction;
 use blockifier::transaction::transaction_types::TransactionType;
 use blockifier::transaction::transactions::ExecutableTransaction;
+use dp_receipt::TransactionReceipt;
+use rayon::prelude::*;
+use starknet_api::core::ClassHash;
 use starknet_api::transaction::TransactionHash;
+use starknet_types_core::felt::Felt;
 
-use crate::{Error, ExecutionContext, ExecutionResult, TxFeeEstimationError, TxReexecError};
+use crate::blockifier_state_adapter::BlockifierStateAdapter;
+use crate::parallel::{
+    apply_writes, written_keys, RecordingState, SequencerBalance, StateKey, PARALLEL_EXECUTION_MIN_TXS,
+};
+use crate::state_cache::SharedState;
+use crate::syscalls::report_syscalls;
+use crate::trace::make_state_diff;
+use crate::{
+    BlockExecution, Error, ExecutionCache, ExecutionContext, ExecutionResult, InsufficientBalanceError, OverrideState,
+    TxFeeEstimationError, TxReexecError,
+};
 
 impl<'a> ExecutionContext<'a> {
+    /// Same as [`ExecutionContext::execute_transactions`], but the results are looked up in the cache first. The
+    /// transactions are only executed if any of the results is missing, and their results are then cached.
+    ///
+    /// The cache is bypassed for the pending block, and when there are state or header overrides or a syscall hook.
+    pub fn execute_transactions_cached(
+        &self,
+        cache: &ExecutionCache,
+        transactions_before: Vec<Transaction>,
+        transactions_to_trace: Vec<Transaction>,
+        charge_fee: bool,
+        validate: bool,
+    ) -> Result<Vec<Arc<ExecutionResult>>, Error> {
+        let Some(block_hash) = self.cacheable_block_hash() else {
+            let results =
+                self.execute_transactions(transactions_before, transactions_to_trace, charge_fee, validate)?;
+            return Ok(results.into_iter().map(Arc::new).collect());
+        };
+
+        let tx_hashes = transactions_before.iter().chain(&transactions_to_trace).map(TxInfo::tx_hash);
+        let mut keys = cache.keys(block_hash, tx_hashes, charge_fee, validate, self.limits);
+        let keys = keys.split_off(transactions_before.len());
+        if let Some(results) = cache.get_all(&keys) {
+            return Ok(results);
+        }
+
+        let results: Vec<_> = self
+            .execute_transactions(transactions_before, transactions_to_trace, charge_fee, validate)?
+            .into_iter()
+            .map(Arc::new)
+            .collect();
+        cache.put_all(keys.into_iter().zip(results.iter().cloned()));
+        Ok(results)
+    }
+
+    /// Executes `transactions_to_trace` on top of `transactions_before`, and returns their results.
+    ///
+    /// Without `charge_fee`, as with the `SKIP_FEE_CHARGE` simulation flag, the balance of the senders is not checked
+    /// and the fee is not transferred: the results have no fee transfer call, but their fee is still computed.
+    /// Without `validate`, as with the `SKIP_VALIDATE` simulation flag, the validation entry point of the accounts is
+    /// not called and the results have no validation call. Both only apply to `transactions_to_trace`, the
+    /// transactions before are always executed as they were in their block.
+    ///
+    /// The transactions are executed in parallel, see the `parallel` module: a transaction is only executed again on
+    /// top of the transactions before it when it read a state key one of them wrote to.
     pub fn execute_transactions(
         &self,
         transactions_before: impl IntoIterator<Item = Transaction>,
@@ -19,64 +83,235 @@ impl<'a> ExecutionContext<'a> {
         charge_fee: bool,
         validate: bool,
     ) -> Result<Vec<ExecutionResult>, Error> {
-        let mut cached_state = self.init_cached_state();
-
-        let mut executed_prev = 0;
-        for (index, tx) in transactions_before.into_iter().enumerate() {
-            let hash = tx.tx_hash();
-            log::debug!("executing {hash:#}");
-            tx.execute(&mut cached_state, &self.block_context, charge_fee, validate).map_err(|err| TxReexecError {
-                block_n: self.db_id,
-                hash,
-                index,
-                err,
-            })?;
-            executed_prev += 1;
+        let mut transactions: Vec<_> = transactions_before.into_iter().collect();
+        let n_before = transactions.len();
+        transactions.extend(transactions_to_trace);
+
+        let (results, _) = self.execute_all(&transactions, n_before, charge_fee, validate)?;
+        Ok(results)
+    }
+
+    /// Executes the transactions of a block on top of the state of its parent block, and returns their results
+    /// along with the state diff of the whole block.
+    ///
+    /// The results are put in `cache` when it is given, so that tracing one of the transactions afterwards does not
+    /// execute the block again. The state diff is computed from the final state of the execution rather than by
+    /// merging the state diffs of the transactions.
+    pub fn execute_block(
+        &self,
+        cache: Option<&ExecutionCache>,
+        transactions: Vec<Transaction>,
+    ) -> Result<BlockExecution, Error> {
+        let (results, mut state) = self.execute_all(&transactions, 0, true, true)?;
+
+        let deprecated_declared_classes = transactions.iter().filter_map(TxInfo::deprecated_declared_class);
+        let state_diff =
+            make_state_diff(state.to_state_diff(), &mut self.init_state_adapter(), deprecated_declared_classes)
+                .map_err(Error::BlockStateDiff)?;
+
+        let results: Vec<_> = results.into_iter().map(Arc::new).collect();
+        if let (Some(cache), Some(block_hash)) = (cache, self.cacheable_block_hash()) {
+            let keys = cache.keys(block_hash, transactions.iter().map(TxInfo::tx_hash), true, true, self.limits);
+            cache.put_all(keys.into_iter().zip(results.iter().cloned()));
         }
 
-        transactions_to_trace
-            .into_iter()
-            .enumerate()
-            .map(|(index, tx)| {
-                let hash = tx.tx_hash();
-                log::debug!("executing {hash:#} (trace)");
-                let tx_type = tx.tx_type();
-                let fee_type = tx.fee_type();
-
-                let minimal_l1_gas = match &tx {
-                    Transaction::AccountTransaction(tx) => Some(
-                        estimate_minimal_gas_vector(&self.block_context, tx)
-                            .map_err(TransactionExecutionError::TransactionPreValidationError)
-                            .map_err(|err| TxFeeEstimationError { block_n: self.db_id, index, err })?,
-                    ),
-                    Transaction::L1HandlerTransaction(_) => None,
-                };
-                let mut state = CachedState::<_>::create_transactional(&mut cached_state);
-                let execution_info = tx
-                    .execute(&mut state, &self.block_context, charge_fee, validate)
-                    .and_then(|mut tx_info| {
-                        if tx_info.actual_fee.0 == 0 {
-                            tx_info.actual_fee = blockifier::fee::fee_utils::calculate_tx_fee(
-                                &tx_info.actual_resources,
-                                &self.block_context,
-                                &fee_type,
-                            )?;
-                        }
-                        Ok(tx_info)
-                    })
-                    .map_err(|err| TxReexecError { block_n: self.db_id, hash, index: executed_prev + index, err })?;
-                let state_diff = state.to_state_diff();
-                state.commit();
-                Ok(ExecutionResult { hash, tx_type, fee_type, minimal_l1_gas, execution_info, state_diff })
-            })
-            .collect::<Result<Vec<_>, _>>()
+        Ok(BlockExecution { results, state_diff })
+    }
+
+    /// Hash of the block the results of the executions can be cached for, `None` if they cannot be cached.
+    fn cacheable_block_hash(&self) -> Option<Felt> {
+        self.block_hash
+            .filter(|_| self.state_overrides.is_none() && !self.header_overridden && self.syscall_hook.is_none())
+    }
+
+    /// Executes the transactions one after the other, and returns the results of the transactions after the first
+    /// `n_before` along with the state they were all committed to.
+    fn execute_all(
+        &self,
+        transactions: &[Transaction],
+        n_before: usize,
+        charge_fee: bool,
+        validate: bool,
+    ) -> Result<(Vec<ExecutionResult>, CachedState<OverrideState<SharedState<BlockifierStateAdapter<'a>>>>), Error>
+    {
+        self.check_exact_fees()?;
+        let contract_cache = GlobalContractCache::new(16);
+        let speculations: Vec<_> = if transactions.len() >= PARALLEL_EXECUTION_MIN_TXS {
+            transactions
+                .par_iter()
+                .enumerate()
+                .map(|(index, tx)| {
+                    let mut state =
+                        CachedState::new(RecordingState::new(self.init_state_adapter()), contract_cache.clone());
+                    let execution = self.execute_transaction(&mut state, tx, index, n_before, charge_fee, validate);
+                    Some(Speculation { execution, reads: state.state.reads })
+                })
+                .collect()
+        } else {
+            transactions.iter().map(|_| None).collect()
+        };
+
+        let fee_tokens = &self.block_context.chain_info().fee_token_addresses;
+        let sequencer_balance = SequencerBalance::new(
+            self.block_context.block_info().sequencer_address,
+            [fee_tokens.eth_fee_token_address, fee_tokens.strk_fee_token_address],
+        );
+        let mut base_state = self.init_state_adapter();
+        let mut cached_state = CachedState::new(self.init_state_adapter(), contract_cache);
+        let mut written = HashSet::new();
+        let mut results = Vec::with_capacity(transactions.len() - n_before);
+
+        for ((index, tx), speculation) in transactions.iter().enumerate().zip(speculations) {
+            let execution = match speculation {
+                Some(Speculation { execution: Ok(mut execution), reads })
+                    if !sequencer_balance.conflicts(&reads, Some(&execution.result.execution_info), &written) =>
+                {
+                    sequencer_balance
+                        .rebase(
+                            &mut base_state,
+                            &mut cached_state,
+                            &mut execution.writes,
+                            &mut execution.result.state_diff,
+                        )
+                        .and_then(|()| apply_writes(&mut cached_state, &execution.writes, tx.declared_class()))
+                        .map_err(|err| TxReexecError {
+                            block_n: self.db_id,
+                            hash: tx.tx_hash(),
+                            index,
+                            err: err.into(),
+                        })?;
+                    execution
+                }
+                // Executing it again on top of the same state would fail the same way.
+                Some(Speculation { execution: Err(err), reads })
+                    if !sequencer_balance.conflicts(&reads, None, &written) =>
+                {
+                    return Err(err)
+                }
+                _ => self.execute_transaction(&mut cached_state, tx, index, n_before, charge_fee, validate)?,
+            };
+
+            written.extend(written_keys(&execution.writes, tx.declared_class().map(|(class_hash, _)| class_hash)));
+            if index >= n_before {
+                if let Some(hook) = &self.syscall_hook {
+                    report_syscalls(hook.as_ref(), &execution.result);
+                }
+                results.push(execution.result);
+            }
+        }
+
+        Ok((results, cached_state))
+    }
+
+    /// Executes a transaction on top of `state`, and commits its writes to it. `index` is the index of the
+    /// transaction among all the executed transactions, the first `n_before` of which are not traced. `charge_fee`
+    /// and `validate` only apply to the traced transactions.
+    fn execute_transaction<S: StateReader>(
+        &self,
+        state: &mut CachedState<S>,
+        tx: &Transaction,
+        index: usize,
+        n_before: usize,
+        charge_fee: bool,
+        validate: bool,
+    ) -> Result<TxExecution, Error> {
+        let hash = tx.tx_hash();
+        log::debug!("executing {hash:#}");
+        self.check_cancelled()?;
+
+        let minimal_l1_gas = match tx {
+            Transaction::AccountTransaction(tx) if index >= n_before => Some(
+                estimate_minimal_gas_vector(&self.block_context, tx)
+                    .map_err(TransactionExecutionError::TransactionPreValidationError)
+                    .map_err(|err| TxFeeEstimationError { block_n: self.db_id, index: index - n_before, err })?,
+            ),
+            _ => None,
+        };
+        // The transactions before are executed as they were in their block, whatever the simulation flags
+        let (charge_fee, validate) = if index >= n_before { (charge_fee, validate) } else { (true, true) };
+        let started_at = Instant::now();
+        let execution = execute_and_commit(&self.block_context, state, tx, charge_fee, validate);
+        // The execution fails or is reverted on the first state read once cancelled
+        self.check_cancelled()?;
+        let (execution_info, writes, state_diff) = execution.map_err(|err| match err {
+            err if index >= n_before && is_insufficient_balance(&err) => {
+                Error::from(InsufficientBalanceError { block_n: self.db_id, hash, index: index - n_before, err })
+            }
+            err => TxReexecError { block_n: self.db_id, hash, index, err }.into(),
+        })?;
+        if let Some(metrics) = &self.metrics {
+            metrics.transaction_executed(tx.tx_type(), &execution_info, started_at.elapsed());
+        }
+
+        Ok(TxExecution {
+            result: ExecutionResult {
+                hash,
+                tx_type: tx.tx_type(),
+                fee_type: tx.fee_type(),
+                minimal_l1_gas,
+                receipt: TransactionReceipt::from_blockifier_execution_info(tx, &execution_info),
+                execution_info,
+                state_diff,
+            },
+            writes,
+        })
     }
 }
 
-trait TxInfo {
+/// Executes a transaction on top of `state`, and commits its writes to it. Returns the execution info of the
+/// transaction, along with the writes it made and its state diff.
+pub(crate) fn execute_and_commit<S: StateReader>(
+    block_context: &BlockContext,
+    state: &mut CachedState<S>,
+    tx: &Transaction,
+    charge_fee: bool,
+    validate: bool,
+) -> Result<(TransactionExecutionInfo, CommitmentStateDiff, starknet_core::types::StateDiff), TransactionExecutionError>
+{
+    let mut state = CachedState::<_>::create_transactional(state);
+    let mut execution_info = tx.execute(&mut state, block_context, charge_fee, validate)?;
+    if execution_info.actual_fee.0 == 0 {
+        execution_info.actual_fee = blockifier::fee::fee_utils::calculate_tx_fee(
+            &execution_info.actual_resources,
+            block_context,
+            &tx.fee_type(),
+        )?;
+    }
+    let writes = state.to_state_diff();
+    let state_diff = make_state_diff(writes.clone(), &mut state.state, tx.deprecated_declared_class())?;
+    state.commit();
+    Ok((execution_info, writes, state_diff))
+}
+
+/// Whether the transaction was rejected because the balance of its sender does not cover its max fee or its resource
+/// bounds. A balance which does not cover the actual fee reverts the transaction instead.
+fn is_insufficient_balance(err: &TransactionExecutionError) -> bool {
+    matches!(
+        err,
+        TransactionExecutionError::TransactionPreValidationError(TransactionPreValidationError::TransactionFeeError(
+            TransactionFeeError::MaxFeeExceedsBalance { .. } | TransactionFeeError::L1GasBoundsExceedBalance { .. }
+        ))
+    )
+}
+
+/// An executed transaction, with the writes it made to the state it was executed on.
+struct TxExecution {
+    result: ExecutionResult,
+    writes: CommitmentStateDiff,
+}
+
+/// A transaction executed on top of the state of the block, with the state keys it read.
+struct Speculation {
+    execution: Result<TxExecution, Error>,
+    reads: HashSet<StateKey>,
+}
+
+pub(crate) trait TxInfo {
     fn tx_hash(&self) -> TransactionHash;
     fn tx_type(&self) -> TransactionType;
     fn fee_type(&self) -> FeeType;
+    fn deprecated_declared_class(&self) -> Option<ClassHash>;
+    fn declared_class(&self) -> Option<(ClassHash, ContractClass)>;
 }
 
 impl TxInfo for Transaction {
@@ -104,4 +339,23 @@ impl TxInfo for Transaction {
             Self::L1HandlerTransaction(tx) => tx.fee_type(),
         }
     }
+
+    fn deprecated_declared_class(&self) -> Option<ClassHash> {
+        match self {
+            Self::AccountTransaction(AccountTransaction::Declare(tx)) => match tx.contract_class() {
+                ContractClass::V0(_) => Some(tx.tx().class_hash()),
+                ContractClass::V1(_) => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn declared_class(&self) -> Option<(ClassHash, ContractClass)> {
+        match self {
+            Self::AccountTransaction(AccountTransaction::Declare(tx)) => {
+                Some((tx.tx().class_hash(), tx.contract_class()))
+            }
+            _ => None,
+        }
+    }
 }
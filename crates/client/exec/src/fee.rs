@@ -1,7 +1,24 @@
 use crate::{ExecutionContext, ExecutionResult};
-use blockifier::transaction::objects::FeeType;
+use dp_receipt::FeeBreakdown;
+
+/// Margins added to the estimated resources, in percent. Estimates matching the exact resources consumed tend to fail
+/// once the transaction is sent, as the state or the prices changed in the meantime.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FeeMargin {
+    pub gas_percent: u32,
+    pub data_gas_percent: u32,
+}
+
+fn with_margin(amount: u128, percent: u32) -> u128 {
+    amount.saturating_mul(100 + u128::from(percent)) / 100
+}
 
 impl<'a> ExecutionContext<'a> {
+    /// Adds this margin to the resources of the fee estimates.
+    pub fn with_fee_margin(self, fee_margin: FeeMargin) -> Self {
+        Self { fee_margin, ..self }
+    }
+
     pub fn execution_result_to_fee_estimate(
         &self,
         executions_result: &ExecutionResult,
@@ -15,28 +32,22 @@ impl<'a> ExecutionContext<'a> {
             .get_data_gas_price_by_fee_type(&executions_result.fee_type)
             .get();
 
-        let data_gas_consumed = executions_result.execution_info.da_gas.l1_data_gas;
-        let data_gas_fee = data_gas_consumed.saturating_mul(data_gas_price);
-        let gas_consumed =
-            executions_result.execution_info.actual_fee.0.saturating_sub(data_gas_fee) / gas_price.max(1);
-        let minimal_gas_consumed = executions_result.minimal_l1_gas.unwrap_or_default().l1_gas;
-        let minimal_data_gas_consumed = executions_result.minimal_l1_gas.unwrap_or_default().l1_data_gas;
-        let gas_consumed = gas_consumed.max(minimal_gas_consumed);
-        let data_gas_consumed = data_gas_consumed.max(minimal_data_gas_consumed);
-        let overall_fee =
-            gas_consumed.saturating_mul(gas_price).saturating_add(data_gas_consumed.saturating_mul(data_gas_price));
-
-        let unit = match executions_result.fee_type {
-            FeeType::Eth => starknet_core::types::PriceUnit::Wei,
-            FeeType::Strk => starknet_core::types::PriceUnit::Fri,
-        };
-        starknet_core::types::FeeEstimate {
-            gas_consumed: gas_consumed.into(),
-            gas_price: gas_price.into(),
-            data_gas_consumed: data_gas_consumed.into(),
-            data_gas_price: data_gas_price.into(),
-            overall_fee: overall_fee.into(),
-            unit,
+        let fee = FeeBreakdown::from_fee(
+            executions_result.execution_info.actual_fee.0,
+            executions_result.execution_info.da_gas.l1_data_gas,
+            gas_price,
+            data_gas_price,
+            executions_result.fee_type.into(),
+        );
+        let minimal_l1_gas = executions_result.minimal_l1_gas.unwrap_or_default();
+        FeeBreakdown {
+            gas_consumed: with_margin(fee.gas_consumed.max(minimal_l1_gas.l1_gas), self.fee_margin.gas_percent),
+            data_gas_consumed: with_margin(
+                fee.data_gas_consumed.max(minimal_l1_gas.l1_data_gas),
+                self.fee_margin.data_gas_percent,
+            ),
+            ..fee
         }
+        .into()
     }
 }
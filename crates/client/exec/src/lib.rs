@@ -1,38 +1,90 @@
 mod block_context;
 mod blockifier_state_adapter;
+mod cache;
 mod call;
+mod cancel;
+mod context_pool;
 mod execution;
 mod fee;
+mod limits;
+mod message;
+mod metrics;
+mod parallel;
+mod replay;
+mod state_cache;
+mod state_overrides;
+mod syscalls;
+mod tight_bounds;
 mod trace;
+mod transaction;
+mod verify;
+mod versioned_constants;
 
-pub use block_context::ExecutionContext;
-use blockifier::{
-    state::cached_state::CommitmentStateDiff,
-    transaction::{
-        errors::TransactionExecutionError,
-        objects::{FeeType, GasVector, TransactionExecutionInfo},
-        transaction_types::TransactionType,
-    },
+use std::sync::Arc;
+
+pub use block_context::{BlockHeaderOverrides, ExecutionContext, ETH_TOKEN_ADDR, STRK_TOKEN_ADDR};
+use blockifier::state::errors::StateError;
+use blockifier::transaction::{
+    errors::TransactionExecutionError,
+    objects::{FeeType, GasVector, TransactionExecutionInfo},
+    transaction_types::TransactionType,
 };
+pub use cache::ExecutionCache;
+pub use call::CallResources;
+pub use cancel::{CancelOnDrop, CancellationToken};
+pub use context_pool::ExecutionContextPool;
 use dc_db::{db_block_id::DbBlockId, DeoxysStorageError};
+use dp_block::StarknetVersion;
+pub use fee::FeeMargin;
+pub use limits::ExecutionLimits;
+pub use metrics::ExecutionMetrics;
+pub use replay::{record_replay, ReplayLog, ReplayTransaction, StateReads};
 use starknet_api::transaction::TransactionHash;
+use starknet_api::StarknetApiError;
 use starknet_core::types::Felt;
+pub use state_cache::StateCache;
+pub use state_overrides::{ContractStateOverride, OverrideState, StateOverrides, StateOverridesDiff};
+pub use syscalls::{Syscall, SyscallHook};
+pub use tight_bounds::TightBounds;
 pub use trace::execution_result_to_tx_trace;
+pub use transaction::{block_to_blockifier_transactions, to_blockifier_transaction};
+pub use verify::{verify_block_execution, verify_blocks_execution, Divergence, DivergenceKind};
+pub use versioned_constants::{load_versioned_constants, VersionedConstantsError};
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
-    #[error("Unsupported protocol version")]
-    UnsupportedProtocolVersion,
+    #[error("Unsupported protocol version {0}, its versioned constants are unknown")]
+    UnsupportedProtocolVersion(StarknetVersion),
     #[error("{0:#}")]
     Reexecution(#[from] TxReexecError),
     #[error("{0:#}")]
     FeeEstimation(#[from] TxFeeEstimationError),
     #[error("{0:#}")]
+    InsufficientBalance(#[from] InsufficientBalanceError),
+    #[error("{0:#}")]
     MessageFeeEstimation(#[from] MessageFeeEstimationError),
     #[error("{0:#}")]
     CallContract(#[from] CallContractError),
     #[error("Storage error: {0:#}")]
     Storage(#[from] DeoxysStorageError),
+    #[error("Invalid state override: {0:#}")]
+    StateOverride(#[from] StarknetApiError),
+    #[error("Invalid block header override: {0:#}")]
+    HeaderOverride(StarknetApiError),
+    #[error("Block #{0} not found")]
+    BlockNotFound(u64),
+    #[error("Class {0:#x} not found")]
+    ClassNotFound(Felt),
+    #[error("Transaction {0:#x} not found")]
+    TransactionNotFound(Felt),
+    #[error("Converting transaction {hash:#} to a blockifier transaction: {reason}")]
+    TransactionConversion { hash: TransactionHash, reason: String },
+    #[error("Computing the state diff of the block: {0:#}")]
+    BlockStateDiff(StateError),
+    #[error("Invalid replay log: {0}")]
+    InvalidReplayLog(String),
+    #[error("Execution cancelled")]
+    Cancelled,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -44,6 +96,18 @@ pub struct TxReexecError {
     err: TransactionExecutionError,
 }
 
+impl TxReexecError {
+    /// Index of the failing transaction among the executed transactions.
+    pub fn tx_index(&self) -> usize {
+        self.index
+    }
+
+    /// Why the execution failed.
+    pub fn execution_error(&self) -> &TransactionExecutionError {
+        &self.err
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 #[error("Estimating fee for tx index {index} on top of {block_n}: {err:#}")]
 pub struct TxFeeEstimationError {
@@ -52,6 +116,31 @@ pub struct TxFeeEstimationError {
     err: TransactionExecutionError,
 }
 
+impl TxFeeEstimationError {
+    /// Index of the failing transaction among the executed transactions.
+    pub fn tx_index(&self) -> usize {
+        self.index
+    }
+}
+
+/// The balance of the sender of a traced transaction does not cover its max fee or its resource bounds. Only
+/// checked when the fee is charged.
+#[derive(thiserror::Error, Debug)]
+#[error("Executing tx {hash:#} (index {index}) on top of {block_n}: insufficient balance: {err:#}")]
+pub struct InsufficientBalanceError {
+    block_n: DbBlockId,
+    hash: TransactionHash,
+    index: usize,
+    err: TransactionExecutionError,
+}
+
+impl InsufficientBalanceError {
+    /// Index of the transaction among the traced transactions.
+    pub fn tx_index(&self) -> usize {
+        self.index
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 #[error("Estimating message fee on top of {block_n}: {err:#}")]
 pub struct MessageFeeEstimationError {
@@ -73,5 +162,14 @@ pub struct ExecutionResult {
     pub fee_type: FeeType,
     pub minimal_l1_gas: Option<GasVector>,
     pub execution_info: TransactionExecutionInfo,
-    pub state_diff: CommitmentStateDiff,
+    pub state_diff: starknet_core::types::StateDiff,
+    /// The receipt of the transaction, as the sequencer would produce it.
+    pub receipt: dp_receipt::TransactionReceipt,
+}
+
+/// The execution of all the transactions of a block, see [`ExecutionContext::execute_block`].
+pub struct BlockExecution {
+    /// In block order.
+    pub results: Vec<Arc<ExecutionResult>>,
+    pub state_diff: starknet_core::types::StateDiff,
 }
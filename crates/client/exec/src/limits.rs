@@ -0,0 +1,35 @@
+use blockifier::context::BlockContext;
+use blockifier::versioned_constants::VersionedConstants;
+
+/// Limits on the resources used by the execution. The limits left to `None` are the ones of the versioned constants
+/// of the block.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct ExecutionLimits {
+    /// Maximum number of steps of the execution of a transaction, also used for `call_contract`.
+    pub max_steps: Option<u32>,
+    /// Maximum number of steps of the validation of a transaction.
+    pub validate_max_steps: Option<u32>,
+    /// Maximum depth of nested calls.
+    pub max_recursion_depth: Option<usize>,
+}
+
+impl ExecutionLimits {
+    fn apply(&self, versioned_constants: &mut VersionedConstants) {
+        if let Some(max_steps) = self.max_steps {
+            versioned_constants.invoke_tx_max_n_steps = max_steps;
+        }
+        if let Some(validate_max_steps) = self.validate_max_steps {
+            versioned_constants.validate_max_n_steps = validate_max_steps;
+        }
+        if let Some(max_recursion_depth) = self.max_recursion_depth {
+            versioned_constants.max_recursion_depth = max_recursion_depth;
+        }
+    }
+
+    /// The block context with these limits instead of the ones of its versioned constants.
+    pub(crate) fn apply_to_context(&self, block_context: &BlockContext) -> BlockContext {
+        let mut versioned_constants = block_context.versioned_constants().clone();
+        self.apply(&mut versioned_constants);
+        BlockContext::new_unchecked(block_context.block_info(), block_context.chain_info(), &versioned_constants)
+    }
+}
@@ -0,0 +1,44 @@
+//! Execution of the messages sent from L1.
+//!
+//! A message sent from L1 is executed on L2 as an L1 handler transaction. It has no account to charge: its fee is
+//! paid on L1 along with the message, and must cover the L1 gas of the execution as well as the data gas of its
+//! state diff.
+
+use blockifier::transaction::transaction_execution::Transaction;
+use blockifier::transaction::transactions::L1HandlerTransaction;
+use dp_convert::ToStarkFelt;
+use starknet_api::transaction::{Fee, TransactionHash};
+use starknet_types_core::felt::Felt;
+
+use crate::{Error, ExecutionContext, MessageFeeEstimationError, TxReexecError};
+
+impl<'a> ExecutionContext<'a> {
+    /// Converts a message sent from L1 to the L1 handler transaction executing it. `paid_fee_on_l1` is the fee paid
+    /// on L1 along with the message, in wei.
+    pub fn l1_handler_transaction(
+        &self,
+        message: &dp_transactions::L1HandlerTransaction,
+        paid_fee_on_l1: Fee,
+    ) -> Result<Transaction, Error> {
+        let chain_id = Felt::from_bytes_be_slice(self.block_context.chain_info().chain_id.0.as_bytes());
+        let hash = TransactionHash(message.compute_hash(chain_id, false, false).to_stark_felt());
+        let tx = message.try_into().map_err(|err| Error::TransactionConversion { hash, reason: format!("{err:#}") })?;
+        Ok(Transaction::L1HandlerTransaction(L1HandlerTransaction { tx, tx_hash: hash, paid_fee_on_l1 }))
+    }
+
+    /// Executes a message sent from L1, and estimates the fee to pay on L1 for it. The overall fee of the estimate is
+    /// the fee the message must be sent with, in wei.
+    pub fn estimate_message_fee(
+        &self,
+        message: &dp_transactions::L1HandlerTransaction,
+    ) -> Result<starknet_core::types::FeeEstimate, Error> {
+        // The fee actually required is only known once executed, and blockifier only checks that some fee was paid.
+        let transaction = self.l1_handler_transaction(message, Fee(u128::MAX))?;
+        let mut results = self.execute_transactions([], [transaction], true, false).map_err(|err| match err {
+            Error::Reexecution(TxReexecError { block_n, err, .. }) => MessageFeeEstimationError { block_n, err }.into(),
+            err => err,
+        })?;
+        let result = results.pop().expect("One transaction was executed");
+        Ok(self.execution_result_to_fee_estimate(&result))
+    }
+}
@@ -0,0 +1,99 @@
+use std::time::Duration;
+
+use blockifier::transaction::objects::TransactionExecutionInfo;
+use blockifier::transaction::transaction_types::TransactionType;
+use dc_metrics::{
+    exponential_buckets, CounterVec, Gauge, HistogramOpts, HistogramVec, MetricsRegistry, Opts, PrometheusError, F64,
+    U64,
+};
+
+/// Metrics of the execution of the transactions.
+#[derive(Clone, Debug)]
+pub struct ExecutionMetrics {
+    /// Number of transactions executed, by transaction type.
+    transactions: CounterVec<U64>,
+    /// Time blockifier took to execute a transaction, in seconds, by transaction type.
+    transaction_time: HistogramVec,
+    /// Number of Cairo steps run by the transactions, by transaction type.
+    steps: CounterVec<U64>,
+    /// Number of builtin applications of the transactions, by builtin.
+    builtins: CounterVec<U64>,
+    /// Lookups of the contract classes in the state cache, by whether the class was cached.
+    class_cache_lookups: CounterVec<U64>,
+    /// Number of transactions re-executed per second by `--verify-execution`, over the last blocks.
+    verified_transactions_per_second: Gauge<F64>,
+}
+
+impl ExecutionMetrics {
+    pub fn register(registry: &MetricsRegistry) -> Result<Self, PrometheusError> {
+        Ok(Self {
+            transactions: registry.register(CounterVec::new(
+                Opts::new("exec_transactions", "Number of transactions executed"),
+                &["tx_type"],
+            )?)?,
+            transaction_time: registry.register(HistogramVec::new(
+                HistogramOpts::new(
+                    "exec_transaction_time",
+                    "Time blockifier took to execute a transaction, in seconds",
+                )
+                .buckets(exponential_buckets(0.0001, 4.0, 9)?),
+                &["tx_type"],
+            )?)?,
+            steps: registry
+                .register(CounterVec::new(Opts::new("exec_steps", "Number of Cairo steps run"), &["tx_type"])?)?,
+            builtins: registry.register(CounterVec::new(
+                Opts::new("exec_builtin_applications", "Number of builtin applications"),
+                &["builtin"],
+            )?)?,
+            class_cache_lookups: registry.register(CounterVec::new(
+                Opts::new("exec_class_cache_lookups", "Lookups of the contract classes in the state cache"),
+                &["result"],
+            )?)?,
+            verified_transactions_per_second: registry.register(Gauge::new(
+                "exec_verified_transactions_per_second",
+                "Number of transactions re-executed per second when verifying the execution of the blocks",
+            )?)?,
+        })
+    }
+
+    pub(crate) fn transaction_executed(
+        &self,
+        tx_type: TransactionType,
+        execution_info: &TransactionExecutionInfo,
+        time: Duration,
+    ) {
+        let tx_type = tx_type_label(tx_type);
+        self.transactions.with_label_values(&[tx_type]).inc();
+        self.transaction_time.with_label_values(&[tx_type]).observe(time.as_secs_f64());
+
+        // The resources of a call include the ones of its inner calls
+        let calls = [
+            &execution_info.validate_call_info,
+            &execution_info.execute_call_info,
+            &execution_info.fee_transfer_call_info,
+        ];
+        for resources in calls.into_iter().flatten().map(|call| &call.resources) {
+            self.steps.with_label_values(&[tx_type]).inc_by(resources.n_steps as u64);
+            for (builtin, count) in &resources.builtin_instance_counter {
+                self.builtins.with_label_values(&[builtin]).inc_by(*count as u64);
+            }
+        }
+    }
+
+    pub(crate) fn class_cache_lookup(&self, hit: bool) {
+        self.class_cache_lookups.with_label_values(&[if hit { "hit" } else { "miss" }]).inc();
+    }
+
+    pub(crate) fn set_verification_rate(&self, transactions: usize, time: Duration) {
+        self.verified_transactions_per_second.set(transactions as f64 / time.as_secs_f64().max(f64::EPSILON));
+    }
+}
+
+fn tx_type_label(tx_type: TransactionType) -> &'static str {
+    match tx_type {
+        TransactionType::Declare => "declare",
+        TransactionType::DeployAccount => "deploy_account",
+        TransactionType::InvokeFunction => "invoke",
+        TransactionType::L1Handler => "l1_handler",
+    }
+}
@@ -0,0 +1,243 @@
+//! Optimistic parallel execution of the transactions of a block.
+//!
+//! Every transaction is first executed on its own on top of the state of the block, on the rayon thread pool,
+//! while recording the state keys it reads. The results are then committed in order: a transaction is only
+//! executed again, on top of the transactions committed before it, when it read a key one of them wrote to.
+
+use std::collections::HashSet;
+
+use blockifier::abi::abi_utils::get_storage_var_address;
+use blockifier::execution::call_info::CallInfo;
+use blockifier::execution::contract_class::ContractClass;
+use blockifier::state::cached_state::{CachedState, CommitmentStateDiff};
+use blockifier::state::errors::StateError;
+use blockifier::state::state_api::{State, StateReader, StateResult};
+use blockifier::transaction::objects::TransactionExecutionInfo;
+use dp_convert::ToFelt;
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce};
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::StorageKey;
+
+/// Below this number of transactions, they are executed serially: the speculative execution is not worth it.
+pub(crate) const PARALLEL_EXECUTION_MIN_TXS: usize = 4;
+
+/// A key of the state, read or written by a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum StateKey {
+    Storage(ContractAddress, StorageKey),
+    Nonce(ContractAddress),
+    ClassHash(ContractAddress),
+    CompiledClassHash(ClassHash),
+    Class(ClassHash),
+}
+
+/// The keys written by a transaction. `declared_class` is the class declared by the transaction, if any.
+pub(crate) fn written_keys(
+    writes: &CommitmentStateDiff,
+    declared_class: Option<ClassHash>,
+) -> impl Iterator<Item = StateKey> + '_ {
+    let storage = writes
+        .storage_updates
+        .iter()
+        .flat_map(|(address, storage)| storage.keys().map(|key| StateKey::Storage(*address, *key)));
+    let nonces = writes.address_to_nonce.keys().map(|address| StateKey::Nonce(*address));
+    let class_hashes = writes.address_to_class_hash.keys().map(|address| StateKey::ClassHash(*address));
+    let compiled_class_hashes = writes
+        .class_hash_to_compiled_class_hash
+        .keys()
+        .flat_map(|class_hash| [StateKey::CompiledClassHash(*class_hash), StateKey::Class(*class_hash)]);
+
+    storage.chain(nonces).chain(class_hashes).chain(compiled_class_hashes).chain(declared_class.map(StateKey::Class))
+}
+
+/// Applies the writes of a transaction executed speculatively to the state it is committed on.
+pub(crate) fn apply_writes<S: StateReader>(
+    state: &mut CachedState<S>,
+    writes: &CommitmentStateDiff,
+    declared_class: Option<(ClassHash, ContractClass)>,
+) -> StateResult<()> {
+    for (address, storage) in &writes.storage_updates {
+        for (key, value) in storage {
+            state.set_storage_at(*address, *key, *value)?;
+        }
+    }
+    // The transaction did not conflict, so the nonces it incremented have not changed since it read them.
+    for address in writes.address_to_nonce.keys() {
+        state.increment_nonce(*address)?;
+    }
+    for (address, class_hash) in &writes.address_to_class_hash {
+        state.set_class_hash_at(*address, *class_hash)?;
+    }
+    for (class_hash, compiled_class_hash) in &writes.class_hash_to_compiled_class_hash {
+        state.set_compiled_class_hash(*class_hash, *compiled_class_hash)?;
+    }
+    if let Some((class_hash, contract_class)) = declared_class {
+        state.set_contract_class(class_hash, contract_class)?;
+    }
+    Ok(())
+}
+
+/// State reader recording the keys a transaction reads.
+pub(crate) struct RecordingState<S> {
+    inner: S,
+    pub(crate) reads: HashSet<StateKey>,
+}
+
+impl<S> RecordingState<S> {
+    pub(crate) fn new(inner: S) -> Self {
+        Self { inner, reads: HashSet::new() }
+    }
+}
+
+impl<S: StateReader> StateReader for RecordingState<S> {
+    fn get_storage_at(&mut self, contract_address: ContractAddress, key: StorageKey) -> StateResult<StarkFelt> {
+        self.reads.insert(StateKey::Storage(contract_address, key));
+        self.inner.get_storage_at(contract_address, key)
+    }
+
+    fn get_nonce_at(&mut self, contract_address: ContractAddress) -> StateResult<Nonce> {
+        self.reads.insert(StateKey::Nonce(contract_address));
+        self.inner.get_nonce_at(contract_address)
+    }
+
+    fn get_class_hash_at(&mut self, contract_address: ContractAddress) -> StateResult<ClassHash> {
+        self.reads.insert(StateKey::ClassHash(contract_address));
+        self.inner.get_class_hash_at(contract_address)
+    }
+
+    fn get_compiled_contract_class(&mut self, class_hash: ClassHash) -> StateResult<ContractClass> {
+        self.reads.insert(StateKey::Class(class_hash));
+        self.inner.get_compiled_contract_class(class_hash)
+    }
+
+    fn get_compiled_class_hash(&mut self, class_hash: ClassHash) -> StateResult<CompiledClassHash> {
+        self.reads.insert(StateKey::CompiledClassHash(class_hash));
+        self.inner.get_compiled_class_hash(class_hash)
+    }
+}
+
+/// Balance of the sequencer in the fee tokens.
+///
+/// The fee transfer of every transaction reads and writes it, so it is not considered as a conflict between
+/// transactions: the change a transaction made to the balance is instead added on top of the balance committed by
+/// the transactions before it. This only holds if the transaction did not access the balance outside of its fee
+/// transfer.
+pub(crate) struct SequencerBalance {
+    tokens: [ContractAddress; 2],
+    /// The low and high 128 bits of the balance.
+    keys: [StorageKey; 2],
+}
+
+impl SequencerBalance {
+    pub(crate) fn new(sequencer_address: ContractAddress, tokens: [ContractAddress; 2]) -> Self {
+        let low_key = get_storage_var_address("ERC20_balances", &[*sequencer_address.0.key()]);
+        // Storage variable addresses are always below the bound of the storage keys
+        let high_key = low_key.next_storage_key().expect("Storage variable address out of bounds");
+        Self { tokens, keys: [low_key, high_key] }
+    }
+
+    fn is_balance_key(&self, key: &StateKey) -> bool {
+        matches!(key, StateKey::Storage(address, key) if self.tokens.contains(address) && self.keys.contains(key))
+    }
+
+    /// Whether the validation or the execution of the transaction accessed the balance.
+    fn accessed_by(&self, execution_info: &TransactionExecutionInfo) -> bool {
+        fn accessed(balance: &SequencerBalance, call: &CallInfo) -> bool {
+            (balance.tokens.contains(&call.call.storage_address)
+                && call.accessed_storage_keys.iter().any(|key| balance.keys.contains(key)))
+                || call.inner_calls.iter().any(|call| accessed(balance, call))
+        }
+
+        [&execution_info.validate_call_info, &execution_info.execute_call_info]
+            .into_iter()
+            .flatten()
+            .any(|call| accessed(self, call))
+    }
+
+    /// Whether the keys read by a transaction were written by the transactions committed before it.
+    pub(crate) fn conflicts(
+        &self,
+        reads: &HashSet<StateKey>,
+        execution_info: Option<&TransactionExecutionInfo>,
+        written: &HashSet<StateKey>,
+    ) -> bool {
+        let rebasable = execution_info.is_some_and(|execution_info| !self.accessed_by(execution_info));
+        reads.iter().any(|key| written.contains(key) && !(rebasable && self.is_balance_key(key)))
+    }
+
+    /// Rebases the balance written by a transaction executed on top of `base`, on top of `state`. The rebased values
+    /// are updated in both the writes and the state diff of the transaction.
+    pub(crate) fn rebase(
+        &self,
+        base: &mut impl StateReader,
+        state: &mut impl StateReader,
+        writes: &mut CommitmentStateDiff,
+        state_diff: &mut starknet_core::types::StateDiff,
+    ) -> StateResult<()> {
+        let [low_key, high_key] = self.keys;
+        for token in self.tokens {
+            let Some(storage) = writes.storage_updates.get_mut(&token) else { continue };
+            if !self.keys.iter().any(|key| storage.contains_key(key)) {
+                continue;
+            }
+
+            let read = (base.get_storage_at(token, low_key)?, base.get_storage_at(token, high_key)?);
+            let written =
+                (storage.get(&low_key).copied().unwrap_or(read.0), storage.get(&high_key).copied().unwrap_or(read.1));
+            let committed = (state.get_storage_at(token, low_key)?, state.get_storage_at(token, high_key)?);
+            let rebased = rebase_u256(read, written, committed)
+                .ok_or_else(|| StateError::StateReadError("Sequencer balance is not a valid u256".into()))?;
+
+            for (key, value, committed) in [(low_key, rebased.0, committed.0), (high_key, rebased.1, committed.1)] {
+                if storage.contains_key(&key) || value != committed {
+                    storage.insert(key, value);
+                    set_storage_entry(state_diff, token, key, value);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `committed + (written - read)`, on u256 values split in their low and high 128 bits.
+fn rebase_u256(
+    read: (StarkFelt, StarkFelt),
+    written: (StarkFelt, StarkFelt),
+    committed: (StarkFelt, StarkFelt),
+) -> Option<(StarkFelt, StarkFelt)> {
+    fn to_u128(value: StarkFelt) -> Option<u128> {
+        let (high, low) = value.bytes().split_at(16);
+        high.iter().all(|byte| *byte == 0).then(|| u128::from_be_bytes(low.try_into().expect("16 bytes")))
+    }
+
+    let (read_low, read_high) = (to_u128(read.0)?, to_u128(read.1)?);
+    let (written_low, written_high) = (to_u128(written.0)?, to_u128(written.1)?);
+    let (committed_low, committed_high) = (to_u128(committed.0)?, to_u128(committed.1)?);
+
+    let (delta_low, borrow) = written_low.overflowing_sub(read_low);
+    let delta_high = written_high.wrapping_sub(read_high).wrapping_sub(borrow as u128);
+    let (low, carry) = committed_low.overflowing_add(delta_low);
+    let high = committed_high.wrapping_add(delta_high).wrapping_add(carry as u128);
+
+    Some((StarkFelt::from(low), StarkFelt::from(high)))
+}
+
+fn set_storage_entry(
+    state_diff: &mut starknet_core::types::StateDiff,
+    address: ContractAddress,
+    key: StorageKey,
+    value: StarkFelt,
+) {
+    let (address, key, value) = (address.to_felt(), key.to_felt(), value.to_felt());
+    let Some(item) = state_diff.storage_diffs.iter_mut().find(|item| item.address == address) else {
+        state_diff.storage_diffs.push(starknet_core::types::ContractStorageDiffItem {
+            address,
+            storage_entries: vec![starknet_core::types::StorageEntry { key, value }],
+        });
+        return;
+    };
+    match item.storage_entries.iter_mut().find(|entry| entry.key == key) {
+        Some(entry) => entry.value = value,
+        None => item.storage_entries.push(starknet_core::types::StorageEntry { key, value }),
+    }
+}
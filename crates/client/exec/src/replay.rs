@@ -0,0 +1,260 @@
+//! Deterministic replay of the execution of a transaction.
+//!
+//! Recording a transaction re-executes it on top of the transactions before it in its block, and keeps every value
+//! the execution read from the state of the parent block. The resulting [`ReplayLog`] is self-contained: it can be
+//! written to a file, attached to a bug report, and executed again with [`ReplayLog::replay`] without a database.
+
+use std::collections::HashMap;
+use std::io;
+use std::sync::{Arc, Mutex};
+
+use blockifier::execution::contract_class::ContractClass;
+use blockifier::state::cached_state::{CachedState, GlobalContractCache};
+use blockifier::state::errors::StateError;
+use blockifier::state::state_api::{StateReader, StateResult};
+use dc_db::db_block_id::DbBlockId;
+use dc_db::DeoxysBackend;
+use dp_block::DeoxysMaybePendingBlockInfo;
+use dp_class::{to_blockifier_class, ClassInfo, CompiledClass};
+use dp_convert::{ToFelt, ToStarkFelt};
+use dp_receipt::TransactionReceipt;
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce};
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::StorageKey;
+use starknet_api::transaction::TransactionHash;
+use starknet_types_core::felt::Felt;
+
+use crate::block_context::make_block_context;
+use crate::execution::{execute_and_commit, TxInfo};
+use crate::state_cache::BlockStateCache;
+use crate::transaction::convert_transaction;
+use crate::{Error, ExecutionContext, ExecutionLimits, ExecutionResult, TxReexecError};
+
+/// The execution of a transaction, with everything needed to execute it again without a database.
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct ReplayLog {
+    pub chain_id: Felt,
+    /// Number of the block of the transaction, which the pending block does not know.
+    pub block_number: u64,
+    pub block_info: DeoxysMaybePendingBlockInfo,
+    pub limits: ExecutionLimits,
+    /// The transactions before the recorded transaction in its block, followed by the recorded transaction.
+    pub transactions: Vec<ReplayTransaction>,
+    pub reads: StateReads,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct ReplayTransaction {
+    pub hash: Felt,
+    pub transaction: dp_transactions::Transaction,
+    /// The class declared by a declare transaction.
+    pub declared_class: Option<(ClassInfo, CompiledClass)>,
+}
+
+/// Values the execution read from the state of the parent block.
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+pub struct StateReads {
+    /// `(contract_address, key, value)`
+    pub storage: Vec<(Felt, Felt, Felt)>,
+    /// `(contract_address, nonce)`
+    pub nonces: Vec<(Felt, Felt)>,
+    /// `(contract_address, class_hash)`
+    pub class_hashes: Vec<(Felt, Felt)>,
+    /// `(class_hash, compiled_class_hash)`
+    pub compiled_class_hashes: Vec<(Felt, Felt)>,
+    pub classes: Vec<(Felt, CompiledClass)>,
+}
+
+/// Re-executes a transaction of the database on top of the transactions before it in its block, and records a
+/// replay log of the execution.
+pub fn record_replay(backend: &DeoxysBackend, tx_hash: Felt, limits: ExecutionLimits) -> Result<ReplayLog, Error> {
+    let (block, tx_index) = backend.find_tx_hash_block(&tx_hash)?.ok_or(Error::TransactionNotFound(tx_hash))?;
+    let block_id = block.info.as_block_id();
+    let block_number = match &block.info {
+        DeoxysMaybePendingBlockInfo::Pending(_) => backend.get_latest_block_n()?.map(|el| el + 1).unwrap_or(0),
+        DeoxysMaybePendingBlockInfo::NotPending(block) => block.header.block_number,
+    };
+
+    // Every value read from the state of the parent block ends up in the state cache of the execution
+    let reads = Arc::new(BlockStateCache::default());
+    let exec_context = ExecutionContext {
+        state_cache: Some(Arc::clone(&reads)),
+        ..ExecutionContext::new(backend, &block.info)?.with_limits(limits)
+    };
+
+    let mut transactions = vec![];
+    let mut replay_transactions = vec![];
+    for (transaction, hash) in block.inner.transactions.iter().zip(block.info.tx_hashes()).take(tx_index.0 as usize + 1)
+    {
+        let mut declared_class_hash = None;
+        transactions.push(convert_transaction(transaction, &TransactionHash(hash.to_stark_felt()), |class_hash| {
+            declared_class_hash = Some(class_hash);
+            backend.get_class(&block_id, &class_hash)?.ok_or(Error::ClassNotFound(class_hash))
+        })?);
+        let declared_class = declared_class_hash
+            .map(|class_hash| backend.get_class(&block_id, &class_hash)?.ok_or(Error::ClassNotFound(class_hash)))
+            .transpose()?;
+        replay_transactions.push(ReplayTransaction { hash: *hash, transaction: transaction.clone(), declared_class });
+    }
+
+    let transaction = transactions.pop().ok_or(Error::TransactionNotFound(tx_hash))?;
+    exec_context.execute_transactions(transactions, [transaction], true, true)?;
+
+    let reads = StateReads {
+        storage: lock(&reads.storage)
+            .iter()
+            .map(|((address, key), value)| (address.to_felt(), key.to_felt(), value.to_felt()))
+            .collect(),
+        nonces: lock(&reads.nonces).iter().map(|(address, nonce)| (address.to_felt(), nonce.to_felt())).collect(),
+        class_hashes: lock(&reads.class_hashes)
+            .iter()
+            .map(|(address, class_hash)| (address.to_felt(), class_hash.to_felt()))
+            .collect(),
+        compiled_class_hashes: lock(&reads.compiled_class_hashes)
+            .iter()
+            .map(|(class_hash, compiled_class_hash)| (class_hash.to_felt(), compiled_class_hash.to_felt()))
+            .collect(),
+        classes: match exec_context.on_top_of() {
+            Some(on_top_of) => lock(&reads.classes)
+                .keys()
+                .map(|class_hash| {
+                    let class_hash = class_hash.to_felt();
+                    let (_, compiled_class) =
+                        backend.get_class(&on_top_of, &class_hash)?.ok_or(Error::ClassNotFound(class_hash))?;
+                    Ok((class_hash, compiled_class))
+                })
+                .collect::<Result<_, Error>>()?,
+            None => vec![],
+        },
+    };
+
+    Ok(ReplayLog {
+        chain_id: backend.chain_info()?.chain_id,
+        block_number,
+        block_info: block.info,
+        limits,
+        transactions: replay_transactions,
+        reads,
+    })
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().expect("poisoned mutex")
+}
+
+impl ReplayLog {
+    /// Writes the log as JSON.
+    pub fn write(&self, writer: impl io::Write) -> serde_json::Result<()> {
+        serde_json::to_writer(writer, self)
+    }
+
+    /// Reads a log written with [`ReplayLog::write`].
+    pub fn read(reader: impl io::Read) -> serde_json::Result<Self> {
+        serde_json::from_reader(reader)
+    }
+
+    /// Executes the transactions of the log one after the other, without a database, and returns the result of the
+    /// recorded transaction.
+    pub fn replay(self) -> Result<ExecutionResult, Error> {
+        let block_context =
+            self.limits.apply_to_context(&make_block_context(self.chain_id, &self.block_info, self.block_number)?);
+        let db_id = match self.block_info {
+            DeoxysMaybePendingBlockInfo::Pending(_) => DbBlockId::Pending,
+            DeoxysMaybePendingBlockInfo::NotPending(_) => DbBlockId::BlockN(self.block_number),
+        };
+        let mut state = CachedState::new(ReplayState::new(self.reads)?, GlobalContractCache::new(16));
+
+        let mut result = None;
+        for (index, ReplayTransaction { hash, transaction, declared_class }) in
+            self.transactions.into_iter().enumerate()
+        {
+            let hash = TransactionHash(hash.to_stark_felt());
+            let tx = convert_transaction(&transaction, &hash, |class_hash| {
+                declared_class.ok_or(Error::ClassNotFound(class_hash))
+            })?;
+            let (execution_info, _, state_diff) = execute_and_commit(&block_context, &mut state, &tx, true, true)
+                .map_err(|err| TxReexecError { block_n: db_id, hash, index, err })?;
+            result = Some(ExecutionResult {
+                hash,
+                tx_type: tx.tx_type(),
+                fee_type: tx.fee_type(),
+                minimal_l1_gas: None,
+                receipt: TransactionReceipt::from_blockifier_execution_info(&tx, &execution_info),
+                execution_info,
+                state_diff,
+            });
+        }
+        result.ok_or_else(|| Error::InvalidReplayLog("The log has no transaction".into()))
+    }
+}
+
+/// State reader answering from the values recorded in a replay log.
+struct ReplayState {
+    storage: HashMap<(Felt, Felt), Felt>,
+    nonces: HashMap<Felt, Felt>,
+    class_hashes: HashMap<Felt, Felt>,
+    compiled_class_hashes: HashMap<Felt, Felt>,
+    classes: HashMap<Felt, ContractClass>,
+}
+
+impl ReplayState {
+    fn new(reads: StateReads) -> Result<Self, Error> {
+        Ok(Self {
+            storage: reads.storage.into_iter().map(|(address, key, value)| ((address, key), value)).collect(),
+            nonces: reads.nonces.into_iter().collect(),
+            class_hashes: reads.class_hashes.into_iter().collect(),
+            compiled_class_hashes: reads.compiled_class_hashes.into_iter().collect(),
+            classes: reads
+                .classes
+                .into_iter()
+                .map(|(class_hash, compiled_class)| {
+                    let class = to_blockifier_class(compiled_class)
+                        .map_err(|err| Error::InvalidReplayLog(format!("Converting class {class_hash:#x}: {err:#}")))?;
+                    Ok((class_hash, class))
+                })
+                .collect::<Result<_, Error>>()?,
+        })
+    }
+}
+
+fn not_recorded(what: String) -> StateError {
+    StateError::StateReadError(format!("{what} is not in the replay log"))
+}
+
+impl StateReader for ReplayState {
+    fn get_storage_at(&mut self, contract_address: ContractAddress, key: StorageKey) -> StateResult<StarkFelt> {
+        let (contract_address, key) = (contract_address.to_felt(), key.to_felt());
+        let value = self.storage.get(&(contract_address, key));
+        value
+            .map(ToStarkFelt::to_stark_felt)
+            .ok_or_else(|| not_recorded(format!("Storage of contract {contract_address:#x} at key {key:#x}")))
+    }
+
+    fn get_nonce_at(&mut self, contract_address: ContractAddress) -> StateResult<Nonce> {
+        let contract_address = contract_address.to_felt();
+        let nonce = self.nonces.get(&contract_address);
+        nonce
+            .map(|nonce| Nonce(nonce.to_stark_felt()))
+            .ok_or_else(|| not_recorded(format!("Nonce of contract {contract_address:#x}")))
+    }
+
+    fn get_class_hash_at(&mut self, contract_address: ContractAddress) -> StateResult<ClassHash> {
+        let contract_address = contract_address.to_felt();
+        let class_hash = self.class_hashes.get(&contract_address);
+        class_hash
+            .map(|class_hash| ClassHash(class_hash.to_stark_felt()))
+            .ok_or_else(|| not_recorded(format!("Class hash of contract {contract_address:#x}")))
+    }
+
+    fn get_compiled_contract_class(&mut self, class_hash: ClassHash) -> StateResult<ContractClass> {
+        // Only the declared classes are recorded
+        self.classes.get(&class_hash.to_felt()).cloned().ok_or(StateError::UndeclaredClassHash(class_hash))
+    }
+
+    fn get_compiled_class_hash(&mut self, class_hash: ClassHash) -> StateResult<CompiledClassHash> {
+        let compiled_class_hash = self.compiled_class_hashes.get(&class_hash.to_felt());
+        compiled_class_hash
+            .map(|compiled_class_hash| CompiledClassHash(compiled_class_hash.to_stark_felt()))
+            .ok_or(StateError::UndeclaredClassHash(class_hash))
+    }
+}
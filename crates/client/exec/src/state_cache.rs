@@ -0,0 +1,127 @@
+//! State read cache shared by the executions on top of the same block.
+//!
+//! Successive calls pinned to the same block read the same contracts: the values they read from the database, and
+//! the contract classes they deserialize, are kept per block so that the next executions do not read them again.
+//! Only the values read from the state of the block are cached, never the writes of the executions, so the cache
+//! is shared by executions with different transactions or state overrides. The pending block is never cached, as
+//! its state changes.
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+
+use blockifier::execution::contract_class::ContractClass;
+use blockifier::state::state_api::{StateReader, StateResult};
+use lru::LruCache;
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce};
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::StorageKey;
+use starknet_types_core::felt::Felt;
+
+use crate::{CancellationToken, ExecutionMetrics};
+
+/// Values read from the state of a block.
+#[derive(Default)]
+pub(crate) struct BlockStateCache {
+    pub(crate) storage: Mutex<HashMap<(ContractAddress, StorageKey), StarkFelt>>,
+    pub(crate) nonces: Mutex<HashMap<ContractAddress, Nonce>>,
+    pub(crate) class_hashes: Mutex<HashMap<ContractAddress, ClassHash>>,
+    pub(crate) compiled_class_hashes: Mutex<HashMap<ClassHash, CompiledClassHash>>,
+    pub(crate) classes: Mutex<HashMap<ClassHash, ContractClass>>,
+}
+
+pub struct StateCache {
+    /// By block hash, `None` when the cache is disabled.
+    blocks: Option<Mutex<LruCache<Felt, Arc<BlockStateCache>>>>,
+}
+
+impl StateCache {
+    /// A cache holding the state reads of up to `capacity` blocks. A capacity of zero disables the cache.
+    pub fn new(capacity: usize) -> Self {
+        Self { blocks: NonZeroUsize::new(capacity).map(|capacity| Mutex::new(LruCache::new(capacity))) }
+    }
+
+    pub(crate) fn block(&self, block_hash: Felt) -> Option<Arc<BlockStateCache>> {
+        let mut blocks = self.blocks.as_ref()?.lock().expect("poisoned mutex");
+        Some(Arc::clone(blocks.get_or_insert(block_hash, Default::default)))
+    }
+}
+
+/// State reader looking up the values in the cache of its block first, and caching the values it reads. Its reads
+/// fail once the execution is cancelled.
+pub struct SharedState<S> {
+    inner: S,
+    cache: Option<Arc<BlockStateCache>>,
+    metrics: Option<ExecutionMetrics>,
+    cancellation: Option<CancellationToken>,
+}
+
+impl<S> SharedState<S> {
+    pub(crate) fn new(
+        inner: S,
+        cache: Option<Arc<BlockStateCache>>,
+        metrics: Option<ExecutionMetrics>,
+        cancellation: Option<CancellationToken>,
+    ) -> Self {
+        Self { inner, cache, metrics, cancellation }
+    }
+
+    fn check_cancelled(&self) -> StateResult<()> {
+        self.cancellation.as_ref().map_or(Ok(()), CancellationToken::check_read)
+    }
+}
+
+fn cached<K: Copy + Eq + Hash, V: Clone>(
+    cache: Option<&Mutex<HashMap<K, V>>>,
+    key: K,
+    read: impl FnOnce() -> StateResult<V>,
+) -> StateResult<V> {
+    let Some(cache) = cache else { return read() };
+    if let Some(value) = cache.lock().expect("poisoned mutex").get(&key) {
+        return Ok(value.clone());
+    }
+    // The lock is not held while reading, concurrent executions may read the same value twice.
+    let value = read()?;
+    cache.lock().expect("poisoned mutex").insert(key, value.clone());
+    Ok(value)
+}
+
+impl<S: StateReader> StateReader for SharedState<S> {
+    fn get_storage_at(&mut self, contract_address: ContractAddress, key: StorageKey) -> StateResult<StarkFelt> {
+        self.check_cancelled()?;
+        let cache = self.cache.as_ref().map(|cache| &cache.storage);
+        cached(cache, (contract_address, key), || self.inner.get_storage_at(contract_address, key))
+    }
+
+    fn get_nonce_at(&mut self, contract_address: ContractAddress) -> StateResult<Nonce> {
+        self.check_cancelled()?;
+        let cache = self.cache.as_ref().map(|cache| &cache.nonces);
+        cached(cache, contract_address, || self.inner.get_nonce_at(contract_address))
+    }
+
+    fn get_class_hash_at(&mut self, contract_address: ContractAddress) -> StateResult<ClassHash> {
+        self.check_cancelled()?;
+        let cache = self.cache.as_ref().map(|cache| &cache.class_hashes);
+        cached(cache, contract_address, || self.inner.get_class_hash_at(contract_address))
+    }
+
+    fn get_compiled_contract_class(&mut self, class_hash: ClassHash) -> StateResult<ContractClass> {
+        self.check_cancelled()?;
+        let cache = self.cache.as_ref().map(|cache| &cache.classes);
+        let mut miss = false;
+        let class = cached(cache, class_hash, || {
+            miss = true;
+            self.inner.get_compiled_contract_class(class_hash)
+        })?;
+        if let (Some(metrics), Some(_)) = (&self.metrics, cache) {
+            metrics.class_cache_lookup(!miss);
+        }
+        Ok(class)
+    }
+
+    fn get_compiled_class_hash(&mut self, class_hash: ClassHash) -> StateResult<CompiledClassHash> {
+        self.check_cancelled()?;
+        let cache = self.cache.as_ref().map(|cache| &cache.compiled_class_hashes);
+        cached(cache, class_hash, || self.inner.get_compiled_class_hash(class_hash))
+    }
+}
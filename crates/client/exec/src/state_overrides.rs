@@ -0,0 +1,145 @@
+//! Changes layered on top of the state of a block before executing.
+//!
+//! The rpc state overrides are converted to a [`StateOverridesDiff`], which can also be built directly to execute on
+//! top of arbitrary changes, such as impersonating an account or simulating what-if scenarios. The diff is applied by
+//! the [`OverrideState`] reader, which wraps the state of the block.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use blockifier::abi::abi_utils::get_storage_var_address;
+use blockifier::execution::contract_class::ContractClass;
+use blockifier::state::state_api::{StateReader, StateResult};
+use dp_convert::ToStarkFelt;
+use indexmap::IndexMap;
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce};
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::StorageKey;
+use starknet_api::StarknetApiError;
+use starknet_types_core::felt::Felt;
+
+use crate::block_context::{ETH_TOKEN_ADDR, STRK_TOKEN_ADDR};
+
+/// Changes applied on top of the state of a contract before executing, with the semantics of the `stateDiff`
+/// overrides of `eth_call`: the storage slots which are not overridden keep their value.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContractStateOverride {
+    pub nonce: Option<Felt>,
+    /// Replaces the class of the contract, or deploys the contract if there is none at this address. The class
+    /// must be declared.
+    pub class_hash: Option<Felt>,
+    /// Balance of the contract in both the ETH and STRK fee tokens.
+    pub balance: Option<Felt>,
+    pub storage: Vec<(Felt, Felt)>,
+}
+
+/// State overrides, by contract address.
+pub type StateOverrides = HashMap<Felt, ContractStateOverride>;
+
+/// Arbitrary changes to the state, in the blockifier types. The values it does not override are read from the
+/// underlying state.
+#[derive(Debug, Clone, Default)]
+pub struct StateOverridesDiff {
+    pub storage: IndexMap<ContractAddress, IndexMap<StorageKey, StarkFelt>>,
+    pub nonces: IndexMap<ContractAddress, Nonce>,
+    pub class_hashes: IndexMap<ContractAddress, ClassHash>,
+    /// Classes declared by the overrides, with their compiled class hash.
+    pub classes: IndexMap<ClassHash, (CompiledClassHash, ContractClass)>,
+}
+
+impl StateOverridesDiff {
+    /// Converts the rpc state overrides.
+    pub fn new(overrides: &StateOverrides) -> Result<Self, StarknetApiError> {
+        let mut diff = Self::default();
+
+        for (address, contract) in overrides {
+            let address: ContractAddress = address.to_stark_felt().try_into()?;
+
+            if let Some(nonce) = contract.nonce {
+                diff.nonces.insert(address, Nonce(nonce.to_stark_felt()));
+            }
+            if let Some(class_hash) = contract.class_hash {
+                diff.class_hashes.insert(address, ClassHash(class_hash.to_stark_felt()));
+            }
+            if let Some(balance) = contract.balance {
+                // Balances are u256 values, stored as their low and high 128 bits in two consecutive slots.
+                let bytes = balance.to_bytes_be();
+                let (high, low) = (Felt::from_bytes_be_slice(&bytes[..16]), Felt::from_bytes_be_slice(&bytes[16..]));
+                let low_key = get_storage_var_address("ERC20_balances", &[*address.0.key()]);
+                let high_key = low_key.next_storage_key()?;
+
+                for token in [ETH_TOKEN_ADDR, STRK_TOKEN_ADDR] {
+                    let token_storage = diff.storage.entry(token.to_stark_felt().try_into()?).or_default();
+                    token_storage.insert(low_key, low.to_stark_felt());
+                    token_storage.insert(high_key, high.to_stark_felt());
+                }
+            }
+
+            let storage = diff.storage.entry(address).or_default();
+            for (key, value) in &contract.storage {
+                storage.insert(StorageKey(key.to_stark_felt().try_into()?), value.to_stark_felt());
+            }
+        }
+
+        Ok(diff)
+    }
+
+    /// Layers `other` on top of these overrides: its values win when both override the same key.
+    pub fn extend(&mut self, other: StateOverridesDiff) {
+        for (address, storage) in other.storage {
+            self.storage.entry(address).or_default().extend(storage);
+        }
+        self.nonces.extend(other.nonces);
+        self.class_hashes.extend(other.class_hashes);
+        self.classes.extend(other.classes);
+    }
+}
+
+/// State reader applying a [`StateOverridesDiff`] on top of another state.
+pub struct OverrideState<S> {
+    inner: S,
+    overrides: Arc<StateOverridesDiff>,
+}
+
+impl<S> OverrideState<S> {
+    pub fn new(inner: S, overrides: Arc<StateOverridesDiff>) -> Self {
+        Self { inner, overrides }
+    }
+}
+
+impl<S: StateReader> StateReader for OverrideState<S> {
+    fn get_storage_at(&mut self, contract_address: ContractAddress, key: StorageKey) -> StateResult<StarkFelt> {
+        match self.overrides.storage.get(&contract_address).and_then(|storage| storage.get(&key)) {
+            Some(value) => Ok(*value),
+            None => self.inner.get_storage_at(contract_address, key),
+        }
+    }
+
+    fn get_nonce_at(&mut self, contract_address: ContractAddress) -> StateResult<Nonce> {
+        match self.overrides.nonces.get(&contract_address) {
+            Some(nonce) => Ok(*nonce),
+            None => self.inner.get_nonce_at(contract_address),
+        }
+    }
+
+    fn get_class_hash_at(&mut self, contract_address: ContractAddress) -> StateResult<ClassHash> {
+        match self.overrides.class_hashes.get(&contract_address) {
+            Some(class_hash) => Ok(*class_hash),
+            None => self.inner.get_class_hash_at(contract_address),
+        }
+    }
+
+    fn get_compiled_contract_class(&mut self, class_hash: ClassHash) -> StateResult<ContractClass> {
+        match self.overrides.classes.get(&class_hash) {
+            Some((_, contract_class)) => Ok(contract_class.clone()),
+            None => self.inner.get_compiled_contract_class(class_hash),
+        }
+    }
+
+    fn get_compiled_class_hash(&mut self, class_hash: ClassHash) -> StateResult<CompiledClassHash> {
+        match self.overrides.classes.get(&class_hash) {
+            Some((compiled_class_hash, _)) => Ok(*compiled_class_hash),
+            None => self.inner.get_compiled_class_hash(class_hash),
+        }
+    }
+}
@@ -0,0 +1,136 @@
+//! Syscall level tracing of the execution.
+//!
+//! A [`SyscallHook`] registered on an [`ExecutionContext`](crate::ExecutionContext) is called for every syscall made
+//! by the traced transactions. The syscalls are replayed from the call tree blockifier records, once the transaction
+//! is committed: blockifier 0.6 has no way to observe them while they are executed.
+
+use blockifier::execution::call_info::CallInfo;
+use blockifier::execution::entry_point::CallType;
+use blockifier::transaction::transaction_types::TransactionType;
+use dp_convert::ToFelt;
+use starknet_types_core::felt::Felt;
+
+use crate::ExecutionResult;
+
+/// A syscall made during the execution of a transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Syscall {
+    /// Start of a call: a top level call of the transaction, a `call_contract`, a `library_call` or a constructor.
+    /// The syscalls made by the call follow, up to the matching [`Syscall::CallEnd`].
+    Call {
+        caller_address: Felt,
+        contract_address: Felt,
+        class_hash: Option<Felt>,
+        entry_point_selector: Felt,
+        calldata: Vec<Felt>,
+        library_call: bool,
+    },
+    /// End of the last started call.
+    CallEnd {
+        result: Vec<Felt>,
+        failed: bool,
+    },
+    /// A `storage_read`. Blockifier only records the values read, not their keys.
+    StorageRead {
+        value: Felt,
+    },
+    /// A storage write. Blockifier does not record the writes per call, so they are reported once all the calls of
+    /// the transaction are done, with the final value of each written key.
+    StorageWrite {
+        contract_address: Felt,
+        key: Felt,
+        value: Felt,
+    },
+    EmitEvent {
+        keys: Vec<Felt>,
+        data: Vec<Felt>,
+    },
+    SendMessageToL1 {
+        to_address: Felt,
+        payload: Vec<Felt>,
+    },
+}
+
+/// Receives the syscalls of the traced transactions.
+///
+/// Within a call, the storage reads are reported first, then the events, then the messages sent to L1, then the inner
+/// calls: blockifier does not record the order between syscalls of different kinds.
+pub trait SyscallHook: Send + Sync {
+    /// Called for every syscall of a transaction, once it is executed. `depth` is the depth of the call the syscall
+    /// belongs to: the top level calls of the transaction are at depth 0.
+    fn on_syscall(&self, tx_hash: Felt, depth: usize, syscall: Syscall);
+}
+
+/// Reports the syscalls of an executed transaction to the hook.
+pub(crate) fn report_syscalls(hook: &dyn SyscallHook, result: &ExecutionResult) {
+    let tx_hash = result.hash.to_felt();
+    let execution_info = &result.execution_info;
+
+    // The constructor of a deployed account runs before its validation
+    let (first, second) = match result.tx_type {
+        TransactionType::DeployAccount => (&execution_info.execute_call_info, &execution_info.validate_call_info),
+        _ => (&execution_info.validate_call_info, &execution_info.execute_call_info),
+    };
+    for call_info in [first, second, &execution_info.fee_transfer_call_info].into_iter().flatten() {
+        report_call(hook, tx_hash, 0, call_info);
+    }
+
+    for item in &result.state_diff.storage_diffs {
+        for entry in &item.storage_entries {
+            let syscall = Syscall::StorageWrite { contract_address: item.address, key: entry.key, value: entry.value };
+            hook.on_syscall(tx_hash, 0, syscall);
+        }
+    }
+}
+
+fn report_call(hook: &dyn SyscallHook, tx_hash: Felt, depth: usize, call_info: &CallInfo) {
+    hook.on_syscall(
+        tx_hash,
+        depth,
+        Syscall::Call {
+            caller_address: call_info.call.caller_address.to_felt(),
+            contract_address: call_info.call.storage_address.to_felt(),
+            class_hash: call_info.call.class_hash.map(ToFelt::to_felt),
+            entry_point_selector: call_info.call.entry_point_selector.to_felt(),
+            calldata: call_info.call.calldata.0.iter().map(ToFelt::to_felt).collect(),
+            library_call: call_info.call.call_type == CallType::Delegate,
+        },
+    );
+
+    for value in &call_info.storage_read_values {
+        hook.on_syscall(tx_hash, depth, Syscall::StorageRead { value: value.to_felt() });
+    }
+
+    let mut events: Vec<_> = call_info.execution.events.iter().collect();
+    events.sort_by_key(|event| event.order);
+    for event in events {
+        let syscall = Syscall::EmitEvent {
+            keys: event.event.keys.iter().map(ToFelt::to_felt).collect(),
+            data: event.event.data.0.iter().map(ToFelt::to_felt).collect(),
+        };
+        hook.on_syscall(tx_hash, depth, syscall);
+    }
+
+    let mut messages: Vec<_> = call_info.execution.l2_to_l1_messages.iter().collect();
+    messages.sort_by_key(|message| message.order);
+    for message in messages {
+        let syscall = Syscall::SendMessageToL1 {
+            to_address: message.message.to_address.0.to_felt(),
+            payload: message.message.payload.0.iter().map(ToFelt::to_felt).collect(),
+        };
+        hook.on_syscall(tx_hash, depth, syscall);
+    }
+
+    for inner_call in &call_info.inner_calls {
+        report_call(hook, tx_hash, depth + 1, inner_call);
+    }
+
+    hook.on_syscall(
+        tx_hash,
+        depth,
+        Syscall::CallEnd {
+            result: call_info.execution.retdata.0.iter().map(ToFelt::to_felt).collect(),
+            failed: call_info.execution.failed,
+        },
+    );
+}
@@ -0,0 +1,144 @@
+//! Tight resource bounds of v3 transactions.
+//!
+//! A fee estimate is an upper bound of the resources of a transaction. Some callers want the lowest bounds their
+//! transaction still succeeds with instead: they are found by executing the transaction again with lower and lower L1
+//! gas `max_amount`, by binary search.
+
+use blockifier::fee::gas_usage::estimate_minimal_gas_vector;
+use blockifier::state::cached_state::CachedState;
+use blockifier::state::state_api::StateReader;
+use blockifier::transaction::account_transaction::AccountTransaction;
+use blockifier::transaction::transaction_execution::Transaction;
+use blockifier::transaction::transactions::{DeployAccountTransaction, ExecutableTransaction, InvokeTransaction};
+use starknet_api::transaction::{Resource, ResourceBounds};
+
+use crate::execution::{execute_and_commit, TxInfo};
+use crate::{Error, ExecutionContext, TxReexecError};
+
+/// The tight L1 gas bounds of a transaction, see [`ExecutionContext::tight_l1_gas_bounds`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TightBounds {
+    /// The lowest L1 gas bounds the transaction succeeds with.
+    Found(ResourceBounds),
+    /// The transaction is not a v3 invoke or deploy account transaction, its bounds cannot be changed.
+    Unsupported,
+    /// The transaction fails even with the largest bounds, with this error.
+    Failing(String),
+}
+
+impl<'a> ExecutionContext<'a> {
+    /// Finds the lowest L1 gas bounds each transaction succeeds with, on top of the transactions before it. The max
+    /// price per unit of the bounds is the L1 gas price of the block. The fee is not charged, the transactions are
+    /// validated with `validate`.
+    ///
+    /// Each transaction is executed once per step of the binary search on its `max_amount`: this is a lot more
+    /// expensive than a fee estimate.
+    pub fn tight_l1_gas_bounds(&self, transactions: &[Transaction], validate: bool) -> Result<Vec<TightBounds>, Error> {
+        self.check_exact_fees()?;
+        let mut state = self.init_cached_state();
+        let mut tight_bounds = Vec::with_capacity(transactions.len());
+        for (index, tx) in transactions.iter().enumerate() {
+            tight_bounds.push(self.tight_l1_gas_bound(&mut state, tx, validate)?);
+
+            // The next transactions are executed on top of this one, with its own bounds
+            execute_and_commit(&self.block_context, &mut state, tx, false, validate).map_err(|err| TxReexecError {
+                block_n: self.db_id,
+                hash: tx.tx_hash(),
+                index,
+                err,
+            })?;
+        }
+        Ok(tight_bounds)
+    }
+
+    fn tight_l1_gas_bound<S: StateReader>(
+        &self,
+        state: &mut CachedState<S>,
+        tx: &Transaction,
+        validate: bool,
+    ) -> Result<TightBounds, Error> {
+        let max_price_per_unit = self.block_context.block_info().gas_prices.strk_l1_gas_price.get();
+        let bounds = |max_amount| ResourceBounds { max_amount, max_price_per_unit };
+        let account_tx = match tx {
+            Transaction::AccountTransaction(account_tx) if with_l1_gas_bounds(tx, bounds(0)).is_some() => account_tx,
+            _ => return Ok(TightBounds::Unsupported),
+        };
+        let actual_fee = match self.probe(state, tx, bounds(u64::MAX), validate)? {
+            Ok(actual_fee) => actual_fee,
+            Err(error) => return Ok(TightBounds::Failing(error)),
+        };
+
+        // Succeeds with `succeeding`, fails with `failing`. A bound of zero disables the fee checks, the bounds below
+        // the minimal gas of the transaction are considered failing.
+        let minimal_gas = estimate_minimal_gas_vector(&self.block_context, account_tx)
+            .map_or(0, |gas_vector| u64::try_from(gas_vector.l1_gas).unwrap_or(u64::MAX));
+        let mut failing = minimal_gas.saturating_sub(1);
+        let mut succeeding = u64::MAX;
+        // The gas of the actual fee usually succeeds, which saves most of the steps
+        let actual_gas = u64::try_from(actual_fee.div_ceil(max_price_per_unit)).unwrap_or(u64::MAX);
+        if actual_gas > failing && self.probe(state, tx, bounds(actual_gas), validate)?.is_ok() {
+            succeeding = actual_gas;
+        }
+        while succeeding - failing > 1 {
+            let max_amount = failing + (succeeding - failing) / 2;
+            match self.probe(state, tx, bounds(max_amount), validate)? {
+                Ok(_) => succeeding = max_amount,
+                Err(_) => failing = max_amount,
+            }
+        }
+        Ok(TightBounds::Found(bounds(succeeding)))
+    }
+
+    /// Executes the transaction with these L1 gas bounds on top of `state`, without committing it. Returns its
+    /// actual fee when it succeeds, else its error.
+    fn probe<S: StateReader>(
+        &self,
+        state: &mut CachedState<S>,
+        tx: &Transaction,
+        bounds: ResourceBounds,
+        validate: bool,
+    ) -> Result<Result<u128, String>, Error> {
+        self.check_cancelled()?;
+        let tx = with_l1_gas_bounds(tx, bounds).expect("Only called on v3 transactions");
+        let mut state = CachedState::<_>::create_transactional(state);
+        Ok(match tx.execute(&mut state, &self.block_context, false, validate) {
+            Ok(execution_info) => match execution_info.revert_error {
+                Some(revert_error) => Err(revert_error),
+                None => Ok(execution_info.actual_fee.0),
+            },
+            Err(err) => Err(format!("{err:#}")),
+        })
+    }
+}
+
+/// The same transaction with other L1 gas bounds, `None` if it is not a v3 invoke or deploy account transaction. The
+/// hash of the transaction is kept: the signature checked by the account stays valid.
+fn with_l1_gas_bounds(tx: &Transaction, bounds: ResourceBounds) -> Option<Transaction> {
+    match tx {
+        Transaction::AccountTransaction(AccountTransaction::Invoke(tx)) => match &tx.tx {
+            starknet_api::transaction::InvokeTransaction::V3(tx_v3) => {
+                let mut tx_v3 = tx_v3.clone();
+                tx_v3.resource_bounds.0.insert(Resource::L1Gas, bounds);
+                let tx_v3 = starknet_api::transaction::InvokeTransaction::V3(tx_v3);
+                Some(Transaction::AccountTransaction(AccountTransaction::Invoke(InvokeTransaction {
+                    tx: tx_v3,
+                    ..tx.clone()
+                })))
+            }
+            _ => None,
+        },
+        Transaction::AccountTransaction(AccountTransaction::DeployAccount(tx)) => match &tx.tx {
+            starknet_api::transaction::DeployAccountTransaction::V3(tx_v3) => {
+                let mut tx_v3 = tx_v3.clone();
+                tx_v3.resource_bounds.0.insert(Resource::L1Gas, bounds);
+                let tx_v3 = starknet_api::transaction::DeployAccountTransaction::V3(tx_v3);
+                Some(Transaction::AccountTransaction(AccountTransaction::DeployAccount(DeployAccountTransaction {
+                    tx: tx_v3,
+                    ..tx.clone()
+                })))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
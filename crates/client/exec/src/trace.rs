@@ -1,8 +1,10 @@
 use std::collections::HashMap;
 
 use blockifier::state::cached_state::CommitmentStateDiff;
+use blockifier::state::state_api::{StateReader, StateResult};
 use blockifier::{execution::call_info::CallInfo, transaction::transaction_types::TransactionType};
 use dp_convert::ToFelt;
+use starknet_api::core::ClassHash;
 
 use crate::{ExecutionResult, TransactionExecutionError};
 
@@ -31,7 +33,7 @@ pub fn execution_result_to_tx_trace(
 
     let state_diff = match state_diff_is_empty(state_diff) {
         true => None,
-        false => Some(to_state_diff(state_diff)),
+        false => Some(state_diff.clone()),
     };
 
     let validate_invocation =
@@ -222,11 +224,33 @@ fn resources_mapping(
     }
 }
 
-fn to_state_diff(commitment_state_diff: &CommitmentStateDiff) -> starknet_core::types::StateDiff {
-    starknet_core::types::StateDiff {
+/// Builds the state diff of a transaction, or of a whole block. `parent_state` is the state the transactions were
+/// executed on top of, which is used to tell deployed contracts apart from replaced classes.
+pub(crate) fn make_state_diff(
+    commitment_state_diff: CommitmentStateDiff,
+    parent_state: &mut impl StateReader,
+    deprecated_declared_classes: impl IntoIterator<Item = ClassHash>,
+) -> StateResult<starknet_core::types::StateDiff> {
+    let mut deployed_contracts = vec![];
+    let mut replaced_classes = vec![];
+    for (address, class_hash) in commitment_state_diff.address_to_class_hash {
+        if parent_state.get_class_hash_at(address)? == ClassHash::default() {
+            deployed_contracts.push(starknet_core::types::DeployedContractItem {
+                address: address.to_felt(),
+                class_hash: class_hash.to_felt(),
+            });
+        } else {
+            replaced_classes.push(starknet_core::types::ReplacedClassItem {
+                contract_address: address.to_felt(),
+                class_hash: class_hash.to_felt(),
+            });
+        }
+    }
+
+    Ok(starknet_core::types::StateDiff {
         storage_diffs: commitment_state_diff
             .storage_updates
-            .iter()
+            .into_iter()
             .map(|(address, updates)| {
                 let storage_entries = updates
                     .into_iter()
@@ -238,26 +262,35 @@ fn to_state_diff(commitment_state_diff: &CommitmentStateDiff) -> starknet_core::
                 starknet_core::types::ContractStorageDiffItem { address: address.to_felt(), storage_entries }
             })
             .collect(),
-        deprecated_declared_classes: vec![],
-        declared_classes: vec![],
-        deployed_contracts: vec![],
-        replaced_classes: vec![],
+        deprecated_declared_classes: deprecated_declared_classes.into_iter().map(ToFelt::to_felt).collect(),
+        declared_classes: commitment_state_diff
+            .class_hash_to_compiled_class_hash
+            .into_iter()
+            .map(|(class_hash, compiled_class_hash)| starknet_core::types::DeclaredClassItem {
+                class_hash: class_hash.to_felt(),
+                compiled_class_hash: compiled_class_hash.to_felt(),
+            })
+            .collect(),
+        deployed_contracts,
+        replaced_classes,
         nonces: commitment_state_diff
             .address_to_nonce
-            .iter()
+            .into_iter()
             .map(|(address, nonce)| starknet_core::types::NonceUpdate {
                 contract_address: address.to_felt(),
                 nonce: nonce.to_felt(),
             })
             .collect(),
-    }
+    })
 }
 
-fn state_diff_is_empty(commitment_state_diff: &CommitmentStateDiff) -> bool {
-    commitment_state_diff.address_to_class_hash.is_empty()
-        && commitment_state_diff.address_to_nonce.is_empty()
-        && commitment_state_diff.storage_updates.is_empty()
-        && commitment_state_diff.class_hash_to_compiled_class_hash.is_empty()
+fn state_diff_is_empty(state_diff: &starknet_core::types::StateDiff) -> bool {
+    state_diff.storage_diffs.is_empty()
+        && state_diff.deprecated_declared_classes.is_empty()
+        && state_diff.declared_classes.is_empty()
+        && state_diff.deployed_contracts.is_empty()
+        && state_diff.replaced_classes.is_empty()
+        && state_diff.nonces.is_empty()
 }
 
 fn agregate_execution_ressources(
@@ -0,0 +1,77 @@
+use blockifier::execution::contract_class::ClassInfo as BlockifierClassInfo;
+use blockifier::transaction::transaction_execution::Transaction;
+use dc_db::DeoxysBackend;
+use dp_block::{BlockId, DeoxysMaybePendingBlock};
+use dp_class::{to_blockifier_class, ClassInfo, CompiledClass};
+use dp_convert::{ToFelt, ToStarkFelt};
+use starknet_api::transaction::TransactionHash;
+use starknet_types_core::felt::Felt;
+
+use crate::Error;
+
+/// Converts the transactions of a block to blockifier transactions, in block order. This is how the blocks are
+/// re-executed.
+pub fn block_to_blockifier_transactions<'a>(
+    backend: &'a DeoxysBackend,
+    block: &'a DeoxysMaybePendingBlock,
+) -> impl Iterator<Item = Result<Transaction, Error>> + 'a {
+    let block_id = block.info.as_block_id();
+    Iterator::zip(block.inner.transactions.iter(), block.info.tx_hashes())
+        .map(move |(tx, hash)| to_blockifier_transaction(backend, block_id, tx, &TransactionHash(hash.to_stark_felt())))
+}
+
+/// Converts a transaction of the database to a blockifier transaction. The class declared by a declare
+/// transaction is read from the database at `block_id`.
+///
+/// **note:** this function does not support deploy transactions, because they are not supported by blockifier.
+pub fn to_blockifier_transaction(
+    backend: &DeoxysBackend,
+    block_id: BlockId,
+    transaction: &dp_transactions::Transaction,
+    tx_hash: &TransactionHash,
+) -> Result<Transaction, Error> {
+    convert_transaction(transaction, tx_hash, |class_hash| {
+        backend.get_class(&block_id, &class_hash)?.ok_or(Error::ClassNotFound(class_hash))
+    })
+}
+
+/// Converts a transaction to a blockifier transaction. The class declared by a declare transaction is given by
+/// `get_class`, from its hash.
+pub(crate) fn convert_transaction(
+    transaction: &dp_transactions::Transaction,
+    tx_hash: &TransactionHash,
+    get_class: impl FnOnce(Felt) -> Result<(ClassInfo, CompiledClass), Error>,
+) -> Result<Transaction, Error> {
+    let make_err = |reason: &str| Error::TransactionConversion { hash: *tx_hash, reason: reason.into() };
+
+    let transaction: starknet_api::transaction::Transaction =
+        transaction.try_into().map_err(|_| make_err("unsupported transaction type"))?;
+
+    let paid_fee_on_l1 = match transaction {
+        starknet_api::transaction::Transaction::L1Handler(_) => Some(starknet_api::transaction::Fee(1_000_000_000_000)),
+        _ => None,
+    };
+
+    let class_info = match transaction {
+        starknet_api::transaction::Transaction::Declare(ref declare_tx) => {
+            let class_hash = declare_tx.class_hash().to_felt();
+
+            let (class_info, compiled_class) = get_class(class_hash)?;
+
+            let blockifier_contract_class = to_blockifier_class(compiled_class)
+                .map_err(|err| make_err(&format!("converting the declared class: {err:#}")))?;
+
+            let sierra_program_length = class_info.contract_class.sierra_program_length();
+            let abi_length = class_info.contract_class.abi_length();
+
+            Some(
+                BlockifierClassInfo::new(&blockifier_contract_class, sierra_program_length, abi_length)
+                    .map_err(|_| make_err("mismatch between the length of the sierra program and the class version"))?,
+            )
+        }
+        _ => None,
+    };
+
+    Transaction::from_api(transaction, *tx_hash, class_info, paid_fee_on_l1, None, false)
+        .map_err(|err| make_err(&format!("{err:#}")))
+}
@@ -0,0 +1,265 @@
+//! Verification of the execution of the blocks of the database.
+//!
+//! Blocks are re-executed on top of the state of their parent block, and the results of their transactions are
+//! compared to the stored receipts, and the state diff of the block to the stored one. This catches the differences
+//! between the execution of the node and the one of the sequencer, such as mismatching versioned constants, which
+//! verifying the commitments does not.
+
+use std::fmt;
+use std::time::Instant;
+
+use dc_db::db_block_id::DbBlockId;
+use dc_db::DeoxysBackend;
+use dp_receipt::{Event, FeePayment, MsgToL1, TransactionReceipt};
+use dp_state_update::StateDiff;
+use dp_utils::PerfStopwatch;
+use rayon::prelude::*;
+use starknet_types_core::felt::Felt;
+
+use crate::versioned_constants::has_versioned_constants;
+use crate::{
+    block_to_blockifier_transactions, BlockExecution, Error, ExecutionContext, ExecutionLimits, ExecutionMetrics,
+    ExecutionResult,
+};
+
+/// Number of blocks verified between two progress logs.
+const PROGRESS_LOG_INTERVAL: u64 = 1000;
+
+/// A difference between the execution of a transaction and its stored receipt, or between the state diff of a block
+/// and its stored state diff.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    pub block_n: u64,
+    /// Index and hash of the diverging transaction, `None` for the state diff of the block.
+    pub transaction: Option<(usize, Felt)>,
+    pub kind: DivergenceKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DivergenceKind {
+    /// The transaction reverted when the receipt says it succeeded, or the other way around.
+    ExecutionStatus {
+        stored_reverted: bool,
+        computed_reverted: bool,
+    },
+    ActualFee {
+        stored: FeePayment,
+        computed: FeePayment,
+    },
+    Events {
+        stored: Vec<Event>,
+        computed: Vec<Event>,
+    },
+    MessagesSent {
+        stored: Vec<MsgToL1>,
+        computed: Vec<MsgToL1>,
+    },
+    /// Both state diffs are sorted.
+    StateDiff {
+        stored: StateDiff,
+        computed: StateDiff,
+    },
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.transaction {
+            Some((tx_index, tx_hash)) => write!(f, "Block #{} tx {tx_hash:#x} (index {tx_index}): ", self.block_n)?,
+            None => write!(f, "Block #{}: ", self.block_n)?,
+        }
+        match &self.kind {
+            DivergenceKind::ExecutionStatus { stored_reverted, computed_reverted } => {
+                write!(f, "stored reverted: {stored_reverted}, computed reverted: {computed_reverted}")
+            }
+            DivergenceKind::ActualFee { stored, computed } => write!(
+                f,
+                "stored fee: {:#x} {:?}, computed fee: {:#x} {:?}",
+                stored.amount, stored.unit, computed.amount, computed.unit
+            ),
+            DivergenceKind::Events { stored, computed } => {
+                write!(f, "stored events: {stored:?}, computed events: {computed:?}")
+            }
+            DivergenceKind::MessagesSent { stored, computed } => {
+                write!(f, "stored messages: {stored:?}, computed messages: {computed:?}")
+            }
+            DivergenceKind::StateDiff { stored, computed } => write!(
+                f,
+                "stored state diff: {} updates (hash {:#x}), computed state diff: {} updates (hash {:#x})",
+                stored.len(),
+                stored.compute_hash(),
+                computed.len(),
+                computed.compute_hash()
+            ),
+        }
+    }
+}
+
+/// Re-executes a block with the given limits, and compares the results of its transactions to the stored receipts
+/// and its state diff to the stored state diff.
+///
+/// # Returns
+///
+/// The divergences found, in transaction order, followed by the divergence of the state diff if any. Returns
+/// [`Error::UnsupportedProtocolVersion`] for blocks whose protocol version has no versioned constants, as their fees
+/// cannot be verified.
+pub fn verify_block_execution(
+    backend: &DeoxysBackend,
+    block_n: u64,
+    limits: ExecutionLimits,
+    metrics: &ExecutionMetrics,
+) -> Result<Vec<Divergence>, Error> {
+    verify_block(backend, block_n, limits, metrics).map(|(_, divergences)| divergences)
+}
+
+/// Same as [`verify_block_execution`], also returns the number of transactions of the block.
+fn verify_block(
+    backend: &DeoxysBackend,
+    block_n: u64,
+    limits: ExecutionLimits,
+    metrics: &ExecutionMetrics,
+) -> Result<(usize, Vec<Divergence>), Error> {
+    let block = backend.get_block(&DbBlockId::BlockN(block_n))?.ok_or(Error::BlockNotFound(block_n))?;
+    let protocol_version = *block.info.protocol_version();
+    if !has_versioned_constants(protocol_version) {
+        return Err(Error::UnsupportedProtocolVersion(protocol_version));
+    }
+
+    let exec_context = ExecutionContext::new(backend, &block.info)?.with_limits(limits).with_metrics(metrics.clone());
+    let transactions = block_to_blockifier_transactions(backend, &block).collect::<Result<Vec<_>, _>>()?;
+    let BlockExecution { results, state_diff } = exec_context.execute_block(None, transactions)?;
+
+    let mut divergences: Vec<_> = results
+        .iter()
+        .zip(&block.inner.receipts)
+        .enumerate()
+        .flat_map(|(tx_index, (result, receipt))| {
+            compare_receipt(result, receipt).into_iter().map(move |kind| Divergence {
+                block_n,
+                transaction: Some((tx_index, receipt.transaction_hash())),
+                kind,
+            })
+        })
+        .collect();
+
+    let stored = backend.get_block_state_diff(&DbBlockId::BlockN(block_n))?.ok_or(Error::BlockNotFound(block_n))?;
+    let (stored, computed) = (sorted_state_diff(stored), sorted_state_diff(state_diff.into()));
+    if stored != computed {
+        divergences.push(Divergence {
+            block_n,
+            transaction: None,
+            kind: DivergenceKind::StateDiff { stored, computed },
+        });
+    }
+    Ok((results.len(), divergences))
+}
+
+/// Re-executes the blocks `first_block..=last_block` in parallel, see [`verify_block_execution`]. The blocks which
+/// cannot be verified are skipped.
+///
+/// # Returns
+///
+/// The divergences found, in block order.
+pub fn verify_blocks_execution(
+    backend: &DeoxysBackend,
+    first_block: u64,
+    last_block: u64,
+    limits: ExecutionLimits,
+    metrics: &ExecutionMetrics,
+) -> Result<Vec<Divergence>, Error> {
+    log::info!("🔍 Re-executing blocks #{first_block}..=#{last_block} from the database");
+    let sw = PerfStopwatch::new();
+
+    let mut divergences = vec![];
+    let mut skipped = 0;
+    let mut chunk_start = first_block;
+    while chunk_start <= last_block {
+        let chunk_end = last_block.min(chunk_start.saturating_add(PROGRESS_LOG_INTERVAL - 1));
+        let chunk_sw = Instant::now();
+
+        let results: Vec<_> = (chunk_start..=chunk_end)
+            .into_par_iter()
+            .map(|block_n| verify_block(backend, block_n, limits, metrics))
+            .collect();
+        let mut chunk_transactions = 0;
+        for result in results {
+            match result {
+                Ok((n_transactions, block_divergences)) => {
+                    chunk_transactions += n_transactions;
+                    for divergence in &block_divergences {
+                        log::warn!("⚠️ {divergence}");
+                    }
+                    divergences.extend(block_divergences);
+                }
+                Err(Error::UnsupportedProtocolVersion(_)) => skipped += 1,
+                Err(err) => return Err(err),
+            }
+        }
+
+        metrics.set_verification_rate(chunk_transactions, chunk_sw.elapsed());
+        log::info!("🔍 Re-executed blocks up to #{chunk_end}, {} divergences found so far", divergences.len());
+        chunk_start = chunk_end + 1;
+    }
+
+    if skipped > 0 {
+        log::info!("⏭️  Skipped {skipped} blocks without versioned constants to verify their fees with");
+    }
+    match divergences.len() {
+        0 => {
+            let elapsed = sw.elapsed();
+            log::info!("✅ Re-executed blocks #{first_block}..=#{last_block}, no divergence found ({elapsed:?})")
+        }
+        n => log::error!(
+            "❌ Re-executed blocks #{first_block}..=#{last_block}, {n} divergences found ({:?})",
+            sw.elapsed()
+        ),
+    }
+    Ok(divergences)
+}
+
+fn compare_receipt(result: &ExecutionResult, receipt: &TransactionReceipt) -> Vec<DivergenceKind> {
+    let computed = &result.receipt;
+    let mut divergences = vec![];
+
+    let is_reverted = |receipt: &TransactionReceipt| {
+        matches!(receipt.execution_result(), dp_receipt::ExecutionResult::Reverted { .. })
+    };
+    let (stored_reverted, computed_reverted) = (is_reverted(receipt), is_reverted(computed));
+    if stored_reverted != computed_reverted {
+        divergences.push(DivergenceKind::ExecutionStatus { stored_reverted, computed_reverted });
+    }
+
+    if receipt.actual_fee() != computed.actual_fee() {
+        divergences.push(DivergenceKind::ActualFee {
+            stored: receipt.actual_fee().clone(),
+            computed: computed.actual_fee().clone(),
+        });
+    }
+
+    if receipt.events() != computed.events() {
+        divergences
+            .push(DivergenceKind::Events { stored: receipt.events().to_vec(), computed: computed.events().to_vec() });
+    }
+
+    if receipt.messages_sent() != computed.messages_sent() {
+        divergences.push(DivergenceKind::MessagesSent {
+            stored: receipt.messages_sent().to_vec(),
+            computed: computed.messages_sent().to_vec(),
+        });
+    }
+
+    divergences
+}
+
+/// Sorts the updates of a state diff, so that state diffs can be compared regardless of the order of their updates.
+fn sorted_state_diff(mut state_diff: StateDiff) -> StateDiff {
+    for item in &mut state_diff.storage_diffs {
+        item.storage_entries.sort_by_key(|entry| entry.key);
+    }
+    state_diff.storage_diffs.sort_by_key(|item| item.address);
+    state_diff.deprecated_declared_classes.sort();
+    state_diff.declared_classes.sort_by_key(|item| item.class_hash);
+    state_diff.deployed_contracts.sort_by_key(|item| item.address);
+    state_diff.replaced_classes.sort_by_key(|item| item.contract_address);
+    state_diff.nonces.sort_by_key(|item| item.contract_address);
+    state_diff
+}
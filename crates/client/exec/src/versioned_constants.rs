@@ -0,0 +1,114 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use blockifier::versioned_constants::VersionedConstants;
+use dp_block::header::{
+    BLOCKIFIER_VERSIONED_CONSTANTS_0_13_0, BLOCKIFIER_VERSIONED_CONSTANTS_0_13_1, BLOCKIFIER_VERSIONED_CONSTANTS_0_13_2,
+};
+use dp_block::StarknetVersion;
+
+use crate::Error;
+
+/// Versioned constants loaded from files at startup, by the first protocol version they apply to.
+static CUSTOM_VERSIONED_CONSTANTS: OnceLock<BTreeMap<StarknetVersion, VersionedConstants>> = OnceLock::new();
+
+#[derive(thiserror::Error, Debug)]
+pub enum VersionedConstantsError {
+    #[error("Reading versioned constants file {path:?}: {err:#}")]
+    Io { path: PathBuf, err: std::io::Error },
+    #[error("Parsing versioned constants file {path:?}: {err:#}")]
+    Parse { path: PathBuf, err: serde_json::Error },
+    #[error("Versioned constants are already loaded")]
+    AlreadyLoaded,
+}
+
+/// Loads versioned constants from JSON files, in the blockifier format. The constants of a file apply to the blocks
+/// starting from its protocol version, up to the next protocol version which has constants. They take precedence
+/// over the constants built into the node for the same protocol version.
+///
+/// This is meant to be called once at startup, before executing any transaction.
+pub fn load_versioned_constants(
+    files: impl IntoIterator<Item = (StarknetVersion, PathBuf)>,
+) -> Result<(), VersionedConstantsError> {
+    let mut constants = BTreeMap::new();
+    for (protocol_version, path) in files {
+        let file = std::fs::read(&path).map_err(|err| VersionedConstantsError::Io { path: path.clone(), err })?;
+        let versioned_constants =
+            serde_json::from_slice(&file).map_err(|err| VersionedConstantsError::Parse { path: path.clone(), err })?;
+        log::info!("📜 Loaded the versioned constants of Starknet {protocol_version} from {path:?}");
+        constants.insert(protocol_version, versioned_constants);
+    }
+    CUSTOM_VERSIONED_CONSTANTS.set(constants).map_err(|_| VersionedConstantsError::AlreadyLoaded)
+}
+
+/// The versioned constants built into the node. Each entry applies from its protocol version up to the protocol
+/// version of the next entry, along with whether they are the exact constants of these versions. The versions of an
+/// entry without constants are unknown to the node. The last entry applies to the later protocol versions.
+fn builtin_versioned_constants() -> [(StarknetVersion, Option<&'static VersionedConstants>, bool); 5] {
+    [
+        // No constants are built into the node for the blocks older than Starknet 0.13.0: calls are executed the
+        // same with the ones of 0.13.0, but the fees and resources of their transactions would differ from the ones
+        // which were charged
+        (StarknetVersion::default(), Some(&*BLOCKIFIER_VERSIONED_CONSTANTS_0_13_0), false),
+        (StarknetVersion::STARKNET_VERSION_0_13_0, Some(&*BLOCKIFIER_VERSIONED_CONSTANTS_0_13_0), true),
+        (StarknetVersion::STARKNET_VERSION_0_13_1, Some(&*BLOCKIFIER_VERSIONED_CONSTANTS_0_13_1), true),
+        (StarknetVersion::STARKNET_VERSION_0_13_1_1, Some(VersionedConstants::latest_constants()), true),
+        (StarknetVersion::STARKNET_VERSION_0_13_2, Some(&*BLOCKIFIER_VERSIONED_CONSTANTS_0_13_2), true),
+    ]
+}
+
+/// The versioned constants to execute the blocks of a protocol version with. Fails with
+/// [`Error::UnsupportedProtocolVersion`] for the protocol versions unknown to the node, unless constants were loaded
+/// for them. The constants are not exact for every version, see [`has_versioned_constants`].
+pub(crate) fn versioned_constants(protocol_version: StarknetVersion) -> Result<&'static VersionedConstants, Error> {
+    registry_entry(protocol_version).0.ok_or(Error::UnsupportedProtocolVersion(protocol_version))
+}
+
+/// Whether the blocks of a protocol version have their own versioned constants, so that their fees are computed
+/// exactly. Only calls are executed on the blocks without them.
+pub(crate) fn has_versioned_constants(protocol_version: StarknetVersion) -> bool {
+    matches!(registry_entry(protocol_version), (Some(_), true))
+}
+
+/// The entry of the built in or loaded constants the protocol version falls in, the loaded ones taking precedence
+/// over the built in ones of the same version.
+fn registry_entry(protocol_version: StarknetVersion) -> (Option<&'static VersionedConstants>, bool) {
+    let (builtin_version, builtin, exact) = builtin_versioned_constants()
+        .into_iter()
+        .filter(|(version, _, _)| *version <= protocol_version)
+        .last()
+        .expect("The first entry applies to every version");
+    let custom = CUSTOM_VERSIONED_CONSTANTS
+        .get()
+        .and_then(|constants| constants.range(..=protocol_version).next_back())
+        .filter(|(custom_version, _)| **custom_version >= builtin_version);
+
+    match custom {
+        Some((_, custom)) => (Some(custom), true),
+        None => (builtin, exact),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_versioned_constants() {
+        for version in ["0.13.0", "0.13.1", "0.13.1.1", "0.13.2", "0.13.2.1", "0.13.3"] {
+            let version: StarknetVersion = version.parse().unwrap();
+            assert!(versioned_constants(version).is_ok(), "{version}");
+            assert!(has_versioned_constants(version), "{version}");
+        }
+        assert_eq!(
+            versioned_constants(StarknetVersion::STARKNET_VERSION_0_13_2).unwrap().invoke_tx_max_n_steps,
+            10_000_000
+        );
+
+        // Only calls are executed on the blocks older than 0.13.0
+        let version: StarknetVersion = "0.12.3".parse().unwrap();
+        assert!(versioned_constants(version).is_ok());
+        assert!(!has_versioned_constants(version));
+    }
+}
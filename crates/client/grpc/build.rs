@@ -0,0 +1,6 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Use a vendored protoc, so that building does not require it to be installed.
+    std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+    tonic_build::configure().build_client(false).compile(&["proto/deoxys.proto"], &["proto"])?;
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use dc_rpc::StarknetReadRpcApiServer;
+use starknet_core::types::BlockId;
+use tonic::{Request, Response, Status};
+
+use crate::convert::{block_id, felt_bytes, to_status};
+use crate::proto::block_service_server::BlockService;
+use crate::proto::{
+    BlockHashAndNumberRequest, BlockHashAndNumberResponse, BlockHeader, BlockWithTxHashes, GetBlockRequest,
+    SubscribeNewHeadsRequest,
+};
+use crate::stream::follow_blocks;
+use crate::{DeoxysGrpc, ResponseStream};
+
+#[tonic::async_trait]
+impl BlockService for DeoxysGrpc {
+    async fn block_hash_and_number(
+        &self,
+        _request: Request<BlockHashAndNumberRequest>,
+    ) -> Result<Response<BlockHashAndNumberResponse>, Status> {
+        let res = self.starknet.block_hash_and_number().map_err(to_status)?;
+        Ok(Response::new(BlockHashAndNumberResponse {
+            block_hash: felt_bytes(&res.block_hash),
+            block_number: res.block_number,
+        }))
+    }
+
+    async fn get_block_with_tx_hashes(
+        &self,
+        request: Request<GetBlockRequest>,
+    ) -> Result<Response<BlockWithTxHashes>, Status> {
+        let block_id = block_id(request.into_inner().block_id)?;
+        let block = self.starknet.get_block_with_tx_hashes(block_id).map_err(to_status)?;
+        Ok(Response::new(block.into()))
+    }
+
+    type SubscribeNewHeadsStream = ResponseStream<BlockHeader>;
+
+    async fn subscribe_new_heads(
+        &self,
+        request: Request<SubscribeNewHeadsRequest>,
+    ) -> Result<Response<Self::SubscribeNewHeadsStream>, Status> {
+        let starknet = self.starknet.clone();
+        let stream = follow_blocks(self, request.into_inner().from_block, move |block_n| {
+            let block = starknet.get_block_with_tx_hashes(BlockId::Number(block_n)).map_err(to_status);
+            async move { Ok(BlockWithTxHashes::from(block?).header.into_iter().collect()) }
+        })?;
+        Ok(Response::new(stream))
+    }
+}
@@ -0,0 +1,136 @@
+//! Conversions between the protobuf messages and the Starknet types.
+
+use jsonrpsee::types::ErrorObjectOwned;
+use starknet_core::types::{
+    BlockId, BlockStatus, BlockTag, EmittedEvent, L1DataAvailabilityMode, MaybePendingBlockWithTxHashes, ResourcePrice,
+};
+use starknet_types_core::felt::Felt;
+use tonic::Status;
+
+use crate::proto;
+
+/// JSON-RPC error codes of the Starknet specification which mean that the requested item does not exist.
+const NOT_FOUND_ERROR_CODES: [i32; 4] = [
+    20, // CONTRACT_NOT_FOUND
+    24, // BLOCK_NOT_FOUND
+    28, // CLASS_HASH_NOT_FOUND
+    29, // TXN_HASH_NOT_FOUND
+];
+
+/// Converts a JSON-RPC error into a gRPC status. The JSON-RPC error code is kept in the message.
+pub(crate) fn to_status(err: ErrorObjectOwned) -> Status {
+    let message = format!("{} ({})", err.message(), err.code());
+    match err.code() {
+        code if NOT_FOUND_ERROR_CODES.contains(&code) => Status::not_found(message),
+        jsonrpsee::types::error::INTERNAL_ERROR_CODE => Status::internal(message),
+        _ => Status::invalid_argument(message),
+    }
+}
+
+pub(crate) fn felt(bytes: &[u8]) -> Result<Felt, Status> {
+    if bytes.len() > 32 {
+        return Err(Status::invalid_argument("Field element is longer than 32 bytes"));
+    }
+    Ok(Felt::from_bytes_be_slice(bytes))
+}
+
+pub(crate) fn felt_bytes(felt: &Felt) -> Vec<u8> {
+    felt.to_bytes_be().to_vec()
+}
+
+fn felts_bytes(felts: &[Felt]) -> Vec<Vec<u8>> {
+    felts.iter().map(felt_bytes).collect()
+}
+
+pub(crate) fn block_id(block_id: Option<proto::BlockId>) -> Result<BlockId, Status> {
+    opt_block_id(block_id)?.ok_or_else(|| Status::invalid_argument("Missing block id"))
+}
+
+pub(crate) fn opt_block_id(block_id: Option<proto::BlockId>) -> Result<Option<BlockId>, Status> {
+    let Some(id) = block_id.and_then(|block_id| block_id.id) else { return Ok(None) };
+    let block_id = match id {
+        proto::block_id::Id::Number(block_n) => BlockId::Number(block_n),
+        proto::block_id::Id::Hash(hash) => BlockId::Hash(felt(&hash)?),
+        proto::block_id::Id::Tag(tag) => match proto::BlockTag::try_from(tag) {
+            Ok(proto::BlockTag::Latest) => BlockId::Tag(BlockTag::Latest),
+            Ok(proto::BlockTag::Pending) => BlockId::Tag(BlockTag::Pending),
+            Err(_) => return Err(Status::invalid_argument(format!("Unknown block tag {tag}"))),
+        },
+    };
+    Ok(Some(block_id))
+}
+
+fn resource_price(price: &ResourcePrice) -> proto::ResourcePrice {
+    proto::ResourcePrice {
+        price_in_fri: felt_bytes(&price.price_in_fri),
+        price_in_wei: felt_bytes(&price.price_in_wei),
+    }
+}
+
+fn l1_da_mode(mode: L1DataAvailabilityMode) -> proto::L1DataAvailabilityMode {
+    match mode {
+        L1DataAvailabilityMode::Calldata => proto::L1DataAvailabilityMode::Calldata,
+        L1DataAvailabilityMode::Blob => proto::L1DataAvailabilityMode::Blob,
+    }
+}
+
+fn block_status(status: BlockStatus) -> proto::BlockStatus {
+    match status {
+        BlockStatus::Pending => proto::BlockStatus::Pending,
+        BlockStatus::AcceptedOnL2 => proto::BlockStatus::AcceptedOnL2,
+        BlockStatus::AcceptedOnL1 => proto::BlockStatus::AcceptedOnL1,
+        BlockStatus::Rejected => proto::BlockStatus::Rejected,
+    }
+}
+
+impl From<MaybePendingBlockWithTxHashes> for proto::BlockWithTxHashes {
+    fn from(block: MaybePendingBlockWithTxHashes) -> Self {
+        match block {
+            MaybePendingBlockWithTxHashes::Block(block) => Self {
+                header: Some(proto::BlockHeader {
+                    block_hash: Some(felt_bytes(&block.block_hash)),
+                    parent_hash: felt_bytes(&block.parent_hash),
+                    block_number: Some(block.block_number),
+                    new_root: Some(felt_bytes(&block.new_root)),
+                    timestamp: block.timestamp,
+                    sequencer_address: felt_bytes(&block.sequencer_address),
+                    l1_gas_price: Some(resource_price(&block.l1_gas_price)),
+                    l1_data_gas_price: Some(resource_price(&block.l1_data_gas_price)),
+                    l1_da_mode: l1_da_mode(block.l1_da_mode).into(),
+                    starknet_version: block.starknet_version,
+                    status: block_status(block.status).into(),
+                }),
+                transactions: felts_bytes(&block.transactions),
+            },
+            MaybePendingBlockWithTxHashes::PendingBlock(block) => Self {
+                header: Some(proto::BlockHeader {
+                    block_hash: None,
+                    parent_hash: felt_bytes(&block.parent_hash),
+                    block_number: None,
+                    new_root: None,
+                    timestamp: block.timestamp,
+                    sequencer_address: felt_bytes(&block.sequencer_address),
+                    l1_gas_price: Some(resource_price(&block.l1_gas_price)),
+                    l1_data_gas_price: Some(resource_price(&block.l1_data_gas_price)),
+                    l1_da_mode: l1_da_mode(block.l1_da_mode).into(),
+                    starknet_version: block.starknet_version,
+                    status: proto::BlockStatus::Pending.into(),
+                }),
+                transactions: felts_bytes(&block.transactions),
+            },
+        }
+    }
+}
+
+impl From<EmittedEvent> for proto::EmittedEvent {
+    fn from(event: EmittedEvent) -> Self {
+        Self {
+            from_address: felt_bytes(&event.from_address),
+            keys: felts_bytes(&event.keys),
+            data: felts_bytes(&event.data),
+            block_hash: event.block_hash.as_ref().map(felt_bytes),
+            block_number: event.block_number,
+            transaction_hash: felt_bytes(&event.transaction_hash),
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use dc_rpc::constants::MAX_EVENTS_KEYS;
+use dc_rpc::{Starknet, StarknetReadRpcApiServer};
+use starknet_core::types::{BlockId, EventFilter, EventFilterWithPage, ResultPageRequest};
+use starknet_types_core::felt::Felt;
+use tonic::{Request, Response, Status};
+
+use crate::convert::{felt, opt_block_id, to_status};
+use crate::proto::event_service_server::EventService;
+use crate::proto::{self, EmittedEvent, EventsPage, GetEventsRequest, SubscribeEventsRequest};
+use crate::stream::follow_blocks;
+use crate::{DeoxysGrpc, ResponseStream};
+
+#[tonic::async_trait]
+impl EventService for DeoxysGrpc {
+    async fn get_events(&self, request: Request<GetEventsRequest>) -> Result<Response<EventsPage>, Status> {
+        let request = request.into_inner();
+        let (address, keys) = event_filter(request.filter)?;
+        let filter = EventFilterWithPage {
+            event_filter: EventFilter {
+                from_block: opt_block_id(request.from_block)?,
+                to_block: opt_block_id(request.to_block)?,
+                address,
+                keys: Some(keys),
+            },
+            result_page_request: ResultPageRequest {
+                continuation_token: request.continuation_token,
+                chunk_size: request.chunk_size,
+            },
+        };
+
+        let page = self.starknet.get_events(filter).await.map_err(to_status)?;
+        Ok(Response::new(EventsPage {
+            events: page.events.into_iter().map(Into::into).collect(),
+            continuation_token: page.continuation_token,
+        }))
+    }
+
+    type SubscribeEventsStream = ResponseStream<EmittedEvent>;
+
+    async fn subscribe_events(
+        &self,
+        request: Request<SubscribeEventsRequest>,
+    ) -> Result<Response<Self::SubscribeEventsStream>, Status> {
+        let request = request.into_inner();
+        let (address, keys) = event_filter(request.filter)?;
+        if keys.len() > MAX_EVENTS_KEYS {
+            return Err(Status::invalid_argument(format!("Too many keys in filter, the maximum is {MAX_EVENTS_KEYS}")));
+        }
+
+        let starknet = self.starknet.clone();
+        let stream = follow_blocks(self, request.from_block, move |block_n| {
+            block_events(starknet.clone(), block_n, address, keys.clone())
+        })?;
+        Ok(Response::new(stream))
+    }
+}
+
+fn event_filter(filter: Option<proto::EventFilter>) -> Result<(Option<Felt>, Vec<Vec<Felt>>), Status> {
+    let filter = filter.unwrap_or_default();
+    let address = filter.from_address.as_deref().map(felt).transpose()?;
+    let keys = filter
+        .keys
+        .iter()
+        .map(|keys| keys.keys.iter().map(|key| felt(key)).collect::<Result<_, _>>())
+        .collect::<Result<_, _>>()?;
+    Ok((address, keys))
+}
+
+/// The events of a stored block matching the filter.
+async fn block_events(
+    starknet: Starknet,
+    block_n: u64,
+    address: Option<Felt>,
+    keys: Vec<Vec<Felt>>,
+) -> Result<Vec<EmittedEvent>, Status> {
+    let chunk_size = starknet.events_max_chunk_size() as u64;
+    let mut events = Vec::new();
+    let mut continuation_token = None;
+    loop {
+        let filter = EventFilterWithPage {
+            event_filter: EventFilter {
+                from_block: Some(BlockId::Number(block_n)),
+                to_block: Some(BlockId::Number(block_n)),
+                address,
+                keys: Some(keys.clone()),
+            },
+            result_page_request: ResultPageRequest { continuation_token, chunk_size },
+        };
+        let page = starknet.get_events(filter).await.map_err(to_status)?;
+        events.extend(page.events.into_iter().map(EmittedEvent::from));
+
+        match page.continuation_token {
+            Some(token) => continuation_token = Some(token),
+            None => return Ok(events),
+        }
+    }
+}
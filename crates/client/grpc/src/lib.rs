@@ -0,0 +1,114 @@
+//! gRPC server exposing the read API of the node.
+//!
+//! It mirrors a subset of the `starknet_` JSON-RPC read methods, plus server-side streaming of the new blocks and
+//! events, for the clients for which JSON serialization is the bottleneck. The calls are answered by the same
+//! [`Starknet`] implementation as the JSON-RPC server.
+//!
+//! The server does not authenticate nor rate limit the callers itself: the embedder checks each call with a
+//! [`GrpcInterceptor`].
+
+mod block;
+mod convert;
+mod events;
+mod state;
+mod stream;
+
+use std::net::{Ipv4Addr, SocketAddr};
+use std::pin::Pin;
+use std::sync::Arc;
+
+use anyhow::Context;
+use dc_db::DeoxysBackend;
+use dc_rpc::Starknet;
+use dp_utils::graceful_shutdown;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio_stream::Stream;
+use tonic::transport::Server;
+use tonic::{Request, Status};
+
+pub mod proto {
+    tonic::include_proto!("deoxys.v1");
+}
+
+use proto::block_service_server::BlockServiceServer;
+use proto::event_service_server::EventServiceServer;
+use proto::state_service_server::StateServiceServer;
+
+/// Number of messages buffered for each streaming call before waiting for the client.
+const STREAM_BUFFER: usize = 64;
+
+type ResponseStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>;
+
+/// Check run before every call, with the metadata and the remote address of the request. The call is rejected with
+/// the returned status.
+pub type GrpcInterceptor = Arc<dyn Fn(&Request<()>) -> Result<(), Status> + Send + Sync>;
+
+/// Implementation of the gRPC services.
+#[derive(Clone)]
+pub struct DeoxysGrpc {
+    starknet: Starknet,
+    backend: Arc<DeoxysBackend>,
+    /// Every open stream holds a permit until it ends.
+    streams: Arc<Semaphore>,
+}
+
+pub struct GrpcService {
+    grpc_disabled: bool,
+    grpc_external: bool,
+    grpc_port: u16,
+    api: DeoxysGrpc,
+    interceptor: Option<GrpcInterceptor>,
+}
+
+impl GrpcService {
+    pub fn new(
+        grpc_disabled: bool,
+        grpc_external: bool,
+        grpc_port: u16,
+        max_streams: usize,
+        starknet: Starknet,
+        backend: Arc<DeoxysBackend>,
+    ) -> Self {
+        let api = DeoxysGrpc { starknet, backend, streams: Arc::new(Semaphore::new(max_streams)) };
+        Self { grpc_disabled, grpc_external, grpc_port, api, interceptor: None }
+    }
+
+    pub fn with_interceptor(self, interceptor: GrpcInterceptor) -> Self {
+        Self { interceptor: Some(interceptor), ..self }
+    }
+
+    pub async fn start(&mut self, join_set: &mut JoinSet<anyhow::Result<()>>) -> anyhow::Result<()> {
+        if self.grpc_disabled {
+            return Ok(());
+        }
+
+        let listen_addr = if self.grpc_external {
+            Ipv4Addr::UNSPECIFIED // listen on 0.0.0.0
+        } else {
+            Ipv4Addr::LOCALHOST
+        };
+        let addr = SocketAddr::new(listen_addr.into(), self.grpc_port);
+        let api = self.api.clone();
+        let interceptor = self.interceptor.clone();
+        let intercept = move |request: Request<()>| -> Result<Request<()>, Status> {
+            if let Some(interceptor) = &interceptor {
+                interceptor(&request)?;
+            }
+            Ok(request)
+        };
+
+        join_set.spawn(async move {
+            log::info!("🛰️  gRPC server started at {}", addr);
+            Server::builder()
+                .add_service(BlockServiceServer::with_interceptor(api.clone(), intercept.clone()))
+                .add_service(StateServiceServer::with_interceptor(api.clone(), intercept.clone()))
+                .add_service(EventServiceServer::with_interceptor(api, intercept))
+                .serve_with_shutdown(addr, graceful_shutdown())
+                .await
+                .with_context(|| format!("Running gRPC server at {addr}"))
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,37 @@
+use dc_rpc::StarknetReadRpcApiServer;
+use tonic::{Request, Response, Status};
+
+use crate::convert::{block_id, felt, felt_bytes, to_status};
+use crate::proto::state_service_server::StateService;
+use crate::proto::{Felt, GetClassHashAtRequest, GetNonceRequest, GetStorageAtRequest};
+use crate::DeoxysGrpc;
+
+#[tonic::async_trait]
+impl StateService for DeoxysGrpc {
+    async fn get_storage_at(&self, request: Request<GetStorageAtRequest>) -> Result<Response<Felt>, Status> {
+        let request = request.into_inner();
+        let value = self
+            .starknet
+            .get_storage_at(felt(&request.contract_address)?, felt(&request.key)?, block_id(request.block_id)?)
+            .map_err(to_status)?;
+        Ok(Response::new(Felt { value: felt_bytes(&value) }))
+    }
+
+    async fn get_nonce(&self, request: Request<GetNonceRequest>) -> Result<Response<Felt>, Status> {
+        let request = request.into_inner();
+        let nonce = self
+            .starknet
+            .get_nonce(block_id(request.block_id)?, felt(&request.contract_address)?)
+            .map_err(to_status)?;
+        Ok(Response::new(Felt { value: felt_bytes(&nonce) }))
+    }
+
+    async fn get_class_hash_at(&self, request: Request<GetClassHashAtRequest>) -> Result<Response<Felt>, Status> {
+        let request = request.into_inner();
+        let class_hash = self
+            .starknet
+            .get_class_hash_at(block_id(request.block_id)?, felt(&request.contract_address)?)
+            .map_err(to_status)?;
+        Ok(Response::new(Felt { value: felt_bytes(&class_hash) }))
+    }
+}
@@ -0,0 +1,105 @@
+//! Server-side streaming of the new blocks.
+
+use std::future::Future;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+use dc_rpc::constants::MAX_BLOCKS_BACK;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::Status;
+
+use crate::{DeoxysGrpc, ResponseStream, STREAM_BUFFER};
+
+/// Streams the messages returned by `on_block` for every block from `from_block` up to the current tip, and then
+/// for each new block once it has been stored. A client which falls behind is caught up from the database, so that
+/// no block is ever skipped.
+///
+/// Fails right away when `from_block` is more than [`MAX_BLOCKS_BACK`] blocks in the past, or when the maximum number
+/// of open streams is reached. Errors returned by `on_block` are sent to the client, and end the stream.
+pub(crate) fn follow_blocks<T, F, Fut>(
+    grpc: &DeoxysGrpc,
+    from_block: Option<u64>,
+    mut on_block: F,
+) -> Result<ResponseStream<T>, Status>
+where
+    T: Send + 'static,
+    F: FnMut(u64) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<Vec<T>, Status>> + Send,
+{
+    // Held until the stream ends
+    let permit = Arc::clone(&grpc.streams)
+        .try_acquire_owned()
+        .map_err(|_| Status::resource_exhausted("Too many open streams, try again later"))?;
+    let backend = &grpc.backend;
+
+    // Subscribe before reading the database, so that no block can be stored in between without being notified
+    let mut new_blocks = backend.subscribe_new_blocks();
+
+    let latest_block_n = backend
+        .get_latest_block_n()
+        .map_err(|err| Status::internal(format!("Error getting the latest block number: {err:#}")))?;
+    let mut next_block_n = match from_block {
+        Some(block_n) if latest_block_n.unwrap_or(0).saturating_sub(block_n) > MAX_BLOCKS_BACK => {
+            return Err(Status::invalid_argument(format!("Cannot start more than {MAX_BLOCKS_BACK} blocks back")));
+        }
+        Some(block_n) => block_n,
+        None => latest_block_n.unwrap_or(0),
+    };
+
+    let (tx, rx) = mpsc::channel(STREAM_BUFFER);
+
+    tokio::spawn(async move {
+        let _permit = permit;
+        let mut res = Ok(true);
+        if let Some(latest_block_n) = latest_block_n {
+            res = send_blocks(&tx, &mut on_block, next_block_n..=latest_block_n).await;
+            next_block_n = u64::max(next_block_n, latest_block_n + 1);
+        }
+
+        while let Ok(true) = res {
+            let block = tokio::select! {
+                block = new_blocks.recv() => block,
+                _ = tx.closed() => return,
+            };
+            res = match block {
+                Ok(block) => {
+                    let block_n = block.info.header.block_number;
+                    let res = send_blocks(&tx, &mut on_block, next_block_n..=block_n).await;
+                    next_block_n = u64::max(next_block_n, block_n + 1);
+                    res
+                }
+                // the missed blocks are read from the database when the next one is received
+                Err(RecvError::Lagged(_)) => Ok(true),
+                Err(RecvError::Closed) => Ok(false),
+            };
+        }
+
+        if let Err(status) = res {
+            let _ = tx.send(Err(status)).await;
+        }
+    });
+
+    Ok(Box::pin(ReceiverStream::new(rx)))
+}
+
+/// Sends the messages of the blocks, and returns whether the client is still there.
+async fn send_blocks<T, F, Fut>(
+    tx: &mpsc::Sender<Result<T, Status>>,
+    on_block: &mut F,
+    blocks: RangeInclusive<u64>,
+) -> Result<bool, Status>
+where
+    F: FnMut(u64) -> Fut,
+    Fut: Future<Output = Result<Vec<T>, Status>>,
+{
+    for block_n in blocks {
+        for message in on_block(block_n).await? {
+            if tx.send(Ok(message)).await.is_err() {
+                return Ok(false);
+            }
+        }
+    }
+    Ok(true)
+}
@@ -1,4 +1,18 @@
 /// Maximum number of filter keys that can be passed to the `get_events` RPC.
 pub const MAX_EVENTS_KEYS: usize = 100;
-/// Maximum number of events that can be fetched in a single chunk for the `get_events` RPC.
+/// Default maximum number of events that can be fetched in a single chunk for the `get_events` RPC.
 pub const MAX_EVENTS_CHUNK_SIZE: usize = 1000;
+/// Default maximum number of blocks a single `get_events` call can scan for matching events.
+pub const DEFAULT_EVENTS_MAX_BLOCKS_SCANNED: u64 = 50_000;
+
+/// Maximum number of blocks back a websocket subscription can start from.
+pub const MAX_BLOCKS_BACK: u64 = 1024;
+/// Error notified to the subscribers when their subscription is closed because the node shuts down.
+pub const SUBSCRIPTION_SHUTDOWN_MESSAGE: &str = "The node is shutting down";
+
+/// Maximum number of storage keys that can be proven in a single `pathfinder_getProof` call.
+pub const MAX_STORAGE_PROOF_KEYS: usize = 100;
+
+/// Maximum number of storage keys that can be read in a single `deoxys_getStorageBatch` or
+/// `deoxys_getContractsStorageBatch` call.
+pub const MAX_STORAGE_BATCH_KEYS: usize = 1024;
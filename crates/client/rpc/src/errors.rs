@@ -44,7 +44,7 @@ pub enum StarknetRpcApiError {
     #[error("Failed to fetch pending transactions")]
     FailedToFetchPendingTransactions,
     #[error("Contract error")]
-    ContractError,
+    ContractError { revert_error: String },
     #[error("Transaction execution error")]
     TxnExecutionError { tx_index: usize, error: String },
     #[error("Invalid contract class")]
@@ -58,7 +58,7 @@ pub enum StarknetRpcApiError {
     #[error("Account balance is smaller than the transaction's max_fee")]
     InsufficientAccountBalance,
     #[error("Account validation failed")]
-    ValidationFailure,
+    ValidationFailure { error: String },
     #[error("Compilation failed")]
     CompilationFailed,
     #[error("Contract class size is too large")]
@@ -81,51 +81,107 @@ pub enum StarknetRpcApiError {
     UnimplementedMethod,
     #[error("Too many storage keys requested")]
     ProofLimitExceeded,
+    #[error("Merkle trie proof is not available")]
+    ProofMissing,
+    #[error("Cannot go back more than 1024 blocks")]
+    TooManyBlocksBack,
+    #[error("The node doesn't support storage proofs for blocks that are too far in the past")]
+    StorageProofNotSupported,
+    #[error("Too many storage keys in a single batch")]
+    StorageBatchLimitExceeded,
+    #[error("Too many blocks to scan for events, reduce the block range")]
+    EventsScanLimitExceeded,
+    #[error("The node is busy executing other calls, try again later")]
+    ExecutionBusy,
 }
 
-impl From<&StarknetRpcApiError> for i32 {
-    fn from(err: &StarknetRpcApiError) -> Self {
-        match err {
-            StarknetRpcApiError::FailedToReceiveTxn => 1,
-            StarknetRpcApiError::ContractNotFound => 20,
-            StarknetRpcApiError::BlockNotFound => 24,
-            StarknetRpcApiError::InvalidTxnHash => 25,
-            StarknetRpcApiError::InvalidBlockHash => 26,
-            StarknetRpcApiError::InvalidTxnIndex => 27,
-            StarknetRpcApiError::ClassHashNotFound => 28,
-            StarknetRpcApiError::TxnHashNotFound => 29,
-            StarknetRpcApiError::PageSizeTooBig => 31,
-            StarknetRpcApiError::NoBlocks => 32,
-            StarknetRpcApiError::InvalidContinuationToken => 33,
-            StarknetRpcApiError::TooManyKeysInFilter => 34,
-            StarknetRpcApiError::FailedToFetchPendingTransactions => 38,
-            StarknetRpcApiError::ContractError => 40,
-            StarknetRpcApiError::TxnExecutionError { .. } => 41,
-            StarknetRpcApiError::InvalidContractClass => 50,
-            StarknetRpcApiError::ClassAlreadyDeclared => 51,
-            StarknetRpcApiError::InvalidTxnNonce => 52,
-            StarknetRpcApiError::InsufficientMaxFee => 53,
-            StarknetRpcApiError::InsufficientAccountBalance => 54,
-            StarknetRpcApiError::ValidationFailure => 55,
-            StarknetRpcApiError::CompilationFailed => 56,
-            StarknetRpcApiError::ContractClassSizeTooLarge => 57,
-            StarknetRpcApiError::NonAccount => 58,
-            StarknetRpcApiError::DuplicateTxn => 59,
-            StarknetRpcApiError::CompiledClassHashMismatch => 60,
-            StarknetRpcApiError::UnsupportedTxnVersion => 61,
-            StarknetRpcApiError::UnsupportedContractClassVersion => 62,
-            StarknetRpcApiError::ErrUnexpectedError { .. } => 63,
-            StarknetRpcApiError::InternalServerError => 500,
-            StarknetRpcApiError::UnimplementedMethod => 501,
-            StarknetRpcApiError::ProofLimitExceeded => 10000,
+/// Whether an error comes from a mistake of the caller, or from a failure of the node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorOrigin {
+    Caller,
+    Node,
+}
+
+impl ErrorOrigin {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ErrorOrigin::Caller => "caller",
+            ErrorOrigin::Node => "node",
         }
     }
 }
 
+/// Generates the code of each error, and the reverse lookup of its name and origin, from a single table.
+macro_rules! error_codes {
+    ($($variant:ident => $code:literal, $origin:ident;)*) => {
+        impl StarknetRpcApiError {
+            /// Name and origin of the error answered with `code`, `None` for the codes which are not defined here.
+            /// Used to label the error metrics.
+            pub fn describe_code(code: i32) -> Option<(&'static str, ErrorOrigin)> {
+                match code {
+                    $($code => Some((stringify!($variant), ErrorOrigin::$origin)),)*
+                    _ => None,
+                }
+            }
+        }
+
+        impl From<&StarknetRpcApiError> for i32 {
+            fn from(err: &StarknetRpcApiError) -> Self {
+                match err {
+                    $(StarknetRpcApiError::$variant { .. } => $code,)*
+                }
+            }
+        }
+    };
+}
+
+error_codes! {
+    FailedToReceiveTxn => 1, Node;
+    ContractNotFound => 20, Caller;
+    BlockNotFound => 24, Caller;
+    InvalidTxnHash => 25, Caller;
+    InvalidBlockHash => 26, Caller;
+    InvalidTxnIndex => 27, Caller;
+    ClassHashNotFound => 28, Caller;
+    TxnHashNotFound => 29, Caller;
+    PageSizeTooBig => 31, Caller;
+    NoBlocks => 32, Node;
+    InvalidContinuationToken => 33, Caller;
+    TooManyKeysInFilter => 34, Caller;
+    FailedToFetchPendingTransactions => 38, Node;
+    ContractError => 40, Caller;
+    TxnExecutionError => 41, Caller;
+    InvalidContractClass => 50, Caller;
+    ClassAlreadyDeclared => 51, Caller;
+    InvalidTxnNonce => 52, Caller;
+    InsufficientMaxFee => 53, Caller;
+    InsufficientAccountBalance => 54, Caller;
+    ValidationFailure => 55, Caller;
+    CompilationFailed => 56, Caller;
+    ContractClassSizeTooLarge => 57, Caller;
+    NonAccount => 58, Caller;
+    DuplicateTxn => 59, Caller;
+    CompiledClassHashMismatch => 60, Caller;
+    UnsupportedTxnVersion => 61, Caller;
+    UnsupportedContractClassVersion => 62, Caller;
+    ErrUnexpectedError => 63, Node;
+    InternalServerError => 500, Node;
+    UnimplementedMethod => 501, Node;
+    ProofLimitExceeded => 10000, Caller;
+    ProofMissing => 10001, Node;
+    TooManyBlocksBack => 68, Caller;
+    StorageProofNotSupported => 42, Caller;
+    StorageBatchLimitExceeded => 10002, Caller;
+    EventsScanLimitExceeded => 10003, Caller;
+    ExecutionBusy => 10004, Node;
+}
+
 impl StarknetRpcApiError {
     pub fn data(&self) -> Option<serde_json::Value> {
         match self {
             StarknetRpcApiError::ErrUnexpectedError { data } => Some(json!(data)),
+            StarknetRpcApiError::ValidationFailure { error } => Some(json!(error)),
+            StarknetRpcApiError::ContractError { revert_error } => Some(json!({ "revert_error": revert_error })),
             StarknetRpcApiError::TxnExecutionError { tx_index, error } => Some(json!({
                 "transaction_index": tx_index,
                 "execution_error": error,
@@ -137,7 +193,44 @@ impl StarknetRpcApiError {
 
 impl From<dc_exec::Error> for StarknetRpcApiError {
     fn from(err: dc_exec::Error) -> Self {
-        Self::TxnExecutionError { tx_index: 0, error: format!("{:#}", err) }
+        match err {
+            dc_exec::Error::CallContract(err) => Self::ContractError { revert_error: format!("{:#}", err) },
+            dc_exec::Error::MessageFeeEstimation(err) => Self::ContractError { revert_error: format!("{:#}", err) },
+            dc_exec::Error::Reexecution(err) => {
+                Self::TxnExecutionError { tx_index: err.tx_index(), error: format!("{:#}", err) }
+            }
+            dc_exec::Error::InsufficientBalance(err) => {
+                log::debug!(target: "rpc_errors", "{err:#}");
+                Self::InsufficientAccountBalance
+            }
+            dc_exec::Error::FeeEstimation(err) => {
+                Self::TxnExecutionError { tx_index: err.tx_index(), error: format!("{:#}", err) }
+            }
+            err @ dc_exec::Error::UnsupportedProtocolVersion(_) => {
+                Self::ErrUnexpectedError { data: format!("{err:#}") }
+            }
+            // Only the calls which were dropped are cancelled, nobody reads this error
+            dc_exec::Error::Cancelled => Self::InternalServerError,
+            dc_exec::Error::StateOverride(err) | dc_exec::Error::HeaderOverride(err) => {
+                Self::ErrUnexpectedError { data: format!("{:#}", err) }
+            }
+            dc_exec::Error::Storage(err) => {
+                log::error!(target: "rpc_errors", "Storage error during execution: {:#}", err);
+                Self::InternalServerError
+            }
+            dc_exec::Error::BlockNotFound(_) => Self::BlockNotFound,
+            dc_exec::Error::TransactionNotFound(_) => Self::TxnHashNotFound,
+            dc_exec::Error::ClassNotFound(class_hash) => {
+                log::error!(target: "rpc_errors", "Failed to retrieve class {class_hash:#x}");
+                Self::ContractNotFound
+            }
+            err @ (dc_exec::Error::TransactionConversion { .. }
+            | dc_exec::Error::BlockStateDiff(_)
+            | dc_exec::Error::InvalidReplayLog(_)) => {
+                log::error!(target: "rpc_errors", "{err:#}");
+                Self::InternalServerError
+            }
+        }
     }
 }
 
@@ -148,7 +241,9 @@ impl From<StarknetTransactionExecutionError> for StarknetRpcApiError {
             StarknetTransactionExecutionError::ClassAlreadyDeclared => StarknetRpcApiError::ClassAlreadyDeclared,
             StarknetTransactionExecutionError::ClassHashNotFound => StarknetRpcApiError::ClassHashNotFound,
             StarknetTransactionExecutionError::InvalidContractClass => StarknetRpcApiError::InvalidContractClass,
-            StarknetTransactionExecutionError::ContractError => StarknetRpcApiError::ContractError,
+            StarknetTransactionExecutionError::ContractError => {
+                StarknetRpcApiError::ContractError { revert_error: String::new() }
+            }
         }
     }
 }
@@ -172,12 +267,14 @@ impl From<StarknetError> for StarknetRpcApiError {
             StarknetError::NoBlocks => StarknetRpcApiError::NoBlocks,
             StarknetError::InvalidContinuationToken => StarknetRpcApiError::InvalidContinuationToken,
             StarknetError::TooManyKeysInFilter => StarknetRpcApiError::TooManyKeysInFilter,
-            StarknetError::ContractError(_) => StarknetRpcApiError::ContractError,
+            StarknetError::ContractError(data) => {
+                StarknetRpcApiError::ContractError { revert_error: data.revert_error }
+            }
             StarknetError::ClassAlreadyDeclared => StarknetRpcApiError::ClassAlreadyDeclared,
             StarknetError::InvalidTransactionNonce => StarknetRpcApiError::InvalidTxnNonce,
             StarknetError::InsufficientMaxFee => StarknetRpcApiError::InsufficientMaxFee,
             StarknetError::InsufficientAccountBalance => StarknetRpcApiError::InsufficientAccountBalance,
-            StarknetError::ValidationFailure(_) => StarknetRpcApiError::ValidationFailure,
+            StarknetError::ValidationFailure(error) => StarknetRpcApiError::ValidationFailure { error },
             StarknetError::CompilationFailed => StarknetRpcApiError::CompilationFailed,
             StarknetError::ContractClassSizeIsTooLarge => StarknetRpcApiError::ContractClassSizeTooLarge,
             StarknetError::NonAccount => StarknetRpcApiError::NonAccount,
@@ -195,6 +292,27 @@ impl From<StarknetError> for StarknetRpcApiError {
     }
 }
 
+impl From<dc_devnet::DevnetError> for StarknetRpcApiError {
+    fn from(err: dc_devnet::DevnetError) -> Self {
+        match err {
+            dc_devnet::DevnetError::Execution(err) => err.into(),
+            dc_devnet::DevnetError::Storage(err) => err.into(),
+            dc_devnet::DevnetError::NoParentBlock => Self::NoBlocks,
+            dc_devnet::DevnetError::Class(err) => {
+                log::debug!(target: "rpc_errors", "{err:#}");
+                Self::CompilationFailed
+            }
+            err @ (dc_devnet::DevnetError::InvalidTransaction(_)
+            | dc_devnet::DevnetError::BalanceOverflow(_)
+            | dc_devnet::DevnetError::TimestampOverflow(_)) => Self::ErrUnexpectedError { data: format!("{err:#}") },
+            err @ dc_devnet::DevnetError::Task(_) => {
+                log::error!(target: "rpc_errors", "{err:#}");
+                Self::InternalServerError
+            }
+        }
+    }
+}
+
 impl From<DeoxysStorageError> for StarknetRpcApiError {
     fn from(_: DeoxysStorageError) -> Self {
         StarknetRpcApiError::ErrUnexpectedError { data: "DB error".to_string() }
@@ -206,3 +324,58 @@ impl From<StarknetApiError> for StarknetRpcApiError {
         StarknetRpcApiError::ErrUnexpectedError { data: err.to_string() }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_code_round_trip() {
+        let errors = [
+            StarknetRpcApiError::FailedToReceiveTxn,
+            StarknetRpcApiError::ContractNotFound,
+            StarknetRpcApiError::BlockNotFound,
+            StarknetRpcApiError::InvalidTxnHash,
+            StarknetRpcApiError::InvalidBlockHash,
+            StarknetRpcApiError::InvalidTxnIndex,
+            StarknetRpcApiError::ClassHashNotFound,
+            StarknetRpcApiError::TxnHashNotFound,
+            StarknetRpcApiError::PageSizeTooBig,
+            StarknetRpcApiError::NoBlocks,
+            StarknetRpcApiError::InvalidContinuationToken,
+            StarknetRpcApiError::TooManyKeysInFilter,
+            StarknetRpcApiError::FailedToFetchPendingTransactions,
+            StarknetRpcApiError::ContractError { revert_error: String::new() },
+            StarknetRpcApiError::TxnExecutionError { tx_index: 0, error: String::new() },
+            StarknetRpcApiError::InvalidContractClass,
+            StarknetRpcApiError::ClassAlreadyDeclared,
+            StarknetRpcApiError::InvalidTxnNonce,
+            StarknetRpcApiError::InsufficientMaxFee,
+            StarknetRpcApiError::InsufficientAccountBalance,
+            StarknetRpcApiError::ValidationFailure { error: String::new() },
+            StarknetRpcApiError::CompilationFailed,
+            StarknetRpcApiError::ContractClassSizeTooLarge,
+            StarknetRpcApiError::NonAccount,
+            StarknetRpcApiError::DuplicateTxn,
+            StarknetRpcApiError::CompiledClassHashMismatch,
+            StarknetRpcApiError::UnsupportedTxnVersion,
+            StarknetRpcApiError::UnsupportedContractClassVersion,
+            StarknetRpcApiError::ErrUnexpectedError { data: String::new() },
+            StarknetRpcApiError::InternalServerError,
+            StarknetRpcApiError::UnimplementedMethod,
+            StarknetRpcApiError::ProofLimitExceeded,
+            StarknetRpcApiError::ProofMissing,
+            StarknetRpcApiError::TooManyBlocksBack,
+            StarknetRpcApiError::StorageProofNotSupported,
+            StarknetRpcApiError::StorageBatchLimitExceeded,
+            StarknetRpcApiError::EventsScanLimitExceeded,
+            StarknetRpcApiError::ExecutionBusy,
+        ];
+        for err in errors {
+            let code = i32::from(&err);
+            let name = format!("{err:?}").split(|c: char| !c.is_alphanumeric()).next().unwrap().to_owned();
+            let (described, _) = StarknetRpcApiError::describe_code(code).unwrap();
+            assert_eq!(described, name, "code {code}");
+        }
+    }
+}
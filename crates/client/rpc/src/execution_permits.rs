@@ -0,0 +1,32 @@
+//! Bounds the number of execution-heavy calls running at once.
+//!
+//! Calls, fee estimations, simulations and traces run on the rayon pool, which the sync also uses to verify
+//! blocks. Without a bound, a burst of these calls would monopolize the pool and starve the sync.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+#[derive(Clone, Debug)]
+pub struct ExecutionPermits {
+    semaphore: Arc<Semaphore>,
+    /// How long a call waits for a permit before it fails, it fails right away when zero.
+    queue_timeout: Duration,
+}
+
+impl ExecutionPermits {
+    pub fn new(max_concurrent: usize, queue_timeout: Duration) -> Self {
+        Self { semaphore: Arc::new(Semaphore::new(max_concurrent)), queue_timeout }
+    }
+
+    /// Waits for a free permit, returns `None` when there is still none after the queue timeout.
+    pub(crate) async fn acquire(&self) -> Option<OwnedSemaphorePermit> {
+        let semaphore = Arc::clone(&self.semaphore);
+        if self.queue_timeout.is_zero() {
+            return semaphore.try_acquire_owned().ok();
+        }
+        // The semaphore is never closed
+        tokio::time::timeout(self.queue_timeout, semaphore.acquire_owned()).await.ok()?.ok()
+    }
+}
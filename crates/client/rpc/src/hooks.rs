@@ -0,0 +1,110 @@
+//! Hooks called around every RPC call, for the embedders of this crate.
+//!
+//! A hook can inspect and modify a request before it is answered, refuse it, and inspect its response along
+//! with the time it took. Hooks are registered in an [`RpcHooks`], and are applied to the methods of an rpc
+//! module by adding an [`RpcHooksLayer`] to the rpc middleware of the server:
+//!
+//! ```ignore
+//! let hooks = RpcHooks::default().with_hook(AuditLog::new());
+//! let rpc_middleware = RpcServiceBuilder::new().layer(RpcHooksLayer::new(hooks));
+//! ```
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use futures::future::{BoxFuture, FutureExt};
+use jsonrpsee::server::middleware::rpc::RpcServiceT;
+use jsonrpsee::types::{ErrorObjectOwned, Request};
+use jsonrpsee::MethodResponse;
+
+pub trait RpcHook: Send + Sync + 'static {
+    /// Called before the call is answered. The request can be modified, and returning an error answers the
+    /// call with it without calling the method.
+    fn on_request(&self, _req: &mut Request<'_>) -> Result<(), ErrorObjectOwned> {
+        Ok(())
+    }
+
+    /// Called once the call has been answered, with the time it took. Not called for the calls refused by a
+    /// hook.
+    fn on_response(&self, _req: &Request<'_>, _response: &MethodResponse, _elapsed: Duration) {}
+}
+
+/// The hooks of a server, called in the order they were registered.
+#[derive(Clone, Default)]
+pub struct RpcHooks(Arc<Vec<Arc<dyn RpcHook>>>);
+
+impl RpcHooks {
+    pub fn with_hook(self, hook: impl RpcHook) -> Self {
+        let mut hooks = Vec::clone(&self.0);
+        hooks.push(Arc::new(hook));
+        Self(Arc::new(hooks))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl std::fmt::Debug for RpcHooks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RpcHooks").field("len", &self.0.len()).finish()
+    }
+}
+
+/// Rpc middleware layer calling the hooks.
+#[derive(Debug, Clone)]
+pub struct RpcHooksLayer {
+    hooks: RpcHooks,
+}
+
+impl RpcHooksLayer {
+    pub fn new(hooks: RpcHooks) -> Self {
+        Self { hooks }
+    }
+}
+
+impl<S> tower::Layer<S> for RpcHooksLayer {
+    type Service = RpcHooksService<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        RpcHooksService { service, hooks: self.hooks.clone() }
+    }
+}
+
+pub struct RpcHooksService<S> {
+    service: S,
+    hooks: RpcHooks,
+}
+
+impl<'a, S> RpcServiceT<'a> for RpcHooksService<S>
+where
+    S: Send + Sync + RpcServiceT<'a> + Clone + 'static,
+{
+    type Future = BoxFuture<'a, MethodResponse>;
+
+    fn call(&self, mut req: Request<'a>) -> Self::Future {
+        let service = self.service.clone();
+        let hooks = self.hooks.clone();
+
+        async move {
+            if hooks.is_empty() {
+                return service.call(req).await;
+            }
+
+            for hook in hooks.0.iter() {
+                if let Err(err) = hook.on_request(&mut req) {
+                    return MethodResponse::error(req.id, err);
+                }
+            }
+
+            let now = Instant::now();
+            let rp = service.call(req.clone()).await;
+            let elapsed = now.elapsed();
+
+            for hook in hooks.0.iter() {
+                hook.on_response(&req, &rp, elapsed);
+            }
+            rp
+        }
+        .boxed()
+    }
+}
@@ -2,31 +2,57 @@
 //!
 //! It uses the deoxys client and backend in order to answer queries.
 
-mod constants;
+pub mod constants;
 mod errors;
+pub mod execution_permits;
+pub mod hooks;
 mod methods;
+pub mod metrics;
+pub mod openrpc;
+pub mod timings;
 mod types;
 pub mod utils;
+pub mod versions;
 
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use constants::{DEFAULT_EVENTS_MAX_BLOCKS_SCANNED, MAX_EVENTS_CHUNK_SIZE};
 use dc_db::db_block_id::DbBlockIdResolvable;
 use dc_db::DeoxysBackend;
-use dp_block::{DeoxysMaybePendingBlock, DeoxysMaybePendingBlockInfo};
+use dc_devnet::Devnet;
+use dc_exec::{
+    CancellationToken, ExecutionCache, ExecutionContext, ExecutionContextPool, ExecutionLimits, ExecutionMetrics,
+    FeeMargin, StateCache,
+};
+use dp_block::{BlockId, DeoxysMaybePendingBlock, DeoxysMaybePendingBlockInfo};
+use dp_utils::PauseHandle;
+pub use errors::{ErrorOrigin, StarknetRpcApiError};
 use errors::{StarknetRpcApiError, StarknetRpcResult};
-use jsonrpsee::core::RpcResult;
+use execution_permits::ExecutionPermits;
+use jsonrpsee::core::{RpcResult, SubscriptionResult};
 use jsonrpsee::proc_macros::rpc;
+use methods::v0_6::types as v0_6;
+use metrics::{SubscriptionGuard, SubscriptionMetrics};
 use starknet_core::types::Felt;
 use starknet_core::types::{
-    BlockHashAndNumber, BlockId, BroadcastedDeclareTransaction, BroadcastedDeployAccountTransaction,
+    BlockHashAndNumber, BroadcastedDeclareTransaction, BroadcastedDeployAccountTransaction,
     BroadcastedInvokeTransaction, BroadcastedTransaction, ContractClass, DeclareTransactionResult,
-    DeployAccountTransactionResult, EventFilterWithPage, EventsPage, FeeEstimate, FunctionCall,
+    DeployAccountTransactionResult, EmittedEvent, EventFilterWithPage, EventsPage, FeeEstimate, FunctionCall, Hash256,
     InvokeTransactionResult, MaybePendingBlockWithReceipts, MaybePendingBlockWithTxHashes, MaybePendingBlockWithTxs,
-    MaybePendingStateUpdate, MsgFromL1, SimulatedTransaction, SimulationFlag, SimulationFlagForEstimateFee,
-    SyncStatusType, Transaction, TransactionReceiptWithBlockInfo, TransactionStatus, TransactionTraceWithHash,
+    MaybePendingStateUpdate, MsgFromL1, PriceUnit, ResourceBoundsMapping, SimulatedTransaction, SimulationFlag,
+    SimulationFlagForEstimateFee, SyncStatusType, Transaction, TransactionReceiptWithBlockInfo, TransactionStatus,
+    TransactionTraceWithHash,
 };
 use starknet_providers::{SequencerGatewayProvider, Url};
+use timings::CallTimings;
+use types::{
+    AccountState, BlockHeader, BlockOverrides, CallWithResources, ContractStateOverride, ContractStorageKeys,
+    GetProofResult, MessageStatus, StorageProof, SyncStatusUpdate, TransactionSyscallTrace,
+};
 use utils::ResultExt;
+pub use versions::RpcVersion;
 
 // Starknet RPC API trait and types
 //
@@ -73,9 +99,14 @@ pub trait StarknetReadRpcApi {
     #[method(name = "blockHashAndNumber")]
     fn block_hash_and_number(&self) -> RpcResult<BlockHashAndNumber>;
 
-    /// Call a contract function at a given block id
+    /// Call a contract function at a given block id, optionally on top of state overrides
     #[method(name = "call")]
-    fn call(&self, request: FunctionCall, block_id: BlockId) -> RpcResult<Vec<Felt>>;
+    async fn call(
+        &self,
+        request: FunctionCall,
+        block_id: BlockId,
+        state_overrides: Option<HashMap<Felt, ContractStateOverride>>,
+    ) -> RpcResult<Vec<Felt>>;
 
     /// Get the chain id
     #[method(name = "chainId")]
@@ -92,6 +123,7 @@ pub trait StarknetReadRpcApi {
         request: Vec<BroadcastedTransaction>,
         simulation_flags: Vec<SimulationFlagForEstimateFee>,
         block_id: BlockId,
+        block_overrides: Option<BlockOverrides>,
     ) -> RpcResult<Vec<FeeEstimate>>;
 
     /// Estimate the L2 fee of a message sent on L1
@@ -123,6 +155,10 @@ pub trait StarknetReadRpcApi {
     #[method(name = "getClass")]
     fn get_class(&self, block_id: BlockId, class_hash: Felt) -> RpcResult<ContractClass>;
 
+    /// Get the CASM the node compiled from the Sierra class associated with the given hash
+    #[method(name = "getCompiledCasm")]
+    fn get_compiled_casm(&self, class_hash: Felt) -> RpcResult<serde_json::Value>;
+
     /// Returns all events matching the given filter
     #[method(name = "getEvents")]
     async fn get_events(&self, filter: EventFilterWithPage) -> RpcResult<EventsPage>;
@@ -149,7 +185,11 @@ pub trait StarknetReadRpcApi {
 
     /// Gets the Transaction Status, Including Mempool Status and Execution Details
     #[method(name = "getTransactionStatus")]
-    fn get_transaction_status(&self, transaction_hash: Felt) -> RpcResult<TransactionStatus>;
+    async fn get_transaction_status(&self, transaction_hash: Felt) -> RpcResult<TransactionStatus>;
+
+    /// Gets the status of the L1 -> L2 messages sent by an L1 transaction
+    #[method(name = "getMessagesStatus")]
+    fn get_messages_status(&self, transaction_hash: Hash256) -> RpcResult<Vec<MessageStatus>>;
 
     /// Get an object about the sync status, or false if the node is not syncing
     #[method(name = "syncing")]
@@ -169,6 +209,7 @@ pub trait StarknetTraceRpcApi {
         block_id: BlockId,
         transactions: Vec<BroadcastedTransaction>,
         simulation_flags: Vec<SimulationFlag>,
+        block_overrides: Option<BlockOverrides>,
     ) -> RpcResult<Vec<SimulatedTransaction>>;
 
     #[method(name = "traceBlockTransactions")]
@@ -180,6 +221,224 @@ pub trait StarknetTraceRpcApi {
     async fn trace_transaction(&self, transaction_hash: Felt) -> RpcResult<TransactionTraceWithHash>;
 }
 
+/// Starknet read rpc interface, for the methods whose shapes differ in the JSON-RPC specification 0.6. Only served
+/// on the 0.6 endpoint, in place of the 0.7 methods of the same name.
+#[rpc(server, namespace = "starknet")]
+pub trait StarknetV0_6ReadRpcApi {
+    /// Estimate the fee associated with transaction
+    #[method(name = "estimateFee")]
+    async fn estimate_fee(
+        &self,
+        request: Vec<BroadcastedTransaction>,
+        simulation_flags: Vec<SimulationFlagForEstimateFee>,
+        block_id: BlockId,
+        block_overrides: Option<BlockOverrides>,
+    ) -> RpcResult<Vec<v0_6::FeeEstimate>>;
+
+    /// Estimate the L2 fee of a message sent on L1
+    #[method(name = "estimateMessageFee")]
+    async fn estimate_message_fee(&self, message: MsgFromL1, block_id: BlockId) -> RpcResult<v0_6::FeeEstimate>;
+
+    /// Get block information with transaction hashes given the block id
+    #[method(name = "getBlockWithTxHashes")]
+    fn get_block_with_tx_hashes(&self, block_id: BlockId) -> RpcResult<v0_6::MaybePendingBlock<Felt>>;
+
+    /// Get block information with full transactions given the block id
+    #[method(name = "getBlockWithTxs")]
+    fn get_block_with_txs(&self, block_id: BlockId) -> RpcResult<v0_6::MaybePendingBlock<Transaction>>;
+
+    /// Returns the receipt of a transaction by transaction hash.
+    #[method(name = "getTransactionReceipt")]
+    async fn get_transaction_receipt(&self, transaction_hash: Felt) -> RpcResult<v0_6::TransactionReceipt>;
+}
+
+/// Starknet trace rpc interface, for the methods whose shapes differ in the JSON-RPC specification 0.6. Only served
+/// on the 0.6 endpoint, in place of the 0.7 methods of the same name.
+#[rpc(server, namespace = "starknet")]
+pub trait StarknetV0_6TraceRpcApi {
+    /// Returns the execution trace of a transaction by simulating it in the runtime.
+    #[method(name = "simulateTransactions")]
+    async fn simulate_transactions(
+        &self,
+        block_id: BlockId,
+        transactions: Vec<BroadcastedTransaction>,
+        simulation_flags: Vec<SimulationFlag>,
+        block_overrides: Option<BlockOverrides>,
+    ) -> RpcResult<Vec<v0_6::SimulatedTransaction>>;
+
+    #[method(name = "traceBlockTransactions")]
+    /// Returns the execution traces of all transactions included in the given block
+    async fn trace_block_transactions(&self, block_id: BlockId) -> RpcResult<Vec<v0_6::TransactionTraceWithHash>>;
+
+    #[method(name = "traceTransaction")]
+    /// Returns the execution trace of a transaction
+    async fn trace_transaction(&self, transaction_hash: Felt) -> RpcResult<v0_6::TransactionTraceWithHash>;
+}
+
+/// Starknet websocket rpc interface. Subscriptions are only available when connecting over websocket.
+///
+/// The unsubscribe methods declared here are not served: they are replaced by a single `starknet_unsubscribe`
+/// method, which ends any of these subscriptions by its id as in the specification.
+#[rpc(server, namespace = "starknet")]
+pub trait StarknetWsRpcApi {
+    /// Subscribe to the headers of the new blocks. When a block id is given, the headers of the blocks since this
+    /// block are sent first.
+    #[subscription(
+        name = "subscribeNewHeads" => "subscriptionNewHeads",
+        unsubscribe = "unsubscribeNewHeads",
+        item = BlockHeader
+    )]
+    async fn subscribe_new_heads(&self, block_id: Option<BlockId>) -> SubscriptionResult;
+
+    /// Subscribe to the events matching a filter, with the same semantics as `starknet_getEvents`. When a block id is
+    /// given, the matching events since this block are replayed first, however far in the past it is. The events of
+    /// the pending block are sent as well when `pending` is true, in which case they are sent again once their block
+    /// is closed.
+    #[subscription(
+        name = "subscribeEvents" => "subscriptionEvents",
+        unsubscribe = "unsubscribeEvents",
+        item = EmittedEvent
+    )]
+    async fn subscribe_events(
+        &self,
+        from_address: Option<Felt>,
+        keys: Option<Vec<Vec<Felt>>>,
+        block_id: Option<BlockId>,
+        pending: Option<bool>,
+    ) -> SubscriptionResult;
+}
+
+/// Starknet read rpc interface, for the methods added by the JSON-RPC specification 0.8. The 0.8 endpoint is not
+/// served yet, so they are served on every endpoint as an extension, like the subscriptions.
+#[rpc(server, namespace = "starknet")]
+pub trait StarknetV0_8ReadRpcApi {
+    /// Returns the merkle proofs of classes, contracts and contract storage keys, along with the global roots.
+    /// Only the latest block can be proven.
+    #[method(name = "getStorageProof")]
+    fn get_storage_proof(
+        &self,
+        block_id: BlockId,
+        class_hashes: Option<Vec<Felt>>,
+        contract_addresses: Option<Vec<Felt>>,
+        contracts_storage_keys: Option<Vec<ContractStorageKeys>>,
+    ) -> RpcResult<StorageProof>;
+}
+
+/// Pathfinder extension rpc interface, for compatibility with the tooling built around it.
+#[rpc(server, namespace = "pathfinder")]
+pub trait PathfinderRpcApi {
+    /// Returns the merkle proofs of a contract and of some of its storage keys. Only the latest block can be
+    /// proven.
+    #[method(name = "getProof")]
+    fn get_proof(&self, block_id: BlockId, contract_address: Felt, keys: Vec<Felt>) -> RpcResult<GetProofResult>;
+}
+
+/// Deoxys extension rpc interface, for the methods specific to this node.
+#[rpc(server, namespace = "deoxys")]
+pub trait DeoxysRpcApi {
+    /// Returns the values of many storage keys of a contract, all read from the same state.
+    #[method(name = "getStorageBatch")]
+    fn get_storage_batch(&self, contract_address: Felt, keys: Vec<Felt>, block_id: BlockId) -> RpcResult<Vec<Felt>>;
+
+    /// Returns the values of many storage keys of several contracts, all read from the same state.
+    #[method(name = "getContractsStorageBatch")]
+    fn get_contracts_storage_batch(
+        &self,
+        contracts: Vec<ContractStorageKeys>,
+        block_id: BlockId,
+    ) -> RpcResult<Vec<Vec<Felt>>>;
+
+    /// Calls a contract like `starknet_call`, and returns the resources used by the call along with its result. This
+    /// is meant to profile view functions against the state of a block.
+    #[method(name = "callWithResources")]
+    async fn call_with_resources(&self, request: FunctionCall, block_id: BlockId) -> RpcResult<CallWithResources>;
+
+    /// Estimates the lowest L1 gas bounds v3 transactions succeed with, by executing them again with lower and lower
+    /// bounds. Where `starknet_estimateFee` returns an upper bound of the resources, this returns tight bounds.
+    #[method(name = "estimateResourceBounds")]
+    async fn estimate_resource_bounds(
+        &self,
+        request: Vec<BroadcastedTransaction>,
+        simulation_flags: Vec<SimulationFlagForEstimateFee>,
+        block_id: BlockId,
+    ) -> RpcResult<Vec<ResourceBoundsMapping>>;
+
+    /// Returns the class hash and nonce of an account, along with the values of some of its storage keys.
+    #[method(name = "getAccountState")]
+    fn get_account_state(
+        &self,
+        contract_address: Felt,
+        block_id: BlockId,
+        storage_keys: Option<Vec<Felt>>,
+    ) -> RpcResult<AccountState>;
+
+    /// Subscribe to the sync status of the node: the current and highest known blocks, the sync speed and the
+    /// reorgs of the stored chain. Only available when connecting over websocket.
+    #[subscription(
+        name = "subscribeSyncStatus" => "subscriptionSyncStatus",
+        unsubscribe = "unsubscribeSyncStatus",
+        item = SyncStatusUpdate
+    )]
+    async fn subscribe_sync_status(&self) -> SubscriptionResult;
+}
+
+/// Deoxys specific rpc methods which execute transactions.
+#[rpc(server, namespace = "deoxys")]
+pub trait DeoxysTraceRpcApi {
+    /// Returns the syscalls made by a transaction, by re-executing it on top of the transactions before it in its
+    /// block. This is a lower level trace than `starknet_traceTransaction`, meant for debuggers and profilers.
+    #[method(name = "traceTransactionSyscalls")]
+    async fn trace_transaction_syscalls(&self, transaction_hash: Felt) -> RpcResult<TransactionSyscallTrace>;
+}
+
+/// Node administration rpc interface. These methods are never exposed with `--rpc-methods safe`.
+#[rpc(server, namespace = "admin")]
+pub trait AdminRpcApi {
+    /// Pause the L1 and L2 sync, once the block or L1 state update currently being imported has
+    /// been stored. Returns false if the sync was already paused.
+    #[method(name = "pauseSync")]
+    async fn pause_sync(&self) -> RpcResult<bool>;
+
+    /// Resume the sync service. Returns false if the sync was not paused.
+    #[method(name = "resumeSync")]
+    async fn resume_sync(&self) -> RpcResult<bool>;
+
+    /// Returns whether the sync service is paused
+    #[method(name = "isSyncPaused")]
+    async fn is_sync_paused(&self) -> RpcResult<bool>;
+}
+
+/// Devnet rpc interface, only served in devnet mode. These methods are never exposed with `--rpc-methods safe`.
+#[rpc(server, namespace = "dev")]
+pub trait DevRpcApi {
+    /// Credits an amount of fee token to an address, in the pending block. The unit selects the token: ETH for
+    /// `WEI`, the default, and STRK for `FRI`.
+    #[method(name = "mint")]
+    async fn mint(&self, address: Felt, amount: Felt, unit: Option<PriceUnit>) -> RpcResult<()>;
+
+    /// Closes the pending block and mines `n_blocks - 1` empty blocks after it, one by default. Returns the hash of
+    /// the last block mined.
+    #[method(name = "mine")]
+    async fn mine(&self, n_blocks: Option<u64>) -> RpcResult<Felt>;
+
+    /// Whether a block is mined for each transaction, instead of on the block time or by `dev_mine`.
+    #[method(name = "setAutoMine")]
+    async fn set_auto_mine(&self, auto_mine: bool) -> RpcResult<()>;
+
+    /// Sets the timestamp of the next block, the following blocks advance from it with the wall clock.
+    #[method(name = "setBlockTimestamp")]
+    async fn set_block_timestamp(&self, timestamp: u64) -> RpcResult<()>;
+
+    /// Executes the transactions of an account without calling its validation entry point, so that they need no
+    /// valid signature. Returns false if the account was already impersonated.
+    #[method(name = "impersonateAccount")]
+    async fn impersonate_account(&self, address: Felt) -> RpcResult<bool>;
+
+    /// Returns false if the account was not impersonated.
+    #[method(name = "stopImpersonatingAccount")]
+    async fn stop_impersonating_account(&self, address: Felt) -> RpcResult<bool>;
+}
+
 #[derive(Clone)]
 pub struct ChainConfig {
     pub chain_id: starknet_types_core::felt::Felt,
@@ -187,12 +446,65 @@ pub struct ChainConfig {
     pub gateway: Url,
 }
 
+/// Number of transaction execution results kept in memory by default.
+pub const DEFAULT_EXECUTION_CACHE_SIZE: usize = 1024;
+
+/// Number of blocks whose state reads are kept in memory by default.
+pub const DEFAULT_STATE_CACHE_SIZE: usize = 16;
+
+/// How long the execution context of a block is kept for the next executions on top of it by default.
+pub const DEFAULT_EXECUTION_CONTEXT_TTL: Duration = Duration::from_secs(2);
+
 /// A Starknet RPC server for Deoxys
+#[derive(Clone)]
 pub struct Starknet {
     backend: Arc<DeoxysBackend>,
     sequencer_provider: Arc<SequencerGatewayProvider>,
+    execution_cache: Arc<ExecutionCache>,
+    state_cache: Arc<StateCache>,
+    execution_contexts: Arc<ExecutionContextPool>,
+    execution_context_ttl: Duration,
+    execution_limits: ExecutionLimits,
+    fee_margin: FeeMargin,
+    /// Whether the fee estimations and simulations charge the fee, whatever their simulation flags.
+    enforce_fee_charge: bool,
+    execution_metrics: Option<ExecutionMetrics>,
+    subscription_metrics: Option<SubscriptionMetrics>,
+    version: RpcVersion,
+    /// Largest page of `starknet_getEvents`.
+    events_max_chunk_size: usize,
+    /// Maximum number of blocks a single `starknet_getEvents` call can scan, unbounded when `None`.
+    events_max_blocks_scanned: Option<u64>,
+    /// Shared by the clones, bounds the execution-heavy calls running at once. Unbounded when `None`.
+    execution_permits: Option<ExecutionPermits>,
+    /// Set on the clone running an execution, cancelled when its call is dropped.
+    cancellation: Option<CancellationToken>,
     starting_block: u64,
     chain_config: ChainConfig,
+    /// In devnet mode, the transactions are added to the local pending block instead of being sent to the gateway.
+    devnet: Option<Devnet>,
+}
+
+/// Deoxys admin RPC server
+pub struct Admin {
+    sync_pause: PauseHandle,
+}
+
+impl Admin {
+    pub fn new(sync_pause: PauseHandle) -> Self {
+        Self { sync_pause }
+    }
+}
+
+/// Deoxys devnet RPC server
+pub struct Dev {
+    devnet: Devnet,
+}
+
+impl Dev {
+    pub fn new(devnet: Devnet) -> Self {
+        Self { devnet }
+    }
 }
 
 impl Starknet {
@@ -205,7 +517,112 @@ impl Starknet {
                 chain_config.feeder_gateway.clone(),
                 chain_config.chain_id,
             )),
+            execution_cache: Arc::new(ExecutionCache::new(DEFAULT_EXECUTION_CACHE_SIZE)),
+            state_cache: Arc::new(StateCache::new(DEFAULT_STATE_CACHE_SIZE)),
+            execution_contexts: Arc::new(ExecutionContextPool::new(
+                DEFAULT_EXECUTION_CONTEXT_TTL,
+                ExecutionLimits::default(),
+            )),
+            execution_context_ttl: DEFAULT_EXECUTION_CONTEXT_TTL,
+            execution_limits: ExecutionLimits::default(),
+            fee_margin: FeeMargin::default(),
+            enforce_fee_charge: false,
+            execution_metrics: None,
+            subscription_metrics: None,
+            version: RpcVersion::LATEST,
+            events_max_chunk_size: MAX_EVENTS_CHUNK_SIZE,
+            events_max_blocks_scanned: Some(DEFAULT_EVENTS_MAX_BLOCKS_SCANNED),
+            execution_permits: None,
+            cancellation: None,
             chain_config,
+            devnet: None,
+        }
+    }
+
+    /// Sets the number of transaction execution results kept in memory. Zero disables the execution cache.
+    pub fn with_execution_cache_size(mut self, size: usize) -> Self {
+        self.execution_cache = Arc::new(ExecutionCache::new(size));
+        self
+    }
+
+    /// Sets the number of blocks whose state reads are kept in memory, so that the executions on top of the same
+    /// block do not read the same values and classes again. Zero disables the state cache.
+    pub fn with_state_cache_size(mut self, size: usize) -> Self {
+        self.state_cache = Arc::new(StateCache::new(size));
+        self
+    }
+
+    /// Sets the limits of the executions triggered by the rpc calls, the versioned constants of the blocks are used
+    /// by default.
+    pub fn with_execution_limits(mut self, limits: ExecutionLimits) -> Self {
+        self.execution_limits = limits;
+        self.execution_contexts = Arc::new(ExecutionContextPool::new(self.execution_context_ttl, limits));
+        self
+    }
+
+    /// Sets how long the execution context of a block is kept, so that bursts of calls on top of the same block do
+    /// not build it again. Zero disables the pool.
+    pub fn with_execution_context_ttl(mut self, ttl: Duration) -> Self {
+        self.execution_context_ttl = ttl;
+        self.execution_contexts = Arc::new(ExecutionContextPool::new(ttl, self.execution_limits));
+        self
+    }
+
+    /// Sets the margin added to the resources of the fee estimates, none by default.
+    pub fn with_fee_margin(mut self, fee_margin: FeeMargin) -> Self {
+        self.fee_margin = fee_margin;
+        self
+    }
+
+    /// Charges the fee in the fee estimations and simulations, as the sequencer does when it accepts a transaction:
+    /// the balance of the senders must cover the max fee or the resource bounds of their transactions. The
+    /// `SKIP_FEE_CHARGE` simulation flag is then ignored. Disabled by default.
+    pub fn with_enforce_fee_charge(mut self, enforce_fee_charge: bool) -> Self {
+        self.enforce_fee_charge = enforce_fee_charge;
+        self
+    }
+
+    /// Sets the specification version served by this instance.
+    pub fn with_version(mut self, version: RpcVersion) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Sets the largest page of `starknet_getEvents`, and the maximum number of blocks a single call can scan.
+    pub fn with_events_limits(mut self, max_chunk_size: usize, max_blocks_scanned: Option<u64>) -> Self {
+        self.events_max_chunk_size = max_chunk_size;
+        self.events_max_blocks_scanned = max_blocks_scanned;
+        self
+    }
+
+    pub fn with_execution_permits(mut self, permits: ExecutionPermits) -> Self {
+        self.execution_permits = Some(permits);
+        self
+    }
+
+    pub fn with_execution_metrics(mut self, metrics: ExecutionMetrics) -> Self {
+        self.execution_metrics = Some(metrics);
+        self
+    }
+
+    pub fn with_subscription_metrics(mut self, metrics: SubscriptionMetrics) -> Self {
+        self.subscription_metrics = Some(metrics);
+        self
+    }
+
+    /// Adds the submitted transactions to the pending block of `devnet` instead of sending them to the gateway.
+    pub fn with_devnet(mut self, devnet: Devnet) -> Self {
+        self.devnet = Some(devnet);
+        self
+    }
+
+    pub(crate) fn subscription_started(&self, subscription: &'static str) -> Option<SubscriptionGuard> {
+        self.subscription_metrics.as_ref().map(|metrics| metrics.subscription_started(subscription))
+    }
+
+    pub(crate) fn subscription_lagged(&self, subscription: &'static str, skipped: u64) {
+        if let Some(metrics) = &self.subscription_metrics {
+            metrics.notifications_dropped(subscription, skipped);
         }
     }
 
@@ -217,6 +634,34 @@ impl Starknet {
         &self.sequencer_provider
     }
 
+    /// Largest page of `starknet_getEvents`.
+    pub fn events_max_chunk_size(&self) -> usize {
+        self.events_max_chunk_size
+    }
+
+    /// Set in devnet mode.
+    pub(crate) fn devnet(&self) -> Option<&Devnet> {
+        self.devnet.as_ref()
+    }
+
+    /// Context to execute on top of a block, with the caches, limits, fee margin and metrics of this instance.
+    pub(crate) fn execution_context(
+        &self,
+        block_info: &DeoxysMaybePendingBlockInfo,
+    ) -> StarknetRpcResult<ExecutionContext<'_>> {
+        let exec_context = ExecutionContext::pooled(&self.execution_contexts, &self.backend, block_info)?
+            .with_state_cache(&self.state_cache)
+            .with_fee_margin(self.fee_margin);
+        let exec_context = match &self.execution_metrics {
+            Some(metrics) => exec_context.with_metrics(metrics.clone()),
+            None => exec_context,
+        };
+        Ok(match &self.cancellation {
+            Some(cancellation) => exec_context.with_cancellation(cancellation.clone()),
+            None => exec_context,
+        })
+    }
+
     pub fn get_block_info(
         &self,
         block_id: &impl DbBlockIdResolvable,
@@ -234,6 +679,23 @@ impl Starknet {
             .ok_or(StarknetRpcApiError::BlockNotFound)
     }
 
+    /// Whether the stored pending block is built on top of the latest block. The pending block lags behind
+    /// the latest block until the next one is fetched, and its state is outdated until then.
+    pub fn is_pending_on_latest(&self) -> StarknetRpcResult<bool> {
+        let pending_info = self
+            .backend
+            .get_block_info(&starknet_core::types::BlockId::Tag(starknet_core::types::BlockTag::Pending))
+            .or_internal_server_error("Error getting pending block info")?;
+        let Some(pending_info) = pending_info.as_ref().and_then(DeoxysMaybePendingBlockInfo::as_pending) else {
+            return Ok(false);
+        };
+        let latest_block_hash = self
+            .backend
+            .get_block_hash(&starknet_core::types::BlockId::Tag(starknet_core::types::BlockTag::Latest))
+            .or_internal_server_error("Error getting latest block hash")?;
+        Ok(latest_block_hash == Some(pending_info.header.parent_block_hash))
+    }
+
     pub fn get_block(&self, block_id: &impl DbBlockIdResolvable) -> StarknetRpcResult<DeoxysMaybePendingBlock> {
         self.backend
             .get_block(block_id)
@@ -241,6 +703,37 @@ impl Starknet {
             .ok_or(StarknetRpcApiError::BlockNotFound)
     }
 
+    /// Runs an execution-heavy method on the rayon pool, so that it does not block the async runtime and
+    /// the call can time out.
+    ///
+    /// Waits for an execution permit first, and fails with `ExecutionBusy` when none frees up in time.
+    pub(crate) async fn spawn_execution<R, F>(&self, f: F) -> StarknetRpcResult<R>
+    where
+        F: FnOnce(&Starknet) -> StarknetRpcResult<R> + Send + 'static,
+        R: Send + 'static,
+    {
+        let permit = match &self.execution_permits {
+            Some(permits) => Some(permits.acquire().await.ok_or(StarknetRpcApiError::ExecutionBusy)?),
+            None => None,
+        };
+        // A call which times out is dropped, the execution is then cancelled rather than left running
+        let cancellation = CancellationToken::new();
+        let _cancel_on_drop = cancellation.cancel_on_drop();
+        let starknet = Self { cancellation: Some(cancellation), ..self.clone() };
+        let timings = CallTimings::current();
+        dp_utils::spawn_rayon_task(move || {
+            // Held by the task rather than the call, a timed out call runs until it notices it was cancelled
+            let _permit = permit;
+            let started = Instant::now();
+            let res = f(&starknet);
+            if let Some(timings) = timings {
+                timings.add_exec(started.elapsed());
+            }
+            res
+        })
+        .await
+    }
+
     pub fn chain_id(&self) -> Felt {
         self.chain_config.chain_id
     }
@@ -250,7 +743,7 @@ impl Starknet {
     }
 
     pub fn current_spec_version(&self) -> String {
-        "0.7.1".to_string()
+        self.version.spec_version().to_string()
     }
 
     pub fn get_l1_last_confirmed_block(&self) -> StarknetRpcResult<u64> {
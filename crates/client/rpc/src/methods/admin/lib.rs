@@ -0,0 +1,19 @@
+use jsonrpsee::core::{async_trait, RpcResult};
+
+use super::sync::*;
+use crate::{Admin, AdminRpcApiServer};
+
+#[async_trait]
+impl AdminRpcApiServer for Admin {
+    async fn pause_sync(&self) -> RpcResult<bool> {
+        Ok(pause_sync(self).await)
+    }
+
+    async fn resume_sync(&self) -> RpcResult<bool> {
+        Ok(resume_sync(self).await)
+    }
+
+    async fn is_sync_paused(&self) -> RpcResult<bool> {
+        Ok(self.sync_pause.is_paused().await)
+    }
+}
@@ -0,0 +1,29 @@
+use crate::Admin;
+
+/// Pause the sync service, so that no more blocks are written to the database. This waits for the
+/// block currently being imported to be stored, making it safe to take a backup or a snapshot of
+/// the database once it returns.
+///
+/// # Returns
+///
+/// * `false` if the sync service was already paused
+pub async fn pause_sync(admin: &Admin) -> bool {
+    let paused = admin.sync_pause.pause().await;
+    if paused {
+        log::info!("⏸️  Sync paused");
+    }
+    paused
+}
+
+/// Resume the sync service after a call to `admin_pauseSync`.
+///
+/// # Returns
+///
+/// * `false` if the sync service was not paused
+pub async fn resume_sync(admin: &Admin) -> bool {
+    let resumed = admin.sync_pause.resume().await;
+    if resumed {
+        log::info!("▶️  Sync resumed");
+    }
+    resumed
+}
@@ -0,0 +1,32 @@
+use dp_block::BlockId;
+use starknet_core::types::FunctionCall;
+
+use crate::errors::StarknetRpcResult;
+use crate::types::CallWithResources;
+use crate::Starknet;
+
+/// Calls a contract without creating a transaction, and returns the resources used by the call.
+///
+/// ### Arguments
+///
+/// * `request` - The details of the function call, as for `starknet_call`.
+/// * `block_id` - The block the contract is called on top of.
+///
+/// ### Returns
+///
+/// The result of the call, along with the steps, memory holes and builtin applications of the call and
+/// all its inner calls. Returns the same errors as `starknet_call`.
+pub fn call_with_resources(
+    starknet: &Starknet,
+    request: FunctionCall,
+    block_id: BlockId,
+) -> StarknetRpcResult<CallWithResources> {
+    let block_info = starknet.get_block_info(&block_id)?;
+    let exec_context = starknet.execution_context(&block_info)?;
+
+    let FunctionCall { contract_address, entry_point_selector, calldata } = request;
+    let (result, resources) =
+        exec_context.call_contract_with_resources(&contract_address, &entry_point_selector, &calldata)?;
+
+    Ok(CallWithResources { result, execution_resources: resources.into() })
+}
@@ -0,0 +1,61 @@
+use dc_exec::TightBounds;
+use dp_block::BlockId;
+use dp_transactions::broadcasted_to_blockifier;
+use starknet_core::types::{
+    BroadcastedTransaction, ResourceBounds, ResourceBoundsMapping, SimulationFlagForEstimateFee,
+};
+
+use crate::errors::{StarknetRpcApiError, StarknetRpcResult};
+use crate::utils::ResultExt;
+use crate::Starknet;
+
+/// Estimates the lowest resource bounds of v3 transactions, rather than an upper bound of their resources as
+/// `starknet_estimateFee` does.
+///
+/// ### Arguments
+///
+/// * `request` - The transactions, executed one after the other as for `starknet_estimateFee`.
+/// * `simulation_flags` - As for `starknet_estimateFee`.
+/// * `block_id` - The block the transactions are executed on top of.
+///
+/// ### Returns
+///
+/// The lowest L1 gas `max_amount` each transaction succeeds with, found by executing it again with lower and lower
+/// bounds. The max price per unit is the L1 gas price of the block, in fri.
+///
+/// ### Errors
+///
+/// * `UNSUPPORTED_TX_VERSION` - If one of the transactions is not a v3 invoke or deploy account transaction.
+/// * `TRANSACTION_EXECUTION_ERROR` - If one of the transactions fails even with the largest bounds, with its index
+///   in the request and its error.
+pub fn estimate_resource_bounds(
+    starknet: &Starknet,
+    request: Vec<BroadcastedTransaction>,
+    simulation_flags: Vec<SimulationFlagForEstimateFee>,
+    block_id: BlockId,
+) -> StarknetRpcResult<Vec<ResourceBoundsMapping>> {
+    let block_info = starknet.get_block_info(&block_id)?;
+    let exec_context = starknet.execution_context(&block_info)?;
+
+    let transactions = request
+        .into_iter()
+        .map(|tx| broadcasted_to_blockifier(tx, starknet.chain_id()))
+        .collect::<Result<Vec<_>, _>>()
+        .or_internal_server_error("Failed to convert BroadcastedTransaction to AccountTransaction")?;
+
+    let validate = !simulation_flags.contains(&SimulationFlagForEstimateFee::SkipValidate);
+
+    exec_context
+        .tight_l1_gas_bounds(&transactions, validate)?
+        .into_iter()
+        .enumerate()
+        .map(|(tx_index, tight_bounds)| match tight_bounds {
+            TightBounds::Found(bounds) => Ok(ResourceBoundsMapping {
+                l1_gas: ResourceBounds { max_amount: bounds.max_amount, max_price_per_unit: bounds.max_price_per_unit },
+                l2_gas: ResourceBounds { max_amount: 0, max_price_per_unit: 0 },
+            }),
+            TightBounds::Unsupported => Err(StarknetRpcApiError::UnsupportedTxnVersion),
+            TightBounds::Failing(error) => Err(StarknetRpcApiError::TxnExecutionError { tx_index, error }),
+        })
+        .collect()
+}
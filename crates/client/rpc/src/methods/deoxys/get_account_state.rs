@@ -0,0 +1,48 @@
+use dp_block::BlockId;
+use starknet_core::types::Felt;
+
+use super::get_storage_batch::get_storage_batch;
+use crate::errors::{StarknetRpcApiError, StarknetRpcResult};
+use crate::types::AccountState;
+use crate::utils::ResultExt;
+use crate::Starknet;
+
+/// Get the state of an account in a single request: what wallets need to render it.
+///
+/// ### Arguments
+///
+/// * `contract_address` - The address of the account.
+/// * `block_id` - The block at which the state is read.
+/// * `storage_keys` - Storage keys of the account to read along with its nonce and class hash, at most 1024.
+///
+/// ### Returns
+///
+/// The class hash and nonce of the account and, if storage keys were requested, their values in the same
+/// order. Returns `CONTRACT_NOT_FOUND` if the account is not deployed at this block.
+pub fn get_account_state(
+    starknet: &Starknet,
+    contract_address: Felt,
+    block_id: BlockId,
+    storage_keys: Option<Vec<Felt>>,
+) -> StarknetRpcResult<AccountState> {
+    // Makes sure the block exists, as missing blocks and contracts both resolve to no class hash
+    starknet.get_block_info(&block_id)?;
+
+    let class_hash = starknet
+        .backend
+        .get_contract_class_hash_at(&block_id, &contract_address)
+        .or_internal_server_error("Error getting contract class hash")?
+        .ok_or(StarknetRpcApiError::ContractNotFound)?;
+    let nonce = starknet
+        .backend
+        .get_contract_nonce_at(&block_id, &contract_address)
+        .or_internal_server_error("Error getting contract nonce")?
+        .unwrap_or(Felt::ZERO);
+
+    let storage = match storage_keys {
+        Some(keys) => Some(get_storage_batch(starknet, contract_address, keys, block_id)?),
+        None => None,
+    };
+
+    Ok(AccountState { class_hash, nonce, storage })
+}
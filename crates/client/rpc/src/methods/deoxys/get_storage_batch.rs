@@ -0,0 +1,64 @@
+use dp_block::BlockId;
+use starknet_core::types::Felt;
+
+use crate::constants::MAX_STORAGE_BATCH_KEYS;
+use crate::errors::{StarknetRpcApiError, StarknetRpcResult};
+use crate::types::ContractStorageKeys;
+use crate::utils::ResultExt;
+use crate::Starknet;
+
+/// Get the values of many storage keys of a contract in a single request.
+///
+/// ### Arguments
+///
+/// * `contract_address` - The address of the contract to read from.
+/// * `keys` - The storage keys to read, at most 1024.
+/// * `block_id` - The block at which the storage is read.
+///
+/// ### Returns
+///
+/// The values at the given keys, in the same order. Every value is read from the same state, and unset keys
+/// read as zero. Returns `CONTRACT_NOT_FOUND` if the contract is not deployed at this block.
+pub fn get_storage_batch(
+    starknet: &Starknet,
+    contract_address: Felt,
+    keys: Vec<Felt>,
+    block_id: BlockId,
+) -> StarknetRpcResult<Vec<Felt>> {
+    let contracts = vec![ContractStorageKeys { contract_address, storage_keys: keys }];
+    let mut values = get_contracts_storage_batch(starknet, contracts, block_id)?;
+    values.pop().ok_or(StarknetRpcApiError::InternalServerError)
+}
+
+/// Get the values of many storage keys of several contracts in a single request.
+///
+/// ### Arguments
+///
+/// * `contracts` - The contracts to read from, with their storage keys. At most 1024 keys can be read in
+///   total.
+/// * `block_id` - The block at which the storage is read.
+///
+/// ### Returns
+///
+/// For each contract, the values at its keys, in the same order. Every value is read from the same state, and
+/// unset keys read as zero. Returns `CONTRACT_NOT_FOUND` if any of the contracts is not deployed at this block.
+pub fn get_contracts_storage_batch(
+    starknet: &Starknet,
+    contracts: Vec<ContractStorageKeys>,
+    block_id: BlockId,
+) -> StarknetRpcResult<Vec<Vec<Felt>>> {
+    let n_keys: usize = contracts.iter().map(|contract| contract.storage_keys.len()).sum();
+    if n_keys > MAX_STORAGE_BATCH_KEYS {
+        return Err(StarknetRpcApiError::StorageBatchLimitExceeded);
+    }
+
+    let contracts: Vec<_> =
+        contracts.into_iter().map(|contract| (contract.contract_address, contract.storage_keys)).collect();
+    let values = starknet
+        .backend
+        .get_contracts_storage_at(&block_id, &contracts)
+        .or_internal_server_error("Error getting contracts storage")?
+        .ok_or(StarknetRpcApiError::BlockNotFound)?;
+
+    values.into_iter().map(|values| values.ok_or(StarknetRpcApiError::ContractNotFound)).collect()
+}
@@ -0,0 +1,65 @@
+use dp_block::BlockId;
+use jsonrpsee::core::{async_trait, RpcResult, SubscriptionResult};
+use jsonrpsee::PendingSubscriptionSink;
+use starknet_core::types::{
+    BroadcastedTransaction, Felt, FunctionCall, ResourceBoundsMapping, SimulationFlagForEstimateFee,
+};
+
+use super::call_with_resources::*;
+use super::estimate_resource_bounds::*;
+use super::get_account_state::*;
+use super::get_storage_batch::*;
+use super::subscribe_sync_status::*;
+use super::trace_transaction_syscalls::*;
+use crate::types::{AccountState, CallWithResources, ContractStorageKeys, TransactionSyscallTrace};
+use crate::{DeoxysRpcApiServer, DeoxysTraceRpcApiServer, Starknet};
+
+#[async_trait]
+impl DeoxysRpcApiServer for Starknet {
+    fn get_storage_batch(&self, contract_address: Felt, keys: Vec<Felt>, block_id: BlockId) -> RpcResult<Vec<Felt>> {
+        Ok(get_storage_batch(self, contract_address, keys, block_id)?)
+    }
+
+    fn get_contracts_storage_batch(
+        &self,
+        contracts: Vec<ContractStorageKeys>,
+        block_id: BlockId,
+    ) -> RpcResult<Vec<Vec<Felt>>> {
+        Ok(get_contracts_storage_batch(self, contracts, block_id)?)
+    }
+
+    async fn call_with_resources(&self, request: FunctionCall, block_id: BlockId) -> RpcResult<CallWithResources> {
+        Ok(self.spawn_execution(move |starknet| call_with_resources(starknet, request, block_id)).await?)
+    }
+
+    async fn estimate_resource_bounds(
+        &self,
+        request: Vec<BroadcastedTransaction>,
+        simulation_flags: Vec<SimulationFlagForEstimateFee>,
+        block_id: BlockId,
+    ) -> RpcResult<Vec<ResourceBoundsMapping>> {
+        Ok(self
+            .spawn_execution(move |starknet| estimate_resource_bounds(starknet, request, simulation_flags, block_id))
+            .await?)
+    }
+
+    fn get_account_state(
+        &self,
+        contract_address: Felt,
+        block_id: BlockId,
+        storage_keys: Option<Vec<Felt>>,
+    ) -> RpcResult<AccountState> {
+        Ok(get_account_state(self, contract_address, block_id, storage_keys)?)
+    }
+
+    async fn subscribe_sync_status(&self, subscription_sink: PendingSubscriptionSink) -> SubscriptionResult {
+        subscribe_sync_status(self, subscription_sink).await
+    }
+}
+
+#[async_trait]
+impl DeoxysTraceRpcApiServer for Starknet {
+    async fn trace_transaction_syscalls(&self, transaction_hash: Felt) -> RpcResult<TransactionSyscallTrace> {
+        Ok(self.spawn_execution(move |starknet| trace_transaction_syscalls(starknet, transaction_hash)).await?)
+    }
+}
@@ -0,0 +1,7 @@
+pub mod call_with_resources;
+pub mod estimate_resource_bounds;
+pub mod get_account_state;
+pub mod get_storage_batch;
+pub mod lib;
+pub mod subscribe_sync_status;
+pub mod trace_transaction_syscalls;
@@ -0,0 +1,140 @@
+use std::time::{Duration, Instant};
+
+use dp_block::{BlockId, BlockTag};
+use jsonrpsee::core::SubscriptionResult;
+use jsonrpsee::{PendingSubscriptionSink, SubscriptionMessage};
+use starknet_core::types::Felt;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::constants::SUBSCRIPTION_SHUTDOWN_MESSAGE;
+use crate::errors::StarknetRpcResult;
+use crate::types::SyncStatusUpdate;
+use crate::utils::ResultExt;
+use crate::Starknet;
+
+const SUBSCRIPTION: &str = "deoxys_subscribeSyncStatus";
+/// Minimum time between two notifications, so that subscribers are not flooded while the node catches up.
+const NOTIFICATION_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The latest stored block, as last notified.
+struct Tip {
+    block_number: u64,
+    block_hash: Felt,
+}
+
+/// Subscribe to the sync status of the node.
+///
+/// ### Returns
+///
+/// Sends the current sync status, and then an update at most every second while new blocks are stored, with the
+/// rate at which they are stored since the previous update. An update following a reorg of the stored chain
+/// reports the first replaced block.
+pub async fn subscribe_sync_status(
+    starknet: &Starknet,
+    subscription_sink: PendingSubscriptionSink,
+) -> SubscriptionResult {
+    // Subscribe before reading the database, so that no block can be stored in between without being notified
+    let mut new_blocks = starknet.backend.subscribe_new_blocks();
+
+    let mut tip = match latest_tip(starknet) {
+        Ok(tip) => tip,
+        Err(err) => {
+            subscription_sink.reject(err).await;
+            return Ok(());
+        }
+    };
+    let sink = subscription_sink.accept().await?;
+    let _subscription = starknet.subscription_started(SUBSCRIPTION);
+
+    let mut last_notified = tip.as_ref().map(|tip| tip.block_number);
+    let mut last_notified_at = Instant::now();
+    sink.send(SubscriptionMessage::from_json(&sync_status(starknet, &tip, 0.0, None)?)?).await?;
+
+    let mut interval = tokio::time::interval(NOTIFICATION_INTERVAL);
+    let mut reorg_block_num: Option<u64> = None;
+    loop {
+        tokio::select! {
+            block = new_blocks.recv() => match block {
+                Ok(block) => {
+                    let block_number = block.info.header.block_number;
+                    let replaced = first_replaced_block(&tip, block_number, block.info.header.parent_block_hash);
+                    if let Some(replaced) = replaced {
+                        reorg_block_num = Some(reorg_block_num.map_or(replaced, |n| u64::min(n, replaced)));
+                    }
+                    tip = Some(Tip { block_number, block_hash: block.info.block_hash });
+                }
+                // the tip is caught up with the next received block
+                Err(RecvError::Lagged(skipped)) => starknet.subscription_lagged(SUBSCRIPTION, skipped),
+                Err(RecvError::Closed) => return Ok(()),
+            },
+            _ = interval.tick() => {
+                let current = tip.as_ref().map(|tip| tip.block_number);
+                if current == last_notified && reorg_block_num.is_none() {
+                    continue;
+                }
+                let elapsed = last_notified_at.elapsed().as_secs_f64();
+                let stored = current.unwrap_or(0).saturating_sub(last_notified.unwrap_or(0));
+                let blocks_per_second = if elapsed > 0.0 { stored as f64 / elapsed } else { 0.0 };
+
+                let status = sync_status(starknet, &tip, blocks_per_second, reorg_block_num.take())?;
+                sink.send(SubscriptionMessage::from_json(&status)?).await?;
+                last_notified = current;
+                last_notified_at = Instant::now();
+            }
+            _ = sink.closed() => return Ok(()),
+            _ = dp_utils::graceful_shutdown() => return Err(SUBSCRIPTION_SHUTDOWN_MESSAGE.into()),
+        }
+    }
+}
+
+/// The first block of the stored chain replaced by a new block, if it does not extend the tip.
+fn first_replaced_block(tip: &Option<Tip>, block_number: u64, parent_block_hash: Felt) -> Option<u64> {
+    let tip = tip.as_ref()?;
+    if block_number <= tip.block_number {
+        Some(block_number)
+    } else if block_number == tip.block_number + 1 && parent_block_hash != tip.block_hash {
+        Some(tip.block_number)
+    } else {
+        None
+    }
+}
+
+fn latest_tip(starknet: &Starknet) -> StarknetRpcResult<Option<Tip>> {
+    let block_info = starknet
+        .backend
+        .get_block_info(&BlockId::Tag(BlockTag::Latest))
+        .or_internal_server_error("Error getting latest block")?;
+    Ok(block_info
+        .as_ref()
+        .and_then(|info| info.as_nonpending())
+        .map(|info| Tip { block_number: info.header.block_number, block_hash: info.block_hash }))
+}
+
+fn sync_status(
+    starknet: &Starknet,
+    tip: &Option<Tip>,
+    blocks_per_second: f64,
+    reorg_block_num: Option<u64>,
+) -> StarknetRpcResult<SyncStatusUpdate> {
+    // The highest known block is probed periodically from the gateway, it can lag behind our own latest block
+    let highest = starknet
+        .backend
+        .get_highest_known_block()
+        .or_internal_server_error("Error getting highest known block")?
+        .filter(|highest| tip.as_ref().map_or(true, |tip| highest.block_number > tip.block_number));
+
+    let (highest_block_num, highest_block_hash) = match (highest, tip) {
+        (Some(highest), _) => (Some(highest.block_number), Some(highest.block_hash)),
+        (None, Some(tip)) => (Some(tip.block_number), Some(tip.block_hash)),
+        (None, None) => (None, None),
+    };
+
+    Ok(SyncStatusUpdate {
+        current_block_num: tip.as_ref().map(|tip| tip.block_number),
+        current_block_hash: tip.as_ref().map(|tip| tip.block_hash),
+        highest_block_num,
+        highest_block_hash,
+        blocks_per_second,
+        reorg_block_num,
+    })
+}
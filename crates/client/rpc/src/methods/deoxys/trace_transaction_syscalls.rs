@@ -0,0 +1,43 @@
+use std::sync::{Arc, Mutex};
+
+use dc_exec::{block_to_blockifier_transactions, Syscall, SyscallHook};
+use starknet_core::types::Felt;
+
+use crate::errors::{StarknetRpcApiError, StarknetRpcResult};
+use crate::types::{SyscallTraceEntry, TransactionSyscallTrace};
+use crate::utils::{OptionExt, ResultExt};
+use crate::Starknet;
+
+/// Collects the syscalls of the traced transaction.
+#[derive(Default)]
+struct SyscallCollector(Mutex<Vec<SyscallTraceEntry>>);
+
+impl SyscallHook for SyscallCollector {
+    fn on_syscall(&self, _tx_hash: Felt, depth: usize, syscall: Syscall) {
+        self.0.lock().expect("Poisoned lock").push(SyscallTraceEntry { depth, syscall: syscall.into() });
+    }
+}
+
+pub fn trace_transaction_syscalls(
+    starknet: &Starknet,
+    transaction_hash: Felt,
+) -> StarknetRpcResult<TransactionSyscallTrace> {
+    let (block, tx_index) = starknet
+        .backend
+        .find_tx_hash_block(&transaction_hash)
+        .or_internal_server_error("Error while getting block from tx hash")?
+        .ok_or(StarknetRpcApiError::TxnHashNotFound)?;
+
+    let collector = Arc::new(SyscallCollector::default());
+    let exec_context = starknet.execution_context(&block.info)?.with_syscall_hook(collector.clone());
+
+    let mut block_txs = block_to_blockifier_transactions(&starknet.backend, &block);
+    let transactions_before: Vec<_> = block_txs.by_ref().take(tx_index.0 as usize).collect::<Result<_, _>>()?;
+    let transaction =
+        block_txs.next().ok_or_internal_server_error("There should be at least one transaction in the block")??;
+
+    exec_context.execute_transactions(transactions_before, [transaction], true, true)?;
+
+    let syscalls = std::mem::take(&mut *collector.0.lock().expect("Poisoned lock"));
+    Ok(TransactionSyscallTrace { transaction_hash, syscalls })
+}
@@ -0,0 +1,46 @@
+use jsonrpsee::core::{async_trait, RpcResult};
+use jsonrpsee::types::error::INVALID_PARAMS_CODE;
+use jsonrpsee::types::ErrorObjectOwned;
+use starknet_core::types::{Felt, PriceUnit};
+
+use crate::errors::StarknetRpcApiError;
+use crate::{Dev, DevRpcApiServer};
+
+#[async_trait]
+impl DevRpcApiServer for Dev {
+    async fn mint(&self, address: Felt, amount: Felt, unit: Option<PriceUnit>) -> RpcResult<()> {
+        let unit = unit.unwrap_or(PriceUnit::Wei).into();
+        Ok(self.devnet.mint(address, amount, unit).await.map_err(StarknetRpcApiError::from)?)
+    }
+
+    async fn mine(&self, n_blocks: Option<u64>) -> RpcResult<Felt> {
+        let n_blocks = n_blocks.unwrap_or(1).max(1);
+        Ok(self.devnet.mine(n_blocks).await.map_err(StarknetRpcApiError::from)?)
+    }
+
+    async fn set_auto_mine(&self, auto_mine: bool) -> RpcResult<()> {
+        self.devnet.set_auto_mine(auto_mine);
+        Ok(())
+    }
+
+    async fn set_block_timestamp(&self, timestamp: u64) -> RpcResult<()> {
+        self.devnet.set_block_timestamp(timestamp).map_err(|err| match err {
+            err @ dc_devnet::DevnetError::TimestampOverflow(_) => {
+                ErrorObjectOwned::owned(INVALID_PARAMS_CODE, err.to_string(), None::<()>)
+            }
+            err => StarknetRpcApiError::from(err).into(),
+        })
+    }
+
+    async fn impersonate_account(&self, address: Felt) -> RpcResult<bool> {
+        let impersonated = self.devnet.impersonate(address);
+        if impersonated {
+            log::info!("🎭 Impersonating account {address:#x}");
+        }
+        Ok(impersonated)
+    }
+
+    async fn stop_impersonating_account(&self, address: Felt) -> RpcResult<bool> {
+        Ok(self.devnet.stop_impersonating(address))
+    }
+}
@@ -1,3 +1,10 @@
+pub mod admin;
+pub mod deoxys;
+pub mod dev;
+pub mod pathfinder;
 pub mod read;
 pub mod trace;
+pub mod v0_6;
+pub mod v0_8;
 pub mod write;
+pub mod ws;
@@ -0,0 +1,83 @@
+use dp_block::BlockId;
+use starknet_core::types::Felt;
+
+use crate::constants::MAX_STORAGE_PROOF_KEYS;
+use crate::errors::{StarknetRpcApiError, StarknetRpcResult};
+use crate::types::{ContractData, GetProofResult, ProofNode};
+use crate::utils::ResultExt;
+use crate::Starknet;
+
+/// Get the merkle proofs of a contract and of some of its storage keys, as returned by pathfinder.
+///
+/// ### Arguments
+///
+/// * `block_id` - The block to prove the state of. The tries only hold the latest state, so this has to
+///   resolve to the latest block.
+/// * `contract_address` - The contract to prove, which does not need to be deployed.
+/// * `keys` - The storage keys of the contract to prove, at most 100.
+///
+/// ### Returns
+///
+/// The state and class commitments of the block, the proof of the contract in the contract trie and, if the
+/// contract is deployed, its leaf data with the proofs of the requested keys in its storage trie. Returns
+/// `PROOF_LIMIT_EXCEEDED` if too many keys are requested, or `PROOF_MISSING` if the block is not the latest
+/// block.
+pub fn get_proof(
+    starknet: &Starknet,
+    block_id: BlockId,
+    contract_address: Felt,
+    keys: Vec<Felt>,
+) -> StarknetRpcResult<GetProofResult> {
+    if keys.len() > MAX_STORAGE_PROOF_KEYS {
+        return Err(StarknetRpcApiError::ProofLimitExceeded);
+    }
+
+    let block_info = starknet.get_block_info(&block_id)?;
+    // the pending state is not merkelized
+    let block_info = block_info.as_nonpending().ok_or(StarknetRpcApiError::ProofMissing)?;
+    if block_info.header.block_number != starknet.current_block_number()? {
+        return Err(StarknetRpcApiError::ProofMissing);
+    }
+
+    let backend = &starknet.backend;
+    let contract_proof = backend
+        .get_contract_proof(&contract_address)
+        .or_internal_server_error("Error getting contract proof")?
+        .into_iter()
+        .map(ProofNode::from)
+        .collect();
+
+    let class_hash = backend
+        .get_contract_class_hash_at(&block_id, &contract_address)
+        .or_internal_server_error("Error getting contract class hash")?;
+
+    let contract_data = match class_hash {
+        Some(class_hash) => {
+            let nonce = backend
+                .get_contract_nonce_at(&block_id, &contract_address)
+                .or_internal_server_error("Error getting contract nonce")?
+                .unwrap_or(Felt::ZERO);
+            let root = backend
+                .get_contract_storage_root(&contract_address)
+                .or_internal_server_error("Error getting contract storage root")?;
+            let storage_proofs = backend
+                .get_contract_storage_proofs(&contract_address, &keys)
+                .or_internal_server_error("Error getting contract storage proofs")?
+                .into_iter()
+                .map(|proof| proof.into_iter().map(ProofNode::from).collect())
+                .collect();
+
+            Some(ContractData { class_hash, nonce, root, contract_state_hash_version: Felt::ZERO, storage_proofs })
+        }
+        None => None,
+    };
+
+    let class_commitment = backend.get_class_trie_root().or_internal_server_error("Error getting class trie root")?;
+
+    Ok(GetProofResult {
+        state_commitment: block_info.header.global_state_root,
+        class_commitment,
+        contract_proof,
+        contract_data,
+    })
+}
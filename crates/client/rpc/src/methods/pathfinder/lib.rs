@@ -0,0 +1,13 @@
+use dp_block::BlockId;
+use jsonrpsee::core::RpcResult;
+use starknet_core::types::Felt;
+
+use super::get_proof::*;
+use crate::types::GetProofResult;
+use crate::{PathfinderRpcApiServer, Starknet};
+
+impl PathfinderRpcApiServer for Starknet {
+    fn get_proof(&self, block_id: BlockId, contract_address: Felt, keys: Vec<Felt>) -> RpcResult<GetProofResult> {
+        Ok(get_proof(self, block_id, contract_address, keys)?)
+    }
+}
@@ -1,10 +1,14 @@
-use dc_exec::ExecutionContext;
+use std::collections::HashMap;
+
+use dc_exec::StateOverrides;
+use dp_block::BlockId;
 use starknet_core::types::Felt;
-use starknet_core::types::{BlockId, FunctionCall};
+use starknet_core::types::FunctionCall;
 
 use crate::errors::StarknetRpcApiError;
 use crate::errors::StarknetRpcResult;
-use crate::methods::trace::trace_transaction::FALLBACK_TO_SEQUENCER_WHEN_VERSION_BELOW;
+use crate::types::ContractStateOverride;
+use crate::utils::ResultExt;
 use crate::Starknet;
 
 /// Call a Function in a Contract Without Creating a Transaction
@@ -15,6 +19,10 @@ use crate::Starknet;
 ///   contract address, function signature, and arguments.
 /// * `block_id` - The identifier of the block used to reference the state or call the transaction
 ///   on. This can be the hash of the block, its number (height), or a specific block tag.
+/// * `state_overrides` - Extension to the specification: changes applied on top of the state of the
+///   block before calling, by contract address. Each contract can override its `nonce`, its
+///   `class_hash` (which must be declared), its `balance` in both fee tokens, and some of its
+///   `storage` slots, the other slots keeping their value.
 ///
 /// ### Returns
 ///
@@ -27,13 +35,30 @@ use crate::Starknet;
 /// * `CONTRACT_NOT_FOUND` - If the specified contract address does not exist.
 /// * `CONTRACT_ERROR` - If there is an error with the contract or the function call.
 /// * `BLOCK_NOT_FOUND` - If the specified block does not exist in the blockchain.
-pub fn call(starknet: &Starknet, request: FunctionCall, block_id: BlockId) -> StarknetRpcResult<Vec<Felt>> {
+/// * `CLASS_HASH_NOT_FOUND` - If a class hash override is not declared.
+pub fn call(
+    starknet: &Starknet,
+    request: FunctionCall,
+    block_id: BlockId,
+    state_overrides: Option<HashMap<Felt, ContractStateOverride>>,
+) -> StarknetRpcResult<Vec<Felt>> {
     let block_info = starknet.get_block_info(&block_id)?;
 
-    let exec_context = ExecutionContext::new(&starknet.backend, &block_info)?;
+    let mut exec_context = starknet.execution_context(&block_info)?;
 
-    if block_info.protocol_version() < &FALLBACK_TO_SEQUENCER_WHEN_VERSION_BELOW {
-        return Err(StarknetRpcApiError::UnsupportedTxnVersion);
+    if let Some(state_overrides) = state_overrides {
+        for class_hash in state_overrides.values().filter_map(|contract| contract.class_hash) {
+            if !starknet
+                .backend
+                .contains_class(&block_id, &class_hash)
+                .or_internal_server_error("Error checking if the class is declared")?
+            {
+                return Err(StarknetRpcApiError::ClassHashNotFound);
+            }
+        }
+        let state_overrides: StateOverrides =
+            state_overrides.into_iter().map(|(address, contract)| (address, contract.into())).collect();
+        exec_context = exec_context.with_state_overrides(&state_overrides)?;
     }
 
     let FunctionCall { contract_address, entry_point_selector, calldata } = request;
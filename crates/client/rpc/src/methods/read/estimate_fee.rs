@@ -1,11 +1,11 @@
-use dc_exec::ExecutionContext;
+use dp_block::BlockId;
 use dp_transactions::broadcasted_to_blockifier;
-use starknet_core::types::{BlockId, BroadcastedTransaction, FeeEstimate, SimulationFlagForEstimateFee};
+use starknet_core::types::{BroadcastedTransaction, FeeEstimate, SimulationFlagForEstimateFee};
 
-use crate::errors::StarknetRpcResult;
+use crate::errors::{StarknetRpcApiError, StarknetRpcResult};
+use crate::types::BlockOverrides;
 use crate::utils::ResultExt;
 use crate::Starknet;
-use crate::{errors::StarknetRpcApiError, methods::trace::trace_transaction::FALLBACK_TO_SEQUENCER_WHEN_VERSION_BELOW};
 
 /// Estimate the fee associated with transaction
 ///
@@ -13,33 +13,62 @@ use crate::{errors::StarknetRpcApiError, methods::trace::trace_transaction::FALL
 ///
 /// * `request` - starknet transaction request
 /// * `block_id` - hash of the requested block, number (height), or tag
+/// * `block_overrides` - Extension to the specification: changes to the header of the block the
+///   transactions are executed in, such as its timestamp or gas prices, to estimate what they would
+///   cost in a later block.
 ///
 /// # Returns
 ///
 /// * `fee_estimate` - fee estimate in gwei
-pub async fn estimate_fee(
+///
+/// # Errors
+///
+/// * `TRANSACTION_EXECUTION_ERROR` - If one of the transactions fails or is reverted, with the
+///   index of the first failing transaction in the request and its error.
+/// * `INSUFFICIENT_ACCOUNT_BALANCE` - If the fee charge is enforced by the node, and the balance
+///   of the sender of one of the transactions does not cover its max fee or resource bounds.
+pub fn estimate_fee(
     starknet: &Starknet,
     request: Vec<BroadcastedTransaction>,
     simulation_flags: Vec<SimulationFlagForEstimateFee>,
     block_id: BlockId,
+    block_overrides: Option<BlockOverrides>,
 ) -> StarknetRpcResult<Vec<FeeEstimate>> {
     let block_info = starknet.get_block_info(&block_id)?;
 
-    if block_info.protocol_version() < &FALLBACK_TO_SEQUENCER_WHEN_VERSION_BELOW {
-        return Err(StarknetRpcApiError::UnsupportedTxnVersion);
+    let mut exec_context = starknet.execution_context(&block_info)?;
+    if let Some(block_overrides) = block_overrides {
+        exec_context = exec_context.with_header_overrides(&block_overrides.try_into()?)?;
     }
 
-    let exec_context = ExecutionContext::new(&starknet.backend, &block_info)?;
-
     let transactions = request
         .into_iter()
         .map(|tx| broadcasted_to_blockifier(tx, starknet.chain_id()))
         .collect::<Result<Vec<_>, _>>()
         .or_internal_server_error("Failed to convert BroadcastedTransaction to AccountTransaction")?;
 
+    // The fee is estimated without being charged, as with the `SKIP_FEE_CHARGE` simulation flag, unless the node
+    // enforces it
+    let charge_fee = starknet.enforce_fee_charge;
     let validate = !simulation_flags.contains(&SimulationFlagForEstimateFee::SkipValidate);
 
-    let execution_results = exec_context.execute_transactions([], transactions, validate, true)?;
+    let execution_results = exec_context.execute_transactions_cached(
+        &starknet.execution_cache,
+        vec![],
+        transactions,
+        charge_fee,
+        validate,
+    )?;
+
+    // A reverted transaction would still be charged, but it is reported as failing so that the caller knows which
+    // of its transactions is broken.
+    if let Some((tx_index, revert_error)) = execution_results
+        .iter()
+        .enumerate()
+        .find_map(|(tx_index, result)| result.execution_info.revert_error.as_ref().map(|err| (tx_index, err)))
+    {
+        return Err(StarknetRpcApiError::TxnExecutionError { tx_index, error: revert_error.clone() });
+    }
 
     let fee_estimates =
         execution_results.iter().map(|result| exec_context.execution_result_to_fee_estimate(result)).collect();
@@ -1,14 +1,7 @@
-use dc_exec::ExecutionContext;
-use dp_convert::ToStarkFelt;
-use dp_transactions::L1HandlerTransaction;
-use starknet_api::transaction::{Fee, TransactionHash};
-use starknet_core::types::{BlockId, FeeEstimate, MsgFromL1};
-use starknet_types_core::felt::Felt;
+use dp_block::BlockId;
+use starknet_core::types::{FeeEstimate, MsgFromL1};
 
-use crate::errors::StarknetRpcApiError;
 use crate::errors::StarknetRpcResult;
-use crate::methods::trace::trace_transaction::FALLBACK_TO_SEQUENCER_WHEN_VERSION_BELOW;
-use crate::utils::OptionExt;
 use crate::Starknet;
 
 /// Estimate the L2 fee of a message sent on L1
@@ -27,42 +20,16 @@ use crate::Starknet;
 /// BlockNotFound : If the specified block does not exist.
 /// ContractNotFound : If the specified contract address does not exist.
 /// ContractError : If there is an error with the contract.
-pub async fn estimate_message_fee(
+pub fn estimate_message_fee(
     starknet: &Starknet,
     message: MsgFromL1,
     block_id: BlockId,
 ) -> StarknetRpcResult<FeeEstimate> {
     let block_info = starknet.get_block_info(&block_id)?;
 
-    if block_info.protocol_version() < &FALLBACK_TO_SEQUENCER_WHEN_VERSION_BELOW {
-        return Err(StarknetRpcApiError::UnsupportedTxnVersion);
-    }
+    let exec_context = starknet.execution_context(&block_info)?;
 
-    let exec_context = ExecutionContext::new(&starknet.backend, &block_info)?;
-
-    let transaction = convert_message_into_transaction(message, starknet.chain_id());
-    let execution_result = exec_context
-        .execute_transactions([], [transaction], false, true)?
-        .pop()
-        .ok_or_internal_server_error("Failed to convert BroadcastedTransaction to AccountTransaction")?;
-
-    let fee_estimate = exec_context.execution_result_to_fee_estimate(&execution_result);
+    let fee_estimate = exec_context.estimate_message_fee(&message.into())?;
 
     Ok(fee_estimate)
 }
-
-pub fn convert_message_into_transaction(
-    message: MsgFromL1,
-    chain_id: Felt,
-) -> blockifier::transaction::transaction_execution::Transaction {
-    let l1_handler: L1HandlerTransaction = message.into();
-    let tx_hash = l1_handler.compute_hash(chain_id, false, false);
-    let tx: starknet_api::transaction::L1HandlerTransaction = (&l1_handler).try_into().unwrap();
-
-    let tx = blockifier::transaction::transactions::L1HandlerTransaction {
-        tx,
-        tx_hash: TransactionHash(tx_hash.to_stark_felt()),
-        paid_fee_on_l1: Fee(1),
-    };
-    blockifier::transaction::transaction_execution::Transaction::L1HandlerTransaction(tx)
-}
@@ -1,5 +1,4 @@
-use dp_block::DeoxysMaybePendingBlockInfo;
-use starknet_core::types::BlockId;
+use dp_block::{BlockId, DeoxysMaybePendingBlockInfo};
 
 use crate::{errors::StarknetRpcResult, Starknet};
 
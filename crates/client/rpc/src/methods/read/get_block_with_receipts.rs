@@ -1,7 +1,7 @@
-use dp_block::DeoxysMaybePendingBlockInfo;
+use dp_block::{BlockId, DeoxysMaybePendingBlockInfo};
 use starknet_core::types::{
-    BlockId, BlockStatus, BlockWithReceipts, MaybePendingBlockWithReceipts, PendingBlockWithReceipts,
-    TransactionFinalityStatus, TransactionWithReceipt,
+    BlockStatus, BlockWithReceipts, MaybePendingBlockWithReceipts, PendingBlockWithReceipts, TransactionFinalityStatus,
+    TransactionWithReceipt,
 };
 
 use crate::errors::StarknetRpcResult;
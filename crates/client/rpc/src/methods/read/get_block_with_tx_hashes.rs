@@ -1,7 +1,7 @@
-use starknet_core::types::{BlockId, MaybePendingBlockWithTxHashes};
+use starknet_core::types::MaybePendingBlockWithTxHashes;
 
 use crate::errors::StarknetRpcResult;
-use dp_block::DeoxysMaybePendingBlockInfo;
+use dp_block::{BlockId, DeoxysMaybePendingBlockInfo};
 use starknet_core::types::{BlockStatus, BlockWithTxHashes, PendingBlockWithTxHashes};
 
 use crate::Starknet;
@@ -1,6 +1,6 @@
-use starknet_core::types::{BlockId, MaybePendingBlockWithTxs};
+use starknet_core::types::MaybePendingBlockWithTxs;
 
-use dp_block::DeoxysMaybePendingBlockInfo;
+use dp_block::{BlockId, DeoxysMaybePendingBlockInfo};
 use jsonrpsee::core::RpcResult;
 use starknet_core::types::{BlockStatus, BlockWithTxs, PendingBlockWithTxs};
 
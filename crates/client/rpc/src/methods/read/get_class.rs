@@ -1,13 +1,33 @@
-use starknet_core::types::{BlockId, ContractClass, Felt};
+use dp_block::BlockId;
+use starknet_core::types::{ContractClass, Felt};
 
 use crate::errors::{StarknetRpcApiError, StarknetRpcResult};
 use crate::utils::ResultExt;
 use crate::Starknet;
 
+/// Get the definition of a class, as it was declared at the given block.
+///
+/// ### Arguments
+///
+/// * `block_id` - The hash of the requested block, or number (height) of the requested block, or a
+///   block tag
+/// * `class_hash` - The hash of the requested class
+///
+/// ### Returns
+///
+/// The definition of the class. Returns `BLOCK_NOT_FOUND` if the block does not exist, and
+/// `CLASS_HASH_NOT_FOUND` if the class had not been declared yet at this block.
 pub fn get_class(starknet: &Starknet, block_id: BlockId, class_hash: Felt) -> StarknetRpcResult<ContractClass> {
+    let resolved_block_id = starknet
+        .backend
+        .resolve_block_id(&block_id)
+        .or_internal_server_error("Error resolving block id")?
+        .ok_or(StarknetRpcApiError::BlockNotFound)?;
+
+    // The class info is only found at the blocks following its declaration
     let class_data = starknet
         .backend
-        .get_class_info(&block_id, &class_hash)
+        .get_class_info(&resolved_block_id, &class_hash)
         .or_internal_server_error("Error getting contract class info")?
         .ok_or(StarknetRpcApiError::ClassHashNotFound)?;
 
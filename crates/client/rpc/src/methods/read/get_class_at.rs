@@ -1,7 +1,8 @@
-use starknet_core::types::{BlockId, ContractClass, Felt};
+use dp_block::BlockId;
+use starknet_core::types::{ContractClass, Felt};
 
 use crate::errors::{StarknetRpcApiError, StarknetRpcResult};
-use crate::utils::{OptionExt, ResultExt};
+use crate::utils::ResultExt;
 use crate::Starknet;
 
 /// Get the Contract Class Definition at a Given Address in a Specific Block
@@ -40,11 +41,12 @@ pub fn get_class_at(
         .or_internal_server_error("Error getting contract class hash at")?
         .ok_or(StarknetRpcApiError::ContractNotFound)?;
 
+    // The class info is only found at the blocks following its declaration
     let class_data = starknet
         .backend
         .get_class_info(&resolved_block_id, &class_hash)
         .or_internal_server_error("Error getting contract class info")?
-        .ok_or_internal_server_error("Class has no info")?;
+        .ok_or(StarknetRpcApiError::ClassHashNotFound)?;
 
     Ok(class_data.contract_class.into())
 }
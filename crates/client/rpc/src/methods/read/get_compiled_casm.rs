@@ -0,0 +1,32 @@
+use dp_block::{BlockId, BlockTag};
+use dp_class::CompiledClass;
+use starknet_core::types::Felt;
+
+use crate::errors::{StarknetRpcApiError, StarknetRpcResult};
+use crate::utils::ResultExt;
+use crate::Starknet;
+
+/// Get the CASM compiled from a Sierra class.
+///
+/// ### Arguments
+///
+/// * `class_hash` - The hash of the Sierra class, declared in the pending block or before.
+///
+/// ### Returns
+///
+/// The compiled class stored by the node, in the format of the Cairo compiler. Returns
+/// `CLASS_HASH_NOT_FOUND` if the class is not declared, or `UNSUPPORTED_CONTRACT_CLASS_VERSION` if it is a
+/// legacy class, which is not compiled to CASM.
+pub fn get_compiled_casm(starknet: &Starknet, class_hash: Felt) -> StarknetRpcResult<serde_json::Value> {
+    let (_info, compiled_class) = starknet
+        .backend
+        .get_class(&BlockId::Tag(BlockTag::Pending), &class_hash)
+        .or_internal_server_error("Error getting compiled class")?
+        .ok_or(StarknetRpcApiError::ClassHashNotFound)?;
+
+    if !matches!(compiled_class, CompiledClass::Sierra(_)) {
+        return Err(StarknetRpcApiError::UnsupportedContractClassVersion);
+    }
+
+    serde_json::from_slice(&compiled_class).or_internal_server_error("Error deserializing compiled class")
+}
@@ -1,9 +1,11 @@
-use dp_block::{DeoxysMaybePendingBlock, DeoxysMaybePendingBlockInfo};
+use dp_block::{DeoxysBlockInner, DeoxysMaybePendingBlock, DeoxysMaybePendingBlockInfo};
+use dp_receipt::EventFilter;
 use starknet_core::types::{BlockId, BlockTag, EmittedEvent, EventFilterWithPage, EventsPage, Felt};
 
-use crate::constants::{MAX_EVENTS_CHUNK_SIZE, MAX_EVENTS_KEYS};
+use crate::constants::MAX_EVENTS_KEYS;
 use crate::errors::{StarknetRpcApiError, StarknetRpcResult};
 use crate::types::ContinuationToken;
+use crate::utils::ResultExt;
 use crate::Starknet;
 
 /// Returns all events matching the given filter.
@@ -26,15 +28,22 @@ use crate::Starknet;
 /// block in which they occurred, and the transaction that triggered them. In case of
 /// errors, such as `PAGE_SIZE_TOO_BIG`, `INVALID_CONTINUATION_TOKEN`, `BLOCK_NOT_FOUND`, or
 /// `TOO_MANY_KEYS_IN_FILTER`, returns a `StarknetRpcApiError` indicating the specific issue.
+///
+/// A single call scans a bounded number of blocks: `EVENTS_SCAN_LIMIT_EXCEEDED` is returned when the page cannot
+/// be filled within this bound, and the range has to be narrowed down.
+///
+/// When `to_block` is the `pending` tag, the events of the pending block are returned after the
+/// events of the latest block, with a `null` block hash and number. There are none when the node
+/// has no pending block on top of its latest block.
 pub async fn get_events(starknet: &Starknet, filter: EventFilterWithPage) -> StarknetRpcResult<EventsPage> {
     let from_address = filter.event_filter.address;
-    let keys = filter.event_filter.keys.unwrap_or_default();
+    let event_filter = EventFilter::new(from_address, filter.event_filter.keys.unwrap_or_default());
     let chunk_size = filter.result_page_request.chunk_size;
 
-    if keys.len() > MAX_EVENTS_KEYS {
+    if event_filter.keys.len() > MAX_EVENTS_KEYS {
         return Err(StarknetRpcApiError::TooManyKeysInFilter);
     }
-    if chunk_size > MAX_EVENTS_CHUNK_SIZE as u64 {
+    if chunk_size > starknet.events_max_chunk_size as u64 {
         return Err(StarknetRpcApiError::PageSizeTooBig);
     }
 
@@ -42,65 +51,131 @@ pub async fn get_events(starknet: &Starknet, filter: EventFilterWithPage) -> Sta
     let (from_block, to_block, latest_block) =
         block_range(starknet, filter.event_filter.from_block, filter.event_filter.to_block)?;
 
-    let continuation_token = match filter.result_page_request.continuation_token {
-        Some(token) => ContinuationToken::parse(token).map_err(|_| StarknetRpcApiError::InvalidContinuationToken)?,
-        None => ContinuationToken { block_n: from_block, event_n: 0 },
-    };
-
     // Verify that the requested range is valid
     if from_block > to_block {
         return Ok(EventsPage { events: vec![], continuation_token: None });
     }
 
-    let from_block = continuation_token.block_n;
+    let continuation_token = match filter.result_page_request.continuation_token {
+        Some(token) => {
+            Some(ContinuationToken::parse(token).map_err(|_| StarknetRpcApiError::InvalidContinuationToken)?)
+        }
+        None => None,
+    };
+    let start_block = continuation_token.as_ref().map_or(from_block, |token| token.block_n);
+    // A token is only valid for the range it was issued for
+    if !(from_block..=to_block).contains(&start_block) {
+        return Err(StarknetRpcApiError::InvalidContinuationToken);
+    }
+
     let mut filtered_events: Vec<EmittedEvent> = Vec::new();
 
-    for current_block in from_block..=to_block {
-        let (_pending, block) = if current_block <= latest_block {
-            (false, starknet.get_block(&BlockId::Number(current_block))?)
+    let blocks = blocks_to_scan(starknet, from_address, start_block, to_block, latest_block)?;
+    for (scanned, current_block) in blocks.enumerate() {
+        let current_block = current_block?;
+        // The page is not full yet, the caller has to narrow the range down
+        if starknet.events_max_blocks_scanned.is_some_and(|max| scanned as u64 >= max) {
+            return Err(StarknetRpcApiError::EventsScanLimitExceeded);
+        }
+        let block = if current_block <= latest_block {
+            starknet.get_block(&BlockId::Number(current_block))?
         } else {
-            (true, starknet.get_block(&BlockId::Tag(BlockTag::Pending))?)
+            match pending_block(starknet, latest_block)? {
+                Some(block) => block,
+                None => continue,
+            }
+        };
+        let parent_block_hash = match &block.info {
+            DeoxysMaybePendingBlockInfo::Pending(info) => info.header.parent_block_hash,
+            DeoxysMaybePendingBlockInfo::NotPending(info) => info.header.parent_block_hash,
         };
 
-        let block_filtered_events: Vec<EmittedEvent> = get_block_events(starknet, &block)
-            .into_iter()
-            .filter(|event| event_match_filter(event, from_address, &keys))
-            .collect();
+        // The token points to the position of the next event in its block, which makes it independent of the
+        // filter. It is rejected if the chain has been reorganized below its block since it was issued.
+        let start_at = match &continuation_token {
+            Some(token) if token.block_n == current_block => {
+                if !token.matches_parent(&parent_block_hash) {
+                    return Err(StarknetRpcApiError::InvalidContinuationToken);
+                }
+                (token.tx_index, token.event_index)
+            }
+            _ => (0, 0),
+        };
 
-        if current_block == from_block && (block_filtered_events.len() as u64) < continuation_token.event_n {
-            return Err(StarknetRpcApiError::InvalidContinuationToken);
+        let block_filtered_events = get_block_events(starknet, &block).into_iter().filter(|(position, event)| {
+            *position >= start_at && event_filter.matches_parts(&event.from_address, &event.keys)
+        });
+
+        for ((tx_index, event_index), event) in block_filtered_events {
+            if filtered_events.len() == chunk_size as usize {
+                let token =
+                    ContinuationToken::new(current_block, tx_index, event_index, &parent_block_hash).to_string();
+                return Ok(EventsPage { events: filtered_events, continuation_token: Some(token) });
+            }
+            filtered_events.push(event);
         }
 
-        #[allow(clippy::iter_skip_zero)]
-        let block_filtered_reduced_events: Vec<EmittedEvent> = block_filtered_events
-            .into_iter()
-            .skip(if current_block == from_block { continuation_token.event_n as usize } else { 0 })
-            .take(chunk_size as usize - filtered_events.len())
-            .collect();
-
-        let num_events = block_filtered_reduced_events.len();
-
-        filtered_events.extend(block_filtered_reduced_events);
-
-        if filtered_events.len() == chunk_size as usize {
-            let event_n =
-                if current_block == from_block { continuation_token.event_n + chunk_size } else { num_events as u64 };
-            let token = Some(ContinuationToken { block_n: current_block, event_n }.to_string());
-
-            return Ok(EventsPage { events: filtered_events, continuation_token: token });
+        // Only the pending block has no hash, and it is always the last block of the range
+        if filtered_events.len() == chunk_size as usize && current_block < to_block {
+            if let Some(info) = block.info.as_nonpending() {
+                let token = ContinuationToken::new(current_block + 1, 0, 0, &info.block_hash).to_string();
+                return Ok(EventsPage { events: filtered_events, continuation_token: Some(token) });
+            }
         }
     }
     Ok(EventsPage { events: filtered_events, continuation_token: None })
 }
 
-#[inline]
-fn event_match_filter(event: &EmittedEvent, address: Option<Felt>, keys: &[Vec<Felt>]) -> bool {
-    let match_from_address = address.map_or(true, |addr| addr == event.from_address);
-    let match_keys = keys
-        .iter()
-        .enumerate()
-        .all(|(i, keys)| event.keys.len() > i && (keys.is_empty() || keys.contains(&event.keys[i])));
-    match_from_address && match_keys
+/// The pending block, if it has been built on top of the latest block. The stored pending block lags behind
+/// the latest block until the next one is fetched.
+fn pending_block(starknet: &Starknet, latest_block: u64) -> StarknetRpcResult<Option<DeoxysMaybePendingBlock>> {
+    let Some(block) = starknet
+        .backend
+        .get_block(&BlockId::Tag(BlockTag::Pending))
+        .or_internal_server_error("Error getting pending block")?
+    else {
+        return Ok(None);
+    };
+    let Some(pending_info) = block.info.as_pending() else { return Ok(None) };
+
+    let latest_info = starknet.get_block_info(&BlockId::Number(latest_block))?;
+    let latest_block_hash = latest_info.as_nonpending().map(|info| info.block_hash);
+    if latest_block_hash != Some(pending_info.header.parent_block_hash) {
+        return Ok(None);
+    }
+    Ok(Some(block))
+}
+
+/// Returns the blocks which may contain matching events, in ascending order. When the filter has an address,
+/// the event index is used to skip the blocks where this contract did not emit any event. Blocks stored before the
+/// index was introduced are always scanned.
+pub(crate) fn blocks_to_scan(
+    starknet: &Starknet,
+    from_address: Option<Felt>,
+    from_block: u64,
+    to_block: u64,
+    latest_block: u64,
+) -> StarknetRpcResult<Box<dyn Iterator<Item = StarknetRpcResult<u64>> + '_>> {
+    let last_stored_block = u64::min(to_block, latest_block);
+    let pending = (to_block > latest_block).then_some(latest_block + 1).map(Ok);
+
+    let Some(from_address) = from_address else {
+        return Ok(Box::new((from_block..=last_stored_block).map(Ok).chain(pending)));
+    };
+
+    let index_start = starknet
+        .backend
+        .get_event_index_start()
+        .or_internal_server_error("Error getting the event index start")?
+        .unwrap_or(u64::MAX);
+
+    let unindexed = (from_block..u64::min(last_stored_block + 1, index_start)).map(Ok);
+    let indexed = starknet
+        .backend
+        .get_event_blocks(&from_address, u64::max(from_block, index_start), last_stored_block)
+        .map(|res| res.or_internal_server_error("Error getting blocks from the event index"));
+
+    Ok(Box::new(unindexed.chain(indexed).chain(pending)))
 }
 
 fn block_range(
@@ -122,25 +197,49 @@ fn block_range(
     Ok((from_block_n, to_block_n, latest_block_n))
 }
 
-fn get_block_events(_starknet: &Starknet, block: &DeoxysMaybePendingBlock) -> Vec<EmittedEvent> {
+fn get_block_events(_starknet: &Starknet, block: &DeoxysMaybePendingBlock) -> Vec<((u64, u64), EmittedEvent)> {
     let (block_hash, block_number) = match &block.info {
         DeoxysMaybePendingBlockInfo::Pending(_) => (None, None),
         DeoxysMaybePendingBlockInfo::NotPending(block) => (Some(block.block_hash), Some(block.header.block_number)),
     };
+    positioned_block_events(block_hash, block_number, &block.inner).collect()
+}
 
-    let tx_hash_and_events = block.inner.receipts.iter().flat_map(|receipt| {
+/// The events emitted in a block, with their transaction hash. Block hash and number are `None` for the pending
+/// block.
+pub(crate) fn block_events(
+    block_hash: Option<Felt>,
+    block_number: Option<u64>,
+    block_inner: &DeoxysBlockInner,
+) -> impl Iterator<Item = EmittedEvent> + '_ {
+    positioned_block_events(block_hash, block_number, block_inner).map(|(_, event)| event)
+}
+
+/// The events emitted in a block, along with their position in the block: the index of their transaction and
+/// their index in this transaction.
+fn positioned_block_events(
+    block_hash: Option<Felt>,
+    block_number: Option<u64>,
+    block_inner: &DeoxysBlockInner,
+) -> impl Iterator<Item = ((u64, u64), EmittedEvent)> + '_ {
+    let tx_hash_and_events = block_inner.receipts.iter().enumerate().flat_map(|(tx_index, receipt)| {
         let tx_hash = receipt.transaction_hash();
-        receipt.events().iter().map(move |events| (tx_hash, events))
+        receipt
+            .events()
+            .iter()
+            .enumerate()
+            .map(move |(event_index, event)| ((tx_index as u64, event_index as u64), tx_hash, event))
     });
 
-    tx_hash_and_events
-        .map(|(transaction_hash, event)| EmittedEvent {
+    tx_hash_and_events.map(move |(position, transaction_hash, event)| {
+        let event = EmittedEvent {
             from_address: event.from_address,
             keys: event.keys.clone(),
             data: event.data.clone(),
             block_hash,
             block_number,
             transaction_hash,
-        })
-        .collect()
+        };
+        (position, event)
+    })
 }
@@ -0,0 +1,67 @@
+use dp_receipt::ExecutionResult;
+use starknet_core::types::{Hash256, SequencerTransactionStatus, TransactionExecutionStatus};
+
+use crate::errors::{StarknetRpcApiError, StarknetRpcResult};
+use crate::types::MessageStatus;
+use crate::utils::ResultExt;
+use crate::Starknet;
+
+/// Get the Status of the L1 -> L2 Messages Sent by an L1 Transaction
+///
+/// ### Arguments
+///
+/// * `transaction_hash` - The hash of the L1 transaction which sent the messages.
+///
+/// ### Returns
+///
+/// * The status of the `l1_handler` transaction consuming each of the messages, in the order in
+///   which they were sent. Transactions which have not been included in a block yet are
+///   `RECEIVED`, with no execution status.
+///
+/// ### Errors
+///
+/// * `TXN_HASH_NOT_FOUND` - If the L1 transaction did not send any message, or was sent before the
+///   first L1 block whose messages are indexed, see `--l1-messages-start-block`.
+pub fn get_messages_status(starknet: &Starknet, transaction_hash: Hash256) -> StarknetRpcResult<Vec<MessageStatus>> {
+    let l1_handler_tx_hashes = starknet
+        .backend
+        .get_l1_messages_to_l2(transaction_hash.as_bytes())
+        .or_internal_server_error("Error getting L1 messages from db")?
+        .ok_or(StarknetRpcApiError::TxnHashNotFound)?;
+    let l1_last_confirmed_block = starknet.get_l1_last_confirmed_block()?;
+
+    l1_handler_tx_hashes
+        .into_iter()
+        .map(|transaction_hash| {
+            let Some((block, tx_index)) = starknet
+                .backend
+                .find_tx_hash_block(&transaction_hash)
+                .or_internal_server_error("Error find tx hash block info from db")?
+            else {
+                return Ok(MessageStatus {
+                    transaction_hash,
+                    finality_status: SequencerTransactionStatus::Received,
+                    execution_status: None,
+                    failure_reason: None,
+                });
+            };
+
+            let receipt = block.inner.receipts.get(tx_index.0 as usize).ok_or(StarknetRpcApiError::TxnHashNotFound)?;
+            let (execution_status, failure_reason) = match receipt.execution_result() {
+                ExecutionResult::Succeeded => (TransactionExecutionStatus::Succeeded, None),
+                ExecutionResult::Reverted { reason } => (TransactionExecutionStatus::Reverted, Some(reason)),
+            };
+            let finality_status = match block.info.block_n() {
+                Some(block_n) if block_n <= l1_last_confirmed_block => SequencerTransactionStatus::AcceptedOnL1,
+                _ => SequencerTransactionStatus::AcceptedOnL2,
+            };
+
+            Ok(MessageStatus {
+                transaction_hash,
+                finality_status,
+                execution_status: Some(execution_status),
+                failure_reason,
+            })
+        })
+        .collect()
+}
@@ -1,4 +1,4 @@
-use starknet_core::types::BlockId;
+use dp_block::{BlockId, BlockTag};
 use starknet_types_core::felt::Felt;
 
 use crate::errors::{StarknetRpcApiError, StarknetRpcResult};
@@ -21,13 +21,32 @@ use crate::Starknet;
 /// count or other contract-specific operations. In case of errors, such as
 /// `BLOCK_NOT_FOUND` or `CONTRACT_NOT_FOUND`, returns a `StarknetRpcApiError` indicating the
 /// specific issue.
-
+///
+/// With the `pending` tag, the nonce includes the transactions of the pending block, unless it is
+/// not built on top of the latest block yet, in which case the nonce at the latest block is returned.
+/// The pending nonce comes only from the pending block: the node has no mempool, the transactions
+/// it received are forwarded to the gateway and only counted once they are in the pending block.
 pub fn get_nonce(starknet: &Starknet, block_id: BlockId, contract_address: Felt) -> StarknetRpcResult<Felt> {
+    let block_id = match block_id {
+        BlockId::Tag(BlockTag::Pending) if !starknet.is_pending_on_latest()? => BlockId::Tag(BlockTag::Latest),
+        block_id => block_id,
+    };
+
     let nonce = starknet
         .backend
         .get_contract_nonce_at(&block_id, &contract_address)
-        .or_internal_server_error("Error getting nonce")?
-        .ok_or(StarknetRpcApiError::ContractNotFound)?;
+        .or_internal_server_error("Error getting nonce")?;
 
-    Ok(nonce)
+    match nonce {
+        Some(nonce) => Ok(nonce),
+        // The nonce of a contract is only stored once it has been updated
+        None => {
+            starknet
+                .backend
+                .get_contract_class_hash_at(&block_id, &contract_address)
+                .or_internal_server_error("Error getting contract class hash")?
+                .ok_or(StarknetRpcApiError::ContractNotFound)?;
+            Ok(Felt::ZERO)
+        }
+    }
 }
@@ -1,4 +1,5 @@
-use starknet_core::types::{BlockId, BlockTag, Felt, MaybePendingStateUpdate, PendingStateUpdate, StateUpdate};
+use dp_block::{BlockId, BlockTag};
+use starknet_core::types::{Felt, MaybePendingStateUpdate, PendingStateUpdate, StateUpdate};
 
 use crate::errors::{StarknetRpcApiError, StarknetRpcResult};
 use crate::utils::OptionExt;
@@ -1,4 +1,4 @@
-use starknet_core::types::BlockId;
+use dp_block::BlockId;
 use starknet_types_core::felt::Felt;
 
 use crate::errors::{StarknetRpcApiError, StarknetRpcResult};
@@ -1,4 +1,5 @@
-use starknet_core::types::{BlockId, Transaction};
+use dp_block::BlockId;
+use starknet_core::types::Transaction;
 
 use crate::errors::{StarknetRpcApiError, StarknetRpcResult};
 use crate::Starknet;
@@ -1,10 +1,11 @@
 use dp_block::DeoxysMaybePendingBlockInfo;
 use dp_receipt::ExecutionResult;
 use starknet_core::types::{Felt, TransactionExecutionStatus, TransactionStatus};
+use starknet_providers::{Provider, ProviderError};
 
 use crate::errors::{StarknetRpcApiError, StarknetRpcResult};
 use crate::utils::ResultExt;
-use crate::Starknet;
+use crate::{bail_internal_server_error, Starknet};
 
 /// Gets the Transaction Status, Including Mempool Status and Execution Details
 ///
@@ -13,6 +14,10 @@ use crate::Starknet;
 /// mempool. The status includes both finality status and execution status of the
 /// transaction.
 ///
+/// Transactions found in the database are `ACCEPTED_ON_L1` once their block has been confirmed
+/// on L1, and `ACCEPTED_ON_L2` otherwise, with the execution status of their receipt. The status
+/// of the other transactions, such as `RECEIVED` or `REJECTED`, is asked to the sequencer.
+///
 /// ### Arguments
 ///
 /// * `transaction_hash` - The hash of the transaction for which the status is requested.
@@ -24,12 +29,22 @@ use crate::Starknet;
 ///     confirmed, pending, or rejected.
 ///   - `execution_status`: The execution status of the transaction, providing details on the
 ///     execution outcome if the transaction has been processed.
-pub fn get_transaction_status(starknet: &Starknet, transaction_hash: Felt) -> StarknetRpcResult<TransactionStatus> {
-    let (block, tx_index) = starknet
+pub async fn get_transaction_status(
+    starknet: &Starknet,
+    transaction_hash: Felt,
+) -> StarknetRpcResult<TransactionStatus> {
+    let Some((block, tx_index)) = starknet
         .backend
         .find_tx_hash_block(&transaction_hash)
         .or_internal_server_error("Error find tx hash block info from db")?
-        .ok_or(StarknetRpcApiError::TxnHashNotFound)?;
+    else {
+        // Transactions which have not made it into a block yet, or were rejected, are only known by the sequencer
+        return match starknet.sequencer_provider().get_transaction_status(transaction_hash).await {
+            Ok(status) => Ok(status),
+            Err(ProviderError::StarknetError(e)) => Err(StarknetRpcApiError::from(e)),
+            Err(e) => bail_internal_server_error!("Failed to get the transaction status from the sequencer: {e}"),
+        };
+    };
 
     let tx_receipt = block.inner.receipts.get(tx_index.0 as usize).ok_or(StarknetRpcApiError::TxnHashNotFound)?;
 
@@ -39,14 +54,13 @@ pub fn get_transaction_status(starknet: &Starknet, transaction_hash: Felt) -> St
     };
 
     match block.info {
-        DeoxysMaybePendingBlockInfo::Pending(_) => {
-            Ok(TransactionStatus::Received) // TODO(merge): is that correct?
-        }
+        // Transactions of the pending block have already been executed by the sequencer
+        DeoxysMaybePendingBlockInfo::Pending(_) => Ok(TransactionStatus::AcceptedOnL2(tx_execution_status)),
         DeoxysMaybePendingBlockInfo::NotPending(block) => {
             if block.header.block_number <= starknet.get_l1_last_confirmed_block()? {
-                Ok(TransactionStatus::AcceptedOnL2(tx_execution_status))
-            } else {
                 Ok(TransactionStatus::AcceptedOnL1(tx_execution_status))
+            } else {
+                Ok(TransactionStatus::AcceptedOnL2(tx_execution_status))
             }
         }
     }
@@ -1,7 +1,10 @@
+use std::collections::HashMap;
+
+use dp_block::BlockId;
 use jsonrpsee::core::{async_trait, RpcResult};
 use starknet_core::types::{
-    BlockHashAndNumber, BlockId, BroadcastedTransaction, ContractClass, EventFilterWithPage, EventsPage, FeeEstimate,
-    FunctionCall, MaybePendingBlockWithReceipts, MaybePendingBlockWithTxHashes, MaybePendingBlockWithTxs,
+    BlockHashAndNumber, BroadcastedTransaction, ContractClass, EventFilterWithPage, EventsPage, FeeEstimate,
+    FunctionCall, Hash256, MaybePendingBlockWithReceipts, MaybePendingBlockWithTxHashes, MaybePendingBlockWithTxs,
     MaybePendingStateUpdate, MsgFromL1, SimulationFlagForEstimateFee, SyncStatusType, Transaction,
     TransactionReceiptWithBlockInfo, TransactionStatus,
 };
@@ -18,7 +21,9 @@ use super::get_block_with_txs::*;
 use super::get_class::*;
 use super::get_class_at::*;
 use super::get_class_hash_at::*;
+use super::get_compiled_casm::*;
 use super::get_events::*;
+use super::get_messages_status::*;
 use super::get_nonce::*;
 use super::get_state_update::*;
 use super::get_storage_at::*;
@@ -27,6 +32,7 @@ use super::get_transaction_by_hash::*;
 use super::get_transaction_receipt::*;
 use super::get_transaction_status::*;
 use super::syncing::*;
+use crate::types::{BlockOverrides, ContractStateOverride, MessageStatus};
 use crate::{Starknet, StarknetReadRpcApiServer};
 
 #[async_trait]
@@ -43,8 +49,13 @@ impl StarknetReadRpcApiServer for Starknet {
         Ok(block_hash_and_number(self)?)
     }
 
-    fn call(&self, request: FunctionCall, block_id: BlockId) -> RpcResult<Vec<Felt>> {
-        Ok(call(self, request, block_id)?)
+    async fn call(
+        &self,
+        request: FunctionCall,
+        block_id: BlockId,
+        state_overrides: Option<HashMap<Felt, ContractStateOverride>>,
+    ) -> RpcResult<Vec<Felt>> {
+        Ok(self.spawn_execution(move |starknet| call(starknet, request, block_id, state_overrides)).await?)
     }
 
     fn chain_id(&self) -> RpcResult<Felt> {
@@ -60,12 +71,17 @@ impl StarknetReadRpcApiServer for Starknet {
         request: Vec<BroadcastedTransaction>,
         simulation_flags: Vec<SimulationFlagForEstimateFee>,
         block_id: BlockId,
+        block_overrides: Option<BlockOverrides>,
     ) -> RpcResult<Vec<FeeEstimate>> {
-        Ok(estimate_fee(self, request, simulation_flags, block_id).await?)
+        Ok(self
+            .spawn_execution(move |starknet| {
+                estimate_fee(starknet, request, simulation_flags, block_id, block_overrides)
+            })
+            .await?)
     }
 
     async fn estimate_message_fee(&self, message: MsgFromL1, block_id: BlockId) -> RpcResult<FeeEstimate> {
-        Ok(estimate_message_fee(self, message, block_id).await?)
+        Ok(self.spawn_execution(move |starknet| estimate_message_fee(starknet, message, block_id)).await?)
     }
 
     async fn get_block_with_receipts(&self, block_id: BlockId) -> RpcResult<MaybePendingBlockWithReceipts> {
@@ -92,6 +108,10 @@ impl StarknetReadRpcApiServer for Starknet {
         Ok(get_class(self, block_id, class_hash)?)
     }
 
+    fn get_compiled_casm(&self, class_hash: Felt) -> RpcResult<serde_json::Value> {
+        Ok(get_compiled_casm(self, class_hash)?)
+    }
+
     async fn get_events(&self, filter: EventFilterWithPage) -> RpcResult<EventsPage> {
         Ok(get_events(self, filter).await?)
     }
@@ -116,8 +136,12 @@ impl StarknetReadRpcApiServer for Starknet {
         Ok(get_transaction_receipt(self, transaction_hash).await?)
     }
 
-    fn get_transaction_status(&self, transaction_hash: Felt) -> RpcResult<TransactionStatus> {
-        Ok(get_transaction_status(self, transaction_hash)?)
+    async fn get_transaction_status(&self, transaction_hash: Felt) -> RpcResult<TransactionStatus> {
+        Ok(get_transaction_status(self, transaction_hash).await?)
+    }
+
+    fn get_messages_status(&self, transaction_hash: Hash256) -> RpcResult<Vec<MessageStatus>> {
+        Ok(get_messages_status(self, transaction_hash)?)
     }
 
     async fn syncing(&self) -> RpcResult<SyncStatusType> {
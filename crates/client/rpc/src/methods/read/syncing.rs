@@ -35,11 +35,22 @@ pub async fn syncing(starknet: &Starknet) -> StarknetRpcResult<SyncStatusType> {
     let current_block_num = current_block_info.header.block_number;
     let current_block_hash = current_block_info.block_hash;
 
+    // The highest known block is probed periodically from the gateway, it can lag behind our own
+    // latest block.
+    let (highest_block_num, highest_block_hash) = match starknet
+        .backend
+        .get_highest_known_block()
+        .or_internal_server_error("Error getting highest known block")?
+    {
+        Some(highest) if highest.block_number > current_block_num => (highest.block_number, highest.block_hash),
+        _ => (current_block_num, current_block_hash),
+    };
+
     Ok(SyncStatusType::Syncing(SyncStatus {
         starting_block_num,
         starting_block_hash,
-        highest_block_num: current_block_num, // TODO(merge): is this correct,,?
-        highest_block_hash: current_block_hash,
+        highest_block_num,
+        highest_block_hash,
         current_block_num,
         current_block_hash,
     }))
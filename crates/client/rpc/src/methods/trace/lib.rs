@@ -1,11 +1,13 @@
+use dp_block::BlockId;
 use jsonrpsee::core::{async_trait, RpcResult};
 use starknet_core::types::{
-    BlockId, BroadcastedTransaction, Felt, SimulatedTransaction, SimulationFlag, TransactionTraceWithHash,
+    BroadcastedTransaction, Felt, SimulatedTransaction, SimulationFlag, TransactionTraceWithHash,
 };
 
 use super::simulate_transactions::simulate_transactions;
 use super::trace_block_transactions::trace_block_transactions;
 use super::trace_transaction::trace_transaction;
+use crate::types::BlockOverrides;
 use crate::{Starknet, StarknetTraceRpcApiServer};
 
 #[async_trait]
@@ -15,15 +17,20 @@ impl StarknetTraceRpcApiServer for Starknet {
         block_id: BlockId,
         transactions: Vec<BroadcastedTransaction>,
         simulation_flags: Vec<SimulationFlag>,
+        block_overrides: Option<BlockOverrides>,
     ) -> RpcResult<Vec<SimulatedTransaction>> {
-        Ok(simulate_transactions(self, block_id, transactions, simulation_flags).await?)
+        Ok(self
+            .spawn_execution(move |starknet| {
+                simulate_transactions(starknet, block_id, transactions, simulation_flags, block_overrides)
+            })
+            .await?)
     }
 
     async fn trace_block_transactions(&self, block_id: BlockId) -> RpcResult<Vec<TransactionTraceWithHash>> {
-        Ok(trace_block_transactions(self, block_id).await?)
+        Ok(self.spawn_execution(move |starknet| trace_block_transactions(starknet, block_id)).await?)
     }
 
     async fn trace_transaction(&self, transaction_hash: Felt) -> RpcResult<TransactionTraceWithHash> {
-        Ok(trace_transaction(self, transaction_hash).await?)
+        Ok(self.spawn_execution(move |starknet| trace_transaction(starknet, transaction_hash)).await?)
     }
 }
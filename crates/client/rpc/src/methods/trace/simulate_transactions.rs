@@ -1,26 +1,26 @@
 use crate::errors::{StarknetRpcApiError, StarknetRpcResult};
+use crate::types::BlockOverrides;
 use crate::utils::ResultExt;
 use crate::Starknet;
-use dc_exec::{execution_result_to_tx_trace, ExecutionContext};
+use dc_exec::execution_result_to_tx_trace;
+use dp_block::BlockId;
 use dp_transactions::broadcasted_to_blockifier;
-use starknet_core::types::{BlockId, BroadcastedTransaction, SimulatedTransaction, SimulationFlag};
+use starknet_core::types::{BroadcastedTransaction, SimulatedTransaction, SimulationFlag};
 
-use super::trace_transaction::FALLBACK_TO_SEQUENCER_WHEN_VERSION_BELOW;
-
-pub async fn simulate_transactions(
+pub fn simulate_transactions(
     starknet: &Starknet,
     block_id: BlockId,
     transactions: Vec<BroadcastedTransaction>,
     simulation_flags: Vec<SimulationFlag>,
+    block_overrides: Option<BlockOverrides>,
 ) -> StarknetRpcResult<Vec<SimulatedTransaction>> {
     let block_info = starknet.get_block_info(&block_id)?;
-
-    if block_info.protocol_version() < &FALLBACK_TO_SEQUENCER_WHEN_VERSION_BELOW {
-        return Err(StarknetRpcApiError::UnsupportedTxnVersion);
+    let mut exec_context = starknet.execution_context(&block_info)?;
+    if let Some(block_overrides) = block_overrides {
+        exec_context = exec_context.with_header_overrides(&block_overrides.try_into()?)?;
     }
-    let exec_context = ExecutionContext::new(&starknet.backend, &block_info)?;
 
-    let charge_fee = !simulation_flags.contains(&SimulationFlag::SkipFeeCharge);
+    let charge_fee = starknet.enforce_fee_charge || !simulation_flags.contains(&SimulationFlag::SkipFeeCharge);
     let validate = !simulation_flags.contains(&SimulationFlag::SkipValidate);
 
     let user_transactions = transactions
@@ -29,7 +29,13 @@ pub async fn simulate_transactions(
         .collect::<Result<Vec<_>, _>>()
         .or_internal_server_error("Failed to convert broadcasted transaction to blockifier")?;
 
-    let execution_resuls = exec_context.execute_transactions([], user_transactions, charge_fee, validate)?;
+    let execution_resuls = exec_context.execute_transactions_cached(
+        &starknet.execution_cache,
+        vec![],
+        user_transactions,
+        charge_fee,
+        validate,
+    )?;
 
     let simulated_transactions = execution_resuls
         .iter()
@@ -1,39 +1,42 @@
-use dc_exec::{execution_result_to_tx_trace, ExecutionContext};
-use dp_convert::{ToFelt, ToStarkFelt};
-use starknet_api::transaction::TransactionHash;
-use starknet_core::types::{BlockId, TransactionTraceWithHash};
+use dc_exec::{block_to_blockifier_transactions, execution_result_to_tx_trace};
+use dp_block::BlockId;
+use dp_convert::ToFelt;
+use starknet_core::types::TransactionTraceWithHash;
 
-use super::trace_transaction::FALLBACK_TO_SEQUENCER_WHEN_VERSION_BELOW;
 use crate::errors::{StarknetRpcApiError, StarknetRpcResult};
-use crate::utils::transaction::to_blockifier_transactions;
 use crate::utils::ResultExt;
 use crate::Starknet;
 
-pub async fn trace_block_transactions(
+/// Returns the execution traces of all the transactions of a block.
+///
+/// ### Arguments
+///
+/// * `block_id` - The hash of the requested block, or number (height) of the requested block, or a
+///   block tag.
+///
+/// ### Returns
+///
+/// The traces of the transactions, in block order. The block is re-executed on top of the state of its
+/// parent block. Returns `BLOCK_NOT_FOUND` if the block does not exist. Blocks older than Starknet
+/// 0.13.0 cannot be traced unless versioned constants are loaded for them.
+///
+/// The block is always executed again, but the execution results of confirmed blocks are put in the
+/// execution cache, so that tracing one of their transactions afterwards does not re-execute the block
+/// again.
+pub fn trace_block_transactions(
     starknet: &Starknet,
     block_id: BlockId,
 ) -> StarknetRpcResult<Vec<TransactionTraceWithHash>> {
     let block = starknet.get_block(&block_id)?;
 
-    if block.info.protocol_version() < &FALLBACK_TO_SEQUENCER_WHEN_VERSION_BELOW {
-        return Err(StarknetRpcApiError::UnsupportedTxnVersion);
-    }
+    let exec_context = starknet.execution_context(&block.info)?;
 
-    let exec_context = ExecutionContext::new(&starknet.backend, &block.info)?;
+    let transactions: Vec<_> = block_to_blockifier_transactions(&starknet.backend, &block).collect::<Result<_, _>>()?;
 
-    let transactions: Vec<_> = block
-        .inner
-        .transactions
-        .iter()
-        .zip(block.info.tx_hashes())
-        .map(|(tx, hash)| {
-            to_blockifier_transactions(starknet, block_id.into(), tx, &TransactionHash(hash.to_stark_felt()))
-        })
-        .collect::<Result<_, _>>()?;
-
-    let executions_results = exec_context.execute_transactions([], transactions, true, true)?;
+    let block_execution = exec_context.execute_block(Some(&starknet.execution_cache), transactions)?;
 
-    let traces = executions_results
+    let traces = block_execution
+        .results
         .into_iter()
         .map(|result| {
             let transaction_hash = result.hash.to_felt();
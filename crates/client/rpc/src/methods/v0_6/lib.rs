@@ -0,0 +1,75 @@
+use dp_block::BlockId;
+use jsonrpsee::core::{async_trait, RpcResult};
+use starknet_core::types::{
+    BroadcastedTransaction, Felt, MsgFromL1, SimulationFlag, SimulationFlagForEstimateFee, Transaction,
+};
+
+use super::types::{
+    FeeEstimate, MaybePendingBlock, SimulatedTransaction, TransactionReceipt, TransactionTraceWithHash,
+};
+use crate::types::BlockOverrides;
+use crate::{
+    Starknet, StarknetReadRpcApiServer, StarknetTraceRpcApiServer, StarknetV0_6ReadRpcApiServer,
+    StarknetV0_6TraceRpcApiServer,
+};
+
+#[async_trait]
+impl StarknetV0_6ReadRpcApiServer for Starknet {
+    async fn estimate_fee(
+        &self,
+        request: Vec<BroadcastedTransaction>,
+        simulation_flags: Vec<SimulationFlagForEstimateFee>,
+        block_id: BlockId,
+        block_overrides: Option<BlockOverrides>,
+    ) -> RpcResult<Vec<FeeEstimate>> {
+        let fees =
+            StarknetReadRpcApiServer::estimate_fee(self, request, simulation_flags, block_id, block_overrides).await?;
+        Ok(fees.into_iter().map(Into::into).collect())
+    }
+
+    async fn estimate_message_fee(&self, message: MsgFromL1, block_id: BlockId) -> RpcResult<FeeEstimate> {
+        Ok(StarknetReadRpcApiServer::estimate_message_fee(self, message, block_id).await?.into())
+    }
+
+    fn get_block_with_tx_hashes(&self, block_id: BlockId) -> RpcResult<MaybePendingBlock<Felt>> {
+        Ok(StarknetReadRpcApiServer::get_block_with_tx_hashes(self, block_id)?.into())
+    }
+
+    fn get_block_with_txs(&self, block_id: BlockId) -> RpcResult<MaybePendingBlock<Transaction>> {
+        Ok(StarknetReadRpcApiServer::get_block_with_txs(self, block_id)?.into())
+    }
+
+    async fn get_transaction_receipt(&self, transaction_hash: Felt) -> RpcResult<TransactionReceipt> {
+        Ok(StarknetReadRpcApiServer::get_transaction_receipt(self, transaction_hash).await?.into())
+    }
+}
+
+#[async_trait]
+impl StarknetV0_6TraceRpcApiServer for Starknet {
+    async fn simulate_transactions(
+        &self,
+        block_id: BlockId,
+        transactions: Vec<BroadcastedTransaction>,
+        simulation_flags: Vec<SimulationFlag>,
+        block_overrides: Option<BlockOverrides>,
+    ) -> RpcResult<Vec<SimulatedTransaction>> {
+        let simulated = StarknetTraceRpcApiServer::simulate_transactions(
+            self,
+            block_id,
+            transactions,
+            simulation_flags,
+            block_overrides,
+        )
+        .await?;
+        Ok(simulated.into_iter().map(Into::into).collect())
+    }
+
+    async fn trace_block_transactions(&self, block_id: BlockId) -> RpcResult<Vec<TransactionTraceWithHash>> {
+        let traces = StarknetTraceRpcApiServer::trace_block_transactions(self, block_id).await?;
+        Ok(traces.into_iter().map(Into::into).collect())
+    }
+
+    async fn trace_transaction(&self, transaction_hash: Felt) -> RpcResult<TransactionTraceWithHash> {
+        Ok(StarknetTraceRpcApiServer::trace_transaction(self, transaction_hash).await?.into())
+    }
+}
@@ -0,0 +1,2 @@
+pub mod lib;
+pub mod types;
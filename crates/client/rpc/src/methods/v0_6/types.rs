@@ -0,0 +1,401 @@
+//! Requests and responses of the JSON-RPC specification 0.6 whose shapes differ from the ones of 0.7.
+//!
+//! 0.7 only added fields to them: the L1 data gas price and the data availability mode of the block headers, the
+//! data availability resources of the receipts and traces, and the data gas of the fee estimates. The 0.6 shapes
+//! are converted from the 0.7 ones by leaving these fields out.
+
+use serde::Serialize;
+use starknet_core::types::{
+    BlockStatus, ComputationResources, Event, ExecuteInvocation, FeePayment, Felt, FunctionInvocation, Hash256,
+    MsgToL1, PriceUnit, ResourcePrice, StateDiff, Transaction, TransactionExecutionStatus, TransactionFinalityStatus,
+};
+
+#[derive(Clone, Debug, Serialize)]
+pub struct FeeEstimate {
+    pub gas_consumed: Felt,
+    pub gas_price: Felt,
+    pub overall_fee: Felt,
+    pub unit: PriceUnit,
+}
+
+impl From<starknet_core::types::FeeEstimate> for FeeEstimate {
+    fn from(fee: starknet_core::types::FeeEstimate) -> Self {
+        Self { gas_consumed: fee.gas_consumed, gas_price: fee.gas_price, overall_fee: fee.overall_fee, unit: fee.unit }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct BlockWithTransactions<T> {
+    pub status: BlockStatus,
+    pub block_hash: Felt,
+    pub parent_hash: Felt,
+    pub block_number: u64,
+    pub new_root: Felt,
+    pub timestamp: u64,
+    pub sequencer_address: Felt,
+    pub l1_gas_price: ResourcePrice,
+    pub starknet_version: String,
+    pub transactions: Vec<T>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PendingBlockWithTransactions<T> {
+    pub parent_hash: Felt,
+    pub timestamp: u64,
+    pub sequencer_address: Felt,
+    pub l1_gas_price: ResourcePrice,
+    pub starknet_version: String,
+    pub transactions: Vec<T>,
+}
+
+/// A block, with the hashes of its transactions or its full transactions.
+#[derive(Clone, Debug, Serialize)]
+#[serde(untagged)]
+pub enum MaybePendingBlock<T> {
+    Block(BlockWithTransactions<T>),
+    PendingBlock(PendingBlockWithTransactions<T>),
+}
+
+impl From<starknet_core::types::MaybePendingBlockWithTxHashes> for MaybePendingBlock<Felt> {
+    fn from(block: starknet_core::types::MaybePendingBlockWithTxHashes) -> Self {
+        use starknet_core::types::MaybePendingBlockWithTxHashes;
+        match block {
+            MaybePendingBlockWithTxHashes::Block(block) => Self::Block(BlockWithTransactions {
+                status: block.status,
+                block_hash: block.block_hash,
+                parent_hash: block.parent_hash,
+                block_number: block.block_number,
+                new_root: block.new_root,
+                timestamp: block.timestamp,
+                sequencer_address: block.sequencer_address,
+                l1_gas_price: block.l1_gas_price,
+                starknet_version: block.starknet_version,
+                transactions: block.transactions,
+            }),
+            MaybePendingBlockWithTxHashes::PendingBlock(block) => Self::PendingBlock(PendingBlockWithTransactions {
+                parent_hash: block.parent_hash,
+                timestamp: block.timestamp,
+                sequencer_address: block.sequencer_address,
+                l1_gas_price: block.l1_gas_price,
+                starknet_version: block.starknet_version,
+                transactions: block.transactions,
+            }),
+        }
+    }
+}
+
+impl From<starknet_core::types::MaybePendingBlockWithTxs> for MaybePendingBlock<Transaction> {
+    fn from(block: starknet_core::types::MaybePendingBlockWithTxs) -> Self {
+        use starknet_core::types::MaybePendingBlockWithTxs;
+        match block {
+            MaybePendingBlockWithTxs::Block(block) => Self::Block(BlockWithTransactions {
+                status: block.status,
+                block_hash: block.block_hash,
+                parent_hash: block.parent_hash,
+                block_number: block.block_number,
+                new_root: block.new_root,
+                timestamp: block.timestamp,
+                sequencer_address: block.sequencer_address,
+                l1_gas_price: block.l1_gas_price,
+                starknet_version: block.starknet_version,
+                transactions: block.transactions,
+            }),
+            MaybePendingBlockWithTxs::PendingBlock(block) => Self::PendingBlock(PendingBlockWithTransactions {
+                parent_hash: block.parent_hash,
+                timestamp: block.timestamp,
+                sequencer_address: block.sequencer_address,
+                l1_gas_price: block.l1_gas_price,
+                starknet_version: block.starknet_version,
+                transactions: block.transactions,
+            }),
+        }
+    }
+}
+
+/// The properties shared by the receipts of every transaction type. The execution resources are the computation
+/// resources of 0.7.
+#[derive(Clone, Debug, Serialize)]
+pub struct ReceiptProperties {
+    pub transaction_hash: Felt,
+    pub actual_fee: FeePayment,
+    pub execution_status: TransactionExecutionStatus,
+    pub finality_status: TransactionFinalityStatus,
+    /// `None` for the pending block.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_hash: Option<Felt>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    pub messages_sent: Vec<MsgToL1>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revert_reason: Option<String>,
+    pub events: Vec<Event>,
+    pub execution_resources: ComputationResources,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TransactionReceipt {
+    Invoke(ReceiptProperties),
+    L1Handler {
+        #[serde(flatten)]
+        properties: ReceiptProperties,
+        message_hash: Hash256,
+    },
+    Declare(ReceiptProperties),
+    Deploy {
+        #[serde(flatten)]
+        properties: ReceiptProperties,
+        contract_address: Felt,
+    },
+    DeployAccount {
+        #[serde(flatten)]
+        properties: ReceiptProperties,
+        contract_address: Felt,
+    },
+}
+
+impl From<starknet_core::types::TransactionReceiptWithBlockInfo> for TransactionReceipt {
+    fn from(receipt: starknet_core::types::TransactionReceiptWithBlockInfo) -> Self {
+        use starknet_core::types::{ExecutionResult, ReceiptBlock, TransactionReceipt as Receipt};
+
+        let (block_hash, block_number) = match receipt.block {
+            ReceiptBlock::Pending => (None, None),
+            ReceiptBlock::Block { block_hash, block_number } => (Some(block_hash), Some(block_number)),
+        };
+        let properties = |transaction_hash,
+                          actual_fee,
+                          finality_status,
+                          messages_sent,
+                          events,
+                          execution_resources: starknet_core::types::ExecutionResources,
+                          execution_result| {
+            let (execution_status, revert_reason) = match execution_result {
+                ExecutionResult::Succeeded => (TransactionExecutionStatus::Succeeded, None),
+                ExecutionResult::Reverted { reason } => (TransactionExecutionStatus::Reverted, Some(reason)),
+            };
+            ReceiptProperties {
+                transaction_hash,
+                actual_fee,
+                execution_status,
+                finality_status,
+                block_hash,
+                block_number,
+                messages_sent,
+                revert_reason,
+                events,
+                execution_resources: execution_resources.computation_resources,
+            }
+        };
+
+        match receipt.receipt {
+            Receipt::Invoke(r) => Self::Invoke(properties(
+                r.transaction_hash,
+                r.actual_fee,
+                r.finality_status,
+                r.messages_sent,
+                r.events,
+                r.execution_resources,
+                r.execution_result,
+            )),
+            Receipt::L1Handler(r) => Self::L1Handler {
+                properties: properties(
+                    r.transaction_hash,
+                    r.actual_fee,
+                    r.finality_status,
+                    r.messages_sent,
+                    r.events,
+                    r.execution_resources,
+                    r.execution_result,
+                ),
+                message_hash: r.message_hash,
+            },
+            Receipt::Declare(r) => Self::Declare(properties(
+                r.transaction_hash,
+                r.actual_fee,
+                r.finality_status,
+                r.messages_sent,
+                r.events,
+                r.execution_resources,
+                r.execution_result,
+            )),
+            Receipt::Deploy(r) => Self::Deploy {
+                properties: properties(
+                    r.transaction_hash,
+                    r.actual_fee,
+                    r.finality_status,
+                    r.messages_sent,
+                    r.events,
+                    r.execution_resources,
+                    r.execution_result,
+                ),
+                contract_address: r.contract_address,
+            },
+            Receipt::DeployAccount(r) => Self::DeployAccount {
+                properties: properties(
+                    r.transaction_hash,
+                    r.actual_fee,
+                    r.finality_status,
+                    r.messages_sent,
+                    r.events,
+                    r.execution_resources,
+                    r.execution_result,
+                ),
+                contract_address: r.contract_address,
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TransactionTrace {
+    Invoke {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        validate_invocation: Option<FunctionInvocation>,
+        execute_invocation: ExecuteInvocation,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        fee_transfer_invocation: Option<FunctionInvocation>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        state_diff: Option<StateDiff>,
+    },
+    Declare {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        validate_invocation: Option<FunctionInvocation>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        fee_transfer_invocation: Option<FunctionInvocation>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        state_diff: Option<StateDiff>,
+    },
+    DeployAccount {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        validate_invocation: Option<FunctionInvocation>,
+        constructor_invocation: FunctionInvocation,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        fee_transfer_invocation: Option<FunctionInvocation>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        state_diff: Option<StateDiff>,
+    },
+    L1Handler {
+        function_invocation: FunctionInvocation,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        state_diff: Option<StateDiff>,
+    },
+}
+
+impl From<starknet_core::types::TransactionTrace> for TransactionTrace {
+    fn from(trace: starknet_core::types::TransactionTrace) -> Self {
+        use starknet_core::types::TransactionTrace as Trace;
+        match trace {
+            Trace::Invoke(trace) => Self::Invoke {
+                validate_invocation: trace.validate_invocation,
+                execute_invocation: trace.execute_invocation,
+                fee_transfer_invocation: trace.fee_transfer_invocation,
+                state_diff: trace.state_diff,
+            },
+            Trace::Declare(trace) => Self::Declare {
+                validate_invocation: trace.validate_invocation,
+                fee_transfer_invocation: trace.fee_transfer_invocation,
+                state_diff: trace.state_diff,
+            },
+            Trace::DeployAccount(trace) => Self::DeployAccount {
+                validate_invocation: trace.validate_invocation,
+                constructor_invocation: trace.constructor_invocation,
+                fee_transfer_invocation: trace.fee_transfer_invocation,
+                state_diff: trace.state_diff,
+            },
+            Trace::L1Handler(trace) => {
+                Self::L1Handler { function_invocation: trace.function_invocation, state_diff: trace.state_diff }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TransactionTraceWithHash {
+    pub transaction_hash: Felt,
+    pub trace_root: TransactionTrace,
+}
+
+impl From<starknet_core::types::TransactionTraceWithHash> for TransactionTraceWithHash {
+    fn from(trace: starknet_core::types::TransactionTraceWithHash) -> Self {
+        Self { transaction_hash: trace.transaction_hash, trace_root: trace.trace_root.into() }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SimulatedTransaction {
+    pub transaction_trace: TransactionTrace,
+    pub fee_estimation: FeeEstimate,
+}
+
+impl From<starknet_core::types::SimulatedTransaction> for SimulatedTransaction {
+    fn from(simulated: starknet_core::types::SimulatedTransaction) -> Self {
+        Self { transaction_trace: simulated.transaction_trace.into(), fee_estimation: simulated.fee_estimation.into() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fee_estimate_shape() {
+        let fee = FeeEstimate::from(starknet_core::types::FeeEstimate {
+            gas_consumed: Felt::from(10),
+            gas_price: Felt::from(2),
+            data_gas_consumed: Felt::from(3),
+            data_gas_price: Felt::from(1),
+            overall_fee: Felt::from(23),
+            unit: PriceUnit::Wei,
+        });
+        let value = serde_json::to_value(fee).unwrap();
+        assert_eq!(value["overall_fee"], serde_json::json!("0x17"));
+        assert!(value.get("data_gas_consumed").is_none());
+        assert!(value.get("data_gas_price").is_none());
+    }
+
+    #[test]
+    fn test_receipt_shape() {
+        let receipt = starknet_core::types::TransactionReceiptWithBlockInfo {
+            receipt: starknet_core::types::TransactionReceipt::Deploy(starknet_core::types::DeployTransactionReceipt {
+                transaction_hash: Felt::ONE,
+                actual_fee: FeePayment { amount: Felt::TWO, unit: PriceUnit::Fri },
+                finality_status: TransactionFinalityStatus::AcceptedOnL2,
+                messages_sent: vec![],
+                events: vec![],
+                execution_resources: starknet_core::types::ExecutionResources {
+                    computation_resources: ComputationResources {
+                        steps: 100,
+                        memory_holes: None,
+                        range_check_builtin_applications: Some(3),
+                        pedersen_builtin_applications: None,
+                        poseidon_builtin_applications: None,
+                        ec_op_builtin_applications: None,
+                        ecdsa_builtin_applications: None,
+                        bitwise_builtin_applications: None,
+                        keccak_builtin_applications: None,
+                        segment_arena_builtin: None,
+                    },
+                    data_resources: starknet_core::types::DataResources {
+                        data_availability: starknet_core::types::DataAvailabilityResources {
+                            l1_gas: 0,
+                            l1_data_gas: 128,
+                        },
+                    },
+                },
+                execution_result: starknet_core::types::ExecutionResult::Reverted { reason: "out of gas".into() },
+                contract_address: Felt::THREE,
+            }),
+            block: starknet_core::types::ReceiptBlock::Pending,
+        };
+
+        let value = serde_json::to_value(TransactionReceipt::from(receipt)).unwrap();
+        assert_eq!(value["type"], "DEPLOY");
+        assert_eq!(value["execution_status"], "REVERTED");
+        assert_eq!(value["revert_reason"], "out of gas");
+        assert_eq!(value["contract_address"], "0x3");
+        assert_eq!(value["execution_resources"]["steps"], 100);
+        assert!(value["execution_resources"].get("data_availability").is_none());
+        assert!(value.get("block_hash").is_none());
+    }
+}
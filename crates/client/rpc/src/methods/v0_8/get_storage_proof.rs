@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+
+use dc_db::proof_db::ProofNode;
+use dp_block::BlockId;
+use starknet_core::types::Felt;
+use starknet_types_core::hash::{Pedersen, Poseidon, StarkHash};
+
+use crate::constants::MAX_STORAGE_PROOF_KEYS;
+use crate::errors::{StarknetRpcApiError, StarknetRpcResult};
+use crate::types::{ContractLeafData, ContractStorageKeys, ContractsProof, GlobalRoots, NodeHashToNode, StorageProof};
+use crate::utils::ResultExt;
+use crate::Starknet;
+
+/// Get the merkle proofs of classes, contracts and contract storage keys, along with the global roots.
+///
+/// ### Arguments
+///
+/// * `block_id` - The block to prove the state of. The tries only hold the latest state, so this has to
+///   resolve to the latest block.
+/// * `class_hashes` - The classes to prove in the class trie.
+/// * `contract_addresses` - The contracts to prove in the contract trie, which do not need to be deployed.
+/// * `contracts_storage_keys` - The storage keys to prove in the storage trie of each contract.
+///
+/// ### Returns
+///
+/// The nodes of the proofs in each trie, keyed by their hash, the leaf data of the requested contracts, and
+/// the roots of the contract and class tries along with the hash of the proven block. Returns
+/// `PROOF_LIMIT_EXCEEDED` if more than 100 items are requested, or `STORAGE_PROOF_NOT_SUPPORTED` if the
+/// block is not the latest block.
+pub fn get_storage_proof(
+    starknet: &Starknet,
+    block_id: BlockId,
+    class_hashes: Vec<Felt>,
+    contract_addresses: Vec<Felt>,
+    contracts_storage_keys: Vec<ContractStorageKeys>,
+) -> StarknetRpcResult<StorageProof> {
+    let n_items = class_hashes.len()
+        + contract_addresses.len()
+        + contracts_storage_keys.iter().map(|contract| contract.storage_keys.len()).sum::<usize>();
+    if n_items > MAX_STORAGE_PROOF_KEYS {
+        return Err(StarknetRpcApiError::ProofLimitExceeded);
+    }
+
+    let block_info = starknet.get_block_info(&block_id)?;
+    // the pending state is not merkelized
+    let block_info = block_info.as_nonpending().ok_or(StarknetRpcApiError::StorageProofNotSupported)?;
+    if block_info.header.block_number != starknet.current_block_number()? {
+        return Err(StarknetRpcApiError::StorageProofNotSupported);
+    }
+
+    let backend = &starknet.backend;
+
+    let mut classes_proof = ProofNodes::default();
+    for class_hash in &class_hashes {
+        let proof = backend.get_class_proof(class_hash).or_internal_server_error("Error getting class proof")?;
+        classes_proof.extend::<Poseidon>(proof);
+    }
+
+    let mut contracts_proof = ProofNodes::default();
+    let mut contract_leaves_data = Vec::with_capacity(contract_addresses.len());
+    for contract_address in &contract_addresses {
+        let proof =
+            backend.get_contract_proof(contract_address).or_internal_server_error("Error getting contract proof")?;
+        contracts_proof.extend::<Pedersen>(proof);
+
+        let class_hash = backend
+            .get_contract_class_hash_at(&block_id, contract_address)
+            .or_internal_server_error("Error getting contract class hash")?
+            .unwrap_or(Felt::ZERO);
+        let nonce = backend
+            .get_contract_nonce_at(&block_id, contract_address)
+            .or_internal_server_error("Error getting contract nonce")?
+            .unwrap_or(Felt::ZERO);
+        contract_leaves_data.push(ContractLeafData { nonce, class_hash });
+    }
+
+    let contracts_storage_proofs = contracts_storage_keys
+        .iter()
+        .map(|contract| {
+            let proofs = backend
+                .get_contract_storage_proofs(&contract.contract_address, &contract.storage_keys)
+                .or_internal_server_error("Error getting contract storage proofs")?;
+            let mut storage_proof = ProofNodes::default();
+            for proof in proofs {
+                storage_proof.extend::<Pedersen>(proof);
+            }
+            Ok(storage_proof.into_nodes())
+        })
+        .collect::<StarknetRpcResult<_>>()?;
+
+    let global_roots = GlobalRoots {
+        contracts_tree_root: backend
+            .get_contract_trie_root()
+            .or_internal_server_error("Error getting contract trie root")?,
+        classes_tree_root: backend.get_class_trie_root().or_internal_server_error("Error getting class trie root")?,
+        block_hash: block_info.block_hash,
+    };
+
+    Ok(StorageProof {
+        classes_proof: classes_proof.into_nodes(),
+        contracts_proof: ContractsProof { nodes: contracts_proof.into_nodes(), contract_leaves_data },
+        contracts_storage_proofs,
+        global_roots,
+    })
+}
+
+/// The nodes of several proofs in the same trie, which share the nodes close to the root.
+#[derive(Default)]
+struct ProofNodes(HashMap<Felt, ProofNode>);
+
+impl ProofNodes {
+    fn extend<H: StarkHash>(&mut self, proof: Vec<ProofNode>) {
+        self.0.extend(proof.into_iter().map(|node| (node.hash::<H>(), node)));
+    }
+
+    fn into_nodes(self) -> Vec<NodeHashToNode> {
+        self.0.into_iter().map(|(node_hash, node)| NodeHashToNode { node_hash, node: node.into() }).collect()
+    }
+}
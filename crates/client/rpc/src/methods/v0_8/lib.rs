@@ -0,0 +1,25 @@
+use dp_block::BlockId;
+use jsonrpsee::core::RpcResult;
+use starknet_core::types::Felt;
+
+use super::get_storage_proof::*;
+use crate::types::{ContractStorageKeys, StorageProof};
+use crate::{Starknet, StarknetV0_8ReadRpcApiServer};
+
+impl StarknetV0_8ReadRpcApiServer for Starknet {
+    fn get_storage_proof(
+        &self,
+        block_id: BlockId,
+        class_hashes: Option<Vec<Felt>>,
+        contract_addresses: Option<Vec<Felt>>,
+        contracts_storage_keys: Option<Vec<ContractStorageKeys>>,
+    ) -> RpcResult<StorageProof> {
+        Ok(get_storage_proof(
+            self,
+            block_id,
+            class_hashes.unwrap_or_default(),
+            contract_addresses.unwrap_or_default(),
+            contracts_storage_keys.unwrap_or_default(),
+        )?)
+    }
+}
@@ -0,0 +1,2 @@
+pub mod get_storage_proof;
+pub mod lib;
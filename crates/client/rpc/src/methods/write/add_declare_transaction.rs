@@ -1,7 +1,8 @@
-use starknet_core::types::{BroadcastedDeclareTransaction, DeclareTransactionResult};
+use starknet_core::types::{BroadcastedDeclareTransaction, BroadcastedTransaction, DeclareTransactionResult};
 use starknet_providers::{Provider, ProviderError};
 
 use crate::errors::{StarknetRpcApiError, StarknetRpcResult};
+use crate::utils::transaction::{check_gateway_transaction_hash, validate_broadcasted_transaction};
 use crate::{bail_internal_server_error, Starknet};
 
 /// Submit a new declare transaction to be added to the chain
@@ -17,6 +18,16 @@ pub async fn add_declare_transaction(
     starknet: &Starknet,
     declare_transaction: BroadcastedDeclareTransaction,
 ) -> StarknetRpcResult<DeclareTransactionResult> {
+    let local_hash =
+        validate_broadcasted_transaction(starknet, BroadcastedTransaction::Declare(declare_transaction.clone()))
+            .await?;
+
+    if let Some(devnet) = starknet.devnet() {
+        let added = devnet.add_transaction(BroadcastedTransaction::Declare(declare_transaction)).await?;
+        let class_hash = added.class_hash.expect("declare transactions declare a class");
+        return Ok(DeclareTransactionResult { transaction_hash: added.transaction_hash, class_hash });
+    }
+
     let sequencer = starknet.sequencer_provider();
 
     let sequencer_response = match sequencer.add_declare_transaction(declare_transaction).await {
@@ -24,8 +35,9 @@ pub async fn add_declare_transaction(
         Err(ProviderError::StarknetError(e)) => {
             return Err(StarknetRpcApiError::from(e));
         }
-        Err(e) => bail_internal_server_error!("Failed to add invoke transaction to sequencer: {e}"),
+        Err(e) => bail_internal_server_error!("Failed to add declare transaction to sequencer: {e}"),
     };
 
+    check_gateway_transaction_hash(local_hash, sequencer_response.transaction_hash)?;
     Ok(sequencer_response)
 }
@@ -1,7 +1,10 @@
-use starknet_core::types::{BroadcastedDeployAccountTransaction, DeployAccountTransactionResult};
+use starknet_core::types::{
+    BroadcastedDeployAccountTransaction, BroadcastedTransaction, DeployAccountTransactionResult,
+};
 use starknet_providers::{Provider, ProviderError};
 
 use crate::errors::{StarknetRpcApiError, StarknetRpcResult};
+use crate::utils::transaction::{check_gateway_transaction_hash, validate_broadcasted_transaction};
 use crate::{bail_internal_server_error, Starknet};
 
 /// Add an Deploy Account Transaction
@@ -18,6 +21,18 @@ pub async fn add_deploy_account_transaction(
     starknet: &Starknet,
     deploy_account_transaction: BroadcastedDeployAccountTransaction,
 ) -> StarknetRpcResult<DeployAccountTransactionResult> {
+    let local_hash = validate_broadcasted_transaction(
+        starknet,
+        BroadcastedTransaction::DeployAccount(deploy_account_transaction.clone()),
+    )
+    .await?;
+
+    if let Some(devnet) = starknet.devnet() {
+        let added = devnet.add_transaction(BroadcastedTransaction::DeployAccount(deploy_account_transaction)).await?;
+        let contract_address = added.contract_address.expect("deploy account transactions deploy an account");
+        return Ok(DeployAccountTransactionResult { transaction_hash: added.transaction_hash, contract_address });
+    }
+
     let sequencer = starknet.sequencer_provider();
 
     let sequencer_response = match sequencer.add_deploy_account_transaction(deploy_account_transaction).await {
@@ -25,8 +40,9 @@ pub async fn add_deploy_account_transaction(
         Err(ProviderError::StarknetError(e)) => {
             return Err(StarknetRpcApiError::from(e));
         }
-        Err(e) => bail_internal_server_error!("Failed to add invoke transaction to sequencer: {e}"),
+        Err(e) => bail_internal_server_error!("Failed to add deploy account transaction to sequencer: {e}"),
     };
 
+    check_gateway_transaction_hash(local_hash, sequencer_response.transaction_hash)?;
     Ok(sequencer_response)
 }
@@ -1,7 +1,8 @@
-use starknet_core::types::{BroadcastedInvokeTransaction, InvokeTransactionResult};
+use starknet_core::types::{BroadcastedInvokeTransaction, BroadcastedTransaction, InvokeTransactionResult};
 use starknet_providers::{Provider, ProviderError};
 
 use crate::errors::{StarknetRpcApiError, StarknetRpcResult};
+use crate::utils::transaction::{check_gateway_transaction_hash, validate_broadcasted_transaction};
 use crate::{bail_internal_server_error, Starknet};
 
 /// Add an Invoke Transaction to invoke a contract function
@@ -17,6 +18,14 @@ pub async fn add_invoke_transaction(
     starknet: &Starknet,
     invoke_transaction: BroadcastedInvokeTransaction,
 ) -> StarknetRpcResult<InvokeTransactionResult> {
+    let local_hash =
+        validate_broadcasted_transaction(starknet, BroadcastedTransaction::Invoke(invoke_transaction.clone())).await?;
+
+    if let Some(devnet) = starknet.devnet() {
+        let added = devnet.add_transaction(BroadcastedTransaction::Invoke(invoke_transaction)).await?;
+        return Ok(InvokeTransactionResult { transaction_hash: added.transaction_hash });
+    }
+
     let sequencer = starknet.sequencer_provider();
 
     let sequencer_response = match sequencer.add_invoke_transaction(invoke_transaction).await {
@@ -24,8 +33,9 @@ pub async fn add_invoke_transaction(
         Err(ProviderError::StarknetError(e)) => {
             return Err(StarknetRpcApiError::from(e));
         }
-        Err(e) => bail_internal_server_error!("Failed to add invoke transaction to sequencer: {e:#}"),
+        Err(e) => bail_internal_server_error!("Failed to add invoke transaction to sequencer: {e}"),
     };
 
+    check_gateway_transaction_hash(local_hash, sequencer_response.transaction_hash)?;
     Ok(sequencer_response)
 }
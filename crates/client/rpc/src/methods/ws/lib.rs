@@ -0,0 +1,38 @@
+use dp_block::BlockId;
+use jsonrpsee::core::{async_trait, SubscriptionResult};
+use jsonrpsee::PendingSubscriptionSink;
+use starknet_core::types::Felt;
+
+use super::subscribe_events::*;
+use super::subscribe_new_heads::*;
+use crate::{Starknet, StarknetWsRpcApiServer};
+
+#[async_trait]
+impl StarknetWsRpcApiServer for Starknet {
+    async fn subscribe_new_heads(
+        &self,
+        subscription_sink: PendingSubscriptionSink,
+        block_id: Option<BlockId>,
+    ) -> SubscriptionResult {
+        subscribe_new_heads(self, subscription_sink, block_id).await
+    }
+
+    async fn subscribe_events(
+        &self,
+        subscription_sink: PendingSubscriptionSink,
+        from_address: Option<Felt>,
+        keys: Option<Vec<Vec<Felt>>>,
+        block_id: Option<BlockId>,
+        pending: Option<bool>,
+    ) -> SubscriptionResult {
+        subscribe_events(
+            self,
+            subscription_sink,
+            from_address,
+            keys.unwrap_or_default(),
+            block_id,
+            pending.unwrap_or(false),
+        )
+        .await
+    }
+}
@@ -0,0 +1,3 @@
+pub mod lib;
+pub mod subscribe_events;
+pub mod subscribe_new_heads;
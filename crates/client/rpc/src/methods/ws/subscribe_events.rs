@@ -0,0 +1,165 @@
+use dp_block::BlockId;
+use dp_receipt::EventFilter;
+use jsonrpsee::core::{StringError, SubscriptionResult};
+use jsonrpsee::{PendingSubscriptionSink, SubscriptionMessage, SubscriptionSink};
+use starknet_core::types::{EmittedEvent, Felt};
+use tokio::sync::broadcast::error::RecvError;
+
+use super::subscribe_new_heads::start_block_n;
+use crate::constants::{MAX_EVENTS_KEYS, SUBSCRIPTION_SHUTDOWN_MESSAGE};
+use crate::errors::StarknetRpcApiError;
+use crate::methods::read::get_events::{block_events, blocks_to_scan};
+use crate::Starknet;
+
+const SUBSCRIPTION: &str = "starknet_subscribeEvents";
+/// Number of blocks whose events are looked up at once when replaying the stored blocks.
+const REPLAY_CHUNK_SIZE: u64 = 1024;
+
+/// Subscribe to the events matching a filter.
+///
+/// ### Arguments
+///
+/// * `from_address` - Only send the events emitted by this contract.
+/// * `keys` - The keys to match, with the same semantics as `starknet_getEvents`.
+/// * `block_id` - The block to start the notifications from, which can be any block in the past. Defaults to
+///   the latest block.
+/// * `pending` - Whether to send the events of the pending block as well.
+///
+/// ### Returns
+///
+/// Replays the matching events of every block from `block_id` up to the current tip from the database, and
+/// then seamlessly switches to the matching events of each new block once it has been stored. Events of the
+/// pending block have no block hash and number, and are sent again with them once the block is closed.
+/// Returns `TOO_MANY_KEYS_IN_FILTER` or `BLOCK_NOT_FOUND` when the subscription cannot be created.
+pub async fn subscribe_events(
+    starknet: &Starknet,
+    subscription_sink: PendingSubscriptionSink,
+    from_address: Option<Felt>,
+    keys: Vec<Vec<Felt>>,
+    block_id: Option<BlockId>,
+    pending: bool,
+) -> SubscriptionResult {
+    if keys.len() > MAX_EVENTS_KEYS {
+        subscription_sink.reject(StarknetRpcApiError::TooManyKeysInFilter).await;
+        return Ok(());
+    }
+
+    // Subscribe before reading the database, so that no block can be stored in between without being notified
+    let mut new_blocks = starknet.backend.subscribe_new_blocks();
+    let mut pending_blocks = pending.then(|| starknet.backend.subscribe_pending_blocks());
+
+    // Any block can be replayed, the historical events are streamed at the pace of the subscriber
+    let (mut next_block_n, latest_block_n) = match start_block_n(starknet, block_id, None) {
+        Ok(res) => res,
+        Err(err) => {
+            subscription_sink.reject(err).await;
+            return Ok(());
+        }
+    };
+    let sink = subscription_sink.accept().await?;
+    let _subscription = starknet.subscription_started(SUBSCRIPTION);
+    let filter = EventFilter::new(from_address, keys);
+
+    if let Some(latest_block_n) = latest_block_n {
+        send_stored_events(starknet, &sink, &filter, next_block_n, latest_block_n).await?;
+        next_block_n = latest_block_n + 1;
+    }
+
+    // The pending block is updated in place: only the events which were not sent yet are sent on an update.
+    let mut pending_parent_hash = None;
+    let mut pending_events_sent = 0;
+
+    loop {
+        let pending_block = async {
+            match &mut pending_blocks {
+                Some(pending_blocks) => pending_blocks.recv().await,
+                None => std::future::pending().await,
+            }
+        };
+
+        tokio::select! {
+            block = new_blocks.recv() => match block {
+                Ok(block) => {
+                    let block_n = block.info.header.block_number;
+                    if block_n < next_block_n {
+                        // already sent from the database
+                        continue;
+                    }
+                    if block_n > next_block_n {
+                        send_stored_events(starknet, &sink, &filter, next_block_n, block_n - 1).await?;
+                    }
+                    let events = block_events(Some(block.info.block_hash), Some(block_n), &block.inner);
+                    send_events(&sink, &filter, events).await?;
+                    next_block_n = block_n + 1;
+                }
+                // the missed blocks are read from the database when the next one is received
+                Err(RecvError::Lagged(skipped)) => {
+                    starknet.subscription_lagged(SUBSCRIPTION, skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            },
+            block = pending_block => match block {
+                Ok(block) => {
+                    if pending_parent_hash != Some(block.info.header.parent_block_hash) {
+                        pending_parent_hash = Some(block.info.header.parent_block_hash);
+                        pending_events_sent = 0;
+                    }
+                    let events = block_events(None, None, &block.inner).skip(pending_events_sent);
+                    pending_events_sent += send_events(&sink, &filter, events).await?;
+                }
+                // a newer pending block will be received
+                Err(RecvError::Lagged(skipped)) => {
+                    starknet.subscription_lagged(SUBSCRIPTION, skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            },
+            _ = sink.closed() => return Ok(()),
+            _ = dp_utils::graceful_shutdown() => return Err(SUBSCRIPTION_SHUTDOWN_MESSAGE.into()),
+        }
+    }
+}
+
+/// Sends the events matching the filter, and returns the number of events which were looked at.
+async fn send_events(
+    sink: &SubscriptionSink,
+    filter: &EventFilter,
+    events: impl Iterator<Item = EmittedEvent>,
+) -> Result<usize, StringError> {
+    let mut n_events = 0;
+    for event in events {
+        n_events += 1;
+        if filter.matches_parts(&event.from_address, &event.keys) {
+            sink.send(SubscriptionMessage::from_json(&event)?).await?;
+        }
+    }
+    Ok(n_events)
+}
+
+async fn send_stored_events(
+    starknet: &Starknet,
+    sink: &SubscriptionSink,
+    filter: &EventFilter,
+    from_block_n: u64,
+    to_block_n: u64,
+) -> SubscriptionResult {
+    let mut chunk_start = from_block_n;
+    while chunk_start <= to_block_n {
+        let chunk_end = u64::min(chunk_start.saturating_add(REPLAY_CHUNK_SIZE - 1), to_block_n);
+        // When the filter has an address, only the blocks where the contract emitted events are read
+        let blocks: Vec<u64> = blocks_to_scan(starknet, filter.from_address, chunk_start, chunk_end, chunk_end)?
+            .collect::<Result<_, _>>()?;
+        for block_n in blocks {
+            // A long replay can outlive its subscriber
+            if sink.is_closed() {
+                return Ok(());
+            }
+            let block = starknet.get_block(&BlockId::Number(block_n))?;
+            let block_hash = block.info.as_nonpending().map(|info| info.block_hash);
+            send_events(sink, filter, block_events(block_hash, Some(block_n), &block.inner)).await?;
+        }
+        chunk_start = chunk_end + 1;
+    }
+    Ok(())
+}
@@ -0,0 +1,113 @@
+use dp_block::BlockId;
+use jsonrpsee::core::SubscriptionResult;
+use jsonrpsee::{PendingSubscriptionSink, SubscriptionMessage, SubscriptionSink};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::constants::{MAX_BLOCKS_BACK, SUBSCRIPTION_SHUTDOWN_MESSAGE};
+use crate::errors::{StarknetRpcApiError, StarknetRpcResult};
+use crate::types::BlockHeader;
+use crate::utils::{OptionExt, ResultExt};
+use crate::Starknet;
+
+const SUBSCRIPTION: &str = "starknet_subscribeNewHeads";
+
+/// Subscribe to the headers of the new blocks.
+///
+/// ### Arguments
+///
+/// * `block_id` - The block to start the notifications from, at most 1024 blocks back. Defaults to the
+///   latest block.
+///
+/// ### Returns
+///
+/// Sends the header of every block from `block_id` up to the current tip, and then the header of each new
+/// block once it has been stored. A subscriber which falls behind is caught up from the database, so that no
+/// block is ever skipped. Returns `TOO_MANY_BLOCKS_BACK` if `block_id` is too far in the past, or
+/// `BLOCK_NOT_FOUND` if it does not exist.
+pub async fn subscribe_new_heads(
+    starknet: &Starknet,
+    subscription_sink: PendingSubscriptionSink,
+    block_id: Option<BlockId>,
+) -> SubscriptionResult {
+    // Subscribe before reading the database, so that no block can be stored in between without being notified
+    let mut new_blocks = starknet.backend.subscribe_new_blocks();
+
+    let (mut next_block_n, latest_block_n) = match start_block_n(starknet, block_id, Some(MAX_BLOCKS_BACK)) {
+        Ok(res) => res,
+        Err(err) => {
+            subscription_sink.reject(err).await;
+            return Ok(());
+        }
+    };
+    let sink = subscription_sink.accept().await?;
+    let _subscription = starknet.subscription_started(SUBSCRIPTION);
+
+    if let Some(latest_block_n) = latest_block_n {
+        send_stored_headers(starknet, &sink, next_block_n, latest_block_n).await?;
+        next_block_n = latest_block_n + 1;
+    }
+
+    loop {
+        let block = tokio::select! {
+            block = new_blocks.recv() => block,
+            _ = sink.closed() => return Ok(()),
+            _ = dp_utils::graceful_shutdown() => return Err(SUBSCRIPTION_SHUTDOWN_MESSAGE.into()),
+        };
+
+        match block {
+            Ok(block) => {
+                let block_n = block.info.header.block_number;
+                if block_n < next_block_n {
+                    // already sent from the database
+                    continue;
+                }
+                if block_n > next_block_n {
+                    send_stored_headers(starknet, &sink, next_block_n, block_n - 1).await?;
+                }
+                sink.send(SubscriptionMessage::from_json(&BlockHeader::from(&block.info))?).await?;
+                next_block_n = block_n + 1;
+            }
+            // the missed blocks are read from the database when the next one is received
+            Err(RecvError::Lagged(skipped)) => {
+                starknet.subscription_lagged(SUBSCRIPTION, skipped);
+                continue;
+            }
+            Err(RecvError::Closed) => return Ok(()),
+        }
+    }
+}
+
+/// Returns the first block to notify, and the latest stored block if any. The first block can be at most
+/// `max_blocks_back` blocks in the past.
+pub(super) fn start_block_n(
+    starknet: &Starknet,
+    block_id: Option<BlockId>,
+    max_blocks_back: Option<u64>,
+) -> StarknetRpcResult<(u64, Option<u64>)> {
+    let latest_block_n =
+        starknet.backend.get_latest_block_n().or_internal_server_error("Error getting the latest block number")?;
+
+    let Some(block_id) = block_id else {
+        return Ok((latest_block_n.unwrap_or(0), latest_block_n));
+    };
+
+    let block_n = starknet.get_block_n(&block_id)?;
+    if max_blocks_back.is_some_and(|max| latest_block_n.unwrap_or(0).saturating_sub(block_n) > max) {
+        return Err(StarknetRpcApiError::TooManyBlocksBack);
+    }
+    Ok((block_n, latest_block_n))
+}
+
+async fn send_stored_headers(
+    starknet: &Starknet,
+    sink: &SubscriptionSink,
+    from_block_n: u64,
+    to_block_n: u64,
+) -> SubscriptionResult {
+    for block_n in from_block_n..=to_block_n {
+        let block_info = starknet.get_block_info(&BlockId::Number(block_n))?;
+        let block_info = block_info.as_nonpending().ok_or_internal_server_error("Stored block is pending")?;
+        sink.send(SubscriptionMessage::from_json(&BlockHeader::from(block_info))?).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use dc_metrics::{CounterVec, GaugeVec, MetricsRegistry, Opts, PrometheusError, U64};
+
+/// Metrics of the websocket subscriptions, labeled by subscription method.
+#[derive(Clone, Debug)]
+pub struct SubscriptionMetrics {
+    /// Number of subscriptions currently alive.
+    subscriptions_active: GaugeVec<U64>,
+    /// Number of block notifications which a subscription missed because it could not keep up with the node.
+    /// Missed blocks are sent from the database afterwards, missed pending blocks are superseded by the next one.
+    notifications_dropped: CounterVec<U64>,
+}
+
+impl SubscriptionMetrics {
+    pub fn register(registry: &MetricsRegistry) -> Result<Self, PrometheusError> {
+        Ok(Self {
+            subscriptions_active: registry.register(GaugeVec::new(
+                Opts::new("rpc_subscriptions_active", "Number of websocket subscriptions currently alive"),
+                &["subscription"],
+            )?)?,
+            notifications_dropped: registry.register(CounterVec::new(
+                Opts::new(
+                    "rpc_subscriptions_notifications_dropped",
+                    "Number of block notifications missed by subscriptions which could not keep up with the node",
+                ),
+                &["subscription"],
+            )?)?,
+        })
+    }
+
+    /// Counts the subscription as alive until the returned guard is dropped.
+    pub(crate) fn subscription_started(&self, subscription: &'static str) -> SubscriptionGuard {
+        let active = self.subscriptions_active.with_label_values(&[subscription]);
+        active.inc();
+        SubscriptionGuard { active }
+    }
+
+    pub(crate) fn notifications_dropped(&self, subscription: &'static str, count: u64) {
+        self.notifications_dropped.with_label_values(&[subscription]).inc_by(count);
+    }
+}
+
+pub(crate) struct SubscriptionGuard {
+    active: dc_metrics::Gauge<U64>,
+}
+
+impl Drop for SubscriptionGuard {
+    fn drop(&mut self) {
+        self.active.dec();
+    }
+}
@@ -0,0 +1,34 @@
+//! OpenRPC discovery document, returned by `rpc.discover` so that clients can introspect the methods served by
+//! the node.
+//!
+//! The document lists the methods actually served, which depend on the namespaces enabled on the node, and
+//! refers to the specification of the version for the schemas of their requests and responses.
+
+use serde_json::json;
+
+use crate::RpcVersion;
+
+/// The discovery method, as defined by the OpenRPC specification.
+pub const DISCOVER_METHOD: &str = "rpc.discover";
+/// Version of the OpenRPC specification the document follows, the one of the Starknet specification.
+const OPENRPC_VERSION: &str = "1.0.0-rc1";
+
+/// The discovery document of a version, listing the given methods.
+pub fn discover_document<'a>(version: RpcVersion, methods: impl IntoIterator<Item = &'a str>) -> serde_json::Value {
+    let mut methods: Vec<_> = methods.into_iter().collect();
+    methods.sort_unstable();
+    methods.dedup();
+
+    json!({
+        "openrpc": OPENRPC_VERSION,
+        "info": {
+            "title": "Starknet Node API",
+            "version": version.spec_version(),
+        },
+        "externalDocs": {
+            "description": "Starknet JSON-RPC specification, with the schemas of every method",
+            "url": version.spec_url(),
+        },
+        "methods": methods.into_iter().map(|name| json!({ "name": name })).collect::<Vec<_>>(),
+    })
+}
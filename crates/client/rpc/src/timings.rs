@@ -0,0 +1,40 @@
+//! Time spent by a call in the different parts of the node, reported by the slow call log.
+//!
+//! The timings of a call are scoped to its future with [`CallTimings::scope`], and the methods record into
+//! them without knowing whether anyone is listening.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+tokio::task_local! {
+    static CALL_TIMINGS: Arc<CallTimings>;
+}
+
+#[derive(Debug, Default)]
+pub struct CallTimings {
+    /// Time spent executing transactions and calls, in microseconds.
+    exec_micros: AtomicU64,
+}
+
+impl CallTimings {
+    /// Runs a call, recording its timings into `timings`.
+    pub async fn scope<F: Future>(timings: Arc<CallTimings>, call: F) -> F::Output {
+        CALL_TIMINGS.scope(timings, call).await
+    }
+
+    /// The timings of the current call, if they are recorded.
+    pub(crate) fn current() -> Option<Arc<CallTimings>> {
+        CALL_TIMINGS.try_with(Arc::clone).ok()
+    }
+
+    pub(crate) fn add_exec(&self, duration: Duration) {
+        self.exec_micros.fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    /// Time spent executing transactions and calls, including the state reads of the execution.
+    pub fn exec(&self) -> Duration {
+        Duration::from_micros(self.exec_micros.load(Ordering::Relaxed))
+    }
+}
@@ -1,34 +1,424 @@
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 use std::num::ParseIntError;
 
+use dp_block::DeoxysBlockInfo;
+use dp_convert::felt_to_u128;
+use starknet_core::types::{
+    Felt, L1DataAvailabilityMode, ResourcePrice, SequencerTransactionStatus, TransactionExecutionStatus,
+};
+
+use crate::errors::StarknetRpcApiError;
+
+/// Header of a block, as sent to the `starknet_subscribeNewHeads` subscribers.
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct BlockHeader {
+    pub block_hash: Felt,
+    pub parent_hash: Felt,
+    pub block_number: u64,
+    pub new_root: Felt,
+    pub timestamp: u64,
+    pub sequencer_address: Felt,
+    pub l1_gas_price: ResourcePrice,
+    pub l1_data_gas_price: ResourcePrice,
+    pub l1_da_mode: L1DataAvailabilityMode,
+    pub starknet_version: String,
+}
+
+impl From<&DeoxysBlockInfo> for BlockHeader {
+    fn from(info: &DeoxysBlockInfo) -> Self {
+        let header = &info.header;
+        Self {
+            block_hash: info.block_hash,
+            parent_hash: header.parent_block_hash,
+            block_number: header.block_number,
+            new_root: header.global_state_root,
+            timestamp: header.block_timestamp,
+            sequencer_address: header.sequencer_address,
+            l1_gas_price: header.l1_gas_price.l1_gas_price(),
+            l1_data_gas_price: header.l1_gas_price.l1_data_gas_price(),
+            l1_da_mode: header.l1_da_mode.into(),
+            starknet_version: header.protocol_version.to_string(),
+        }
+    }
+}
+
+/// Status of an `l1_handler` transaction, as returned by `starknet_getMessagesStatus`.
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct MessageStatus {
+    pub transaction_hash: Felt,
+    pub finality_status: SequencerTransactionStatus,
+    /// `None` while the transaction has not been executed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub execution_status: Option<TransactionExecutionStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure_reason: Option<String>,
+}
+
+/// Result of `pathfinder_getProof`, with the same layout as pathfinder.
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct GetProofResult {
+    pub state_commitment: Felt,
+    pub class_commitment: Felt,
+    /// Proof of the contract leaf in the contract trie, or of its absence.
+    pub contract_proof: Vec<ProofNode>,
+    /// `None` when the contract is not deployed.
+    pub contract_data: Option<ContractData>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ContractData {
+    pub class_hash: Felt,
+    pub nonce: Felt,
+    pub root: Felt,
+    pub contract_state_hash_version: Felt,
+    /// One proof per requested key, in the order of the request.
+    pub storage_proofs: Vec<Vec<ProofNode>>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProofNode {
+    Binary { left: Felt, right: Felt },
+    Edge { child: Felt, path: EdgePath },
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct EdgePath {
+    pub value: Felt,
+    pub len: usize,
+}
+
+impl From<dc_db::proof_db::ProofNode> for ProofNode {
+    fn from(node: dc_db::proof_db::ProofNode) -> Self {
+        match node {
+            dc_db::proof_db::ProofNode::Binary { left, right } => Self::Binary { left, right },
+            dc_db::proof_db::ProofNode::Edge { child, path, length } => {
+                Self::Edge { child, path: EdgePath { value: path, len: length } }
+            }
+        }
+    }
+}
+
+/// Storage keys of a contract, to prove with `starknet_getStorageProof` or to read with
+/// `deoxys_getContractsStorageBatch`.
+#[derive(Clone, Debug, serde::Deserialize)]
+pub struct ContractStorageKeys {
+    pub contract_address: Felt,
+    pub storage_keys: Vec<Felt>,
+}
+
+/// Sync status of the node, as sent to the `deoxys_subscribeSyncStatus` subscribers. The block fields are
+/// `None` until the first block is stored.
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct SyncStatusUpdate {
+    pub current_block_num: Option<u64>,
+    pub current_block_hash: Option<Felt>,
+    pub highest_block_num: Option<u64>,
+    pub highest_block_hash: Option<Felt>,
+    /// Rate at which the blocks were stored since the previous update.
+    pub blocks_per_second: f64,
+    /// First block replaced by a reorg of the stored chain since the previous update.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reorg_block_num: Option<u64>,
+}
+
+/// Result of `deoxys_getAccountState`
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct AccountState {
+    pub class_hash: Felt,
+    pub nonce: Felt,
+    /// The values of the requested storage keys, in the order of the request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub storage: Option<Vec<Felt>>,
+}
+
+/// Result of `deoxys_callWithResources`
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct CallWithResources {
+    pub result: Vec<Felt>,
+    pub execution_resources: CallExecutionResources,
+}
+
+/// Resources used by a call, including its inner calls.
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct CallExecutionResources {
+    pub steps: usize,
+    pub memory_holes: usize,
+    /// Number of applications of each builtin, by builtin name.
+    pub builtins: BTreeMap<String, usize>,
+}
+
+impl From<dc_exec::CallResources> for CallExecutionResources {
+    fn from(resources: dc_exec::CallResources) -> Self {
+        Self { steps: resources.steps, memory_holes: resources.memory_holes, builtins: resources.builtins }
+    }
+}
+
+/// Result of `deoxys_traceTransactionSyscalls`
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct TransactionSyscallTrace {
+    pub transaction_hash: Felt,
+    /// The syscalls of the transaction, see [`dc_exec::SyscallHook`] for their order.
+    pub syscalls: Vec<SyscallTraceEntry>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct SyscallTraceEntry {
+    /// Depth of the call the syscall belongs to, the top level calls of the transaction are at depth 0.
+    pub depth: usize,
+    #[serde(flatten)]
+    pub syscall: SyscallTraceItem,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(tag = "type", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SyscallTraceItem {
+    Call {
+        caller_address: Felt,
+        contract_address: Felt,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        class_hash: Option<Felt>,
+        entry_point_selector: Felt,
+        calldata: Vec<Felt>,
+        library_call: bool,
+    },
+    CallEnd {
+        result: Vec<Felt>,
+        failed: bool,
+    },
+    StorageRead {
+        value: Felt,
+    },
+    StorageWrite {
+        contract_address: Felt,
+        key: Felt,
+        value: Felt,
+    },
+    EmitEvent {
+        keys: Vec<Felt>,
+        data: Vec<Felt>,
+    },
+    SendMessageToL1 {
+        to_address: Felt,
+        payload: Vec<Felt>,
+    },
+}
+
+impl From<dc_exec::Syscall> for SyscallTraceItem {
+    fn from(syscall: dc_exec::Syscall) -> Self {
+        match syscall {
+            dc_exec::Syscall::Call {
+                caller_address,
+                contract_address,
+                class_hash,
+                entry_point_selector,
+                calldata,
+                library_call,
+            } => Self::Call {
+                caller_address,
+                contract_address,
+                class_hash,
+                entry_point_selector,
+                calldata,
+                library_call,
+            },
+            dc_exec::Syscall::CallEnd { result, failed } => Self::CallEnd { result, failed },
+            dc_exec::Syscall::StorageRead { value } => Self::StorageRead { value },
+            dc_exec::Syscall::StorageWrite { contract_address, key, value } => {
+                Self::StorageWrite { contract_address, key, value }
+            }
+            dc_exec::Syscall::EmitEvent { keys, data } => Self::EmitEvent { keys, data },
+            dc_exec::Syscall::SendMessageToL1 { to_address, payload } => Self::SendMessageToL1 { to_address, payload },
+        }
+    }
+}
+
+/// Result of `starknet_getStorageProof`
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct StorageProof {
+    pub classes_proof: Vec<NodeHashToNode>,
+    pub contracts_proof: ContractsProof,
+    /// One proof per requested contract, in the order of the request.
+    pub contracts_storage_proofs: Vec<Vec<NodeHashToNode>>,
+    pub global_roots: GlobalRoots,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct NodeHashToNode {
+    pub node_hash: Felt,
+    pub node: MerkleNode,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(untagged)]
+pub enum MerkleNode {
+    Binary { left: Felt, right: Felt },
+    Edge { path: Felt, length: usize, child: Felt },
+}
+
+impl From<dc_db::proof_db::ProofNode> for MerkleNode {
+    fn from(node: dc_db::proof_db::ProofNode) -> Self {
+        match node {
+            dc_db::proof_db::ProofNode::Binary { left, right } => Self::Binary { left, right },
+            dc_db::proof_db::ProofNode::Edge { child, path, length } => Self::Edge { path, length, child },
+        }
+    }
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ContractsProof {
+    pub nodes: Vec<NodeHashToNode>,
+    /// One leaf per requested contract, in the order of the request.
+    pub contract_leaves_data: Vec<ContractLeafData>,
+}
+
+/// Leaf of a contract in the contract trie, zeroed when the contract is not deployed.
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ContractLeafData {
+    pub nonce: Felt,
+    pub class_hash: Felt,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct GlobalRoots {
+    pub contracts_tree_root: Felt,
+    pub classes_tree_root: Felt,
+    pub block_hash: Felt,
+}
+
+/// State override of a contract for `starknet_call`, keyed by the contract address.
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ContractStateOverride {
+    #[serde(default)]
+    pub nonce: Option<Felt>,
+    #[serde(default)]
+    pub class_hash: Option<Felt>,
+    #[serde(default)]
+    pub balance: Option<Felt>,
+    #[serde(default)]
+    pub storage: HashMap<Felt, Felt>,
+}
+
+impl From<ContractStateOverride> for dc_exec::ContractStateOverride {
+    fn from(value: ContractStateOverride) -> Self {
+        Self {
+            nonce: value.nonce,
+            class_hash: value.class_hash,
+            balance: value.balance,
+            storage: value.storage.into_iter().collect(),
+        }
+    }
+}
+
+/// Header override of the block `starknet_estimateFee` and `starknet_simulateTransactions` execute in, to
+/// execute as if in a later block. The state is still the one of the requested block.
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BlockOverrides {
+    #[serde(default)]
+    pub timestamp: Option<u64>,
+    #[serde(default)]
+    pub sequencer_address: Option<Felt>,
+    #[serde(default)]
+    pub l1_gas_price: Option<ResourcePrice>,
+    #[serde(default)]
+    pub l1_data_gas_price: Option<ResourcePrice>,
+}
+
+impl TryFrom<BlockOverrides> for dc_exec::BlockHeaderOverrides {
+    type Error = StarknetRpcApiError;
+
+    fn try_from(value: BlockOverrides) -> Result<Self, Self::Error> {
+        let to_u128 = |price: &Felt| {
+            felt_to_u128(price)
+                .map_err(|_| StarknetRpcApiError::ErrUnexpectedError { data: "Gas price overflows u128".into() })
+        };
+        Ok(Self {
+            block_timestamp: value.timestamp,
+            sequencer_address: value.sequencer_address,
+            eth_l1_gas_price: value.l1_gas_price.as_ref().map(|price| to_u128(&price.price_in_wei)).transpose()?,
+            strk_l1_gas_price: value.l1_gas_price.as_ref().map(|price| to_u128(&price.price_in_fri)).transpose()?,
+            eth_l1_data_gas_price: value
+                .l1_data_gas_price
+                .as_ref()
+                .map(|price| to_u128(&price.price_in_wei))
+                .transpose()?,
+            strk_l1_data_gas_price: value
+                .l1_data_gas_price
+                .as_ref()
+                .map(|price| to_u128(&price.price_in_fri))
+                .transpose()?,
+        })
+    }
+}
+
+/// Version of the format of the continuation tokens, bumped whenever it changes.
+const CONTINUATION_TOKEN_VERSION: u8 = 1;
+
+/// Continuation token of `starknet_getEvents`. It only depends on the chain, which makes it valid across node
+/// restarts, and it is checked against the parent of its block so that it is rejected after a reorg.
+///
+/// It is formatted as `{version}-{block_n}-{tx_index}-{event_index}-{parent_check}`, in decimal except for the
+/// parent check which is in hexadecimal.
 #[derive(PartialEq, Eq, Debug, Default)]
 pub struct ContinuationToken {
     pub block_n: u64,
-    pub event_n: u64,
+    /// Index of the transaction of the next event to return in the block.
+    pub tx_index: u64,
+    /// Index of the next event to return in its transaction.
+    pub event_index: u64,
+    /// Lowest 64 bits of the hash of the parent block.
+    pub parent_check: u64,
 }
 
 #[derive(PartialEq, Eq, Debug)]
 pub enum ParseTokenError {
     WrongToken,
+    UnsupportedVersion,
     ParseFailed(ParseIntError),
 }
 
 impl fmt::Display for ContinuationToken {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}-{}", self.block_n, self.event_n)
+        write!(
+            f,
+            "{}-{}-{}-{}-{:x}",
+            CONTINUATION_TOKEN_VERSION, self.block_n, self.tx_index, self.event_index, self.parent_check
+        )
     }
 }
 
 impl ContinuationToken {
+    pub fn new(block_n: u64, tx_index: u64, event_index: u64, parent_block_hash: &Felt) -> Self {
+        Self { block_n, tx_index, event_index, parent_check: Self::hash_check(parent_block_hash) }
+    }
+
+    pub fn matches_parent(&self, parent_block_hash: &Felt) -> bool {
+        self.parent_check == Self::hash_check(parent_block_hash)
+    }
+
+    fn hash_check(hash: &Felt) -> u64 {
+        let bytes = hash.to_bytes_be();
+        u64::from_be_bytes(bytes[24..].try_into().expect("slice of 8 bytes"))
+    }
+
     pub fn parse(token: String) -> Result<Self, ParseTokenError> {
         let arr: Vec<&str> = token.split('-').collect();
-        if arr.len() != 2 {
+        if arr.len() != 5 {
             return Err(ParseTokenError::WrongToken);
         }
-        let block_n = arr[0].parse::<u64>().map_err(ParseTokenError::ParseFailed)?;
-        let event_n = arr[1].parse::<u64>().map_err(ParseTokenError::ParseFailed)?;
+        let version = arr[0].parse::<u8>().map_err(ParseTokenError::ParseFailed)?;
+        if version != CONTINUATION_TOKEN_VERSION {
+            return Err(ParseTokenError::UnsupportedVersion);
+        }
+        let block_n = arr[1].parse::<u64>().map_err(ParseTokenError::ParseFailed)?;
+        let tx_index = arr[2].parse::<u64>().map_err(ParseTokenError::ParseFailed)?;
+        let event_index = arr[3].parse::<u64>().map_err(ParseTokenError::ParseFailed)?;
+        let parent_check = u64::from_str_radix(arr[4], 16).map_err(ParseTokenError::ParseFailed)?;
 
-        Ok(ContinuationToken { block_n, event_n })
+        Ok(ContinuationToken { block_n, tx_index, event_index, parent_check })
     }
 }
 
@@ -39,39 +429,68 @@ mod tests {
     use crate::types::*;
 
     #[rstest]
-    #[case(0, 0, "0-0")]
-    #[case(1, 4, "1-4")]
-    #[case(2, 4, "2-4")]
-    #[case(0, 4, "0-4")]
-    fn to_string_works(#[case] block_n: u64, #[case] event_n: u64, #[case] expected: String) {
-        let token = ContinuationToken { block_n, event_n };
+    #[case(0, 0, 0, 0, "1-0-0-0-0")]
+    #[case(1, 4, 2, 0xff, "1-1-4-2-ff")]
+    #[case(2, 0, 4, u64::MAX, "1-2-0-4-ffffffffffffffff")]
+    fn to_string_works(
+        #[case] block_n: u64,
+        #[case] tx_index: u64,
+        #[case] event_index: u64,
+        #[case] parent_check: u64,
+        #[case] expected: String,
+    ) {
+        let token = ContinuationToken { block_n, tx_index, event_index, parent_check };
         assert_eq!(expected, token.to_string())
     }
 
     #[rstest]
-    #[case("0-0", 0, 0)]
-    #[case("1-4", 1, 4)]
-    #[case("2-4", 2, 4)]
-    fn parse_works(#[case] string_token: String, #[case] block_n: u64, #[case] event_n: u64) {
-        let expected = ContinuationToken { block_n, event_n };
+    #[case("1-0-0-0-0", 0, 0, 0, 0)]
+    #[case("1-1-4-2-ff", 1, 4, 2, 0xff)]
+    #[case("1-2-0-4-ffffffffffffffff", 2, 0, 4, u64::MAX)]
+    fn parse_works(
+        #[case] string_token: String,
+        #[case] block_n: u64,
+        #[case] tx_index: u64,
+        #[case] event_index: u64,
+        #[case] parent_check: u64,
+    ) {
+        let expected = ContinuationToken { block_n, tx_index, event_index, parent_check };
         assert_eq!(expected, ContinuationToken::parse(string_token).unwrap());
     }
 
     #[rstest]
     #[case("100")]
-    #[case("0,")]
-    #[case("0,0,0")]
+    #[case("0-0")]
+    #[case("1-0-0-0")]
+    #[case("1-0-0-0-0-0")]
     fn parse_should_fail(#[case] string_token: String) {
         let result = ContinuationToken::parse(string_token);
-        assert!(result.is_err());
+        assert_eq!(result, Err(ParseTokenError::WrongToken));
     }
 
     #[rstest]
-    #[case("2y,4")]
-    #[case("30,255g")]
-    #[case("1,1,")]
+    #[case("1-2y-0-4-0")]
+    #[case("1-30-255g-0-0")]
+    #[case("1-1-1-1-0xz")]
     fn parse_u64_should_fail(#[case] string_token: String) {
         let result = ContinuationToken::parse(string_token);
-        assert!(result.is_err());
+        assert!(matches!(result, Err(ParseTokenError::ParseFailed(_))));
+    }
+
+    #[rstest]
+    #[case("0-1-0-0-0")]
+    #[case("2-1-0-0-0")]
+    fn parse_unsupported_version_should_fail(#[case] string_token: String) {
+        let result = ContinuationToken::parse(string_token);
+        assert_eq!(result, Err(ParseTokenError::UnsupportedVersion));
+    }
+
+    #[test]
+    fn parent_check_works() {
+        let parent_block_hash = Felt::from_hex_unchecked("0x1234567890abcdef0123456789abcdef");
+        let token = ContinuationToken::new(3, 1, 0, &parent_block_hash);
+        assert_eq!(token.parent_check, 0x0123456789abcdef);
+        assert!(token.matches_parent(&parent_block_hash));
+        assert!(!token.matches_parent(&Felt::ONE));
     }
 }
@@ -27,7 +27,6 @@ pub trait ResultExt<T, E> {
         self,
         context_fn: F,
     ) -> Result<T, StarknetRpcApiError>;
-    fn or_contract_error<C: fmt::Display>(self, context: C) -> Result<T, StarknetRpcApiError>;
 }
 
 impl<T, E: Into<anyhow::Error>> ResultExt<T, E> for Result<T, E> {
@@ -55,18 +54,6 @@ impl<T, E: Into<anyhow::Error>> ResultExt<T, E> for Result<T, E> {
             }
         }
     }
-
-    // TODO: should this be a thing?
-    #[inline]
-    fn or_contract_error<C: fmt::Display>(self, context: C) -> Result<T, StarknetRpcApiError> {
-        match self {
-            Ok(val) => Ok(val),
-            Err(err) => {
-                log::error!(target: "rpc_errors", "Contract storage error: {context}: {:#}", E::into(err));
-                Err(StarknetRpcApiError::ContractError)
-            }
-        }
-    }
 }
 
 pub trait OptionExt<T> {
@@ -1,58 +1,139 @@
-use blockifier::execution::contract_class::ClassInfo;
-use blockifier::transaction::transaction_execution as btx;
-use dp_block::BlockId;
-use dp_class::to_blockifier_class;
-use dp_convert::ToFelt;
-use starknet_api::transaction::{Transaction, TransactionHash};
+use blockifier::transaction::errors::{TransactionExecutionError, TransactionFeeError, TransactionPreValidationError};
+use dp_block::{BlockId, BlockTag};
+use dp_transactions::{broadcasted_to_blockifier, BroadcastedToBlockifierError, TransactionWithHash};
+use starknet_core::types::{
+    BroadcastedDeclareTransaction, BroadcastedDeployAccountTransaction, BroadcastedInvokeTransaction,
+    BroadcastedTransaction, DataAvailabilityMode, Felt,
+};
 
 use crate::errors::{StarknetRpcApiError, StarknetRpcResult};
+use crate::utils::ResultExt;
 use crate::Starknet;
 
-/// Convert an starknet-api Transaction to a blockifier Transaction
+/// Checks a broadcasted transaction as the sequencer would before it is forwarded to the gateway, and returns its
+/// hash computed with the chain id of the node.
 ///
-/// **note:** this function does not support deploy transaction
-/// because it is not supported by blockifier
-pub(crate) fn to_blockifier_transactions(
+/// The transaction is executed on top of the pending block, with the validation entry point of its account which
+/// checks its signature against this hash: a transaction signed for another chain, with a wrong nonce or whose
+/// sender cannot pay its max fee is rejected without reaching the gateway. In devnet mode, the transaction is only
+/// executed once, when it is added to the pending block.
+pub(crate) async fn validate_broadcasted_transaction(
     starknet: &Starknet,
-    block_id: BlockId,
-    transaction: &dp_transactions::Transaction,
-    tx_hash: &TransactionHash,
-) -> StarknetRpcResult<btx::Transaction> {
-    let transaction: Transaction = transaction.try_into().map_err(|_| StarknetRpcApiError::InternalServerError)?;
-
-    let paid_fee_on_l1 = match transaction {
-        Transaction::L1Handler(_) => Some(starknet_api::transaction::Fee(1_000_000_000_000)),
+    transaction: BroadcastedTransaction,
+) -> StarknetRpcResult<Felt> {
+    check_supported_version(&transaction)?;
+
+    let class_hash = match &transaction {
+        BroadcastedTransaction::Declare(BroadcastedDeclareTransaction::V1(tx)) => {
+            Some(tx.contract_class.class_hash().map_err(|e| {
+                log::debug!("Failed to compute the class hash of a declared legacy class: {e:#}");
+                StarknetRpcApiError::InvalidContractClass
+            })?)
+        }
+        BroadcastedTransaction::Declare(BroadcastedDeclareTransaction::V2(tx)) => Some(tx.contract_class.class_hash()),
+        BroadcastedTransaction::Declare(BroadcastedDeclareTransaction::V3(tx)) => Some(tx.contract_class.class_hash()),
         _ => None,
     };
+    let hash = TransactionWithHash::from_broadcasted(transaction.clone(), starknet.chain_id(), class_hash).hash;
 
-    let class_info = match transaction {
-        Transaction::Declare(ref declare_tx) => {
-            let class_hash = declare_tx.class_hash();
+    if starknet.devnet().is_none() {
+        starknet.spawn_execution(move |starknet| validate_execution(starknet, transaction)).await?;
+    }
+    Ok(hash)
+}
 
-            let Ok(Some((class_info, compiled_class))) = starknet.backend.get_class(&block_id, &class_hash.to_felt())
-            else {
-                log::error!("Failed to retrieve class from class_hash '{class_hash}'");
-                return Err(StarknetRpcApiError::ContractNotFound);
-            };
+/// Rejects the transactions the sequencer does not accept: the query versions are only meant for simulation, and the
+/// v3 transactions cannot use the L2 data availability mode nor a paymaster yet.
+fn check_supported_version(transaction: &BroadcastedTransaction) -> StarknetRpcResult<()> {
+    if dp_transactions::is_query(transaction) {
+        return Err(StarknetRpcApiError::UnsupportedTxnVersion);
+    }
 
-            let blockifier_contract_class = to_blockifier_class(compiled_class).map_err(|e| {
-                log::error!("Failed to convert contract class to blockifier contract class: {e}");
-                StarknetRpcApiError::InternalServerError
-            })?;
+    let (nonce_da_mode, fee_da_mode, paymaster_data, account_deployment_data) = match transaction {
+        BroadcastedTransaction::Invoke(BroadcastedInvokeTransaction::V3(tx)) => (
+            tx.nonce_data_availability_mode,
+            tx.fee_data_availability_mode,
+            &tx.paymaster_data,
+            &tx.account_deployment_data[..],
+        ),
+        BroadcastedTransaction::Declare(BroadcastedDeclareTransaction::V3(tx)) => (
+            tx.nonce_data_availability_mode,
+            tx.fee_data_availability_mode,
+            &tx.paymaster_data,
+            &tx.account_deployment_data[..],
+        ),
+        BroadcastedTransaction::DeployAccount(BroadcastedDeployAccountTransaction::V3(tx)) => {
+            (tx.nonce_data_availability_mode, tx.fee_data_availability_mode, &tx.paymaster_data, &[][..])
+        }
+        _ => return Ok(()),
+    };
+    if nonce_da_mode != DataAvailabilityMode::L1
+        || fee_da_mode != DataAvailabilityMode::L1
+        || !paymaster_data.is_empty()
+        || !account_deployment_data.is_empty()
+    {
+        return Err(StarknetRpcApiError::UnsupportedTxnVersion);
+    }
+    Ok(())
+}
 
-            let sierra_program_length = class_info.contract_class.sierra_program_length();
-            let abi_length = class_info.contract_class.abi_length();
+/// Executes the transaction on top of the pending block, or the latest block when there is none, charging its fee
+/// and calling the validation entry point of its account.
+fn validate_execution(starknet: &Starknet, transaction: BroadcastedTransaction) -> StarknetRpcResult<()> {
+    let block_info = match starknet.get_block_info(&BlockId::Tag(BlockTag::Pending)) {
+        Err(StarknetRpcApiError::BlockNotFound) => starknet.get_block_info(&BlockId::Tag(BlockTag::Latest))?,
+        block_info => block_info?,
+    };
 
-            Some(ClassInfo::new(&blockifier_contract_class, sierra_program_length, abi_length).map_err(|_| {
-                log::error!("Mismatch between the length of the sierra program and the class version");
-                StarknetRpcApiError::InternalServerError
-            })?)
+    let transaction = broadcasted_to_blockifier(transaction, starknet.chain_id()).map_err(|err| match err {
+        BroadcastedToBlockifierError::CompilationFailed(err) => {
+            log::debug!(target: "rpc_errors", "Failed to compile a declared class: {err:#}");
+            StarknetRpcApiError::CompilationFailed
         }
-        _ => None,
-    };
+        err => {
+            log::debug!(target: "rpc_errors", "Failed to convert a broadcasted transaction: {err:#}");
+            StarknetRpcApiError::InvalidContractClass
+        }
+    })?;
+
+    starknet
+        .execution_context(&block_info)?
+        .execute_transactions([], [transaction], true, true)
+        .map_err(validation_error)?;
+    Ok(())
+}
+
+/// The error the sequencer would reject a transaction failing its execution with. A reverted transaction is
+/// accepted, only the failures of its validation and of its fee checks are errors.
+fn validation_error(err: dc_exec::Error) -> StarknetRpcApiError {
+    let dc_exec::Error::Reexecution(err) = err else { return err.into() };
+    match err.execution_error() {
+        TransactionExecutionError::TransactionPreValidationError(TransactionPreValidationError::InvalidNonce {
+            ..
+        }) => StarknetRpcApiError::InvalidTxnNonce,
+        TransactionExecutionError::TransactionPreValidationError(
+            TransactionPreValidationError::TransactionFeeError(
+                TransactionFeeError::MaxFeeTooLow { .. }
+                | TransactionFeeError::MaxL1GasAmountTooLow { .. }
+                | TransactionFeeError::MaxL1GasPriceTooLow { .. },
+            ),
+        ) => StarknetRpcApiError::InsufficientMaxFee,
+        TransactionExecutionError::DeclareTransactionError { .. } => StarknetRpcApiError::ClassAlreadyDeclared,
+        err => StarknetRpcApiError::ValidationFailure { error: format!("{err:#}") },
+    }
+}
 
-    btx::Transaction::from_api(transaction.clone(), *tx_hash, class_info, paid_fee_on_l1, None, false).map_err(|_| {
-        log::error!("Failed to convert transaction to blockifier transaction");
-        StarknetRpcApiError::InternalServerError
-    })
+/// The gateway hash is the one the transaction is known by. A different local hash means that the node and the
+/// gateway are not configured for the same chain, or that the node computes the hashes of this kind of transaction
+/// wrongly: the caller would be handed a hash which does not match its signature.
+pub(crate) fn check_gateway_transaction_hash(local_hash: Felt, gateway_hash: Felt) -> StarknetRpcResult<()> {
+    if local_hash != gateway_hash {
+        return Err(StarknetRpcApiError::ErrUnexpectedError {
+            data: format!(
+                "Transaction hash returned by the gateway {gateway_hash:#x} does not match the hash computed locally \
+                 {local_hash:#x}, is the gateway serving the chain the node is configured for?"
+            ),
+        });
+    }
+    Ok(())
 }
@@ -0,0 +1,69 @@
+//! JSON-RPC specification versions served by the node.
+//!
+//! Each version is served under its own `/rpc/v<major>_<minor>` path, and the latest released one is also
+//! served at the root path. A version only differs from the others by the shapes of its requests and responses
+//! and by the methods it adds, and new versions are added here along with their own rpc modules. A version is only
+//! served once the methods whose shapes differ have their own rpc module, as the 0.6 ones in `methods::v0_6`.
+
+/// A JSON-RPC specification version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RpcVersion {
+    V0_6,
+    V0_7,
+}
+
+impl RpcVersion {
+    pub const LATEST: Self = Self::V0_7;
+    /// Every served version, oldest first.
+    pub const ALL: &'static [Self] = &[Self::V0_6, Self::V0_7];
+
+    /// The path the version is served at.
+    pub fn path(&self) -> &'static str {
+        match self {
+            Self::V0_6 => "/rpc/v0_6",
+            Self::V0_7 => "/rpc/v0_7",
+        }
+    }
+
+    /// The version returned by `starknet_specVersion`.
+    pub fn spec_version(&self) -> &'static str {
+        match self {
+            Self::V0_6 => "0.6.0",
+            Self::V0_7 => "0.7.1",
+        }
+    }
+
+    /// The OpenRPC specification of the version, with the schemas of its requests and responses.
+    pub fn spec_url(&self) -> &'static str {
+        match self {
+            Self::V0_6 => "https://github.com/starkware-libs/starknet-specs/tree/v0.6.0/api",
+            Self::V0_7 => "https://github.com/starkware-libs/starknet-specs/tree/v0.7.1/api",
+        }
+    }
+
+    /// The version served at a request path, ignoring trailing slashes. Returns `None` for an unknown
+    /// version.
+    pub fn from_path(path: &str) -> Option<Self> {
+        match path.trim_end_matches('/') {
+            "" => Some(Self::LATEST),
+            path => Self::ALL.iter().copied().find(|version| version.path() == path),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_path() {
+        assert_eq!(RpcVersion::from_path("/"), Some(RpcVersion::LATEST));
+        assert_eq!(RpcVersion::from_path(""), Some(RpcVersion::LATEST));
+        assert_eq!(RpcVersion::from_path("/rpc/v0_7"), Some(RpcVersion::V0_7));
+        assert_eq!(RpcVersion::from_path("/rpc/v0_7/"), Some(RpcVersion::V0_7));
+        assert_eq!(RpcVersion::from_path("/rpc/v0_6"), Some(RpcVersion::V0_6));
+        assert_eq!(RpcVersion::from_path("/rpc/v0_5"), None);
+        assert_eq!(RpcVersion::from_path("/rpc/v0_8"), None);
+        assert_eq!(RpcVersion::from_path("/other"), None);
+    }
+}
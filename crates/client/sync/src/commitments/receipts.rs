@@ -1,11 +1,62 @@
+use dp_block::commitments::receipt_commitment;
 use dp_receipt::TransactionReceipt;
-use rayon::prelude::*;
 use starknet_types_core::felt::Felt;
-use starknet_types_core::hash::Poseidon;
 
-use super::compute_root;
+/// Re-hashes the receipts of a block one by one and logs the breakdown of each receipt hash, to
+/// help figuring out why the receipt commitment disagrees with the block hash from the gateway.
+///
+/// Receipts are checked against the transaction hashes computed for the block: a receipt which
+/// does not refer to the transaction at the same index is reported as diverging, with all of its
+/// hashed fields.
+///
+/// # Returns
+///
+/// The index of the first diverging receipt, if any.
+pub fn log_receipt_commitment_mismatch(
+    block_number: u64,
+    receipts: &[TransactionReceipt],
+    tx_hashes: &[Felt],
+) -> Option<usize> {
+    if receipts.len() != tx_hashes.len() {
+        log::warn!(
+            "Block #{block_number}: got {} receipts for {} transactions, the receipt commitment cannot match",
+            receipts.len(),
+            tx_hashes.len()
+        );
+    }
 
-pub fn memory_receipt_commitment(receipts: &[TransactionReceipt]) -> Felt {
-    let receipts_hash = receipts.par_iter().map(TransactionReceipt::compute_hash).collect::<Vec<_>>();
-    compute_root::<Poseidon>(&receipts_hash)
+    let mut first_diverging = None;
+    for (index, receipt) in receipts.iter().enumerate() {
+        let [transaction_hash, actual_fee, messages_sent_hash, execution_result_hash, l2_gas, l1_gas, l1_data_gas] =
+            receipt.hash_elements();
+        let expected_tx_hash = tx_hashes.get(index).copied();
+
+        let fields = format!(
+            "transaction_hash: {transaction_hash:#x}, actual_fee: {actual_fee:#x}, messages_sent_hash: \
+             {messages_sent_hash:#x}, execution_result_hash: {execution_result_hash:#x}, l2_gas: {l2_gas:#x}, \
+             l1_gas: {l1_gas:#x}, l1_data_gas: {l1_data_gas:#x}, receipt_hash: {:#x}",
+            receipt.compute_hash()
+        );
+
+        if expected_tx_hash != Some(transaction_hash) {
+            log::warn!(
+                "Block #{block_number}: receipt {index} diverges, expected transaction hash {}; got {fields}",
+                expected_tx_hash.map(|hash| format!("{hash:#x}")).unwrap_or_else(|| "<none>".into()),
+            );
+            first_diverging.get_or_insert(index);
+        } else {
+            log::debug!("Block #{block_number}: receipt {index}: {fields}");
+        }
+    }
+
+    match first_diverging {
+        Some(index) => log::warn!("Block #{block_number}: first diverging receipt is at index {index}"),
+        None => log::warn!(
+            "Block #{block_number}: all {} receipts match their transactions, recomputed receipt commitment is {:#x}",
+            receipts.len(),
+            receipt_commitment(receipts)
+        ),
+    }
+
+    first_diverging
 }
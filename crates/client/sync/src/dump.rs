@@ -0,0 +1,415 @@
+//! Block dumps, to export blocks from the database and import them from a local file instead of the
+//! feeder gateway.
+//!
+//! A dump is a gzip compressed stream made of a header (magic bytes, format version and
+//! [`DumpHeader`]) followed by bincode encoded [`DumpBlock`]s in increasing block number order. The
+//! stream ends with an explicit end marker so that truncated dumps are detected.
+//!
+//! Imported blocks go through the same pipeline as blocks fetched from the network: commitments and
+//! block hashes are recomputed, classes are compiled, and the state root is verified before storing.
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+use std::pin::pin;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{bail, Context};
+use dc_db::db_metrics::DbMetrics;
+use dc_db::storage_updates::DbClassUpdate;
+use dc_db::DeoxysBackend;
+use dc_telemetry::TelemetryHandle;
+use dp_block::{BlockId, BlockTag, DeoxysBlock, DeoxysMaybePendingBlockInfo};
+use dp_state_update::{DeclaredClassItem, DeployedContractItem, StateDiff};
+use dp_utils::{channel_wait_or_graceful_shutdown, spawn_rayon_task, stopwatch_end, PauseHandle, PerfStopwatch};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use futures::{stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use starknet_types_core::felt::Felt;
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+
+use crate::convert::{convert_and_verify_class, verify_block, BlockHashCheck};
+use crate::l2::{l2_verify_and_apply_task, L2ConvertedBlockAndUpdates};
+use crate::metrics::block_metrics::BlockMetrics;
+
+pub const DUMP_MAGIC: [u8; 8] = *b"DXSDUMP\0";
+pub const DUMP_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DumpHeader {
+    pub chain_id: Felt,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DumpClass {
+    pub class_hash: Felt,
+    pub contract_class: dp_class::ContractClass,
+    pub compiled_class_hash: Felt,
+}
+
+/// A block with everything needed to import it: its state diff and the classes it declares.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DumpBlock {
+    pub block: DeoxysBlock,
+    pub state_diff: StateDiff,
+    pub classes: Vec<DumpClass>,
+}
+
+pub struct DumpReader<R: Read> {
+    reader: GzDecoder<R>,
+}
+
+impl DumpReader<BufReader<File>> {
+    pub fn open(path: &Path, chain_id: Felt) -> anyhow::Result<Self> {
+        let file = File::open(path).with_context(|| format!("Opening dump file {}", path.display()))?;
+        Self::new(BufReader::new(file), chain_id)
+    }
+}
+
+impl<R: Read> DumpReader<R> {
+    pub fn new(reader: R, chain_id: Felt) -> anyhow::Result<Self> {
+        let mut reader = GzDecoder::new(reader);
+
+        let mut magic = [0u8; DUMP_MAGIC.len()];
+        reader.read_exact(&mut magic).context("Reading dump magic bytes")?;
+        if magic != DUMP_MAGIC {
+            bail!("Not a block dump file");
+        }
+
+        let mut version = [0u8; 4];
+        reader.read_exact(&mut version).context("Reading dump version")?;
+        let version = u32::from_le_bytes(version);
+        if version != DUMP_VERSION {
+            bail!("Unsupported dump version {version}, expected version {DUMP_VERSION}");
+        }
+
+        let header: DumpHeader = bincode::deserialize_from(&mut reader).context("Reading dump header")?;
+        if header.chain_id != chain_id {
+            bail!(
+                "The dump has been created for chain id {:#x}, but the node is configured for chain id {:#x}",
+                header.chain_id,
+                chain_id
+            );
+        }
+
+        Ok(Self { reader })
+    }
+
+    /// Returns `None` once the end marker of the dump has been reached.
+    pub fn next_block(&mut self) -> anyhow::Result<Option<DumpBlock>> {
+        bincode::deserialize_from(&mut self.reader).context("Reading block from dump, the dump may be truncated")
+    }
+}
+
+pub struct DumpWriter<W: Write> {
+    writer: GzEncoder<W>,
+}
+
+impl DumpWriter<BufWriter<File>> {
+    pub fn create(path: &Path, chain_id: Felt) -> anyhow::Result<Self> {
+        let file = File::create(path).with_context(|| format!("Creating dump file {}", path.display()))?;
+        Self::new(BufWriter::new(file), chain_id)
+    }
+}
+
+impl<W: Write> DumpWriter<W> {
+    pub fn new(writer: W, chain_id: Felt) -> anyhow::Result<Self> {
+        let mut writer = GzEncoder::new(writer, Compression::default());
+        writer.write_all(&DUMP_MAGIC).context("Writing dump magic bytes")?;
+        writer.write_all(&DUMP_VERSION.to_le_bytes()).context("Writing dump version")?;
+        bincode::serialize_into(&mut writer, &DumpHeader { chain_id }).context("Writing dump header")?;
+        Ok(Self { writer })
+    }
+
+    pub fn write_block(&mut self, block: &DumpBlock) -> anyhow::Result<()> {
+        bincode::serialize_into(&mut self.writer, &Some(block)).context("Writing block to dump")
+    }
+
+    /// Writes the end marker and flushes the dump. A dump which has not been finished will be
+    /// rejected as truncated on import.
+    pub fn finish(mut self) -> anyhow::Result<W> {
+        bincode::serialize_into(&mut self.writer, &None::<DumpBlock>).context("Writing dump end marker")?;
+        let mut writer = self.writer.finish().context("Finishing dump compression")?;
+        writer.flush().context("Flushing dump")?;
+        Ok(writer)
+    }
+}
+
+/// Exports the blocks `first_block..=last_block` of the database into a dump file. When no last
+/// block is given, the export goes up to the current sync tip.
+///
+/// This is blocking, it should be run in a blocking context.
+pub fn export_dump(
+    backend: &DeoxysBackend,
+    path: &Path,
+    chain_id: Felt,
+    first_block: u64,
+    last_block: Option<u64>,
+) -> anyhow::Result<()> {
+    let last_block = match last_block {
+        Some(last_block) => last_block,
+        None => backend.get_latest_block_n().context("getting sync tip")?.context("The database is empty")?,
+    };
+    if first_block > last_block {
+        bail!("Invalid block range to export: #{first_block} is after #{last_block}");
+    }
+
+    log::info!("📦 Exporting blocks #{}..=#{} to dump {}", first_block, last_block, path.display());
+    let sw = PerfStopwatch::new();
+
+    let mut writer = DumpWriter::create(path, chain_id)?;
+    for block_n in first_block..=last_block {
+        let block = get_dump_block(backend, block_n).with_context(|| format!("Exporting block #{block_n}"))?;
+        writer.write_block(&block)?;
+
+        if block_n % 1000 == 0 {
+            log::info!("📦 Exported block #{block_n}");
+        }
+    }
+    writer.finish()?;
+
+    log::info!("✅ Exported {} blocks ({:?})", last_block - first_block + 1, sw.elapsed());
+    Ok(())
+}
+
+pub(crate) fn get_dump_block(backend: &DeoxysBackend, block_n: u64) -> anyhow::Result<DumpBlock> {
+    let block_id = BlockId::Number(block_n);
+    let block = backend.get_block(&block_id)?.context("Block not found in database")?;
+    let DeoxysMaybePendingBlockInfo::NotPending(info) = block.info else {
+        bail!("Cannot export a pending block");
+    };
+    let state_diff = backend.get_block_state_diff(&block_id)?.context("State diff not found in database")?;
+
+    // Classes are exported alongside the first block they appear in, which is the block they have
+    // been stored with. Old blocks can deploy contracts of classes which were never declared.
+    let class_hashes = state_diff
+        .declared_classes
+        .iter()
+        .map(|DeclaredClassItem { class_hash, .. }| *class_hash)
+        .chain(state_diff.deprecated_declared_classes.iter().copied())
+        .chain(state_diff.deployed_contracts.iter().map(|DeployedContractItem { class_hash, .. }| *class_hash))
+        .collect::<HashSet<_>>();
+
+    let mut classes = Vec::new();
+    for class_hash in class_hashes {
+        let Some(class_info) = backend.get_class_info(&block_id, &class_hash)? else {
+            bail!("Class {class_hash:#x} not found in database");
+        };
+        if class_info.block_number == Some(block_n) {
+            classes.push(DumpClass {
+                class_hash,
+                contract_class: class_info.contract_class,
+                compiled_class_hash: class_info.compiled_class_hash,
+            });
+        }
+    }
+
+    Ok(DumpBlock { block: DeoxysBlock::new(info, block.inner), state_diff, classes })
+}
+
+/// Imports the blocks of a dump file into the database, starting right after the current sync tip.
+/// Blocks of the dump which are already in the database are skipped.
+#[allow(clippy::too_many_arguments)]
+pub async fn import_dump(
+    backend: &Arc<DeoxysBackend>,
+    path: PathBuf,
+    verify: bool,
+    backup_every_n_blocks: Option<u64>,
+    block_metrics: BlockMetrics,
+    db_metrics: DbMetrics,
+    chain_id: Felt,
+    telemetry: TelemetryHandle,
+    sync_pause: PauseHandle,
+    max_blocks_per_second: Option<NonZeroU32>,
+    hash_check: BlockHashCheck,
+) -> anyhow::Result<()> {
+    let first_block = backend
+        .get_block_n(&BlockId::Tag(BlockTag::Latest))
+        .context("getting sync tip")?
+        .map(|block_n| block_n + 1)
+        .unwrap_or_default();
+
+    log::info!("📦 Importing blocks from dump {}, starting at block {}", path.display(), first_block);
+
+    let (dump_sender, dump_receiver) = mpsc::channel(8);
+    let (block_conv_sender, block_conv_receiver) = mpsc::channel(4);
+
+    // [Dump read task] ==dump blocks=> [Block verification task] ======> [Verification and apply task]
+    // This mirrors the l2 sync pipeline, with the fetch task replaced by reading the dump file.
+    let mut join_set = JoinSet::new();
+    join_set.spawn(async move {
+        tokio::task::spawn_blocking(move || dump_read_task(&path, chain_id, first_block, dump_sender))
+            .await
+            .context("dump read task was dropped")?
+    });
+    join_set.spawn(dump_block_conversion_task(dump_receiver, block_conv_sender, chain_id, Arc::new(hash_check)));
+    join_set.spawn(l2_verify_and_apply_task(
+        Arc::clone(backend),
+        block_conv_receiver,
+        verify,
+        backup_every_n_blocks,
+        block_metrics,
+        db_metrics,
+        first_block,
+        Arc::new(Mutex::new(None)),
+        telemetry,
+        sync_pause,
+        max_blocks_per_second,
+    ));
+
+    while let Some(res) = join_set.join_next().await {
+        res.context("task was dropped")??;
+    }
+
+    log::info!("🥳 The dump has been imported");
+    Ok(())
+}
+
+/// This is a blocking task.
+fn dump_read_task(
+    path: &Path,
+    chain_id: Felt,
+    first_block: u64,
+    output: mpsc::Sender<DumpBlock>,
+) -> anyhow::Result<()> {
+    let mut reader = DumpReader::open(path, chain_id)?;
+
+    let mut next_block = first_block;
+    while let Some(block) = reader.next_block()? {
+        let block_n = block.block.info.header.block_number;
+        if block_n < next_block {
+            // already imported
+            continue;
+        }
+        if block_n != next_block {
+            bail!("The dump is missing block #{next_block}, the next block in the dump is #{block_n}");
+        }
+
+        if output.blocking_send(block).is_err() {
+            // channel closed
+            break;
+        }
+        next_block += 1;
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn dump_block_conversion_task(
+    dump_receiver: mpsc::Receiver<DumpBlock>,
+    output: mpsc::Sender<L2ConvertedBlockAndUpdates>,
+    chain_id: Felt,
+    hash_check: Arc<BlockHashCheck>,
+) -> anyhow::Result<()> {
+    let conversion_stream = stream::unfold(dump_receiver, |mut dump_recv| {
+        let hash_check = Arc::clone(&hash_check);
+        async move {
+            channel_wait_or_graceful_shutdown(dump_recv.recv())
+                .await
+                .map(|block| (spawn_rayon_task(move || convert_dump_block(block, chain_id, &hash_check)), dump_recv))
+        }
+    });
+
+    let mut stream = pin!(conversion_stream.buffered(10));
+    while let Some(block) = channel_wait_or_graceful_shutdown(stream.next()).await {
+        if output.send(block?).await.is_err() {
+            // channel closed
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Compute heavy, this should only be called in a rayon ctx
+fn convert_dump_block(
+    block: DumpBlock,
+    chain_id: Felt,
+    hash_check: &BlockHashCheck,
+) -> anyhow::Result<L2ConvertedBlockAndUpdates> {
+    let sw = PerfStopwatch::new();
+    let DumpBlock { block, state_diff, classes } = block;
+    let block_n = block.info.header.block_number;
+
+    let classes = classes
+        .into_iter()
+        .map(|DumpClass { class_hash, contract_class, compiled_class_hash }| DbClassUpdate {
+            class_hash,
+            contract_class: contract_class.into(),
+            compiled_class_hash,
+        })
+        .collect();
+
+    let (verified_block, converted_classes) = rayon::join(
+        || verify_block(block, state_diff, chain_id, hash_check).context("Verifying block"),
+        || convert_and_verify_class(classes, Some(block_n)).context("Converting classes"),
+    );
+    stopwatch_end!(sw, "convert_dump_block {:?}: {:?}", block_n);
+
+    let (converted_block, converted_state_diff) = verified_block?;
+    Ok(L2ConvertedBlockAndUpdates { converted_block, converted_state_diff, converted_classes: converted_classes? })
+}
+
+#[cfg(test)]
+mod tests {
+    use dp_block::{DeoxysBlockInfo, DeoxysBlockInner, Header};
+
+    use super::*;
+
+    fn dump_block(block_number: u64) -> DumpBlock {
+        DumpBlock {
+            block: DeoxysBlock::new(
+                DeoxysBlockInfo::new(Header { block_number, ..Default::default() }, vec![], Felt::from(block_number)),
+                DeoxysBlockInner::new(vec![], vec![]),
+            ),
+            state_diff: StateDiff {
+                storage_diffs: vec![],
+                deprecated_declared_classes: vec![Felt::ONE],
+                declared_classes: vec![],
+                deployed_contracts: vec![],
+                replaced_classes: vec![],
+                nonces: vec![],
+            },
+            classes: vec![],
+        }
+    }
+
+    #[test]
+    fn test_dump_roundtrip() {
+        let chain_id = Felt::from_bytes_be_slice(b"SN_TEST");
+
+        let mut writer = DumpWriter::new(Vec::new(), chain_id).unwrap();
+        writer.write_block(&dump_block(0)).unwrap();
+        writer.write_block(&dump_block(1)).unwrap();
+        let dump = writer.finish().unwrap();
+
+        let mut reader = DumpReader::new(dump.as_slice(), chain_id).unwrap();
+        let block_0 = reader.next_block().unwrap().unwrap();
+        assert_eq!(block_0.block.info.header.block_number, 0);
+        assert_eq!(block_0.state_diff, dump_block(0).state_diff);
+        let block_1 = reader.next_block().unwrap().unwrap();
+        assert_eq!(block_1.block.info.block_hash, Felt::ONE);
+        assert!(reader.next_block().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_dump_wrong_chain_id() {
+        let dump = DumpWriter::new(Vec::new(), Felt::ONE).unwrap().finish().unwrap();
+        assert!(DumpReader::new(dump.as_slice(), Felt::TWO).is_err());
+    }
+
+    #[test]
+    fn test_dump_truncated() {
+        let mut writer = DumpWriter::new(Vec::new(), Felt::ONE).unwrap();
+        writer.write_block(&dump_block(0)).unwrap();
+        // not finished: no end marker
+        let dump = writer.writer.finish().unwrap();
+
+        let mut reader = DumpReader::new(dump.as_slice(), Felt::ONE).unwrap();
+        assert!(reader.next_block().unwrap().is_some());
+        assert!(reader.next_block().is_err());
+    }
+}
@@ -2,6 +2,8 @@
 use core::fmt;
 use core::time::Duration;
 use std::collections::HashMap;
+use std::num::NonZeroU32;
+use std::path::PathBuf;
 
 use dc_db::storage_updates::DbClassUpdate;
 use dc_db::DeoxysBackend;
@@ -16,6 +18,8 @@ use starknet_providers::{Provider, ProviderError, SequencerGatewayProvider};
 use starknet_types_core::felt::Felt;
 use url::Url;
 
+use super::gateway_cache::GatewayCache;
+use crate::convert::BlockHashCheck;
 use crate::l2::L2SyncError;
 
 /// The configuration of the worker responsible for fetching new blocks and state updates from the
@@ -42,6 +46,43 @@ pub struct FetchConfig {
     pub n_blocks_to_sync: Option<u64>,
     /// Disable l1 sync
     pub sync_l1_disabled: bool,
+    /// Directory where the gateway responses are cached, if any
+    pub gateway_cache_dir: Option<PathBuf>,
+    /// Maximum number of blocks imported per second, if any
+    pub max_blocks_per_second: Option<NonZeroU32>,
+    /// How mismatched block hashes are handled
+    pub block_hash_check: BlockHashCheck,
+    /// Settings of the http client used to reach the gateways
+    pub http_client: HttpClientConfig,
+}
+
+/// Connection settings of the http client shared by all the fetchers. A single client is used for the
+/// whole fetch pipeline so that the parallel fetches reuse the same connections.
+#[derive(Clone, Debug)]
+pub struct HttpClientConfig {
+    /// Maximum number of idle connections kept open to the gateway
+    pub pool_max_idle: usize,
+    /// How long an idle connection is kept open
+    pub pool_idle_timeout: Duration,
+    /// TCP keepalive interval, if any
+    pub tcp_keepalive: Option<Duration>,
+    /// Use HTTP/2 without negotiating it first. All the fetches are then multiplexed on a few connections.
+    pub http2: bool,
+}
+
+impl HttpClientConfig {
+    pub fn build_client(&self) -> reqwest::Result<reqwest::Client> {
+        let builder = reqwest::Client::builder()
+            .pool_max_idle_per_host(self.pool_max_idle)
+            .pool_idle_timeout(self.pool_idle_timeout)
+            .tcp_keepalive(self.tcp_keepalive);
+        let builder = if self.http2 {
+            builder.http2_prior_knowledge().http2_keep_alive_interval(self.tcp_keepalive).http2_adaptive_window(true)
+        } else {
+            builder
+        };
+        builder.build()
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -96,14 +137,15 @@ pub async fn fetch_block_and_updates(
     backend: &DeoxysBackend,
     block_id: FetchBlockId,
     provider: &SequencerGatewayProvider,
+    gateway_cache: Option<&GatewayCache>,
 ) -> Result<L2BlockAndUpdates, L2SyncError> {
     const MAX_RETRY: u32 = 15;
     let base_delay = Duration::from_secs(1);
 
     let sw = PerfStopwatch::new();
     let (state_update, block) =
-        retry(|| fetch_state_update_with_block(provider, block_id), MAX_RETRY, base_delay).await?;
-    let class_update = fetch_class_updates(backend, &state_update, block_id, provider).await?;
+        retry(|| fetch_state_update_with_block(provider, gateway_cache, block_id), MAX_RETRY, base_delay).await?;
+    let class_update = fetch_class_updates(backend, &state_update, block_id, provider, gateway_cache).await?;
 
     stopwatch_end!(sw, "fetching {:?}: {:?}", block_id);
     Ok(L2BlockAndUpdates { block_id, block, state_diff: state_update.state_diff, class_update })
@@ -145,9 +187,15 @@ where
 /// retrieves state update with block from Starknet sequencer in only one request
 async fn fetch_state_update_with_block(
     provider: &SequencerGatewayProvider,
+    gateway_cache: Option<&GatewayCache>,
     block_id: FetchBlockId,
 ) -> Result<(StateUpdate, p::Block), ProviderError> {
-    let state_update_with_block = provider.get_state_update_with_block(block_id.into()).await?;
+    let state_update_with_block = match (gateway_cache, block_id) {
+        (Some(gateway_cache), FetchBlockId::BlockN(block_n)) => {
+            gateway_cache.get_state_update_with_block(block_n).await?
+        }
+        _ => provider.get_state_update_with_block(block_id.into()).await?,
+    };
 
     Ok((state_update_with_block.state_update.to_state_update_core(), state_update_with_block.block))
 }
@@ -158,6 +206,7 @@ async fn fetch_class_updates(
     state_update: &StateUpdate,
     block_id: FetchBlockId,
     provider: &SequencerGatewayProvider,
+    gateway_cache: Option<&GatewayCache>,
 ) -> Result<Vec<DbClassUpdate>, L2SyncError> {
     let missing_classes: Vec<_> = std::iter::empty()
         .chain(
@@ -194,7 +243,8 @@ async fn fetch_class_updates(
             {
                 // Fetch the class definition in parallel, retrying up to 15 times for each class
                 let (class_hash, contract_class) =
-                    retry(|| fetch_class(class_hash, block_id, provider), 15, Duration::from_secs(1)).await?;
+                    retry(|| fetch_class(class_hash, block_id, provider, gateway_cache), 15, Duration::from_secs(1))
+                        .await?;
                 Ok::<_, L2SyncError>(Some(DbClassUpdate { class_hash, contract_class, compiled_class_hash }))
             } else {
                 Ok(None)
@@ -212,7 +262,15 @@ async fn fetch_class(
     class_hash: Felt,
     block_id: FetchBlockId,
     provider: &SequencerGatewayProvider,
+    gateway_cache: Option<&GatewayCache>,
 ) -> Result<(Felt, ContractClass), ProviderError> {
+    if let Some(contract_class) = gateway_cache.and_then(|gateway_cache| gateway_cache.get_class(&class_hash)) {
+        return Ok((class_hash, contract_class));
+    }
+
     let contract_class = provider.get_class(starknet_core::types::BlockId::from(block_id), class_hash).await?;
+    if let Some(gateway_cache) = gateway_cache {
+        gateway_cache.put_class(&class_hash, &contract_class);
+    }
     Ok((class_hash, contract_class))
 }
@@ -0,0 +1,171 @@
+//! On-disk cache of the gateway responses.
+//!
+//! State updates (with their blocks) are stored as the raw json returned by the feeder gateway,
+//! keyed by block number, and class definitions are stored keyed by class hash. This allows a node
+//! to replay the sync from local data after a database wipe instead of downloading everything again.
+//! Pending blocks are never cached.
+//!
+//! The sequencer models of the provider can only be deserialized, so a missing state update is
+//! fetched by the cache itself, with a single request whose response is both parsed and stored,
+//! instead of going through the provider.
+use std::any::Any;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use starknet_core::types::{ContractClass, StarknetError};
+use starknet_providers::sequencer::models::{self as p};
+use starknet_providers::{ProviderError, ProviderImplError};
+use starknet_types_core::felt::Felt;
+use url::Url;
+
+const STATE_UPDATES_DIR: &str = "state_updates";
+const CLASSES_DIR: &str = "classes";
+const BLOCK_NOT_FOUND_CODE: &str = "StarknetErrorCode.BLOCK_NOT_FOUND";
+
+/// Failure of a request made by the cache, reported as [`ProviderError::Other`] like the provider
+/// would.
+#[derive(Debug, thiserror::Error)]
+pub enum GatewayCacheError {
+    #[error("Invalid feeder gateway url")]
+    InvalidUrl,
+    #[error("Gateway request failed: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("Gateway answered with status {status}: {body}")]
+    Status { status: StatusCode, body: String },
+    #[error("Invalid gateway response: {0}")]
+    Deserialize(#[from] serde_json::Error),
+}
+
+impl ProviderImplError for GatewayCacheError {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl From<GatewayCacheError> for ProviderError {
+    fn from(err: GatewayCacheError) -> Self {
+        ProviderError::Other(Box::new(err))
+    }
+}
+
+/// Error body of the feeder gateway.
+#[derive(Deserialize)]
+struct GatewayErrorBody {
+    code: String,
+}
+
+pub struct GatewayCache {
+    dir: PathBuf,
+    client: reqwest::Client,
+    feeder_gateway: Url,
+    api_key: Option<String>,
+}
+
+impl GatewayCache {
+    pub fn new(
+        dir: PathBuf,
+        client: reqwest::Client,
+        feeder_gateway: Url,
+        api_key: Option<String>,
+    ) -> io::Result<Self> {
+        std::fs::create_dir_all(dir.join(STATE_UPDATES_DIR))?;
+        std::fs::create_dir_all(dir.join(CLASSES_DIR))?;
+        log::info!("🗃️  Using gateway cache at: {}", dir.display());
+        Ok(Self { dir, client, feeder_gateway, api_key })
+    }
+
+    fn state_update_path(&self, block_n: u64) -> PathBuf {
+        self.dir.join(STATE_UPDATES_DIR).join(format!("{block_n}.json"))
+    }
+
+    fn class_path(&self, class_hash: &Felt) -> PathBuf {
+        self.dir.join(CLASSES_DIR).join(format!("{class_hash:#x}.json"))
+    }
+
+    /// Returns the state update with block from the cache, or fetches it from the feeder gateway
+    /// and caches it.
+    ///
+    /// The errors are the ones the provider would return: a block which does not exist yet is
+    /// [`StarknetError::BlockNotFound`], and is not cached.
+    pub async fn get_state_update_with_block(&self, block_n: u64) -> Result<p::StateUpdateWithBlock, ProviderError> {
+        let path = self.state_update_path(block_n);
+        if let Some(cached) = read_entry(&path) {
+            log::debug!("gateway cache hit for block #{block_n}");
+            return Ok(cached);
+        }
+
+        let raw = self.fetch_raw_state_update_with_block(block_n).await?;
+        let state_update_with_block = serde_json::from_slice(&raw).map_err(GatewayCacheError::from)?;
+        write_entry(&path, &raw);
+        Ok(state_update_with_block)
+    }
+
+    async fn fetch_raw_state_update_with_block(&self, block_n: u64) -> Result<Vec<u8>, ProviderError> {
+        let mut url = self.feeder_gateway.clone();
+        url.path_segments_mut().map_err(|_| GatewayCacheError::InvalidUrl)?.pop_if_empty().push("get_state_update");
+        url.query_pairs_mut().append_pair("blockNumber", &block_n.to_string()).append_pair("includeBlock", "true");
+
+        let mut request = self.client.get(url);
+        if let Some(api_key) = &self.api_key {
+            request = request.header("X-Throttling-Bypass", api_key);
+        }
+
+        let response = request.send().await.map_err(GatewayCacheError::from)?;
+        let status = response.status();
+        let body = response.bytes().await.map_err(GatewayCacheError::from)?;
+        if status.is_success() {
+            return Ok(body.to_vec());
+        }
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            return Err(ProviderError::RateLimited);
+        }
+        match serde_json::from_slice::<GatewayErrorBody>(&body) {
+            Ok(error) if error.code == BLOCK_NOT_FOUND_CODE => {
+                Err(ProviderError::StarknetError(StarknetError::BlockNotFound))
+            }
+            _ => Err(GatewayCacheError::Status { status, body: String::from_utf8_lossy(&body).into_owned() }.into()),
+        }
+    }
+
+    pub fn get_class(&self, class_hash: &Felt) -> Option<ContractClass> {
+        read_entry(&self.class_path(class_hash))
+    }
+
+    pub fn put_class(&self, class_hash: &Felt, contract_class: &ContractClass) {
+        match serde_json::to_vec(contract_class) {
+            Ok(raw) => write_entry(&self.class_path(class_hash), &raw),
+            Err(err) => log::warn!("Failed to serialize class {class_hash:#x} for the gateway cache: {err:#}"),
+        }
+    }
+}
+
+/// Cache errors are never fatal: a missing or corrupted entry is simply fetched again.
+fn read_entry<T: DeserializeOwned>(path: &Path) -> Option<T> {
+    let raw = match std::fs::read(path) {
+        Ok(raw) => raw,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return None,
+        Err(err) => {
+            log::warn!("Failed to read gateway cache entry {}: {err:#}", path.display());
+            return None;
+        }
+    };
+
+    match serde_json::from_slice(&raw) {
+        Ok(value) => Some(value),
+        Err(err) => {
+            log::warn!("Ignoring corrupted gateway cache entry {}: {err:#}", path.display());
+            None
+        }
+    }
+}
+
+/// Writes to a temporary file first so that an interrupted write never leaves a truncated entry.
+fn write_entry(path: &Path, raw: &[u8]) {
+    let tmp_path = path.with_extension("json.tmp");
+    if let Err(err) = std::fs::write(&tmp_path, raw).and_then(|_| std::fs::rename(&tmp_path, path)) {
+        log::warn!("Failed to write gateway cache entry {}: {err:#}", path.display());
+    }
+}
@@ -1,19 +1,26 @@
 use std::sync::Arc;
 use std::time::Duration;
 
+use dc_db::block_db::HighestKnownBlock;
 use dc_db::DeoxysBackend;
 use dp_utils::{channel_wait_or_graceful_shutdown, wait_or_graceful_shutdown};
 use fetchers::FetchBlockId;
 use futures::prelude::*;
 use starknet_core::types::StarknetError;
-use starknet_providers::{ProviderError, SequencerGatewayProvider};
-use tokio::sync::{mpsc, oneshot};
+use starknet_providers::{Provider, ProviderError, SequencerGatewayProvider};
+use tokio::sync::{mpsc, oneshot, watch};
+use tokio::time::Interval;
 
 use self::fetchers::L2BlockAndUpdates;
+use self::gateway_cache::GatewayCache;
 use crate::fetch::fetchers::fetch_block_and_updates;
 use crate::l2::L2SyncError;
 
 pub mod fetchers;
+pub mod gateway_cache;
+
+/// How often the gateway is asked for the tip of the chain.
+const CHAIN_TIP_PROBE_INTERVAL: Duration = Duration::from_secs(10);
 
 #[allow(clippy::too_many_arguments)]
 pub async fn l2_fetch_task(
@@ -22,11 +29,13 @@ pub async fn l2_fetch_task(
     n_blocks_to_sync: Option<u64>,
     fetch_stream_sender: mpsc::Sender<L2BlockAndUpdates>,
     provider: Arc<SequencerGatewayProvider>,
+    gateway_cache: Option<Arc<GatewayCache>>,
     sync_polling_interval: Option<Duration>,
     once_caught_up_callback: oneshot::Sender<()>,
 ) -> anyhow::Result<()> {
     // First, catch up with the chain
     let backend = &backend;
+    let gateway_cache = gateway_cache.as_deref();
 
     let mut next_block = first_block;
 
@@ -34,7 +43,12 @@ pub async fn l2_fetch_task(
         // Fetch blocks and updates in parallel one time before looping
         let fetch_stream = (first_block..).take(n_blocks_to_sync.unwrap_or(u64::MAX) as _).map(|block_n| {
             let provider = Arc::clone(&provider);
-            async move { (block_n, fetch_block_and_updates(backend, FetchBlockId::BlockN(block_n), &provider).await) }
+            async move {
+                (
+                    block_n,
+                    fetch_block_and_updates(backend, FetchBlockId::BlockN(block_n), &provider, gateway_cache).await,
+                )
+            }
         });
 
         // Have 10 fetches in parallel at once, using futures Buffered
@@ -69,7 +83,8 @@ pub async fn l2_fetch_task(
         interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
         while wait_or_graceful_shutdown(interval.tick()).await.is_some() {
             loop {
-                match fetch_block_and_updates(backend, FetchBlockId::BlockN(next_block), &provider).await {
+                match fetch_block_and_updates(backend, FetchBlockId::BlockN(next_block), &provider, gateway_cache).await
+                {
                     Err(L2SyncError::Provider(ProviderError::StarknetError(StarknetError::BlockNotFound))) => {
                         break;
                     }
@@ -87,3 +102,56 @@ pub async fn l2_fetch_task(
     }
     Ok(())
 }
+
+/// Waits for the next tick of `interval`. Returns `false` once the fetch task has finished, which
+/// closes `fetch_finished`, or on graceful shutdown.
+pub(crate) async fn tick_until_fetch_finished(
+    interval: &mut Interval,
+    fetch_finished: &mut watch::Receiver<()>,
+) -> bool {
+    let tick = async {
+        tokio::select! {
+            _ = interval.tick() => true,
+            _ = fetch_finished.changed() => false,
+        }
+    };
+    wait_or_graceful_shutdown(tick).await.unwrap_or(false)
+}
+
+/// Periodically queries the gateway for the tip of the chain and stores it in the database as the
+/// highest known block, which is reported by `starknet_syncing`. Stops with the fetch task.
+///
+/// Probing errors are not fatal, the highest known block is simply not updated.
+pub async fn chain_tip_probe_task(
+    backend: Arc<DeoxysBackend>,
+    provider: Arc<SequencerGatewayProvider>,
+    mut fetch_finished: watch::Receiver<()>,
+) -> anyhow::Result<()> {
+    let mut interval = tokio::time::interval(CHAIN_TIP_PROBE_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    let mut last_known = backend.get_highest_known_block().unwrap_or_else(|err| {
+        log::warn!("Failed to get the highest known block: {err:#}");
+        None
+    });
+
+    while tick_until_fetch_finished(&mut interval, &mut fetch_finished).await {
+        let tip = match provider.block_hash_and_number().await {
+            Ok(tip) => HighestKnownBlock { block_number: tip.block_number, block_hash: tip.block_hash },
+            Err(err) => {
+                log::debug!("chain_tip_probe_task: failed to get the chain tip: {err:#}");
+                continue;
+            }
+        };
+
+        if last_known != Some(tip) {
+            log::debug!("chain_tip_probe_task: chain tip is #{} ({:#x})", tip.block_number, tip.block_hash);
+            if let Err(err) = backend.write_highest_known_block(&tip) {
+                log::warn!("Failed to store the highest known block: {err:#}");
+                continue;
+            }
+            last_known = Some(tip);
+        }
+    }
+
+    Ok(())
+}
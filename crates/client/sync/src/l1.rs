@@ -1,17 +1,18 @@
 //! Contains the necessaries to perform an L1 verification of the state
 
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::{bail, Context, Result};
 use dc_db::DeoxysBackend;
 use dp_convert::ToFelt;
 use dp_convert::ToStarkFelt;
 use dp_transactions::TEST_CHAIN_ID;
-use dp_utils::channel_wait_or_graceful_shutdown;
-use ethers::contract::{abigen, EthEvent};
-use ethers::providers::{Http, Middleware, Provider};
+use dp_utils::{channel_wait_or_graceful_shutdown, wait_or_graceful_shutdown, PauseHandle};
+use ethers::contract::{abigen, parse_log, EthEvent};
+use ethers::providers::{Http, Middleware, Provider, Ws};
 use ethers::types::transaction::eip2718::TypedTransaction;
-use ethers::types::{Address, BlockNumber as EthBlockNumber, Filter, TransactionRequest, I256, U256, U64};
+use ethers::types::{Address, Block, BlockNumber as EthBlockNumber, Filter, Log, TransactionRequest, I256, U256, U64};
 use ethers::utils::hex::decode;
 use futures::stream::StreamExt;
 use primitive_types::H256;
@@ -22,7 +23,7 @@ use starknet_api::hash::{StarkFelt, StarkHash};
 use starknet_types_core::felt::Felt;
 
 use crate::metrics::block_metrics::BlockMetrics;
-use crate::utility::{convert_log_state_update, trim_hash};
+use crate::utility::{convert_log_message_to_l2, convert_log_state_update, trim_hash};
 use crate::utils::constant::LOG_STATE_UPDTATE_TOPIC;
 
 /// Contains the Starknet verified state on L1
@@ -41,12 +42,60 @@ pub struct LogStateUpdate {
     pub block_hash: U256,
 }
 
+/// Starknet core LogMessageToL2 event
+#[derive(Clone, Debug, EthEvent)]
+pub struct LogMessageToL2 {
+    #[ethevent(indexed)]
+    pub from_address: Address,
+    #[ethevent(indexed)]
+    pub to_address: U256,
+    #[ethevent(indexed)]
+    pub selector: U256,
+    pub payload: Vec<U256>,
+    pub nonce: U256,
+    pub fee: U256,
+}
+
+/// Interval between two polls of the L1 -> L2 messages.
+const L1_MESSAGES_POLL_INTERVAL: Duration = Duration::from_secs(12);
+/// Maximum number of L1 blocks whose messages are requested at once.
+const L1_MESSAGES_MAX_BLOCK_RANGE: u64 = 2000;
+
+/// The L1 block from which the last state update has been read, used to detect L1 reorgs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct L1Origin {
+    pub block_number: u64,
+    pub block_hash: H256,
+}
+
+impl L1Origin {
+    fn from_log(log: &Log) -> Option<Self> {
+        Some(Self { block_number: log.block_number?.as_u64(), block_hash: log.block_hash? })
+    }
+
+    /// Returns whether the L1 block is still part of the canonical chain.
+    async fn is_canonical(&self, provider: &impl Middleware) -> anyhow::Result<bool> {
+        let block = provider.get_block(self.block_number).await.map_err(|err| anyhow::anyhow!("{err:#}"))?;
+        Ok(block.and_then(|block| block.hash) == Some(self.block_hash))
+    }
+}
+
+enum WsEvent {
+    NewHead(Block<H256>),
+    Log(Log),
+}
+
 /// Ethereum client to interact with L1
 #[derive(Clone)]
 pub struct EthereumClient {
     provider: Arc<Provider<Http>>,
+    /// Optional websocket connection, used to subscribe to new L1 heads and core contract logs
+    /// instead of polling the http endpoint.
+    ws_provider: Option<Arc<Provider<Ws>>>,
     url: Url,
     l1_core_address: Address,
+    /// Held while indexing a range of L1 messages, so that a reorg does not revert the index in the middle of it.
+    l1_messages_lock: Arc<tokio::sync::Mutex<()>>,
 }
 
 /// Implementation of the Ethereum client to interact with L1
@@ -54,7 +103,21 @@ impl EthereumClient {
     /// Create a new EthereumClient instance with the given RPC URL
     pub async fn new(url: Url, l1_core_address: Address) -> Result<Self> {
         let provider = Provider::<Http>::try_from(url.as_str())?;
-        Ok(Self { provider: Arc::new(provider), url, l1_core_address })
+        Ok(Self {
+            provider: Arc::new(provider),
+            ws_provider: None,
+            url,
+            l1_core_address,
+            l1_messages_lock: Default::default(),
+        })
+    }
+
+    /// Connect to a websocket endpoint, which will be used for subscriptions. The http endpoint is
+    /// still used for the calls to the core contract. On failure, the client is left polling over http.
+    pub async fn connect_ws(&mut self, ws_url: Url) -> Result<()> {
+        let ws = Ws::connect(ws_url.as_str()).await.context("Connecting to the L1 websocket endpoint")?;
+        self.ws_provider = Some(Arc::new(Provider::new(ws)));
+        Ok(())
     }
 
     /// Get current RPC URL
@@ -76,6 +139,11 @@ impl EthereumClient {
 
     /// Get the block number of the last occurrence of a given event.
     pub async fn get_last_event_block_number(&self) -> anyhow::Result<u64> {
+        Ok(self.get_last_event_origin().await?.block_number)
+    }
+
+    /// Get the L1 block of the last occurrence of a given event.
+    pub async fn get_last_event_origin(&self) -> anyhow::Result<L1Origin> {
         let topic = H256::from_slice(&hex::decode(&LOG_STATE_UPDTATE_TOPIC[2..])?);
         let address = self.l1_core_address;
         let latest_block = self.get_latest_block_number().await.expect("Failed to retrieve latest block number");
@@ -90,8 +158,7 @@ impl EthereumClient {
         let logs = self.provider.get_logs(&filter).await?;
 
         if let Some(last_log) = logs.last() {
-            let last_block = last_log.block_number.context("no block number in log")?;
-            Ok(last_block.as_u64())
+            L1Origin::from_log(last_log).context("no block number or hash in log")
         } else {
             bail!("no event found")
         }
@@ -142,12 +209,116 @@ impl EthereumClient {
 
     /// Subscribes to the LogStateUpdate event from the Starknet core contract and store latest
     /// verified state
+    ///
+    /// When a websocket endpoint is configured, `eth_subscribe` is used and we only fall back to
+    /// polling the http endpoint if the subscription is dropped.
     pub async fn listen_and_update_state(
         &self,
         backend: &DeoxysBackend,
         start_block: u64,
         block_metrics: BlockMetrics,
         chain_id: Felt,
+        sync_pause: &PauseHandle,
+    ) -> anyhow::Result<()> {
+        let start_block = match &self.ws_provider {
+            Some(ws_provider) => {
+                let Some(last_l1_block) = self
+                    .listen_ws(ws_provider, backend, start_block, block_metrics.clone(), chain_id, sync_pause)
+                    .await?
+                else {
+                    return Ok(());
+                };
+                log::warn!("⚠️  L1 websocket subscription closed, falling back to http polling");
+                last_l1_block
+            }
+            None => start_block,
+        };
+
+        self.listen_http(backend, start_block, block_metrics, chain_id, sync_pause).await
+    }
+
+    /// Subscribes to new L1 heads and LogStateUpdate logs over websocket. Logs emitted since
+    /// `start_block` are fetched first, so that no state update is missed.
+    ///
+    /// # Returns
+    ///
+    /// The L1 block of the last received state update, from which to resume listening if the
+    /// subscription has been closed by the endpoint, or `None` on graceful shutdown.
+    async fn listen_ws(
+        &self,
+        ws_provider: &Provider<Ws>,
+        backend: &DeoxysBackend,
+        start_block: u64,
+        block_metrics: BlockMetrics,
+        chain_id: Felt,
+        sync_pause: &PauseHandle,
+    ) -> anyhow::Result<Option<u64>> {
+        let filter = Filter::new()
+            .from_block(start_block)
+            .address(vec![self.l1_core_address])
+            .topic0(H256::from_slice(&hex::decode(&LOG_STATE_UPDTATE_TOPIC[2..])?));
+
+        let mut heads = ws_provider.subscribe_blocks().await.context("Subscribing to new L1 heads")?;
+        let mut logs = ws_provider.subscribe_logs(&filter).await.context("Subscribing to LogStateUpdate logs")?;
+        log::debug!("listen_ws: subscribed to L1 heads and logs from L1 block {start_block}");
+
+        let mut last_l1_block = start_block;
+        let mut origin: Option<L1Origin> = None;
+        loop {
+            let next = async {
+                tokio::select! {
+                    head = heads.next() => head.map(WsEvent::NewHead),
+                    log = logs.next() => log.map(WsEvent::Log),
+                }
+            };
+            // Outer `None` is a graceful shutdown, inner `None` means one of the subscriptions ended.
+            let Some(event) = wait_or_graceful_shutdown(next).await else { return Ok(None) };
+            let Some(event) = event else { return Ok(Some(last_l1_block)) };
+
+            match event {
+                WsEvent::NewHead(head) => {
+                    if let Some(number) = head.number {
+                        log::debug!("listen_ws: new L1 head #{number}");
+                    }
+                    // Reorgs which do not remove any log are not notified by the logs subscription.
+                    if let Some(current) = origin {
+                        if !current.is_canonical(ws_provider).await.context("Checking L1 block hash")? {
+                            self.handle_reorg(backend, current, block_metrics.clone(), chain_id, sync_pause).await?;
+                            origin = None;
+                        }
+                    }
+                }
+                WsEvent::Log(log) if log.removed == Some(true) => {
+                    // The logs of the canonical branch will be sent again by the subscription.
+                    if let Some(removed) = L1Origin::from_log(&log).filter(|removed| Some(*removed) == origin) {
+                        self.handle_reorg(backend, removed, block_metrics.clone(), chain_id, sync_pause).await?;
+                        origin = None;
+                    }
+                }
+                WsEvent::Log(log) => {
+                    let log_origin = L1Origin::from_log(&log);
+                    let event: LogStateUpdate = parse_log(log).context("decoding LogStateUpdate log")?;
+                    let format_event =
+                        convert_log_state_update(event).context("formatting event into an L1StateUpdate")?;
+                    update_l1(backend, format_event, log_origin, block_metrics.clone(), chain_id, sync_pause).await?;
+
+                    if let Some(log_origin) = log_origin {
+                        last_l1_block = log_origin.block_number;
+                    }
+                    origin = log_origin;
+                }
+            }
+        }
+    }
+
+    /// Polls the http endpoint for LogStateUpdate events.
+    async fn listen_http(
+        &self,
+        backend: &DeoxysBackend,
+        start_block: u64,
+        block_metrics: BlockMetrics,
+        chain_id: Felt,
+        sync_pause: &PauseHandle,
     ) -> anyhow::Result<()> {
         let client = self.provider.clone();
         let address: Address = self.l1_core_address;
@@ -160,26 +331,154 @@ impl EthereumClient {
 
         let event_filter = contract.event::<LogStateUpdate>().from_block(start_block).to_block(EthBlockNumber::Latest);
 
-        let mut event_stream = event_filter.stream().await.context("initiatializing event stream")?;
+        let mut event_stream = event_filter.stream_with_meta().await.context("initiatializing event stream")?;
 
+        let mut origin: Option<L1Origin> = None;
         while let Some(event_result) = channel_wait_or_graceful_shutdown(event_stream.next()).await {
-            let log = event_result.context("listening for events")?;
+            let (log, meta) = event_result.context("listening for events")?;
+
+            // Polling does not notify us of removed logs: make sure the previous state update has
+            // not been reorged out before applying the next one.
+            if let Some(current) = origin {
+                if !current.is_canonical(self.provider.as_ref()).await.context("Checking L1 block hash")? {
+                    self.handle_reorg(backend, current, block_metrics.clone(), chain_id, sync_pause).await?;
+                }
+            }
+
             let format_event =
                 convert_log_state_update(log.clone()).context("formatting event into an L1StateUpdate")?;
-            update_l1(backend, format_event, block_metrics.clone(), chain_id)?;
+            let log_origin = L1Origin { block_number: meta.block_number.as_u64(), block_hash: meta.block_hash };
+            update_l1(backend, format_event, Some(log_origin), block_metrics.clone(), chain_id, sync_pause).await?;
+            origin = Some(log_origin);
         }
 
         Ok(())
     }
+
+    /// The L1 block the last state update has been read from is no longer canonical: the state
+    /// update it contained is invalidated by reading the latest state update again from the core
+    /// contract, on the canonical chain, and the L1 messages indexed from this block onwards are
+    /// reverted to be indexed again.
+    async fn handle_reorg(
+        &self,
+        backend: &DeoxysBackend,
+        origin: L1Origin,
+        block_metrics: BlockMetrics,
+        chain_id: Felt,
+        sync_pause: &PauseHandle,
+    ) -> anyhow::Result<()> {
+        log::warn!(
+            "↩️  L1 reorg detected: L1 block #{} ({:#x}) is no longer canonical, reverting to the canonical L1 state",
+            origin.block_number,
+            origin.block_hash
+        );
+        self.revert_l1_messages_from(backend, origin.block_number, sync_pause).await?;
+
+        let canonical_origin = self.get_last_event_origin().await.context("Getting the canonical L1 origin")?;
+        let canonical_state = Self::get_initial_state(self).await.context("Getting canonical ethereum state")?;
+        update_l1(backend, canonical_state, Some(canonical_origin), block_metrics, chain_id, sync_pause).await
+    }
+
+    /// Reverts the L1 messages indexed from the L1 block `l1_block_n` onwards, once the indexing of the current
+    /// range of L1 blocks is done.
+    async fn revert_l1_messages_from(
+        &self,
+        backend: &DeoxysBackend,
+        l1_block_n: u64,
+        sync_pause: &PauseHandle,
+    ) -> anyhow::Result<()> {
+        let _messages_lock = self.l1_messages_lock.lock().await;
+        let Some(_pause_guard) = wait_or_graceful_shutdown(sync_pause.acquire()).await else { return Ok(()) };
+        backend.revert_l1_messages_from(l1_block_n).context("Reverting the L1 messages")?;
+        log::debug!("revert_l1_messages_from: reverted the L1 messages from L1 block #{l1_block_n}");
+        Ok(())
+    }
 }
 
-/// Update the L1 state with the latest data
-pub fn update_l1(
+impl EthereumClient {
+    /// Indexes the `l1_handler` transactions produced by the `LogMessageToL2` events of the core contract, by L1
+    /// transaction hash. Indexing resumes from the last indexed L1 block, or starts at `start_block` when the messages
+    /// have not been indexed from this block yet.
+    pub async fn index_messages_to_l2(
+        &self,
+        backend: &DeoxysBackend,
+        start_block: u64,
+        chain_id: Felt,
+        sync_pause: &PauseHandle,
+    ) -> anyhow::Result<()> {
+        {
+            let _messages_lock = self.l1_messages_lock.lock().await;
+            if backend.backfill_l1_messages_from(start_block).context("Moving the L1 messages sync tip")? {
+                log::info!("📨 Indexing the L1 messages from L1 block #{start_block}");
+            }
+        }
+
+        let mut interval = tokio::time::interval(L1_MESSAGES_POLL_INTERVAL);
+        while wait_or_graceful_shutdown(interval.tick()).await.is_some() {
+            let _messages_lock = self.l1_messages_lock.lock().await;
+            // The sync tip is moved back when the messages of reorged L1 blocks are reverted
+            let mut from_block = match backend.get_l1_messages_sync_tip()? {
+                Some(sync_tip) => sync_tip + 1,
+                None => start_block,
+            };
+
+            let latest_block = match self.get_latest_block_number().await {
+                Ok(latest_block) => latest_block.as_u64(),
+                Err(err) => {
+                    log::warn!("Failed to get the latest L1 block to index the L1 messages: {err:#}");
+                    continue;
+                }
+            };
+
+            while from_block <= latest_block {
+                let to_block = latest_block.min(from_block + L1_MESSAGES_MAX_BLOCK_RANGE - 1);
+                let filter = Filter::new()
+                    .address(self.l1_core_address)
+                    .event(&LogMessageToL2::abi_signature())
+                    .from_block(from_block)
+                    .to_block(to_block);
+                let logs = match self.provider.get_logs(&filter).await {
+                    Ok(logs) => logs,
+                    Err(err) => {
+                        log::warn!("Failed to get the L1 messages of L1 blocks {from_block}..={to_block}: {err:#}");
+                        break;
+                    }
+                };
+
+                let Some(_pause_guard) = wait_or_graceful_shutdown(sync_pause.acquire()).await else { return Ok(()) };
+                for log in logs {
+                    let (Some(l1_tx_hash), Some(l1_block_n)) = (log.transaction_hash, log.block_number) else {
+                        continue;
+                    };
+                    let event: LogMessageToL2 = parse_log(log).context("decoding LogMessageToL2 log")?;
+                    let l1_handler = convert_log_message_to_l2(event).context("converting LogMessageToL2 log")?;
+                    let tx_hash = l1_handler.compute_hash(chain_id, false, false);
+                    backend
+                        .add_l1_message_to_l2(l1_block_n.as_u64(), l1_tx_hash.as_fixed_bytes(), tx_hash)
+                        .context("Storing L1 message")?;
+                }
+
+                backend.write_l1_messages_sync_tip(to_block).context("Setting the L1 messages sync tip")?;
+                from_block = to_block + 1;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Update the L1 state with the latest data, once the sync is not paused. The L1 block the state update has been read
+/// from is stored along with it, when known, to detect on startup a reorg which happened while the node was stopped.
+pub async fn update_l1(
     backend: &DeoxysBackend,
     state_update: L1StateUpdate,
+    origin: Option<L1Origin>,
     block_metrics: BlockMetrics,
     chain_id: Felt,
+    sync_pause: &PauseHandle,
 ) -> anyhow::Result<()> {
+    let Some(_pause_guard) = wait_or_graceful_shutdown(sync_pause.acquire()).await else { return Ok(()) };
+
     // This is a provisory check to avoid updating the state with an L1StateUpdate that should not have been detected
     //
     // TODO: Remove this check when the L1StateUpdate is properly verified
@@ -196,6 +495,11 @@ pub fn update_l1(
         backend
             .write_last_confirmed_block(state_update.block_number)
             .context("Setting l1 last confirmed block number")?;
+        if let Some(origin) = origin {
+            backend
+                .write_l1_last_confirmed_origin(origin.block_number, origin.block_hash.to_fixed_bytes())
+                .context("Setting l1 last confirmed block origin")?;
+        }
         log::debug!("update_l1: wrote last confirmed block number");
     }
 
@@ -236,32 +540,70 @@ pub fn update_l1(
 //     Ok(())
 // }
 
-/// Syncronize with the L1 latest state updates
+/// Syncronize with the L1 latest state updates. The L1 messages are indexed from `l1_messages_start_block`, or from the
+/// L1 block of the latest state update.
+#[allow(clippy::too_many_arguments)]
 pub async fn sync(
     backend: &DeoxysBackend,
     l1_url: Url,
+    l1_ws_url: Option<Url>,
     block_metrics: BlockMetrics,
     l1_core_address: Address,
+    l1_messages_start_block: Option<u64>,
     chain_id: Felt,
+    sync_pause: PauseHandle,
 ) -> anyhow::Result<()> {
-    // Clear L1 confirmed block at startup
-    backend.clear_last_confirmed_block().context("Clearing l1 last confirmed block number")?;
-    log::debug!("update_l1: cleared confirmed block number");
+    let mut client = EthereumClient::new(l1_url, l1_core_address).await.context("Creating ethereum client")?;
+    if let Some(l1_ws_url) = l1_ws_url {
+        if let Err(err) = client.connect_ws(l1_ws_url).await {
+            log::warn!("⚠️  Failed to connect to the L1 websocket endpoint, falling back to http polling: {err:#}");
+        }
+    }
 
-    let client = EthereumClient::new(l1_url, l1_core_address).await.context("Creating ethereum client")?;
+    // Clear L1 confirmed block at startup, and revert the L1 messages of its L1 block if it has been reorged out
+    // while the node was stopped
+    let stored_origin = backend.get_l1_last_confirmed_origin().context("Getting l1 last confirmed block origin")?;
+    if let Some((block_number, block_hash)) = stored_origin {
+        let origin = L1Origin { block_number, block_hash: H256(block_hash) };
+        if !origin.is_canonical(client.provider.as_ref()).await.context("Checking L1 block hash")? {
+            log::warn!(
+                "↩️  L1 reorg detected: L1 block #{} ({:#x}) is no longer canonical, reverting the L1 messages",
+                origin.block_number,
+                origin.block_hash
+            );
+            client.revert_l1_messages_from(backend, origin.block_number, &sync_pause).await?;
+        }
+    }
+    {
+        let Some(_pause_guard) = wait_or_graceful_shutdown(sync_pause.acquire()).await else { return Ok(()) };
+        backend.clear_last_confirmed_block().context("Clearing l1 last confirmed block number")?;
+        log::debug!("update_l1: cleared confirmed block number");
+    }
 
     log::info!("🚀 Subscribed to L1 state verification");
 
     // Get and store the latest verified state
+    let origin = client.get_last_event_origin().await.context("Retrieving the last event L1 block")?;
     let initial_state = EthereumClient::get_initial_state(&client).await.context("Getting initial ethereum state")?;
-    update_l1(backend, initial_state, block_metrics.clone(), chain_id)?;
+    update_l1(backend, initial_state, Some(origin), block_metrics.clone(), chain_id, &sync_pause).await?;
 
     // Listen to LogStateUpdate (0x77552641) update and send changes continusly
-    let start_block = client.get_last_event_block_number().await.context("Retrieving the last event block number")?;
-    client
-        .listen_and_update_state(backend, start_block, block_metrics, chain_id)
-        .await
-        .context("Subscribing to the LogStateUpdate event")?;
+    let start_block = origin.block_number;
+    let messages_start_block = l1_messages_start_block.unwrap_or(start_block);
+    tokio::try_join!(
+        async {
+            client
+                .listen_and_update_state(backend, start_block, block_metrics, chain_id, &sync_pause)
+                .await
+                .context("Subscribing to the LogStateUpdate event")
+        },
+        async {
+            client
+                .index_messages_to_l2(backend, messages_start_block, chain_id, &sync_pause)
+                .await
+                .context("Indexing the LogMessageToL2 events")
+        },
+    )?;
 
     Ok(())
 }
@@ -1,5 +1,6 @@
 //! Contains the code required to sync data from the feeder efficiently.
 use std::borrow::Cow;
+use std::num::NonZeroU32;
 use std::pin::pin;
 use std::sync::{Arc, Mutex};
 use std::time::Instant;
@@ -19,18 +20,20 @@ use futures::{stream, StreamExt};
 use num_traits::FromPrimitive;
 use starknet_providers::{ProviderError, SequencerGatewayProvider};
 use starknet_types_core::felt::Felt;
-use tokio::sync::{mpsc, oneshot};
+use tokio::sync::{mpsc, oneshot, watch};
 use tokio::task::JoinSet;
-use tokio::time::Duration;
+use tokio::time::{Duration, MissedTickBehavior};
 
 use crate::commitments::compute_state_root;
-use crate::convert::{convert_and_verify_block, convert_and_verify_class};
+use crate::convert::{convert_and_verify_block, convert_and_verify_class, BlockHashCheck};
 use crate::fetch::fetchers::{fetch_block_and_updates, FetchBlockId, L2BlockAndUpdates};
-use crate::fetch::l2_fetch_task;
+use crate::fetch::gateway_cache::GatewayCache;
+use crate::fetch::{chain_tip_probe_task, l2_fetch_task, tick_until_fetch_finished};
 use crate::metrics::block_metrics::BlockMetrics;
 use crate::utility::trim_hash;
 use dp_utils::{
-    channel_wait_or_graceful_shutdown, spawn_rayon_task, stopwatch_end, wait_or_graceful_shutdown, PerfStopwatch,
+    channel_wait_or_graceful_shutdown, spawn_rayon_task, stopwatch_end, wait_or_graceful_shutdown, PauseHandle,
+    PerfStopwatch,
 };
 
 // TODO: add more explicit error variants
@@ -44,6 +47,18 @@ pub enum L2SyncError {
     BlockFormat(Cow<'static, str>),
     #[error("Mismatched block hash for block {0}")]
     MismatchedBlockHash(u64),
+    #[error(
+        "Mismatched transaction hash for transaction {index} ({tx_type} v{version}) of block {block_number}: expected \
+         {expected:#x}, computed {computed:#x}"
+    )]
+    MismatchedTransactionHash {
+        block_number: u64,
+        index: usize,
+        tx_type: &'static str,
+        version: Felt,
+        expected: Felt,
+        computed: Felt,
+    },
     #[error("Gas price is too high: 0x{0:x}")]
     GasPriceOutOfBounds(Felt),
     #[error("Invalid Starknet version: {0}")]
@@ -61,7 +76,7 @@ pub struct L2StateUpdate {
 }
 
 #[allow(clippy::too_many_arguments)]
-async fn l2_verify_and_apply_task(
+pub(crate) async fn l2_verify_and_apply_task(
     backend: Arc<DeoxysBackend>,
     mut updates_receiver: mpsc::Receiver<L2ConvertedBlockAndUpdates>,
     verify: bool,
@@ -71,10 +86,26 @@ async fn l2_verify_and_apply_task(
     starting_block: u64,
     sync_timer: Arc<Mutex<Option<Instant>>>,
     telemetry: TelemetryHandle,
+    sync_pause: PauseHandle,
+    max_blocks_per_second: Option<NonZeroU32>,
 ) -> anyhow::Result<()> {
+    let mut throttle = max_blocks_per_second.map(|max_blocks_per_second| {
+        let mut interval = tokio::time::interval(Duration::from_secs(1) / max_blocks_per_second.get());
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        interval
+    });
+
     while let Some(L2ConvertedBlockAndUpdates { converted_block, converted_state_diff, converted_classes }) =
         channel_wait_or_graceful_shutdown(pin!(updates_receiver.recv())).await
     {
+        if let Some(throttle) = &mut throttle {
+            if wait_or_graceful_shutdown(throttle.tick()).await.is_none() {
+                break;
+            }
+        }
+        // The sync cannot be paused while a block is being imported.
+        let Some(_pause_guard) = wait_or_graceful_shutdown(sync_pause.acquire()).await else { break };
+
         let block_n = converted_block.info.header.block_number;
         let block_hash = converted_block.info.block_hash;
         let global_state_root = converted_block.info.header.global_state_root;
@@ -95,7 +126,8 @@ async fn l2_verify_and_apply_task(
 
             if global_state_root != state_root {
                 bail!(
-                    "Verified state root: {:#x} doesn't match fetched state root: {:#x}",
+                    "Block #{}: verified state root: {:#x} doesn't match fetched state root: {:#x}",
+                    block_n,
                     state_root,
                     global_state_root
                 );
@@ -187,35 +219,42 @@ async fn l2_block_conversion_task(
     updates_receiver: mpsc::Receiver<L2BlockAndUpdates>,
     output: mpsc::Sender<L2ConvertedBlockAndUpdates>,
     chain_id: Felt,
+    hash_check: Arc<BlockHashCheck>,
 ) -> anyhow::Result<()> {
     // Items of this stream are futures that resolve to blocks, which becomes a regular stream of blocks
     // using futures buffered.
-    let conversion_stream = stream::unfold((updates_receiver, chain_id), |(mut updates_recv, chain_id)| async move {
-        channel_wait_or_graceful_shutdown(updates_recv.recv()).await.map(
-            |L2BlockAndUpdates { block, state_diff, class_update, .. }| {
-                (
-                    spawn_rayon_task(move || {
-                        let sw = PerfStopwatch::new();
-                        let block_n = block.block_number;
-                        let task_convert_block =
-                            || convert_and_verify_block(block, state_diff, chain_id).context("Converting block");
-                        let task_convert_classes =
-                            || convert_and_verify_class(class_update, block_n).context("Converting classes");
-                        let (converted_block_with_state_diff, converted_classes) =
-                            rayon::join(task_convert_block, task_convert_classes);
-                        stopwatch_end!(sw, "convert_block_and_class {:?}: {:?}", block_n);
-                        let (converted_block, converted_state_diff) = converted_block_with_state_diff?;
-                        anyhow::Ok(L2ConvertedBlockAndUpdates {
-                            converted_block,
-                            converted_state_diff,
-                            converted_classes: converted_classes?,
-                        })
-                    }),
-                    (updates_recv, chain_id),
-                )
-            },
-        )
-    });
+    let conversion_stream = stream::unfold(
+        (updates_receiver, chain_id, hash_check),
+        |(mut updates_recv, chain_id, hash_check)| async move {
+            channel_wait_or_graceful_shutdown(updates_recv.recv()).await.map(
+                |L2BlockAndUpdates { block, state_diff, class_update, .. }| {
+                    let hash_check_ = Arc::clone(&hash_check);
+                    (
+                        spawn_rayon_task(move || {
+                            let sw = PerfStopwatch::new();
+                            let block_n = block.block_number;
+                            let task_convert_block = || {
+                                convert_and_verify_block(block, state_diff, chain_id, &hash_check_)
+                                    .context("Converting block")
+                            };
+                            let task_convert_classes =
+                                || convert_and_verify_class(class_update, block_n).context("Converting classes");
+                            let (converted_block_with_state_diff, converted_classes) =
+                                rayon::join(task_convert_block, task_convert_classes);
+                            stopwatch_end!(sw, "convert_block_and_class {:?}: {:?}", block_n);
+                            let (converted_block, converted_state_diff) = converted_block_with_state_diff?;
+                            anyhow::Ok(L2ConvertedBlockAndUpdates {
+                                converted_block,
+                                converted_state_diff,
+                                converted_classes: converted_classes?,
+                            })
+                        }),
+                        (updates_recv, chain_id, hash_check),
+                    )
+                },
+            )
+        },
+    );
 
     let mut stream = pin!(conversion_stream.buffered(10));
     while let Some(block) = channel_wait_or_graceful_shutdown(stream.next()).await {
@@ -231,8 +270,11 @@ async fn l2_pending_block_task(
     backend: Arc<DeoxysBackend>,
     sync_finished_cb: oneshot::Receiver<()>,
     provider: Arc<SequencerGatewayProvider>,
+    gateway_cache: Option<Arc<GatewayCache>>,
     chain_id: Felt,
     pending_block_poll_interval: Duration,
+    sync_pause: PauseHandle,
+    mut fetch_finished: watch::Receiver<()>,
 ) -> anyhow::Result<()> {
     // clear pending status
     {
@@ -248,13 +290,15 @@ async fn l2_pending_block_task(
 
     log::debug!("start pending block poll");
 
+    // the pending block is built on top of the chain tip, it is not polled anymore once the fetch task has stopped
+    // following it
     let mut interval = tokio::time::interval(pending_block_poll_interval);
     interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
-    while wait_or_graceful_shutdown(interval.tick()).await.is_some() {
+    while tick_until_fetch_finished(&mut interval, &mut fetch_finished).await {
         log::debug!("getting pending block...");
 
         let L2BlockAndUpdates { block_id: _, block, state_diff, class_update } =
-            fetch_block_and_updates(&backend, FetchBlockId::Pending, &provider)
+            fetch_block_and_updates(&backend, FetchBlockId::Pending, &provider, gateway_cache.as_deref())
                 .await
                 .context("Getting pending block from sequencer")?;
 
@@ -265,6 +309,8 @@ async fn l2_pending_block_task(
 
         log::debug!("pending block hash parent hash: {:#}", block.parent_block_hash.to_stark_felt());
 
+        let Some(_pause_guard) = wait_or_graceful_shutdown(sync_pause.acquire()).await else { break };
+
         if block.parent_block_hash == block_hash_best {
             log::debug!("pending block parent block hash matches chain tip, writing pending block");
 
@@ -304,6 +350,10 @@ pub struct L2SyncConfig {
     pub sync_polling_interval: Option<Duration>,
     pub backup_every_n_blocks: Option<u64>,
     pub pending_block_poll_interval: Duration,
+    pub gateway_cache: Option<GatewayCache>,
+    pub sync_pause: PauseHandle,
+    pub max_blocks_per_second: Option<NonZeroU32>,
+    pub block_hash_check: BlockHashCheck,
 }
 
 /// Spawns workers to fetch blocks and state updates from the feeder.
@@ -321,8 +371,11 @@ pub async fn sync(
     let (fetch_stream_sender, fetch_stream_receiver) = mpsc::channel(8);
     let (block_conv_sender, block_conv_receiver) = mpsc::channel(4);
     let provider = Arc::new(provider);
+    let gateway_cache = config.gateway_cache.map(Arc::new);
     let sync_timer = Arc::new(Mutex::new(None));
     let (once_caught_up_cb_sender, once_caught_up_cb_receiver) = oneshot::channel();
+    // Never sent to, the receivers are notified when the fetch task finishes and drops it
+    let (fetch_finished_sender, fetch_finished_receiver) = watch::channel(());
 
     // [Fetch task] ==new blocks and updates=> [Block conversion task] ======> [Verification and apply
     // task]
@@ -335,16 +388,27 @@ pub async fn sync(
     // starves the tokio worker
 
     let mut join_set = JoinSet::new();
-    join_set.spawn(l2_fetch_task(
+    let fetch_task = l2_fetch_task(
         Arc::clone(backend),
         config.first_block,
         config.n_blocks_to_sync,
         fetch_stream_sender,
         Arc::clone(&provider),
+        gateway_cache.clone(),
         config.sync_polling_interval,
         once_caught_up_cb_sender,
+    );
+    join_set.spawn(async move {
+        let _fetch_finished_sender = fetch_finished_sender;
+        fetch_task.await
+    });
+    join_set.spawn(chain_tip_probe_task(Arc::clone(backend), Arc::clone(&provider), fetch_finished_receiver.clone()));
+    join_set.spawn(l2_block_conversion_task(
+        fetch_stream_receiver,
+        block_conv_sender,
+        chain_id,
+        Arc::new(config.block_hash_check),
     ));
-    join_set.spawn(l2_block_conversion_task(fetch_stream_receiver, block_conv_sender, chain_id));
     join_set.spawn(l2_verify_and_apply_task(
         Arc::clone(backend),
         block_conv_receiver,
@@ -355,13 +419,18 @@ pub async fn sync(
         starting_block,
         Arc::clone(&sync_timer),
         telemetry,
+        config.sync_pause.clone(),
+        config.max_blocks_per_second,
     ));
     join_set.spawn(l2_pending_block_task(
         Arc::clone(backend),
         once_caught_up_cb_receiver,
         provider,
+        gateway_cache,
         chain_id,
         config.pending_block_poll_interval,
+        config.sync_pause,
+        fetch_finished_receiver,
     ));
 
     while let Some(res) = join_set.join_next().await {
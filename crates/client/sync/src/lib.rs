@@ -1,11 +1,13 @@
 #![allow(deprecated)]
 
 pub mod commitments;
+pub mod dump;
 pub mod fetch;
 pub mod l1;
 pub mod l2;
 pub mod metrics;
 pub mod reorgs;
+pub mod reverify;
 pub mod utils;
 
 #[cfg(feature = "m")]
@@ -22,10 +24,12 @@ pub mod starknet_sync_worker {
     use anyhow::Context;
     use dc_db::{db_metrics::DbMetrics, DeoxysBackend};
     use dc_telemetry::TelemetryHandle;
+    use dp_utils::PauseHandle;
     use reqwest::Url;
     use starknet_providers::SequencerGatewayProvider;
 
     use self::fetch::fetchers::FetchConfig;
+    use self::fetch::gateway_cache::GatewayCache;
     use super::*;
     use crate::metrics::block_metrics::BlockMetrics;
 
@@ -34,7 +38,9 @@ pub mod starknet_sync_worker {
         backend: &Arc<DeoxysBackend>,
         fetch_config: FetchConfig,
         l1_url: Option<Url>,
+        l1_ws_url: Option<Url>,
         l1_core_address: ethers::abi::Address,
+        l1_messages_start_block: Option<u64>,
         starting_block: Option<u64>,
         backup_every_n_blocks: Option<u64>,
         block_metrics: BlockMetrics,
@@ -42,6 +48,7 @@ pub mod starknet_sync_worker {
         chain_id: Felt,
         telemetry: TelemetryHandle,
         pending_block_poll_interval: Duration,
+        sync_pause: PauseHandle,
     ) -> anyhow::Result<()> {
         // let starting_block = starting_block + 1;
 
@@ -57,20 +64,42 @@ pub mod starknet_sync_worker {
 
         log::info!("⛓️  Starting L2 sync from block {}", starting_block);
 
-        let provider = SequencerGatewayProvider::new(
+        let http_client = fetch_config.http_client.build_client().context("Creating the gateway http client")?;
+        let provider = SequencerGatewayProvider::new_with_client(
             fetch_config.gateway.clone(),
             fetch_config.feeder_gateway.clone(),
             fetch_config.chain_id,
+            http_client.clone(),
         );
         let provider = match &fetch_config.api_key {
             Some(api_key) => provider.with_header("X-Throttling-Bypass".to_string(), api_key.clone()),
             None => provider,
         };
 
+        let gateway_cache = fetch_config
+            .gateway_cache_dir
+            .clone()
+            .map(|dir| {
+                GatewayCache::new(dir, http_client, fetch_config.feeder_gateway.clone(), fetch_config.api_key.clone())
+            })
+            .transpose()
+            .context("Creating gateway cache directory")?;
+
         let l1_block_metric = block_metrics.clone();
+        let l1_sync_pause = sync_pause.clone();
         let l1_fut = async {
             if let Some(l1_url) = l1_url {
-                l1::sync(backend, l1_url.clone(), l1_block_metric, l1_core_address, chain_id).await
+                l1::sync(
+                    backend,
+                    l1_url.clone(),
+                    l1_ws_url,
+                    l1_block_metric,
+                    l1_core_address,
+                    l1_messages_start_block,
+                    chain_id,
+                    l1_sync_pause,
+                )
+                .await
             } else {
                 Ok(())
             }
@@ -88,6 +117,10 @@ pub mod starknet_sync_worker {
                     sync_polling_interval: fetch_config.sync_polling_interval,
                     backup_every_n_blocks,
                     pending_block_poll_interval,
+                    gateway_cache,
+                    sync_pause,
+                    max_blocks_per_second: fetch_config.max_blocks_per_second,
+                    block_hash_check: fetch_config.block_hash_check,
                 },
                 block_metrics,
                 db_metrics,
@@ -0,0 +1,112 @@
+//! Re-verification of an already synced database, for example one restored from a backup or copied
+//! from a snapshot.
+//!
+//! State roots can only be recomputed by applying the state diffs on top of the previous state, so
+//! the stored chain is replayed from genesis into a scratch database. Blocks go through the same
+//! pipeline as a dump import: commitments and block hashes are recomputed from the stored
+//! transactions, receipts and state diffs, and the state root is verified after each block. Nothing
+//! is fetched from the network.
+use std::sync::{Arc, Mutex};
+
+use anyhow::Context;
+use dc_db::db_metrics::DbMetrics;
+use dc_db::DeoxysBackend;
+use dc_telemetry::TelemetryHandle;
+use dp_utils::{PauseHandle, PerfStopwatch};
+use starknet_types_core::felt::Felt;
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+
+use crate::convert::BlockHashCheck;
+use crate::dump::{dump_block_conversion_task, get_dump_block, DumpBlock};
+use crate::l2::l2_verify_and_apply_task;
+use crate::metrics::block_metrics::BlockMetrics;
+
+/// Replays all the blocks of `backend` into the empty `scratch` database, recomputing and checking
+/// every commitment, block hash and state root.
+///
+/// # Returns
+///
+/// An error describing the first divergent block, if any.
+pub async fn reverify_database(
+    backend: &Arc<DeoxysBackend>,
+    scratch: &Arc<DeoxysBackend>,
+    block_metrics: BlockMetrics,
+    db_metrics: DbMetrics,
+    chain_id: Felt,
+    telemetry: TelemetryHandle,
+    hash_check: BlockHashCheck,
+) -> anyhow::Result<()> {
+    let last_block = backend.get_latest_block_n().context("getting sync tip")?.context("The database is empty")?;
+    if scratch.get_latest_block_n().context("getting scratch database sync tip")?.is_some() {
+        anyhow::bail!("The scratch database used for re-verification is not empty");
+    }
+
+    log::info!("🔍 Re-verifying blocks #0..=#{last_block} from the database");
+    let sw = PerfStopwatch::new();
+
+    let (db_sender, db_receiver) = mpsc::channel(8);
+    let (block_conv_sender, block_conv_receiver) = mpsc::channel(4);
+
+    // [Database read task] ==dump blocks=> [Block verification task] ======> [Verification and apply task]
+    let mut join_set = JoinSet::new();
+    let backend_ = Arc::clone(backend);
+    join_set.spawn(async move {
+        tokio::task::spawn_blocking(move || db_read_task(&backend_, last_block, db_sender))
+            .await
+            .context("database read task was dropped")?
+    });
+    join_set.spawn(dump_block_conversion_task(db_receiver, block_conv_sender, chain_id, Arc::new(hash_check)));
+    join_set.spawn(l2_verify_and_apply_task(
+        Arc::clone(scratch),
+        block_conv_receiver,
+        true,
+        None,
+        block_metrics,
+        db_metrics,
+        0,
+        Arc::new(Mutex::new(None)),
+        telemetry,
+        PauseHandle::new(),
+        None,
+    ));
+
+    let mut result = Ok(());
+    while let Some(res) = join_set.join_next().await {
+        if let Err(err) = res.context("task was dropped").and_then(|res| res) {
+            // Stop the other tasks as soon as a divergent block is found
+            join_set.abort_all();
+            result = Err(err);
+            break;
+        }
+    }
+
+    if let Err(err) = result {
+        log::error!("❌ Database re-verification failed: {err:#}");
+        return Err(err.context("Re-verifying database"));
+    }
+
+    match scratch.get_latest_block_n().context("getting scratch database sync tip")? {
+        Some(block_n) if block_n == last_block => {
+            log::info!("✅ Re-verified {} blocks, the database is consistent ({:?})", last_block + 1, sw.elapsed());
+            Ok(())
+        }
+        // Graceful shutdown
+        _ => {
+            log::info!("⏹️  Database re-verification interrupted");
+            Ok(())
+        }
+    }
+}
+
+/// This is a blocking task.
+fn db_read_task(backend: &DeoxysBackend, last_block: u64, output: mpsc::Sender<DumpBlock>) -> anyhow::Result<()> {
+    for block_n in 0..=last_block {
+        let block = get_dump_block(backend, block_n).with_context(|| format!("Reading block #{block_n}"))?;
+        if output.blocking_send(block).is_err() {
+            // channel closed
+            break;
+        }
+    }
+    Ok(())
+}
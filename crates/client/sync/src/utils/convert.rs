@@ -1,19 +1,21 @@
 //! Converts types from [`starknet_providers`] to deoxys's expected types.
 
+use std::ops::RangeInclusive;
+
 use dc_db::storage_updates::DbClassUpdate;
+use dp_block::commitments::BlockCommitments;
 use dp_block::header::{GasPrices, L1DataAvailabilityMode, PendingHeader};
 use dp_block::{
     DeoxysBlock, DeoxysBlockInfo, DeoxysBlockInner, DeoxysPendingBlock, DeoxysPendingBlockInfo, Header, StarknetVersion,
 };
-use dp_class::{ClassInfo, ConvertedClass, ToCompiledClass};
+use dp_class::{compilation_cache, ClassInfo, ConvertedClass};
 use dp_convert::felt_to_u128;
 use dp_receipt::{Event, TransactionReceipt};
 use dp_state_update::StateDiff;
-use dp_transactions::MAIN_CHAIN_ID;
 use rayon::prelude::*;
 use starknet_types_core::felt::Felt;
 
-use crate::commitments::{memory_event_commitment, memory_receipt_commitment, memory_transaction_commitment};
+use crate::commitments::log_receipt_commitment_mismatch;
 use crate::l2::L2SyncError;
 
 pub fn convert_inner(
@@ -29,6 +31,28 @@ pub fn convert_inner(
     Ok(DeoxysBlockInner::new(transactions, transactions_receipts))
 }
 
+/// How blocks whose hash does not match the recomputed one are handled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockHashCheck {
+    /// Every block hash must match.
+    Strict,
+    /// Mismatches are allowed for these blocks only. Some chains have historical blocks whose hash
+    /// cannot be recomputed.
+    AllowMismatches(Vec<RangeInclusive<u64>>),
+    /// Mismatches are logged and ignored.
+    Lenient,
+}
+
+impl BlockHashCheck {
+    pub fn allows_mismatch(&self, block_number: u64) -> bool {
+        match self {
+            Self::Strict => false,
+            Self::AllowMismatches(ranges) => ranges.iter().any(|range| range.contains(&block_number)),
+            Self::Lenient => true,
+        }
+    }
+}
+
 /// This function does not check block hashes and such
 pub fn convert_pending(
     block: starknet_providers::sequencer::models::Block,
@@ -50,7 +74,7 @@ pub fn convert_pending(
     // TODO tx_hash
 
     // let ((_transaction_commitment, txs_hashes), event_commitment) =
-    //     memory_transaction_commitment(&block_inner.transactions, &events, chain_id, block_number);
+    //     transaction_commitment(&block_inner.transactions, &events, chain_id, block_number);
 
     Ok((DeoxysPendingBlock::new(DeoxysPendingBlockInfo::new(header, vec![]), block_inner), converted_state_diff))
 }
@@ -60,6 +84,7 @@ pub fn convert_and_verify_block(
     block: starknet_providers::sequencer::models::Block,
     state_diff: starknet_core::types::StateDiff,
     chain_id: Felt,
+    hash_check: &BlockHashCheck,
 ) -> Result<(DeoxysBlock, StateDiff), L2SyncError> {
     let block_inner = convert_inner(block.transactions, block.transaction_receipts)?;
     let converted_state_diff: StateDiff = state_diff.into();
@@ -76,17 +101,20 @@ pub fn convert_and_verify_block(
     let state_diff_length = converted_state_diff.len() as u64;
     let starknet_version = protocol_version(block.starknet_version)?;
 
-    // compute the 4 commitments in parallel
-    let tasks_tx_and_event_commitment = || {
-        rayon::join(
-            || memory_transaction_commitment(&block_inner.transactions, chain_id, starknet_version, block_number),
-            || memory_event_commitment(&events_with_tx_hash, starknet_version),
-        )
-    };
-    let tasks_receipt_and_state_diff_commitment =
-        || rayon::join(|| memory_receipt_commitment(&block_inner.receipts), || converted_state_diff.compute_hash());
-    let (((transaction_commitment, txs_hashes), event_commitment), (receipt_commitment, state_diff_commitment)) =
-        rayon::join(tasks_tx_and_event_commitment, tasks_receipt_and_state_diff_commitment);
+    let BlockCommitments {
+        transaction_commitment,
+        txs_hashes,
+        event_commitment,
+        receipt_commitment,
+        state_diff_commitment,
+    } = BlockCommitments::compute(
+        &block_inner,
+        &events_with_tx_hash,
+        &converted_state_diff,
+        chain_id,
+        starknet_version,
+        block_number,
+    );
 
     let header = Header::new(
         block.parent_block_hash,
@@ -107,15 +135,124 @@ pub fn convert_and_verify_block(
     );
 
     let computed_block_hash = header.compute_hash(chain_id);
-
-    // mismatched block hash is allowed for blocks 1466..=2242 on mainnet
-    if computed_block_hash != block_hash && !((1466..=2242).contains(&block_number) && chain_id == MAIN_CHAIN_ID) {
-        return Err(L2SyncError::MismatchedBlockHash(block_number));
+    if computed_block_hash != block_hash {
+        check_block_hash_mismatch(hash_check, block_number, starknet_version, &block_inner, &txs_hashes)?;
     }
 
     Ok((DeoxysBlock::new(DeoxysBlockInfo::new(header, txs_hashes, block_hash), block_inner), converted_state_diff))
 }
 
+/// Verifies a block which has already been converted, for example when it comes from a block dump
+/// instead of the feeder gateway. The commitments are recomputed from the block content, and the
+/// block hash from the resulting header.
+///
+/// Compute heavy, this should only be called in a rayon ctx
+pub fn verify_block(
+    block: DeoxysBlock,
+    state_diff: StateDiff,
+    chain_id: Felt,
+    hash_check: &BlockHashCheck,
+) -> Result<(DeoxysBlock, StateDiff), L2SyncError> {
+    let block_number = block.info.header.block_number;
+    let starknet_version = block.info.header.protocol_version;
+    let events_with_tx_hash = events_with_tx_hash(&block.inner.receipts);
+
+    let BlockCommitments {
+        transaction_commitment,
+        txs_hashes,
+        event_commitment,
+        receipt_commitment,
+        state_diff_commitment,
+    } = BlockCommitments::compute(
+        &block.inner,
+        &events_with_tx_hash,
+        &state_diff,
+        chain_id,
+        starknet_version,
+        block_number,
+    );
+
+    let header = Header {
+        transaction_count: block.inner.transactions.len() as u64,
+        transaction_commitment,
+        event_count: events_with_tx_hash.len() as u64,
+        event_commitment,
+        state_diff_length: state_diff.len() as u64,
+        state_diff_commitment,
+        receipt_commitment,
+        ..block.info.header.clone()
+    };
+
+    let computed_block_hash = header.compute_hash(chain_id);
+    if computed_block_hash != block.info.block_hash {
+        check_block_hash_mismatch(hash_check, block_number, starknet_version, &block.inner, &txs_hashes)?;
+    }
+
+    let block_hash = block.info.block_hash;
+    Ok((DeoxysBlock::new(DeoxysBlockInfo::new(header, txs_hashes, block_hash), block.inner), state_diff))
+}
+
+fn check_block_hash_mismatch(
+    hash_check: &BlockHashCheck,
+    block_number: u64,
+    starknet_version: StarknetVersion,
+    block_inner: &DeoxysBlockInner,
+    txs_hashes: &[Felt],
+) -> Result<(), L2SyncError> {
+    if hash_check.allows_mismatch(block_number) {
+        if *hash_check == BlockHashCheck::Lenient {
+            log::warn!("Ignoring mismatched block hash for block {block_number}");
+        }
+        return Ok(());
+    }
+
+    // a wrong transaction hash is the most common cause of a mismatch, and the most precise to report
+    check_transaction_hashes(block_number, block_inner, txs_hashes)?;
+
+    if starknet_version.has_receipt_commitment() {
+        log_receipt_commitment_mismatch(block_number, &block_inner.receipts, txs_hashes);
+    }
+    Err(L2SyncError::MismatchedBlockHash(block_number))
+}
+
+/// Compares the recomputed transaction hashes with the ones provided by the gateway, which are
+/// carried by the receipts. Every mismatching transaction is logged, and the first one is returned
+/// as the error.
+fn check_transaction_hashes(
+    block_number: u64,
+    block_inner: &DeoxysBlockInner,
+    txs_hashes: &[Felt],
+) -> Result<(), L2SyncError> {
+    let mut first_mismatch = None;
+    for (index, ((tx, receipt), computed)) in
+        block_inner.transactions.iter().zip(&block_inner.receipts).zip(txs_hashes).enumerate()
+    {
+        let expected = receipt.transaction_hash();
+        if expected == *computed {
+            continue;
+        }
+
+        log::warn!(
+            "Block #{block_number}: transaction {index} ({} v{}) has hash {computed:#x}, expected {expected:#x}",
+            tx.tx_type(),
+            tx.version()
+        );
+        first_mismatch.get_or_insert(L2SyncError::MismatchedTransactionHash {
+            block_number,
+            index,
+            tx_type: tx.tx_type(),
+            version: tx.version(),
+            expected,
+            computed: *computed,
+        });
+    }
+
+    match first_mismatch {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
 fn protocol_version(version: Option<String>) -> Result<StarknetVersion, L2SyncError> {
     match version {
         None => Ok(StarknetVersion::default()),
@@ -183,8 +320,9 @@ pub fn convert_and_verify_class(
             //     // return Err(ConvertClassError::MismatchedClassHash { expected, got: class_hash });
             // }
 
-            let compiled_class =
-                contract_class.compile().map_err(|e| ConvertClassError::CompilationClassError(e.to_string()))?;
+            let compiled_class = compilation_cache()
+                .get_or_compile(class_hash, &contract_class)
+                .map_err(|e| ConvertClassError::CompilationClassError(format!("{e:#}")))?;
 
             let class_info =
                 ClassInfo { contract_class: contract_class.into(), block_number: block_n, compiled_class_hash };
@@ -1,6 +1,8 @@
 //! Utility functions for Deoxys.
 
 use anyhow::{bail, Context};
+use dp_convert::ToFelt;
+use dp_transactions::L1HandlerTransaction;
 use ethers::types::{I256, U256};
 use rand::seq::SliceRandom;
 use rand::thread_rng;
@@ -9,7 +11,7 @@ use starknet_api::hash::StarkFelt;
 use starknet_types_core::felt::Felt;
 use thiserror::Error;
 
-use crate::l1::{L1StateUpdate, LogStateUpdate};
+use crate::l1::{L1StateUpdate, LogMessageToL2, LogStateUpdate};
 
 /// Returns a random Pokémon name.
 pub async fn get_random_pokemon_name() -> Result<String, Box<dyn std::error::Error>> {
@@ -43,6 +45,25 @@ pub fn convert_log_state_update(log_state_update: LogStateUpdate) -> anyhow::Res
     Ok(L1StateUpdate { block_number, global_root, block_hash })
 }
 
+/// The `l1_handler` transaction which consumes an L1 -> L2 message.
+pub fn convert_log_message_to_l2(log_message_to_l2: LogMessageToL2) -> anyhow::Result<L1HandlerTransaction> {
+    let nonce = u64::try_from(log_message_to_l2.nonce).map_err(|_| anyhow::anyhow!("Message nonce is too big"))?;
+    let from_address = Felt::from_bytes_be_slice(log_message_to_l2.from_address.as_bytes());
+    let payload = log_message_to_l2
+        .payload
+        .into_iter()
+        .map(|value| u256_to_starkfelt(value).map(|value| value.to_felt()))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    Ok(L1HandlerTransaction {
+        version: Felt::ZERO,
+        nonce,
+        contract_address: u256_to_starkfelt(log_message_to_l2.to_address)?.to_felt(),
+        entry_point_selector: u256_to_starkfelt(log_message_to_l2.selector)?.to_felt(),
+        calldata: std::iter::once(from_address).chain(payload).collect(),
+    })
+}
+
 #[derive(Error, Debug)]
 pub enum RpcError {
     #[error("HTTP request failed for L1 Free RPC check")]
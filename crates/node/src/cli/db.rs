@@ -1,16 +1,112 @@
 use std::path::PathBuf;
 
+use dc_exec::ExecutionLimits;
+use starknet_types_core::felt::Felt;
+
+/// Parses a transaction hash given in hexadecimal.
+fn parse_tx_hash(s: &str) -> Result<Felt, String> {
+    Felt::from_hex(s.trim()).map_err(|err| format!("Invalid transaction hash `{s}`: {err}"))
+}
+
 #[derive(Clone, Debug, clap::Args)]
 pub struct DbParams {
     /// The path where deoxys will store the database. You should probably change it.
     #[clap(long, default_value = "/tmp/deoxys", value_name = "PATH")]
     pub base_path: PathBuf,
 
-    /// Directory for backups. Use it with `--restore-from-latest-backup` or `--backup-every-n-blocks <NUMBER OF BLOCKS>`.
+    /// Directory for backups. Use it with `--restore-from-latest-backup` or
+    /// `--backup-every-n-blocks <NUMBER OF BLOCKS>`.
     #[clap(long, value_name = "PATH")]
     pub backup_dir: Option<PathBuf>,
 
     /// Restore the database at startup from the latest backup version. Use it with `--backup-dir <PATH>`
     #[clap(long)]
     pub restore_from_latest_backup: bool,
+
+    /// Export the blocks of the database to a compressed dump file and exit, instead of starting the node. The dump can
+    /// then be imported using `--import-dump <PATH>`.
+    #[clap(long, value_name = "PATH")]
+    pub export_dump: Option<PathBuf>,
+
+    /// First block to export. Use it with `--export-dump <PATH>`.
+    #[clap(long, value_name = "BLOCK NUMBER", default_value_t = 0, requires = "export_dump")]
+    pub export_dump_from: u64,
+
+    /// Last block to export, inclusive. Defaults to the latest synchronized block. Use it with `--export-dump <PATH>`.
+    #[clap(long, value_name = "BLOCK NUMBER", requires = "export_dump")]
+    pub export_dump_to: Option<u64>,
+
+    /// Re-verify the database and exit, instead of starting the node. All the blocks are replayed from genesis into a
+    /// scratch database, recomputing every commitment, block hash and state root without fetching anything from the
+    /// network. The first divergent block is reported. Use it to validate a database restored from a backup or copied
+    /// from a snapshot.
+    #[clap(long, conflicts_with = "export_dump")]
+    pub reverify_db: bool,
+
+    /// Re-execute the blocks of the database and exit, instead of starting the node. The receipts, events and fees
+    /// computed by the execution are compared to the stored ones, and every divergence is reported. Where
+    /// `--reverify-db` verifies the commitments of the blocks, this verifies their execution. Blocks older than
+    /// Starknet 0.13.0 are skipped, unless their versioned constants are loaded with `--versioned-constants`.
+    #[clap(long, conflicts_with_all = ["export_dump", "reverify_db"])]
+    pub verify_execution: bool,
+
+    /// First block to re-execute. Use it with `--verify-execution`.
+    #[clap(long, value_name = "BLOCK NUMBER", default_value_t = 0, requires = "verify_execution")]
+    pub verify_execution_from: u64,
+
+    /// Last block to re-execute, inclusive. Defaults to the latest synchronized block. Use it with
+    /// `--verify-execution`.
+    #[clap(long, value_name = "BLOCK NUMBER", requires = "verify_execution")]
+    pub verify_execution_to: Option<u64>,
+
+    /// Maximum number of Cairo steps of the execution of a transaction, when re-executing the blocks. Defaults to the
+    /// limit of the protocol version of the block. Use it with `--verify-execution`.
+    #[clap(long, value_name = "STEPS", requires = "verify_execution")]
+    pub verify_execution_max_steps: Option<u32>,
+
+    /// Maximum number of Cairo steps of the validation of a transaction, when re-executing the blocks. Defaults to the
+    /// limit of the protocol version of the block. Use it with `--verify-execution`.
+    #[clap(long, value_name = "STEPS", requires = "verify_execution")]
+    pub verify_execution_validate_max_steps: Option<u32>,
+
+    /// Maximum depth of nested contract calls, when re-executing the blocks. Defaults to the limit of the protocol
+    /// version of the block. Use it with `--verify-execution`.
+    #[clap(long, value_name = "DEPTH", requires = "verify_execution")]
+    pub verify_execution_max_recursion_depth: Option<usize>,
+
+    /// Re-execute a transaction of the database and exit, instead of starting the node. Every value the execution reads
+    /// from the state is recorded to a replay file, along with the transactions before it in its block. The replay file
+    /// is self-contained: attach it to a bug report to reproduce an execution divergence with `--replay <PATH>`. Use it
+    /// with `--replay-file <PATH>`.
+    #[clap(
+        long,
+        value_name = "TX HASH",
+        value_parser = parse_tx_hash,
+        requires = "replay_file",
+        conflicts_with_all = ["export_dump", "reverify_db", "verify_execution"]
+    )]
+    pub record_replay: Option<Felt>,
+
+    /// The replay file written by `--record-replay <TX HASH>`.
+    #[clap(long, value_name = "PATH", requires = "record_replay")]
+    pub replay_file: Option<PathBuf>,
+
+    /// Execute a transaction again from a replay file recorded with `--record-replay <TX HASH>` and exit, instead of
+    /// starting the node. The database is not opened. The trace of the transaction is printed.
+    #[clap(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = ["export_dump", "reverify_db", "verify_execution", "record_replay"]
+    )]
+    pub replay: Option<PathBuf>,
+}
+
+impl DbParams {
+    pub fn verify_execution_limits(&self) -> ExecutionLimits {
+        ExecutionLimits {
+            max_steps: self.verify_execution_max_steps,
+            validate_max_steps: self.verify_execution_validate_max_steps,
+            max_recursion_depth: self.verify_execution_max_recursion_depth,
+        }
+    }
 }
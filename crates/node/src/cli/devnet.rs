@@ -0,0 +1,24 @@
+use clap::Args;
+use starknet_types_core::felt::Felt;
+
+fn parse_address(s: &str) -> Result<Felt, String> {
+    Felt::from_hex(s.trim()).map_err(|err| format!("Invalid address `{s}`: {err}"))
+}
+
+/// Parameters used to config the devnet mode.
+#[derive(Debug, Clone, Args)]
+pub struct DevnetParams {
+    /// Produce blocks locally instead of following the chain, on top of the blocks already in the database. The
+    /// transactions sent to the write rpc methods are executed by the node, and the `dev_` rpc methods are served
+    /// along with the admin methods. The sync is disabled.
+    #[arg(long)]
+    pub devnet: bool,
+
+    /// Mine a block every this many seconds. By default, a block is mined for each transaction.
+    #[arg(long, value_name = "SECONDS", value_parser = clap::value_parser!(u64).range(1..), requires = "devnet")]
+    pub devnet_block_time: Option<u64>,
+
+    /// Address of the sequencer of the devnet blocks, the sequencer of the latest block by default.
+    #[arg(long, value_name = "ADDRESS", value_parser = parse_address, requires = "devnet")]
+    pub devnet_sequencer_address: Option<Felt>,
+}
@@ -0,0 +1,26 @@
+use clap::Args;
+
+/// Parameters used to config the gRPC server.
+#[derive(Debug, Clone, Args)]
+pub struct GrpcParams {
+    /// Enable the gRPC server, which serves the block, state and event read API as well as
+    /// streams of the new blocks and events. It is disabled by default.
+    #[arg(long)]
+    pub grpc: bool,
+
+    /// The port used by the gRPC server.
+    #[arg(long, value_name = "PORT", default_value = "50051")]
+    pub grpc_port: u16,
+
+    /// Listen on all network interfaces. This usually means the gRPC server will be accessible externally.
+    ///
+    /// The callers are authenticated and rate limited like the callers of the RPC read methods, with
+    /// `--rpc-api-keys-file`, `--rpc-jwt-secret`, `--rpc-rate-limit` and
+    /// `--rpc-rate-limit-whitelisted-ips`.
+    #[arg(long)]
+    pub grpc_external: bool,
+
+    /// Maximum number of block and event streams open at once, over every connection.
+    #[arg(long, value_name = "STREAMS", default_value_t = 256)]
+    pub grpc_max_streams: usize,
+}
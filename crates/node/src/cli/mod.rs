@@ -1,10 +1,14 @@
 pub mod db;
+pub mod devnet;
+pub mod grpc;
 pub mod prometheus;
 pub mod rpc;
 pub mod sync;
 pub mod telemetry;
 
 pub use db::*;
+pub use devnet::*;
+pub use grpc::*;
 pub use prometheus::*;
 pub use rpc::*;
 pub use sync::*;
@@ -37,6 +41,14 @@ pub struct RunCmd {
     #[clap(flatten)]
     pub rpc_params: RpcParams,
 
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub grpc_params: GrpcParams,
+
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub devnet_params: DevnetParams,
+
     /// Run the TUI dashboard
     #[cfg(feature = "tui")]
     #[clap(long)]
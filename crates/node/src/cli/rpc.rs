@@ -1,11 +1,16 @@
 use std::convert::Infallible;
 use std::net::{Ipv4Addr, SocketAddr};
 use std::num::NonZeroU32;
+use std::path::PathBuf;
 use std::str::FromStr;
 
 use clap::ValueEnum;
+use dc_exec::{ExecutionLimits, FeeMargin};
+use dc_rpc::constants::{DEFAULT_EVENTS_MAX_BLOCKS_SCANNED, MAX_EVENTS_CHUNK_SIZE};
+use dc_rpc::{DEFAULT_EXECUTION_CACHE_SIZE, DEFAULT_EXECUTION_CONTEXT_TTL, DEFAULT_STATE_CACHE_SIZE};
 use ip_network::IpNetwork;
 use jsonrpsee::server::BatchRequestConfig;
+use url::Url;
 
 /// Available RPC methods.
 #[derive(Debug, Copy, Clone, PartialEq, ValueEnum)]
@@ -28,6 +33,29 @@ pub const RPC_DEFAULT_MAX_SUBS_PER_CONN: u32 = 1024;
 pub const RPC_DEFAULT_MAX_REQUEST_SIZE_MB: u32 = 15;
 /// The default max response size in MB.
 pub const RPC_DEFAULT_MAX_RESPONSE_SIZE_MB: u32 = 15;
+/// The default max number of calls in a batch request.
+pub const RPC_DEFAULT_MAX_BATCH_REQUEST_LEN: u32 = 256;
+/// The default number of batch calls answered concurrently.
+pub const RPC_DEFAULT_BATCH_CONCURRENCY: u32 = 32;
+/// The default time budget of a call in seconds.
+pub const RPC_DEFAULT_TIMEOUT_SECS: u64 = 30;
+/// The default time budget of a trace, simulation, fee estimation or `starknet_call` call in seconds.
+pub const RPC_DEFAULT_TRACE_TIMEOUT_SECS: u64 = 300;
+
+/// Default time the in-flight RPC requests have to complete on shutdown.
+pub const RPC_DEFAULT_SHUTDOWN_DRAIN_TIMEOUT_SECS: u64 = 10;
+
+/// Default maximum number of execution-heavy RPC calls running at once.
+pub const RPC_DEFAULT_MAX_CONCURRENT_EXECUTIONS: usize = 16;
+
+/// Default time an execution-heavy RPC call waits for its turn, in milliseconds.
+pub const RPC_DEFAULT_EXECUTION_QUEUE_TIMEOUT_MILLIS: u64 = 5000;
+/// The default interval between two websocket pings in seconds.
+pub const RPC_DEFAULT_WS_PING_INTERVAL_SECS: u64 = 30;
+/// The default time in seconds after which a silent websocket connection is considered inactive.
+pub const RPC_DEFAULT_WS_IDLE_TIMEOUT_SECS: u64 = 60;
+/// The default number of blocks the node can lag behind the tip of the chain while being ready.
+pub const RPC_DEFAULT_HEALTH_MAX_SYNC_LAG: u64 = 10;
 /// The default number of connection..
 pub const RPC_DEFAULT_MAX_CONNECTIONS: u32 = 100;
 /// The default number of messages the RPC server
@@ -48,7 +76,7 @@ impl FromStr for Cors {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut is_all = false;
         let mut origins = Vec::new();
-        for part in s.split(',') {
+        for part in s.split(',').map(str::trim).filter(|part| !part.is_empty()) {
             match part {
                 "all" | "*" => {
                     is_all = true;
@@ -89,15 +117,31 @@ pub struct RpcParams {
 	)]
     pub rpc_methods: RpcMethods,
 
-    /// RPC rate limiting (calls/minute) for each connection.
+    /// RPC rate limiting (calls/minute) for each ip address.
     ///
     /// This is disabled by default.
     ///
     /// For example `--rpc-rate-limit 10` will maximum allow
-    /// 10 calls per minute per connection.
+    /// 10 calls per minute per ip address. This limit applies to the
+    /// read methods, and to the trace and write methods unless they
+    /// have their own limit.
     #[arg(long)]
     pub rpc_rate_limit: Option<NonZeroU32>,
 
+    /// RPC rate limiting (calls/minute) of the trace, simulation, fee
+    /// estimation and `starknet_call` methods for each ip address.
+    ///
+    /// Defaults to `--rpc-rate-limit`.
+    #[arg(long)]
+    pub rpc_rate_limit_trace: Option<NonZeroU32>,
+
+    /// RPC rate limiting (calls/minute) of the write and admin methods
+    /// for each ip address.
+    ///
+    /// Defaults to `--rpc-rate-limit`.
+    #[arg(long)]
+    pub rpc_rate_limit_write: Option<NonZeroU32>,
+
     /// Disable RPC rate limiting for certain ip addresses or ranges.
     ///
     /// Each IP address must be in the following notation: `1.2.3.4/24`.
@@ -109,15 +153,48 @@ pub struct RpcParams {
     /// When using a reverse proxy setup, the real requester IP is usually added to the headers as `X-Real-IP` or `X-Forwarded-For`.
     /// By default, the RPC server will not trust these headers.
     ///
-    /// This is currently only useful for rate-limiting reasons.
+    /// This is currently only useful for rate-limiting reasons, and for `--rpc-write-allowed-ips`.
     #[arg(long)]
     pub rpc_rate_limit_trust_proxy_headers: bool,
 
-    /// Set the maximum RPC request payload size for both HTTP and WebSockets in megabytes.
+    /// Only allow the callers from these ip addresses or ranges to call the write and admin
+    /// methods. Other callers can still call the read and trace methods. By default, any caller
+    /// allowed by `--rpc-methods` can call them.
+    ///
+    /// Each IP address must be in the following notation: `1.2.3.4/24`.
+    #[arg(long, num_args = 1..)]
+    pub rpc_write_allowed_ips: Vec<IpNetwork>,
+
+    /// Require the RPC callers to authenticate with one of the api keys of this file.
+    ///
+    /// The file is a JSON list of `{ "key": "<key>", "methods": ["read", "trace", "write"] }`
+    /// objects, `methods` defaulting to every kind of method. Keys are passed as
+    /// `Authorization: Bearer <key>` or `X-Api-Key: <key>`. The file is reloaded when the node
+    /// receives a SIGHUP.
+    #[arg(long, value_name = "PATH")]
+    pub rpc_api_keys_file: Option<PathBuf>,
+
+    /// Accept the JWTs signed with this secret (HS256) as RPC credentials.
+    ///
+    /// Tokens must have an `exp` claim, and an optional `methods` claim restricts the kinds of
+    /// methods they can call.
+    #[arg(long, value_name = "SECRET")]
+    pub rpc_jwt_secret: Option<String>,
+
+    /// Also serve the RPC on a unix domain socket at this path, under the same versioned paths as
+    /// over TCP. Every namespace, including `admin`, is exposed on the socket regardless of
+    /// `--rpc-methods`, and its clients are neither authenticated nor rate limited: use the file
+    /// permissions of the socket to control who can access it.
+    #[arg(long, value_name = "PATH")]
+    pub rpc_unix_socket: Option<PathBuf>,
+
+    /// Set the maximum RPC request payload size for both HTTP and WebSockets in megabytes. Larger
+    /// requests are rejected with a `-32007` error.
     #[arg(long, default_value_t = RPC_DEFAULT_MAX_REQUEST_SIZE_MB)]
     pub rpc_max_request_size: u32,
 
-    /// Set the maximum RPC response payload size for both HTTP and WebSockets in megabytes.
+    /// Set the maximum RPC response payload size for both HTTP and WebSockets in megabytes. Calls
+    /// with a larger response get a `-32008` error instead.
     #[arg(long, default_value_t = RPC_DEFAULT_MAX_RESPONSE_SIZE_MB)]
     pub rpc_max_response_size: u32,
 
@@ -133,25 +210,168 @@ pub struct RpcParams {
     #[arg(long, value_name = "COUNT", default_value_t = RPC_DEFAULT_MAX_CONNECTIONS)]
     pub rpc_max_connections: u32,
 
+    /// Maximum number of websocket connections at a given time, within `--rpc-max-connections`.
+    /// Further websocket upgrades are refused with a `503` until a connection is closed.
+    ///
+    /// Defaults to `--rpc-max-connections`.
+    #[arg(long, value_name = "COUNT")]
+    pub rpc_max_ws_connections: Option<u32>,
+
+    /// Interval between two pings sent to the websocket clients, in seconds.
+    #[arg(long, value_name = "SECS", default_value_t = RPC_DEFAULT_WS_PING_INTERVAL_SECS)]
+    pub rpc_ws_ping_interval: u64,
+
+    /// A websocket connection which has sent nothing, not even a pong, for this many seconds is
+    /// inactive. It is closed after three inactive periods in a row.
+    #[arg(long, value_name = "SECS", default_value_t = RPC_DEFAULT_WS_IDLE_TIMEOUT_SECS)]
+    pub rpc_ws_idle_timeout: u64,
+
     /// The maximum number of messages that can be kept in memory at a given time, per connection.
     /// The server enforces backpressure, and this buffering is useful when the client cannot keep up with our server.
     #[arg(long, default_value_t = RPC_DEFAULT_MESSAGE_CAPACITY_PER_CONN)]
     pub rpc_message_buffer_capacity_per_connection: u32,
 
+    /// Time budget of an RPC call in seconds. Calls which take longer are answered with a
+    /// `-32997` error, and their execution is cancelled.
+    #[arg(long, value_name = "SECS", default_value_t = RPC_DEFAULT_TIMEOUT_SECS)]
+    pub rpc_timeout: u64,
+
+    /// Time budget of an RPC trace, simulation, fee estimation or
+    /// `starknet_call` call in seconds.
+    #[arg(long, value_name = "SECS", default_value_t = RPC_DEFAULT_TRACE_TIMEOUT_SECS)]
+    pub rpc_trace_timeout: u64,
+
+    /// Time in seconds the in-flight RPC requests have to complete on shutdown. New connections
+    /// are refused in the meantime, and the websocket subscriptions are closed with a
+    /// notification.
+    #[arg(long, value_name = "SECS", default_value_t = RPC_DEFAULT_SHUTDOWN_DRAIN_TIMEOUT_SECS)]
+    pub rpc_shutdown_drain_timeout: u64,
+
+    /// Maximum number of `starknet_call`, fee estimation, simulation and trace calls running at
+    /// once, so that they cannot starve the block verification of the sync.
+    #[arg(long, value_name = "CALLS", default_value_t = RPC_DEFAULT_MAX_CONCURRENT_EXECUTIONS)]
+    pub rpc_max_concurrent_executions: usize,
+
+    /// Time in milliseconds an execution-heavy call waits for its turn when
+    /// `--rpc-max-concurrent-executions` calls are already running, after which it fails with a
+    /// `10004` busy error. Set to 0 to fail right away.
+    #[arg(long, value_name = "MILLIS", default_value_t = RPC_DEFAULT_EXECUTION_QUEUE_TIMEOUT_MILLIS)]
+    pub rpc_execution_queue_timeout: u64,
+
+    /// Log the RPC calls which take longer than this many milliseconds, with a summary of their
+    /// params and the time spent executing them. Disabled by default.
+    #[arg(long, value_name = "MILLIS")]
+    pub rpc_slow_log_threshold: Option<u64>,
+
+    /// Number of transaction execution results kept in memory, so that tracing, simulating or
+    /// estimating the same transactions again does not re-execute them. Set to 0 to disable the
+    /// execution cache.
+    #[arg(long, alias = "rpc-trace-cache-size", value_name = "RESULTS", default_value_t = DEFAULT_EXECUTION_CACHE_SIZE)]
+    pub rpc_execution_cache_size: usize,
+
+    /// Number of blocks whose state reads are kept in memory, so that successive calls on top of
+    /// the same block do not read the same storage values and contract classes again. Set to 0 to
+    /// disable the state cache.
+    #[arg(long, value_name = "BLOCKS", default_value_t = DEFAULT_STATE_CACHE_SIZE)]
+    pub rpc_state_cache_size: usize,
+
+    /// How long the execution context of a block is kept, in milliseconds, so that bursts of
+    /// calls on top of `latest` or `pending` do not build it again. Set to 0 to disable the pool.
+    #[arg(long, value_name = "MILLIS", default_value_t = DEFAULT_EXECUTION_CONTEXT_TTL.as_millis() as u64)]
+    pub rpc_execution_context_ttl: u64,
+
+    /// Maximum number of Cairo steps of the execution of a transaction or of a `starknet_call`,
+    /// in the fee estimation, simulation, trace and call methods. Defaults to the limit of the
+    /// protocol version of the block.
+    #[arg(long, value_name = "STEPS")]
+    pub rpc_max_steps: Option<u32>,
+
+    /// Maximum number of Cairo steps of the validation of a transaction, in the fee estimation,
+    /// simulation and trace methods. Defaults to the limit of the protocol version of the block.
+    #[arg(long, value_name = "STEPS")]
+    pub rpc_validate_max_steps: Option<u32>,
+
+    /// Maximum depth of nested contract calls, in the fee estimation, simulation, trace and call
+    /// methods. Defaults to the limit of the protocol version of the block.
+    #[arg(long, value_name = "DEPTH")]
+    pub rpc_max_recursion_depth: Option<usize>,
+
+    /// Margin added to the L1 gas of the fee estimates, in percent. Transactions sent with an
+    /// exact estimate tend to fail when the network is congested.
+    #[arg(long, value_name = "PERCENT", default_value_t = 0)]
+    pub rpc_fee_margin_gas: u32,
+
+    /// Margin added to the L1 data gas of the fee estimates, in percent.
+    #[arg(long, value_name = "PERCENT", default_value_t = 0)]
+    pub rpc_fee_margin_data_gas: u32,
+
+    /// Charge the fee in the fee estimations and simulations, as the sequencer does when it
+    /// accepts a transaction. The balance of the senders must then cover the max fee or the
+    /// resource bounds of their transactions, else the call fails with an
+    /// `INSUFFICIENT_ACCOUNT_BALANCE` error. The `SKIP_FEE_CHARGE` simulation flag is ignored.
+    #[arg(long)]
+    pub rpc_enforce_fee_charge: bool,
+
+    /// Maximum number of events returned by a single `starknet_getEvents` page. Larger pages are
+    /// rejected with a `PAGE_SIZE_TOO_BIG` error.
+    #[arg(long, value_name = "EVENTS", default_value_t = MAX_EVENTS_CHUNK_SIZE)]
+    pub rpc_get_events_max_chunk_size: usize,
+
+    /// Maximum number of blocks a single `starknet_getEvents` call scans. When the page cannot be
+    /// filled within this bound, the call fails and the block range has to be reduced. Set to 0
+    /// to disable the bound.
+    #[arg(long, value_name = "BLOCKS", default_value_t = DEFAULT_EVENTS_MAX_BLOCKS_SCANNED)]
+    pub rpc_get_events_max_blocks_scanned: u64,
+
+    /// Number of blocks the node can lag behind the tip of the chain before `/health/ready`
+    /// reports it as not ready.
+    #[arg(long, value_name = "BLOCKS", default_value_t = RPC_DEFAULT_HEALTH_MAX_SYNC_LAG)]
+    pub rpc_health_max_sync_lag: u64,
+
+    /// Proxy the read calls the node cannot answer to this JSON-RPC provider: the methods which
+    /// are not implemented yet, and the queries on data the node does not keep, such as old
+    /// storage proofs. The provider should serve the same specification version as the path the
+    /// calls are made on.
+    #[arg(long, value_name = "URL")]
+    pub rpc_fallback_provider: Option<Url>,
+
+    /// Do not compress the HTTP responses. By default, the responses are compressed with gzip or
+    /// brotli when the caller accepts it.
+    #[arg(long)]
+    pub rpc_disable_compression: bool,
+
     /// Disable RPC batch requests.
     #[arg(long, alias = "rpc_no_batch_requests", conflicts_with_all = &["rpc_max_batch_request_len"])]
     pub rpc_disable_batch_requests: bool,
 
-    /// Limit the max length for an RPC batch request.
-    #[arg(long, conflicts_with_all = &["rpc_disable_batch_requests"], value_name = "LEN")]
-    pub rpc_max_batch_request_len: Option<u32>,
+    /// Limit the max length for an RPC batch request. Longer batches are rejected as a whole
+    /// with a `-32010` error.
+    #[arg(
+        long,
+        conflicts_with_all = &["rpc_disable_batch_requests"],
+        value_name = "LEN",
+        default_value_t = RPC_DEFAULT_MAX_BATCH_REQUEST_LEN
+    )]
+    pub rpc_max_batch_request_len: u32,
+
+    /// Maximum number of calls of HTTP batch requests which are answered concurrently, over all
+    /// the batches. Set to 1 to answer the calls of batches one after the other.
+    #[arg(
+        long,
+        conflicts_with_all = &["rpc_disable_batch_requests"],
+        value_name = "CALLS",
+        value_parser = clap::value_parser!(u32).range(1..),
+        default_value_t = RPC_DEFAULT_BATCH_CONCURRENCY
+    )]
+    pub rpc_batch_concurrency: u32,
 
     /// Specify browser *origins* allowed to access the HTTP & WebSocket RPC servers.
     ///
     /// For most purposes, an origin can be thought of as just `protocol://domain`.
     /// By default, only browser requests from localhost will work.
     ///
-    /// This argument is a comma separated list of origins, or the special `all` value.
+    /// This argument is a comma separated list of origins, or the special `all` value. A `:*`
+    /// port matches any port, for example `http://localhost:*`.
     ///
     /// Learn more about CORS and web security at <https://developer.mozilla.org/en-US/docs/Web/HTTP/CORS>.
     #[arg(long, value_name = "ORIGINS")]
@@ -159,6 +379,23 @@ pub struct RpcParams {
 }
 
 impl RpcParams {
+    pub fn execution_limits(&self) -> ExecutionLimits {
+        ExecutionLimits {
+            max_steps: self.rpc_max_steps,
+            validate_max_steps: self.rpc_validate_max_steps,
+            max_recursion_depth: self.rpc_max_recursion_depth,
+        }
+    }
+
+    pub fn fee_margin(&self) -> FeeMargin {
+        FeeMargin { gas_percent: self.rpc_fee_margin_gas, data_gas_percent: self.rpc_fee_margin_data_gas }
+    }
+
+    /// Maximum number of blocks a single `starknet_getEvents` call can scan, unbounded when `None`.
+    pub fn events_max_blocks_scanned(&self) -> Option<u64> {
+        (self.rpc_get_events_max_blocks_scanned != 0).then_some(self.rpc_get_events_max_blocks_scanned)
+    }
+
     pub fn cors(&self) -> Option<Vec<String>> {
         let cors = self.rpc_cors.clone().unwrap_or_else(|| {
             Cors::List(vec![
@@ -188,10 +425,8 @@ impl RpcParams {
     pub fn batch_config(&self) -> BatchRequestConfig {
         if self.rpc_disable_batch_requests {
             BatchRequestConfig::Disabled
-        } else if let Some(l) = self.rpc_max_batch_request_len {
-            BatchRequestConfig::Limit(l)
         } else {
-            BatchRequestConfig::Unlimited
+            BatchRequestConfig::Limit(self.rpc_max_batch_request_len)
         }
     }
 }
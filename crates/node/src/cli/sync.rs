@@ -1,7 +1,12 @@
+use std::num::NonZeroU32;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
 use std::time::Duration;
 
-use dc_sync::fetch::fetchers::FetchConfig;
+use dc_sync::convert::BlockHashCheck;
+use dc_sync::fetch::fetchers::{FetchConfig, HttpClientConfig};
 use dc_sync::utils::constant::starknet_core_address;
+use dp_block::StarknetVersion;
 use primitive_types::H160;
 use url::Url;
 
@@ -9,6 +14,27 @@ fn parse_url(s: &str) -> Result<Url, url::ParseError> {
     s.parse()
 }
 
+/// Parses a block range, either a single block `<N>` or an inclusive range `<FROM>-<TO>`.
+fn parse_block_range(s: &str) -> Result<RangeInclusive<u64>, String> {
+    let parse = |s: &str| s.trim().parse::<u64>().map_err(|err| format!("Invalid block number `{s}`: {err}"));
+    let range = match s.split_once('-') {
+        Some((from, to)) => parse(from)?..=parse(to)?,
+        None => parse(s)?..=parse(s)?,
+    };
+    if range.is_empty() {
+        return Err(format!("Invalid block range `{s}`"));
+    }
+    Ok(range)
+}
+
+/// Parses versioned constants given as `<VERSION>=<PATH>`.
+fn parse_versioned_constants(s: &str) -> Result<(StarknetVersion, PathBuf), String> {
+    let (version, path) =
+        s.split_once('=').ok_or_else(|| format!("Invalid versioned constants `{s}`, expected `<VERSION>=<PATH>`"))?;
+    let version = version.trim().parse().map_err(|err| format!("Invalid Starknet version `{version}`: {err}"))?;
+    Ok((version, PathBuf::from(path)))
+}
+
 #[derive(Clone, Debug, clap::Args)]
 pub struct SyncParams {
     /// Disable the sync service. The sync service is responsible for listening for new blocks on starknet and ethereum.
@@ -23,6 +49,19 @@ pub struct SyncParams {
     #[clap(long, value_parser = parse_url, value_name = "ETHEREUM RPC URL")]
     pub l1_endpoint: Option<Url>,
 
+    /// The L1 websocket endpoint url. When set, new L1 heads and state updates are received through `eth_subscribe`
+    /// instead of polling the rpc endpoint, which is still used for contract calls. Use it with `--l1-endpoint`. The
+    /// node falls back to polling when it cannot connect to this endpoint.
+    #[clap(long, value_parser = parse_url, value_name = "ETHEREUM WS URL", requires = "l1_endpoint")]
+    pub l1_ws_endpoint: Option<Url>,
+
+    /// The L1 block from which the messages sent to L2 are indexed, for `starknet_getMessagesStatus`. By default, they
+    /// are indexed from the L1 block of the latest state update when the node first syncs L1. Set it to the deployment
+    /// block of the Starknet core contract to backfill the whole history, even on a node which already indexed the
+    /// recent messages.
+    #[clap(long, value_name = "L1 BLOCK NUMBER")]
+    pub l1_messages_start_block: Option<u64>,
+
     /// The block you want to start syncing from.
     #[clap(long, value_name = "BLOCK NUMBER")]
     pub starting_block: Option<u64>,
@@ -66,9 +105,78 @@ pub struct SyncParams {
     /// Periodically create a backup, for debugging purposes. Use it with `--backup-dir <PATH>`.
     #[clap(long, value_name = "NUMBER OF BLOCKS")]
     pub backup_every_n_blocks: Option<u64>,
+
+    /// Cache the raw gateway responses (blocks, state updates and classes) in this directory. A resync after a database
+    /// wipe will then replay from this local data instead of downloading everything again.
+    #[clap(long, value_name = "PATH")]
+    pub gateway_cache_dir: Option<PathBuf>,
+
+    /// Import blocks from a block dump file instead of syncing from the feeder gateway. The blocks are verified the same
+    /// way as blocks fetched from the network. Blocks already in the database are skipped.
+    #[clap(long, value_name = "PATH")]
+    pub import_dump: Option<PathBuf>,
+
+    /// Limit the number of blocks imported per second. This deliberately slows down the catch-up sync, to smooth out the
+    /// CPU and disk usage on machines shared with other services.
+    #[clap(long, value_name = "BLOCKS PER SECOND")]
+    pub sync_max_blocks_per_second: Option<NonZeroU32>,
+
+    /// How blocks whose hash does not match the recomputed one are handled. `known` only allows the historical
+    /// mismatches of the network, plus the ones given with `--allowed-block-hash-mismatches`.
+    #[clap(long, value_name = "MODE", default_value = "known")]
+    pub block_hash_check: BlockHashCheckMode,
+
+    /// Additional blocks for which a mismatched block hash is allowed, as a comma separated list of block numbers or
+    /// inclusive ranges, for example `1466-2242,3000`. This is useful for appchains.
+    #[clap(long, value_name = "BLOCK RANGES", value_parser = parse_block_range, value_delimiter = ',')]
+    pub allowed_block_hash_mismatches: Vec<RangeInclusive<u64>>,
+
+    /// Versioned constants to execute the blocks of a Starknet version with, as `<VERSION>=<PATH>` where the file is
+    /// in the blockifier JSON format, for example `0.13.3=./versioned_constants_13_3.json`. They apply from this
+    /// version up to the next one which has constants, and replace the ones built into the node. This is useful to
+    /// support new Starknet versions, or the constants of an appchain. Can be given multiple times.
+    #[clap(long, value_name = "VERSION=PATH", value_parser = parse_versioned_constants)]
+    pub versioned_constants: Vec<(StarknetVersion, PathBuf)>,
+
+    /// Directory where the compiled classes are persisted, so that the next runs of the node do not compile them
+    /// again. By default, the compiled classes are only cached in memory.
+    #[clap(long, value_name = "PATH")]
+    pub compiled_classes_dir: Option<PathBuf>,
+
+    /// Maximum number of idle connections kept open to the gateway. The parallel block fetches reuse these
+    /// connections instead of opening new ones.
+    #[clap(long, default_value = "16", value_name = "CONNECTIONS")]
+    pub gateway_pool_max_idle: usize,
+
+    /// How long an idle gateway connection is kept open, in seconds.
+    #[clap(long, default_value = "90", value_name = "SECONDS")]
+    pub gateway_pool_idle_timeout: u64,
+
+    /// TCP keepalive interval for the gateway connections, in seconds. Set it to 0 to disable keepalive.
+    #[clap(long, default_value = "60", value_name = "SECONDS")]
+    pub gateway_keepalive: u64,
+
+    /// Talk HTTP/2 to the gateway without negotiating it first, multiplexing all the fetches on a few connections.
+    #[clap(long)]
+    pub gateway_http2: bool,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum, PartialEq)]
+pub enum BlockHashCheckMode {
+    /// Every block hash must match.
+    Strict,
+    /// Only the known historical mismatches are allowed.
+    Known,
+    /// Mismatches are logged and ignored.
+    Lenient,
 }
 
 impl SyncParams {
+    /// Whether the synced state is verified against L1, see `SyncService::new`.
+    pub fn l1_sync_enabled(&self) -> bool {
+        !self.sync_disabled && !self.sync_l1_disabled && self.import_dump.is_none()
+    }
+
     pub fn block_fetch_config(&self) -> FetchConfig {
         let chain_id = self.network.chain_id();
 
@@ -94,6 +202,29 @@ impl SyncParams {
             sync_polling_interval: polling,
             n_blocks_to_sync: self.n_blocks_to_sync,
             sync_l1_disabled: self.sync_l1_disabled,
+            gateway_cache_dir: self.gateway_cache_dir.clone(),
+            max_blocks_per_second: self.sync_max_blocks_per_second,
+            block_hash_check: self.block_hash_check(),
+            http_client: HttpClientConfig {
+                pool_max_idle: self.gateway_pool_max_idle,
+                pool_idle_timeout: Duration::from_secs(self.gateway_pool_idle_timeout),
+                tcp_keepalive: Some(self.gateway_keepalive).filter(|secs| *secs > 0).map(Duration::from_secs),
+                http2: self.gateway_http2,
+            },
+        }
+    }
+
+    pub fn block_hash_check(&self) -> BlockHashCheck {
+        match self.block_hash_check {
+            BlockHashCheckMode::Strict => BlockHashCheck::Strict,
+            BlockHashCheckMode::Known => BlockHashCheck::AllowMismatches(
+                self.network
+                    .known_block_hash_mismatches()
+                    .into_iter()
+                    .chain(self.allowed_block_hash_mismatches.iter().cloned())
+                    .collect(),
+            ),
+            BlockHashCheckMode::Lenient => BlockHashCheck::Lenient,
         }
     }
 }
@@ -131,6 +262,14 @@ impl NetworkType {
         dc_db::block_db::ChainInfo { chain_id: self.chain_id(), chain_name: chain_name.into() }
     }
 
+    pub fn rpc_chain_config(&self) -> dc_rpc::ChainConfig {
+        dc_rpc::ChainConfig {
+            chain_id: self.chain_id(),
+            feeder_gateway: self.feeder_gateway(),
+            gateway: self.gateway(),
+        }
+    }
+
     pub fn gateway(&self) -> Url {
         format!("{}/gateway", self.uri()).parse().unwrap()
     }
@@ -147,6 +286,14 @@ impl NetworkType {
         }
     }
 
+    /// Historical blocks whose hash cannot be recomputed.
+    pub fn known_block_hash_mismatches(&self) -> Vec<RangeInclusive<u64>> {
+        match self {
+            NetworkType::Main => vec![1466..=2242],
+            NetworkType::Test | NetworkType::Integration => vec![],
+        }
+    }
+
     pub fn l1_core_address(&self) -> H160 {
         match self {
             NetworkType::Main => starknet_core_address::MAINNET.parse().unwrap(),
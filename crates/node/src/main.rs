@@ -1,6 +1,8 @@
 //! Deoxys node command line.
 #![warn(missing_docs)]
 
+use std::sync::Arc;
+
 use anyhow::Context;
 use clap::Parser;
 
@@ -9,10 +11,14 @@ mod service;
 mod util;
 
 use cli::RunCmd;
+use dc_db::db_metrics::DbMetrics;
 use dc_db::DatabaseService;
+use dc_exec::ExecutionMetrics;
 use dc_metrics::MetricsService;
+use dc_sync::metrics::block_metrics::BlockMetrics;
 use dc_telemetry::{SysInfo, TelemetryService};
-use service::{RpcService, SyncService};
+use dp_utils::PauseHandle;
+use service::{DevnetService, GrpcService, RpcService, SyncService};
 use tokio::task::JoinSet;
 
 const GREET_IMPL_NAME: &str = "Deoxys";
@@ -37,9 +43,33 @@ async fn main() -> anyhow::Result<()> {
     }
     log::info!("💁 Support URL: {}", GREET_SUPPORT_URL);
     log::info!("🏷  Node Name: {}", node_name);
-    log::info!("👤 Role: Full Node");
+    log::info!("👤 Role: {}", if run_cmd.devnet_params.devnet { "Devnet" } else { "Full Node" });
     log::info!("🌐 Network: {}", network_name);
 
+    dc_exec::load_versioned_constants(run_cmd.sync_params.versioned_constants.clone())
+        .context("Loading versioned constants")?;
+    if let Some(dir) = run_cmd.sync_params.compiled_classes_dir.clone() {
+        let cache = dp_class::CompilationCache::new(dp_class::DEFAULT_COMPILATION_CACHE_CAPACITY).with_dir(dir);
+        if dp_class::init_compilation_cache(cache).is_err() {
+            anyhow::bail!("The compilation cache is already set");
+        }
+    }
+
+    if let Some(path) = run_cmd.db_params.replay.clone() {
+        tokio::task::spawn_blocking(move || {
+            let file = std::fs::File::open(&path).with_context(|| format!("Opening replay file {}", path.display()))?;
+            let replay_log = dc_exec::ReplayLog::read(std::io::BufReader::new(file)).context("Reading replay file")?;
+            let result = replay_log.replay()?;
+            let trace = dc_exec::execution_result_to_tx_trace(&result)?;
+            log::info!("📼 Replayed transaction {:#}", result.hash);
+            println!("{}", serde_json::to_string_pretty(&trace)?);
+            anyhow::Ok(())
+        })
+        .await
+        .context("Replaying transaction")??;
+        return Ok(());
+    }
+
     let sys_info = SysInfo::probe();
     sys_info.show();
 
@@ -63,17 +93,124 @@ async fn main() -> anyhow::Result<()> {
     )
     .await
     .context("Initializing db service")?;
-    let mut rpc = RpcService::new(&run_cmd.rpc_params, &db, run_cmd.sync_params.network, prometheus_service.registry())
-        .context("Initializing rpc service")?;
-    let mut sync_service =
-        SyncService::new(&run_cmd.sync_params, &db, prometheus_service.registry(), telemetry_service.new_handle())
+
+    if let Some(path) = run_cmd.db_params.export_dump.clone() {
+        let backend = db.backend().clone();
+        let chain_id = run_cmd.sync_params.network.chain_id();
+        let (first_block, last_block) = (run_cmd.db_params.export_dump_from, run_cmd.db_params.export_dump_to);
+        tokio::task::spawn_blocking(move || {
+            dc_sync::dump::export_dump(&backend, &path, chain_id, first_block, last_block)
+        })
+        .await
+        .context("Exporting block dump")??;
+        return Ok(());
+    }
+
+    if run_cmd.db_params.reverify_db {
+        // The scratch database is always started from scratch, and removed once done.
+        let scratch_dir = run_cmd.db_params.base_path.join("reverify");
+        if scratch_dir.exists() {
+            std::fs::remove_dir_all(&scratch_dir).context("Removing previous re-verification scratch database")?;
+        }
+        let scratch = DatabaseService::new(&scratch_dir, None, false, &run_cmd.sync_params.network.db_chain_info())
             .await
-            .context("Initializing sync service")?;
+            .context("Initializing re-verification scratch database")?;
+
+        let registry = prometheus_service.registry();
+        let result = dc_sync::reverify::reverify_database(
+            db.backend(),
+            scratch.backend(),
+            BlockMetrics::register(&registry)?,
+            DbMetrics::register(&registry)?,
+            run_cmd.sync_params.network.chain_id(),
+            telemetry_service.new_handle(),
+            run_cmd.sync_params.block_hash_check(),
+        )
+        .await;
+
+        drop(scratch);
+        std::fs::remove_dir_all(&scratch_dir).context("Removing re-verification scratch database")?;
+        return result;
+    }
+
+    if let Some(tx_hash) = run_cmd.db_params.record_replay {
+        let backend = Arc::clone(db.backend());
+        let path = run_cmd.db_params.replay_file.clone().context("Missing replay file")?;
+        tokio::task::spawn_blocking(move || {
+            let replay_log = dc_exec::record_replay(&backend, tx_hash, Default::default())?;
+            let file =
+                std::fs::File::create(&path).with_context(|| format!("Creating replay file {}", path.display()))?;
+            replay_log.write(std::io::BufWriter::new(file)).context("Writing replay file")?;
+            log::info!("📼 Recorded the execution of transaction {tx_hash:#x} to {}", path.display());
+            anyhow::Ok(())
+        })
+        .await
+        .context("Recording transaction replay")??;
+        return Ok(());
+    }
+
+    if run_cmd.db_params.verify_execution {
+        let backend = Arc::clone(db.backend());
+        let (first_block, last_block) =
+            (run_cmd.db_params.verify_execution_from, run_cmd.db_params.verify_execution_to);
+        let limits = run_cmd.db_params.verify_execution_limits();
+        let metrics = ExecutionMetrics::register(&prometheus_service.registry())?;
+        // The verification can take hours, its progress is exposed through the metrics
+        let mut metrics_tasks = JoinSet::new();
+        prometheus_service.start(&mut metrics_tasks).await.context("Starting prometheus metrics service")?;
+        let divergences = tokio::task::spawn_blocking(move || {
+            let last_block = match last_block {
+                Some(last_block) => last_block,
+                None => backend.get_latest_block_n()?.context("The database is empty")?,
+            };
+            anyhow::Ok(dc_exec::verify_blocks_execution(&backend, first_block, last_block, limits, &metrics)?)
+        })
+        .await
+        .context("Verifying block execution")??;
+
+        if !divergences.is_empty() {
+            anyhow::bail!("Found {} execution divergences", divergences.len());
+        }
+        return Ok(());
+    }
+
+    if run_cmd.devnet_params.devnet {
+        // The blocks are produced locally, the chain is not followed
+        run_cmd.sync_params.sync_disabled = true;
+        run_cmd.sync_params.sync_l1_disabled = true;
+    }
+    let mut devnet_service = DevnetService::new(&run_cmd.devnet_params, &db).context("Initializing devnet service")?;
+
+    // Shared between the sync service and the admin rpc methods.
+    let sync_pause = PauseHandle::new();
+
+    let mut rpc = RpcService::new(
+        &run_cmd.rpc_params,
+        &db,
+        &run_cmd.sync_params,
+        prometheus_service.registry(),
+        sync_pause.clone(),
+        devnet_service.devnet(),
+    )
+    .context("Initializing rpc service")?;
+    let mut grpc_service = GrpcService::new(&run_cmd.grpc_params, &run_cmd.rpc_params, &db, &run_cmd.sync_params)
+        .context("Initializing grpc service")?;
+    let mut sync_service = SyncService::new(
+        &run_cmd.sync_params,
+        &db,
+        prometheus_service.registry(),
+        telemetry_service.new_handle(),
+        sync_pause,
+    )
+    .await
+    .context("Initializing sync service")?;
 
     let mut task_set = JoinSet::new();
 
     sync_service.start(&mut task_set).await.context("Starting sync service")?;
+    devnet_service.start(&mut task_set).await.context("Starting devnet service")?;
     rpc.start(&mut task_set).await.context("Starting rpc service")?;
+    grpc_service.start(&mut task_set).await.context("Starting grpc service")?;
     telemetry_service.start(&mut task_set).await.context("Starting telemetry service")?;
     prometheus_service.start(&mut task_set).await.context("Starting prometheus metrics service")?;
 
@@ -84,6 +221,8 @@ async fn main() -> anyhow::Result<()> {
         &sys_info,
     );
 
+    // On shutdown, the rpc server drains its in-flight requests before stopping. The database is only flushed and
+    // closed once every service is done with it, when the last reference to the backend is dropped.
     while let Some(result) = task_set.join_next().await {
         // Ignore tokio join errors, only bubble service errors
         if let Ok(err) = result {
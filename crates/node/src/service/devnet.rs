@@ -0,0 +1,40 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use dc_db::DatabaseService;
+use dc_devnet::Devnet;
+use tokio::task::JoinSet;
+
+use crate::cli::DevnetParams;
+
+pub struct DevnetService {
+    devnet: Option<Devnet>,
+    block_time: Option<Duration>,
+}
+
+impl DevnetService {
+    pub fn new(config: &DevnetParams, db: &DatabaseService) -> anyhow::Result<Self> {
+        if !config.devnet {
+            return Ok(Self { devnet: None, block_time: None });
+        }
+
+        let block_time = config.devnet_block_time.map(Duration::from_secs);
+        // Without a block time, a block is mined for each transaction
+        let devnet = Devnet::new(Arc::clone(db.backend()), config.devnet_sequencer_address, block_time.is_none())
+            .context("Initializing devnet")?;
+        Ok(Self { devnet: Some(devnet), block_time })
+    }
+
+    /// Set in devnet mode.
+    pub fn devnet(&self) -> Option<&Devnet> {
+        self.devnet.as_ref()
+    }
+
+    pub async fn start(&mut self, join_set: &mut JoinSet<anyhow::Result<()>>) -> anyhow::Result<()> {
+        if let (Some(devnet), Some(block_time)) = (self.devnet.clone(), self.block_time) {
+            join_set.spawn(async move { Ok(devnet.mine_every(block_time).await?) });
+        }
+        Ok(())
+    }
+}
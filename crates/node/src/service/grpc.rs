@@ -0,0 +1,109 @@
+//! gRPC server, whose callers are checked like the callers of the RPC read methods.
+
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use dc_db::DatabaseService;
+use dc_grpc::GrpcInterceptor;
+use dc_rpc::Starknet;
+use dp_utils::wait_or_graceful_shutdown;
+use ip_network::IpNetwork;
+use tokio::task::JoinSet;
+use tonic::{Request, Status};
+
+use super::rpc::auth::RpcAuth;
+use super::rpc::middleware::{MethodClass, RateLimit};
+use super::rpc::server::RATE_LIMIT_RETAIN_INTERVAL;
+use crate::cli::{GrpcParams, RpcParams, SyncParams};
+
+/// Authentication and rate limiting of the gRPC callers, with the api keys, JWT secret and read rate limit of the
+/// RPC server. The gRPC server only serves read methods.
+#[derive(Clone)]
+struct GrpcAccess {
+    auth: Option<Arc<RpcAuth>>,
+    rate_limit: Option<RateLimit>,
+    rate_limit_whitelisted_ips: Arc<[IpNetwork]>,
+}
+
+impl GrpcAccess {
+    fn check(&self, request: &Request<()>) -> Result<(), Status> {
+        // The remote address is always known for tcp connections
+        let Some(ip) = request.remote_addr().map(|addr| addr.ip()) else { return Ok(()) };
+
+        if let Some(auth) = &self.auth {
+            let headers = request.metadata().clone().into_headers();
+            match auth.authenticate(&headers) {
+                Some(methods) if methods.contains(&MethodClass::Read) => {}
+                Some(_) => return Err(Status::permission_denied("The read methods are not allowed")),
+                None => return Err(Status::unauthenticated("Missing or invalid api key")),
+            }
+        }
+
+        if let Some(rate_limit) = &self.rate_limit {
+            if !self.is_whitelisted(&ip) && !rate_limit.check(&ip) {
+                return Err(Status::resource_exhausted("Rate limited, try again later"));
+            }
+        }
+        Ok(())
+    }
+
+    fn is_whitelisted(&self, ip: &IpAddr) -> bool {
+        self.rate_limit_whitelisted_ips.iter().any(|ips| ips.contains(*ip))
+    }
+}
+
+pub struct GrpcService {
+    inner: dc_grpc::GrpcService,
+    access: Option<GrpcAccess>,
+}
+
+impl GrpcService {
+    pub fn new(
+        config: &GrpcParams,
+        rpc_config: &RpcParams,
+        db: &DatabaseService,
+        sync_params: &SyncParams,
+    ) -> anyhow::Result<Self> {
+        let inner = dc_grpc::GrpcService::new(
+            !config.grpc,
+            config.grpc_external,
+            config.grpc_port,
+            config.grpc_max_streams,
+            Starknet::new(Arc::clone(db.backend()), 0, sync_params.network.rpc_chain_config())
+                .with_events_limits(rpc_config.rpc_get_events_max_chunk_size, rpc_config.events_max_blocks_scanned()),
+            Arc::clone(db.backend()),
+        );
+        if !config.grpc {
+            return Ok(Self { inner, access: None });
+        }
+
+        let access = GrpcAccess {
+            auth: RpcAuth::new(rpc_config.rpc_api_keys_file.clone(), rpc_config.rpc_jwt_secret.as_deref())?,
+            rate_limit: rpc_config.rpc_rate_limit.map(RateLimit::new),
+            rate_limit_whitelisted_ips: rpc_config.rpc_rate_limit_whitelisted_ips.clone().into(),
+        };
+        let interceptor: GrpcInterceptor = {
+            let access = access.clone();
+            Arc::new(move |request| access.check(request))
+        };
+        Ok(Self { inner: inner.with_interceptor(interceptor), access: Some(access) })
+    }
+
+    pub async fn start(&mut self, join_set: &mut JoinSet<anyhow::Result<()>>) -> anyhow::Result<()> {
+        if let Some(rate_limit) = self.access.as_ref().and_then(|access| access.rate_limit.clone()) {
+            join_set.spawn(async move {
+                let mut interval = tokio::time::interval(RATE_LIMIT_RETAIN_INTERVAL);
+                while wait_or_graceful_shutdown(interval.tick()).await.is_some() {
+                    rate_limit.retain_recent();
+                }
+                Ok(())
+            });
+        }
+
+        if let Some(auth) = self.access.as_ref().and_then(|access| access.auth.as_ref()) {
+            auth.reload_on_sighup(join_set)?;
+        }
+
+        self.inner.start(join_set).await
+    }
+}
@@ -1,5 +1,9 @@
+pub mod devnet;
+pub mod grpc;
 pub mod rpc;
 pub mod sync;
 
+pub use devnet::DevnetService;
+pub use grpc::GrpcService;
 pub use rpc::RpcService;
 pub use sync::SyncService;
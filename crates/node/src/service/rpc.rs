@@ -1,18 +1,38 @@
+use auth::RpcAuth;
 use dc_db::DatabaseService;
+use dc_devnet::Devnet;
+use dc_exec::ExecutionMetrics;
 use dc_metrics::MetricsRegistry;
-use dc_rpc::{ChainConfig, Starknet, StarknetReadRpcApiServer, StarknetTraceRpcApiServer, StarknetWriteRpcApiServer};
+use dc_rpc::execution_permits::ExecutionPermits;
+use dc_rpc::hooks::RpcHooks;
+use dc_rpc::metrics::SubscriptionMetrics;
+use dc_rpc::{
+    Admin, AdminRpcApiServer, DeoxysRpcApiServer, DeoxysTraceRpcApiServer, Dev, DevRpcApiServer,
+    PathfinderRpcApiServer, RpcVersion, Starknet, StarknetReadRpcApiServer, StarknetTraceRpcApiServer,
+    StarknetV0_6ReadRpcApiServer, StarknetV0_6TraceRpcApiServer, StarknetV0_8ReadRpcApiServer,
+    StarknetWriteRpcApiServer, StarknetWsRpcApiServer,
+};
+use dp_utils::PauseHandle;
+use health::HealthChecks;
+use jsonrpsee::core::server::{MethodCallback, UnsubscriptionMethod};
 use jsonrpsee::server::ServerHandle;
-use jsonrpsee::RpcModule;
+use jsonrpsee::{MethodResponse, Methods, ResponsePayload, RpcModule};
 use metrics::RpcMetrics;
+use middleware::CallTimeouts;
 use server::{start_server, ServerConfig};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::task::JoinSet;
 
-use crate::cli::{NetworkType, RpcMethods, RpcParams};
+use crate::cli::{RpcMethods, RpcParams, SyncParams};
 
+pub(crate) mod auth;
+mod batch;
+mod fallback;
+mod health;
 mod metrics;
-mod middleware;
-mod server;
+pub(crate) mod middleware;
+pub(crate) mod server;
 
 pub struct RpcService {
     server_config: Option<ServerConfig>,
@@ -22,74 +42,97 @@ impl RpcService {
     pub fn new(
         config: &RpcParams,
         db: &DatabaseService,
-        network_type: NetworkType,
+        sync_params: &SyncParams,
         metrics_handle: MetricsRegistry,
+        sync_pause: PauseHandle,
+        devnet: Option<&Devnet>,
     ) -> anyhow::Result<Self> {
         if config.rpc_disabled {
             return Ok(Self { server_config: None, server_handle: None });
         }
 
-        let mut rpc_api = RpcModule::new(());
-
-        let (read, write, trace) = match (config.rpc_methods, config.rpc_external) {
-            (RpcMethods::Safe, _) => (true, false, false),
-            (RpcMethods::Unsafe, _) => (true, true, true),
-            (RpcMethods::Auto, false) => (true, true, true),
+        let (read, write, trace, admin) = match (config.rpc_methods, config.rpc_external) {
+            (RpcMethods::Safe, _) => (true, false, false, false),
+            (RpcMethods::Unsafe, _) => (true, true, true, true),
+            (RpcMethods::Auto, false) => (true, true, true, true),
             (RpcMethods::Auto, true) => {
                 log::warn!(
-                    "Option `--rpc-external` will hide Write and Trace endpoints. To enable them, please pass \
-                     `--rpc-methods unsafe`."
+                    "Option `--rpc-external` will hide Write, Trace, Admin and Dev endpoints. To enable them, please \
+                     pass `--rpc-methods unsafe`."
                 );
-                (true, false, false)
+                (true, false, false, false)
             }
         };
 
-        let chain_config = ChainConfig {
-            chain_id: network_type.chain_id(),
-            feeder_gateway: network_type.feeder_gateway(),
-            gateway: network_type.gateway(),
+        // A single instance is shared by every module, so that they share its caches
+        let starknet = Starknet::new(Arc::clone(db.backend()), 0, sync_params.network.rpc_chain_config())
+            .with_execution_cache_size(config.rpc_execution_cache_size)
+            .with_state_cache_size(config.rpc_state_cache_size)
+            .with_execution_context_ttl(Duration::from_millis(config.rpc_execution_context_ttl))
+            .with_execution_limits(config.execution_limits())
+            .with_fee_margin(config.fee_margin())
+            .with_enforce_fee_charge(config.rpc_enforce_fee_charge)
+            .with_events_limits(config.rpc_get_events_max_chunk_size, config.events_max_blocks_scanned())
+            .with_execution_permits(ExecutionPermits::new(
+                config.rpc_max_concurrent_executions,
+                Duration::from_millis(config.rpc_execution_queue_timeout),
+            ))
+            .with_execution_metrics(ExecutionMetrics::register(&metrics_handle)?)
+            .with_subscription_metrics(SubscriptionMetrics::register(&metrics_handle)?);
+        let starknet = match devnet {
+            Some(devnet) => starknet.with_devnet(devnet.clone()),
+            None => starknet,
         };
 
-        if read {
-            // TODO: staring block
-            rpc_api.merge(StarknetReadRpcApiServer::into_rpc(Starknet::new(
-                Arc::clone(db.backend()),
-                0,
-                chain_config.clone(),
-            )))?;
-        }
-        if write {
-            rpc_api.merge(StarknetWriteRpcApiServer::into_rpc(Starknet::new(
-                Arc::clone(db.backend()),
-                0,
-                chain_config.clone(),
-            )))?;
-        }
-        if trace {
-            rpc_api.merge(StarknetTraceRpcApiServer::into_rpc(Starknet::new(
-                Arc::clone(db.backend()),
-                0,
-                chain_config.clone(),
-            )))?;
-        }
+        let rpc_apis = rpc_modules(read, write, trace, admin, &starknet, &sync_pause, devnet)?;
+        // The unix socket is protected by its file permissions, it serves every namespace
+        let unix_socket = match &config.rpc_unix_socket {
+            Some(path) => Some((path.clone(), rpc_modules(true, true, true, true, &starknet, &sync_pause, devnet)?)),
+            None => None,
+        };
 
         let metrics = RpcMetrics::register(&metrics_handle)?;
+        // The sync lag is only checked when the node follows the chain
+        let max_sync_lag =
+            (!sync_params.sync_disabled && sync_params.import_dump.is_none()).then_some(config.rpc_health_max_sync_lag);
+        let health = HealthChecks::new(Arc::clone(db.backend()), max_sync_lag, sync_params.l1_sync_enabled());
 
         Ok(Self {
             server_config: Some(ServerConfig {
                 addr: config.addr(),
                 batch_config: config.batch_config(),
+                batch_concurrency: config.rpc_batch_concurrency,
                 max_connections: config.rpc_max_connections,
+                max_ws_connections: config.rpc_max_ws_connections,
+                ws_ping_interval: Duration::from_secs(config.rpc_ws_ping_interval),
+                ws_idle_timeout: Duration::from_secs(config.rpc_ws_idle_timeout),
                 max_payload_in_mb: config.rpc_max_request_size,
                 max_payload_out_mb: config.rpc_max_response_size,
                 max_subs_per_conn: config.rpc_max_subscriptions_per_connection,
                 message_buffer_capacity: config.rpc_message_buffer_capacity_per_connection,
-                rpc_api,
+                rpc_apis,
                 metrics,
                 cors: config.cors(),
                 rate_limit: config.rpc_rate_limit,
+                rate_limit_trace: config.rpc_rate_limit_trace.or(config.rpc_rate_limit),
+                rate_limit_write: config.rpc_rate_limit_write.or(config.rpc_rate_limit),
                 rate_limit_whitelisted_ips: config.rpc_rate_limit_whitelisted_ips.clone(),
                 rate_limit_trust_proxy_headers: config.rpc_rate_limit_trust_proxy_headers,
+                write_allowed_ips: (!config.rpc_write_allowed_ips.is_empty())
+                    .then(|| config.rpc_write_allowed_ips.clone()),
+                timeouts: CallTimeouts {
+                    default: Duration::from_secs(config.rpc_timeout),
+                    trace: Duration::from_secs(config.rpc_trace_timeout),
+                },
+                drain_timeout: Duration::from_secs(config.rpc_shutdown_drain_timeout),
+                slow_log_threshold: config.rpc_slow_log_threshold.map(Duration::from_millis),
+                auth: RpcAuth::new(config.rpc_api_keys_file.clone(), config.rpc_jwt_secret.as_deref())?,
+                unix_socket,
+                health,
+                // The node does not register any hook itself, they are meant for the embedders of the rpc crate
+                hooks: RpcHooks::default(),
+                compression: !config.rpc_disable_compression,
+                fallback_provider: config.rpc_fallback_provider.clone(),
             }),
             server_handle: None,
         })
@@ -103,3 +146,132 @@ impl RpcService {
         Ok(())
     }
 }
+
+fn rpc_modules(
+    read: bool,
+    write: bool,
+    trace: bool,
+    admin: bool,
+    starknet: &Starknet,
+    sync_pause: &PauseHandle,
+    devnet: Option<&Devnet>,
+) -> anyhow::Result<Vec<(RpcVersion, RpcModule<()>)>> {
+    let mut rpc_apis = Vec::new();
+    // The versions serve the same methods, except the ones whose shapes diverge
+    for &version in RpcVersion::ALL {
+        // TODO: staring block
+        let starknet = || starknet.clone().with_version(version);
+        let mut rpc_api = RpcModule::new(());
+
+        if read {
+            match version {
+                RpcVersion::V0_6 => merge_overridden(
+                    &mut rpc_api,
+                    StarknetReadRpcApiServer::into_rpc(starknet()),
+                    StarknetV0_6ReadRpcApiServer::into_rpc(starknet()),
+                    &["starknet_getBlockWithReceipts"],
+                )?,
+                RpcVersion::V0_7 => rpc_api.merge(StarknetReadRpcApiServer::into_rpc(starknet()))?,
+            }
+            merge_shared_unsubscribe(
+                &mut rpc_api,
+                StarknetWsRpcApiServer::into_rpc(starknet()),
+                "starknet_unsubscribe",
+            )?;
+            rpc_api.merge(PathfinderRpcApiServer::into_rpc(starknet()))?;
+            rpc_api.merge(DeoxysRpcApiServer::into_rpc(starknet()))?;
+            rpc_api.merge(StarknetV0_8ReadRpcApiServer::into_rpc(starknet()))?;
+        }
+        if write {
+            rpc_api.merge(StarknetWriteRpcApiServer::into_rpc(starknet()))?;
+        }
+        if trace {
+            match version {
+                RpcVersion::V0_6 => merge_overridden(
+                    &mut rpc_api,
+                    StarknetTraceRpcApiServer::into_rpc(starknet()),
+                    StarknetV0_6TraceRpcApiServer::into_rpc(starknet()),
+                    &[],
+                )?,
+                RpcVersion::V0_7 => rpc_api.merge(StarknetTraceRpcApiServer::into_rpc(starknet()))?,
+            }
+            rpc_api.merge(DeoxysTraceRpcApiServer::into_rpc(starknet()))?;
+        }
+
+        if admin {
+            rpc_api.merge(AdminRpcApiServer::into_rpc(Admin::new(sync_pause.clone())))?;
+            if let Some(devnet) = devnet {
+                rpc_api.merge(DevRpcApiServer::into_rpc(Dev::new(devnet.clone())))?;
+            }
+        }
+
+        rpc_apis.push((version, rpc_api));
+    }
+    Ok(rpc_apis)
+}
+
+/// Merges the methods of `module` into `rpc_api`, replacing the ones of the same name by the methods of `overrides`
+/// and leaving out the `excluded` ones, for the versions whose shapes diverge.
+fn merge_overridden(
+    rpc_api: &mut RpcModule<()>,
+    module: impl Into<Methods>,
+    overrides: impl Into<Methods>,
+    excluded: &[&str],
+) -> anyhow::Result<()> {
+    let module = module.into();
+    let overrides = overrides.into();
+    for name in module.method_names() {
+        if overrides.method(name).is_some() || excluded.contains(&name) {
+            continue;
+        }
+        if let Some(callback) = module.method(name) {
+            rpc_api.verify_and_insert(name, callback.clone())?;
+        }
+    }
+    rpc_api.merge(overrides)?;
+    Ok(())
+}
+
+/// Merges the methods of `module` into `rpc_api`, replacing the unsubscribe methods of its subscriptions by a single
+/// `unsubscribe` method which ends a subscription of any of them. The subscription ids are unique across the server,
+/// so at most one of the replaced methods knows the id it is given.
+fn merge_shared_unsubscribe(
+    rpc_api: &mut RpcModule<()>,
+    module: impl Into<Methods>,
+    unsubscribe: &'static str,
+) -> anyhow::Result<()> {
+    let module = module.into();
+    let mut unsubscribe_methods = Vec::new();
+    for name in module.method_names() {
+        match module.method(name) {
+            Some(MethodCallback::Unsubscription(callback)) => unsubscribe_methods.push(callback.clone()),
+            Some(callback) => {
+                rpc_api.verify_and_insert(name, callback.clone())?;
+            }
+            None => {}
+        }
+    }
+
+    let callback: UnsubscriptionMethod = Arc::new(move |id, params, conn_id, max_response_size| {
+        let mut response = None;
+        for unsubscribe in &unsubscribe_methods {
+            let res = unsubscribe(id.clone(), params.clone(), conn_id, max_response_size);
+            if is_unsubscribed(&res) {
+                return res;
+            }
+            response = Some(res);
+        }
+        response.unwrap_or_else(|| MethodResponse::response(id, ResponsePayload::success(false), max_response_size))
+    });
+    rpc_api.verify_and_insert(unsubscribe, MethodCallback::Unsubscription(callback))?;
+    Ok(())
+}
+
+/// Whether an unsubscribe method answered that it ended the subscription.
+fn is_unsubscribed(response: &MethodResponse) -> bool {
+    #[derive(serde::Deserialize)]
+    struct UnsubscribeResponse {
+        result: Option<bool>,
+    }
+    serde_json::from_str::<UnsubscribeResponse>(response.as_result()).is_ok_and(|res| res.result == Some(true))
+}
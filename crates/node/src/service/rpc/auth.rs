@@ -0,0 +1,139 @@
+//! Authentication of the RPC callers.
+//!
+//! Callers authenticate with a static api key or a JWT signed with a shared secret, passed either as
+//! `Authorization: Bearer <token>` or as an `X-Api-Key` header. Each api key, and each JWT through its
+//! `methods` claim, can be restricted to some [`MethodClass`]es.
+//!
+//! The api keys file is a JSON list of `{ "key": "...", "methods": ["read", "trace", "write"] }` objects,
+//! where `methods` defaults to every kind of method. It is reloaded when the node receives a SIGHUP.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+use anyhow::Context;
+use dp_utils::wait_or_graceful_shutdown;
+use hyper::header::{HeaderName, AUTHORIZATION};
+use hyper::HeaderMap;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use tokio::task::JoinSet;
+
+use super::middleware::MethodClass;
+
+pub(crate) static X_API_KEY: HeaderName = HeaderName::from_static("x-api-key");
+
+const ALL_METHODS: &[MethodClass] = &[MethodClass::Read, MethodClass::Trace, MethodClass::Write];
+
+/// Methods a caller is allowed to call.
+pub type AllowedMethods = Arc<[MethodClass]>;
+
+#[derive(Debug, serde::Deserialize)]
+struct ApiKeyEntry {
+    key: String,
+    methods: Option<Vec<MethodClass>>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct JwtClaims {
+    methods: Option<Vec<MethodClass>>,
+}
+
+pub struct RpcAuth {
+    keys_file: Option<PathBuf>,
+    api_keys: RwLock<HashMap<String, AllowedMethods>>,
+    jwt_key: Option<DecodingKey>,
+}
+
+impl fmt::Debug for RpcAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RpcAuth")
+            .field("keys_file", &self.keys_file)
+            .field("jwt", &self.jwt_key.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl RpcAuth {
+    /// Returns `None` when authentication is disabled, that is when neither an api keys file nor a JWT
+    /// secret is configured.
+    pub fn new(keys_file: Option<PathBuf>, jwt_secret: Option<&str>) -> anyhow::Result<Option<Arc<Self>>> {
+        if keys_file.is_none() && jwt_secret.is_none() {
+            return Ok(None);
+        }
+
+        let auth = Self {
+            keys_file,
+            api_keys: Default::default(),
+            jwt_key: jwt_secret.map(|secret| DecodingKey::from_secret(secret.as_bytes())),
+        };
+        auth.reload()?;
+        Ok(Some(Arc::new(auth)))
+    }
+
+    pub fn has_keys_file(&self) -> bool {
+        self.keys_file.is_some()
+    }
+
+    /// Reads the api keys file again. The previous keys are kept if the file is invalid.
+    pub fn reload(&self) -> anyhow::Result<()> {
+        let Some(path) = &self.keys_file else { return Ok(()) };
+
+        let content = std::fs::read(path).with_context(|| format!("Reading RPC api keys file {}", path.display()))?;
+        let entries: Vec<ApiKeyEntry> = serde_json::from_slice(&content)
+            .with_context(|| format!("Parsing RPC api keys file {}", path.display()))?;
+
+        let api_keys = entries
+            .into_iter()
+            .map(|ApiKeyEntry { key, methods }| (key, methods.map_or_else(|| ALL_METHODS.into(), Into::into)))
+            .collect::<HashMap<_, _>>();
+
+        log::info!("🔑 Loaded {} RPC api keys", api_keys.len());
+        // UNWRAP: the lock is never held across a panic
+        *self.api_keys.write().unwrap() = api_keys;
+        Ok(())
+    }
+
+    /// Reads the api keys file again each time the node receives a SIGHUP.
+    pub fn reload_on_sighup(self: &Arc<Self>, join_set: &mut JoinSet<anyhow::Result<()>>) -> anyhow::Result<()> {
+        if !self.has_keys_file() {
+            return Ok(());
+        }
+
+        let auth = Arc::clone(self);
+        let mut sighup = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())
+            .context("Listening to SIGHUP to reload the RPC api keys")?;
+        join_set.spawn(async move {
+            while wait_or_graceful_shutdown(sighup.recv()).await.flatten().is_some() {
+                if let Err(err) = auth.reload() {
+                    log::error!("Failed to reload the RPC api keys: {err:#}");
+                }
+            }
+            Ok(())
+        });
+        Ok(())
+    }
+
+    /// Returns the methods the caller is allowed to call, or `None` if it is not authenticated.
+    pub fn authenticate(&self, headers: &HeaderMap) -> Option<AllowedMethods> {
+        let token = headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok()?.strip_prefix("Bearer "))
+            .or_else(|| headers.get(&X_API_KEY)?.to_str().ok())?
+            .trim();
+
+        // UNWRAP: the lock is never held across a panic
+        if let Some(methods) = self.api_keys.read().unwrap().get(token) {
+            return Some(Arc::clone(methods));
+        }
+
+        let jwt_key = self.jwt_key.as_ref()?;
+        match jsonwebtoken::decode::<JwtClaims>(token, jwt_key, &Validation::new(Algorithm::HS256)) {
+            Ok(data) => Some(data.claims.methods.map_or_else(|| ALL_METHODS.into(), Into::into)),
+            Err(err) => {
+                log::debug!(target: "rpc", "Rejected JWT: {err}");
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! Concurrent execution of the calls of HTTP batch requests.
+//!
+//! jsonrpsee answers the calls of a batch one after the other. Instead, the calls of an HTTP batch are sent to
+//! the server as single requests, which run concurrently, and their responses are put back together in the order
+//! of the batch. Each call still goes through the whole middleware stack, so it is authenticated, rate limited
+//! and measured like a single request. Websocket batches are still answered by jsonrpsee.
+use std::error::Error as StdError;
+use std::sync::Arc;
+
+use futures::stream::{self, StreamExt, TryStreamExt};
+use hyper::header::{CONTENT_LENGTH, CONTENT_TYPE};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use jsonrpsee::server::BatchRequestConfig;
+use jsonrpsee::types::error::reject_too_big_batch_response;
+use jsonrpsee::types::Id;
+use jsonrpsee::MethodResponse;
+use serde_json::value::RawValue;
+use tokio::sync::Semaphore;
+use tower::Service;
+
+type BoxError = Box<dyn StdError + Send + Sync + 'static>;
+
+#[derive(Debug, Clone)]
+pub struct BatchConfig {
+    pub batch_config: BatchRequestConfig,
+    pub max_request_size: u32,
+    pub max_response_size: u32,
+    /// Calls of batches which can run at the same time, over all the batches being answered.
+    pub permits: Arc<Semaphore>,
+}
+
+/// Answers the request, running the calls concurrently if it is a batch.
+pub async fn call<S>(svc: S, req: Request<Body>, config: BatchConfig) -> Result<Response<Body>, BoxError>
+where
+    S: Service<Request<Body>, Response = Response<Body>, Error = BoxError> + Clone + Send,
+    S::Future: Send,
+{
+    let max_len = match config.batch_config {
+        // let jsonrpsee reject the batches
+        BatchRequestConfig::Disabled => return call_single(svc, req).await,
+        BatchRequestConfig::Limit(limit) => limit as usize,
+        BatchRequestConfig::Unlimited => usize::MAX,
+    };
+    // Only the bodies which are known to fit in the request size limit are read here, jsonrpsee handles the
+    // others, streamed ones included.
+    let content_length = req
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok());
+    if req.method() != Method::POST || !content_length.is_some_and(|len| len <= config.max_request_size as u64) {
+        return call_single(svc, req).await;
+    }
+
+    let (parts, body) = req.into_parts();
+    let body = hyper::body::to_bytes(body).await?;
+    let calls = match serde_json::from_slice::<Vec<&RawValue>>(&body) {
+        Ok(calls) if !calls.is_empty() && calls.len() <= max_len => calls,
+        // not a batch, or a batch which jsonrpsee rejects
+        _ => return call_single(svc, Request::from_parts(parts, Body::from(body))).await,
+    };
+
+    let n_calls = calls.len();
+    let responses: Vec<Response<Body>> = stream::iter(calls)
+        .map(|call| {
+            let mut svc = svc.clone();
+            let mut req = Request::builder().method(parts.method.clone()).uri(parts.uri.clone());
+            if let Some(headers) = req.headers_mut() {
+                headers.extend(
+                    parts
+                        .headers
+                        .iter()
+                        .filter(|(name, _)| *name != CONTENT_LENGTH)
+                        .map(|(name, value)| (name.clone(), value.clone())),
+                );
+            }
+            let req = req.body(Body::from(call.get().to_owned()));
+            let permits = Arc::clone(&config.permits);
+            async move {
+                let _permit = permits.acquire_owned().await?;
+                svc.call(req?).await
+            }
+        })
+        .buffered(n_calls)
+        .try_collect()
+        .await?;
+
+    let mut batch_response = String::from("[");
+    for response in responses {
+        // a request refused by the http middleware is refused as a whole
+        if response.status() != StatusCode::OK {
+            return Ok(response);
+        }
+        let body = hyper::body::to_bytes(response.into_body()).await?;
+        // notifications are not answered
+        if body.is_empty() {
+            continue;
+        }
+        if batch_response.len() > 1 {
+            batch_response.push(',');
+        }
+        batch_response.push_str(std::str::from_utf8(&body)?);
+        if batch_response.len() > config.max_response_size as usize {
+            let error = reject_too_big_batch_response(config.max_response_size as usize);
+            return json_response(MethodResponse::error(Id::Null, error).into_result());
+        }
+    }
+    batch_response.push(']');
+
+    // a batch of notifications is not answered either
+    if batch_response.len() == 2 {
+        return json_response(String::new());
+    }
+    json_response(batch_response)
+}
+
+async fn call_single<S>(mut svc: S, req: Request<Body>) -> Result<Response<Body>, BoxError>
+where
+    S: Service<Request<Body>, Response = Response<Body>, Error = BoxError>,
+{
+    svc.call(req).await
+}
+
+fn json_response(body: String) -> Result<Response<Body>, BoxError> {
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(CONTENT_TYPE, "application/json; charset=utf-8")
+        .body(Body::from(body))?)
+}
@@ -0,0 +1,68 @@
+//! Proxying of the calls the node cannot answer to a remote provider.
+//!
+//! The calls to methods which are not implemented, or on data the node does not keep, are sent as they are to
+//! the fallback provider, and its answer is returned to the caller. When the provider cannot be reached, the
+//! caller gets the answer of the node.
+
+use jsonrpsee::types::error::METHOD_NOT_FOUND_CODE;
+use jsonrpsee::types::{ErrorObjectOwned, Request};
+use jsonrpsee::{MethodResponse, ResponsePayload};
+use serde_json::value::RawValue;
+use url::Url;
+
+/// Error codes of the node answers which are proxied.
+const FALLBACK_ERROR_CODES: &[i32] = &[
+    METHOD_NOT_FOUND_CODE,
+    // Unimplemented method
+    501,
+    // Storage proof requested on a block too far in the past
+    42,
+    // Merkle trie proof missing
+    10001,
+];
+
+#[derive(Debug, serde::Deserialize)]
+struct FallbackResponse {
+    result: Option<Box<RawValue>>,
+    error: Option<ErrorObjectOwned>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FallbackProvider {
+    client: reqwest::Client,
+    url: Url,
+    max_response_size: usize,
+}
+
+impl FallbackProvider {
+    pub fn new(url: Url, max_response_size: usize) -> Self {
+        Self { client: reqwest::Client::new(), url, max_response_size }
+    }
+
+    /// Whether the call answered with `response` should be sent to the fallback provider.
+    pub fn should_proxy(&self, response: &MethodResponse) -> bool {
+        response.as_error_code().is_some_and(|code| FALLBACK_ERROR_CODES.contains(&code))
+    }
+
+    /// Sends the call to the fallback provider, `response` is the answer of the node.
+    pub async fn call(&self, req: &Request<'_>, response: MethodResponse) -> MethodResponse {
+        match self.send(req).await {
+            Ok(FallbackResponse { error: Some(error), .. }) => MethodResponse::error(req.id.clone(), error),
+            Ok(FallbackResponse { result: Some(result), .. }) => {
+                MethodResponse::response(req.id.clone(), ResponsePayload::success(result), self.max_response_size)
+            }
+            Ok(_) => {
+                log::debug!(target: "rpc", "Fallback provider gave an empty answer to {}", req.method_name());
+                response
+            }
+            Err(err) => {
+                log::debug!(target: "rpc", "Fallback provider failed to answer {}: {err:#}", req.method_name());
+                response
+            }
+        }
+    }
+
+    async fn send(&self, req: &Request<'_>) -> reqwest::Result<FallbackResponse> {
+        self.client.post(self.url.clone()).json(req).send().await?.error_for_status()?.json().await
+    }
+}
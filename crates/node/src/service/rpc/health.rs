@@ -0,0 +1,92 @@
+//! Readiness and liveness probes, served on the RPC HTTP server.
+use std::sync::Arc;
+
+use dc_db::DeoxysBackend;
+use hyper::header::CONTENT_TYPE;
+use hyper::{Body, Response, StatusCode};
+use serde_json::json;
+
+pub const LIVENESS_PATH: &str = "/health/live";
+pub const READINESS_PATH: &str = "/health/ready";
+
+#[derive(Clone)]
+pub struct HealthChecks {
+    backend: Arc<DeoxysBackend>,
+    /// `None` when the node does not sync from the feeder gateway.
+    max_sync_lag: Option<u64>,
+    /// Whether the state updates should be verified against L1 before the node is ready.
+    check_l1: bool,
+}
+
+impl std::fmt::Debug for HealthChecks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HealthChecks")
+            .field("max_sync_lag", &self.max_sync_lag)
+            .field("check_l1", &self.check_l1)
+            .finish()
+    }
+}
+
+impl HealthChecks {
+    pub fn new(backend: Arc<DeoxysBackend>, max_sync_lag: Option<u64>, check_l1: bool) -> Self {
+        Self { backend, max_sync_lag, check_l1 }
+    }
+
+    /// The node is live as long as its database can be read.
+    pub fn live(&self) -> hyper::http::Result<Response<Body>> {
+        match self.backend.get_latest_block_n() {
+            Ok(_) => respond(StatusCode::OK, json!({ "status": "ok" })),
+            Err(err) => {
+                log::warn!("Liveness check failed to read the database: {err:#}");
+                respond(StatusCode::SERVICE_UNAVAILABLE, json!({ "status": "error", "db": format!("{err:#}") }))
+            }
+        }
+    }
+
+    /// The node is ready once it has caught up with the tip of the chain and, if enabled, received the state of L1.
+    pub fn ready(&self) -> hyper::http::Result<Response<Body>> {
+        let (latest_block, highest_known_block, l1_last_confirmed_block) = match (
+            self.backend.get_latest_block_n(),
+            self.backend.get_highest_known_block(),
+            self.backend.get_l1_last_confirmed_block(),
+        ) {
+            (Ok(latest), Ok(highest), Ok(l1_last)) => (latest, highest.map(|highest| highest.block_number), l1_last),
+            (Err(err), _, _) | (_, Err(err), _) | (_, _, Err(err)) => {
+                log::warn!("Readiness check failed to read the database: {err:#}");
+                return respond(StatusCode::SERVICE_UNAVAILABLE, json!({ "ready": false, "db": format!("{err:#}") }));
+            }
+        };
+
+        let sync_lag = highest_known_block.map(|highest| highest.saturating_sub(latest_block.unwrap_or_default()));
+        let synced = match self.max_sync_lag {
+            None => true,
+            Some(max_sync_lag) => latest_block.is_some() && sync_lag.is_some_and(|lag| lag <= max_sync_lag),
+        };
+        // The last confirmed block is reset to 0 when L1 sync starts, and set once the L1 state has been fetched.
+        let l1_connected = !self.check_l1 || l1_last_confirmed_block.is_some_and(|block_n| block_n > 0);
+
+        let ready = synced && l1_connected;
+        let status = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+        respond(
+            status,
+            json!({
+                "ready": ready,
+                "db": "ok",
+                "sync": {
+                    "synced": synced,
+                    "latest_block": latest_block,
+                    "highest_known_block": highest_known_block,
+                    "lag": sync_lag,
+                },
+                "l1": {
+                    "connected": l1_connected,
+                    "last_confirmed_block": l1_last_confirmed_block,
+                },
+            }),
+        )
+    }
+}
+
+fn respond(status: StatusCode, body: serde_json::Value) -> hyper::http::Result<Response<Body>> {
+    Response::builder().status(status).header(CONTENT_TYPE, "application/json").body(Body::from(body.to_string()))
+}
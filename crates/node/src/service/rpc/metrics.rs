@@ -1,6 +1,14 @@
+use std::collections::HashSet;
+use std::sync::Arc;
 use std::time::Instant;
 
-use dc_metrics::{Counter, CounterVec, HistogramOpts, HistogramVec, MetricsRegistry, Opts, PrometheusError, U64};
+use dc_metrics::{
+    Counter, CounterVec, Gauge, GaugeVec, HistogramOpts, HistogramVec, MetricsRegistry, Opts, PrometheusError, U64,
+};
+use dc_rpc::{ErrorOrigin, StarknetRpcApiError};
+use jsonrpsee::types::error::{
+    INTERNAL_ERROR_CODE, INVALID_PARAMS_CODE, INVALID_REQUEST_CODE, METHOD_NOT_FOUND_CODE, PARSE_ERROR_CODE,
+};
 use jsonrpsee::types::Request;
 use jsonrpsee::MethodResponse;
 
@@ -8,6 +16,30 @@ use jsonrpsee::MethodResponse;
 const HISTOGRAM_BUCKETS: [f64; 11] =
     [5.0, 25.0, 100.0, 500.0, 1_000.0, 2_500.0, 10_000.0, 25_000.0, 100_000.0, 1_000_000.0, 10_000_000.0];
 
+/// Histogram size buckets in bytes.
+const HISTOGRAM_SIZE_BUCKETS: [f64; 7] =
+    [128.0, 1_024.0, 10_240.0, 102_400.0, 1_048_576.0, 10_485_760.0, 104_857_600.0];
+
+/// Name and origin of an error code, for the errors answered by the node and its middleware. Other codes are
+/// labeled `other`, so that a fallback provider cannot create an unbounded number of metric series.
+fn error_labels(code: i32) -> (&'static str, &'static str) {
+    if let Some((name, origin)) = StarknetRpcApiError::describe_code(code) {
+        return (name, origin.label());
+    }
+    let (name, origin) = match code {
+        PARSE_ERROR_CODE => ("ParseError", ErrorOrigin::Caller),
+        INVALID_REQUEST_CODE => ("InvalidRequest", ErrorOrigin::Caller),
+        METHOD_NOT_FOUND_CODE => ("MethodNotFound", ErrorOrigin::Caller),
+        INVALID_PARAMS_CODE => ("InvalidParams", ErrorOrigin::Caller),
+        INTERNAL_ERROR_CODE => ("InternalError", ErrorOrigin::Node),
+        -32997 => ("Timeout", ErrorOrigin::Node),
+        -32998 => ("NotAllowed", ErrorOrigin::Caller),
+        -32999 => ("RateLimited", ErrorOrigin::Caller),
+        _ => return ("other", "unknown"),
+    };
+    (name, origin.label())
+}
+
 /// Metrics for RPC middleware storing information about the number of requests started/completed,
 /// calls started/completed and their timings.
 #[derive(Debug, Clone)]
@@ -18,12 +50,26 @@ pub struct RpcMetrics {
     calls_started: CounterVec<U64>,
     /// Number of calls completed.
     calls_finished: CounterVec<U64>,
+    /// Histogram over RPC response sizes.
+    calls_response_size: HistogramVec,
+    /// Number of calls answered with an error, by error and by whether the caller or the node is at fault.
+    calls_errors: CounterVec<U64>,
     /// Number of Websocket sessions opened.
     ws_sessions_opened: Option<Counter<U64>>,
     /// Number of Websocket sessions closed.
     ws_sessions_closed: Option<Counter<U64>>,
     /// Histogram over RPC websocket sessions.
     ws_sessions_time: HistogramVec,
+    /// Number of Websocket sessions currently open.
+    ws_sessions_active: Gauge<U64>,
+    /// Number of Websocket sessions refused because of `--rpc-max-ws-connections`.
+    ws_sessions_rejected: Counter<U64>,
+    /// Number of HTTP and unix socket connections currently open. A connection upgraded to a websocket is counted
+    /// as a websocket session instead.
+    connections_active: GaugeVec<U64>,
+    /// Methods served by the node. Other method names are labeled `unknown`, so that callers cannot create
+    /// an unbounded number of metric series.
+    known_methods: Arc<HashSet<&'static str>>,
 }
 
 impl RpcMetrics {
@@ -43,6 +89,15 @@ impl RpcMetrics {
                 Opts::new("rpc_calls_finished", "Number of processed RPC calls (unique un-batched requests)"),
                 &["protocol", "method", "is_error", "is_rate_limited"],
             )?)?,
+            calls_response_size: registry.register(HistogramVec::new(
+                HistogramOpts::new("rpc_calls_response_size", "Size [bytes] of the responses to RPC calls")
+                    .buckets(HISTOGRAM_SIZE_BUCKETS.to_vec()),
+                &["protocol", "method"],
+            )?)?,
+            calls_errors: registry.register(CounterVec::new(
+                Opts::new("rpc_calls_errors", "Number of RPC calls answered with an error, by error"),
+                &["protocol", "method", "error", "origin"],
+            )?)?,
             ws_sessions_opened: registry
                 .register(Counter::new("rpc_sessions_opened", "Number of persistent RPC sessions opened")?)?
                 .into(),
@@ -54,13 +109,38 @@ impl RpcMetrics {
                     .buckets(HISTOGRAM_BUCKETS.to_vec()),
                 &["protocol"],
             )?)?,
+            ws_sessions_active: registry
+                .register(Gauge::new("rpc_sessions_active", "Number of websocket sessions currently open")?)?,
+            ws_sessions_rejected: registry.register(Counter::new(
+                "rpc_sessions_rejected",
+                "Number of websocket sessions refused because the connection limit was reached",
+            )?)?,
+            connections_active: registry.register(GaugeVec::new(
+                Opts::new("rpc_connections_active", "Number of HTTP and unix socket connections currently open"),
+                &["protocol"],
+            )?)?,
+            known_methods: Default::default(),
         })
     }
 
+    /// Sets the methods served by the node, which are the only ones labeled with their name.
+    pub fn with_known_methods(self, methods: impl IntoIterator<Item = &'static str>) -> Self {
+        Self { known_methods: Arc::new(methods.into_iter().collect()), ..self }
+    }
+
+    fn method_label<'a>(&self, method_name: &'a str) -> &'a str {
+        if self.known_methods.contains(method_name) {
+            method_name
+        } else {
+            "unknown"
+        }
+    }
+
     pub(crate) fn ws_connect(&self) {
         if let Some(counter) = self.ws_sessions_opened.as_ref() {
             counter.inc()
         }
+        self.ws_sessions_active.inc();
     }
 
     pub(crate) fn ws_disconnect(&self, now: Instant) {
@@ -69,9 +149,21 @@ impl RpcMetrics {
         if let Some(counter) = self.ws_sessions_closed.as_ref() {
             counter.inc()
         }
+        self.ws_sessions_active.dec();
         self.ws_sessions_time.with_label_values(&["ws"]).observe(micros as _);
     }
 
+    pub(crate) fn ws_rejected(&self) {
+        self.ws_sessions_rejected.inc();
+    }
+
+    /// Counts the connection as open until the returned guard is dropped.
+    pub(crate) fn connection_opened(&self, transport_label: &'static str) -> ConnectionGuard {
+        let active = self.connections_active.with_label_values(&[transport_label]);
+        active.inc();
+        ConnectionGuard { active }
+    }
+
     pub(crate) fn on_call(&self, req: &Request, transport_label: &'static str) {
         log::trace!(
             target: "rpc_metrics",
@@ -80,7 +172,7 @@ impl RpcMetrics {
             req.params(),
         );
 
-        self.calls_started.with_label_values(&[transport_label, req.method_name()]).inc();
+        self.calls_started.with_label_values(&[transport_label, self.method_label(req.method_name())]).inc();
     }
 
     pub(crate) fn on_response(
@@ -102,18 +194,42 @@ impl RpcMetrics {
             micros,
         );
         self.calls_time
-            .with_label_values(&[transport_label, req.method_name(), if is_rate_limited { "true" } else { "false" }])
+            .with_label_values(&[
+                transport_label,
+                self.method_label(req.method_name()),
+                if is_rate_limited { "true" } else { "false" },
+            ])
             .observe(micros as _);
         self.calls_finished
             .with_label_values(&[
                 transport_label,
-                req.method_name(),
+                self.method_label(req.method_name()),
                 // the label "is_error", so `success` should be regarded as false
                 // and vice-versa to be registered correctly.
                 if rp.is_success() { "false" } else { "true" },
                 if is_rate_limited { "true" } else { "false" },
             ])
             .inc();
+        if let Some(code) = rp.as_error_code() {
+            let (error, origin) = error_labels(code);
+            self.calls_errors
+                .with_label_values(&[transport_label, self.method_label(req.method_name()), error, origin])
+                .inc();
+        }
+        self.calls_response_size
+            .with_label_values(&[transport_label, self.method_label(req.method_name())])
+            .observe(rp.as_result().len() as _);
+    }
+}
+
+/// Decrements the number of open connections when dropped.
+pub(crate) struct ConnectionGuard {
+    active: Gauge<U64>,
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.active.dec();
     }
 }
 
@@ -138,6 +254,10 @@ impl Metrics {
         self.inner.ws_disconnect(now)
     }
 
+    pub(crate) fn ws_rejected(&self) {
+        self.inner.ws_rejected()
+    }
+
     pub(crate) fn on_call(&self, req: &Request) {
         self.inner.on_call(req, self.transport_label)
     }
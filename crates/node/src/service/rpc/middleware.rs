@@ -1,41 +1,200 @@
 //! JSON-RPC specific middleware.
 
+use std::net::IpAddr;
 use std::num::NonZeroU32;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+use dc_rpc::timings::CallTimings;
+use dp_utils::request_id;
 use futures::future::{BoxFuture, FutureExt};
 use governor::clock::{Clock, DefaultClock, QuantaClock};
 use governor::middleware::NoOpMiddleware;
-use governor::state::{InMemoryState, NotKeyed};
+use governor::state::keyed::DashMapStateStore;
 use governor::{Jitter, Quota, RateLimiter};
 use jsonrpsee::server::middleware::rpc::RpcServiceT;
 use jsonrpsee::types::{ErrorObject, Request};
 use jsonrpsee::MethodResponse;
 
+use super::auth::AllowedMethods;
+use super::fallback::FallbackProvider;
 pub use super::metrics::{Metrics, RpcMetrics};
 
-/// Rate limit middleware
+/// Kind of RPC method, each kind having its own rate limit and permission. The methods executing
+/// transactions or calls, which are the expensive ones, are Trace methods.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MethodClass {
+    Read,
+    Trace,
+    Write,
+}
+
+impl MethodClass {
+    pub fn of(method_name: &str) -> Self {
+        if method_name.starts_with("starknet_trace")
+            || method_name.starts_with("starknet_simulate")
+            || method_name.starts_with("starknet_estimate")
+            || method_name == "starknet_call"
+            || method_name.starts_with("deoxys_trace")
+        {
+            Self::Trace
+        } else if method_name.starts_with("starknet_add")
+            || method_name.starts_with("admin_")
+            || method_name.starts_with("dev_")
+        {
+            Self::Write
+        } else {
+            Self::Read
+        }
+    }
+}
+
+/// Rate limit middleware, keyed by the ip of the caller.
 #[derive(Debug, Clone)]
 pub struct RateLimit {
-    pub(crate) inner: Arc<governor::RateLimiter<NotKeyed, InMemoryState, DefaultClock, NoOpMiddleware>>,
+    pub(crate) inner: Arc<governor::RateLimiter<IpAddr, DashMapStateStore<IpAddr>, DefaultClock, NoOpMiddleware>>,
     pub(crate) clock: QuantaClock,
 }
 
 impl RateLimit {
     pub fn new(max_burst: NonZeroU32) -> Self {
         let clock = QuantaClock::default();
-        Self { inner: Arc::new(RateLimiter::direct_with_clock(Quota::per_minute(max_burst), &clock)), clock }
+        Self { inner: Arc::new(RateLimiter::dashmap_with_clock(Quota::per_minute(max_burst), &clock)), clock }
+    }
+
+    /// Forgets the ips which are not rate limited anymore.
+    pub fn retain_recent(&self) {
+        self.inner.retain_recent();
+        self.inner.shrink_to_fit();
+    }
+
+    /// Returns whether `ip` is allowed to make a call right away.
+    pub(crate) fn check(&self, ip: &IpAddr) -> bool {
+        self.inner.check_key(ip).is_ok()
+    }
+
+    /// Waits until `ip` is allowed to make a call. Returns whether it had to wait, or `None` if it is still
+    /// rate limited after [`MAX_RETRIES`] attempts.
+    async fn acquire(&self, ip: &IpAddr) -> Option<bool> {
+        let jitter = Jitter::up_to(MAX_JITTER);
+
+        for attempt in 0..MAX_RETRIES {
+            match self.inner.check_key(ip) {
+                Ok(_) => return Some(attempt > 0),
+                Err(rejected) => tokio::time::sleep(jitter + rejected.wait_time_from(self.clock.now())).await,
+            }
+        }
+        None
+    }
+}
+
+/// Per minute rate limits of each [`MethodClass`]. They are shared by every connection, so that a client
+/// cannot work around them by opening new connections.
+#[derive(Debug, Clone, Default)]
+pub struct RateLimits {
+    read: Option<RateLimit>,
+    trace: Option<RateLimit>,
+    write: Option<RateLimit>,
+}
+
+impl RateLimits {
+    pub fn new(read: Option<NonZeroU32>, trace: Option<NonZeroU32>, write: Option<NonZeroU32>) -> Self {
+        Self { read: read.map(RateLimit::new), trace: trace.map(RateLimit::new), write: write.map(RateLimit::new) }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.read.is_some() || self.trace.is_some() || self.write.is_some()
+    }
+
+    fn get(&self, class: MethodClass) -> Option<&RateLimit> {
+        match class {
+            MethodClass::Read => self.read.as_ref(),
+            MethodClass::Trace => self.trace.as_ref(),
+            MethodClass::Write => self.write.as_ref(),
+        }
+    }
+
+    /// Forgets the ips which are not rate limited anymore.
+    pub fn retain_recent(&self) {
+        for limit in [&self.read, &self.trace, &self.write].into_iter().flatten() {
+            limit.retain_recent();
+        }
+    }
+}
+
+/// Time budget of a call, depending on its [`MethodClass`]. Calls which take longer are answered with an
+/// error and their future is dropped.
+#[derive(Debug, Clone, Copy)]
+pub struct CallTimeouts {
+    pub default: Duration,
+    pub trace: Duration,
+}
+
+impl CallTimeouts {
+    fn get(&self, class: MethodClass) -> Duration {
+        match class {
+            MethodClass::Trace => self.trace,
+            MethodClass::Read | MethodClass::Write => self.default,
+        }
     }
 }
 
 const MAX_JITTER: Duration = Duration::from_millis(50);
 const MAX_RETRIES: usize = 10;
+/// Strings of the slow call log params summary which are longer are truncated, this fits a felt.
+const SLOW_LOG_MAX_STRING_LEN: usize = 66;
+/// Maximum number of params and object fields listed by the slow call log params summary.
+const SLOW_LOG_MAX_ITEMS: usize = 8;
+
+/// A short summary of the params of a call, for the slow call log. Long strings and arrays (calldata,
+/// signatures, compiled classes...) are replaced by their length.
+fn params_summary(params: Option<&str>) -> String {
+    fn summarize(value: &serde_json::Value, depth: usize) -> String {
+        match value {
+            serde_json::Value::String(s) if s.len() > SLOW_LOG_MAX_STRING_LEN => format!("string({})", s.len()),
+            serde_json::Value::Array(items) if depth > 0 => format!("array({})", items.len()),
+            serde_json::Value::Object(fields) if depth > 1 => format!("object({})", fields.len()),
+            serde_json::Value::Array(items) => {
+                let mut summary: Vec<_> =
+                    items.iter().take(SLOW_LOG_MAX_ITEMS).map(|item| summarize(item, depth + 1)).collect();
+                if items.len() > SLOW_LOG_MAX_ITEMS {
+                    summary.push(format!("...{} more", items.len() - SLOW_LOG_MAX_ITEMS));
+                }
+                format!("[{}]", summary.join(", "))
+            }
+            serde_json::Value::Object(fields) => {
+                let mut summary: Vec<_> = fields
+                    .iter()
+                    .take(SLOW_LOG_MAX_ITEMS)
+                    .map(|(key, value)| format!("{key}: {}", summarize(value, depth + 1)))
+                    .collect();
+                if fields.len() > SLOW_LOG_MAX_ITEMS {
+                    summary.push(format!("...{} more", fields.len() - SLOW_LOG_MAX_ITEMS));
+                }
+                format!("{{{}}}", summary.join(", "))
+            }
+            value => value.to_string(),
+        }
+    }
+
+    match params.map(serde_json::from_str::<serde_json::Value>) {
+        None => "[]".to_string(),
+        Some(Ok(params)) => summarize(&params, 0),
+        Some(Err(_)) => "invalid".to_string(),
+    }
+}
 
 #[derive(Debug, Clone, Default)]
 pub struct MiddlewareLayer {
-    rate_limit: Option<RateLimit>,
+    rate_limit: Option<(RateLimits, IpAddr)>,
+    allowed_methods: Option<AllowedMethods>,
+    write_denied: bool,
+    timeouts: Option<CallTimeouts>,
     metrics: Option<Metrics>,
+    slow_log_threshold: Option<Duration>,
+    request_id: Option<Arc<str>>,
+    fallback: Option<Arc<FallbackProvider>>,
 }
 
 impl MiddlewareLayer {
@@ -43,14 +202,44 @@ impl MiddlewareLayer {
         Self::default()
     }
 
-    /// Enable new rate limit middleware enforced per minute.
-    pub fn with_rate_limit_per_minute(self, n: NonZeroU32) -> Self {
-        Self { rate_limit: Some(RateLimit::new(n)), metrics: self.metrics }
+    /// Enable the rate limit middleware for the calls made by `ip`.
+    pub fn with_rate_limits(self, rate_limits: RateLimits, ip: IpAddr) -> Self {
+        Self { rate_limit: Some((rate_limits, ip)), ..self }
+    }
+
+    /// Only allow calls to some kinds of methods, for authenticated callers.
+    pub fn with_allowed_methods(self, allowed_methods: AllowedMethods) -> Self {
+        Self { allowed_methods: Some(allowed_methods), ..self }
+    }
+
+    /// Refuse the write and admin calls, for the callers outside of the write ip allowlist.
+    pub fn with_write_denied(self, write_denied: bool) -> Self {
+        Self { write_denied, ..self }
+    }
+
+    /// Enable the call timeouts.
+    pub fn with_timeouts(self, timeouts: CallTimeouts) -> Self {
+        Self { timeouts: Some(timeouts), ..self }
     }
 
     /// Enable metrics middleware.
     pub fn with_metrics(self, metrics: Metrics) -> Self {
-        Self { rate_limit: self.rate_limit, metrics: Some(metrics) }
+        Self { metrics: Some(metrics), ..self }
+    }
+
+    /// Use the request id given by the caller for its calls, instead of generating one for each call.
+    pub fn with_request_id(self, request_id: Option<Arc<str>>) -> Self {
+        Self { request_id, ..self }
+    }
+
+    /// Proxy the calls the node cannot answer to the `fallback` provider.
+    pub fn with_fallback(self, fallback: Option<Arc<FallbackProvider>>) -> Self {
+        Self { fallback, ..self }
+    }
+
+    /// Log the calls which take longer than `threshold`, along with where their time was spent.
+    pub fn with_slow_log(self, threshold: Option<Duration>) -> Self {
+        Self { slow_log_threshold: threshold, ..self }
     }
 
     /// Register a new websocket connection.
@@ -60,6 +249,13 @@ impl MiddlewareLayer {
         }
     }
 
+    /// Register that a websocket connection was refused.
+    pub fn ws_rejected(&self) {
+        if let Some(m) = self.metrics.as_ref() {
+            m.ws_rejected()
+        }
+    }
+
     /// Register that a websocket connection was closed.
     pub fn ws_disconnect(&self, now: Instant) {
         if let Some(m) = self.metrics.as_ref() {
@@ -72,14 +268,30 @@ impl<S> tower::Layer<S> for MiddlewareLayer {
     type Service = Middleware<S>;
 
     fn layer(&self, service: S) -> Self::Service {
-        Middleware { service, rate_limit: self.rate_limit.clone(), metrics: self.metrics.clone() }
+        Middleware {
+            service,
+            rate_limit: self.rate_limit.clone(),
+            allowed_methods: self.allowed_methods.clone(),
+            write_denied: self.write_denied,
+            timeouts: self.timeouts,
+            metrics: self.metrics.clone(),
+            slow_log_threshold: self.slow_log_threshold,
+            request_id: self.request_id.clone(),
+            fallback: self.fallback.clone(),
+        }
     }
 }
 
 pub struct Middleware<S> {
     service: S,
-    rate_limit: Option<RateLimit>,
+    rate_limit: Option<(RateLimits, IpAddr)>,
+    allowed_methods: Option<AllowedMethods>,
+    write_denied: bool,
+    timeouts: Option<CallTimeouts>,
     metrics: Option<Metrics>,
+    slow_log_threshold: Option<Duration>,
+    request_id: Option<Arc<str>>,
+    fallback: Option<Arc<FallbackProvider>>,
 }
 
 impl<'a, S> RpcServiceT<'a> for Middleware<S>
@@ -95,37 +307,76 @@ where
             m.on_call(&req)
         }
 
+        let method_class = MethodClass::of(req.method_name());
+        let is_allowed = self.allowed_methods.as_ref().map_or(true, |allowed| allowed.contains(&method_class));
+        let is_denied_ip = self.write_denied && method_class == MethodClass::Write;
+
         let service = self.service.clone();
         let rate_limit = self.rate_limit.clone();
+        let timeout = self.timeouts.map(|timeouts| timeouts.get(method_class));
         let metrics = self.metrics.clone();
+        let slow_log_threshold = self.slow_log_threshold;
+        // Every log line emitted while serving the call carries its id
+        let request_id = self.request_id.clone().unwrap_or_else(request_id::generate);
+        let timings = Arc::new(CallTimings::default());
+        // The write and trace methods the node does not expose must not be reachable through the fallback
+        let fallback = self.fallback.clone().filter(|_| method_class == MethodClass::Read);
 
-        async move {
-            let mut is_rate_limited = false;
-
-            if let Some(limit) = rate_limit.as_ref() {
-                let mut attempts = 0;
-                let jitter = Jitter::up_to(MAX_JITTER);
-
-                loop {
-                    if attempts >= MAX_RETRIES {
-                        return MethodResponse::error(
-                            req.id,
-                            ErrorObject::owned(-32999, "RPC rate limit exceeded", None::<()>),
-                        );
-                    }
-
-                    if let Err(rejected) = limit.inner.check() {
-                        tokio::time::sleep(jitter + rejected.wait_time_from(limit.clock.now())).await;
-                    } else {
-                        break;
+        let call = {
+            let timings = Arc::clone(&timings);
+            move |req: Request<'a>| async move {
+                let id = req.id.clone();
+                let call = async move {
+                    let fallback = fallback.map(|fallback| (fallback, req.clone()));
+                    let rp = CallTimings::scope(timings, service.call(req)).await;
+                    match fallback {
+                        Some((fallback, req)) if fallback.should_proxy(&rp) => fallback.call(&req, rp).await,
+                        _ => rp,
                     }
-
-                    is_rate_limited = true;
-                    attempts += 1;
+                };
+                match timeout {
+                    Some(timeout) => tokio::time::timeout(timeout, call).await.unwrap_or_else(|_| {
+                        MethodResponse::error(id, ErrorObject::owned(-32997, "RPC call timed out", None::<()>))
+                    }),
+                    None => call.await,
                 }
             }
+        };
 
-            let rp = service.call(req.clone()).await;
+        let serve = async move {
+            let limit = rate_limit.as_ref().and_then(|(limits, ip)| Some((limits.get(method_class)?, ip)));
+
+            let (rp, is_rate_limited) = if is_denied_ip {
+                (
+                    MethodResponse::error(
+                        req.id.clone(),
+                        ErrorObject::owned(-32998, "RPC method not allowed from this address", None::<()>),
+                    ),
+                    false,
+                )
+            } else if !is_allowed {
+                (
+                    MethodResponse::error(
+                        req.id.clone(),
+                        ErrorObject::owned(-32998, "RPC method not allowed", None::<()>),
+                    ),
+                    false,
+                )
+            } else {
+                match limit {
+                    Some((limit, ip)) => match limit.acquire(ip).await {
+                        Some(is_rate_limited) => (call(req.clone()).await, is_rate_limited),
+                        None => (
+                            MethodResponse::error(
+                                req.id.clone(),
+                                ErrorObject::owned(-32999, "RPC rate limit exceeded", None::<()>),
+                            ),
+                            true,
+                        ),
+                    },
+                    None => (call(req.clone()).await, false),
+                }
+            };
 
             let method = req.method_name();
             let status = rp.as_error_code().unwrap_or(200);
@@ -141,12 +392,62 @@ where
                 "{method} {status} {res_len} - {response_time:?}",
             );
 
+            if slow_log_threshold.is_some_and(|threshold| response_time >= threshold) {
+                let exec_time = timings.exec();
+                let params = params_summary(req.params.as_ref().map(|params| params.get()));
+                log::warn!(
+                    target: "rpc_slow",
+                    "🐢 Slow call {method} {params} {status} - {response_time:?} (execution: {exec_time:?}, storage \
+                     and other: {:?})",
+                    response_time.saturating_sub(exec_time),
+                );
+            }
+
             if let Some(m) = metrics.as_ref() {
                 m.on_response(&req, &rp, is_rate_limited, now)
             }
 
             rp
+        };
+        request_id::instrument(Some(request_id), serve).boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_method_class_of() {
+        for method in [
+            "starknet_getBlockWithTxs",
+            "starknet_getStorageAt",
+            "starknet_chainId",
+            "starknet_subscribeNewHeads",
+            "pathfinder_getProof",
+            "deoxys_getStorageBatch",
+        ] {
+            assert_eq!(MethodClass::of(method), MethodClass::Read, "{method}");
+        }
+        for method in [
+            "starknet_call",
+            "starknet_estimateFee",
+            "starknet_estimateMessageFee",
+            "starknet_simulateTransactions",
+            "starknet_traceTransaction",
+            "starknet_traceBlockTransactions",
+            "deoxys_traceTransactionSyscalls",
+        ] {
+            assert_eq!(MethodClass::of(method), MethodClass::Trace, "{method}");
+        }
+        for method in [
+            "starknet_addInvokeTransaction",
+            "starknet_addDeclareTransaction",
+            "starknet_addDeployAccountTransaction",
+            "admin_pauseSync",
+            "dev_mint",
+        ] {
+            assert_eq!(MethodClass::of(method), MethodClass::Write, "{method}");
         }
-        .boxed()
     }
 }
@@ -1,33 +1,59 @@
 #![allow(clippy::declare_interior_mutable_const)]
 #![allow(clippy::borrow_interior_mutable_const)]
 
+use std::collections::HashMap;
 use std::convert::Infallible;
 use std::net::{IpAddr, SocketAddr};
 use std::num::NonZeroU32;
+use std::os::unix::fs::FileTypeExt;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::Context;
-use dp_utils::wait_or_graceful_shutdown;
+use dc_rpc::hooks::{RpcHooks, RpcHooksLayer};
+use dc_rpc::openrpc::{discover_document, DISCOVER_METHOD};
+use dc_rpc::RpcVersion;
+use dp_utils::{graceful_shutdown, request_id, wait_or_graceful_shutdown};
 use forwarded_header_value::ForwardedHeaderValue;
-use hyper::header::{HeaderName, HeaderValue};
+use hyper::header::{HeaderName, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
 use hyper::server::conn::AddrStream;
 use hyper::service::{make_service_fn, service_fn};
-use hyper::{Body, Request, Response, StatusCode};
+use hyper::{Body, Method, Request, Response, StatusCode};
 use ip_network::IpNetwork;
 use jsonrpsee::core::id_providers::RandomStringIdProvider;
 use jsonrpsee::server::middleware::http::HostFilterLayer;
 use jsonrpsee::server::middleware::rpc::RpcServiceBuilder;
 use jsonrpsee::server::{stop_channel, ws, BatchRequestConfig, PingConfig, StopHandle, TowerServiceBuilder};
 use jsonrpsee::{Methods, RpcModule};
-use tokio::net::TcpListener;
+use tokio::net::{TcpListener, UnixListener};
+use tokio::sync::Semaphore;
 use tokio::task::JoinSet;
-use tower::Service;
+use tower::{Layer, Service};
+use tower_http::compression::predicate::SizeAbove;
+use tower_http::compression::CompressionLayer;
 use tower_http::cors::{AllowOrigin, CorsLayer};
+use url::Url;
 
-use super::middleware::{Metrics, MiddlewareLayer, RpcMetrics};
+use super::auth::{RpcAuth, X_API_KEY};
+use super::batch::{self, BatchConfig};
+use super::fallback::FallbackProvider;
+use super::health::{HealthChecks, LIVENESS_PATH, READINESS_PATH};
+use super::metrics::ConnectionGuard;
+use super::middleware::{CallTimeouts, Metrics, MiddlewareLayer, RateLimits, RpcMetrics};
 
 const MEGABYTE: u32 = 1024 * 1024;
+/// How often the rate limiters forget about the ips which are not limited anymore.
+pub(crate) const RATE_LIMIT_RETAIN_INTERVAL: Duration = Duration::from_secs(60);
+/// Number of inactive periods in a row after which a websocket connection is closed.
+const WS_MAX_INACTIVE_PERIODS: usize = 3;
+/// Path of the OpenRPC document, under the path of each version.
+const OPENRPC_PATH: &str = "/openrpc.json";
+/// Time the subscriptions have to notify their subscribers on shutdown, before the websocket connections close.
+const WS_CLOSE_DELAY: Duration = Duration::from_millis(100);
+/// Responses smaller than this many bytes are not worth compressing.
+const COMPRESSION_MIN_SIZE: u16 = 1024;
 
 /// RPC server configuration.
 #[derive(Debug, Clone)]
@@ -35,25 +61,60 @@ pub struct ServerConfig {
     pub addr: SocketAddr,
     pub cors: Option<Vec<String>>,
     pub max_connections: u32,
+    /// Maximum number of websocket connections, within `max_connections`.
+    pub max_ws_connections: Option<u32>,
+    /// Interval between two websocket pings.
+    pub ws_ping_interval: Duration,
+    /// Time after which a silent websocket connection is inactive.
+    pub ws_idle_timeout: Duration,
     pub max_subs_per_conn: u32,
     pub max_payload_in_mb: u32,
     pub max_payload_out_mb: u32,
     pub metrics: RpcMetrics,
     pub message_buffer_capacity: u32,
-    pub rpc_api: RpcModule<()>,
+    /// Methods of each served specification version.
+    pub rpc_apis: Vec<(RpcVersion, RpcModule<()>)>,
     /// Batch request config.
     pub batch_config: BatchRequestConfig,
-    /// Rate limit calls per minute.
+    /// Number of calls of HTTP batches answered concurrently, over all the batches.
+    pub batch_concurrency: u32,
+    /// Rate limit of the read methods, in calls per minute per ip.
     pub rate_limit: Option<NonZeroU32>,
+    /// Rate limit of the trace and simulation methods, in calls per minute per ip.
+    pub rate_limit_trace: Option<NonZeroU32>,
+    /// Rate limit of the write and admin methods, in calls per minute per ip.
+    pub rate_limit_write: Option<NonZeroU32>,
     /// Disable rate limit for certain ips.
     pub rate_limit_whitelisted_ips: Vec<IpNetwork>,
-    /// Trust proxy headers for rate limiting.
+    /// Trust proxy headers for rate limiting and the write ip allowlist.
     pub rate_limit_trust_proxy_headers: bool,
+    /// Only the callers in these ip ranges can call the write and admin methods, any caller can when `None`.
+    pub write_allowed_ips: Option<Vec<IpNetwork>>,
+    /// Authentication of the callers, disabled when `None`.
+    pub auth: Option<Arc<RpcAuth>>,
+    /// Time budget of the calls.
+    pub timeouts: CallTimeouts,
+    /// Time the in-flight requests have to complete on shutdown.
+    pub drain_timeout: Duration,
+    /// Calls which take longer are logged.
+    pub slow_log_threshold: Option<Duration>,
+    /// Path of a unix socket to serve the given methods on, in addition to the TCP address.
+    pub unix_socket: Option<(PathBuf, Vec<(RpcVersion, RpcModule<()>)>)>,
+    /// Readiness and liveness probes.
+    pub health: HealthChecks,
+    /// Hooks called around every call, after the authentication and rate limits.
+    pub hooks: RpcHooks,
+    /// Provider the read calls the node cannot answer are proxied to.
+    pub fallback_provider: Option<Url>,
+    /// Compress the HTTP responses with gzip or brotli, when the caller accepts it.
+    pub compression: bool,
 }
 
 #[derive(Debug, Clone)]
 struct PerConnection<RpcMiddleware, HttpMiddleware> {
-    methods: Methods,
+    methods: Arc<HashMap<RpcVersion, Methods>>,
+    /// OpenRPC document of each version, served at `OPENRPC_PATH` under the path of the version.
+    openrpc: Arc<HashMap<RpcVersion, String>>,
     stop_handle: StopHandle,
     metrics: RpcMetrics,
     service_builder: TowerServiceBuilder<RpcMiddleware, HttpMiddleware>,
@@ -67,31 +128,51 @@ pub async fn start_server(
     let ServerConfig {
         addr,
         batch_config,
+        batch_concurrency,
         cors,
         max_payload_in_mb,
         max_payload_out_mb,
         max_connections,
+        max_ws_connections,
+        ws_ping_interval,
+        ws_idle_timeout,
         max_subs_per_conn,
         metrics,
         message_buffer_capacity,
-        rpc_api,
+        rpc_apis,
         rate_limit,
+        rate_limit_trace,
+        rate_limit_write,
         rate_limit_whitelisted_ips,
         rate_limit_trust_proxy_headers,
+        write_allowed_ips,
+        auth,
+        timeouts,
+        drain_timeout,
+        slow_log_threshold,
+        unix_socket,
+        health,
+        hooks,
+        compression,
+        fallback_provider,
     } = config;
 
+    let rate_limits = RateLimits::new(rate_limit, rate_limit_trace, rate_limit_write);
+
     let std_listener = TcpListener::bind(addr)
         .await
         .and_then(|a| a.into_std())
         .with_context(|| format!("binding to address: {addr}"))?;
     let local_addr = std_listener.local_addr().ok();
-    let host_filter = host_filtering(cors.is_some(), local_addr);
 
-    let http_middleware = tower::ServiceBuilder::new()
-		.option_layer(host_filter)
-		// Proxy `GET /health` requests to internal `system_health` method.
-		// .layer(ProxyGetRequestLayer::new("/health", "system_health")?)
-		.layer(try_into_cors(cors.as_ref())?);
+    let cors_layer = try_into_cors(cors.as_ref())?;
+    let http_middleware = |host_filter: Option<HostFilterLayer>| {
+        tower::ServiceBuilder::new()
+            .option_layer(host_filter)
+            // Proxy `GET /health` requests to internal `system_health` method.
+            // .layer(ProxyGetRequestLayer::new("/health", "system_health")?)
+            .layer(cors_layer.clone())
+    };
 
     let builder = jsonrpsee::server::Server::builder()
         .max_request_body_size(max_payload_in_mb.saturating_mul(MEGABYTE))
@@ -100,98 +181,303 @@ pub async fn start_server(
         .max_subscriptions_per_connection(max_subs_per_conn)
         .enable_ws_ping(
             PingConfig::new()
-                .ping_interval(Duration::from_secs(30))
-                .inactive_limit(Duration::from_secs(60))
-                .max_failures(3),
+                .ping_interval(ws_ping_interval)
+                .inactive_limit(ws_idle_timeout)
+                .max_failures(WS_MAX_INACTIVE_PERIODS),
         )
-        .set_http_middleware(http_middleware)
         .set_message_buffer_capacity(message_buffer_capacity)
         .set_batch_request_config(batch_config)
         .set_id_provider(RandomStringIdProvider::new(16));
 
     let (stop_handle, server_handle) = stop_channel();
+    let build_methods = |rpc_apis: Vec<(RpcVersion, RpcModule<()>)>| {
+        let mut methods = HashMap::new();
+        let mut openrpc = HashMap::new();
+        for (version, rpc_api) in rpc_apis {
+            let (rpc_api, document) = build_rpc_api(version, rpc_api);
+            methods.insert(version, Methods::from(rpc_api));
+            openrpc.insert(version, document.to_string());
+        }
+        (Arc::new(methods), Arc::new(openrpc))
+    };
+    let (methods, openrpc) = build_methods(rpc_apis);
+    let unix_socket = unix_socket.map(|(path, rpc_apis)| (path, build_methods(rpc_apis)));
+
+    let known_methods = methods.values().chain(unix_socket.iter().flat_map(|(_, (methods, _))| methods.values()));
+    let metrics = metrics.with_known_methods(known_methods.flat_map(Methods::method_names));
+
     let cfg = PerConnection {
-        methods: build_rpc_api(rpc_api).into(),
-        service_builder: builder.to_service_builder(),
-        metrics,
+        metrics: metrics.clone(),
+        methods,
+        openrpc,
+        service_builder: builder
+            .clone()
+            .set_http_middleware(http_middleware(host_filtering(cors.is_some(), local_addr)))
+            .to_service_builder(),
         stop_handle: stop_handle.clone(),
     };
 
-    let make_service = make_service_fn(move |addr: &AddrStream| {
-        let cfg = cfg.clone();
-        let rate_limit_whitelisted_ips = rate_limit_whitelisted_ips.clone();
-        let ip = addr.remote_addr().ip();
+    if rate_limits.is_enabled() {
+        let rate_limits = rate_limits.clone();
+        join_set.spawn(async move {
+            let mut interval = tokio::time::interval(RATE_LIMIT_RETAIN_INTERVAL);
+            while wait_or_graceful_shutdown(interval.tick()).await.is_some() {
+                rate_limits.retain_recent();
+            }
+            Ok(())
+        });
+    }
+
+    if let Some(auth) = &auth {
+        auth.reload_on_sighup(join_set)?;
+    }
+
+    let batches = BatchConfig {
+        batch_config,
+        max_request_size: max_payload_in_mb.saturating_mul(MEGABYTE),
+        max_response_size: max_payload_out_mb.saturating_mul(MEGABYTE),
+        permits: Arc::new(Semaphore::new(batch_concurrency as usize)),
+    };
+
+    // Negotiated through `Accept-Encoding`. The websocket upgrade responses are empty, they are never compressed
+    let compression = {
+        let layer = CompressionLayer::new().compress_when(SizeAbove::new(COMPRESSION_MIN_SIZE));
+        if compression {
+            layer
+        } else {
+            layer.no_gzip().no_br()
+        }
+    };
 
-        async move {
-            let cfg = cfg.clone();
-            let rate_limit_whitelisted_ips = rate_limit_whitelisted_ips.clone();
+    let fallback = fallback_provider
+        .map(|url| Arc::new(FallbackProvider::new(url, max_payload_out_mb.saturating_mul(MEGABYTE) as usize)));
 
-            Ok::<_, Infallible>(service_fn(move |req| {
+    // Every open websocket connection holds a permit until it is closed
+    let ws_connections = max_ws_connections.map(|max| Arc::new(Semaphore::new(max as usize)));
+
+    // `ip` is `None` for the connections on the unix socket, which are neither authenticated nor rate limited
+    // The service of a connection is dropped with the connection, and `connection` along with it
+    let connection_service = move |cfg: PerConnection<_, _>, ip: Option<IpAddr>, connection: ConnectionGuard| {
+        let auth = auth.clone();
+        let rate_limits = rate_limits.clone();
+        let rate_limit_whitelisted_ips = rate_limit_whitelisted_ips.clone();
+        let write_allowed_ips = write_allowed_ips.clone();
+        let health = health.clone();
+        let ws_connections = ws_connections.clone();
+        let batches = batches.clone();
+        let hooks = hooks.clone();
+        let fallback = fallback.clone();
+
+        compression.layer(service_fn(move |req| {
+            let _connection = &connection;
+            // `None` when authentication is enabled and the caller is not authenticated
+            let allowed_methods = match (&auth, ip) {
+                (Some(auth), Some(_)) => auth.authenticate(req.headers()).map(Some),
+                _ => Some(None),
+            };
+
+            let rate_limit_cfg = ip.and_then(|ip| {
                 let proxy_ip = if rate_limit_trust_proxy_headers { get_proxy_ip(&req) } else { None };
 
-                let rate_limit_cfg = if rate_limit_whitelisted_ips
-                    .iter()
-                    .any(|ips| ips.contains(proxy_ip.unwrap_or(ip)))
-                {
+                let caller_ip = proxy_ip.unwrap_or(ip);
+                if rate_limit_whitelisted_ips.iter().any(|ips| ips.contains(caller_ip)) {
                     log::debug!(target: "rpc", "ip={ip}, proxy_ip={:?} is trusted, disabling rate-limit", proxy_ip);
                     None
                 } else {
                     if !rate_limit_whitelisted_ips.is_empty() {
                         log::debug!(target: "rpc", "ip={ip}, proxy_ip={:?} is not trusted, rate-limit enabled", proxy_ip);
                     }
-                    rate_limit
-                };
-
-                let PerConnection { service_builder, metrics, stop_handle, methods } = cfg.clone();
-
-                let is_websocket = ws::is_upgrade_request(&req);
-                let transport_label = if is_websocket { "ws" } else { "http" };
+                    Some((rate_limits.clone(), caller_ip)).filter(|(rate_limits, _)| rate_limits.is_enabled())
+                }
+            });
+
+            // The callers on the unix socket are local, they are always allowed
+            let write_denied = match (&write_allowed_ips, ip) {
+                (Some(write_allowed_ips), Some(ip)) => {
+                    let proxy_ip = if rate_limit_trust_proxy_headers { get_proxy_ip(&req) } else { None };
+                    let caller_ip = proxy_ip.unwrap_or(ip);
+                    !write_allowed_ips.iter().any(|ips| ips.contains(caller_ip))
+                }
+                _ => false,
+            };
+
+            let PerConnection { service_builder, metrics, stop_handle, methods, openrpc } = cfg.clone();
+
+            // The OpenRPC document of a version is served under its path
+            let openrpc_version = (req.method() == Method::GET)
+                .then(|| req.uri().path().strip_suffix(OPENRPC_PATH))
+                .flatten()
+                .and_then(RpcVersion::from_path);
+            let version = RpcVersion::from_path(req.uri().path()).or(openrpc_version);
+            let methods = version.and_then(|version| methods.get(&version).cloned()).unwrap_or_default();
+            let openrpc = openrpc_version.and_then(|version| openrpc.get(&version).cloned());
+
+            let is_websocket = ws::is_upgrade_request(&req);
+            let transport_label = match (ip, is_websocket) {
+                (None, _) => "ipc",
+                (Some(_), true) => "ws",
+                (Some(_), false) => "http",
+            };
+
+            let request_id =
+                req.headers().get(X_REQUEST_ID).and_then(|id| id.to_str().ok()).and_then(request_id::parse);
+            // The id given by the caller is sent back on the http responses
+            let response_request_id = request_id.as_deref().and_then(|id| HeaderValue::from_str(id).ok());
+            let middleware_layer = MiddlewareLayer::new()
+                .with_metrics(Metrics::new(metrics, transport_label))
+                .with_timeouts(timeouts)
+                .with_slow_log(slow_log_threshold)
+                .with_request_id(request_id)
+                .with_write_denied(write_denied)
+                .with_fallback(fallback.clone());
+            let middleware_layer = match rate_limit_cfg {
+                None => middleware_layer,
+                Some((rate_limits, caller_ip)) => middleware_layer.with_rate_limits(rate_limits, caller_ip),
+            };
+
+            let middleware_layer = match allowed_methods.clone().flatten() {
+                Some(allowed_methods) => middleware_layer.with_allowed_methods(allowed_methods),
+                None => middleware_layer,
+            };
+
+            let rpc_middleware =
+                RpcServiceBuilder::new().layer(middleware_layer.clone()).layer(RpcHooksLayer::new(hooks.clone()));
+
+            let mut svc = service_builder.set_rpc_middleware(rpc_middleware).build(methods, stop_handle);
+
+            let health = health.clone();
+            let batches = batches.clone();
+            // `Err` when the websocket connection limit is reached
+            let ws_permit = match &ws_connections {
+                Some(ws_connections) if is_websocket => Arc::clone(ws_connections).try_acquire_owned().map(Some),
+                _ => Ok(None),
+            };
+
+            async move {
+                if req.uri().path() == "/health" {
+                    Ok(Response::builder().status(StatusCode::OK).body(Body::from("OK"))?)
+                } else if req.uri().path() == LIVENESS_PATH {
+                    Ok(health.live()?)
+                } else if req.uri().path() == READINESS_PATH {
+                    Ok(health.ready()?)
+                } else if version.is_none() {
+                    let supported = RpcVersion::ALL.iter().map(RpcVersion::path).collect::<Vec<_>>().join(", ");
+                    Ok(Response::builder()
+                        .status(StatusCode::NOT_FOUND)
+                        .body(Body::from(format!("Unsupported RPC version, supported paths are: {supported}")))?)
+                } else if allowed_methods.is_none() {
+                    Ok(Response::builder().status(StatusCode::UNAUTHORIZED).body(Body::from("Unauthorized"))?)
+                } else if let Some(openrpc) = openrpc {
+                    Ok(Response::builder()
+                        .status(StatusCode::OK)
+                        .header(CONTENT_TYPE, "application/json")
+                        .body(Body::from(openrpc))?)
+                } else if ws_permit.is_err() {
+                    middleware_layer.ws_rejected();
+                    Ok(Response::builder()
+                        .status(StatusCode::SERVICE_UNAVAILABLE)
+                        .body(Body::from("Too many websocket connections"))?)
+                } else if is_websocket {
+                    let on_disconnect = svc.on_session_closed();
+
+                    // Spawn a task to handle when the connection is closed.
+                    tokio::spawn(async move {
+                        let now = std::time::Instant::now();
+                        middleware_layer.ws_connect();
+                        on_disconnect.await;
+                        middleware_layer.ws_disconnect(now);
+                        drop(ws_permit);
+                    });
+
+                    svc.call(req).await
+                } else {
+                    let mut response = batch::call(svc, req, batches).await?;
+                    if let Some(request_id) = response_request_id {
+                        response.headers_mut().insert(X_REQUEST_ID, request_id);
+                    }
+                    Ok(response)
+                }
+            }
+        }))
+    };
 
-                let middleware_layer = match rate_limit_cfg {
-                    None => MiddlewareLayer::new().with_metrics(Metrics::new(metrics, transport_label)),
-                    Some(rate_limit) => MiddlewareLayer::new()
-                        .with_metrics(Metrics::new(metrics, transport_label))
-                        .with_rate_limit_per_minute(rate_limit),
+    if let Some((path, (methods, openrpc))) = unix_socket {
+        let cfg = PerConnection {
+            metrics,
+            methods,
+            openrpc,
+            // the clients of the socket are local, there is no host to filter
+            service_builder: builder.set_http_middleware(http_middleware(None)).to_service_builder(),
+            stop_handle: stop_handle.clone(),
+        };
+        let listener = bind_unix_socket(&path)?;
+        let connection_service = connection_service.clone();
+
+        join_set.spawn(async move {
+            while let Some(res) = wait_or_graceful_shutdown(listener.accept()).await {
+                let stream = match res {
+                    Ok((stream, _)) => stream,
+                    Err(err) => {
+                        log::warn!("Failed to accept a connection on the RPC unix socket: {err:#}");
+                        continue;
+                    }
                 };
+                let connection = cfg.metrics.connection_opened("ipc");
+                let service = connection_service(cfg.clone(), None, connection);
+                tokio::spawn(async move {
+                    if let Err(err) =
+                        hyper::server::conn::Http::new().serve_connection(stream, service).with_upgrades().await
+                    {
+                        log::debug!(target: "rpc", "RPC unix socket connection error: {err:#}");
+                    }
+                });
+            }
+            std::fs::remove_file(&path).with_context(|| format!("Removing the RPC unix socket at {}", path.display()))
+        });
 
-                let rpc_middleware = RpcServiceBuilder::new().layer(middleware_layer.clone());
-
-                let mut svc = service_builder.set_rpc_middleware(rpc_middleware).build(methods, stop_handle);
-
-                async move {
-                    if req.uri().path() == "/health" {
-                        Ok(Response::builder().status(StatusCode::OK).body(Body::from("OK"))?)
-                    } else {
-                        if is_websocket {
-                            let on_disconnect = svc.on_session_closed();
-
-                            // Spawn a task to handle when the connection is closed.
-                            tokio::spawn(async move {
-                                let now = std::time::Instant::now();
-                                middleware_layer.ws_connect();
-                                on_disconnect.await;
-                                middleware_layer.ws_disconnect(now);
-                            });
-                        }
+        log::info!("📱 Running JSON-RPC server at unix socket {}", path.display());
+    }
 
-                        svc.call(req).await
-                    }
-                }
-            }))
-        }
+    let make_service = make_service_fn(move |addr: &AddrStream| {
+        let connection = cfg.metrics.connection_opened("http");
+        let service = connection_service(cfg.clone(), Some(addr.remote_addr().ip()), connection);
+        async move { Ok::<_, Infallible>(service) }
     });
 
     let server = hyper::Server::from_tcp(std_listener)
         .with_context(|| format!("creating hyper server at: {addr}"))?
         .serve(make_service);
 
+    // The websocket connections are only closed by the server handle. The subscriptions are given time to notify
+    // their subscribers that they are closed, before the connections close
+    let ws_stop_handle = server_handle.clone();
     join_set.spawn(async move {
-        server
-            .with_graceful_shutdown(async {
-                wait_or_graceful_shutdown(stop_handle.shutdown()).await;
-            })
-            .await
-            .context("running rpc server")
+        graceful_shutdown().await;
+        tokio::time::sleep(WS_CLOSE_DELAY).await;
+        // it was already stopped if the handle was dropped
+        let _ = ws_stop_handle.stop();
+        Ok(())
+    });
+
+    join_set.spawn(async move {
+        // Stops accepting connections on shutdown, and then waits for the in-flight requests of the open ones
+        let server = server.with_graceful_shutdown(async {
+            wait_or_graceful_shutdown(stop_handle.shutdown()).await;
+        });
+        tokio::pin!(server);
+        tokio::select! {
+            res = &mut server => return res.context("running rpc server"),
+            _ = graceful_shutdown() => {}
+        }
+
+        log::info!("📱 Draining the in-flight RPC requests");
+        match tokio::time::timeout(drain_timeout, server).await {
+            Ok(res) => res.context("running rpc server"),
+            Err(_) => {
+                log::warn!("Closing the RPC connections still open after {}s", drain_timeout.as_secs());
+                Ok(())
+            }
+        }
     });
 
     log::info!(
@@ -203,9 +489,22 @@ pub async fn start_server(
     Ok(server_handle)
 }
 
+/// Binds a unix socket, replacing the socket left over by a previous run if any.
+fn bind_unix_socket(path: &Path) -> anyhow::Result<UnixListener> {
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => std::fs::remove_file(path)
+            .with_context(|| format!("Removing the stale RPC unix socket at {}", path.display()))?,
+        Ok(_) => anyhow::bail!("Cannot create the RPC unix socket at {}: the file already exists", path.display()),
+        Err(_) => {}
+    }
+    UnixListener::bind(path).with_context(|| format!("Binding the RPC unix socket at {}", path.display()))
+}
+
 const X_FORWARDED_FOR: HeaderName = HeaderName::from_static("x-forwarded-for");
 const X_REAL_IP: HeaderName = HeaderName::from_static("x-real-ip");
 const FORWARDED: HeaderName = HeaderName::from_static("forwarded");
+/// Header giving the id of a request, used in the logs instead of generated ids and sent back on the response.
+const X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
 
 pub(crate) fn host_filtering(enabled: bool, addr: Option<SocketAddr>) -> Option<HostFilterLayer> {
     // If the local_addr failed, fallback to wildcard.
@@ -220,12 +519,19 @@ pub(crate) fn host_filtering(enabled: bool, addr: Option<SocketAddr>) -> Option<
     }
 }
 
-pub(crate) fn build_rpc_api<M: Send + Sync + 'static>(mut rpc_api: RpcModule<M>) -> RpcModule<M> {
+/// Registers the `rpc_methods` and `rpc.discover` methods, returning the module along with its OpenRPC document.
+pub(crate) fn build_rpc_api<M: Send + Sync + 'static>(
+    version: RpcVersion,
+    mut rpc_api: RpcModule<M>,
+) -> (RpcModule<M>, serde_json::Value) {
     let mut available_methods = rpc_api.method_names().collect::<Vec<_>>();
-    // The "rpc_methods" is defined below and we want it to be part of the reported methods.
+    // The "rpc_methods" and "rpc.discover" are defined below and we want them to be part of the reported methods.
     available_methods.push("rpc_methods");
+    available_methods.push(DISCOVER_METHOD);
     available_methods.sort();
 
+    let document = discover_document(version, available_methods.iter().copied());
+
     rpc_api
         .register_method("rpc_methods", move |_, _| {
             serde_json::json!({
@@ -233,23 +539,43 @@ pub(crate) fn build_rpc_api<M: Send + Sync + 'static>(mut rpc_api: RpcModule<M>)
             })
         })
         .unwrap();
+    let discover = document.clone();
+    rpc_api.register_method(DISCOVER_METHOD, move |_, _| discover.clone()).unwrap();
 
-    rpc_api
+    (rpc_api, document)
 }
 
 pub(crate) fn try_into_cors(maybe_cors: Option<&Vec<String>>) -> anyhow::Result<CorsLayer> {
     if let Some(cors) = maybe_cors {
-        let mut list = Vec::new();
         for origin in cors {
-            list.push(HeaderValue::from_str(origin)?);
+            HeaderValue::from_str(origin).with_context(|| format!("Invalid CORS origin: {origin}"))?;
         }
-        Ok(CorsLayer::new().allow_origin(AllowOrigin::list(list)))
+        let cors = cors.clone();
+        Ok(CorsLayer::new()
+            .allow_origin(AllowOrigin::predicate(move |origin, _| {
+                cors.iter().any(|allowed| origin_matches(allowed, origin.as_bytes()))
+            }))
+            .allow_methods([Method::GET, Method::POST, Method::OPTIONS])
+            .allow_headers([CONTENT_TYPE, AUTHORIZATION, X_API_KEY.clone(), X_REQUEST_ID])
+            .expose_headers([X_REQUEST_ID]))
     } else {
         // allow all cors
         Ok(CorsLayer::permissive())
     }
 }
 
+/// Whether an origin is allowed by the `allowed` pattern, where a `:*` port matches any port or no port.
+fn origin_matches(allowed: &str, origin: &[u8]) -> bool {
+    match allowed.strip_suffix(":*") {
+        Some(base) => origin.strip_prefix(base.as_bytes()).is_some_and(|port| match port {
+            [] => true,
+            [b':', port @ ..] => !port.is_empty() && port.iter().all(u8::is_ascii_digit),
+            _ => false,
+        }),
+        None => origin == allowed.as_bytes(),
+    }
+}
+
 pub(crate) fn format_cors(maybe_cors: Option<&Vec<String>>) -> String {
     if let Some(cors) = maybe_cors {
         format!("{:?}", cors)
@@ -1,3 +1,4 @@
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -8,6 +9,7 @@ use dc_metrics::MetricsRegistry;
 use dc_sync::fetch::fetchers::FetchConfig;
 use dc_sync::metrics::block_metrics::BlockMetrics;
 use dc_telemetry::TelemetryHandle;
+use dp_utils::PauseHandle;
 use primitive_types::H160;
 use starknet_types_core::felt::Felt;
 use tokio::task::JoinSet;
@@ -21,7 +23,9 @@ pub struct SyncService {
     fetch_config: FetchConfig,
     backup_every_n_blocks: Option<u64>,
     l1_endpoint: Option<Url>,
+    l1_ws_endpoint: Option<Url>,
     l1_core_address: H160,
+    l1_messages_start_block: Option<u64>,
     starting_block: Option<u64>,
     block_metrics: BlockMetrics,
     db_metrics: DbMetrics,
@@ -29,6 +33,8 @@ pub struct SyncService {
     start_params: Option<TelemetryHandle>,
     disabled: bool,
     pending_block_poll_interval: Duration,
+    import_dump: Option<PathBuf>,
+    sync_pause: PauseHandle,
 }
 
 impl SyncService {
@@ -37,12 +43,13 @@ impl SyncService {
         db: &DatabaseService,
         metrics_handle: MetricsRegistry,
         telemetry: TelemetryHandle,
+        sync_pause: PauseHandle,
     ) -> anyhow::Result<Self> {
         let block_metrics = BlockMetrics::register(&metrics_handle)?;
         let db_metrics = DbMetrics::register(&metrics_handle)?;
         let fetch_config = config.block_fetch_config();
 
-        let l1_endpoint = if !config.sync_l1_disabled {
+        let l1_endpoint = if !config.sync_l1_disabled && config.import_dump.is_none() {
             if let Some(l1_rpc_url) = &config.l1_endpoint {
                 Some(l1_rpc_url.clone())
             } else {
@@ -57,8 +64,10 @@ impl SyncService {
         Ok(Self {
             db_backend: Arc::clone(db.backend()),
             fetch_config,
+            l1_ws_endpoint: l1_endpoint.as_ref().and(config.l1_ws_endpoint.clone()),
             l1_endpoint,
             l1_core_address: config.network.l1_core_address(),
+            l1_messages_start_block: config.l1_messages_start_block,
             starting_block: config.starting_block,
             backup_every_n_blocks: config.backup_every_n_blocks,
             block_metrics,
@@ -67,6 +76,8 @@ impl SyncService {
             start_params: Some(telemetry),
             disabled: config.sync_disabled,
             pending_block_poll_interval: Duration::from_secs(config.pending_block_poll_interval),
+            import_dump: config.import_dump.clone(),
+            sync_pause,
         })
     }
     pub async fn start(&mut self, join_set: &mut JoinSet<anyhow::Result<()>>) -> anyhow::Result<()> {
@@ -77,23 +88,50 @@ impl SyncService {
             fetch_config,
             backup_every_n_blocks,
             l1_endpoint,
+            l1_ws_endpoint,
             l1_core_address,
+            l1_messages_start_block,
             starting_block,
             block_metrics,
             db_metrics,
             chain_id,
             pending_block_poll_interval,
+            import_dump,
+            sync_pause,
             ..
         } = self.clone();
         let telemetry = self.start_params.take().context("service already started")?;
 
         let db_backend = Arc::clone(&self.db_backend);
+
+        if let Some(import_dump) = import_dump {
+            join_set.spawn(async move {
+                dc_sync::dump::import_dump(
+                    &db_backend,
+                    import_dump,
+                    fetch_config.verify,
+                    backup_every_n_blocks,
+                    block_metrics,
+                    db_metrics,
+                    chain_id,
+                    telemetry,
+                    sync_pause,
+                    fetch_config.max_blocks_per_second,
+                    fetch_config.block_hash_check,
+                )
+                .await
+            });
+            return Ok(());
+        }
+
         join_set.spawn(async move {
             dc_sync::starknet_sync_worker::sync(
                 &db_backend,
                 fetch_config,
                 l1_endpoint,
+                l1_ws_endpoint,
                 l1_core_address,
+                l1_messages_start_block,
                 starting_block,
                 backup_every_n_blocks,
                 block_metrics,
@@ -101,6 +139,7 @@ impl SyncService {
                 chain_id,
                 telemetry,
                 pending_block_poll_interval,
+                sync_pause,
             )
             .await
         });
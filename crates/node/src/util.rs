@@ -42,6 +42,8 @@ pub fn setup_logging() -> anyhow::Result<()> {
             let ts = Local::now().format("%Y-%m-%d %H:%M:%S");
             let style = fmt.default_level_style(record.level());
             let brackets = Style::new().fg_color(Some(Color::Ansi(AnsiColor::BrightBlack)));
+            // The id of the RPC request being served, if any
+            let request_id = dp_utils::request_id::current().map(|id| format!(" {id}")).unwrap_or_default();
 
             match record.level() {
                 Level::Info if record.target() == "rpc_calls" => {
@@ -70,13 +72,13 @@ pub fn setup_logging() -> anyhow::Result<()> {
 
                     writeln!(
                         fmt,
-                        "{brackets}[{brackets:#}{ts} {rpc_style}HTTP{rpc_style:#}{brackets}]{brackets:#} 🌐 {method} {status_color}{status}{status_color:#} {res_len} bytes - {time_color}{response_time:?}{time_color:#}",
+                        "{brackets}[{brackets:#}{ts} {rpc_style}HTTP{rpc_style:#}{request_id}{brackets}]{brackets:#} 🌐 {method} {status_color}{status}{status_color:#} {res_len} bytes - {time_color}{response_time:?}{time_color:#}",
                     )
                 }
                 Level::Info => {
                     writeln!(
                         fmt,
-                        "{brackets}[{brackets:#}{} {style}{}{style:#}{brackets}]{brackets:#} {}",
+                        "{brackets}[{brackets:#}{} {style}{}{style:#}{request_id}{brackets}]{brackets:#} {}",
                         ts,
                         record.level(),
                         record.args()
@@ -85,7 +87,7 @@ pub fn setup_logging() -> anyhow::Result<()> {
                 Level::Warn => {
                     writeln!(
                         fmt,
-                        "{brackets}[{brackets:#}{} {style}{}{style:#}{brackets}]{brackets:#} ⚠️ {}",
+                        "{brackets}[{brackets:#}{} {style}{}{style:#}{request_id}{brackets}]{brackets:#} ⚠️ {}",
                         ts,
                         record.level(),
                         record.args()
@@ -94,7 +96,7 @@ pub fn setup_logging() -> anyhow::Result<()> {
                 Level::Error if record.target() == "rpc_errors" => {
                     writeln!(
                         fmt,
-                        "{brackets}[{brackets:#}{} {style}{}{style:#}{brackets}]{brackets:#} ❗ {}",
+                        "{brackets}[{brackets:#}{} {style}{}{style:#}{request_id}{brackets}]{brackets:#} ❗ {}",
                         ts,
                         record.level(),
                         record.args()
@@ -103,7 +105,7 @@ pub fn setup_logging() -> anyhow::Result<()> {
                 Level::Error => {
                     writeln!(
                         fmt,
-                        "{brackets}[{brackets:#}{} {style}{}{style:#} {}{brackets}]{brackets:#} ❗ {}",
+                        "{brackets}[{brackets:#}{} {style}{}{style:#} {}{request_id}{brackets}]{brackets:#} ❗ {}",
                         ts,
                         record.level(),
                         record.target(),
@@ -113,7 +115,7 @@ pub fn setup_logging() -> anyhow::Result<()> {
                 _ => {
                     writeln!(
                         fmt,
-                        "{brackets}[{brackets:#}{} {style}{}{style:#} {}{brackets}]{brackets:#} {}",
+                        "{brackets}[{brackets:#}{} {style}{}{style:#} {}{request_id}{brackets}]{brackets:#} {}",
                         ts,
                         record.level(),
                         record.target(),
@@ -1,10 +1,10 @@
-use dp_block::StarknetVersion;
 use dp_receipt::Event;
 use rayon::prelude::*;
 use starknet_types_core::felt::Felt;
 use starknet_types_core::hash::{Pedersen, Poseidon};
 
 use super::compute_root;
+use crate::StarknetVersion;
 
 /// Calculate the event commitment in memory using HashMapDb (which is more efficient for this
 /// usecase).
@@ -16,15 +16,15 @@ use super::compute_root;
 /// # Returns
 ///
 /// The event commitment as `Felt`.
-pub fn memory_event_commitment(events_with_tx_hash: &[(Felt, Event)], starknet_version: StarknetVersion) -> Felt {
-    if starknet_version < StarknetVersion::STARKNET_VERSION_0_13_2 {
-        memory_event_commitment_pedersen(events_with_tx_hash)
+pub fn event_commitment(events_with_tx_hash: &[(Felt, Event)], starknet_version: StarknetVersion) -> Felt {
+    if !starknet_version.poseidon_commitments() {
+        event_commitment_pedersen(events_with_tx_hash)
     } else {
-        memory_event_commitment_poseidon(events_with_tx_hash)
+        event_commitment_poseidon(events_with_tx_hash)
     }
 }
 
-fn memory_event_commitment_pedersen(events_with_tx_hash: &[(Felt, Event)]) -> Felt {
+fn event_commitment_pedersen(events_with_tx_hash: &[(Felt, Event)]) -> Felt {
     if events_with_tx_hash.is_empty() {
         return Felt::ZERO;
     }
@@ -37,7 +37,7 @@ fn memory_event_commitment_pedersen(events_with_tx_hash: &[(Felt, Event)]) -> Fe
     compute_root::<Pedersen>(&events_hash)
 }
 
-fn memory_event_commitment_poseidon(events_with_tx_hash: &[(Felt, Event)]) -> Felt {
+fn event_commitment_poseidon(events_with_tx_hash: &[(Felt, Event)]) -> Felt {
     if events_with_tx_hash.is_empty() {
         return Felt::ZERO;
     }
@@ -0,0 +1,12 @@
+use dp_receipt::TransactionReceipt;
+use rayon::prelude::*;
+use starknet_types_core::felt::Felt;
+use starknet_types_core::hash::Poseidon;
+
+use super::compute_root;
+
+/// The receipt commitment, part of the block hash starting from Starknet 0.13.2.
+pub fn receipt_commitment(receipts: &[TransactionReceipt]) -> Felt {
+    let receipts_hash = receipts.par_iter().map(TransactionReceipt::compute_hash).collect::<Vec<_>>();
+    compute_root::<Poseidon>(&receipts_hash)
+}
@@ -1,4 +1,3 @@
-use dp_block::StarknetVersion;
 use dp_transactions::Transaction;
 use dp_transactions::LEGACY_BLOCK_NUMBER;
 use dp_transactions::MAIN_CHAIN_ID;
@@ -9,6 +8,7 @@ use starknet_types_core::hash::Poseidon;
 use starknet_types_core::hash::{Pedersen, StarkHash};
 
 use super::compute_root;
+use crate::StarknetVersion;
 
 /// Compute the combined hash of the transaction hash and the signature.
 ///
@@ -23,13 +23,13 @@ use super::compute_root;
 /// # Returns
 ///
 /// The transaction hash with signature.
-pub fn calculate_transaction_leaf_with_hash(
+pub fn transaction_leaf_with_hash(
     transaction: &Transaction,
     chain_id: Felt,
     starknet_version: StarknetVersion,
     block_number: u64,
 ) -> (Felt, Felt) {
-    let include_signature = starknet_version >= StarknetVersion::STARKNET_VERSION_0_11_1;
+    let include_signature = starknet_version.commits_all_signatures();
     let legacy = block_number < LEGACY_BLOCK_NUMBER && chain_id == MAIN_CHAIN_ID;
     let is_pre_v0_7 = block_number < V0_7_BLOCK_NUMBER && chain_id == MAIN_CHAIN_ID;
 
@@ -42,7 +42,7 @@ pub fn calculate_transaction_leaf_with_hash(
     let leaf = match transaction {
         Transaction::Invoke(tx) => {
             // Include signatures for Invoke transactions or for all transactions
-            if starknet_version < StarknetVersion::STARKNET_VERSION_0_13_2 {
+            if !starknet_version.poseidon_commitments() {
                 let signature_hash = tx.compute_hash_signature::<Pedersen>();
                 Pedersen::hash(&tx_hash, &signature_hash)
             } else {
@@ -52,7 +52,7 @@ pub fn calculate_transaction_leaf_with_hash(
         }
         Transaction::Declare(tx) => {
             if include_signature {
-                if starknet_version < StarknetVersion::STARKNET_VERSION_0_13_2 {
+                if !starknet_version.poseidon_commitments() {
                     let signature_hash = tx.compute_hash_signature::<Pedersen>();
                     Pedersen::hash(&tx_hash, &signature_hash)
                 } else {
@@ -66,7 +66,7 @@ pub fn calculate_transaction_leaf_with_hash(
         }
         Transaction::DeployAccount(tx) => {
             if include_signature {
-                if starknet_version < StarknetVersion::STARKNET_VERSION_0_13_2 {
+                if !starknet_version.poseidon_commitments() {
                     let signature_hash = tx.compute_hash_signature::<Pedersen>();
                     Pedersen::hash(&tx_hash, &signature_hash)
                 } else {
@@ -79,7 +79,7 @@ pub fn calculate_transaction_leaf_with_hash(
             }
         }
         _ => {
-            if starknet_version < StarknetVersion::STARKNET_VERSION_0_13_2 {
+            if !starknet_version.poseidon_commitments() {
                 let signature_hash = Pedersen::hash_array(&[]);
                 Pedersen::hash(&tx_hash, &signature_hash)
             } else {
@@ -103,7 +103,7 @@ pub fn calculate_transaction_leaf_with_hash(
 /// # Returns
 ///
 /// The transaction commitment as `Felt`.
-pub fn memory_transaction_commitment(
+pub fn transaction_commitment(
     transactions: &[Transaction],
     chain_id: Felt,
     starknet_version: StarknetVersion,
@@ -114,11 +114,11 @@ pub fn memory_transaction_commitment(
     // transaction hashes are computed in parallel
     let (leafs, txs_hashes): (Vec<Felt>, Vec<Felt>) = transactions
         .par_iter()
-        .map(|tx| calculate_transaction_leaf_with_hash(tx, chain_id, starknet_version, block_number))
+        .map(|tx| transaction_leaf_with_hash(tx, chain_id, starknet_version, block_number))
         .unzip();
 
     // once transaction hashes have finished computing, they are inserted into the local Bonsai db
-    let root = if starknet_version < StarknetVersion::STARKNET_VERSION_0_13_2 {
+    let root = if !starknet_version.poseidon_commitments() {
         compute_root::<Pedersen>(&leafs)
     } else {
         compute_root::<Poseidon>(&leafs)
@@ -136,6 +136,7 @@ impl From<L1DataAvailabilityMode> for starknet_core::types::L1DataAvailabilityMo
 
 const BLOCKIFIER_VERSIONED_CONSTANTS_JSON_0_13_0: &[u8] = include_bytes!("../resources/versioned_constants_13_0.json");
 const BLOCKIFIER_VERSIONED_CONSTANTS_JSON_0_13_1: &[u8] = include_bytes!("../resources/versioned_constants_13_1.json");
+const BLOCKIFIER_VERSIONED_CONSTANTS_JSON_0_13_2: &[u8] = include_bytes!("../resources/versioned_constants_13_2.json");
 
 lazy_static::lazy_static! {
 pub static ref BLOCKIFIER_VERSIONED_CONSTANTS_0_13_0: VersionedConstants =
@@ -143,6 +144,9 @@ pub static ref BLOCKIFIER_VERSIONED_CONSTANTS_0_13_0: VersionedConstants =
 
 pub static ref BLOCKIFIER_VERSIONED_CONSTANTS_0_13_1: VersionedConstants =
     serde_json::from_slice(BLOCKIFIER_VERSIONED_CONSTANTS_JSON_0_13_1).unwrap();
+
+pub static ref BLOCKIFIER_VERSIONED_CONSTANTS_0_13_2: VersionedConstants =
+    serde_json::from_slice(BLOCKIFIER_VERSIONED_CONSTANTS_JSON_0_13_2).unwrap();
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -197,7 +201,7 @@ impl Header {
     pub fn compute_hash(&self, chain_id: Felt) -> Felt {
         if self.block_number < V0_7_BLOCK_NUMBER && chain_id == MAIN_CHAIN_ID {
             self.compute_hash_inner_pre_v0_7(chain_id)
-        } else if self.protocol_version < StarknetVersion::STARKNET_VERSION_0_13_2 {
+        } else if !self.protocol_version.poseidon_header_hash() {
             Pedersen::hash_array(&[
                 Felt::from(self.block_number),      // block number
                 self.global_state_root,             // global state root
@@ -1,3 +1,4 @@
+pub mod commitments;
 pub mod header;
 mod starknet_version;
 
@@ -74,6 +75,9 @@ impl From<DeoxysBlockInfo> for DeoxysMaybePendingBlockInfo {
 pub enum BlockTag {
     Latest,
     Pending,
+    /// The latest block accepted on L1.
+    #[serde(rename = "l1_accepted")]
+    L1Accepted,
 }
 
 impl From<starknet_core::types::BlockTag> for BlockTag {
@@ -84,15 +88,23 @@ impl From<starknet_core::types::BlockTag> for BlockTag {
         }
     }
 }
-impl From<BlockTag> for starknet_core::types::BlockTag {
-    fn from(value: BlockTag) -> Self {
+impl TryFrom<BlockTag> for starknet_core::types::BlockTag {
+    type Error = UnsupportedBlockTag;
+
+    fn try_from(value: BlockTag) -> Result<Self, Self::Error> {
         match value {
-            BlockTag::Latest => starknet_core::types::BlockTag::Latest,
-            BlockTag::Pending => starknet_core::types::BlockTag::Pending,
+            BlockTag::Latest => Ok(starknet_core::types::BlockTag::Latest),
+            BlockTag::Pending => Ok(starknet_core::types::BlockTag::Pending),
+            BlockTag::L1Accepted => Err(UnsupportedBlockTag(value)),
         }
     }
 }
 
+/// The block tag has no equivalent in `starknet_core`, and has to be resolved to a block number first.
+#[derive(thiserror::Error, Debug, PartialEq)]
+#[error("Unsupported block tag {0:?}")]
+pub struct UnsupportedBlockTag(pub BlockTag);
+
 /// Block Id
 /// Block hash, number or tag
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -111,13 +123,35 @@ impl From<starknet_core::types::BlockId> for BlockId {
         }
     }
 }
-impl From<BlockId> for starknet_core::types::BlockId {
-    fn from(value: BlockId) -> Self {
+impl TryFrom<BlockId> for starknet_core::types::BlockId {
+    type Error = UnsupportedBlockTag;
+
+    fn try_from(value: BlockId) -> Result<Self, Self::Error> {
         match value {
-            BlockId::Hash(felt) => starknet_core::types::BlockId::Hash(felt),
-            BlockId::Number(number) => starknet_core::types::BlockId::Number(number),
-            BlockId::Tag(tag) => starknet_core::types::BlockId::Tag(tag.into()),
+            BlockId::Hash(felt) => Ok(starknet_core::types::BlockId::Hash(felt)),
+            BlockId::Number(number) => Ok(starknet_core::types::BlockId::Number(number)),
+            BlockId::Tag(tag) => Ok(starknet_core::types::BlockId::Tag(tag.try_into()?)),
+        }
+    }
+}
+
+/// Same representation as the block ids of the JSON-RPC specification: a tag, `{"block_hash": ..}` or
+/// `{"block_number": ..}`.
+impl<'de> serde::Deserialize<'de> for BlockId {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum RawBlockId {
+            Tag(BlockTag),
+            Hash { block_hash: Felt },
+            Number { block_number: u64 },
         }
+
+        Ok(match RawBlockId::deserialize(deserializer)? {
+            RawBlockId::Tag(tag) => BlockId::Tag(tag),
+            RawBlockId::Hash { block_hash } => BlockId::Hash(block_hash),
+            RawBlockId::Number { block_number } => BlockId::Number(block_number),
+        })
     }
 }
 
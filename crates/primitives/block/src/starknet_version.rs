@@ -21,6 +21,27 @@ impl StarknetVersion {
     pub const STARKNET_VERSION_0_13_1: StarknetVersion = StarknetVersion([0, 13, 1, 0]);
     pub const STARKNET_VERSION_0_13_1_1: StarknetVersion = StarknetVersion([0, 13, 1, 1]);
     pub const STARKNET_VERSION_0_13_2: StarknetVersion = StarknetVersion([0, 13, 2, 0]);
+
+    /// The signatures of all the transactions are part of the transaction commitment, not only the ones of the
+    /// invoke transactions.
+    pub fn commits_all_signatures(&self) -> bool {
+        *self >= Self::STARKNET_VERSION_0_11_1
+    }
+
+    /// The transaction and event commitments are Poseidon tries, and their leaves Poseidon hashes.
+    pub fn poseidon_commitments(&self) -> bool {
+        *self >= Self::STARKNET_VERSION_0_13_2
+    }
+
+    /// The block hash is a Poseidon hash of the header, which includes the protocol version.
+    pub fn poseidon_header_hash(&self) -> bool {
+        *self >= Self::STARKNET_VERSION_0_13_2
+    }
+
+    /// The receipts are committed to in the block hash.
+    pub fn has_receipt_commitment(&self) -> bool {
+        *self >= Self::STARKNET_VERSION_0_13_2
+    }
 }
 
 impl std::fmt::Display for StarknetVersion {
@@ -108,4 +129,23 @@ mod tests {
         assert!(version_3 < version_4);
         assert!(version_4 < version_5);
     }
+
+    #[test]
+    fn test_starknet_version_features() {
+        let version = |s: &str| StarknetVersion::from_str(s).unwrap();
+
+        assert!(!version("0.11.0").commits_all_signatures());
+        assert!(version("0.11.1").commits_all_signatures());
+
+        for v in ["0.13.1", "0.13.1.1"] {
+            assert!(!version(v).poseidon_commitments());
+            assert!(!version(v).poseidon_header_hash());
+            assert!(!version(v).has_receipt_commitment());
+        }
+        for v in ["0.13.2", "0.13.2.1", "0.14.0"] {
+            assert!(version(v).poseidon_commitments());
+            assert!(version(v).poseidon_header_hash());
+            assert!(version(v).has_receipt_commitment());
+        }
+    }
 }
@@ -0,0 +1,224 @@
+//! Cache of the compiled classes, shared by the whole process.
+//!
+//! Compiling a Sierra class can take seconds: the sync and the execution of the broadcasted declare transactions
+//! compile their classes through [`compilation_cache`], so that a class is only compiled once. The compiled classes
+//! are kept in memory, up to a capacity, and can be persisted to a directory to be reused by the next runs of the node.
+//! A persisted class is stored along with its Keccak256 checksum, and a file which does not match its checksum is
+//! compiled and written again.
+
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use anyhow::Context;
+use lru::LruCache;
+use sha3::{Digest, Keccak256};
+use starknet_types_core::felt::Felt;
+
+use crate::{CompiledClass, CompiledLegacy, CompiledSierra, ToCompiledClass};
+
+/// Number of compiled classes kept in memory by default.
+pub const DEFAULT_COMPILATION_CACHE_CAPACITY: usize = 256;
+
+static COMPILATION_CACHE: OnceLock<CompilationCache> = OnceLock::new();
+
+/// The cache of the process. It keeps [`DEFAULT_COMPILATION_CACHE_CAPACITY`] classes in memory, unless another cache
+/// was set with [`init_compilation_cache`].
+pub fn compilation_cache() -> &'static CompilationCache {
+    COMPILATION_CACHE.get_or_init(|| CompilationCache::new(DEFAULT_COMPILATION_CACHE_CAPACITY))
+}
+
+/// Sets the cache of the process. This is meant to be called once at startup, before compiling any class: it fails
+/// when the cache is already set, returning `cache`.
+pub fn init_compilation_cache(cache: CompilationCache) -> Result<(), CompilationCache> {
+    COMPILATION_CACHE.set(cache)
+}
+
+/// Compiled classes by class hash.
+pub struct CompilationCache {
+    /// `None` when the classes are not kept in memory.
+    classes: Option<Mutex<LruCache<Felt, CompiledClass>>>,
+    /// Directory where the compiled classes are persisted.
+    dir: Option<PathBuf>,
+    /// Classes being compiled, the concurrent compilations of a class wait for the first one.
+    compiling: Mutex<HashMap<Felt, Arc<Mutex<()>>>>,
+}
+
+impl CompilationCache {
+    /// A cache keeping up to `capacity` compiled classes in memory. A capacity of zero keeps none.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            classes: NonZeroUsize::new(capacity).map(|capacity| Mutex::new(LruCache::new(capacity))),
+            dir: None,
+            compiling: Default::default(),
+        }
+    }
+
+    /// Persists the compiled classes to `dir`, one file per class. The classes found there are not compiled again,
+    /// unless their file is corrupted.
+    pub fn with_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.dir = Some(dir.into());
+        self
+    }
+
+    /// The compiled class of `class_hash`, compiling `class` unless it is in the cache. The hash is not checked
+    /// against the class.
+    pub fn get_or_compile(&self, class_hash: Felt, class: &impl ToCompiledClass) -> anyhow::Result<CompiledClass> {
+        if let Some(compiled_class) = self.get_in_memory(&class_hash) {
+            return Ok(compiled_class);
+        }
+
+        let lock = self.compiling.lock().expect("poisoned mutex").entry(class_hash).or_default().clone();
+        let result = {
+            let _guard = lock.lock().expect("poisoned mutex");
+            self.get_or_compile_locked(class_hash, class)
+        };
+        self.compiling.lock().expect("poisoned mutex").remove(&class_hash);
+        result
+    }
+
+    fn get_or_compile_locked(&self, class_hash: Felt, class: &impl ToCompiledClass) -> anyhow::Result<CompiledClass> {
+        // The class may have been compiled while waiting for the lock
+        if let Some(compiled_class) = self.get_in_memory(&class_hash) {
+            return Ok(compiled_class);
+        }
+
+        let persisted = match &self.dir {
+            Some(dir) => read_class(dir, &class_hash)
+                .with_context(|| format!("Reading the compiled class {class_hash:#x} from {}", dir.display()))?,
+            None => None,
+        };
+        let compiled_class = match persisted {
+            Some(compiled_class) => compiled_class,
+            None => {
+                let compiled_class = class.compile()?;
+                if let Some(dir) = &self.dir {
+                    write_class(dir, &class_hash, &compiled_class)
+                        .with_context(|| format!("Writing the compiled class {class_hash:#x} to {}", dir.display()))?;
+                }
+                compiled_class
+            }
+        };
+
+        if let Some(classes) = &self.classes {
+            classes.lock().expect("poisoned mutex").put(class_hash, compiled_class.clone());
+        }
+        Ok(compiled_class)
+    }
+
+    fn get_in_memory(&self, class_hash: &Felt) -> Option<CompiledClass> {
+        self.classes.as_ref()?.lock().expect("poisoned mutex").get(class_hash).cloned()
+    }
+}
+
+/// The file of a compiled class. The extension tells whether it is a Sierra or a legacy class.
+fn class_path(dir: &Path, class_hash: &Felt, sierra: bool) -> PathBuf {
+    let extension = if sierra { "casm" } else { "json" };
+    dir.join(format!("{class_hash:#x}.{extension}"))
+}
+
+/// Length of the checksum the files of the compiled classes start with.
+const CHECKSUM_LEN: usize = 32;
+
+/// The persisted class, `None` when there is none or when its file does not match its checksum.
+fn read_class(dir: &Path, class_hash: &Felt) -> std::io::Result<Option<CompiledClass>> {
+    for sierra in [true, false] {
+        let bytes = match std::fs::read(class_path(dir, class_hash, sierra)) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err),
+        };
+        if bytes.len() < CHECKSUM_LEN || bytes[..CHECKSUM_LEN] != *Keccak256::digest(&bytes[CHECKSUM_LEN..]) {
+            return Ok(None);
+        }
+        let bytes = bytes[CHECKSUM_LEN..].to_vec();
+        return Ok(Some(if sierra {
+            CompiledClass::Sierra(CompiledSierra(bytes))
+        } else {
+            CompiledClass::Legacy(CompiledLegacy(bytes))
+        }));
+    }
+    Ok(None)
+}
+
+/// The class is written after its checksum to a temporary file first, so that a class interrupted while being written
+/// is not read.
+fn write_class(dir: &Path, class_hash: &Felt, compiled_class: &CompiledClass) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let path = class_path(dir, class_hash, matches!(compiled_class, CompiledClass::Sierra(_)));
+    let tmp_path = path.with_extension("tmp");
+    let mut bytes = Vec::with_capacity(CHECKSUM_LEN + compiled_class.len());
+    bytes.extend_from_slice(&Keccak256::digest(&**compiled_class));
+    bytes.extend_from_slice(compiled_class);
+    std::fs::write(&tmp_path, bytes)?;
+    std::fs::rename(tmp_path, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    /// Counts its compilations.
+    #[derive(Default)]
+    struct CountingClass(AtomicUsize);
+
+    impl ToCompiledClass for CountingClass {
+        fn compile(&self) -> anyhow::Result<CompiledClass> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(CompiledClass::Sierra(CompiledSierra(b"{}".to_vec())))
+        }
+    }
+
+    #[test]
+    fn test_compiled_once() {
+        let cache = CompilationCache::new(8);
+        let class = CountingClass::default();
+
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| cache.get_or_compile(Felt::ONE, &class).unwrap());
+            }
+        });
+        assert_eq!(
+            cache.get_or_compile(Felt::ONE, &class).unwrap(),
+            CompiledClass::Sierra(CompiledSierra(b"{}".to_vec()))
+        );
+        assert_eq!(class.0.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_persisted() {
+        let dir = std::env::temp_dir().join(format!("dp-class-compile-cache-{}", std::process::id()));
+        let class = CountingClass::default();
+
+        let compiled_class = CompilationCache::new(0).with_dir(&dir).get_or_compile(Felt::TWO, &class).unwrap();
+        let persisted = CompilationCache::new(0).with_dir(&dir).get_or_compile(Felt::TWO, &class).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(persisted, compiled_class);
+        assert_eq!(class.0.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_persisted_corrupted() {
+        let dir = std::env::temp_dir().join(format!("dp-class-compile-cache-corrupted-{}", std::process::id()));
+        let class = CountingClass::default();
+
+        let compiled_class = CompilationCache::new(0).with_dir(&dir).get_or_compile(Felt::THREE, &class).unwrap();
+        let path = class_path(&dir, &Felt::THREE, true);
+        let mut bytes = std::fs::read(&path).unwrap();
+        *bytes.last_mut().unwrap() ^= 1;
+        std::fs::write(&path, bytes).unwrap();
+
+        let recompiled = CompilationCache::new(0).with_dir(&dir).get_or_compile(Felt::THREE, &class).unwrap();
+        let persisted = CompilationCache::new(0).with_dir(&dir).get_or_compile(Felt::THREE, &class).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(recompiled, compiled_class);
+        assert_eq!(persisted, compiled_class);
+        assert_eq!(class.0.load(Ordering::SeqCst), 2);
+    }
+}
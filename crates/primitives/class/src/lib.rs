@@ -4,10 +4,21 @@ use starknet_types_core::felt::Felt;
 
 mod class_hash;
 mod compile;
+mod compile_cache;
 mod into_starknet_core;
+mod sierra_abi;
 
 pub use class_hash::ClassHash;
 pub use compile::ToCompiledClass;
+pub use compile_cache::{
+    compilation_cache, init_compilation_cache, CompilationCache, DEFAULT_COMPILATION_CACHE_CAPACITY,
+};
+pub use sierra_abi::{
+    event_selector, selector_from_name, AbiError, SierraAbi, SierraAbiConstructor, SierraAbiEntry, SierraAbiEnum,
+    SierraAbiEvent, SierraAbiEventField, SierraAbiEventFieldKind, SierraAbiEventKind, SierraAbiFunction, SierraAbiImpl,
+    SierraAbiInterface, SierraAbiMember, SierraAbiOutput, SierraAbiStruct, SierraAbiTypedEvent, SierraAbiUntypedEvent,
+    SierraStateMutability,
+};
 
 #[derive(Debug)]
 pub struct ConvertedClass {
@@ -154,7 +165,7 @@ pub enum FunctionStateMutability {
 }
 
 /// A contract class that has been compiled and can be transformed into a `blockifier::execution::contract_class::ContractClass`.
-#[derive(Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum CompiledClass {
     Sierra(CompiledSierra),
     Legacy(CompiledLegacy),
@@ -171,10 +182,10 @@ impl Deref for CompiledClass {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct CompiledSierra(Vec<u8>);
 
-#[derive(Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct CompiledLegacy(Vec<u8>);
 
 pub fn to_blockifier_class(
@@ -0,0 +1,474 @@
+//! Typed ABI of the Sierra classes.
+//!
+//! The ABI of a Sierra class is a JSON string, see [`FlattenedSierraClass::parse_abi`]. It describes the functions of
+//! the class and the events it emits, along with the structs and enums they use: this is enough to check that a
+//! calldata is well formed for a function, and to find the event of an emitted event's first key.
+
+use starknet_core::utils::starknet_keccak;
+use starknet_types_core::felt::Felt;
+
+use crate::FlattenedSierraClass;
+
+/// Types nested deeper than this are rejected, the ABI of a class is not trusted.
+const MAX_TYPE_DEPTH: usize = 64;
+
+/// The name of the default entry points of the legacy classes, their selector is zero.
+const DEFAULT_ENTRY_POINT_NAMES: [&str; 2] = ["__default__", "__l1_default__"];
+
+#[derive(thiserror::Error, Debug)]
+pub enum AbiError {
+    #[error("Invalid ABI: {0}")]
+    Parse(#[from] serde_json::Error),
+    #[error("No function with selector {0:#x} in the ABI")]
+    UnknownSelector(Felt),
+    #[error("Unknown type `{0}`")]
+    UnknownType(String),
+    #[error("Type `{0}` is nested too deeply")]
+    TypeTooDeep(String),
+    #[error("Missing calldata for type `{0}`")]
+    MissingCalldata(String),
+    #[error("{0} unexpected calldata felts")]
+    UnexpectedCalldata(usize),
+    #[error("Invalid value {value:#x} for type `{ty}`")]
+    InvalidValue { ty: String, value: Felt },
+}
+
+/// The selector of an entry point, from its name.
+pub fn selector_from_name(name: &str) -> Felt {
+    if DEFAULT_ENTRY_POINT_NAMES.contains(&name) {
+        Felt::ZERO
+    } else {
+        starknet_keccak(name.as_bytes())
+    }
+}
+
+/// The first key of the events of type `name`. The path of the event is not part of the selector.
+pub fn event_selector(name: &str) -> Felt {
+    let name = name.rsplit("::").next().unwrap_or(name);
+    starknet_keccak(name.as_bytes())
+}
+
+impl FlattenedSierraClass {
+    pub fn parse_abi(&self) -> Result<SierraAbi, AbiError> {
+        self.abi.parse()
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct SierraAbi(pub Vec<SierraAbiEntry>);
+
+impl std::str::FromStr for SierraAbi {
+    type Err = AbiError;
+
+    /// An empty ABI is valid: some classes were declared without ABI.
+    fn from_str(abi: &str) -> Result<Self, Self::Err> {
+        match abi.trim() {
+            "" => Ok(Self::default()),
+            abi => Ok(serde_json::from_str(abi)?),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SierraAbiEntry {
+    Function(SierraAbiFunction),
+    Constructor(SierraAbiConstructor),
+    L1Handler(SierraAbiFunction),
+    Event(SierraAbiEvent),
+    Struct(SierraAbiStruct),
+    Enum(SierraAbiEnum),
+    Interface(SierraAbiInterface),
+    Impl(SierraAbiImpl),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SierraAbiFunction {
+    pub name: String,
+    pub inputs: Vec<SierraAbiMember>,
+    pub outputs: Vec<SierraAbiOutput>,
+    pub state_mutability: SierraStateMutability,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SierraAbiConstructor {
+    pub name: String,
+    pub inputs: Vec<SierraAbiMember>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SierraStateMutability {
+    External,
+    View,
+}
+
+/// A named member of a struct, an input of a function, or a variant of an enum.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SierraAbiMember {
+    pub name: String,
+    pub r#type: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SierraAbiOutput {
+    pub r#type: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SierraAbiStruct {
+    pub name: String,
+    pub members: Vec<SierraAbiMember>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SierraAbiEnum {
+    pub name: String,
+    pub variants: Vec<SierraAbiMember>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SierraAbiInterface {
+    pub name: String,
+    pub items: Vec<SierraAbiEntry>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SierraAbiImpl {
+    pub name: String,
+    pub interface_name: String,
+}
+
+/// The events of the classes compiled with Cairo 2 have a kind, the older ones only have inputs, which are all data.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+pub enum SierraAbiEvent {
+    Typed(SierraAbiTypedEvent),
+    Untyped(SierraAbiUntypedEvent),
+}
+
+impl SierraAbiEvent {
+    pub fn name(&self) -> &str {
+        match self {
+            SierraAbiEvent::Typed(event) => &event.name,
+            SierraAbiEvent::Untyped(event) => &event.name,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SierraAbiTypedEvent {
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: SierraAbiEventKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SierraAbiEventKind {
+    Struct { members: Vec<SierraAbiEventField> },
+    Enum { variants: Vec<SierraAbiEventField> },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SierraAbiEventField {
+    pub name: String,
+    pub r#type: String,
+    pub kind: SierraAbiEventFieldKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SierraAbiEventFieldKind {
+    Key,
+    Data,
+    Nested,
+    Flat,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SierraAbiUntypedEvent {
+    pub name: String,
+    pub inputs: Vec<SierraAbiMember>,
+}
+
+impl SierraAbi {
+    fn entries(&self) -> impl Iterator<Item = &SierraAbiEntry> {
+        self.0.iter().flat_map(|entry| match entry {
+            SierraAbiEntry::Interface(interface) => interface.items.iter().collect(),
+            entry => vec![entry],
+        })
+    }
+
+    /// The external and view functions, including the ones of the interfaces, and the L1 handlers.
+    pub fn functions(&self) -> impl Iterator<Item = &SierraAbiFunction> {
+        self.entries().filter_map(|entry| match entry {
+            SierraAbiEntry::Function(function) | SierraAbiEntry::L1Handler(function) => Some(function),
+            _ => None,
+        })
+    }
+
+    pub fn function(&self, name: &str) -> Option<&SierraAbiFunction> {
+        self.functions().find(|function| function.name == name)
+    }
+
+    pub fn function_by_selector(&self, selector: Felt) -> Option<&SierraAbiFunction> {
+        self.functions().find(|function| selector_from_name(&function.name) == selector)
+    }
+
+    pub fn constructor(&self) -> Option<&SierraAbiConstructor> {
+        self.entries().find_map(|entry| match entry {
+            SierraAbiEntry::Constructor(constructor) => Some(constructor),
+            _ => None,
+        })
+    }
+
+    pub fn events(&self) -> impl Iterator<Item = &SierraAbiEvent> {
+        self.entries().filter_map(|entry| match entry {
+            SierraAbiEntry::Event(event) => Some(event),
+            _ => None,
+        })
+    }
+
+    /// The event whose selector is `key`, the first key of an emitted event. The event enums of the contracts have
+    /// no selector of their own, only their variants do.
+    pub fn event_by_selector(&self, key: Felt) -> Option<&SierraAbiEvent> {
+        self.events()
+            .filter(|event| {
+                !matches!(
+                    event,
+                    SierraAbiEvent::Typed(SierraAbiTypedEvent { kind: SierraAbiEventKind::Enum { .. }, .. })
+                )
+            })
+            .find(|event| event_selector(event.name()) == key)
+    }
+
+    fn struct_by_name(&self, name: &str) -> Option<&SierraAbiStruct> {
+        self.entries().find_map(|entry| match entry {
+            SierraAbiEntry::Struct(item) if item.name == name => Some(item),
+            _ => None,
+        })
+    }
+
+    fn enum_by_name(&self, name: &str) -> Option<&SierraAbiEnum> {
+        self.entries().find_map(|entry| match entry {
+            SierraAbiEntry::Enum(item) if item.name == name => Some(item),
+            _ => None,
+        })
+    }
+
+    /// Checks that `calldata` is the serialization of the inputs of the function with this selector.
+    pub fn validate_calldata(&self, selector: Felt, calldata: &[Felt]) -> Result<(), AbiError> {
+        let function = self.function_by_selector(selector).ok_or(AbiError::UnknownSelector(selector))?;
+        self.validate_inputs(&function.inputs, calldata)
+    }
+
+    /// Checks that `calldata` is the serialization of `inputs`, with no felt left.
+    pub fn validate_inputs(&self, inputs: &[SierraAbiMember], calldata: &[Felt]) -> Result<(), AbiError> {
+        let mut calldata = calldata.iter();
+        for input in inputs {
+            self.consume(&input.r#type, &mut calldata, 0)?;
+        }
+        match calldata.len() {
+            0 => Ok(()),
+            n => Err(AbiError::UnexpectedCalldata(n)),
+        }
+    }
+
+    /// Consumes the serialization of a value of type `ty`.
+    fn consume(&self, ty: &str, calldata: &mut std::slice::Iter<Felt>, depth: usize) -> Result<(), AbiError> {
+        if depth > MAX_TYPE_DEPTH {
+            return Err(AbiError::TypeTooDeep(ty.into()));
+        }
+        let ty = ty.trim();
+
+        if let Some(items) = ty.strip_prefix('(').and_then(|ty| ty.strip_suffix(')')) {
+            for item in split_tuple(items) {
+                self.consume(item, calldata, depth + 1)?;
+            }
+            return Ok(());
+        }
+        if let Some(item) = self.struct_by_name(ty) {
+            for member in &item.members {
+                self.consume(&member.r#type, calldata, depth + 1)?;
+            }
+            return Ok(());
+        }
+        if let Some(item) = self.enum_by_name(ty) {
+            let index = next_felt(ty, calldata)?;
+            let variant = dp_convert::felt_to_u64(&index)
+                .ok()
+                .and_then(|index| item.variants.get(usize::try_from(index).ok()?))
+                .ok_or_else(|| AbiError::InvalidValue { ty: ty.into(), value: index })?;
+            return self.consume(&variant.r#type, calldata, depth + 1);
+        }
+        if let Some(element) = generic_argument(ty, &["core::array::Array", "core::array::Span"]) {
+            let len = next_felt(ty, calldata)?;
+            let len =
+                dp_convert::felt_to_u64(&len).map_err(|_| AbiError::InvalidValue { ty: ty.into(), value: len })?;
+            for _ in 0..len {
+                self.consume(element, calldata, depth + 1)?;
+            }
+            return Ok(());
+        }
+        if let Some(inner) = generic_argument(ty, &["core::box::Box", "core::zeroable::NonZero"]) {
+            return self.consume(inner, calldata, depth + 1);
+        }
+
+        let value = next_felt(ty, calldata)?;
+        let valid = match primitive_type(ty).ok_or_else(|| AbiError::UnknownType(ty.into()))? {
+            PrimitiveType::Felt => true,
+            PrimitiveType::Unsigned(bits) => value.bits() <= bits,
+            PrimitiveType::Signed(bits) => value.bits() < bits || (-value - Felt::ONE).bits() < bits,
+        };
+        if !valid {
+            return Err(AbiError::InvalidValue { ty: ty.into(), value });
+        }
+        Ok(())
+    }
+}
+
+fn next_felt(ty: &str, calldata: &mut std::slice::Iter<Felt>) -> Result<Felt, AbiError> {
+    calldata.next().copied().ok_or_else(|| AbiError::MissingCalldata(ty.into()))
+}
+
+enum PrimitiveType {
+    Felt,
+    /// An unsigned integer of this number of bits.
+    Unsigned(usize),
+    /// A signed integer of this number of bits.
+    Signed(usize),
+}
+
+/// The types serialized as a single felt, by the last segment of their path.
+fn primitive_type(ty: &str) -> Option<PrimitiveType> {
+    let name = ty.rsplit("::").next().unwrap_or(ty);
+    let primitive = match name {
+        "felt252" | "felt" | "StorageAddress" | "StorageBaseAddress" => PrimitiveType::Felt,
+        "ContractAddress" | "ClassHash" => PrimitiveType::Unsigned(251),
+        "bytes31" => PrimitiveType::Unsigned(248),
+        "EthAddress" => PrimitiveType::Unsigned(160),
+        "bool" => PrimitiveType::Unsigned(1),
+        "u8" => PrimitiveType::Unsigned(8),
+        "u16" => PrimitiveType::Unsigned(16),
+        "u32" | "usize" => PrimitiveType::Unsigned(32),
+        "u64" => PrimitiveType::Unsigned(64),
+        "u128" => PrimitiveType::Unsigned(128),
+        "i8" => PrimitiveType::Signed(8),
+        "i16" => PrimitiveType::Signed(16),
+        "i32" => PrimitiveType::Signed(32),
+        "i64" => PrimitiveType::Signed(64),
+        "i128" => PrimitiveType::Signed(128),
+        _ => return None,
+    };
+    Some(primitive)
+}
+
+/// The argument of `ty` when it is one of the generic types `names`, for example `core::felt252` for
+/// `core::array::Array::<core::felt252>`.
+fn generic_argument<'a>(ty: &'a str, names: &[&str]) -> Option<&'a str> {
+    names.iter().find_map(|name| ty.strip_prefix(name)?.strip_prefix("::<")?.strip_suffix('>'))
+}
+
+/// Splits the items of a tuple type, without the parentheses. The items may themselves be tuples or generic types.
+fn split_tuple(items: &str) -> Vec<&str> {
+    let mut result = vec![];
+    let (mut depth, mut start) = (0usize, 0);
+    for (i, c) in items.char_indices() {
+        match c {
+            '(' | '<' => depth += 1,
+            ')' | '>' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                result.push(&items[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if !items[start..].trim().is_empty() {
+        result.push(&items[start..]);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ABI: &str = r#"[
+        {"type": "impl", "name": "TokenImpl", "interface_name": "token::IToken"},
+        {"type": "struct", "name": "core::integer::u256", "members": [
+            {"name": "low", "type": "core::integer::u128"},
+            {"name": "high", "type": "core::integer::u128"}
+        ]},
+        {"type": "enum", "name": "core::bool", "variants": [
+            {"name": "False", "type": "()"},
+            {"name": "True", "type": "()"}
+        ]},
+        {"type": "interface", "name": "token::IToken", "items": [
+            {"type": "function", "name": "transfer", "inputs": [
+                {"name": "recipient", "type": "core::starknet::contract_address::ContractAddress"},
+                {"name": "amount", "type": "core::integer::u256"}
+            ], "outputs": [{"type": "core::bool"}], "state_mutability": "external"},
+            {"type": "function", "name": "batch", "inputs": [
+                {"name": "items", "type": "core::array::Span::<(core::felt252, core::integer::u8)>"}
+            ], "outputs": [], "state_mutability": "view"}
+        ]},
+        {"type": "constructor", "name": "constructor", "inputs": []},
+        {"type": "event", "name": "token::Token::Transfer", "kind": "struct", "members": [
+            {"name": "from", "type": "core::starknet::contract_address::ContractAddress", "kind": "key"},
+            {"name": "value", "type": "core::integer::u256", "kind": "data"}
+        ]},
+        {"type": "event", "name": "token::Token::Event", "kind": "enum", "variants": [
+            {"name": "Transfer", "type": "token::Token::Transfer", "kind": "nested"}
+        ]},
+        {"type": "event", "name": "Approval", "inputs": [{"name": "owner", "type": "core::felt252"}]}
+    ]"#;
+
+    #[test]
+    fn test_parse_abi() {
+        let abi: SierraAbi = ABI.parse().unwrap();
+        assert_eq!(abi.0.len(), 8);
+        assert_eq!(abi.functions().map(|function| function.name.as_str()).collect::<Vec<_>>(), ["transfer", "batch"]);
+        assert!(abi.constructor().is_some());
+        assert_eq!(abi.function_by_selector(selector_from_name("transfer")), abi.function("transfer"));
+        assert_eq!(abi.event_by_selector(event_selector("Transfer")).unwrap().name(), "token::Token::Transfer");
+        assert_eq!(abi.event_by_selector(event_selector("Approval")).unwrap().name(), "Approval");
+        assert!(abi.event_by_selector(event_selector("Event")).is_none());
+        assert_eq!(serde_json::from_str::<SierraAbi>(&serde_json::to_string(&abi).unwrap()).unwrap(), abi);
+        assert_eq!("".parse::<SierraAbi>().unwrap(), SierraAbi::default());
+    }
+
+    #[test]
+    fn test_selector_from_name() {
+        assert_eq!(
+            selector_from_name("transfer"),
+            Felt::from_hex_unchecked("0x83afd3f4caedc6eebf44246fe54e38c95e3179a5ec9ea81740eca5b482d12e")
+        );
+        assert_eq!(selector_from_name("__default__"), Felt::ZERO);
+        assert_eq!(event_selector("token::Token::Transfer"), selector_from_name("Transfer"));
+    }
+
+    #[test]
+    fn test_validate_calldata() {
+        let abi: SierraAbi = ABI.parse().unwrap();
+        let transfer = selector_from_name("transfer");
+        let batch = selector_from_name("batch");
+
+        abi.validate_calldata(transfer, &[Felt::ONE, Felt::TWO, Felt::ZERO]).unwrap();
+        abi.validate_calldata(batch, &[Felt::TWO, Felt::MAX, Felt::ONE, Felt::ZERO, Felt::from(255)]).unwrap();
+        abi.validate_calldata(batch, &[Felt::ZERO]).unwrap();
+
+        assert!(matches!(abi.validate_calldata(transfer, &[Felt::ONE, Felt::TWO]), Err(AbiError::MissingCalldata(_))));
+        assert!(matches!(
+            abi.validate_calldata(transfer, &[Felt::ONE, Felt::TWO, Felt::ZERO, Felt::ZERO]),
+            Err(AbiError::UnexpectedCalldata(1))
+        ));
+        assert!(matches!(
+            abi.validate_calldata(batch, &[Felt::ONE, Felt::ZERO, Felt::from(256)]),
+            Err(AbiError::InvalidValue { .. })
+        ));
+        assert!(matches!(abi.validate_calldata(Felt::ONE, &[]), Err(AbiError::UnknownSelector(_))));
+    }
+}
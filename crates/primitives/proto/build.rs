@@ -0,0 +1,17 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Use a vendored protoc, so that building does not require it to be installed.
+    std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+    prost_build::compile_protos(
+        &[
+            "proto/common.proto",
+            "proto/header.proto",
+            "proto/transaction.proto",
+            "proto/receipt.proto",
+            "proto/event.proto",
+            "proto/state.proto",
+            "proto/class.proto",
+        ],
+        &["proto"],
+    )?;
+    Ok(())
+}
@@ -0,0 +1,167 @@
+use dp_class::{
+    CompressedLegacyContractClass, ContractClass, EntryPointsByType, FlattenedSierraClass, LegacyContractAbiEntry,
+    LegacyContractEntryPoint, LegacyEntryPointsByType, SierraEntryPoint,
+};
+use starknet_types_core::felt::Felt;
+
+use crate::{felt_from_proto, felt_to_proto, felts_from_proto, felts_to_proto, proto, required, ProtoError};
+
+/// Encodes a class, along with its hash. A legacy class without ABI is encoded with an empty ABI.
+pub fn class_to_proto(class_hash: Felt, class: &ContractClass) -> proto::Class {
+    let class = match class {
+        ContractClass::Sierra(class) => proto::class::Class::Cairo1(proto::Cairo1Class {
+            abi: class.abi.clone(),
+            entry_points: Some(proto::Cairo1EntryPoints {
+                externals: class.entry_points_by_type.external.iter().map(sierra_entry_point_to_proto).collect(),
+                l1_handlers: class.entry_points_by_type.l1_handler.iter().map(sierra_entry_point_to_proto).collect(),
+                constructors: class.entry_points_by_type.constructor.iter().map(sierra_entry_point_to_proto).collect(),
+            }),
+            program: felts_to_proto(&class.sierra_program),
+            contract_class_version: class.contract_class_version.clone(),
+        }),
+        ContractClass::Legacy(class) => proto::class::Class::Cairo0(proto::Cairo0Class {
+            abi: class.abi.as_ref().map_or_else(String::new, |abi| {
+                let abi: Vec<_> = abi.iter().cloned().map(starknet_core::types::LegacyContractAbiEntry::from).collect();
+                serde_json::to_string(&abi).expect("Serializing the ABI of a legacy class")
+            }),
+            externals: class.entry_points_by_type.external.iter().map(legacy_entry_point_to_proto).collect(),
+            l1_handlers: class.entry_points_by_type.l1_handler.iter().map(legacy_entry_point_to_proto).collect(),
+            constructors: class.entry_points_by_type.constructor.iter().map(legacy_entry_point_to_proto).collect(),
+            program: base64::encode(&class.program),
+        }),
+    };
+
+    proto::Class { class: Some(class), domain: 0, class_hash: felt_to_proto(&class_hash) }
+}
+
+/// Decodes a class, along with its hash. The hash is not checked against the class.
+pub fn class_from_proto(class: proto::Class) -> Result<(Felt, ContractClass), ProtoError> {
+    let class_hash = felt_from_proto(class.class_hash, "class_hash")?;
+    let class = match required(class.class, "class")? {
+        proto::class::Class::Cairo1(class) => {
+            let entry_points = required(class.entry_points, "entry_points")?;
+            ContractClass::Sierra(FlattenedSierraClass {
+                sierra_program: felts_from_proto(class.program, "program")?,
+                contract_class_version: class.contract_class_version,
+                entry_points_by_type: EntryPointsByType {
+                    constructor: sierra_entry_points_from_proto(entry_points.constructors)?,
+                    external: sierra_entry_points_from_proto(entry_points.externals)?,
+                    l1_handler: sierra_entry_points_from_proto(entry_points.l1_handlers)?,
+                },
+                abi: class.abi,
+            })
+        }
+        proto::class::Class::Cairo0(class) => {
+            let abi = match class.abi.as_str() {
+                "" => None,
+                abi => {
+                    let abi: Vec<starknet_core::types::LegacyContractAbiEntry> =
+                        serde_json::from_str(abi).map_err(|err| ProtoError::invalid("abi", err))?;
+                    Some(abi.into_iter().map(LegacyContractAbiEntry::from).collect())
+                }
+            };
+            ContractClass::Legacy(CompressedLegacyContractClass {
+                program: base64::decode(&class.program).map_err(|err| ProtoError::invalid("program", err))?,
+                entry_points_by_type: LegacyEntryPointsByType {
+                    constructor: legacy_entry_points_from_proto(class.constructors)?,
+                    external: legacy_entry_points_from_proto(class.externals)?,
+                    l1_handler: legacy_entry_points_from_proto(class.l1_handlers)?,
+                },
+                abi,
+            })
+        }
+    };
+    Ok((class_hash, class))
+}
+
+fn sierra_entry_point_to_proto(entry_point: &SierraEntryPoint) -> proto::SierraEntryPoint {
+    proto::SierraEntryPoint { index: entry_point.function_idx, selector: felt_to_proto(&entry_point.selector) }
+}
+
+fn sierra_entry_points_from_proto(
+    entry_points: Vec<proto::SierraEntryPoint>,
+) -> Result<Vec<SierraEntryPoint>, ProtoError> {
+    entry_points
+        .into_iter()
+        .map(|entry_point| {
+            Ok(SierraEntryPoint {
+                selector: felt_from_proto(entry_point.selector, "selector")?,
+                function_idx: entry_point.index,
+            })
+        })
+        .collect()
+}
+
+fn legacy_entry_point_to_proto(entry_point: &LegacyContractEntryPoint) -> proto::EntryPoint {
+    proto::EntryPoint { selector: felt_to_proto(&entry_point.selector), offset: entry_point.offset }
+}
+
+fn legacy_entry_points_from_proto(
+    entry_points: Vec<proto::EntryPoint>,
+) -> Result<Vec<LegacyContractEntryPoint>, ProtoError> {
+    entry_points
+        .into_iter()
+        .map(|entry_point| {
+            Ok(LegacyContractEntryPoint {
+                offset: entry_point.offset,
+                selector: felt_from_proto(entry_point.selector, "selector")?,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use dp_class::{LegacyFunctionAbiEntry, LegacyFunctionAbiType, LegacyTypedParameter};
+    use prost::Message;
+
+    use super::*;
+
+    fn roundtrip(class: ContractClass) {
+        let encoded = class_to_proto(Felt::from(0x1234), &class).encode_to_vec();
+        let decoded = proto::Class::decode(encoded.as_slice()).unwrap();
+        assert_eq!(class_from_proto(decoded).unwrap(), (Felt::from(0x1234), class));
+    }
+
+    #[test]
+    fn test_sierra_class_roundtrip() {
+        roundtrip(ContractClass::Sierra(FlattenedSierraClass {
+            sierra_program: vec![Felt::ONE, Felt::TWO, Felt::MAX],
+            contract_class_version: "0.1.0".into(),
+            entry_points_by_type: EntryPointsByType {
+                constructor: vec![SierraEntryPoint { selector: Felt::THREE, function_idx: 0 }],
+                external: vec![
+                    SierraEntryPoint { selector: Felt::ONE, function_idx: 1 },
+                    SierraEntryPoint { selector: Felt::TWO, function_idx: 2 },
+                ],
+                l1_handler: vec![],
+            },
+            abi: r#"[{"type":"function","name":"foo"}]"#.into(),
+        }));
+    }
+
+    #[test]
+    fn test_legacy_class_roundtrip() {
+        let entry_points_by_type = LegacyEntryPointsByType {
+            constructor: vec![],
+            external: vec![LegacyContractEntryPoint { offset: 12, selector: Felt::ONE }],
+            l1_handler: vec![LegacyContractEntryPoint { offset: 34, selector: Felt::TWO }],
+        };
+        roundtrip(ContractClass::Legacy(CompressedLegacyContractClass {
+            program: vec![0x1f, 0x8b, 0x08, 0x00, 0xff],
+            entry_points_by_type: entry_points_by_type.clone(),
+            abi: Some(vec![LegacyContractAbiEntry::Function(LegacyFunctionAbiEntry {
+                r#type: LegacyFunctionAbiType::Function,
+                name: "foo".into(),
+                inputs: vec![LegacyTypedParameter { name: "bar".into(), r#type: "felt".into() }],
+                outputs: vec![],
+                state_mutability: None,
+            })]),
+        }));
+        roundtrip(ContractClass::Legacy(CompressedLegacyContractClass {
+            program: vec![],
+            entry_points_by_type,
+            abi: None,
+        }));
+    }
+}
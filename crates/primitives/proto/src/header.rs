@@ -0,0 +1,125 @@
+use dp_block::header::{GasPrices, Header, L1DataAvailabilityMode};
+use starknet_types_core::felt::Felt;
+
+use crate::{felt_from_proto, felt_to_proto, proto, required, uint128_from_proto, uint128_to_proto, ProtoError};
+
+/// Encodes the header of a block, along with its hash. The header is not signed.
+pub fn header_to_proto(block_hash: Felt, header: &Header) -> proto::SignedBlockHeader {
+    let l1_data_availability_mode = match header.l1_da_mode {
+        L1DataAvailabilityMode::Calldata => proto::L1DataAvailabilityMode::Calldata,
+        L1DataAvailabilityMode::Blob => proto::L1DataAvailabilityMode::Blob,
+    };
+
+    proto::SignedBlockHeader {
+        block_hash: felt_to_proto(&block_hash),
+        parent_hash: felt_to_proto(&header.parent_block_hash),
+        number: header.block_number,
+        time: header.block_timestamp,
+        sequencer_address: felt_to_proto(&header.sequencer_address),
+        state_root: felt_to_proto(&header.global_state_root),
+        state_diff_commitment: Some(proto::StateDiffCommitment {
+            state_diff_length: header.state_diff_length,
+            root: felt_to_proto(&header.state_diff_commitment),
+        }),
+        transactions: Some(proto::Patricia {
+            n_leaves: header.transaction_count,
+            root: felt_to_proto(&header.transaction_commitment),
+        }),
+        events: Some(proto::Patricia { n_leaves: header.event_count, root: felt_to_proto(&header.event_commitment) }),
+        receipts: felt_to_proto(&header.receipt_commitment),
+        protocol_version: header.protocol_version.to_string(),
+        gas_price_fri: uint128_to_proto(header.l1_gas_price.strk_l1_gas_price),
+        gas_price_wei: uint128_to_proto(header.l1_gas_price.eth_l1_gas_price),
+        data_gas_price_fri: uint128_to_proto(header.l1_gas_price.strk_l1_data_gas_price),
+        data_gas_price_wei: uint128_to_proto(header.l1_gas_price.eth_l1_data_gas_price),
+        l1_data_availability_mode: l1_data_availability_mode.into(),
+        signatures: vec![],
+    }
+}
+
+/// Decodes the header of a block, along with its hash. The signatures are not checked.
+pub fn header_from_proto(header: proto::SignedBlockHeader) -> Result<(Felt, Header), ProtoError> {
+    let state_diff_commitment = required(header.state_diff_commitment, "state_diff_commitment")?;
+    let transactions = required(header.transactions, "transactions")?;
+    let events = required(header.events, "events")?;
+    let l1_da_mode = match proto::L1DataAvailabilityMode::try_from(header.l1_data_availability_mode) {
+        Ok(proto::L1DataAvailabilityMode::Calldata) => L1DataAvailabilityMode::Calldata,
+        Ok(proto::L1DataAvailabilityMode::Blob) => L1DataAvailabilityMode::Blob,
+        Err(err) => return Err(ProtoError::invalid("l1_data_availability_mode", err)),
+    };
+
+    let block_hash = felt_from_proto(header.block_hash, "block_hash")?;
+    let header = Header {
+        parent_block_hash: felt_from_proto(header.parent_hash, "parent_hash")?,
+        block_number: header.number,
+        global_state_root: felt_from_proto(header.state_root, "state_root")?,
+        sequencer_address: felt_from_proto(header.sequencer_address, "sequencer_address")?,
+        block_timestamp: header.time,
+        transaction_count: transactions.n_leaves,
+        transaction_commitment: felt_from_proto(transactions.root, "transactions.root")?,
+        event_count: events.n_leaves,
+        event_commitment: felt_from_proto(events.root, "events.root")?,
+        state_diff_length: state_diff_commitment.state_diff_length,
+        state_diff_commitment: felt_from_proto(state_diff_commitment.root, "state_diff_commitment.root")?,
+        receipt_commitment: felt_from_proto(header.receipts, "receipts")?,
+        protocol_version: header
+            .protocol_version
+            .parse()
+            .map_err(|err| ProtoError::invalid("protocol_version", err))?,
+        l1_gas_price: GasPrices {
+            eth_l1_gas_price: uint128_from_proto(header.gas_price_wei, "gas_price_wei")?,
+            strk_l1_gas_price: uint128_from_proto(header.gas_price_fri, "gas_price_fri")?,
+            eth_l1_data_gas_price: uint128_from_proto(header.data_gas_price_wei, "data_gas_price_wei")?,
+            strk_l1_data_gas_price: uint128_from_proto(header.data_gas_price_fri, "data_gas_price_fri")?,
+        },
+        l1_da_mode,
+    };
+    Ok((block_hash, header))
+}
+
+#[cfg(test)]
+mod tests {
+    use dp_block::StarknetVersion;
+    use prost::Message;
+
+    use super::*;
+
+    #[test]
+    fn test_header_roundtrip() {
+        let header = Header {
+            parent_block_hash: Felt::from(1),
+            block_number: 2,
+            global_state_root: Felt::from(3),
+            sequencer_address: Felt::from(4),
+            block_timestamp: 5,
+            transaction_count: 6,
+            transaction_commitment: Felt::from(7),
+            event_count: 8,
+            event_commitment: Felt::from(9),
+            state_diff_length: 10,
+            state_diff_commitment: Felt::from(11),
+            receipt_commitment: Felt::from(12),
+            protocol_version: StarknetVersion::STARKNET_VERSION_0_13_1_1,
+            l1_gas_price: GasPrices {
+                eth_l1_gas_price: 13,
+                strk_l1_gas_price: u128::MAX,
+                eth_l1_data_gas_price: 15,
+                strk_l1_data_gas_price: 16,
+            },
+            l1_da_mode: L1DataAvailabilityMode::Blob,
+        };
+        let encoded = header_to_proto(Felt::from(17), &header);
+
+        let decoded = proto::SignedBlockHeader::decode(encoded.encode_to_vec().as_slice()).unwrap();
+        let (block_hash, decoded) = header_from_proto(decoded).unwrap();
+        assert_eq!(block_hash, Felt::from(17));
+        assert_eq!(header_to_proto(block_hash, &decoded), encoded);
+    }
+
+    #[test]
+    fn test_header_missing_field() {
+        let header =
+            proto::SignedBlockHeader { state_diff_commitment: None, ..header_to_proto(Felt::ONE, &Header::default()) };
+        assert_eq!(header_from_proto(header).unwrap_err(), ProtoError::MissingField("state_diff_commitment"));
+    }
+}
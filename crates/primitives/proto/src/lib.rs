@@ -0,0 +1,117 @@
+//! Protobuf encodings of the primitives, following the schemas of the Starknet p2p specification.
+//!
+//! Nothing in the node uses them yet: they are the wire format a p2p sync would exchange blocks in, while the block
+//! dumps are still encoded with bincode. Encoding a primitive cannot fail, decoding a message fails with a
+//! [`ProtoError`] when a field is missing or invalid.
+//!
+//! Each primitive has a pair of `x_to_proto` and `x_from_proto` functions:
+//! - [`header_to_proto`] and [`class_to_proto`] encode the block or class hash along with the primitive, the hash is
+//!   not checked when decoding.
+//! - [`transaction_to_proto`] encodes a transaction with its hash. L1 handler transactions are decoded with version 0.
+//! - [`receipt_to_proto`] does not encode the events nor the transaction hash of a receipt, which are given back to
+//!   [`receipt_from_proto`]. The events are encoded on their own with [`event_to_proto`].
+//! - [`state_diff_to_proto`] groups the state diff by contract. The schema does not tell replaced classes from
+//!   deployed contracts, so [`state_diff_from_proto`] decodes them all as deployed contracts.
+
+mod class;
+mod header;
+mod receipt;
+mod state_diff;
+mod transaction;
+
+use std::fmt;
+
+pub use class::{class_from_proto, class_to_proto};
+use dp_convert::{felt_to_u128, felt_to_u64};
+pub use header::{header_from_proto, header_to_proto};
+pub use receipt::{event_from_proto, event_to_proto, receipt_from_proto, receipt_to_proto};
+use starknet_types_core::felt::Felt;
+pub use state_diff::{state_diff_from_proto, state_diff_to_proto};
+pub use transaction::{transaction_from_proto, transaction_to_proto};
+
+/// The messages generated from the schemas of the `proto` directory.
+pub mod proto {
+    include!(concat!(env!("OUT_DIR"), "/starknet.p2p.rs"));
+}
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum ProtoError {
+    #[error("Missing field `{0}`")]
+    MissingField(&'static str),
+    #[error("Invalid field `{field}`: {reason}")]
+    InvalidField { field: &'static str, reason: String },
+}
+
+impl ProtoError {
+    fn invalid(field: &'static str, reason: impl fmt::Display) -> Self {
+        Self::InvalidField { field, reason: reason.to_string() }
+    }
+}
+
+/// The messages holding a single field element, as big-endian bytes.
+trait FeltMessage {
+    fn from_felt(felt: &Felt) -> Self;
+    fn into_bytes(self) -> Vec<u8>;
+}
+
+macro_rules! felt_message {
+    ($($message:ident),*) => {
+        $(
+            impl FeltMessage for proto::$message {
+                fn from_felt(felt: &Felt) -> Self {
+                    Self { elements: felt.to_bytes_be().to_vec() }
+                }
+
+                fn into_bytes(self) -> Vec<u8> {
+                    self.elements
+                }
+            }
+        )*
+    };
+}
+
+felt_message!(Felt252, Hash, Address);
+
+fn required<T>(value: Option<T>, field: &'static str) -> Result<T, ProtoError> {
+    value.ok_or(ProtoError::MissingField(field))
+}
+
+fn felt_to_proto<M: FeltMessage>(felt: &Felt) -> Option<M> {
+    Some(M::from_felt(felt))
+}
+
+fn felts_to_proto<M: FeltMessage>(felts: &[Felt]) -> Vec<M> {
+    felts.iter().map(M::from_felt).collect()
+}
+
+fn felt_from_bytes(bytes: &[u8], field: &'static str) -> Result<Felt, ProtoError> {
+    if bytes.len() > 32 {
+        return Err(ProtoError::invalid(field, "field element is longer than 32 bytes"));
+    }
+    Ok(Felt::from_bytes_be_slice(bytes))
+}
+
+fn felt_from_proto<M: FeltMessage>(message: Option<M>, field: &'static str) -> Result<Felt, ProtoError> {
+    felt_from_bytes(&required(message, field)?.into_bytes(), field)
+}
+
+fn felts_from_proto<M: FeltMessage>(messages: Vec<M>, field: &'static str) -> Result<Vec<Felt>, ProtoError> {
+    messages.into_iter().map(|message| felt_from_bytes(&message.into_bytes(), field)).collect()
+}
+
+fn u64_from_proto(message: Option<proto::Felt252>, field: &'static str) -> Result<u64, ProtoError> {
+    felt_to_u64(&felt_from_proto(message, field)?).map_err(|err| ProtoError::invalid(field, err))
+}
+
+fn u128_from_proto(message: Option<proto::Felt252>, field: &'static str) -> Result<u128, ProtoError> {
+    felt_to_u128(&felt_from_proto(message, field)?).map_err(|err| ProtoError::invalid(field, err))
+}
+
+fn uint128_to_proto(value: u128) -> Option<proto::Uint128> {
+    Some(proto::Uint128 { low: value as u64, high: (value >> 64) as u64 })
+}
+
+fn uint128_from_proto(message: Option<proto::Uint128>, field: &'static str) -> Result<u128, ProtoError> {
+    let proto::Uint128 { low, high } = required(message, field)?;
+    Ok(u128::from(high) << 64 | u128::from(low))
+}
@@ -0,0 +1,325 @@
+use dp_receipt::{
+    DataAvailabilityResources, DeclareTransactionReceipt, DeployAccountTransactionReceipt, DeployTransactionReceipt,
+    Event, ExecutionResources, ExecutionResult, FeePayment, InvokeTransactionReceipt, L1HandlerTransactionReceipt,
+    MsgToL1, PriceUnit, TransactionReceipt,
+};
+use starknet_types_core::felt::Felt;
+
+use crate::proto::receipt::execution_resources::BuiltinCounter;
+use crate::proto::receipt::{Common, Type};
+use crate::{
+    felt_from_bytes, felt_from_proto, felt_to_proto, felts_from_proto, felts_to_proto, proto, required, u64_from_proto,
+    ProtoError,
+};
+
+/// Encodes a receipt. Its events are encoded apart, see [`event_to_proto`].
+pub fn receipt_to_proto(receipt: &TransactionReceipt) -> proto::Receipt {
+    let r#type = match receipt {
+        TransactionReceipt::Invoke(receipt) => Type::Invoke(proto::receipt::Invoke {
+            common: common_to_proto(
+                &receipt.actual_fee,
+                &receipt.messages_sent,
+                &receipt.execution_resources,
+                &receipt.execution_result,
+            ),
+        }),
+        TransactionReceipt::L1Handler(receipt) => Type::L1Handler(proto::receipt::L1Handler {
+            common: common_to_proto(
+                &receipt.actual_fee,
+                &receipt.messages_sent,
+                &receipt.execution_resources,
+                &receipt.execution_result,
+            ),
+            msg_hash: felt_to_proto(&receipt.message_hash),
+        }),
+        TransactionReceipt::Declare(receipt) => Type::Declare(proto::receipt::Declare {
+            common: common_to_proto(
+                &receipt.actual_fee,
+                &receipt.messages_sent,
+                &receipt.execution_resources,
+                &receipt.execution_result,
+            ),
+        }),
+        TransactionReceipt::Deploy(receipt) => Type::DeprecatedDeploy(proto::receipt::Deploy {
+            common: common_to_proto(
+                &receipt.actual_fee,
+                &receipt.messages_sent,
+                &receipt.execution_resources,
+                &receipt.execution_result,
+            ),
+            contract_address: felt_to_proto(&receipt.contract_address),
+        }),
+        TransactionReceipt::DeployAccount(receipt) => Type::DeployAccount(proto::receipt::DeployAccount {
+            common: common_to_proto(
+                &receipt.actual_fee,
+                &receipt.messages_sent,
+                &receipt.execution_resources,
+                &receipt.execution_result,
+            ),
+            contract_address: felt_to_proto(&receipt.contract_address),
+        }),
+    };
+
+    proto::Receipt { r#type: Some(r#type) }
+}
+
+/// Decodes the receipt of the transaction `transaction_hash`, with its events. Neither is part of the message.
+pub fn receipt_from_proto(
+    receipt: proto::Receipt,
+    transaction_hash: Felt,
+    events: Vec<Event>,
+) -> Result<TransactionReceipt, ProtoError> {
+    Ok(match required(receipt.r#type, "type")? {
+        Type::Invoke(receipt) => {
+            let common = CommonFields::from_proto(receipt.common)?;
+            TransactionReceipt::Invoke(InvokeTransactionReceipt {
+                transaction_hash,
+                actual_fee: common.actual_fee,
+                messages_sent: common.messages_sent,
+                events,
+                execution_resources: common.execution_resources,
+                execution_result: common.execution_result,
+            })
+        }
+        Type::L1Handler(receipt) => {
+            let common = CommonFields::from_proto(receipt.common)?;
+            TransactionReceipt::L1Handler(L1HandlerTransactionReceipt {
+                message_hash: felt_from_proto(receipt.msg_hash, "msg_hash")?,
+                transaction_hash,
+                actual_fee: common.actual_fee,
+                messages_sent: common.messages_sent,
+                events,
+                execution_resources: common.execution_resources,
+                execution_result: common.execution_result,
+            })
+        }
+        Type::Declare(receipt) => {
+            let common = CommonFields::from_proto(receipt.common)?;
+            TransactionReceipt::Declare(DeclareTransactionReceipt {
+                transaction_hash,
+                actual_fee: common.actual_fee,
+                messages_sent: common.messages_sent,
+                events,
+                execution_resources: common.execution_resources,
+                execution_result: common.execution_result,
+            })
+        }
+        Type::DeprecatedDeploy(receipt) => {
+            let common = CommonFields::from_proto(receipt.common)?;
+            TransactionReceipt::Deploy(DeployTransactionReceipt {
+                transaction_hash,
+                actual_fee: common.actual_fee,
+                messages_sent: common.messages_sent,
+                events,
+                execution_resources: common.execution_resources,
+                execution_result: common.execution_result,
+                contract_address: felt_from_proto(receipt.contract_address, "contract_address")?,
+            })
+        }
+        Type::DeployAccount(receipt) => {
+            let common = CommonFields::from_proto(receipt.common)?;
+            TransactionReceipt::DeployAccount(DeployAccountTransactionReceipt {
+                transaction_hash,
+                actual_fee: common.actual_fee,
+                messages_sent: common.messages_sent,
+                events,
+                execution_resources: common.execution_resources,
+                execution_result: common.execution_result,
+                contract_address: felt_from_proto(receipt.contract_address, "contract_address")?,
+            })
+        }
+    })
+}
+
+/// Encodes an event emitted by the transaction `transaction_hash`.
+pub fn event_to_proto(transaction_hash: Felt, event: &Event) -> proto::Event {
+    proto::Event {
+        transaction_hash: felt_to_proto(&transaction_hash),
+        from_address: felt_to_proto(&event.from_address),
+        keys: felts_to_proto(&event.keys),
+        data: felts_to_proto(&event.data),
+    }
+}
+
+/// Decodes an event, along with the hash of the transaction which emitted it.
+pub fn event_from_proto(event: proto::Event) -> Result<(Felt, Event), ProtoError> {
+    Ok((
+        felt_from_proto(event.transaction_hash, "transaction_hash")?,
+        Event {
+            from_address: felt_from_proto(event.from_address, "from_address")?,
+            keys: felts_from_proto(event.keys, "keys")?,
+            data: felts_from_proto(event.data, "data")?,
+        },
+    ))
+}
+
+fn common_to_proto(
+    actual_fee: &FeePayment,
+    messages_sent: &[MsgToL1],
+    resources: &ExecutionResources,
+    execution_result: &ExecutionResult,
+) -> Option<Common> {
+    let price_unit = match actual_fee.unit {
+        PriceUnit::Wei => proto::PriceUnit::Wei,
+        PriceUnit::Fri => proto::PriceUnit::Fri,
+    };
+    let revert_reason = match execution_result {
+        ExecutionResult::Succeeded => None,
+        ExecutionResult::Reverted { reason } => Some(reason.clone()),
+    };
+
+    Some(Common {
+        actual_fee: felt_to_proto(&actual_fee.amount),
+        price_unit: price_unit.into(),
+        messages_sent: messages_sent.iter().map(message_to_proto).collect(),
+        execution_resources: Some(proto::receipt::ExecutionResources {
+            builtins: Some(BuiltinCounter {
+                bitwise: count_to_proto(resources.bitwise_builtin_applications),
+                ecdsa: count_to_proto(resources.ecdsa_builtin_applications),
+                ec_op: count_to_proto(resources.ec_op_builtin_applications),
+                pedersen: count_to_proto(resources.pedersen_builtin_applications),
+                range_check: count_to_proto(resources.range_check_builtin_applications),
+                poseidon: count_to_proto(resources.poseidon_builtin_applications),
+                keccak: count_to_proto(resources.keccak_builtin_applications),
+                ..Default::default()
+            }),
+            steps: count_to_proto(Some(resources.steps)),
+            memory_holes: count_to_proto(resources.memory_holes),
+            l1_gas: felt_to_proto(&Felt::from(resources.data_availability.l1_gas)),
+            l1_data_gas: felt_to_proto(&Felt::from(resources.data_availability.l1_data_gas)),
+            total_l1_gas: felt_to_proto(&Felt::from(resources.total_gas_consumed.l1_gas)),
+        }),
+        revert_reason,
+    })
+}
+
+/// The fields shared by all the receipts.
+struct CommonFields {
+    actual_fee: FeePayment,
+    messages_sent: Vec<MsgToL1>,
+    execution_resources: ExecutionResources,
+    execution_result: ExecutionResult,
+}
+
+impl CommonFields {
+    /// The specification has no total L1 data gas: it is the L1 data gas of the data availability, the only one a
+    /// transaction uses.
+    fn from_proto(common: Option<Common>) -> Result<Self, ProtoError> {
+        let common = required(common, "common")?;
+        let resources = required(common.execution_resources, "execution_resources")?;
+        let builtins = required(resources.builtins, "execution_resources.builtins")?;
+        let unit = match proto::PriceUnit::try_from(common.price_unit) {
+            Ok(proto::PriceUnit::Wei) => PriceUnit::Wei,
+            Ok(proto::PriceUnit::Fri) => PriceUnit::Fri,
+            Err(err) => return Err(ProtoError::invalid("price_unit", err)),
+        };
+        let l1_data_gas = u64_from_proto(resources.l1_data_gas, "execution_resources.l1_data_gas")?;
+
+        Ok(Self {
+            actual_fee: FeePayment { amount: felt_from_proto(common.actual_fee, "actual_fee")?, unit },
+            messages_sent: common.messages_sent.into_iter().map(message_from_proto).collect::<Result<_, _>>()?,
+            execution_resources: ExecutionResources {
+                steps: resources.steps.into(),
+                memory_holes: count_from_proto(resources.memory_holes),
+                range_check_builtin_applications: count_from_proto(builtins.range_check),
+                pedersen_builtin_applications: count_from_proto(builtins.pedersen),
+                poseidon_builtin_applications: count_from_proto(builtins.poseidon),
+                ec_op_builtin_applications: count_from_proto(builtins.ec_op),
+                ecdsa_builtin_applications: count_from_proto(builtins.ecdsa),
+                bitwise_builtin_applications: count_from_proto(builtins.bitwise),
+                keccak_builtin_applications: count_from_proto(builtins.keccak),
+                segment_arena_builtin: None,
+                data_availability: DataAvailabilityResources {
+                    l1_gas: u64_from_proto(resources.l1_gas, "execution_resources.l1_gas")?,
+                    l1_data_gas,
+                },
+                total_gas_consumed: DataAvailabilityResources {
+                    l1_gas: u64_from_proto(resources.total_l1_gas, "execution_resources.total_l1_gas")?,
+                    l1_data_gas,
+                },
+            },
+            execution_result: match common.revert_reason {
+                Some(reason) => ExecutionResult::Reverted { reason },
+                None => ExecutionResult::Succeeded,
+            },
+        })
+    }
+}
+
+/// The counters do not distinguish an unused builtin from a builtin used zero times.
+fn count_to_proto(count: Option<u64>) -> u32 {
+    count.map_or(0, |count| u32::try_from(count).unwrap_or(u32::MAX))
+}
+
+fn count_from_proto(count: u32) -> Option<u64> {
+    (count != 0).then_some(count.into())
+}
+
+fn message_to_proto(message: &MsgToL1) -> proto::MessageToL1 {
+    proto::MessageToL1 {
+        from_address: felt_to_proto(&message.from_address),
+        payload: felts_to_proto(&message.payload),
+        // Ethereum addresses are 20 bytes long
+        to_address: Some(proto::EthereumAddress { elements: message.to_address.to_bytes_be()[12..].to_vec() }),
+    }
+}
+
+fn message_from_proto(message: proto::MessageToL1) -> Result<MsgToL1, ProtoError> {
+    let to_address = required(message.to_address, "to_address")?;
+    Ok(MsgToL1 {
+        from_address: felt_from_proto(message.from_address, "from_address")?,
+        to_address: felt_from_bytes(&to_address.elements, "to_address")?,
+        payload: felts_from_proto(message.payload, "payload")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use prost::Message;
+
+    use super::*;
+
+    #[test]
+    fn test_receipt_roundtrip() {
+        let events = vec![Event { from_address: Felt::ONE, keys: vec![Felt::TWO], data: vec![Felt::THREE, Felt::MAX] }];
+        let receipt = TransactionReceipt::L1Handler(L1HandlerTransactionReceipt {
+            message_hash: Felt::from(0x42),
+            transaction_hash: Felt::from(0x1234),
+            actual_fee: FeePayment { amount: Felt::from(1000), unit: PriceUnit::Fri },
+            messages_sent: vec![MsgToL1 {
+                from_address: Felt::ONE,
+                to_address: Felt::from_hex_unchecked("0xae0ee0a63a2ce6baeeffe56e7714fb4efe48d419"),
+                payload: vec![Felt::TWO],
+            }],
+            events: events.clone(),
+            execution_resources: ExecutionResources {
+                steps: 1234,
+                memory_holes: Some(5),
+                range_check_builtin_applications: Some(6),
+                pedersen_builtin_applications: Some(7),
+                poseidon_builtin_applications: None,
+                ec_op_builtin_applications: None,
+                ecdsa_builtin_applications: Some(8),
+                bitwise_builtin_applications: None,
+                keccak_builtin_applications: Some(9),
+                segment_arena_builtin: None,
+                data_availability: DataAvailabilityResources { l1_gas: 0, l1_data_gas: 128 },
+                total_gas_consumed: DataAvailabilityResources { l1_gas: 3000, l1_data_gas: 128 },
+            },
+            execution_result: ExecutionResult::Reverted { reason: "reverted".into() },
+        });
+
+        let encoded = receipt_to_proto(&receipt).encode_to_vec();
+        let decoded = proto::Receipt::decode(encoded.as_slice()).unwrap();
+        assert_eq!(receipt_from_proto(decoded, Felt::from(0x1234), events).unwrap(), receipt);
+    }
+
+    #[test]
+    fn test_event_roundtrip() {
+        let event = Event { from_address: Felt::ONE, keys: vec![Felt::TWO, Felt::THREE], data: vec![] };
+
+        let encoded = event_to_proto(Felt::from(0x1234), &event).encode_to_vec();
+        let decoded = proto::Event::decode(encoded.as_slice()).unwrap();
+        assert_eq!(event_from_proto(decoded).unwrap(), (Felt::from(0x1234), event));
+    }
+}
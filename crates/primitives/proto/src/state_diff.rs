@@ -0,0 +1,149 @@
+use std::collections::BTreeMap;
+
+use dp_state_update::{
+    ContractStorageDiffItem, DeclaredClassItem, DeployedContractItem, NonceUpdate, StateDiff, StorageEntry,
+};
+use starknet_types_core::felt::Felt;
+
+use crate::{felt_from_proto, felt_to_proto, proto, ProtoError};
+
+/// Encodes a state diff, the contract diffs are sorted by address. The class hashes of the replaced classes are
+/// encoded as the ones of the deployed contracts: the schema does not tell them apart.
+pub fn state_diff_to_proto(state_diff: &StateDiff) -> proto::StateDiff {
+    let mut contract_diffs = BTreeMap::new();
+
+    for diff in &state_diff.storage_diffs {
+        contract_diff(&mut contract_diffs, diff.address).values.extend(diff.storage_entries.iter().map(|entry| {
+            proto::ContractStoredValue { key: felt_to_proto(&entry.key), value: felt_to_proto(&entry.value) }
+        }));
+    }
+    for deployed in &state_diff.deployed_contracts {
+        contract_diff(&mut contract_diffs, deployed.address).class_hash = felt_to_proto(&deployed.class_hash);
+    }
+    for replaced in &state_diff.replaced_classes {
+        contract_diff(&mut contract_diffs, replaced.contract_address).class_hash = felt_to_proto(&replaced.class_hash);
+    }
+    for nonce in &state_diff.nonces {
+        contract_diff(&mut contract_diffs, nonce.contract_address).nonce = felt_to_proto(&nonce.nonce);
+    }
+
+    let declared_classes = state_diff
+        .deprecated_declared_classes
+        .iter()
+        .map(|class_hash| proto::DeclaredClass { class_hash: felt_to_proto(class_hash), compiled_class_hash: None })
+        .chain(state_diff.declared_classes.iter().map(|declared| proto::DeclaredClass {
+            class_hash: felt_to_proto(&declared.class_hash),
+            compiled_class_hash: felt_to_proto(&declared.compiled_class_hash),
+        }))
+        .collect();
+
+    proto::StateDiff { contract_diffs: contract_diffs.into_values().collect(), declared_classes }
+}
+
+fn contract_diff(contract_diffs: &mut BTreeMap<Felt, proto::ContractDiff>, address: Felt) -> &mut proto::ContractDiff {
+    contract_diffs
+        .entry(address)
+        .or_insert_with(|| proto::ContractDiff { address: felt_to_proto(&address), ..Default::default() })
+}
+
+/// Decodes a state diff. The class hashes of the contract diffs are all decoded as deployed contracts, there are no
+/// replaced classes: they are the same leaves of the state diff commitment.
+pub fn state_diff_from_proto(state_diff: proto::StateDiff) -> Result<StateDiff, ProtoError> {
+    let mut storage_diffs = Vec::new();
+    let mut deployed_contracts = Vec::new();
+    let mut nonces = Vec::new();
+    for diff in state_diff.contract_diffs {
+        let address = felt_from_proto(diff.address, "address")?;
+        if !diff.values.is_empty() {
+            let storage_entries = diff
+                .values
+                .into_iter()
+                .map(|stored| {
+                    Ok(StorageEntry {
+                        key: felt_from_proto(stored.key, "key")?,
+                        value: felt_from_proto(stored.value, "value")?,
+                    })
+                })
+                .collect::<Result<_, ProtoError>>()?;
+            storage_diffs.push(ContractStorageDiffItem { address, storage_entries });
+        }
+        if diff.class_hash.is_some() {
+            deployed_contracts
+                .push(DeployedContractItem { address, class_hash: felt_from_proto(diff.class_hash, "class_hash")? });
+        }
+        if diff.nonce.is_some() {
+            nonces.push(NonceUpdate { contract_address: address, nonce: felt_from_proto(diff.nonce, "nonce")? });
+        }
+    }
+
+    let mut deprecated_declared_classes = Vec::new();
+    let mut declared_classes = Vec::new();
+    for declared in state_diff.declared_classes {
+        let class_hash = felt_from_proto(declared.class_hash, "class_hash")?;
+        match declared.compiled_class_hash {
+            Some(compiled_class_hash) => declared_classes.push(DeclaredClassItem {
+                class_hash,
+                compiled_class_hash: felt_from_proto(Some(compiled_class_hash), "compiled_class_hash")?,
+            }),
+            None => deprecated_declared_classes.push(class_hash),
+        }
+    }
+
+    Ok(StateDiff {
+        storage_diffs,
+        deprecated_declared_classes,
+        declared_classes,
+        deployed_contracts,
+        replaced_classes: vec![],
+        nonces,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use dp_state_update::ReplacedClassItem;
+    use prost::Message;
+
+    use super::*;
+
+    #[test]
+    fn test_state_diff_roundtrip() {
+        let state_diff = StateDiff {
+            storage_diffs: vec![ContractStorageDiffItem {
+                address: Felt::ONE,
+                storage_entries: vec![
+                    StorageEntry { key: Felt::TWO, value: Felt::THREE },
+                    StorageEntry { key: Felt::MAX, value: Felt::ZERO },
+                ],
+            }],
+            deprecated_declared_classes: vec![Felt::from(4)],
+            declared_classes: vec![DeclaredClassItem { class_hash: Felt::from(5), compiled_class_hash: Felt::from(6) }],
+            deployed_contracts: vec![DeployedContractItem { address: Felt::ONE, class_hash: Felt::from(7) }],
+            replaced_classes: vec![],
+            nonces: vec![NonceUpdate { contract_address: Felt::TWO, nonce: Felt::ONE }],
+        };
+
+        let encoded = state_diff_to_proto(&state_diff).encode_to_vec();
+        let decoded = proto::StateDiff::decode(encoded.as_slice()).unwrap();
+        assert_eq!(state_diff_from_proto(decoded).unwrap(), state_diff);
+    }
+
+    #[test]
+    fn test_replaced_classes_decoded_as_deployed_contracts() {
+        let state_diff = StateDiff {
+            storage_diffs: vec![],
+            deprecated_declared_classes: vec![],
+            declared_classes: vec![],
+            deployed_contracts: vec![],
+            replaced_classes: vec![ReplacedClassItem { contract_address: Felt::ONE, class_hash: Felt::TWO }],
+            nonces: vec![],
+        };
+
+        let decoded = state_diff_from_proto(state_diff_to_proto(&state_diff)).unwrap();
+        assert!(decoded.replaced_classes.is_empty());
+        assert_eq!(
+            decoded.deployed_contracts,
+            vec![DeployedContractItem { address: Felt::ONE, class_hash: Felt::TWO }]
+        );
+    }
+}
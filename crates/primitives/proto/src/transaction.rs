@@ -0,0 +1,438 @@
+use dp_convert::felt_to_u64;
+use dp_transactions::{
+    DataAvailabilityMode, DeclareTransaction, DeclareTransactionV0, DeclareTransactionV1, DeclareTransactionV2,
+    DeclareTransactionV3, DeployAccountTransaction, DeployAccountTransactionV1, DeployAccountTransactionV3,
+    DeployTransaction, InvokeTransaction, InvokeTransactionV0, InvokeTransactionV1, InvokeTransactionV3,
+    L1HandlerTransaction, ResourceBounds, ResourceBoundsMapping, Transaction, TransactionWithHash,
+};
+use starknet_types_core::felt::Felt;
+
+use crate::proto::transaction::{
+    DeclareV0, DeclareV1, DeclareV2, DeclareV3, Deploy, DeployAccountV1, DeployAccountV3, InvokeV0, InvokeV1, InvokeV3,
+    L1HandlerV0, Txn,
+};
+use crate::{
+    felt_from_proto, felt_to_proto, felts_from_proto, felts_to_proto, proto, required, u128_from_proto, u64_from_proto,
+    ProtoError,
+};
+
+/// Encodes a transaction, along with its hash.
+pub fn transaction_to_proto(tx: &TransactionWithHash) -> proto::Transaction {
+    let txn = match &tx.transaction {
+        Transaction::Declare(DeclareTransaction::V0(tx)) => Txn::DeclareV0(DeclareV0 {
+            sender: felt_to_proto(&tx.sender_address),
+            max_fee: felt_to_proto(&tx.max_fee),
+            signature: signature_to_proto(&tx.signature),
+            class_hash: felt_to_proto(&tx.class_hash),
+        }),
+        Transaction::Declare(DeclareTransaction::V1(tx)) => Txn::DeclareV1(DeclareV1 {
+            sender: felt_to_proto(&tx.sender_address),
+            max_fee: felt_to_proto(&tx.max_fee),
+            signature: signature_to_proto(&tx.signature),
+            class_hash: felt_to_proto(&tx.class_hash),
+            nonce: felt_to_proto(&tx.nonce),
+        }),
+        Transaction::Declare(DeclareTransaction::V2(tx)) => Txn::DeclareV2(DeclareV2 {
+            sender: felt_to_proto(&tx.sender_address),
+            max_fee: felt_to_proto(&tx.max_fee),
+            signature: signature_to_proto(&tx.signature),
+            class_hash: felt_to_proto(&tx.class_hash),
+            nonce: felt_to_proto(&tx.nonce),
+            compiled_class_hash: felt_to_proto(&tx.compiled_class_hash),
+        }),
+        Transaction::Declare(DeclareTransaction::V3(tx)) => Txn::DeclareV3(DeclareV3 {
+            sender: felt_to_proto(&tx.sender_address),
+            signature: signature_to_proto(&tx.signature),
+            class_hash: felt_to_proto(&tx.class_hash),
+            nonce: felt_to_proto(&tx.nonce),
+            compiled_class_hash: felt_to_proto(&tx.compiled_class_hash),
+            resource_bounds: resource_bounds_to_proto(&tx.resource_bounds),
+            tip: tx.tip,
+            paymaster_data: felts_to_proto(&tx.paymaster_data),
+            account_deployment_data: felts_to_proto(&tx.account_deployment_data),
+            nonce_data_availability_mode: da_mode_to_proto(tx.nonce_data_availability_mode),
+            fee_data_availability_mode: da_mode_to_proto(tx.fee_data_availability_mode),
+        }),
+        Transaction::Deploy(tx) => Txn::Deploy(Deploy {
+            class_hash: felt_to_proto(&tx.class_hash),
+            address_salt: felt_to_proto(&tx.contract_address_salt),
+            calldata: felts_to_proto(&tx.constructor_calldata),
+            // Deploy transactions are of version 0 or 1
+            version: felt_to_u64(&tx.version).unwrap_or_default() as u32,
+        }),
+        Transaction::DeployAccount(DeployAccountTransaction::V1(tx)) => Txn::DeployAccountV1(DeployAccountV1 {
+            max_fee: felt_to_proto(&tx.max_fee),
+            signature: signature_to_proto(&tx.signature),
+            class_hash: felt_to_proto(&tx.class_hash),
+            nonce: felt_to_proto(&tx.nonce),
+            address_salt: felt_to_proto(&tx.contract_address_salt),
+            calldata: felts_to_proto(&tx.constructor_calldata),
+        }),
+        Transaction::DeployAccount(DeployAccountTransaction::V3(tx)) => Txn::DeployAccountV3(DeployAccountV3 {
+            signature: signature_to_proto(&tx.signature),
+            class_hash: felt_to_proto(&tx.class_hash),
+            nonce: felt_to_proto(&tx.nonce),
+            address_salt: felt_to_proto(&tx.contract_address_salt),
+            calldata: felts_to_proto(&tx.constructor_calldata),
+            resource_bounds: resource_bounds_to_proto(&tx.resource_bounds),
+            tip: tx.tip,
+            paymaster_data: felts_to_proto(&tx.paymaster_data),
+            nonce_data_availability_mode: da_mode_to_proto(tx.nonce_data_availability_mode),
+            fee_data_availability_mode: da_mode_to_proto(tx.fee_data_availability_mode),
+        }),
+        Transaction::Invoke(InvokeTransaction::V0(tx)) => Txn::InvokeV0(InvokeV0 {
+            max_fee: felt_to_proto(&tx.max_fee),
+            signature: signature_to_proto(&tx.signature),
+            address: felt_to_proto(&tx.contract_address),
+            entry_point_selector: felt_to_proto(&tx.entry_point_selector),
+            calldata: felts_to_proto(&tx.calldata),
+        }),
+        Transaction::Invoke(InvokeTransaction::V1(tx)) => Txn::InvokeV1(InvokeV1 {
+            sender: felt_to_proto(&tx.sender_address),
+            max_fee: felt_to_proto(&tx.max_fee),
+            signature: signature_to_proto(&tx.signature),
+            calldata: felts_to_proto(&tx.calldata),
+            nonce: felt_to_proto(&tx.nonce),
+        }),
+        Transaction::Invoke(InvokeTransaction::V3(tx)) => Txn::InvokeV3(InvokeV3 {
+            sender: felt_to_proto(&tx.sender_address),
+            signature: signature_to_proto(&tx.signature),
+            calldata: felts_to_proto(&tx.calldata),
+            resource_bounds: resource_bounds_to_proto(&tx.resource_bounds),
+            tip: tx.tip,
+            paymaster_data: felts_to_proto(&tx.paymaster_data),
+            account_deployment_data: felts_to_proto(&tx.account_deployment_data),
+            nonce_data_availability_mode: da_mode_to_proto(tx.nonce_data_availability_mode),
+            fee_data_availability_mode: da_mode_to_proto(tx.fee_data_availability_mode),
+            nonce: felt_to_proto(&tx.nonce),
+        }),
+        Transaction::L1Handler(tx) => Txn::L1Handler(L1HandlerV0 {
+            nonce: felt_to_proto(&Felt::from(tx.nonce)),
+            address: felt_to_proto(&tx.contract_address),
+            entry_point_selector: felt_to_proto(&tx.entry_point_selector),
+            calldata: felts_to_proto(&tx.calldata),
+        }),
+    };
+
+    proto::Transaction { txn: Some(txn), transaction_hash: felt_to_proto(&tx.hash) }
+}
+
+/// Decodes a transaction, along with its hash. The hash is not checked against the transaction. L1 handler
+/// transactions are decoded with version 0, the only version the specification knows.
+pub fn transaction_from_proto(tx: proto::Transaction) -> Result<TransactionWithHash, ProtoError> {
+    let transaction = match required(tx.txn, "txn")? {
+        Txn::DeclareV0(tx) => Transaction::Declare(DeclareTransaction::V0(DeclareTransactionV0 {
+            sender_address: felt_from_proto(tx.sender, "sender")?,
+            max_fee: felt_from_proto(tx.max_fee, "max_fee")?,
+            signature: signature_from_proto(tx.signature)?,
+            class_hash: felt_from_proto(tx.class_hash, "class_hash")?,
+        })),
+        Txn::DeclareV1(tx) => Transaction::Declare(DeclareTransaction::V1(DeclareTransactionV1 {
+            sender_address: felt_from_proto(tx.sender, "sender")?,
+            max_fee: felt_from_proto(tx.max_fee, "max_fee")?,
+            signature: signature_from_proto(tx.signature)?,
+            nonce: felt_from_proto(tx.nonce, "nonce")?,
+            class_hash: felt_from_proto(tx.class_hash, "class_hash")?,
+        })),
+        Txn::DeclareV2(tx) => Transaction::Declare(DeclareTransaction::V2(DeclareTransactionV2 {
+            sender_address: felt_from_proto(tx.sender, "sender")?,
+            compiled_class_hash: felt_from_proto(tx.compiled_class_hash, "compiled_class_hash")?,
+            max_fee: felt_from_proto(tx.max_fee, "max_fee")?,
+            signature: signature_from_proto(tx.signature)?,
+            nonce: felt_from_proto(tx.nonce, "nonce")?,
+            class_hash: felt_from_proto(tx.class_hash, "class_hash")?,
+        })),
+        Txn::DeclareV3(tx) => Transaction::Declare(DeclareTransaction::V3(DeclareTransactionV3 {
+            sender_address: felt_from_proto(tx.sender, "sender")?,
+            compiled_class_hash: felt_from_proto(tx.compiled_class_hash, "compiled_class_hash")?,
+            signature: signature_from_proto(tx.signature)?,
+            nonce: felt_from_proto(tx.nonce, "nonce")?,
+            class_hash: felt_from_proto(tx.class_hash, "class_hash")?,
+            resource_bounds: resource_bounds_from_proto(tx.resource_bounds)?,
+            tip: tx.tip,
+            paymaster_data: felts_from_proto(tx.paymaster_data, "paymaster_data")?,
+            account_deployment_data: felts_from_proto(tx.account_deployment_data, "account_deployment_data")?,
+            nonce_data_availability_mode: da_mode_from_proto(
+                tx.nonce_data_availability_mode,
+                "nonce_data_availability_mode",
+            )?,
+            fee_data_availability_mode: da_mode_from_proto(
+                tx.fee_data_availability_mode,
+                "fee_data_availability_mode",
+            )?,
+        })),
+        Txn::Deploy(tx) => Transaction::Deploy(DeployTransaction {
+            version: Felt::from(tx.version),
+            contract_address_salt: felt_from_proto(tx.address_salt, "address_salt")?,
+            constructor_calldata: felts_from_proto(tx.calldata, "calldata")?,
+            class_hash: felt_from_proto(tx.class_hash, "class_hash")?,
+        }),
+        Txn::DeployAccountV1(tx) => {
+            Transaction::DeployAccount(DeployAccountTransaction::V1(DeployAccountTransactionV1 {
+                max_fee: felt_from_proto(tx.max_fee, "max_fee")?,
+                signature: signature_from_proto(tx.signature)?,
+                nonce: felt_from_proto(tx.nonce, "nonce")?,
+                contract_address_salt: felt_from_proto(tx.address_salt, "address_salt")?,
+                constructor_calldata: felts_from_proto(tx.calldata, "calldata")?,
+                class_hash: felt_from_proto(tx.class_hash, "class_hash")?,
+            }))
+        }
+        Txn::DeployAccountV3(tx) => {
+            Transaction::DeployAccount(DeployAccountTransaction::V3(DeployAccountTransactionV3 {
+                signature: signature_from_proto(tx.signature)?,
+                nonce: felt_from_proto(tx.nonce, "nonce")?,
+                contract_address_salt: felt_from_proto(tx.address_salt, "address_salt")?,
+                constructor_calldata: felts_from_proto(tx.calldata, "calldata")?,
+                class_hash: felt_from_proto(tx.class_hash, "class_hash")?,
+                resource_bounds: resource_bounds_from_proto(tx.resource_bounds)?,
+                tip: tx.tip,
+                paymaster_data: felts_from_proto(tx.paymaster_data, "paymaster_data")?,
+                nonce_data_availability_mode: da_mode_from_proto(
+                    tx.nonce_data_availability_mode,
+                    "nonce_data_availability_mode",
+                )?,
+                fee_data_availability_mode: da_mode_from_proto(
+                    tx.fee_data_availability_mode,
+                    "fee_data_availability_mode",
+                )?,
+            }))
+        }
+        Txn::InvokeV0(tx) => Transaction::Invoke(InvokeTransaction::V0(InvokeTransactionV0 {
+            max_fee: felt_from_proto(tx.max_fee, "max_fee")?,
+            signature: signature_from_proto(tx.signature)?,
+            contract_address: felt_from_proto(tx.address, "address")?,
+            entry_point_selector: felt_from_proto(tx.entry_point_selector, "entry_point_selector")?,
+            calldata: felts_from_proto(tx.calldata, "calldata")?,
+        })),
+        Txn::InvokeV1(tx) => Transaction::Invoke(InvokeTransaction::V1(InvokeTransactionV1 {
+            sender_address: felt_from_proto(tx.sender, "sender")?,
+            calldata: felts_from_proto(tx.calldata, "calldata")?,
+            max_fee: felt_from_proto(tx.max_fee, "max_fee")?,
+            signature: signature_from_proto(tx.signature)?,
+            nonce: felt_from_proto(tx.nonce, "nonce")?,
+        })),
+        Txn::InvokeV3(tx) => Transaction::Invoke(InvokeTransaction::V3(InvokeTransactionV3 {
+            sender_address: felt_from_proto(tx.sender, "sender")?,
+            calldata: felts_from_proto(tx.calldata, "calldata")?,
+            signature: signature_from_proto(tx.signature)?,
+            nonce: felt_from_proto(tx.nonce, "nonce")?,
+            resource_bounds: resource_bounds_from_proto(tx.resource_bounds)?,
+            tip: tx.tip,
+            paymaster_data: felts_from_proto(tx.paymaster_data, "paymaster_data")?,
+            account_deployment_data: felts_from_proto(tx.account_deployment_data, "account_deployment_data")?,
+            nonce_data_availability_mode: da_mode_from_proto(
+                tx.nonce_data_availability_mode,
+                "nonce_data_availability_mode",
+            )?,
+            fee_data_availability_mode: da_mode_from_proto(
+                tx.fee_data_availability_mode,
+                "fee_data_availability_mode",
+            )?,
+        })),
+        Txn::L1Handler(tx) => Transaction::L1Handler(L1HandlerTransaction {
+            version: Felt::ZERO,
+            nonce: u64_from_proto(tx.nonce, "nonce")?,
+            contract_address: felt_from_proto(tx.address, "address")?,
+            entry_point_selector: felt_from_proto(tx.entry_point_selector, "entry_point_selector")?,
+            calldata: felts_from_proto(tx.calldata, "calldata")?,
+        }),
+    };
+
+    Ok(TransactionWithHash::new(transaction, felt_from_proto(tx.transaction_hash, "transaction_hash")?))
+}
+
+fn signature_to_proto(signature: &[Felt]) -> Option<proto::AccountSignature> {
+    Some(proto::AccountSignature { parts: felts_to_proto(signature) })
+}
+
+fn signature_from_proto(signature: Option<proto::AccountSignature>) -> Result<Vec<Felt>, ProtoError> {
+    felts_from_proto(required(signature, "signature")?.parts, "signature.parts")
+}
+
+fn resource_limits_to_proto(bounds: &ResourceBounds) -> Option<proto::ResourceLimits> {
+    Some(proto::ResourceLimits {
+        max_amount: felt_to_proto(&Felt::from(bounds.max_amount)),
+        max_price_per_unit: felt_to_proto(&Felt::from(bounds.max_price_per_unit)),
+    })
+}
+
+fn resource_limits_from_proto(
+    limits: Option<proto::ResourceLimits>,
+    field: &'static str,
+) -> Result<ResourceBounds, ProtoError> {
+    let limits = required(limits, field)?;
+    Ok(ResourceBounds {
+        max_amount: u64_from_proto(limits.max_amount, "max_amount")?,
+        max_price_per_unit: u128_from_proto(limits.max_price_per_unit, "max_price_per_unit")?,
+    })
+}
+
+fn resource_bounds_to_proto(bounds: &ResourceBoundsMapping) -> Option<proto::ResourceBounds> {
+    Some(proto::ResourceBounds {
+        l1_gas: resource_limits_to_proto(&bounds.l1_gas),
+        l2_gas: resource_limits_to_proto(&bounds.l2_gas),
+    })
+}
+
+fn resource_bounds_from_proto(bounds: Option<proto::ResourceBounds>) -> Result<ResourceBoundsMapping, ProtoError> {
+    let bounds = required(bounds, "resource_bounds")?;
+    Ok(ResourceBoundsMapping {
+        l1_gas: resource_limits_from_proto(bounds.l1_gas, "resource_bounds.l1_gas")?,
+        l2_gas: resource_limits_from_proto(bounds.l2_gas, "resource_bounds.l2_gas")?,
+    })
+}
+
+fn da_mode_to_proto(mode: DataAvailabilityMode) -> i32 {
+    match mode {
+        DataAvailabilityMode::L1 => proto::VolitionDomain::L1,
+        DataAvailabilityMode::L2 => proto::VolitionDomain::L2,
+    }
+    .into()
+}
+
+fn da_mode_from_proto(mode: i32, field: &'static str) -> Result<DataAvailabilityMode, ProtoError> {
+    match proto::VolitionDomain::try_from(mode) {
+        Ok(proto::VolitionDomain::L1) => Ok(DataAvailabilityMode::L1),
+        Ok(proto::VolitionDomain::L2) => Ok(DataAvailabilityMode::L2),
+        Err(err) => Err(ProtoError::invalid(field, err)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prost::Message;
+
+    use super::*;
+
+    fn felts(n: u64) -> Vec<Felt> {
+        (0..n).map(Felt::from).collect()
+    }
+
+    fn roundtrip(transaction: Transaction) {
+        let tx = TransactionWithHash::new(transaction, Felt::from(0x1234));
+        let encoded = transaction_to_proto(&tx).encode_to_vec();
+        let decoded = proto::Transaction::decode(encoded.as_slice()).unwrap();
+        assert_eq!(transaction_from_proto(decoded).unwrap(), tx);
+    }
+
+    #[test]
+    fn test_transaction_roundtrip() {
+        let resource_bounds = ResourceBoundsMapping {
+            l1_gas: ResourceBounds { max_amount: u64::MAX, max_price_per_unit: u128::MAX },
+            l2_gas: ResourceBounds { max_amount: 0, max_price_per_unit: 0 },
+        };
+
+        roundtrip(Transaction::Declare(DeclareTransaction::V0(DeclareTransactionV0 {
+            sender_address: Felt::ONE,
+            max_fee: Felt::TWO,
+            signature: felts(2),
+            class_hash: Felt::THREE,
+        })));
+        roundtrip(Transaction::Declare(DeclareTransaction::V1(DeclareTransactionV1 {
+            sender_address: Felt::ONE,
+            max_fee: Felt::TWO,
+            signature: felts(2),
+            nonce: Felt::THREE,
+            class_hash: Felt::MAX,
+        })));
+        roundtrip(Transaction::Declare(DeclareTransaction::V2(DeclareTransactionV2 {
+            sender_address: Felt::ONE,
+            compiled_class_hash: Felt::TWO,
+            max_fee: Felt::THREE,
+            signature: felts(2),
+            nonce: Felt::from(4),
+            class_hash: Felt::from(5),
+        })));
+        roundtrip(Transaction::Declare(DeclareTransaction::V3(DeclareTransactionV3 {
+            sender_address: Felt::ONE,
+            compiled_class_hash: Felt::TWO,
+            signature: felts(2),
+            nonce: Felt::THREE,
+            class_hash: Felt::from(4),
+            resource_bounds: resource_bounds.clone(),
+            tip: 5,
+            paymaster_data: felts(1),
+            account_deployment_data: felts(3),
+            nonce_data_availability_mode: DataAvailabilityMode::L2,
+            fee_data_availability_mode: DataAvailabilityMode::L1,
+        })));
+        roundtrip(Transaction::Deploy(DeployTransaction {
+            version: Felt::ONE,
+            contract_address_salt: Felt::TWO,
+            constructor_calldata: felts(4),
+            class_hash: Felt::THREE,
+        }));
+        roundtrip(Transaction::DeployAccount(DeployAccountTransaction::V1(DeployAccountTransactionV1 {
+            max_fee: Felt::ONE,
+            signature: felts(2),
+            nonce: Felt::ZERO,
+            contract_address_salt: Felt::TWO,
+            constructor_calldata: felts(3),
+            class_hash: Felt::THREE,
+        })));
+        roundtrip(Transaction::DeployAccount(DeployAccountTransaction::V3(DeployAccountTransactionV3 {
+            signature: felts(2),
+            nonce: Felt::ZERO,
+            contract_address_salt: Felt::ONE,
+            constructor_calldata: felts(3),
+            class_hash: Felt::TWO,
+            resource_bounds: resource_bounds.clone(),
+            tip: 0,
+            paymaster_data: vec![],
+            nonce_data_availability_mode: DataAvailabilityMode::L1,
+            fee_data_availability_mode: DataAvailabilityMode::L2,
+        })));
+        roundtrip(Transaction::Invoke(InvokeTransaction::V0(InvokeTransactionV0 {
+            max_fee: Felt::ONE,
+            signature: felts(2),
+            contract_address: Felt::TWO,
+            entry_point_selector: Felt::THREE,
+            calldata: felts(5),
+        })));
+        roundtrip(Transaction::Invoke(InvokeTransaction::V1(InvokeTransactionV1 {
+            sender_address: Felt::ONE,
+            calldata: felts(5),
+            max_fee: Felt::TWO,
+            signature: felts(2),
+            nonce: Felt::THREE,
+        })));
+        roundtrip(Transaction::Invoke(InvokeTransaction::V3(InvokeTransactionV3 {
+            sender_address: Felt::ONE,
+            calldata: felts(5),
+            signature: felts(2),
+            nonce: Felt::TWO,
+            resource_bounds,
+            tip: u64::MAX,
+            paymaster_data: felts(2),
+            account_deployment_data: vec![],
+            nonce_data_availability_mode: DataAvailabilityMode::L1,
+            fee_data_availability_mode: DataAvailabilityMode::L1,
+        })));
+        roundtrip(Transaction::L1Handler(L1HandlerTransaction {
+            version: Felt::ZERO,
+            nonce: 42,
+            contract_address: Felt::ONE,
+            entry_point_selector: Felt::TWO,
+            calldata: felts(3),
+        }));
+    }
+
+    #[test]
+    fn test_transaction_invalid_field() {
+        let tx = TransactionWithHash::new(
+            Transaction::L1Handler(L1HandlerTransaction {
+                version: Felt::ZERO,
+                nonce: 0,
+                contract_address: Felt::ONE,
+                entry_point_selector: Felt::TWO,
+                calldata: vec![],
+            }),
+            Felt::ONE,
+        );
+        let mut encoded = transaction_to_proto(&tx);
+        let Some(Txn::L1Handler(l1_handler)) = &mut encoded.txn else { unreachable!() };
+        l1_handler.nonce = felt_to_proto(&Felt::MAX);
+
+        assert!(matches!(transaction_from_proto(encoded), Err(ProtoError::InvalidField { field: "nonce", .. })));
+    }
+}
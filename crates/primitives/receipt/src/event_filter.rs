@@ -0,0 +1,81 @@
+use starknet_types_core::felt::Felt;
+
+use crate::Event;
+
+/// Filter on the emitter and the keys of events, as in `starknet_getEvents`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EventFilter {
+    /// Only match the events emitted by this contract.
+    pub from_address: Option<Felt>,
+    /// The keys to match, position by position: the key of an event at a position must be one of the keys given
+    /// for this position, and an empty list matches any key. The events with fewer keys than positions never match.
+    pub keys: Vec<Vec<Felt>>,
+}
+
+impl EventFilter {
+    pub fn new(from_address: Option<Felt>, keys: Vec<Vec<Felt>>) -> Self {
+        Self { from_address, keys }
+    }
+
+    pub fn matches(&self, event: &Event) -> bool {
+        self.matches_parts(&event.from_address, &event.keys)
+    }
+
+    /// Same as [`EventFilter::matches`], for an event given by its emitter and its keys.
+    pub fn matches_parts(&self, from_address: &Felt, keys: &[Felt]) -> bool {
+        self.from_address.as_ref().map_or(true, |address| address == from_address)
+            && keys.len() >= self.keys.len()
+            && self.keys.iter().zip(keys).all(|(allowed, key)| allowed.is_empty() || allowed.contains(key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(from_address: u64, keys: &[u64]) -> Event {
+        Event { from_address: from_address.into(), keys: keys.iter().map(|&key| key.into()).collect(), data: vec![] }
+    }
+
+    fn keys(keys: &[&[u64]]) -> Vec<Vec<Felt>> {
+        keys.iter().map(|keys| keys.iter().map(|&key| key.into()).collect()).collect()
+    }
+
+    #[test]
+    fn test_empty_filter() {
+        let filter = EventFilter::default();
+        assert!(filter.matches(&event(1, &[])));
+        assert!(filter.matches(&event(2, &[3, 4])));
+    }
+
+    #[test]
+    fn test_address() {
+        let filter = EventFilter::new(Some(Felt::ONE), vec![]);
+        assert!(filter.matches(&event(1, &[3])));
+        assert!(!filter.matches(&event(2, &[3])));
+    }
+
+    #[test]
+    fn test_keys() {
+        let filter = EventFilter::new(None, keys(&[&[3, 4], &[], &[5]]));
+        assert!(filter.matches(&event(1, &[3, 7, 5])));
+        assert!(filter.matches(&event(1, &[4, 8, 5, 9])));
+        assert!(!filter.matches(&event(1, &[5, 7, 5])));
+        assert!(!filter.matches(&event(1, &[3, 7, 6])));
+        // fewer keys than positions
+        assert!(!filter.matches(&event(1, &[3, 7])));
+
+        let filter = EventFilter::new(None, keys(&[&[], &[]]));
+        assert!(filter.matches(&event(1, &[3, 4])));
+        assert!(!filter.matches(&event(1, &[3])));
+    }
+
+    #[test]
+    fn test_address_and_keys() {
+        let filter = EventFilter::new(Some(Felt::TWO), keys(&[&[3]]));
+        assert!(filter.matches(&event(2, &[3])));
+        assert!(!filter.matches(&event(1, &[3])));
+        assert!(!filter.matches(&event(2, &[4])));
+        assert!(filter.matches_parts(&Felt::TWO, &[Felt::THREE]));
+    }
+}
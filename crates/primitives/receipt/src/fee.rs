@@ -0,0 +1,112 @@
+use blockifier::transaction::objects::FeeType;
+
+use crate::{FeePayment, PriceUnit};
+
+impl From<FeeType> for PriceUnit {
+    fn from(fee_type: FeeType) -> Self {
+        match fee_type {
+            FeeType::Eth => PriceUnit::Wei,
+            FeeType::Strk => PriceUnit::Fri,
+        }
+    }
+}
+
+impl From<PriceUnit> for FeeType {
+    fn from(unit: PriceUnit) -> Self {
+        match unit {
+            PriceUnit::Wei => FeeType::Eth,
+            PriceUnit::Fri => FeeType::Strk,
+        }
+    }
+}
+
+/// A fee, split into the L1 gas and the L1 data gas it pays for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeBreakdown {
+    pub gas_consumed: u128,
+    pub gas_price: u128,
+    pub data_gas_consumed: u128,
+    pub data_gas_price: u128,
+    pub unit: PriceUnit,
+}
+
+impl FeeBreakdown {
+    /// Splits a fee charged for a transaction: the data gas consumed is paid at the data gas price, and the rest of
+    /// the fee is gas, rounded down.
+    pub fn from_fee(
+        fee: u128,
+        data_gas_consumed: u128,
+        gas_price: u128,
+        data_gas_price: u128,
+        unit: PriceUnit,
+    ) -> Self {
+        let data_gas_fee = data_gas_consumed.saturating_mul(data_gas_price);
+        let gas_consumed = fee.saturating_sub(data_gas_fee) / gas_price.max(1);
+        Self { gas_consumed, gas_price, data_gas_consumed, data_gas_price, unit }
+    }
+
+    pub fn gas_fee(&self) -> u128 {
+        self.gas_consumed.saturating_mul(self.gas_price)
+    }
+
+    pub fn data_gas_fee(&self) -> u128 {
+        self.data_gas_consumed.saturating_mul(self.data_gas_price)
+    }
+
+    pub fn overall_fee(&self) -> u128 {
+        self.gas_fee().saturating_add(self.data_gas_fee())
+    }
+
+    pub fn fee_payment(&self) -> FeePayment {
+        FeePayment { amount: self.overall_fee().into(), unit: self.unit }
+    }
+
+    /// The same resources, paid at the prices of another unit.
+    pub fn in_unit(self, unit: PriceUnit, gas_price: u128, data_gas_price: u128) -> Self {
+        Self { gas_price, data_gas_price, unit, ..self }
+    }
+}
+
+/// Converts an amount into another unit, at the rate given by the prices of a same resource in both units, for
+/// example the L1 gas prices of a block in Wei and in Fri. The result is rounded down.
+pub fn convert_amount(amount: u128, price: u128, price_in_other_unit: u128) -> u128 {
+    let price = price.max(1);
+    (amount / price)
+        .saturating_mul(price_in_other_unit)
+        .saturating_add((amount % price).saturating_mul(price_in_other_unit) / price)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fee_breakdown() {
+        let breakdown = FeeBreakdown::from_fee(1_000 * 7 + 30 * 3 + 5, 30, 7, 3, PriceUnit::Wei);
+        assert_eq!(
+            breakdown,
+            FeeBreakdown {
+                gas_consumed: 1_000,
+                gas_price: 7,
+                data_gas_consumed: 30,
+                data_gas_price: 3,
+                unit: PriceUnit::Wei
+            }
+        );
+        assert_eq!(breakdown.gas_fee(), 7_000);
+        assert_eq!(breakdown.data_gas_fee(), 90);
+        assert_eq!(breakdown.fee_payment(), FeePayment { amount: 7_090u128.into(), unit: PriceUnit::Wei });
+
+        let breakdown = breakdown.in_unit(PriceUnit::Fri, 70, 30);
+        assert_eq!(breakdown.overall_fee(), 70_900);
+        assert_eq!(FeeType::from(breakdown.unit), FeeType::Strk);
+    }
+
+    #[test]
+    fn test_convert_amount() {
+        assert_eq!(convert_amount(7_090, 7, 70), 70_900);
+        assert_eq!(convert_amount(10, 3, 2), 6);
+        assert_eq!(convert_amount(u128::MAX, 1, 2), u128::MAX);
+        assert_eq!(convert_amount(u128::MAX, 1_000_000_000, 1_000_000_000), u128::MAX);
+    }
+}
@@ -0,0 +1,180 @@
+use blockifier::execution::call_info::CallInfo;
+use blockifier::transaction::account_transaction::AccountTransaction;
+use blockifier::transaction::objects::{HasRelatedFeeType, TransactionExecutionInfo};
+use blockifier::transaction::transaction_execution::Transaction;
+use dp_convert::{felt_to_u64, ToFelt};
+
+use crate::{
+    DataAvailabilityResources, DeclareTransactionReceipt, DeployAccountTransactionReceipt, Event, ExecutionResources,
+    ExecutionResult, FeePayment, InvokeTransactionReceipt, L1HandlerTransactionReceipt, MsgToL1, MsgToL2,
+    TransactionReceipt,
+};
+
+impl TransactionReceipt {
+    /// The receipt of a transaction executed by the blockifier, as the sequencer would produce it.
+    pub fn from_blockifier_execution_info(
+        transaction: &Transaction,
+        execution_info: &TransactionExecutionInfo,
+    ) -> Self {
+        let transaction_hash = match transaction {
+            Transaction::AccountTransaction(AccountTransaction::Declare(tx)) => tx.tx_hash,
+            Transaction::AccountTransaction(AccountTransaction::DeployAccount(tx)) => tx.tx_hash,
+            Transaction::AccountTransaction(AccountTransaction::Invoke(tx)) => tx.tx_hash,
+            Transaction::L1HandlerTransaction(tx) => tx.tx_hash,
+        }
+        .to_felt();
+        let fee_type = match transaction {
+            Transaction::AccountTransaction(tx) => tx.fee_type(),
+            Transaction::L1HandlerTransaction(tx) => tx.fee_type(),
+        };
+        let actual_fee = FeePayment { amount: execution_info.actual_fee.0.into(), unit: fee_type.into() };
+        let messages_sent = messages_sent_from_execution_info(execution_info);
+        let events = events_from_execution_info(execution_info);
+        let execution_resources = execution_resources(execution_info);
+        let execution_result = match &execution_info.revert_error {
+            Some(reason) => ExecutionResult::Reverted { reason: reason.clone() },
+            None => ExecutionResult::Succeeded,
+        };
+
+        match transaction {
+            Transaction::AccountTransaction(AccountTransaction::Declare(_)) => {
+                TransactionReceipt::Declare(DeclareTransactionReceipt {
+                    transaction_hash,
+                    actual_fee,
+                    messages_sent,
+                    events,
+                    execution_resources,
+                    execution_result,
+                })
+            }
+            Transaction::AccountTransaction(AccountTransaction::DeployAccount(tx)) => {
+                TransactionReceipt::DeployAccount(DeployAccountTransactionReceipt {
+                    transaction_hash,
+                    actual_fee,
+                    messages_sent,
+                    events,
+                    execution_resources,
+                    execution_result,
+                    contract_address: tx.contract_address.to_felt(),
+                })
+            }
+            Transaction::AccountTransaction(AccountTransaction::Invoke(_)) => {
+                TransactionReceipt::Invoke(InvokeTransactionReceipt {
+                    transaction_hash,
+                    actual_fee,
+                    messages_sent,
+                    events,
+                    execution_resources,
+                    execution_result,
+                })
+            }
+            Transaction::L1HandlerTransaction(tx) => {
+                let calldata: Vec<_> = tx.tx.calldata.0.iter().map(ToFelt::to_felt).collect();
+                let msg_to_l2 = MsgToL2::from_l1_handler(
+                    tx.tx.contract_address.to_felt(),
+                    tx.tx.entry_point_selector.to_felt(),
+                    &calldata,
+                    felt_to_u64(&tx.tx.nonce.to_felt()).unwrap_or_default(),
+                );
+                TransactionReceipt::L1Handler(L1HandlerTransactionReceipt {
+                    message_hash: msg_to_l2.compute_hash().try_into().unwrap_or_default(),
+                    transaction_hash,
+                    actual_fee,
+                    messages_sent,
+                    events,
+                    execution_resources,
+                    execution_result,
+                })
+            }
+        }
+    }
+}
+
+/// The top level calls of a transaction, in the order their events and messages appear in the receipt.
+fn top_level_calls(execution_info: &TransactionExecutionInfo) -> impl Iterator<Item = &CallInfo> {
+    [&execution_info.validate_call_info, &execution_info.execute_call_info, &execution_info.fee_transfer_call_info]
+        .into_iter()
+        .flatten()
+}
+
+/// A call and all its inner calls.
+fn call_tree(call_info: &CallInfo) -> Vec<&CallInfo> {
+    std::iter::once(call_info).chain(call_info.inner_calls.iter().flat_map(call_tree)).collect()
+}
+
+/// Events of a transaction executed by the blockifier, ordered as in its receipt: by top level call, then by order
+/// of emission.
+pub fn events_from_execution_info(execution_info: &TransactionExecutionInfo) -> Vec<Event> {
+    top_level_calls(execution_info)
+        .flat_map(|call_info| {
+            let mut events: Vec<_> = call_tree(call_info)
+                .into_iter()
+                .flat_map(|call| {
+                    call.execution.events.iter().map(|event| {
+                        let event_content = Event {
+                            from_address: call.call.storage_address.to_felt(),
+                            keys: event.event.keys.iter().map(ToFelt::to_felt).collect(),
+                            data: event.event.data.0.iter().map(ToFelt::to_felt).collect(),
+                        };
+                        (event.order, event_content)
+                    })
+                })
+                .collect();
+            events.sort_by_key(|(order, _)| *order);
+            events.into_iter().map(|(_, event)| event)
+        })
+        .collect()
+}
+
+/// Messages sent to L1 by a transaction executed by the blockifier, ordered as in its receipt: by top level call,
+/// then by order of emission.
+pub fn messages_sent_from_execution_info(execution_info: &TransactionExecutionInfo) -> Vec<MsgToL1> {
+    top_level_calls(execution_info)
+        .flat_map(|call_info| {
+            let mut messages: Vec<_> = call_tree(call_info)
+                .into_iter()
+                .flat_map(|call| {
+                    call.execution.l2_to_l1_messages.iter().map(|message| {
+                        let message_content = MsgToL1 {
+                            from_address: call.call.storage_address.to_felt(),
+                            to_address: message.message.to_address.0.to_felt(),
+                            payload: message.message.payload.0.iter().map(ToFelt::to_felt).collect(),
+                        };
+                        (message.order, message_content)
+                    })
+                })
+                .collect();
+            messages.sort_by_key(|(order, _)| *order);
+            messages.into_iter().map(|(_, message)| message)
+        })
+        .collect()
+}
+
+/// The steps, builtins and L1 gas are the ones the fee is charged for, including the resources of the OS. The memory
+/// holes are the ones of the calls, the resources of a call include the ones of its inner calls.
+fn execution_resources(execution_info: &TransactionExecutionInfo) -> ExecutionResources {
+    let resources = &execution_info.actual_resources.0;
+    let resource = |name: &str| resources.get(name).map(|&value| value as u64);
+    let memory_holes: usize = top_level_calls(execution_info).map(|call| call.resources.n_memory_holes).sum();
+
+    ExecutionResources {
+        steps: resource("n_steps").unwrap_or_default(),
+        memory_holes: (memory_holes != 0).then_some(memory_holes as u64),
+        range_check_builtin_applications: resource("range_check_builtin"),
+        pedersen_builtin_applications: resource("pedersen_builtin"),
+        poseidon_builtin_applications: resource("poseidon_builtin"),
+        ec_op_builtin_applications: resource("ec_op_builtin"),
+        ecdsa_builtin_applications: resource("ecdsa_builtin"),
+        bitwise_builtin_applications: resource("bitwise_builtin"),
+        keccak_builtin_applications: resource("keccak_builtin"),
+        segment_arena_builtin: resource("segment_arena_builtin"),
+        data_availability: DataAvailabilityResources {
+            l1_gas: execution_info.da_gas.l1_gas as u64,
+            l1_data_gas: execution_info.da_gas.l1_data_gas as u64,
+        },
+        total_gas_consumed: DataAvailabilityResources {
+            l1_gas: resource("gas_weight").unwrap_or_default(),
+            l1_data_gas: resource("l1_blob_gas_usage").unwrap_or_default(),
+        },
+    }
+}
@@ -4,7 +4,7 @@ use starknet_core::types::Felt;
 use crate::{
     DataAvailabilityResources, DeclareTransactionReceipt, DeployAccountTransactionReceipt, DeployTransactionReceipt,
     Event, ExecutionResources, ExecutionResult, FeePayment, InvokeTransactionReceipt, L1HandlerTransactionReceipt,
-    MsgToL1, PriceUnit, TransactionReceipt,
+    MsgToL1, MsgToL2, PriceUnit, TransactionReceipt,
 };
 
 impl TransactionReceipt {
@@ -29,17 +29,11 @@ impl TransactionReceipt {
                 TransactionReceipt::Invoke(InvokeTransactionReceipt::from(receipt))
             }
             starknet_providers::sequencer::models::TransactionType::L1Handler(tx) => {
-                let (from_address, payload) = tx.calldata.split_first().unwrap_or((&Felt::ZERO, &[]));
                 let nonce_bytes = tx.nonce.unwrap_or_default().to_bytes_le();
                 let nonce = u64::from_le_bytes(nonce_bytes[..8].try_into().unwrap());
-                let msg_to_l2 = starknet_core::types::MsgToL2 {
-                    from_address: (*from_address).try_into().unwrap_or(Felt::ZERO.try_into().unwrap()),
-                    to_address: tx.contract_address,
-                    selector: tx.entry_point_selector,
-                    payload: payload.to_vec(),
-                    nonce,
-                };
-                let message_hash = msg_to_l2.hash();
+                let message_hash =
+                    MsgToL2::from_l1_handler(tx.contract_address, tx.entry_point_selector, &tx.calldata, nonce)
+                        .compute_hash();
                 TransactionReceipt::L1Handler(L1HandlerTransactionReceipt::from_provider(receipt, message_hash))
             }
         }
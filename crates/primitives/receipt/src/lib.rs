@@ -1,7 +1,15 @@
+mod event_filter;
+mod fee;
+mod from_blockifier;
 mod from_starknet_core;
 mod from_starknet_provider;
+mod message_hash;
 mod to_starknet_core;
 
+pub use event_filter::EventFilter;
+pub use fee::{convert_amount, FeeBreakdown};
+pub use from_blockifier::{events_from_execution_info, messages_sent_from_execution_info};
+pub use message_hash::MsgToL2;
 use serde::{Deserialize, Serialize};
 use starknet_core::utils::starknet_keccak;
 use starknet_types_core::{
@@ -90,7 +98,16 @@ impl TransactionReceipt {
     }
 
     pub fn compute_hash(&self) -> Felt {
-        Poseidon::hash_array(&[
+        Poseidon::hash_array(&self.hash_elements())
+    }
+
+    /// The elements hashed together to compute the receipt hash, in order:
+    /// transaction hash, actual fee, messages sent hash, execution result hash,
+    /// L2 gas consumption, L1 gas consumption and L1 data gas consumption.
+    ///
+    /// This is mostly useful to debug receipt commitment mismatches.
+    pub fn hash_elements(&self) -> [Felt; 7] {
+        [
             self.transaction_hash(),
             self.actual_fee().amount,
             compute_messages_sent_hash(self.messages_sent()),
@@ -98,7 +115,7 @@ impl TransactionReceipt {
             Felt::ZERO, // L2 gas consumption.
             self.total_gas_consumed().l1_gas.into(),
             self.total_gas_consumed().l1_data_gas.into(),
-        ])
+        ]
     }
 }
 
@@ -0,0 +1,91 @@
+use sha3::{Digest, Keccak256};
+use starknet_core::types::Hash256;
+use starknet_types_core::felt::Felt;
+
+use crate::MsgToL1;
+
+/// A message sent by an L1 contract to the `l1_handler` entry point `selector` of the L2 contract `to_address`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MsgToL2 {
+    pub from_address: Felt,
+    pub to_address: Felt,
+    pub selector: Felt,
+    pub payload: Vec<Felt>,
+    pub nonce: u64,
+}
+
+impl MsgToL2 {
+    /// The message consumed by an `l1_handler` transaction, whose calldata is the sender of the message followed by
+    /// its payload.
+    pub fn from_l1_handler(to_address: Felt, selector: Felt, calldata: &[Felt], nonce: u64) -> Self {
+        let (from_address, payload) = calldata.split_first().unwrap_or((&Felt::ZERO, &[]));
+        Self { from_address: *from_address, to_address, selector, payload: payload.to_vec(), nonce }
+    }
+
+    /// The hash of the message, as computed by the Starknet core contract on L1 when the message is sent.
+    pub fn compute_hash(&self) -> Hash256 {
+        let mut hasher = Keccak256::new();
+        hasher.update(self.from_address.to_bytes_be());
+        hasher.update(self.to_address.to_bytes_be());
+        hasher.update(Felt::from(self.nonce).to_bytes_be());
+        hasher.update(self.selector.to_bytes_be());
+        hash_payload(hasher, &self.payload)
+    }
+}
+
+impl MsgToL1 {
+    /// The hash of the message, as computed by the Starknet core contract on L1 when the message is consumed.
+    pub fn compute_hash(&self) -> Hash256 {
+        let mut hasher = Keccak256::new();
+        hasher.update(self.from_address.to_bytes_be());
+        hasher.update(self.to_address.to_bytes_be());
+        hash_payload(hasher, &self.payload)
+    }
+}
+
+fn hash_payload(mut hasher: Keccak256, payload: &[Felt]) -> Hash256 {
+    hasher.update(Felt::from(payload.len()).to_bytes_be());
+    for felt in payload {
+        hasher.update(felt.to_bytes_be());
+    }
+    Hash256::from_bytes(hasher.finalize().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_msg_to_l2_hash() {
+        let message = MsgToL2::from_l1_handler(
+            Felt::from_hex_unchecked("0x73314940630fd6dcda0d772d4c972c4e0a9946bef9dabf4ef84eda8ef542b82"),
+            Felt::from_hex_unchecked("0x2d757788a8d8d6f21d1cd40bce38a8222d70654214e96ff95d8086e684fbee5"),
+            &[
+                Felt::from_hex_unchecked("0xc3511006c04ef1d78af4c8e0e74ec18a6e64ff9e"),
+                Felt::from_hex_unchecked("0x689ead7d814e51ed93644bc145f0754839b8dcb340027ce0c30953f38f55d7"),
+                Felt::from_hex_unchecked("0x2c68af0bb140000"),
+                Felt::ZERO,
+            ],
+            775628,
+        );
+
+        assert_eq!(
+            message.compute_hash(),
+            Hash256::from_hex("0xc51a543ef9563ad2545342b390b67edfcddf9886aa36846cf70382362fc5fab3").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_msg_to_l1_hash() {
+        let message = MsgToL1 {
+            from_address: Felt::from_hex_unchecked("0x49d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7"),
+            to_address: Felt::from_hex_unchecked("0xae0ee0a63a2ce6baeeffe56e7714fb4efe48d419"),
+            payload: vec![Felt::ZERO, Felt::from(0x1234), Felt::from(5)],
+        };
+
+        assert_eq!(
+            message.compute_hash(),
+            Hash256::from_hex("0x4a4e48ba7a51ea5fb00555553b09ad7de289f3d50f3340fa275596d04bd75367").unwrap()
+        );
+    }
+}
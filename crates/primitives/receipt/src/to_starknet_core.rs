@@ -1,7 +1,7 @@
 use crate::{
     DataAvailabilityResources, DeclareTransactionReceipt, DeployAccountTransactionReceipt, DeployTransactionReceipt,
-    Event, ExecutionResources, ExecutionResult, FeePayment, InvokeTransactionReceipt, L1HandlerTransactionReceipt,
-    MsgToL1, PriceUnit, TransactionReceipt,
+    Event, ExecutionResources, ExecutionResult, FeeBreakdown, FeePayment, InvokeTransactionReceipt,
+    L1HandlerTransactionReceipt, MsgToL1, PriceUnit, TransactionReceipt,
 };
 
 impl TransactionReceipt {
@@ -123,6 +123,19 @@ impl From<FeePayment> for starknet_core::types::FeePayment {
     }
 }
 
+impl From<FeeBreakdown> for starknet_core::types::FeeEstimate {
+    fn from(fee: FeeBreakdown) -> Self {
+        Self {
+            gas_consumed: fee.gas_consumed.into(),
+            gas_price: fee.gas_price.into(),
+            data_gas_consumed: fee.data_gas_consumed.into(),
+            data_gas_price: fee.data_gas_price.into(),
+            overall_fee: fee.overall_fee().into(),
+            unit: fee.unit.into(),
+        }
+    }
+}
+
 impl From<PriceUnit> for starknet_core::types::PriceUnit {
     fn from(unit: PriceUnit) -> Self {
         match unit {
@@ -1,4 +1,5 @@
 mod into_starknet_core;
+mod squash;
 
 use starknet_types_core::{
     felt::Felt,
@@ -19,7 +20,7 @@ pub struct PendingStateUpdate {
     pub state_diff: StateDiff,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct StateDiff {
     pub storage_diffs: Vec<ContractStorageDiffItem>,
     pub deprecated_declared_classes: Vec<Felt>,
@@ -0,0 +1,150 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use starknet_types_core::felt::Felt;
+
+use crate::{
+    ContractStorageDiffItem, DeclaredClassItem, DeployedContractItem, NonceUpdate, ReplacedClassItem, StateDiff,
+    StorageEntry,
+};
+
+impl StateDiff {
+    /// Applies `other`, the state diff of a later block, on top of this state diff. See [`StateDiff::squash`].
+    pub fn merge(&mut self, other: StateDiff) {
+        let this = std::mem::take(self);
+        *self = Self::squash([this, other]);
+    }
+
+    /// Collapses the state diffs of consecutive blocks, in block order, into the state diff of the whole range.
+    ///
+    /// The last write of a storage key, the last nonce of a contract, and the last compiled class hash of a class
+    /// win. A contract deployed within the range stays a deployed contract, with the class hash it has at the end of
+    /// the range: its class replacements are not part of the squashed diff. The updates of the result are sorted by
+    /// address, key and class hash.
+    pub fn squash(state_diffs: impl IntoIterator<Item = StateDiff>) -> StateDiff {
+        let mut storage_diffs: BTreeMap<Felt, BTreeMap<Felt, Felt>> = BTreeMap::new();
+        let mut deprecated_declared_classes = BTreeSet::new();
+        let mut declared_classes = BTreeMap::new();
+        let mut deployed_contracts = BTreeMap::new();
+        let mut replaced_classes = BTreeMap::new();
+        let mut nonces = BTreeMap::new();
+
+        for state_diff in state_diffs {
+            for item in state_diff.storage_diffs {
+                let entries = storage_diffs.entry(item.address).or_default();
+                entries.extend(item.storage_entries.into_iter().map(|entry| (entry.key, entry.value)));
+            }
+            deprecated_declared_classes.extend(state_diff.deprecated_declared_classes);
+            declared_classes.extend(
+                state_diff.declared_classes.into_iter().map(|item| (item.class_hash, item.compiled_class_hash)),
+            );
+            for item in state_diff.deployed_contracts {
+                replaced_classes.remove(&item.address);
+                deployed_contracts.insert(item.address, item.class_hash);
+            }
+            for item in state_diff.replaced_classes {
+                match deployed_contracts.get_mut(&item.contract_address) {
+                    Some(class_hash) => *class_hash = item.class_hash,
+                    None => {
+                        replaced_classes.insert(item.contract_address, item.class_hash);
+                    }
+                }
+            }
+            nonces.extend(state_diff.nonces.into_iter().map(|item| (item.contract_address, item.nonce)));
+        }
+
+        StateDiff {
+            storage_diffs: storage_diffs
+                .into_iter()
+                .map(|(address, entries)| ContractStorageDiffItem {
+                    address,
+                    storage_entries: entries.into_iter().map(|(key, value)| StorageEntry { key, value }).collect(),
+                })
+                .collect(),
+            deprecated_declared_classes: deprecated_declared_classes.into_iter().collect(),
+            declared_classes: declared_classes
+                .into_iter()
+                .map(|(class_hash, compiled_class_hash)| DeclaredClassItem { class_hash, compiled_class_hash })
+                .collect(),
+            deployed_contracts: deployed_contracts
+                .into_iter()
+                .map(|(address, class_hash)| DeployedContractItem { address, class_hash })
+                .collect(),
+            replaced_classes: replaced_classes
+                .into_iter()
+                .map(|(contract_address, class_hash)| ReplacedClassItem { contract_address, class_hash })
+                .collect(),
+            nonces: nonces
+                .into_iter()
+                .map(|(contract_address, nonce)| NonceUpdate { contract_address, nonce })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn storage(address: u64, entries: &[(u64, u64)]) -> ContractStorageDiffItem {
+        ContractStorageDiffItem {
+            address: address.into(),
+            storage_entries: entries
+                .iter()
+                .map(|&(key, value)| StorageEntry { key: key.into(), value: value.into() })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_squash_storage_and_nonces() {
+        let first = StateDiff {
+            storage_diffs: vec![storage(2, &[(1, 10), (2, 20)]), storage(1, &[(1, 1)])],
+            nonces: vec![NonceUpdate { contract_address: Felt::ONE, nonce: Felt::ONE }],
+            ..Default::default()
+        };
+        let second = StateDiff {
+            storage_diffs: vec![storage(2, &[(2, 0), (3, 30)])],
+            nonces: vec![NonceUpdate { contract_address: Felt::ONE, nonce: Felt::TWO }],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            StateDiff::squash([first, second]),
+            StateDiff {
+                storage_diffs: vec![storage(1, &[(1, 1)]), storage(2, &[(1, 10), (2, 0), (3, 30)])],
+                nonces: vec![NonceUpdate { contract_address: Felt::ONE, nonce: Felt::TWO }],
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_merge_classes() {
+        let mut state_diff = StateDiff {
+            deprecated_declared_classes: vec![Felt::ONE],
+            declared_classes: vec![DeclaredClassItem { class_hash: Felt::TWO, compiled_class_hash: Felt::THREE }],
+            deployed_contracts: vec![DeployedContractItem { address: Felt::ONE, class_hash: Felt::ONE }],
+            replaced_classes: vec![ReplacedClassItem { contract_address: Felt::TWO, class_hash: Felt::ONE }],
+            ..Default::default()
+        };
+        state_diff.merge(StateDiff {
+            deprecated_declared_classes: vec![Felt::ONE],
+            replaced_classes: vec![
+                ReplacedClassItem { contract_address: Felt::ONE, class_hash: Felt::TWO },
+                ReplacedClassItem { contract_address: Felt::TWO, class_hash: Felt::TWO },
+            ],
+            ..Default::default()
+        });
+
+        assert_eq!(
+            state_diff,
+            StateDiff {
+                deprecated_declared_classes: vec![Felt::ONE],
+                declared_classes: vec![DeclaredClassItem { class_hash: Felt::TWO, compiled_class_hash: Felt::THREE }],
+                deployed_contracts: vec![DeployedContractItem { address: Felt::ONE, class_hash: Felt::TWO }],
+                replaced_classes: vec![ReplacedClassItem { contract_address: Felt::TWO, class_hash: Felt::TWO }],
+                ..Default::default()
+            }
+        );
+    }
+}
@@ -1,6 +1,7 @@
-use crate::{to_starknet_api::TransactionApiError, Transaction, TransactionWithHash};
+use crate::{is_query, to_starknet_api::TransactionApiError, Transaction, TransactionWithHash};
+use blockifier::execution::contract_class::ClassInfo;
 use blockifier::{execution::errors::ContractClassError, transaction::errors::TransactionExecutionError};
-use dp_class::{to_blockifier_class, ClassHash, ToCompiledClass};
+use dp_class::{compilation_cache, to_blockifier_class, ClassHash, ToCompiledClass};
 use dp_convert::ToStarkFelt;
 use starknet_api::transaction::TransactionHash;
 use starknet_types_core::felt::Felt;
@@ -26,42 +27,28 @@ pub fn broadcasted_to_blockifier(
     chain_id: Felt,
 ) -> Result<blockifier::transaction::transaction_execution::Transaction, BroadcastedToBlockifierError> {
     let (class_info, class_hash) = match &transaction {
-        starknet_core::types::BroadcastedTransaction::Declare(tx) => match tx {
-            starknet_core::types::BroadcastedDeclareTransaction::V1(tx) => (
-                Some(blockifier::execution::contract_class::ClassInfo::new(
-                    &to_blockifier_class(
-                        tx.contract_class.compile().map_err(BroadcastedToBlockifierError::CompilationFailed)?,
-                    )?,
-                    0,
-                    0,
-                )?),
-                Some(
-                    tx.contract_class
+        starknet_core::types::BroadcastedTransaction::Declare(tx) => {
+            let (class_hash, class_info) = match tx {
+                starknet_core::types::BroadcastedDeclareTransaction::V1(tx) => {
+                    let class_hash = tx
+                        .contract_class
                         .class_hash()
-                        .map_err(BroadcastedToBlockifierError::ComputeLegacyClassHashFailed)?,
-                ),
-            ),
-            starknet_core::types::BroadcastedDeclareTransaction::V2(tx) => (
-                Some(blockifier::execution::contract_class::ClassInfo::new(
-                    &to_blockifier_class(
-                        tx.contract_class.compile().map_err(BroadcastedToBlockifierError::CompilationFailed)?,
-                    )?,
-                    tx.contract_class.sierra_program.len(),
-                    tx.contract_class.abi.len(),
-                )?),
-                Some(tx.contract_class.class_hash()),
-            ),
-            starknet_core::types::BroadcastedDeclareTransaction::V3(tx) => (
-                Some(blockifier::execution::contract_class::ClassInfo::new(
-                    &to_blockifier_class(
-                        tx.contract_class.compile().map_err(BroadcastedToBlockifierError::CompilationFailed)?,
-                    )?,
-                    tx.contract_class.sierra_program.len(),
-                    tx.contract_class.abi.len(),
-                )?),
-                Some(tx.contract_class.class_hash()),
-            ),
-        },
+                        .map_err(BroadcastedToBlockifierError::ComputeLegacyClassHashFailed)?;
+                    (class_hash, compile(class_hash, &*tx.contract_class, 0, 0)?)
+                }
+                starknet_core::types::BroadcastedDeclareTransaction::V2(tx) => {
+                    let class_hash = tx.contract_class.class_hash();
+                    let class = &*tx.contract_class;
+                    (class_hash, compile(class_hash, class, class.sierra_program.len(), class.abi.len())?)
+                }
+                starknet_core::types::BroadcastedDeclareTransaction::V3(tx) => {
+                    let class_hash = tx.contract_class.class_hash();
+                    let class = &*tx.contract_class;
+                    (class_hash, compile(class_hash, class, class.sierra_program.len(), class.abi.len())?)
+                }
+            };
+            (Some(class_info), Some(class_hash))
+        }
         _ => (None, None),
     };
 
@@ -84,20 +71,14 @@ pub fn broadcasted_to_blockifier(
     )?)
 }
 
-fn is_query(transaction: &starknet_core::types::BroadcastedTransaction) -> bool {
-    match transaction {
-        starknet_core::types::BroadcastedTransaction::Invoke(tx) => match tx {
-            starknet_core::types::BroadcastedInvokeTransaction::V1(tx) => tx.is_query,
-            starknet_core::types::BroadcastedInvokeTransaction::V3(tx) => tx.is_query,
-        },
-        starknet_core::types::BroadcastedTransaction::Declare(tx) => match tx {
-            starknet_core::types::BroadcastedDeclareTransaction::V1(tx) => tx.is_query,
-            starknet_core::types::BroadcastedDeclareTransaction::V2(tx) => tx.is_query,
-            starknet_core::types::BroadcastedDeclareTransaction::V3(tx) => tx.is_query,
-        },
-        starknet_core::types::BroadcastedTransaction::DeployAccount(tx) => match tx {
-            starknet_core::types::BroadcastedDeployAccountTransaction::V1(tx) => tx.is_query,
-            starknet_core::types::BroadcastedDeployAccountTransaction::V3(tx) => tx.is_query,
-        },
-    }
+fn compile(
+    class_hash: Felt,
+    class: &impl ToCompiledClass,
+    sierra_program_length: usize,
+    abi_length: usize,
+) -> Result<ClassInfo, BroadcastedToBlockifierError> {
+    let compiled_class = compilation_cache()
+        .get_or_compile(class_hash, class)
+        .map_err(BroadcastedToBlockifierError::CompilationFailed)?;
+    Ok(ClassInfo::new(&to_blockifier_class(compiled_class)?, sierra_program_length, abi_length)?)
 }
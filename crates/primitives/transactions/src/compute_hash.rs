@@ -23,12 +23,16 @@ const L1_GAS: &[u8] = b"L1_GAS";
 const L2_GAS: &[u8] = b"L2_GAS";
 
 impl Transaction {
+    /// Computes the hash of the transaction. With `offset_version`, its version is offset by
+    /// `SIMULATE_TX_VERSION_OFFSET` (2^128) as in the hash of the query transactions, which are only meant for
+    /// simulation and fee estimation. `legacy` selects the hash of the transactions of the first blocks, which has no
+    /// version.
     pub fn compute_hash(&self, chain_id: Felt, offset_version: bool, legacy: bool) -> Felt {
         match self {
             crate::Transaction::Invoke(tx) => tx.compute_hash(chain_id, offset_version, legacy),
             crate::Transaction::L1Handler(tx) => tx.compute_hash(chain_id, offset_version, legacy),
             crate::Transaction::Declare(tx) => tx.compute_hash(chain_id, offset_version),
-            crate::Transaction::Deploy(tx) => tx.compute_hash(chain_id, legacy),
+            crate::Transaction::Deploy(tx) => tx.compute_hash(chain_id, offset_version, legacy),
             crate::Transaction::DeployAccount(tx) => tx.compute_hash(chain_id, offset_version),
         }
     }
@@ -211,7 +215,7 @@ impl DeclareTransactionV0 {
 
 impl DeclareTransactionV1 {
     pub fn compute_hash(&self, chain_id: Felt, offset_version: bool) -> Felt {
-        let version = if offset_version { SIMULATE_TX_VERSION_OFFSET } else { Felt::ONE };
+        let version = if offset_version { SIMULATE_TX_VERSION_OFFSET + Felt::ONE } else { Felt::ONE };
         let class_or_nothing_hash =
             if version == Felt::ZERO { Pedersen::hash_array(&[]) } else { Pedersen::hash_array(&[self.class_hash]) };
 
@@ -361,7 +365,7 @@ impl DeployAccountTransactionV3 {
 }
 
 impl DeployTransaction {
-    pub fn compute_hash(&self, chain_id: Felt, legacy: bool) -> Felt {
+    pub fn compute_hash(&self, chain_id: Felt, offset_version: bool, legacy: bool) -> Felt {
         let contract_address = calculate_contract_address(
             self.contract_address_salt,
             self.class_hash,
@@ -372,7 +376,8 @@ impl DeployTransaction {
         if legacy {
             compute_hash_given_contract_address_legacy(chain_id, contract_address, &self.constructor_calldata)
         } else {
-            compute_hash_given_contract_address(self.version, chain_id, contract_address, &self.constructor_calldata)
+            let version = if offset_version { SIMULATE_TX_VERSION_OFFSET + self.version } else { self.version };
+            compute_hash_given_contract_address(version, chain_id, contract_address, &self.constructor_calldata)
         }
     }
 }
@@ -475,6 +480,8 @@ pub fn calculate_contract_address(
 
 #[cfg(test)]
 mod tests {
+    use dp_convert::ToFelt;
+
     use crate::ResourceBounds;
 
     use super::*;
@@ -512,4 +519,160 @@ mod tests {
             Felt::from_hex_unchecked("0x10000000100000000000000000000000000000000")
         );
     }
+
+    const CHAIN_ID: Felt = crate::MAIN_CHAIN_ID;
+
+    fn felt(hex: &str) -> Felt {
+        Felt::from_hex_unchecked(hex)
+    }
+
+    fn resource_bounds() -> ResourceBoundsMapping {
+        ResourceBoundsMapping {
+            l1_gas: ResourceBounds { max_amount: 0x100, max_price_per_unit: 0x200 },
+            l2_gas: ResourceBounds { max_amount: 0, max_price_per_unit: 0 },
+        }
+    }
+
+    /// Checks the hash of a transaction, and its hash with the query version, against the reference implementation
+    /// of the transaction hashes of the sequencer, `starknet_api::transaction_hash::get_transaction_hash`.
+    fn assert_hashes(tx: Transaction) {
+        let api_tx: starknet_api::transaction::Transaction = (&tx).try_into().unwrap();
+        let chain_id = starknet_api::core::ChainId("SN_MAIN".to_string());
+        for only_query in [false, true] {
+            let expected = starknet_api::transaction_hash::get_transaction_hash(
+                &api_tx,
+                &chain_id,
+                &starknet_api::transaction::TransactionOptions { only_query },
+            )
+            .unwrap();
+            assert_eq!(tx.compute_hash(CHAIN_ID, only_query, false), expected.0.to_felt(), "only_query: {only_query}");
+        }
+    }
+
+    #[test]
+    fn test_query_hash_invoke() {
+        let v0 = InvokeTransactionV0 {
+            max_fee: felt("0x1234"),
+            signature: vec![],
+            contract_address: felt("0x111"),
+            entry_point_selector: felt("0x222"),
+            calldata: vec![Felt::ONE, Felt::TWO, Felt::THREE],
+        };
+        assert_hashes(Transaction::Invoke(InvokeTransaction::V0(v0)));
+
+        let v1 = InvokeTransactionV1 {
+            sender_address: felt("0x111"),
+            calldata: vec![Felt::ONE, Felt::TWO, Felt::THREE],
+            max_fee: felt("0x1234"),
+            signature: vec![],
+            nonce: felt("0x5"),
+        };
+        assert_hashes(Transaction::Invoke(InvokeTransaction::V1(v1)));
+
+        let v3 = InvokeTransactionV3 {
+            sender_address: felt("0x111"),
+            calldata: vec![Felt::ONE, Felt::TWO, Felt::THREE],
+            signature: vec![],
+            nonce: felt("0x5"),
+            resource_bounds: resource_bounds(),
+            tip: 0,
+            paymaster_data: vec![],
+            account_deployment_data: vec![],
+            nonce_data_availability_mode: DataAvailabilityMode::L1,
+            fee_data_availability_mode: DataAvailabilityMode::L1,
+        };
+        assert_hashes(Transaction::Invoke(InvokeTransaction::V3(v3)));
+    }
+
+    #[test]
+    fn test_query_hash_declare() {
+        let v0 = DeclareTransactionV0 {
+            sender_address: felt("0x111"),
+            max_fee: felt("0x1234"),
+            signature: vec![],
+            class_hash: felt("0x333"),
+        };
+        assert_hashes(Transaction::Declare(DeclareTransaction::V0(v0)));
+
+        let v1 = DeclareTransactionV1 {
+            sender_address: felt("0x111"),
+            max_fee: felt("0x1234"),
+            signature: vec![],
+            nonce: felt("0x5"),
+            class_hash: felt("0x333"),
+        };
+        assert_hashes(Transaction::Declare(DeclareTransaction::V1(v1)));
+
+        let v2 = DeclareTransactionV2 {
+            sender_address: felt("0x111"),
+            compiled_class_hash: felt("0x444"),
+            max_fee: felt("0x1234"),
+            signature: vec![],
+            nonce: felt("0x5"),
+            class_hash: felt("0x333"),
+        };
+        assert_hashes(Transaction::Declare(DeclareTransaction::V2(v2)));
+
+        let v3 = DeclareTransactionV3 {
+            sender_address: felt("0x111"),
+            compiled_class_hash: felt("0x444"),
+            signature: vec![],
+            nonce: felt("0x5"),
+            class_hash: felt("0x333"),
+            resource_bounds: resource_bounds(),
+            tip: 0,
+            paymaster_data: vec![],
+            account_deployment_data: vec![],
+            nonce_data_availability_mode: DataAvailabilityMode::L1,
+            fee_data_availability_mode: DataAvailabilityMode::L1,
+        };
+        assert_hashes(Transaction::Declare(DeclareTransaction::V3(v3)));
+    }
+
+    #[test]
+    fn test_query_hash_deploy_account() {
+        let v1 = DeployAccountTransactionV1 {
+            max_fee: felt("0x1234"),
+            signature: vec![],
+            nonce: felt("0x5"),
+            contract_address_salt: felt("0x555"),
+            constructor_calldata: vec![Felt::ONE, Felt::TWO, Felt::THREE],
+            class_hash: felt("0x333"),
+        };
+        assert_hashes(Transaction::DeployAccount(DeployAccountTransaction::V1(v1)));
+
+        let v3 = DeployAccountTransactionV3 {
+            signature: vec![],
+            nonce: felt("0x5"),
+            contract_address_salt: felt("0x555"),
+            constructor_calldata: vec![Felt::ONE, Felt::TWO, Felt::THREE],
+            class_hash: felt("0x333"),
+            resource_bounds: resource_bounds(),
+            tip: 0,
+            paymaster_data: vec![],
+            nonce_data_availability_mode: DataAvailabilityMode::L1,
+            fee_data_availability_mode: DataAvailabilityMode::L1,
+        };
+        assert_hashes(Transaction::DeployAccount(DeployAccountTransaction::V3(v3)));
+    }
+
+    #[test]
+    fn test_query_hash_l1_handler_and_deploy() {
+        let l1_handler = L1HandlerTransaction {
+            version: Felt::ZERO,
+            nonce: 5,
+            contract_address: felt("0x111"),
+            entry_point_selector: felt("0x222"),
+            calldata: vec![Felt::ONE, Felt::TWO, Felt::THREE],
+        };
+        assert_hashes(Transaction::L1Handler(l1_handler));
+
+        let deploy = DeployTransaction {
+            version: Felt::ZERO,
+            contract_address_salt: felt("0x555"),
+            constructor_calldata: vec![Felt::ONE, Felt::TWO, Felt::THREE],
+            class_hash: felt("0x333"),
+        };
+        assert_hashes(Transaction::Deploy(deploy));
+    }
 }
@@ -166,7 +166,9 @@ impl From<starknet_core::types::BroadcastedDeployAccountTransactionV3> for Deplo
     }
 }
 
-fn is_query(tx: &starknet_core::types::BroadcastedTransaction) -> bool {
+/// Whether the transaction is only meant for simulation and fee estimation, in which case its hash is computed
+/// with the query version.
+pub fn is_query(tx: &starknet_core::types::BroadcastedTransaction) -> bool {
     match tx {
         starknet_core::types::BroadcastedTransaction::Invoke(tx) => match tx {
             starknet_core::types::BroadcastedInvokeTransaction::V1(tx) => tx.is_query,
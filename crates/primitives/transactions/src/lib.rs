@@ -6,8 +6,9 @@ mod to_starknet_api;
 mod to_starknet_core;
 pub mod utils;
 
-pub use broadcasted_to_blockifier::broadcasted_to_blockifier;
+pub use broadcasted_to_blockifier::{broadcasted_to_blockifier, BroadcastedToBlockifierError};
 use dp_convert::ToFelt;
+pub use from_broadcasted_transaction::is_query;
 pub use from_starknet_provider::TransactionTypeError;
 use starknet_types_core::{felt::Felt, hash::StarkHash};
 
@@ -45,6 +46,34 @@ pub enum Transaction {
     DeployAccount(DeployAccountTransaction),
 }
 
+impl Transaction {
+    pub fn tx_type(&self) -> &'static str {
+        match self {
+            Transaction::Invoke(_) => "INVOKE",
+            Transaction::L1Handler(_) => "L1_HANDLER",
+            Transaction::Declare(_) => "DECLARE",
+            Transaction::Deploy(_) => "DEPLOY",
+            Transaction::DeployAccount(_) => "DEPLOY_ACCOUNT",
+        }
+    }
+
+    pub fn version(&self) -> Felt {
+        match self {
+            Transaction::Invoke(InvokeTransaction::V0(_)) => Felt::ZERO,
+            Transaction::Invoke(InvokeTransaction::V1(_)) => Felt::ONE,
+            Transaction::Invoke(InvokeTransaction::V3(_)) => Felt::THREE,
+            Transaction::L1Handler(tx) => tx.version,
+            Transaction::Declare(DeclareTransaction::V0(_)) => Felt::ZERO,
+            Transaction::Declare(DeclareTransaction::V1(_)) => Felt::ONE,
+            Transaction::Declare(DeclareTransaction::V2(_)) => Felt::TWO,
+            Transaction::Declare(DeclareTransaction::V3(_)) => Felt::THREE,
+            Transaction::Deploy(tx) => tx.version,
+            Transaction::DeployAccount(DeployAccountTransaction::V1(_)) => Felt::ONE,
+            Transaction::DeployAccount(DeployAccountTransaction::V3(_)) => Felt::THREE,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum InvokeTransaction {
     V0(InvokeTransactionV0),
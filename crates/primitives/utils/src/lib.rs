@@ -1,10 +1,13 @@
 #![allow(clippy::new_without_default)]
 
+pub mod request_id;
+
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
 use futures::Future;
-use tokio::sync::oneshot;
+use tokio::sync::{oneshot, OwnedRwLockReadGuard, OwnedRwLockWriteGuard, RwLock};
 
 /// Prefer this compared to [`tokio::spawn_blocking`], as spawn_blocking creates new OS threads and
 /// we don't really need that
@@ -15,8 +18,10 @@ where
 {
     let (tx, rx) = tokio::sync::oneshot::channel();
 
+    // The task is part of the request being served, if any
+    let request_id = request_id::current();
     rayon::spawn(move || {
-        let _result = tx.send(func());
+        let _result = tx.send(request_id::scope(request_id, func));
     });
 
     rx.await.expect("tokio channel closed")
@@ -24,7 +29,11 @@ where
 
 static CTRL_C: AtomicBool = AtomicBool::new(false);
 
+/// Resolves once the node is asked to shut down, right away if it already was.
 pub async fn graceful_shutdown() {
+    if CTRL_C.load(Ordering::SeqCst) {
+        return;
+    }
     let mut sigint =
         tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()).expect("SIGINT not supported");
 
@@ -67,6 +76,89 @@ impl Drop for StopHandle {
     }
 }
 
+/// Allows pausing a service from the outside, for example to quiesce database writes while taking a
+/// backup.
+///
+/// The service holds a [`PauseGuard`] while doing work which must not be interrupted, and
+/// [`PauseHandle::pause`] waits for the ongoing work to finish before returning.
+#[derive(Clone, Debug, Default)]
+pub struct PauseHandle {
+    lock: Arc<RwLock<()>>,
+    state: Arc<Mutex<PauseState>>,
+}
+
+#[derive(Debug, Default)]
+enum PauseState {
+    #[default]
+    Running,
+    /// A call to [`PauseHandle::pause`] is waiting for the ongoing work to finish.
+    Pausing,
+    Paused(OwnedRwLockWriteGuard<()>),
+}
+
+/// Resets a [`PauseState::Pausing`] state when the call to [`PauseHandle::pause`] is cancelled.
+struct PausingGuard<'a>(&'a Mutex<PauseState>);
+
+impl Drop for PausingGuard<'_> {
+    fn drop(&mut self) {
+        let mut state = self.0.lock().expect("Poisoned lock");
+        if matches!(*state, PauseState::Pausing) {
+            *state = PauseState::Running;
+        }
+    }
+}
+
+/// While this guard is alive, the service cannot be paused.
+#[derive(Debug)]
+pub struct PauseGuard {
+    _guard: OwnedRwLockReadGuard<()>,
+}
+
+impl PauseHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pauses the service once its ongoing work is done. Returns `false` if it was already paused, or being paused.
+    ///
+    /// The state is not locked while waiting for the ongoing work, so that [`PauseHandle::resume`] and
+    /// [`PauseHandle::is_paused`] do not wait for it. A call to [`PauseHandle::resume`] made in the meantime cancels
+    /// the pause.
+    pub async fn pause(&self) -> bool {
+        {
+            let mut state = self.state.lock().expect("Poisoned lock");
+            if !matches!(*state, PauseState::Running) {
+                return false;
+            }
+            *state = PauseState::Pausing;
+        }
+
+        let pausing = PausingGuard(&self.state);
+        let guard = Arc::clone(&self.lock).write_owned().await;
+        let mut state = pausing.0.lock().expect("Poisoned lock");
+        if !matches!(*state, PauseState::Pausing) {
+            return false;
+        }
+        *state = PauseState::Paused(guard);
+        true
+    }
+
+    /// Returns `false` if the service was not paused, or being paused.
+    pub async fn resume(&self) -> bool {
+        let mut state = self.state.lock().expect("Poisoned lock");
+        !matches!(std::mem::take(&mut *state), PauseState::Running)
+    }
+
+    pub async fn is_paused(&self) -> bool {
+        matches!(*self.state.lock().expect("Poisoned lock"), PauseState::Paused(_))
+    }
+
+    /// Waits until the service is not paused.
+    pub async fn acquire(&self) -> PauseGuard {
+        PauseGuard { _guard: Arc::clone(&self.lock).read_owned().await }
+    }
+}
+
 pub struct PerfStopwatch(pub Instant);
 
 impl PerfStopwatch {
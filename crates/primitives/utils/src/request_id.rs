@@ -0,0 +1,75 @@
+//! Id of the RPC request being served by the current thread, so that the log lines emitted while serving it can
+//! be correlated, whichever crate they come from.
+//!
+//! The id follows the request future with [`instrument`], and the tasks spawned with
+//! [`spawn_rayon_task`](crate::spawn_rayon_task).
+
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+/// Longest request id accepted from a caller.
+const MAX_REQUEST_ID_LEN: usize = 64;
+
+thread_local! {
+    static CURRENT: RefCell<Option<Arc<str>>> = const { RefCell::new(None) };
+}
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// The id of the request being served by the current thread, if any.
+pub fn current() -> Option<Arc<str>> {
+    CURRENT.with(|current| current.borrow().clone())
+}
+
+/// A new request id, unique within this run of the node.
+pub fn generate() -> Arc<str> {
+    format!("{:08x}", NEXT_ID.fetch_add(1, Ordering::Relaxed)).into()
+}
+
+/// Validates a request id given by a caller: only short ids made of alphanumeric characters, `-`, `_` and `.`
+/// are accepted, so that they cannot mess with the logs.
+pub fn parse(id: &str) -> Option<Arc<str>> {
+    let is_valid = !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LEN
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    is_valid.then(|| id.into())
+}
+
+/// Restores the previous request id when dropped, even on panic.
+struct ScopeGuard(Option<Arc<str>>);
+
+impl Drop for ScopeGuard {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        CURRENT.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+/// Runs `f` with `id` as the current request id.
+pub fn scope<R>(id: Option<Arc<str>>, f: impl FnOnce() -> R) -> R {
+    let _guard = ScopeGuard(CURRENT.with(|current| current.replace(id)));
+    f()
+}
+
+/// Serves a request future with `id` as the current request id, on whichever thread it is polled.
+pub fn instrument<F: Future>(id: Option<Arc<str>>, future: F) -> Instrumented<F> {
+    Instrumented { id, future: Box::pin(future) }
+}
+
+pub struct Instrumented<F> {
+    id: Option<Arc<str>>,
+    future: Pin<Box<F>>,
+}
+
+impl<F: Future> Future for Instrumented<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        scope(this.id.clone(), || this.future.as_mut().poll(cx))
+    }
+}